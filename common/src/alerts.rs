@@ -0,0 +1,81 @@
+/// What an alert rule watches for in the logged requests.
+pub struct AlertCondition {
+    /// The key stored in the DB (e.g. "error_rate").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What the condition checks and what its value means.
+    pub description: &'static str,
+}
+
+pub const ALERT_CONDITION_ERROR_RATE: &str = "error_rate";
+pub const ALERT_CONDITION_PROMPT_TOKENS: &str = "prompt_tokens";
+pub const ALERT_CONDITION_STOP_REASON: &str = "stop_reason";
+
+/// All alert conditions.
+pub const ALERT_CONDITIONS: &[AlertCondition] = &[
+    AlertCondition {
+        key: ALERT_CONDITION_ERROR_RATE,
+        label: "Error rate above",
+        description: "More than this percentage of the requests finished in the window \
+                      failed or got a 4xx/5xx response. Fires at most once per window.",
+    },
+    AlertCondition {
+        key: ALERT_CONDITION_PROMPT_TOKENS,
+        label: "Prompt tokens above",
+        description: "A response reports more than this many input tokens, cached ones \
+                      included.",
+    },
+    AlertCondition {
+        key: ALERT_CONDITION_STOP_REASON,
+        label: "Stop reason seen",
+        description: "A response stops with this reason, e.g. max_tokens or refusal.",
+    },
+];
+
+/// Look up a known alert condition by its key, or `None` if unknown.
+pub fn find_alert_condition_by_key(key: &str) -> Option<&'static AlertCondition> {
+    ALERT_CONDITIONS
+        .iter()
+        .find(|condition| condition.key == key)
+}
+
+/// Validate a rule's value for its condition: a percentage, a token count
+/// or a stop reason.
+pub fn parse_alert_value(condition: &str, value: &str) -> Result<String, String> {
+    let value = value.trim();
+    match condition {
+        ALERT_CONDITION_ERROR_RATE => match value.parse::<f64>() {
+            Ok(percent) if (0.0..100.0).contains(&percent) => Ok(percent.to_string()),
+            _ => Err(format!("{:?} is not a percentage below 100", value)),
+        },
+        ALERT_CONDITION_PROMPT_TOKENS => match value.parse::<i64>() {
+            Ok(token_count) if token_count > 0 => Ok(token_count.to_string()),
+            _ => Err(format!("{:?} is not a positive token count", value)),
+        },
+        ALERT_CONDITION_STOP_REASON if !value.is_empty() => Ok(value.to_string()),
+        ALERT_CONDITION_STOP_REASON => Err("a stop reason is required".to_string()),
+        _ => Err(format!("unknown condition {:?}", condition)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_alert_value_checks_value_for_condition() {
+        assert_eq!(
+            parse_alert_value(ALERT_CONDITION_ERROR_RATE, " 10 ").as_deref(),
+            Ok("10")
+        );
+        assert!(parse_alert_value(ALERT_CONDITION_ERROR_RATE, "150").is_err());
+        assert_eq!(
+            parse_alert_value(ALERT_CONDITION_PROMPT_TOKENS, "150000").as_deref(),
+            Ok("150000")
+        );
+        assert!(parse_alert_value(ALERT_CONDITION_PROMPT_TOKENS, "-1").is_err());
+        assert!(parse_alert_value(ALERT_CONDITION_STOP_REASON, "").is_err());
+        assert!(parse_alert_value("unknown", "1").is_err());
+    }
+}
@@ -0,0 +1,129 @@
+/// An `anthropic-beta` value offered as a checkbox on the session form.
+pub struct KnownBeta {
+    /// The header value (e.g. "output-128k-2025-02-19").
+    pub value: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+}
+
+/// All known betas.
+pub const KNOWN_BETAS: &[KnownBeta] = &[
+    KnownBeta {
+        value: "output-128k-2025-02-19",
+        label: "Extended output (128k)",
+    },
+    KnownBeta {
+        value: "computer-use-2025-01-24",
+        label: "Computer use",
+    },
+    KnownBeta {
+        value: "interleaved-thinking-2025-05-14",
+        label: "Interleaved thinking",
+    },
+    KnownBeta {
+        value: "token-efficient-tools-2025-02-19",
+        label: "Token-efficient tools",
+    },
+    KnownBeta {
+        value: "fine-grained-tool-streaming-2025-05-14",
+        label: "Fine-grained tool streaming",
+    },
+    KnownBeta {
+        value: "context-1m-2025-08-07",
+        label: "1M context",
+    },
+    KnownBeta {
+        value: "files-api-2025-04-14",
+        label: "Files API",
+    },
+    KnownBeta {
+        value: "mcp-client-2025-04-04",
+        label: "MCP connector",
+    },
+];
+
+/// The `anthropic-beta` values a session adds to and removes from forwarded
+/// requests, so features can be tried without changing the client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BetaRules {
+    /// Added when the client didn't send them.
+    pub inject: Vec<String>,
+    /// Removed from what the client sent.
+    pub strip: Vec<String>,
+}
+
+impl BetaRules {
+    /// Read rules stored as one value per line.
+    pub fn parse(inject: Option<&str>, strip: Option<&str>) -> Self {
+        Self {
+            inject: parse_beta_values(inject),
+            strip: parse_beta_values(strip),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inject.is_empty() && self.strip.is_empty()
+    }
+
+    /// The `anthropic-beta` value to forward in place of the client's, or
+    /// `None` when no betas are left.
+    pub fn apply(&self, anthropic_beta: Option<&str>) -> Option<String> {
+        let mut betas: Vec<String> = parse_beta_values(anthropic_beta)
+            .into_iter()
+            .filter(|beta| !self.strip.contains(beta))
+            .collect();
+        for beta in &self.inject {
+            if !betas.contains(beta) {
+                betas.push(beta.clone());
+            }
+        }
+        (!betas.is_empty()).then(|| betas.join(","))
+    }
+}
+
+/// Split a comma- or newline-separated beta list, skipping blanks.
+pub fn parse_beta_values(text: Option<&str>) -> Vec<String> {
+    text.unwrap_or_default()
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|beta| !beta.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Check a beta list from the session form: each value is made of letters,
+/// digits, `-`, `_` and `.`.
+pub fn validate_beta_values(text: &str) -> Result<(), String> {
+    for beta in parse_beta_values(Some(text)) {
+        if !beta
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(format!("invalid beta: {}", beta));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beta_rules_strip_then_inject() {
+        let beta_rules = BetaRules::parse(
+            Some("output-128k-2025-02-19\ncomputer-use-2025-01-24"),
+            Some("prompt-caching-2024-07-31"),
+        );
+        assert_eq!(
+            beta_rules
+                .apply(Some("prompt-caching-2024-07-31, computer-use-2025-01-24"))
+                .as_deref(),
+            Some("computer-use-2025-01-24,output-128k-2025-02-19")
+        );
+        assert_eq!(BetaRules::parse(None, Some("a")).apply(Some("a")), None);
+        assert_eq!(BetaRules::default().apply(None), None);
+        assert!(validate_beta_values("a-1, b_2.0").is_ok());
+        assert!(validate_beta_values("a b").is_err());
+    }
+}
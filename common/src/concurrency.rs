@@ -0,0 +1,41 @@
+/// What happens to a request that arrives while its session is at its
+/// concurrency limit.
+pub struct OverflowMode {
+    /// The key stored in the DB (e.g. "queue").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+}
+
+/// Wait for a slot to free up. Used when a session sets no mode.
+pub const OVERFLOW_QUEUE: &str = "queue";
+/// Answer 429 right away.
+pub const OVERFLOW_REJECT: &str = "reject";
+
+/// All overflow modes.
+pub const OVERFLOW_MODES: &[OverflowMode] = &[
+    OverflowMode {
+        key: OVERFLOW_QUEUE,
+        label: "Queue until a request finishes",
+    },
+    OverflowMode {
+        key: OVERFLOW_REJECT,
+        label: "Reject with 429",
+    },
+];
+
+/// Look up a known overflow mode by its key, or `None` if unknown.
+pub fn find_by_key(key: &str) -> Option<&'static OverflowMode> {
+    OVERFLOW_MODES.iter().find(|mode| mode.key == key)
+}
+
+/// A session's requests at the moment, for the session page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionLoad {
+    /// Requests being handled, including ones parked for approval.
+    pub in_flight: usize,
+    /// Requests waiting for a slot.
+    pub queued: usize,
+    /// The limit the counts were taken under; `None` when unlimited.
+    pub limit: Option<usize>,
+}
@@ -1,9 +1,128 @@
+use regex::Regex;
 use serde::Deserialize;
+use std::sync::{Arc, RwLock};
+use subtle::ConstantTimeEq;
+
+use crate::truncate::{
+    FieldLimit, TruncationProfile, DEFAULT_TRUNCATE_MAX_CHARS, DEFAULT_TRUNCATION_PROFILE,
+};
+
+fn default_port() -> u16 {
+    8081
+}
+
+fn default_bind_address() -> String {
+    "0.0.0.0".to_string()
+}
+
+fn default_dashboard_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_forward_proxy_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_forward_proxy_mitm_hosts() -> Vec<String> {
+    vec!["api.anthropic.com".to_string()]
+}
+
+fn default_forward_proxy_ca_cert_path() -> String {
+    "forward-proxy-ca.pem".to_string()
+}
+
+fn default_forward_proxy_ca_key_path() -> String {
+    "forward-proxy-ca-key.pem".to_string()
+}
+
+fn default_db_path() -> String {
+    "proxy.db".to_string()
+}
+
+fn default_db_max_connections() -> u32 {
+    5
+}
+
+fn default_max_payload_bytes() -> usize {
+    100 * 1024 * 1024
+}
+
+fn default_approval_timeout_secs() -> u64 {
+    120
+}
+
+fn default_backup_interval_mins() -> u64 {
+    60
+}
+
+fn default_backup_keep() -> usize {
+    24
+}
+
+fn default_dashboard_per_page() -> i64 {
+    50
+}
+
+fn default_collapse_threshold() -> usize {
+    200
+}
+
+fn default_body_compression_threshold() -> usize {
+    128 * 1024
+}
+
+fn default_truncate_max_chars() -> usize {
+    DEFAULT_TRUNCATE_MAX_CHARS
+}
+
+fn default_webfetch_tool_names() -> Vec<String> {
+    vec!["WebFetch".to_string()]
+}
+
+fn default_webfetch_allowed_schemes() -> Vec<String> {
+    vec!["http".to_string(), "https".to_string()]
+}
+
+fn default_webfetch_max_redirects() -> usize {
+    5
+}
+
+fn default_webfetch_max_content_bytes() -> usize {
+    100 * 1024
+}
+
+fn default_webfetch_fetch_timeout_secs() -> u64 {
+    30
+}
+
+fn default_webfetch_cache_ttl_secs() -> u64 {
+    300
+}
+
+fn default_webfetch_agent_max_output_tokens() -> i64 {
+    16384
+}
+
+fn default_webfetch_agent_max_response_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_webfetch_round_timeout_secs() -> u64 {
+    600
+}
+
+fn default_webfetch_followup_fallback() -> String {
+    WEBFETCH_FOLLOWUP_FALLBACK_ORIGINAL.to_string()
+}
 
 fn default_webfetch_agent_model() -> String {
     "us.anthropic.claude-haiku-4-5-20251001-v1:0".to_string()
 }
 
+fn default_title_model() -> String {
+    "claude-haiku-4-5".to_string()
+}
+
 fn default_webfetch_mock_prompt() -> String {
     "[Proxy mock] Web fetch intercepted. URL: '{{url}}'. No real fetch was performed.".to_string()
 }
@@ -43,35 +162,776 @@ Provide a concise response based only on the content above. In your response:
         .to_string()
 }
 
+/// What a dashboard account may do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DashboardRole {
+    /// Browse sessions and requests without changing anything.
+    Viewer,
+    /// Everything, including resolving approvals, editing sessions and
+    /// clearing data.
+    Operator,
+}
+
+/// A read-only dashboard account.
+#[derive(Clone, Deserialize)]
+pub struct ViewerCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// HTTP Basic credentials protecting the dashboard. `username` and `password`
+/// are the operator account; `viewers` are read-only accounts. Auth is
+/// disabled unless one of them is set.
+#[derive(Clone, Default, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub viewers: Vec<ViewerCredentials>,
+}
+
+impl AuthConfig {
+    /// Return the operator's `(username, password)` when configured.
+    pub fn get_credentials(&self) -> Option<(&str, &str)> {
+        match (self.username.as_deref(), self.password.as_deref()) {
+            (Some(username), Some(password)) if !username.is_empty() => Some((username, password)),
+            _ => None,
+        }
+    }
+
+    /// Whether any dashboard account is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.get_credentials().is_some() || !self.viewers.is_empty()
+    }
+
+    /// The role of the account with these credentials, or `None` if they
+    /// match no account.
+    pub fn find_role(&self, username: &str, password: &str) -> Option<DashboardRole> {
+        let is_operator = self
+            .get_credentials()
+            .is_some_and(|credentials| is_same_credentials(credentials, (username, password)));
+        if is_operator {
+            return Some(DashboardRole::Operator);
+        }
+        self.viewers
+            .iter()
+            .any(|viewer| {
+                is_same_credentials((&viewer.username, &viewer.password), (username, password))
+            })
+            .then_some(DashboardRole::Viewer)
+    }
+}
+
+/// Compare credentials in constant time, so response timing doesn't reveal
+/// how much of a username or password was right.
+fn is_same_credentials(expected: (&str, &str), given: (&str, &str)) -> bool {
+    let username_choice = expected.0.as_bytes().ct_eq(given.0.as_bytes());
+    let password_choice = expected.1.as_bytes().ct_eq(given.1.as_bytes());
+    (username_choice & password_choice).into()
+}
+
+/// How a tool executor runs an Accept-ed tool call.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ToolExecutorKind {
+    /// The built-in URL fetcher used for WebFetch; the tool input needs a `url`.
+    Fetch,
+    /// A shell command run with `sh -c`. `{{field}}` placeholders are replaced
+    /// with shell-quoted top-level input fields, and the input JSON is on stdin.
+    Command { command: String },
+    /// POST the call as JSON to an external service that returns the tool_result.
+    Http { url: String },
+    /// Call a tool on the session's MCP server: `tool`, or the tool's own name.
+    Mcp {
+        #[serde(default)]
+        tool: Option<String>,
+    },
+}
+
+/// Maps a tool name to the executor that runs it when a call is Accept-ed.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct ToolExecutorConfig {
+    pub tool_name: String,
+    #[serde(flatten)]
+    pub kind: ToolExecutorKind,
+}
+
+impl ToolExecutorConfig {
+    /// Short description of the executor for the dashboard.
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            ToolExecutorKind::Fetch => "URL fetcher".to_string(),
+            ToolExecutorKind::Command { command } => format!("command: {}", command),
+            ToolExecutorKind::Http { url } => format!("HTTP callback: {}", url),
+            ToolExecutorKind::Mcp { tool: Some(tool) } => format!("MCP server tool: {}", tool),
+            ToolExecutorKind::Mcp { tool: None } => "MCP server".to_string(),
+        }
+    }
+}
+
 #[derive(Clone, Deserialize)]
 pub struct AppConfig {
+    #[serde(default = "default_port")]
+    pub port: u16,
+    /// Interface the proxy listens on, and the dashboard too unless
+    /// `dashboard_port` is set.
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+    /// Serve the dashboard on its own port instead of alongside the proxy.
+    #[serde(default)]
+    pub dashboard_port: Option<u16>,
+    /// Interface the dashboard listens on when `dashboard_port` is set.
+    #[serde(default = "default_dashboard_bind_address")]
+    pub dashboard_bind_address: String,
+    /// PEM certificate chain; the listeners serve HTTPS when this and
+    /// `tls_key_path` are set.
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    /// PEM private key for `tls_cert_path`.
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    /// Generate a self-signed certificate at `tls_cert_path` and
+    /// `tls_key_path` on startup when the certificate doesn't exist yet.
+    #[serde(default)]
+    pub tls_self_signed: bool,
+    /// Accept `HTTPS_PROXY` traffic on this port, on
+    /// `forward_proxy_bind_address`; no forward proxy listens when unset.
+    #[serde(default)]
+    pub forward_proxy_port: Option<u16>,
+    /// Interface the forward proxy listens on. It has no authentication, so
+    /// it stays on loopback unless set otherwise.
+    #[serde(default = "default_forward_proxy_bind_address")]
+    pub forward_proxy_bind_address: String,
+    /// Hosts whose CONNECT tunnels are decrypted and recorded into a
+    /// "Forward proxy: <host>" session. Other hosts are tunnelled untouched.
+    #[serde(default = "default_forward_proxy_mitm_hosts")]
+    pub forward_proxy_mitm_hosts: Vec<String>,
+    /// CA certificate the forward proxy signs its per-host certificates
+    /// with; generated on first use. Clients must trust it.
+    #[serde(default = "default_forward_proxy_ca_cert_path")]
+    pub forward_proxy_ca_cert_path: String,
+    /// Private key for `forward_proxy_ca_cert_path`.
+    #[serde(default = "default_forward_proxy_ca_key_path")]
+    pub forward_proxy_ca_key_path: String,
+    #[serde(default = "default_db_path")]
+    pub db_path: String,
+    #[serde(default = "default_db_max_connections")]
+    pub db_max_connections: u32,
+    /// Directory the database is snapshotted into every
+    /// `backup_interval_mins`; no backups are taken when unset.
+    #[serde(default)]
+    pub backup_dir: Option<String>,
+    #[serde(default = "default_backup_interval_mins")]
+    pub backup_interval_mins: u64,
+    /// Snapshots kept in `backup_dir`; older ones are deleted.
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: usize,
+    #[serde(default = "default_max_payload_bytes")]
+    pub max_payload_bytes: usize,
+    #[serde(default = "default_approval_timeout_secs")]
+    pub approval_timeout_secs: u64,
+    #[serde(default)]
+    pub notification_webhooks: Vec<String>,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Serve a captured database for browsing only: no proxying, dashboard
+    /// changes or background cleanup.
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default = "default_dashboard_per_page")]
+    pub dashboard_per_page: i64,
+    #[serde(default = "default_collapse_threshold")]
+    pub collapse_threshold: usize,
+    /// Length strings are cut to in the stored truncated body; 0 keeps them whole.
+    #[serde(default = "default_truncate_max_chars")]
+    pub truncate_max_chars: usize,
+    /// Overrides of `truncate_max_chars` for some fields. Set in the config
+    /// file only; changes need a restart.
+    #[serde(default)]
+    pub truncate_field_limits: Vec<FieldLimit>,
+    /// Extra limits the Full JSON page can be viewed with. Set in the config
+    /// file only; changes need a restart.
+    #[serde(default)]
+    pub truncation_profiles: Vec<TruncationProfile>,
+    /// Delete requests older than this many days; 0 keeps them forever.
+    #[serde(default)]
+    pub retention_days: u64,
+    /// Request and response bodies over this many bytes are stored
+    /// zstd-compressed; 0 stores them all as text.
+    #[serde(default = "default_body_compression_threshold")]
+    pub body_compression_threshold: usize,
+    /// Send the session id upstream in `x-proxy-session`.
+    #[serde(default)]
+    pub forward_proxy_session_header: bool,
+    /// Mask API keys, emails and `presentation_redact_patterns` matches on
+    /// dashboard pages, e.g. while screen sharing. Stored data is unchanged.
+    #[serde(default)]
+    pub presentation_mode: bool,
+    /// Extra regexes masked in presentation mode.
+    #[serde(default)]
+    pub presentation_redact_patterns: Vec<String>,
+    /// `presentation_redact_patterns` compiled, when the config is loaded
+    /// and when the setting changes.
+    #[serde(skip)]
+    pub presentation_redact_regexes: Vec<Regex>,
+    /// Give Messages requests a generated title in the background, by
+    /// asking `title_model` through the session's upstream.
+    #[serde(default)]
+    pub auto_titles: bool,
+    /// Model asked for request titles, automatic or regenerated.
+    #[serde(default = "default_title_model")]
+    pub title_model: String,
+    #[serde(default = "default_webfetch_tool_names")]
+    pub webfetch_tool_names: Vec<String>,
+    /// URL schemes an Accept-ed WebFetch may fetch.
+    #[serde(default = "default_webfetch_allowed_schemes")]
+    pub webfetch_allowed_schemes: Vec<String>,
+    /// Same-host redirects followed before an Accept-ed WebFetch gives up.
+    #[serde(default = "default_webfetch_max_redirects")]
+    pub webfetch_max_redirects: usize,
+    /// Bytes of a fetched page read before the rest is cut off.
+    #[serde(default = "default_webfetch_max_content_bytes")]
+    pub webfetch_max_content_bytes: usize,
+    /// Seconds an Accept-ed fetch may take, including reading the body.
+    #[serde(default = "default_webfetch_fetch_timeout_secs")]
+    pub webfetch_fetch_timeout_secs: u64,
+    /// Seconds a fetched page is reused for repeat fetches of its URL; 0 disables the cache.
+    #[serde(default = "default_webfetch_cache_ttl_secs")]
+    pub webfetch_cache_ttl_secs: u64,
+    /// Seconds a WebFetch follow-up request may take, including reading its
+    /// response; 0 waits indefinitely.
+    #[serde(default = "default_webfetch_round_timeout_secs")]
+    pub webfetch_round_timeout_secs: u64,
+    /// Seconds the tool calls and follow-up requests of all rounds of an
+    /// interception may take; 0 sets no deadline.
+    #[serde(default)]
+    pub webfetch_intercept_deadline_secs: u64,
+    /// Times a failed WebFetch follow-up request is sent again.
+    #[serde(default)]
+    pub webfetch_followup_retries: usize,
+    /// What the client gets when a follow-up request still fails, one of
+    /// the `WEBFETCH_FOLLOWUP_FALLBACK_*` values.
+    #[serde(default = "default_webfetch_followup_fallback")]
+    pub webfetch_followup_fallback: String,
     #[serde(default = "default_webfetch_agent_model")]
     pub webfetch_agent_model: String,
+    /// `max_tokens` of a webfetch agent request; reading its response also
+    /// stops once the streamed text is estimated past this.
+    #[serde(default = "default_webfetch_agent_max_output_tokens")]
+    pub webfetch_agent_max_output_tokens: i64,
+    /// Bytes of a webfetch agent response read before the stream is cut off.
+    #[serde(default = "default_webfetch_agent_max_response_bytes")]
+    pub webfetch_agent_max_response_bytes: usize,
     #[serde(default = "default_webfetch_mock_prompt")]
     pub webfetch_mock_prompt: String,
     #[serde(default = "default_webfetch_redirect_prompt")]
     pub webfetch_redirect_prompt: String,
     #[serde(default = "default_webfetch_accept_prompt")]
     pub webfetch_accept_prompt: String,
+    /// Executors for Accept-ed calls to tools other than WebFetch. Set in the
+    /// config file only; changes need a restart.
+    #[serde(default)]
+    pub tool_executors: Vec<ToolExecutorConfig>,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
+            port: default_port(),
+            bind_address: default_bind_address(),
+            dashboard_port: None,
+            dashboard_bind_address: default_dashboard_bind_address(),
+            tls_cert_path: None,
+            tls_key_path: None,
+            tls_self_signed: false,
+            forward_proxy_port: None,
+            forward_proxy_bind_address: default_forward_proxy_bind_address(),
+            forward_proxy_mitm_hosts: default_forward_proxy_mitm_hosts(),
+            forward_proxy_ca_cert_path: default_forward_proxy_ca_cert_path(),
+            forward_proxy_ca_key_path: default_forward_proxy_ca_key_path(),
+            db_path: default_db_path(),
+            db_max_connections: default_db_max_connections(),
+            backup_dir: None,
+            backup_interval_mins: default_backup_interval_mins(),
+            backup_keep: default_backup_keep(),
+            max_payload_bytes: default_max_payload_bytes(),
+            approval_timeout_secs: default_approval_timeout_secs(),
+            notification_webhooks: Vec::new(),
+            auth: AuthConfig::default(),
+            read_only: false,
+            dashboard_per_page: default_dashboard_per_page(),
+            collapse_threshold: default_collapse_threshold(),
+            truncate_max_chars: default_truncate_max_chars(),
+            truncate_field_limits: Vec::new(),
+            truncation_profiles: Vec::new(),
+            retention_days: 0,
+            body_compression_threshold: default_body_compression_threshold(),
+            forward_proxy_session_header: false,
+            presentation_mode: false,
+            presentation_redact_patterns: Vec::new(),
+            presentation_redact_regexes: Vec::new(),
+            auto_titles: false,
+            title_model: default_title_model(),
+            webfetch_tool_names: default_webfetch_tool_names(),
+            webfetch_allowed_schemes: default_webfetch_allowed_schemes(),
+            webfetch_max_redirects: default_webfetch_max_redirects(),
+            webfetch_max_content_bytes: default_webfetch_max_content_bytes(),
+            webfetch_fetch_timeout_secs: default_webfetch_fetch_timeout_secs(),
+            webfetch_cache_ttl_secs: default_webfetch_cache_ttl_secs(),
+            webfetch_round_timeout_secs: default_webfetch_round_timeout_secs(),
+            webfetch_intercept_deadline_secs: 0,
+            webfetch_followup_retries: 0,
+            webfetch_followup_fallback: default_webfetch_followup_fallback(),
             webfetch_agent_model: default_webfetch_agent_model(),
+            webfetch_agent_max_output_tokens: default_webfetch_agent_max_output_tokens(),
+            webfetch_agent_max_response_bytes: default_webfetch_agent_max_response_bytes(),
             webfetch_mock_prompt: default_webfetch_mock_prompt(),
             webfetch_redirect_prompt: default_webfetch_redirect_prompt(),
             webfetch_accept_prompt: default_webfetch_accept_prompt(),
+            tool_executors: Vec::new(),
         }
     }
 }
 
+/// `webfetch_followup_fallback` that returns the last good response when a
+/// follow-up request fails: the previous round's, or the original tool_use
+/// response.
+pub const WEBFETCH_FOLLOWUP_FALLBACK_ORIGINAL: &str = "original";
+/// `webfetch_followup_fallback` that answers with a 502 `api_error` when a
+/// follow-up request fails.
+pub const WEBFETCH_FOLLOWUP_FALLBACK_ERROR: &str = "error";
+
+/// Live configuration shared between the proxy and the settings page.
+pub type SharedConfig = Arc<RwLock<AppConfig>>;
+
+/// A config value that can be edited from `/_dashboard/settings` and is
+/// persisted in the `settings` table.
+pub struct RuntimeSetting {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub multiline: bool,
+    pub requires_restart: bool,
+}
+
+pub const RUNTIME_SETTINGS: &[RuntimeSetting] = &[
+    RuntimeSetting {
+        key: "max_payload_bytes",
+        label: "Max Payload Bytes",
+        multiline: false,
+        requires_restart: true,
+    },
+    RuntimeSetting {
+        key: "dashboard_per_page",
+        label: "Dashboard Rows Per Page",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "collapse_threshold",
+        label: "Collapse Threshold (chars)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "truncate_max_chars",
+        label: "Truncated JSON String Length (chars, 0 = whole)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "retention_days",
+        label: "Request Retention (days, 0 = forever)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "body_compression_threshold",
+        label: "Compress Stored Bodies Over (bytes, 0 = never)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "forward_proxy_session_header",
+        label: "Forward x-proxy-session Header (true/false)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "presentation_mode",
+        label: "Presentation Mode: mask keys and emails on dashboard pages (true/false)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "presentation_redact_patterns",
+        label: "Presentation Mode Extra Patterns (regex, one per line)",
+        multiline: true,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "auto_titles",
+        label: "Generate Request Titles in the Background (true/false)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "title_model",
+        label: "Request Title Model",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_tool_names",
+        label: "WebFetch Tool Names (one per line)",
+        multiline: true,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_allowed_schemes",
+        label: "WebFetch Allowed Schemes (one per line)",
+        multiline: true,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_max_redirects",
+        label: "WebFetch Max Redirects",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_max_content_bytes",
+        label: "WebFetch Max Content Bytes",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_fetch_timeout_secs",
+        label: "WebFetch Fetch Timeout (seconds)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_cache_ttl_secs",
+        label: "WebFetch Cache TTL (seconds, 0 = off)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_round_timeout_secs",
+        label: "WebFetch Follow-up Timeout (seconds, 0 = none)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_intercept_deadline_secs",
+        label: "WebFetch Interception Deadline (seconds, 0 = none)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_followup_retries",
+        label: "WebFetch Follow-up Retries",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_followup_fallback",
+        label: "WebFetch Follow-up Failure (original or error)",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_agent_model",
+        label: "WebFetch Agent Model",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_agent_max_output_tokens",
+        label: "WebFetch Agent Max Output Tokens",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_agent_max_response_bytes",
+        label: "WebFetch Agent Max Response Bytes",
+        multiline: false,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_mock_prompt",
+        label: "WebFetch Mock Prompt",
+        multiline: true,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_redirect_prompt",
+        label: "WebFetch Redirect Prompt",
+        multiline: true,
+        requires_restart: false,
+    },
+    RuntimeSetting {
+        key: "webfetch_accept_prompt",
+        label: "WebFetch Accept Prompt",
+        multiline: true,
+        requires_restart: false,
+    },
+];
+
+/// Compile the extra presentation mode patterns, failing on the first
+/// invalid one.
+fn compile_redact_patterns(patterns: &[String]) -> anyhow::Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| Ok(Regex::new(pattern)?))
+        .collect()
+}
+
 impl AppConfig {
+    /// Whether the listeners serve HTTPS.
+    pub fn tls_enabled(&self) -> bool {
+        self.tls_cert_path.is_some() && self.tls_key_path.is_some()
+    }
+
+    /// Scheme, host and port clients on this machine reach the proxy at.
+    pub fn proxy_origin(&self) -> String {
+        self.proxy_origin_for_host("localhost")
+    }
+
+    /// The proxy's scheme and port with `host`, e.g. a session hostname.
+    pub fn proxy_origin_for_host(&self, host: &str) -> String {
+        let scheme = if self.tls_enabled() { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, host, self.port)
+    }
+
+    /// The default truncation profile followed by `truncation_profiles`, which
+    /// fall back to `truncate_max_chars` for fields they set no limit for.
+    pub fn build_truncation_profiles(&self) -> Vec<TruncationProfile> {
+        let default_profile = TruncationProfile {
+            name: DEFAULT_TRUNCATION_PROFILE.to_string(),
+            max_chars: Some(self.truncate_max_chars),
+            field_limits: self.truncate_field_limits.clone(),
+        };
+        let extra_profiles = self
+            .truncation_profiles
+            .iter()
+            .map(|profile| TruncationProfile {
+                max_chars: profile.max_chars.or(Some(self.truncate_max_chars)),
+                ..profile.clone()
+            });
+        std::iter::once(default_profile)
+            .chain(extra_profiles)
+            .collect()
+    }
+
+    /// Return the current value of a runtime setting as a string.
+    pub fn get_runtime_setting(&self, key: &str) -> Option<String> {
+        match key {
+            "max_payload_bytes" => Some(self.max_payload_bytes.to_string()),
+            "dashboard_per_page" => Some(self.dashboard_per_page.to_string()),
+            "collapse_threshold" => Some(self.collapse_threshold.to_string()),
+            "truncate_max_chars" => Some(self.truncate_max_chars.to_string()),
+            "retention_days" => Some(self.retention_days.to_string()),
+            "body_compression_threshold" => Some(self.body_compression_threshold.to_string()),
+            "forward_proxy_session_header" => Some(self.forward_proxy_session_header.to_string()),
+            "presentation_mode" => Some(self.presentation_mode.to_string()),
+            "presentation_redact_patterns" => Some(self.presentation_redact_patterns.join("\n")),
+            "auto_titles" => Some(self.auto_titles.to_string()),
+            "title_model" => Some(self.title_model.clone()),
+            "webfetch_tool_names" => Some(self.webfetch_tool_names.join("\n")),
+            "webfetch_allowed_schemes" => Some(self.webfetch_allowed_schemes.join("\n")),
+            "webfetch_max_redirects" => Some(self.webfetch_max_redirects.to_string()),
+            "webfetch_max_content_bytes" => Some(self.webfetch_max_content_bytes.to_string()),
+            "webfetch_fetch_timeout_secs" => Some(self.webfetch_fetch_timeout_secs.to_string()),
+            "webfetch_cache_ttl_secs" => Some(self.webfetch_cache_ttl_secs.to_string()),
+            "webfetch_round_timeout_secs" => Some(self.webfetch_round_timeout_secs.to_string()),
+            "webfetch_intercept_deadline_secs" => {
+                Some(self.webfetch_intercept_deadline_secs.to_string())
+            }
+            "webfetch_followup_retries" => Some(self.webfetch_followup_retries.to_string()),
+            "webfetch_followup_fallback" => Some(self.webfetch_followup_fallback.clone()),
+            "webfetch_agent_model" => Some(self.webfetch_agent_model.clone()),
+            "webfetch_agent_max_output_tokens" => {
+                Some(self.webfetch_agent_max_output_tokens.to_string())
+            }
+            "webfetch_agent_max_response_bytes" => {
+                Some(self.webfetch_agent_max_response_bytes.to_string())
+            }
+            "webfetch_mock_prompt" => Some(self.webfetch_mock_prompt.clone()),
+            "webfetch_redirect_prompt" => Some(self.webfetch_redirect_prompt.clone()),
+            "webfetch_accept_prompt" => Some(self.webfetch_accept_prompt.clone()),
+            _ => None,
+        }
+    }
+
+    /// Update a runtime setting from its string form.
+    pub fn set_runtime_setting(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        match key {
+            "max_payload_bytes" => self.max_payload_bytes = value.trim().parse()?,
+            "dashboard_per_page" => {
+                let per_page: i64 = value.trim().parse()?;
+                anyhow::ensure!(per_page > 0, "must be at least 1");
+                self.dashboard_per_page = per_page;
+            }
+            "collapse_threshold" => self.collapse_threshold = value.trim().parse()?,
+            "truncate_max_chars" => self.truncate_max_chars = value.trim().parse()?,
+            "retention_days" => self.retention_days = value.trim().parse()?,
+            "body_compression_threshold" => {
+                self.body_compression_threshold = value.trim().parse()?
+            }
+            "forward_proxy_session_header" => {
+                self.forward_proxy_session_header = value.trim().parse()?
+            }
+            "presentation_mode" => self.presentation_mode = value.trim().parse()?,
+            "presentation_redact_patterns" => {
+                let patterns: Vec<String> = value
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty())
+                    .collect();
+                self.presentation_redact_regexes = compile_redact_patterns(&patterns)?;
+                self.presentation_redact_patterns = patterns;
+            }
+            "auto_titles" => self.auto_titles = value.trim().parse()?,
+            "title_model" => {
+                let title_model = value.trim();
+                anyhow::ensure!(!title_model.is_empty(), "must not be empty");
+                self.title_model = title_model.to_string();
+            }
+            "webfetch_tool_names" => {
+                self.webfetch_tool_names = value
+                    .lines()
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty())
+                    .collect()
+            }
+            "webfetch_allowed_schemes" => {
+                self.webfetch_allowed_schemes = value
+                    .lines()
+                    .map(|line| line.trim().to_ascii_lowercase())
+                    .filter(|line| !line.is_empty())
+                    .collect()
+            }
+            "webfetch_max_redirects" => self.webfetch_max_redirects = value.trim().parse()?,
+            "webfetch_max_content_bytes" => {
+                let max_content_bytes: usize = value.trim().parse()?;
+                anyhow::ensure!(max_content_bytes > 0, "must be at least 1");
+                self.webfetch_max_content_bytes = max_content_bytes;
+            }
+            "webfetch_fetch_timeout_secs" => {
+                let timeout_secs: u64 = value.trim().parse()?;
+                anyhow::ensure!(timeout_secs > 0, "must be at least 1");
+                self.webfetch_fetch_timeout_secs = timeout_secs;
+            }
+            "webfetch_cache_ttl_secs" => self.webfetch_cache_ttl_secs = value.trim().parse()?,
+            "webfetch_round_timeout_secs" => {
+                self.webfetch_round_timeout_secs = value.trim().parse()?
+            }
+            "webfetch_intercept_deadline_secs" => {
+                self.webfetch_intercept_deadline_secs = value.trim().parse()?
+            }
+            "webfetch_followup_retries" => self.webfetch_followup_retries = value.trim().parse()?,
+            "webfetch_followup_fallback" => {
+                let fallback = value.trim();
+                anyhow::ensure!(
+                    [
+                        WEBFETCH_FOLLOWUP_FALLBACK_ORIGINAL,
+                        WEBFETCH_FOLLOWUP_FALLBACK_ERROR
+                    ]
+                    .contains(&fallback),
+                    "must be \"{}\" or \"{}\"",
+                    WEBFETCH_FOLLOWUP_FALLBACK_ORIGINAL,
+                    WEBFETCH_FOLLOWUP_FALLBACK_ERROR
+                );
+                self.webfetch_followup_fallback = fallback.to_string();
+            }
+            "webfetch_agent_model" => self.webfetch_agent_model = value.to_string(),
+            "webfetch_agent_max_output_tokens" => {
+                let max_output_tokens: i64 = value.trim().parse()?;
+                anyhow::ensure!(max_output_tokens > 0, "must be at least 1");
+                self.webfetch_agent_max_output_tokens = max_output_tokens;
+            }
+            "webfetch_agent_max_response_bytes" => {
+                let max_response_bytes: usize = value.trim().parse()?;
+                anyhow::ensure!(max_response_bytes > 0, "must be at least 1");
+                self.webfetch_agent_max_response_bytes = max_response_bytes;
+            }
+            "webfetch_mock_prompt" => self.webfetch_mock_prompt = value.to_string(),
+            "webfetch_redirect_prompt" => self.webfetch_redirect_prompt = value.to_string(),
+            "webfetch_accept_prompt" => self.webfetch_accept_prompt = value.to_string(),
+            _ => anyhow::bail!("Unknown setting: {}", key),
+        }
+        Ok(())
+    }
+
     pub fn load(path: &str) -> anyhow::Result<Self> {
         match std::fs::read_to_string(path) {
-            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Ok(contents) => {
+                let mut config: Self = toml::from_str(&contents)?;
+                config.presentation_redact_regexes =
+                    compile_redact_patterns(&config.presentation_redact_patterns)?;
+                Ok(config)
+            }
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
             Err(e) => Err(e.into()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_config_find_role_matches_operator_and_viewers() {
+        let auth_config = AuthConfig {
+            username: Some("admin".to_string()),
+            password: Some("secret".to_string()),
+            viewers: vec![ViewerCredentials {
+                username: "guest".to_string(),
+                password: "look".to_string(),
+            }],
+        };
+        assert!(auth_config.is_enabled());
+        assert_eq!(
+            auth_config.find_role("admin", "secret"),
+            Some(DashboardRole::Operator)
+        );
+        assert_eq!(
+            auth_config.find_role("guest", "look"),
+            Some(DashboardRole::Viewer)
+        );
+        assert_eq!(auth_config.find_role("guest", "secret"), None);
+        assert!(!AuthConfig::default().is_enabled());
+    }
+
+    #[test]
+    fn proxy_origin_uses_https_when_tls_is_configured() {
+        let mut config = AppConfig {
+            port: 8443,
+            ..AppConfig::default()
+        };
+        assert_eq!(config.proxy_origin(), "http://localhost:8443");
+        config.tls_cert_path = Some("cert.pem".to_string());
+        config.tls_key_path = Some("key.pem".to_string());
+        assert_eq!(config.proxy_origin(), "https://localhost:8443");
+    }
+
+    #[test]
+    fn presentation_redact_patterns_compiled_when_set() {
+        let mut config = AppConfig::default();
+        config
+            .set_runtime_setting("presentation_redact_patterns", "acme\n\n(?i)secret\n")
+            .unwrap();
+        assert_eq!(config.presentation_redact_patterns, ["acme", "(?i)secret"]);
+        assert_eq!(config.presentation_redact_regexes.len(), 2);
+        assert!(config.presentation_redact_regexes[1].is_match("SECRET"));
+
+        assert!(config
+            .set_runtime_setting("presentation_redact_patterns", "(")
+            .is_err());
+        assert_eq!(config.presentation_redact_regexes.len(), 2);
+    }
+}
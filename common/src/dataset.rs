@@ -0,0 +1,195 @@
+//! JSONL dataset export: each captured request becomes a line with its
+//! prompt `messages` and the assistant `completion` rebuilt from the
+//! response, for fine-tuning and eval harnesses.
+
+use serde_json::Value;
+
+use crate::sse::{load_response_events, reconstruct_content_blocks};
+
+/// What each dataset record leaves out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DatasetOptions {
+    /// Omit the request's system prompt.
+    pub exclude_system: bool,
+    /// Drop tool calls and tool results, and the turns left empty by that.
+    pub strip_tool_use: bool,
+}
+
+/// Whether a content block is a tool call or a tool result, custom or
+/// server-side.
+fn is_tool_block(block: &Value) -> bool {
+    let block_type = block
+        .get("type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("");
+    block_type == "tool_use"
+        || block_type == "server_tool_use"
+        || block_type.ends_with("tool_result")
+}
+
+fn strip_tool_blocks(content_blocks: &[Value]) -> Vec<Value> {
+    content_blocks
+        .iter()
+        .filter(|block| !is_tool_block(block))
+        .cloned()
+        .collect()
+}
+
+/// Drop tool blocks from every message, then drop the messages left empty
+/// and merge the neighbours that now share a role, so turns still alternate.
+/// String contents come out as a single text block.
+fn strip_tool_turns(messages: &[Value]) -> Vec<Value> {
+    let mut stripped: Vec<Value> = Vec::new();
+    for message in messages {
+        let role = message.get("role").cloned().unwrap_or(Value::Null);
+        let content_blocks = match message.get("content") {
+            Some(Value::Array(content_blocks)) => strip_tool_blocks(content_blocks),
+            Some(Value::String(text)) if !text.is_empty() => {
+                vec![serde_json::json!({"type": "text", "text": text})]
+            }
+            _ => Vec::new(),
+        };
+        if content_blocks.is_empty() {
+            continue;
+        }
+        match stripped.last_mut() {
+            Some(previous) if previous["role"] == role => {
+                if let Some(Value::Array(previous_blocks)) = previous.get_mut("content") {
+                    previous_blocks.extend(content_blocks);
+                }
+            }
+            _ => stripped.push(serde_json::json!({"role": role, "content": content_blocks})),
+        }
+    }
+    stripped
+}
+
+/// The assistant content of a response: rebuilt from its SSE events, or the
+/// `content` of a JSON body, which is stored with no parsed events.
+fn build_completion_blocks(
+    response_events_json: Option<&str>,
+    response_body: Option<&str>,
+) -> Vec<Value> {
+    let events = load_response_events(response_events_json, response_body);
+    let content_blocks = reconstruct_content_blocks(&events);
+    if !content_blocks.is_empty() {
+        return content_blocks;
+    }
+    response_body
+        .and_then(|body| serde_json::from_str::<Value>(body).ok())
+        .and_then(|body| body.get("content")?.as_array().cloned())
+        .unwrap_or_default()
+}
+
+/// One dataset record, or `None` when the request has no messages or the
+/// response no assistant content (e.g. token counts and failed calls).
+pub fn build_dataset_record(
+    messages_json: Option<&str>,
+    system_json: Option<&str>,
+    response_events_json: Option<&str>,
+    response_body: Option<&str>,
+    options: &DatasetOptions,
+) -> Option<Value> {
+    let messages: Vec<Value> = serde_json::from_str(messages_json?).ok()?;
+    let mut completion_blocks = build_completion_blocks(response_events_json, response_body);
+    let messages = if options.strip_tool_use {
+        completion_blocks = strip_tool_blocks(&completion_blocks);
+        strip_tool_turns(&messages)
+    } else {
+        messages
+    };
+    if messages.is_empty() || completion_blocks.is_empty() {
+        return None;
+    }
+
+    let mut record = serde_json::json!({
+        "messages": messages,
+        "completion": {"role": "assistant", "content": completion_blocks},
+    });
+    let system = system_json.and_then(|json| serde_json::from_str::<Value>(json).ok());
+    if let (false, Some(system)) = (options.exclude_system, system) {
+        record["system"] = system;
+    }
+    Some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MESSAGES: &str = r#"[
+        {"role": "user", "content": "What's the weather?"},
+        {"role": "assistant", "content": [
+            {"type": "text", "text": "Checking."},
+            {"type": "tool_use", "id": "t1", "name": "weather", "input": {}}
+        ]},
+        {"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "t1", "content": "sunny"}
+        ]},
+        {"role": "assistant", "content": [
+            {"type": "tool_use", "id": "t2", "name": "forecast", "input": {}}
+        ]},
+        {"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "t2", "content": "warm"},
+            {"type": "text", "text": "And tomorrow?"}
+        ]}
+    ]"#;
+
+    const RESPONSE_BODY: &str = r#"{
+        "type": "message", "role": "assistant", "stop_reason": "end_turn",
+        "content": [{"type": "text", "text": "Warm."}],
+        "usage": {"input_tokens": 10, "output_tokens": 2}
+    }"#;
+
+    #[test]
+    fn build_dataset_record_rebuilds_completion_and_keeps_system() {
+        let record = build_dataset_record(
+            Some(MESSAGES),
+            Some(r#""Be brief.""#),
+            Some(r#"[{"unparsed": true, "data": "{"}]"#),
+            Some(RESPONSE_BODY),
+            &DatasetOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(record["messages"].as_array().unwrap().len(), 5);
+        assert_eq!(record["system"], "Be brief.");
+        assert_eq!(record["completion"]["content"][0]["text"], "Warm.");
+    }
+
+    #[test]
+    fn build_dataset_record_strips_tool_turns_and_system() {
+        let options = DatasetOptions {
+            exclude_system: true,
+            strip_tool_use: true,
+        };
+        let record = build_dataset_record(
+            Some(MESSAGES),
+            Some(r#""Be brief.""#),
+            None,
+            Some(RESPONSE_BODY),
+            &options,
+        )
+        .unwrap();
+        assert!(record.get("system").is_none());
+        assert_eq!(
+            record["messages"],
+            serde_json::json!([
+                {"role": "user", "content": [{"type": "text", "text": "What's the weather?"}]},
+                {"role": "assistant", "content": [{"type": "text", "text": "Checking."}]},
+                {"role": "user", "content": [{"type": "text", "text": "And tomorrow?"}]}
+            ])
+        );
+    }
+
+    #[test]
+    fn build_dataset_record_skips_requests_without_completion() {
+        let record = build_dataset_record(
+            Some(MESSAGES),
+            None,
+            None,
+            Some(r#"{"input_tokens": 10}"#),
+            &DatasetOptions::default(),
+        );
+        assert!(record.is_none());
+    }
+}
@@ -0,0 +1,33 @@
+/// How an Accept-ed WebFetch turns a fetched page into text.
+pub struct ExtractionMode {
+    /// The key stored in the DB (e.g. "readability").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What the extracted text contains.
+    pub description: &'static str,
+}
+
+/// All extraction modes. New sessions start with "text".
+pub const EXTRACTION_MODES: &[ExtractionMode] = &[
+    ExtractionMode {
+        key: "text",
+        label: "Raw Text",
+        description: "The whole page as plain text, including menus and footers.",
+    },
+    ExtractionMode {
+        key: "readability",
+        label: "Readability",
+        description: "Only the main article content; navigation, sidebars and footers are dropped.",
+    },
+    ExtractionMode {
+        key: "markdown",
+        label: "Markdown",
+        description: "The whole page as markdown with inline links.",
+    },
+];
+
+/// Look up a known extraction mode by its key, or `None` if unknown.
+pub fn find_by_key(key: &str) -> Option<&'static ExtractionMode> {
+    EXTRACTION_MODES.iter().find(|mode| mode.key == key)
+}
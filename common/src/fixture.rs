@@ -0,0 +1,70 @@
+//! Test fixture export: a captured request body and its response, in the
+//! format the proxy crate's tests load from `proxy/fixtures/`, so real-world
+//! captures can become regression tests.
+
+use serde_json::Value;
+
+/// The fixture of a logged request, or `None` when its body wasn't recorded
+/// as JSON. Streamed responses are kept as their parsed `{"event", "data"}`
+/// objects under `response_events`, others under `response_body`, as JSON
+/// when they parse.
+pub fn build_request_fixture(
+    path: &str,
+    body_json: Option<&str>,
+    response_status: Option<i64>,
+    response_events_json: Option<&str>,
+    response_body: Option<&str>,
+) -> Option<Value> {
+    let request: Value = serde_json::from_str(body_json?).ok()?;
+    let mut fixture = serde_json::json!({
+        "path": path,
+        "request": request,
+        "response_status": response_status,
+    });
+    let response_events = response_events_json
+        .and_then(|json| serde_json::from_str::<Vec<Value>>(json).ok())
+        .filter(|response_events| !response_events.is_empty());
+    if let Some(response_events) = response_events {
+        fixture["response_events"] = Value::Array(response_events);
+    } else if let Some(response_body) = response_body {
+        fixture["response_body"] = serde_json::from_str(response_body)
+            .unwrap_or_else(|_| Value::String(response_body.to_string()));
+    }
+    Some(fixture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_request_fixture_keeps_streamed_events() {
+        let fixture = build_request_fixture(
+            "/v1/messages",
+            Some(r#"{"model": "claude-sonnet", "stream": true}"#),
+            Some(200),
+            Some(r#"[{"event": "message_stop", "data": {"type": "message_stop"}}]"#),
+            Some("event: message_stop\ndata: {\"type\": \"message_stop\"}\n\n"),
+        )
+        .unwrap();
+        assert_eq!(fixture["request"]["model"], "claude-sonnet");
+        assert_eq!(fixture["response_status"], 200);
+        assert_eq!(fixture["response_events"][0]["event"], "message_stop");
+        assert!(fixture.get("response_body").is_none());
+    }
+
+    #[test]
+    fn build_request_fixture_keeps_plain_body() {
+        let fixture = build_request_fixture(
+            "/v1/messages",
+            Some("{}"),
+            Some(529),
+            Some("[]"),
+            Some("overloaded"),
+        )
+        .unwrap();
+        assert_eq!(fixture["response_body"], "overloaded");
+        assert!(fixture.get("response_events").is_none());
+        assert!(build_request_fixture("/v1/messages", None, None, None, None).is_none());
+    }
+}
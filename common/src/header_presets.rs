@@ -0,0 +1,52 @@
+/// Headers a kind of client sends, merged into a session's forwarded requests
+/// so the upstream sees that client whatever actually sent the request.
+pub struct HeaderPreset {
+    /// The key stored in the DB (e.g. "claude_code").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// Header (name, value) pairs, replacing any the client sent.
+    pub headers: &'static [(&'static str, &'static str)],
+}
+
+/// All header presets.
+pub const HEADER_PRESETS: &[HeaderPreset] = &[
+    HeaderPreset {
+        key: "claude_code",
+        label: "Claude Code",
+        headers: &[
+            ("anthropic-version", "2023-06-01"),
+            (
+                "anthropic-beta",
+                "claude-code-20250219,interleaved-thinking-2025-05-14",
+            ),
+            ("user-agent", "claude-cli/1.0.0 (external, cli)"),
+            ("x-app", "cli"),
+        ],
+    },
+    HeaderPreset {
+        key: "curl",
+        label: "cURL",
+        headers: &[
+            ("anthropic-version", "2023-06-01"),
+            ("user-agent", "curl/8.5.0"),
+            ("accept", "*/*"),
+        ],
+    },
+    HeaderPreset {
+        key: "langchain",
+        label: "LangChain",
+        headers: &[
+            ("anthropic-version", "2023-06-01"),
+            ("user-agent", "Anthropic/Python 0.40.0"),
+            ("x-stainless-lang", "python"),
+            ("x-stainless-package-version", "0.40.0"),
+        ],
+    },
+];
+
+/// Look up a known header preset by its key, or `None` if unknown.
+pub fn find_by_key(key: &str) -> Option<&'static HeaderPreset> {
+    HEADER_PRESETS.iter().find(|preset| preset.key == key)
+}
+
@@ -0,0 +1,119 @@
+/// Client headers never forwarded upstream, whatever the session's rules.
+pub const SKIPPED_FORWARD_HEADERS: &[&str] = &["host"];
+
+/// Upstream response headers never returned, since the proxy re-encodes the body.
+pub const SKIPPED_RESPONSE_HEADERS: &[&str] = &["transfer-encoding", "content-encoding"];
+
+/// Which headers pass between client and upstream: a session has one set for
+/// request headers forwarded upstream and one for response headers returned.
+/// Patterns are header names where `*` matches any run of characters, e.g.
+/// `x-stainless-*`, compared case-insensitively.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderRules {
+    /// When not empty, only matching headers pass.
+    pub allow: Vec<String>,
+    /// Matching headers never pass, even when allowed.
+    pub deny: Vec<String>,
+}
+
+impl HeaderRules {
+    /// Read rules stored as one pattern per line.
+    pub fn parse(allow: Option<&str>, deny: Option<&str>) -> Self {
+        Self {
+            allow: parse_header_patterns(allow),
+            deny: parse_header_patterns(deny),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether a header with this name passes.
+    pub fn is_allowed(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        if self
+            .deny
+            .iter()
+            .any(|pattern| matches_header_pattern(pattern, &name))
+        {
+            return false;
+        }
+        self.allow.is_empty()
+            || self
+                .allow
+                .iter()
+                .any(|pattern| matches_header_pattern(pattern, &name))
+    }
+}
+
+/// Split a pattern list into lowercased patterns, skipping blank lines.
+pub fn parse_header_patterns(text: Option<&str>) -> Vec<String> {
+    text.unwrap_or_default()
+        .lines()
+        .map(|line| line.trim().to_ascii_lowercase())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Match a lowercased header name against a lowercased pattern.
+fn matches_header_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        let Some(index) = rest.find(part) else {
+            return false;
+        };
+        rest = &rest[index + part.len()..];
+    }
+    rest.ends_with(last)
+}
+
+/// Check a pattern list from the session form: each line is a header name
+/// made of letters, digits, `-`, `_` and `*`.
+pub fn validate_header_patterns(text: &str) -> Result<(), String> {
+    for pattern in parse_header_patterns(Some(text)) {
+        if !pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '*'))
+        {
+            return Err(format!("invalid header pattern: {}", pattern));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_header_pattern_wildcards() {
+        assert!(matches_header_pattern("x-stainless-*", "x-stainless-os"));
+        assert!(matches_header_pattern("*-beta", "anthropic-beta"));
+        assert!(matches_header_pattern("x-*-id", "x-request-id"));
+        assert!(matches_header_pattern("*", "anything"));
+        assert!(!matches_header_pattern("x-stainless-*", "x-other"));
+        assert!(!matches_header_pattern("user-agent", "user-agent-extra"));
+    }
+
+    #[test]
+    fn header_rules_deny_wins_over_allow() {
+        let header_rules =
+            HeaderRules::parse(Some("anthropic-*\nContent-Type\n"), Some("anthropic-beta"));
+        assert!(header_rules.is_allowed("Anthropic-Version"));
+        assert!(header_rules.is_allowed("content-type"));
+        assert!(!header_rules.is_allowed("anthropic-beta"));
+        assert!(!header_rules.is_allowed("x-stainless-os"));
+        assert!(HeaderRules::default().is_allowed("x-stainless-os"));
+        assert!(validate_header_patterns("x-*\n\nuser-agent").is_ok());
+        assert!(validate_header_patterns("x-foo: bar").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+/// A request property an intercept rule checks.
+pub struct InterceptRuleField {
+    /// The key stored in the DB (e.g. "model").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What the rule's value is compared with.
+    pub description: &'static str,
+}
+
+/// All intercept rule fields.
+pub const INTERCEPT_RULE_FIELDS: &[InterceptRuleField] = &[
+    InterceptRuleField {
+        key: "model",
+        label: "Model",
+        description: "The request's model equals the value.",
+    },
+    InterceptRuleField {
+        key: "path",
+        label: "Path",
+        description: "The API path after the session prefix equals the value, e.g. /v1/messages.",
+    },
+    InterceptRuleField {
+        key: "user_id",
+        label: "metadata.user_id",
+        description: "The body's metadata.user_id equals the value.",
+    },
+    InterceptRuleField {
+        key: "last_user_message",
+        label: "Last user message",
+        description:
+            "The value is a regular expression found in the text of the last user message.",
+    },
+];
+
+/// Look up a known intercept rule field by its key, or `None` if unknown.
+pub fn find_field_by_key(key: &str) -> Option<&'static InterceptRuleField> {
+    INTERCEPT_RULE_FIELDS.iter().find(|field| field.key == key)
+}
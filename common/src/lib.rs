@@ -1,4 +1,22 @@
+pub mod alerts;
+pub mod betas;
+pub mod concurrency;
 pub mod config;
+pub mod dataset;
 pub mod error_inject;
+pub mod extraction;
+pub mod fixture;
+pub mod header_presets;
+pub mod headers;
+pub mod intercept_rules;
+pub mod mcp;
 pub mod models;
+pub mod policy;
+pub mod share;
+pub mod sse;
+pub mod tokens;
 pub mod truncate;
+pub mod upstream;
+pub mod url;
+pub mod users;
+pub mod validation;
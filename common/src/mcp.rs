@@ -0,0 +1,33 @@
+/// How the proxy talks to a session's MCP server.
+pub struct McpTransport {
+    /// The key stored in the DB (e.g. "stdio").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What the server target is and how it is reached.
+    pub description: &'static str,
+}
+
+/// All MCP transports.
+pub const MCP_TRANSPORTS: &[McpTransport] = &[
+    McpTransport {
+        key: "stdio",
+        label: "stdio",
+        description: "A shell command started for each call; messages go over stdin/stdout.",
+    },
+    McpTransport {
+        key: "http",
+        label: "Streamable HTTP",
+        description: "An http(s) URL of the server's MCP endpoint.",
+    },
+];
+
+/// Health status of an MCP server whose last check or call succeeded.
+pub const MCP_STATUS_OK: &str = "ok";
+/// Health status of an MCP server whose last check or call failed.
+pub const MCP_STATUS_ERROR: &str = "error";
+
+/// Look up a known MCP transport by its key, or `None` if unknown.
+pub fn find_by_key(key: &str) -> Option<&'static McpTransport> {
+    MCP_TRANSPORTS.iter().find(|transport| transport.key == key)
+}
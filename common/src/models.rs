@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Session {
@@ -10,13 +11,83 @@ pub struct Session {
     pub auth_header: Option<String>,
     pub x_api_key: Option<String>,
     pub profile_id: Option<String>,
+    /// AWS region; when set, Bedrock requests are SigV4-signed and forwarded to AWS as-is.
+    pub aws_region: Option<String>,
+    /// Signing keys; the proxy's environment credentials are used when unset.
+    pub aws_access_key_id: Option<String>,
+    pub aws_secret_access_key: Option<String>,
+    pub aws_session_token: Option<String>,
+    /// Key of the `common::upstream` preset the session was set up with.
+    pub upstream_preset: Option<String>,
+    /// Requests handled at once before the overflow mode applies; unlimited when unset.
+    pub max_concurrent_requests: Option<i64>,
+    /// Key of the `common::concurrency` overflow mode; queue when unset.
+    pub concurrency_overflow: Option<String>,
+    /// Largest request body accepted, in place of `max_payload_bytes`; unset uses the global limit.
+    pub max_body_bytes: Option<i64>,
+    /// `common::headers` patterns, one per line, for client headers forwarded upstream.
+    pub forward_header_allow: Option<String>,
+    pub forward_header_deny: Option<String>,
+    /// `common::headers` patterns for upstream response headers returned to the client.
+    pub response_header_allow: Option<String>,
+    pub response_header_deny: Option<String>,
+    /// `name=value` lines added to the forwarded URL's query, e.g. `key=...`.
+    pub auth_query_params: Option<String>,
+    /// Key of the `common::header_presets` preset merged into forwarded headers.
+    pub header_preset: Option<String>,
+    /// `anthropic-beta` values added to forwarded requests, one per line.
+    pub beta_inject: Option<String>,
+    /// `anthropic-beta` values removed from forwarded requests, one per line.
+    pub beta_strip: Option<String>,
+    /// URL posted a summary of each request once it completes.
+    pub completion_webhook_url: Option<String>,
+    /// Virtual hostname routed to this session, so clients can use the real
+    /// API paths instead of `/_proxy/{session_id}/`.
+    pub hostname: Option<String>,
+    /// Requests without a `/_proxy/{session_id}/` prefix (or a session
+    /// hostname) are routed to the default session.
+    pub is_default: bool,
+    /// Requests are held until they are forwarded or rejected on the
+    /// dashboard.
+    pub breakpoint_enabled: bool,
     pub error_inject: Option<String>,
     pub webfetch_intercept: bool,
-    pub webfetch_whitelist: Option<String>,
+    pub webfetch_allow_private_targets: bool,
+    /// Per-session override of `webfetch_max_content_bytes`.
+    pub webfetch_max_content_bytes: Option<i64>,
+    /// Per-session override of `webfetch_fetch_timeout_secs`.
+    pub webfetch_fetch_timeout_secs: Option<i64>,
+    /// Key of the `common::extraction` mode used for fetched pages.
+    pub webfetch_extraction_mode: String,
+    /// Whether Accept-ed fetches honor the target site's robots.txt.
+    pub webfetch_respect_robots_txt: bool,
+    /// Return fetched page text as the tool_result without the agent summary.
+    pub webfetch_raw_mode: bool,
+    /// Messages endpoint for agent summarization instead of the session's upstream.
+    pub webfetch_agent_url: Option<String>,
+    /// Per-session override of `webfetch_agent_model`.
+    pub webfetch_agent_model: Option<String>,
+    /// Sent as `x-api-key` on agent requests in place of the client's credentials.
+    pub webfetch_agent_api_key: Option<String>,
+    /// Key of the `common::mcp` transport of the session's MCP server, if one is set.
+    pub mcp_server_transport: Option<String>,
+    /// The MCP server's command (stdio) or URL (http).
+    pub mcp_server_target: Option<String>,
+    /// `MCP_STATUS_OK` or `MCP_STATUS_ERROR` after the last check or call.
+    pub mcp_server_status: Option<String>,
+    pub mcp_server_status_detail: Option<String>,
+    pub mcp_server_checked_at: Option<String>,
+    pub validation_mode: Option<String>,
+    pub validation_schema: Option<String>,
     pub created_at: String,
     pub updated_at: String,
     #[sqlx(default)]
     pub request_count: i64,
+    /// Requests whose WebFetch tool calls the proxy answered.
+    #[sqlx(default)]
+    pub intercepted_request_count: i64,
+    #[sqlx(default)]
+    pub batch_count: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -46,8 +117,123 @@ pub struct ProxyRequest {
     pub webfetch_first_response_events_json: Option<String>,
     pub webfetch_followup_body_json: Option<String>,
     pub webfetch_rounds_json: Option<String>,
+    /// Why WebFetch interception ended early, with the round it ended in: a
+    /// failed follow-up request, a cancellation or the deadline.
+    pub webfetch_followup_error: Option<String>,
+    pub estimated_input_tokens: Option<i64>,
+    pub counted_input_tokens: Option<i64>,
+    /// Why the upstream call failed, one of the `REQUEST_ERROR_*` kinds.
+    pub error_kind: Option<String>,
+    /// Size of the body as the client sent it.
+    pub request_bytes: Option<i64>,
+    /// Size of the body sent upstream, after the filter profile was applied.
+    pub forwarded_bytes: Option<i64>,
+    /// Size of the stored response body.
+    pub response_bytes: Option<i64>,
+    /// JSON array of the `AppliedFilter`s the filter profile made.
+    pub applied_filters_json: Option<String>,
+    /// The `anthropic-beta` header as forwarded upstream.
+    pub anthropic_beta: Option<String>,
+    /// One-line summary of the conversation, generated by a model. Empty when
+    /// generating it failed, so it isn't retried automatically.
+    pub title: Option<String>,
+    /// SHA-256 of the JSON body with keys sorted and whitespace removed, so
+    /// identical resends share it.
+    pub body_hash: Option<String>,
+    /// The response's `stop_reason`, e.g. "max_tokens" or "refusal".
+    pub stop_reason: Option<String>,
+    /// The client's `User-Agent` header.
+    pub client_user_agent: Option<String>,
+    /// The client's `x-app` header, e.g. "cli".
+    pub client_app: Option<String>,
+    /// The `ip:port` the request came from.
+    pub client_addr: Option<String>,
+    /// The request a WebFetch follow-up round was sent for.
+    pub parent_request_id: Option<String>,
+    /// `body_json` stored zstd-compressed; the db getters decompress it into
+    /// `body_json` and clear this.
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub body_json_zstd: Option<Vec<u8>>,
+    /// `response_body` stored zstd-compressed, like `body_json_zstd`.
+    #[serde(skip)]
+    #[sqlx(default)]
+    pub response_body_zstd: Option<Vec<u8>>,
 }
 
+/// The columns of a request the requests list shows, without the bodies,
+/// tools and system prompt that make a full row large.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RequestSummary {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub method: String,
+    pub path: String,
+    pub model: Option<String>,
+    pub title: Option<String>,
+    pub created_at: String,
+    pub response_status: Option<i64>,
+    pub stop_reason: Option<String>,
+    pub request_bytes: Option<i64>,
+    pub forwarded_bytes: Option<i64>,
+    pub response_bytes: Option<i64>,
+    pub parent_request_id: Option<String>,
+    /// Number of entries in `messages`.
+    pub message_count: Option<i64>,
+    /// The last user message as JSON, or the last message when none is
+    /// from the user.
+    pub last_message_json: Option<String>,
+    pub response_events_json: Option<String>,
+}
+
+/// `ProxyRequest::error_kind` when the upstream couldn't be connected to.
+pub const REQUEST_ERROR_CONNECT: &str = "connect";
+/// `ProxyRequest::error_kind` when the upstream call timed out.
+pub const REQUEST_ERROR_TIMEOUT: &str = "timeout";
+/// `ProxyRequest::error_kind` when sending the request or reading the response failed otherwise.
+pub const REQUEST_ERROR_TRANSPORT: &str = "transport";
+/// `ProxyRequest::error_kind` when a streamed response broke off part way.
+pub const REQUEST_ERROR_STREAM: &str = "stream";
+/// `ProxyRequest::error_kind` when the upstream answered with a non-2xx status.
+pub const REQUEST_ERROR_STATUS: &str = "status";
+/// `ProxyRequest::error_kind` when WebFetch interception ended early, e.g.
+/// on a failed follow-up request, and the client was answered with an error.
+pub const REQUEST_ERROR_WEBFETCH_FOLLOWUP: &str = "webfetch_followup";
+
+/// A tool call whose result a request sends back, stored with the request
+/// for the tool statistics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnsweredToolCall {
+    /// The `tool_use` block id.
+    pub id: String,
+    pub name: String,
+    /// Size of the call's input serialized as JSON.
+    pub input_bytes: usize,
+    pub is_error: bool,
+}
+
+/// One removal or rename made by a filter profile before forwarding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedFilter {
+    /// One of the `APPLIED_FILTER_*` kinds.
+    pub kind: String,
+    /// The pattern, tool name or setting that matched.
+    pub rule: String,
+    /// What was removed or renamed.
+    pub detail: String,
+}
+
+/// `AppliedFilter::kind` for a system block removed by a system filter.
+pub const APPLIED_FILTER_SYSTEM: &str = "system";
+/// `AppliedFilter::kind` for a tool definition removed by a tool filter.
+pub const APPLIED_FILTER_TOOL: &str = "tool";
+/// `AppliedFilter::kind` for a tool_use/tool_result pair dropped by `keep_tool_pairs`.
+pub const APPLIED_FILTER_TOOL_PAIR: &str = "tool_pair";
+/// `AppliedFilter::kind` for a tool renamed by a tool name override.
+pub const APPLIED_FILTER_TOOL_NAME_OVERRIDE: &str = "tool_name_override";
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct FilterProfile {
     #[sqlx(try_from = "String")]
@@ -103,10 +289,257 @@ pub struct MessageFilter {
     pub updated_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MessageBatch {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub request_id: uuid::Uuid,
+    pub upstream_batch_id: String,
+    pub base_path: String,
+    pub processing_status: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MessageBatchItem {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub batch_id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub request_id: uuid::Uuid,
+    pub custom_id: String,
+    pub result_type: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A note left on a request from the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RequestNote {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub request_id: uuid::Uuid,
+    /// The dashboard user name that wrote it.
+    pub author: String,
+    pub note: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SessionEvent {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub kind: String,
+    pub detail: String,
+    pub duration_ms: Option<i64>,
+    pub created_at: String,
+}
+
+/// `SessionEvent::kind` for an SSE error returned by error injection.
+pub const SESSION_EVENT_ERROR_INJECTED: &str = "error_injected";
+/// `SessionEvent::kind` for a webfetch approval decision.
+pub const SESSION_EVENT_APPROVAL: &str = "approval";
+/// `SessionEvent::kind` for a request released from a breakpoint.
+pub const SESSION_EVENT_BREAKPOINT: &str = "breakpoint";
+
+/// A per-session rule that auto-accepts WebFetch calls to a domain.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebfetchWhitelistRule {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub domain: String,
+    pub allow_subdomains: bool,
+    pub path_prefix: Option<String>,
+    pub expires_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A per-session rule that auto-fails WebFetch calls to matching hosts.
+/// `pattern` is an IP range in CIDR notation, a single IP, or a domain.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebfetchDenyRule {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub pattern: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A per-session predicate scoping WebFetch interception and breakpoints.
+/// When a session has rules, only requests matching every rule are
+/// intercepted.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InterceptRule {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    /// Key of a `common::intercept_rules` field.
+    pub field: String,
+    pub value: String,
+    pub created_at: String,
+}
+
+/// A per-session auto-decision policy. Policies are checked in `position`
+/// order and the first one matching a tool call decides it.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebfetchPolicy {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub position: i64,
+    /// Only calls to this tool match; `None` matches every tool.
+    pub tool_name: Option<String>,
+    /// Key of a `common::policy` condition.
+    pub condition: String,
+    /// The condition's value: working hours or an approval count.
+    pub condition_value: Option<String>,
+    /// Key of a `common::policy` action.
+    pub action: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A rule checked by the alerts job against the logged requests.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AlertRule {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    pub name: String,
+    /// Key of a `common::alerts` condition.
+    pub condition: String,
+    /// The condition's value: a percentage, a token count or a stop reason.
+    pub condition_value: String,
+    /// Window the error rate is measured over.
+    pub window_minutes: i64,
+    pub enabled: bool,
+    /// Requests that finished up to this time (Unix ms) have been checked.
+    pub checked_until_ms: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// A firing of an alert rule, with the request that triggered it, if any.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AlertEvent {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub rule_id: uuid::Uuid,
+    pub rule_name: String,
+    pub session_id: Option<String>,
+    pub request_id: Option<String>,
+    pub message: String,
+    pub created_at: String,
+}
+
+/// Text of a page fetched by an Accept-ed WebFetch, reused for repeat
+/// fetches of the same URL within `webfetch_cache_ttl_secs`.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WebfetchCacheEntry {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub url: String,
+    pub content: String,
+    pub original_length: Option<i64>,
+    pub bytes_read: i64,
+    pub truncated: bool,
+    pub fetched_at: String,
+}
+
+/// Size and contents of the SQLite database, for the maintenance page.
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseStats {
+    /// Bytes of the database file on disk.
+    pub file_size_bytes: u64,
+    /// Bytes of the write-ahead log not yet checkpointed into the file.
+    pub wal_size_bytes: u64,
+    pub page_size: i64,
+    pub page_count: i64,
+    /// Free pages VACUUM would give back to the filesystem.
+    pub freelist_count: i64,
+    /// Rows per table, by table name.
+    pub table_row_counts: Vec<(String, i64)>,
+    pub body_compression: BodyCompressionStats,
+}
+
+/// How much of the stored request and response bodies is compressed.
+#[derive(Debug, Clone, Default)]
+pub struct BodyCompressionStats {
+    /// Bodies stored zstd-compressed.
+    pub compressed_count: i64,
+    /// Bytes those bodies take compressed.
+    pub compressed_bytes: i64,
+    /// Bytes those bodies had before compression.
+    pub original_bytes: i64,
+    /// Bodies over the compression threshold still stored as text, e.g.
+    /// logged before compression was turned on.
+    pub pending_count: i64,
+}
+
+/// A database snapshot file in the backup directory.
+#[derive(Debug, Clone)]
+pub struct DatabaseSnapshot {
+    pub file_name: String,
+    pub size_bytes: u64,
+}
+
+/// A request held at a session breakpoint, waiting to be forwarded or
+/// rejected.
+#[derive(Debug, Clone)]
+pub struct HeldRequestInfo {
+    pub request_id: String,
+    pub body: Value,
+    pub waiting_secs: u64,
+}
+
+/// A WebFetch interception whose rounds are still running.
+#[derive(Debug, Clone)]
+pub struct RunningInterceptInfo {
+    /// The intercepted request.
+    pub request_id: String,
+    /// The round in progress, counted from 1.
+    pub round: usize,
+    pub running_secs: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct PendingToolInfo {
+    pub tool_use_id: String,
     pub name: String,
     pub input_summary: String,
+    /// The full tool input, shown for editing before an Accept.
+    pub input: Value,
+    /// Label of the decision already made for this call alone, if any.
+    pub decision: Option<String>,
+    /// The dashboard user who made that decision.
+    pub decided_by: Option<String>,
+}
+
+/// A pending approval round as listed across all sessions.
+#[derive(Debug, Clone)]
+pub struct PendingApprovalInfo {
+    pub approval_id: String,
+    pub session_id: String,
+    pub tools: Vec<PendingToolInfo>,
+    /// How long the round has been waiting for a decision, in seconds.
+    pub waiting_secs: u64,
 }
 
 pub const DEFAULT_SYSTEM_FILTER_SUGGESTIONS: &[&str] = &[
@@ -115,3 +548,98 @@ pub const DEFAULT_SYSTEM_FILTER_SUGGESTIONS: &[&str] = &[
 ];
 
 pub const DEFAULT_TOOL_FILTER_SUGGESTIONS: &[&str] = &["WebSearch"];
+
+pub const DEFAULT_WEBFETCH_DENY_SUGGESTIONS: &[&str] = &[
+    "127.0.0.0/8",
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "169.254.0.0/16",
+    "::1/128",
+    "fc00::/7",
+    "fe80::/10",
+    "localhost",
+    "metadata.google.internal",
+];
+
+/// Traffic across all sessions, for the home page.
+#[derive(Debug, Clone, Default)]
+pub struct RequestStats {
+    /// Requests logged since midnight UTC.
+    pub requests_today: i64,
+    /// Requests with a response stored.
+    pub responded_count: i64,
+    /// Responses with a 4xx or 5xx status.
+    pub error_count: i64,
+    /// Tool calls handled by WebFetch interception, over all rounds.
+    pub intercepted_tool_calls: i64,
+    /// Requests per model, most-used first.
+    pub model_request_counts: Vec<(String, i64)>,
+}
+
+/// A request listed across sessions, on the home page and a model's requests page.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct RecentRequest {
+    #[sqlx(try_from = "String")]
+    pub id: uuid::Uuid,
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub session_name: String,
+    pub method: String,
+    pub path: String,
+    pub model: Option<String>,
+    pub response_status: Option<i64>,
+    pub error_kind: Option<String>,
+    pub created_at: String,
+}
+
+/// One model's traffic across all sessions, for the models page.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ModelStats {
+    pub model: String,
+    pub request_count: i64,
+    /// Input tokens reported in the usage of stored responses.
+    pub input_tokens: i64,
+    /// Output tokens reported in the usage of stored responses.
+    pub output_tokens: i64,
+    /// Mean over requests with a measured latency; `None` when there are none.
+    pub avg_latency_ms: Option<f64>,
+    pub last_seen_at: String,
+}
+
+/// One tool's calls in a session, for the tools page. Calls are counted
+/// once by `tool_use` id, from the requests sending their results back.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ToolCallStats {
+    pub tool_name: String,
+    pub call_count: i64,
+    /// Calls whose `tool_result` had `is_error` set.
+    pub error_count: i64,
+    pub avg_input_bytes: f64,
+    /// Requests carrying results of the tool's calls.
+    pub request_count: i64,
+}
+
+/// Requests in a session with the same body, for the duplicates page.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DuplicateGroup {
+    pub body_hash: String,
+    pub request_count: i64,
+    /// The earliest request sent with this body.
+    #[sqlx(try_from = "String")]
+    pub first_request_id: uuid::Uuid,
+    pub model: Option<String>,
+    pub title: Option<String>,
+    pub last_seen_at: String,
+}
+
+/// Failed upstream calls of one kind in one session, for the errors page.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ErrorSummary {
+    #[sqlx(try_from = "String")]
+    pub session_id: uuid::Uuid,
+    pub session_name: String,
+    pub error_kind: String,
+    pub error_count: i64,
+    pub last_seen_at: String,
+}
@@ -0,0 +1,71 @@
+/// When an auto-decision policy applies to a tool call.
+pub struct PolicyCondition {
+    /// The key stored in the DB (e.g. "outside_hours").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What the condition checks and what its value means.
+    pub description: &'static str,
+}
+
+/// All policy conditions.
+pub const POLICY_CONDITIONS: &[PolicyCondition] = &[
+    PolicyCondition {
+        key: "always",
+        label: "Always",
+        description: "Every call. Takes no value.",
+    },
+    PolicyCondition {
+        key: "outside_hours",
+        label: "Outside working hours",
+        description: "Calls made outside the working hours given as HH:MM-HH:MM (UTC), \
+                      Monday to Friday; weekends are always outside.",
+    },
+    PolicyCondition {
+        key: "repeat_approvals",
+        label: "After N identical approvals",
+        description: "Calls with the same tool and input that you accepted at least N times \
+                      in this session.",
+    },
+];
+
+/// What an auto-decision policy does with a matching tool call.
+pub struct PolicyAction {
+    /// The key stored in the DB (e.g. "mock").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+}
+
+/// All policy actions. "ask" queues the call for the user and stops the
+/// whitelist from accepting it.
+pub const POLICY_ACTIONS: &[PolicyAction] = &[
+    PolicyAction {
+        key: "accept",
+        label: "Accept",
+    },
+    PolicyAction {
+        key: "mock",
+        label: "Mock",
+    },
+    PolicyAction {
+        key: "fail",
+        label: "Fail",
+    },
+    PolicyAction {
+        key: "ask",
+        label: "Ask",
+    },
+];
+
+/// Look up a known policy condition by its key, or `None` if unknown.
+pub fn find_condition_by_key(key: &str) -> Option<&'static PolicyCondition> {
+    POLICY_CONDITIONS
+        .iter()
+        .find(|condition| condition.key == key)
+}
+
+/// Look up a known policy action by its key, or `None` if unknown.
+pub fn find_action_by_key(key: &str) -> Option<&'static PolicyAction> {
+    POLICY_ACTIONS.iter().find(|action| action.key == key)
+}
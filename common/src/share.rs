@@ -0,0 +1,198 @@
+//! Share links: signed tokens granting read-only access to one request's
+//! detail pages without dashboard credentials. A token is
+//! `{session_id}.{request_id}.{expires_at}.{signature}`, where `expires_at`
+//! is a Unix time in seconds (0 never expires) and the signature is an
+//! HMAC-SHA256 of the rest under the server's share secret.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Cookie holding a share token, scoped to the shared request's pages.
+pub const SHARE_COOKIE: &str = "gateway_proxy_share";
+
+/// Settings key of the secret share tokens are signed with.
+pub const SHARE_SECRET_SETTING: &str = "share_link_secret";
+
+/// (hours, label) of the expiries offered when sharing; 0 never expires.
+pub const SHARE_LINK_EXPIRIES: &[(i64, &str)] = &[
+    (1, "1 hour"),
+    (24, "1 day"),
+    (24 * 7, "7 days"),
+    (24 * 30, "30 days"),
+    (0, "Never"),
+];
+
+/// Detail subpages of a shared request a share link opens, by the path
+/// segment after the request's.
+const SHARED_REQUEST_PAGES: &[&str] = &[
+    "messages",
+    "system",
+    "tools",
+    "params",
+    "applied_filters",
+    "headers",
+    "full_json",
+    "response_headers",
+    "response_sse",
+    "response_raw",
+    "response_rendered",
+    "export",
+    "caching",
+    "fixture",
+    "webfetch_intercept",
+];
+
+/// Agent request subpages under the WebFetch Intercept page a share link
+/// opens. Handlers check the agent request belongs to the shared one.
+const SHARED_AGENT_PAGES: &[&str] = &[
+    "messages",
+    "system",
+    "tools",
+    "params",
+    "headers",
+    "full_json",
+    "response_headers",
+    "response_sse",
+];
+
+/// The key share tokens are signed with, generated once and kept in the
+/// settings table so links survive restarts.
+pub struct ShareSecret(pub Vec<u8>);
+
+/// The request a valid share token grants access to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareGrant {
+    pub session_id: String,
+    pub request_id: String,
+    /// Unix time in seconds the grant ends at; `None` never expires.
+    pub expires_at: Option<i64>,
+}
+
+impl ShareGrant {
+    /// The dashboard path of the shared request's detail page.
+    pub fn build_request_path(&self) -> String {
+        format!(
+            "/_dashboard/sessions/{}/requests/{}",
+            self.session_id, self.request_id
+        )
+    }
+
+    /// Whether `path` is the shared request's detail page or one of the
+    /// read-only subpages a share link opens.
+    pub fn allows_path(&self, path: &str) -> bool {
+        let request_path = self.build_request_path();
+        let Some(rest) = path.strip_prefix(&request_path) else {
+            return false;
+        };
+        if rest.is_empty() {
+            return true;
+        }
+        let Some(rest) = rest.strip_prefix('/') else {
+            return false;
+        };
+        let segments: Vec<&str> = rest.split('/').collect();
+        match segments.as_slice() {
+            [page] => SHARED_REQUEST_PAGES.contains(page),
+            ["download", file] | ["blocks", file, "download"] => !file.is_empty(),
+            ["webfetch_intercept", "transcript"] => true,
+            ["webfetch_intercept", "agent", agent_request_id] => !agent_request_id.is_empty(),
+            ["webfetch_intercept", "agent", agent_request_id, page] => {
+                !agent_request_id.is_empty() && SHARED_AGENT_PAGES.contains(page)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn build_share_mac(secret: &[u8], payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+pub fn sign_share_token(secret: &[u8], share_grant: &ShareGrant) -> String {
+    let payload = format!(
+        "{}.{}.{}",
+        share_grant.session_id,
+        share_grant.request_id,
+        share_grant.expires_at.unwrap_or(0)
+    );
+    let signature = hex::encode(build_share_mac(secret, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, signature)
+}
+
+/// The grant of a token signed with `secret` that hasn't expired at `now`
+/// (Unix seconds), or `None`.
+pub fn verify_share_token(secret: &[u8], token: &str, now: i64) -> Option<ShareGrant> {
+    let (payload, signature) = token.rsplit_once('.')?;
+    build_share_mac(secret, payload)
+        .verify_slice(&hex::decode(signature).ok()?)
+        .ok()?;
+    let mut parts = payload.split('.');
+    let session_id = parts.next()?.to_string();
+    let request_id = parts.next()?.to_string();
+    let expires_at: i64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || (expires_at != 0 && expires_at <= now) {
+        return None;
+    }
+    Some(ShareGrant {
+        session_id,
+        request_id,
+        expires_at: (expires_at != 0).then_some(expires_at),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_share_token_checks_signature_and_expiry() {
+        let share_grant = ShareGrant {
+            session_id: "s1".to_string(),
+            request_id: "r1".to_string(),
+            expires_at: Some(1_000),
+        };
+        let token = sign_share_token(b"secret", &share_grant);
+        assert_eq!(
+            verify_share_token(b"secret", &token, 999),
+            Some(share_grant.clone())
+        );
+        assert_eq!(verify_share_token(b"secret", &token, 1_000), None);
+        assert_eq!(verify_share_token(b"other", &token, 999), None);
+        let forged = token.replacen("r1", "r2", 1);
+        assert_eq!(verify_share_token(b"secret", &forged, 999), None);
+
+        let forever = ShareGrant {
+            expires_at: None,
+            ..share_grant
+        };
+        let token = sign_share_token(b"secret", &forever);
+        assert_eq!(
+            verify_share_token(b"secret", &token, i64::MAX),
+            Some(forever)
+        );
+    }
+
+    #[test]
+    fn allows_path_covers_only_the_request_pages() {
+        let share_grant = ShareGrant {
+            session_id: "s1".to_string(),
+            request_id: "r1".to_string(),
+            expires_at: None,
+        };
+        assert!(share_grant.allows_path("/_dashboard/sessions/s1/requests/r1"));
+        assert!(share_grant.allows_path("/_dashboard/sessions/s1/requests/r1/messages"));
+        assert!(share_grant.allows_path("/_dashboard/sessions/s1/requests/r1/download/body.json"));
+        assert!(share_grant.allows_path(
+            "/_dashboard/sessions/s1/requests/r1/webfetch_intercept/agent/a1/messages"
+        ));
+        assert!(!share_grant.allows_path("/_dashboard/sessions/s1/requests/r1/notes"));
+        assert!(!share_grant.allows_path("/_dashboard/sessions/s1/requests/r1/messages/extra"));
+        assert!(!share_grant.allows_path(
+            "/_dashboard/sessions/s1/requests/r1/webfetch_intercept/agent/a1/unknown"
+        ));
+        assert!(!share_grant.allows_path("/_dashboard/sessions/s1/requests/r10"));
+        assert!(!share_grant.allows_path("/_dashboard/sessions/s1/requests"));
+    }
+}
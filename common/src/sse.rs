@@ -0,0 +1,622 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Find the `stop_reason` reported by the `message_delta` event.
+pub fn find_stop_reason(events: &[Value]) -> Option<String> {
+    events.iter().find_map(|event| {
+        if event.get("event").and_then(|field| field.as_str()) != Some("message_delta") {
+            return None;
+        }
+        event
+            .get("data")
+            .and_then(|data| data.get("delta"))
+            .and_then(|delta| delta.get("stop_reason"))
+            .and_then(|field| field.as_str())
+            .map(|string| string.to_string())
+    })
+}
+
+/// Merge the token usage reported by the `message_start` and
+/// `message_delta` events; later counts replace earlier ones.
+pub fn collect_usage(events: &[Value]) -> serde_json::Map<String, Value> {
+    let mut usage = serde_json::Map::new();
+    for event in events {
+        let event_usage = match event.get("event").and_then(|field| field.as_str()) {
+            Some("message_start") => event.pointer("/data/message/usage"),
+            Some("message_delta") => event.pointer("/data/usage"),
+            _ => None,
+        };
+        if let Some(Value::Object(event_usage)) = event_usage {
+            usage.extend(event_usage.clone());
+        }
+    }
+    usage
+}
+
+/// Deltas accumulated for the content block currently being streamed.
+#[derive(Default)]
+struct BlockDeltas {
+    text: String,
+    json: String,
+    thinking: String,
+    signature: String,
+}
+
+impl BlockDeltas {
+    fn push_delta(&mut self, delta: &Value) {
+        let delta_type = delta
+            .get("type")
+            .and_then(|field| field.as_str())
+            .unwrap_or("");
+        let (target, key) = match delta_type {
+            "text_delta" => (&mut self.text, "text"),
+            "input_json_delta" => (&mut self.json, "partial_json"),
+            "thinking_delta" => (&mut self.thinking, "thinking"),
+            "signature_delta" => (&mut self.signature, "signature"),
+            _ => return,
+        };
+        if let Some(part) = delta.get(key).and_then(|field| field.as_str()) {
+            target.push_str(part);
+        }
+    }
+
+    /// Fill the accumulated deltas into the block started by `content_block_start`.
+    fn apply_to_block(&self, block: &mut Value) {
+        let block_type = block
+            .get("type")
+            .and_then(|field| field.as_str())
+            .unwrap_or("")
+            .to_string();
+        match block_type.as_str() {
+            "text" => {
+                block["text"] = Value::String(self.text.clone());
+            }
+            "thinking" => {
+                block["thinking"] = Value::String(self.thinking.clone());
+                if !self.signature.is_empty() {
+                    block["signature"] = Value::String(self.signature.clone());
+                }
+            }
+            // Both custom tool_use and native server_tool_use carry input JSON
+            "tool_use" | "server_tool_use" => {
+                let input: Value =
+                    serde_json::from_str(&self.json).unwrap_or(serde_json::json!({}));
+                block["input"] = input;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reconstruct the final assistant content blocks from parsed SSE events,
+/// joining text, thinking, signature and tool input deltas into each block.
+pub fn reconstruct_content_blocks(events: &[Value]) -> Vec<Value> {
+    let mut content_blocks: Vec<Value> = Vec::new();
+    let mut current_block: Option<Value> = None;
+    let mut block_deltas = BlockDeltas::default();
+
+    for event in events {
+        let Some(event_type) = event.get("event").and_then(|field| field.as_str()) else {
+            continue;
+        };
+        let Some(data) = event.get("data") else {
+            continue;
+        };
+
+        match event_type {
+            "content_block_start" => {
+                current_block = data.get("content_block").cloned();
+                block_deltas = BlockDeltas::default();
+            }
+            "content_block_delta" => {
+                if let Some(delta) = data.get("delta") {
+                    block_deltas.push_delta(delta);
+                }
+            }
+            "content_block_stop" => {
+                if let Some(mut block) = current_block.take() {
+                    block_deltas.apply_to_block(&mut block);
+                    content_blocks.push(block);
+                }
+            }
+            _ => {}
+        }
+    }
+    content_blocks
+}
+
+/// The start, delta and stop events a streamed response would have sent for
+/// one content block of a non-streaming response.
+fn build_block_events(index: usize, block: &Value) -> Vec<Value> {
+    let event = |event_type: &str, data: Value| {
+        let mut data = data;
+        data["type"] = Value::String(event_type.to_string());
+        serde_json::json!({"event": event_type, "data": data})
+    };
+    let mut start_block = block.clone();
+    let mut deltas = Vec::new();
+    match block.get("type").and_then(|field| field.as_str()).unwrap_or("") {
+        "text" => {
+            start_block["text"] = Value::String(String::new());
+            deltas.push(serde_json::json!({"type": "text_delta", "text": block.get("text")}));
+        }
+        "thinking" => {
+            start_block["thinking"] = Value::String(String::new());
+            start_block["signature"] = Value::String(String::new());
+            deltas.push(
+                serde_json::json!({"type": "thinking_delta", "thinking": block.get("thinking")}),
+            );
+            if let Some(signature) = block.get("signature") {
+                deltas.push(serde_json::json!({"type": "signature_delta", "signature": signature}));
+            }
+        }
+        "tool_use" | "server_tool_use" => {
+            start_block["input"] = serde_json::json!({});
+            let input = block.get("input").cloned().unwrap_or(serde_json::json!({}));
+            deltas.push(serde_json::json!({
+                "type": "input_json_delta",
+                "partial_json": input.to_string(),
+            }));
+        }
+        // Other blocks (e.g. tool results) arrive whole in content_block_start.
+        _ => {}
+    }
+
+    let mut events = vec![event(
+        "content_block_start",
+        serde_json::json!({"index": index, "content_block": start_block}),
+    )];
+    events.extend(deltas.into_iter().map(|delta| {
+        event(
+            "content_block_delta",
+            serde_json::json!({"index": index, "delta": delta}),
+        )
+    }));
+    events.push(event("content_block_stop", serde_json::json!({"index": index})));
+    events
+}
+
+/// Rebuild the events a streamed response would have sent from a
+/// non-streaming Messages API response, so both can share one viewer.
+/// Returns `None` unless the body is a `"type": "message"` object.
+pub fn build_message_events(body: &Value) -> Option<Vec<Value>> {
+    if body.get("type").and_then(|field| field.as_str()) != Some("message") {
+        return None;
+    }
+    let content_blocks = body.get("content")?.as_array()?;
+
+    let mut message = body.clone();
+    message["content"] = serde_json::json!([]);
+    message["stop_reason"] = Value::Null;
+    message["stop_sequence"] = Value::Null;
+    let mut events = vec![serde_json::json!({
+        "event": "message_start",
+        "data": {"type": "message_start", "message": message},
+    })];
+    for (index, block) in content_blocks.iter().enumerate() {
+        events.extend(build_block_events(index, block));
+    }
+    let mut message_delta = serde_json::json!({
+        "type": "message_delta",
+        "delta": {
+            "stop_reason": body.get("stop_reason"),
+            "stop_sequence": body.get("stop_sequence"),
+        },
+    });
+    if let Some(output_tokens) = body.pointer("/usage/output_tokens") {
+        message_delta["usage"] = serde_json::json!({"output_tokens": output_tokens});
+    }
+    events.push(serde_json::json!({"event": "message_delta", "data": message_delta}));
+    events.push(serde_json::json!({"event": "message_stop", "data": {"type": "message_stop"}}));
+    Some(events)
+}
+
+/// The events of a stored response: the recorded SSE events, or events
+/// rebuilt from a JSON message body when nothing was streamed.
+pub fn load_response_events(events_json: Option<&str>, response_body: Option<&str>) -> Vec<Value> {
+    let events = events_json
+        .and_then(|json| serde_json::from_str::<Vec<Value>>(json).ok())
+        .unwrap_or_default();
+    if !events.is_empty() {
+        return events;
+    }
+    response_body
+        .and_then(|body| serde_json::from_str::<Value>(body).ok())
+        .and_then(|body| build_message_events(&body))
+        .unwrap_or_default()
+}
+
+/// Event types of the Messages streaming protocol.
+const MESSAGE_STREAM_EVENT_TYPES: &[&str] = &[
+    "message_start",
+    "content_block_start",
+    "content_block_delta",
+    "content_block_stop",
+    "message_delta",
+    "message_stop",
+    "ping",
+    "error",
+];
+
+/// Where a recorded stream is in the Messages streaming protocol.
+#[derive(Default)]
+struct MessageStreamState {
+    message_started: bool,
+    message_stopped: bool,
+    errored: bool,
+    /// Open content blocks by index, with their `input_json_delta` text so far.
+    open_blocks: HashMap<i64, String>,
+    stopped_blocks: Vec<i64>,
+    protocol_issues: Vec<String>,
+}
+
+impl MessageStreamState {
+    fn report(&mut self, event_index: usize, issue: String) {
+        self.protocol_issues
+            .push(format!("event {}: {}", event_index, issue));
+    }
+
+    fn check_event(&mut self, event_index: usize, event: &Value) {
+        if event.get("unparsed").and_then(|field| field.as_bool()) == Some(true) {
+            self.report(event_index, "line outside the SSE grammar".to_string());
+            return;
+        }
+        let event_name = event.get("event").and_then(|field| field.as_str());
+        let data = event.get("data").unwrap_or(&Value::Null);
+        if !data.is_object() {
+            let issue = format!(
+                "{} data is not a JSON object",
+                event_name.unwrap_or("event")
+            );
+            self.report(event_index, issue);
+            return;
+        }
+        let data_type = data.get("type").and_then(|field| field.as_str());
+        match (event_name, data_type) {
+            (None, _) => self.report(event_index, "no event: line".to_string()),
+            (Some(event_name), Some(data_type)) if event_name != data_type => {
+                let issue = format!("event {} carries data of type {}", event_name, data_type);
+                self.report(event_index, issue);
+            }
+            _ => {}
+        }
+        let event_type = event_name.or(data_type).unwrap_or("");
+        if self.message_stopped {
+            self.report(event_index, format!("{} after message_stop", event_type));
+        }
+        if event_type != "message_start"
+            && event_type != "ping"
+            && event_type != "error"
+            && !self.message_started
+        {
+            self.report(event_index, format!("{} before message_start", event_type));
+            self.message_started = true;
+        }
+        let index = data.get("index").and_then(|field| field.as_i64());
+        match event_type {
+            "message_start" if self.message_started => {
+                self.report(event_index, "repeated message_start".to_string());
+            }
+            "message_start" => self.message_started = true,
+            "content_block_start" => self.start_block(event_index, index),
+            "content_block_delta" => self.push_block_delta(event_index, index, data),
+            "content_block_stop" => self.stop_block(event_index, index),
+            "message_delta" | "message_stop" => {
+                for index in self.drain_open_blocks() {
+                    self.report(
+                        event_index,
+                        format!("{} while block {} is still open", event_type, index),
+                    );
+                }
+                self.message_stopped |= event_type == "message_stop";
+            }
+            "error" => self.errored = true,
+            "ping" => {}
+            _ => self.report(event_index, format!("unknown event type {:?}", event_type)),
+        }
+    }
+
+    fn start_block(&mut self, event_index: usize, index: Option<i64>) {
+        let Some(index) = index else {
+            self.report(
+                event_index,
+                "content_block_start without an index".to_string(),
+            );
+            return;
+        };
+        if self.open_blocks.contains_key(&index) || self.stopped_blocks.contains(&index) {
+            self.report(event_index, format!("block {} started twice", index));
+        }
+        self.open_blocks.insert(index, String::new());
+    }
+
+    fn push_block_delta(&mut self, event_index: usize, index: Option<i64>, data: &Value) {
+        let Some(index) = index else {
+            self.report(
+                event_index,
+                "content_block_delta without an index".to_string(),
+            );
+            return;
+        };
+        let Some(partial_json) = self.open_blocks.get_mut(&index) else {
+            let issue = if self.stopped_blocks.contains(&index) {
+                format!("delta for block {} after its content_block_stop", index)
+            } else {
+                format!("delta for block {} before its content_block_start", index)
+            };
+            self.report(event_index, issue);
+            return;
+        };
+        if data.pointer("/delta/type").and_then(|field| field.as_str()) == Some("input_json_delta")
+        {
+            let part = data
+                .pointer("/delta/partial_json")
+                .and_then(|field| field.as_str())
+                .unwrap_or("");
+            partial_json.push_str(part);
+        }
+    }
+
+    fn stop_block(&mut self, event_index: usize, index: Option<i64>) {
+        let Some(index) = index else {
+            self.report(
+                event_index,
+                "content_block_stop without an index".to_string(),
+            );
+            return;
+        };
+        let Some(partial_json) = self.open_blocks.remove(&index) else {
+            self.report(
+                event_index,
+                format!("block {} stopped but was not open", index),
+            );
+            return;
+        };
+        self.stopped_blocks.push(index);
+        if !partial_json.is_empty() {
+            if let Err(e) = serde_json::from_str::<Value>(&partial_json) {
+                self.report(
+                    event_index,
+                    format!("block {} input_json_delta is not valid JSON: {}", index, e),
+                );
+            }
+        }
+    }
+
+    fn drain_open_blocks(&mut self) -> Vec<i64> {
+        let mut open_indices: Vec<i64> = self.open_blocks.drain().map(|(index, _)| index).collect();
+        open_indices.sort_unstable();
+        open_indices
+    }
+
+    fn finish(mut self, event_count: usize) -> Vec<String> {
+        if !self.errored {
+            for index in self.drain_open_blocks() {
+                self.protocol_issues
+                    .push(format!("block {} was never stopped", index));
+            }
+            if !self.message_stopped {
+                self.report(event_count, "stream ended without message_stop".to_string());
+            }
+        }
+        self.protocol_issues
+    }
+}
+
+/// Check recorded SSE events against the Messages streaming protocol and
+/// describe each violation, e.g. `"event 7: delta for block 0 after its
+/// content_block_stop"`. Streams that don't use the protocol at all, such
+/// as chat-completions streams, have no issues.
+pub fn find_protocol_issues(events: &[Value]) -> Vec<String> {
+    let is_message_stream = events.iter().any(|event| {
+        event
+            .get("event")
+            .or_else(|| event.pointer("/data/type"))
+            .and_then(|field| field.as_str())
+            .is_some_and(|event_type| MESSAGE_STREAM_EVENT_TYPES.contains(&event_type))
+    });
+    if !is_message_stream {
+        return vec![];
+    }
+    let mut message_stream_state = MessageStreamState::default();
+    for (event_index, event) in events.iter().enumerate() {
+        message_stream_state.check_event(event_index, event);
+    }
+    message_stream_state.finish(events.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_event(event_type: &str, data: Value) -> Value {
+        serde_json::json!({"event": event_type, "data": data})
+    }
+
+    #[test]
+    fn reconstruct_text_and_tool_use_blocks() {
+        let events = vec![
+            build_event(
+                "content_block_start",
+                serde_json::json!({"index": 0, "content_block": {"type": "text", "text": ""}}),
+            ),
+            build_event(
+                "content_block_delta",
+                serde_json::json!({"index": 0, "delta": {"type": "text_delta", "text": "Hi "}}),
+            ),
+            build_event(
+                "content_block_delta",
+                serde_json::json!({"index": 0, "delta": {"type": "text_delta", "text": "there"}}),
+            ),
+            build_event("content_block_stop", serde_json::json!({"index": 0})),
+            build_event(
+                "content_block_start",
+                serde_json::json!({"index": 1, "content_block": {"type": "tool_use", "id": "t1", "name": "get", "input": {}}}),
+            ),
+            build_event(
+                "content_block_delta",
+                serde_json::json!({"index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"a\": 1}"}}),
+            ),
+            build_event("content_block_stop", serde_json::json!({"index": 1})),
+        ];
+        let blocks = reconstruct_content_blocks(&events);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0]["text"], "Hi there");
+        assert_eq!(blocks[1]["input"]["a"], 1);
+    }
+
+    #[test]
+    fn reconstruct_thinking_with_signature() {
+        let events = vec![
+            build_event(
+                "content_block_start",
+                serde_json::json!({"index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+            ),
+            build_event(
+                "content_block_delta",
+                serde_json::json!({"index": 0, "delta": {"type": "thinking_delta", "thinking": "hmm"}}),
+            ),
+            build_event(
+                "content_block_delta",
+                serde_json::json!({"index": 0, "delta": {"type": "signature_delta", "signature": "sig"}}),
+            ),
+            build_event("content_block_stop", serde_json::json!({"index": 0})),
+        ];
+        let blocks = reconstruct_content_blocks(&events);
+        assert_eq!(blocks[0]["thinking"], "hmm");
+        assert_eq!(blocks[0]["signature"], "sig");
+    }
+
+    #[test]
+    fn stop_reason_found_in_message_delta() {
+        let events = vec![build_event(
+            "message_delta",
+            serde_json::json!({"delta": {"stop_reason": "end_turn"}}),
+        )];
+        assert_eq!(find_stop_reason(&events).as_deref(), Some("end_turn"));
+        assert_eq!(find_stop_reason(&[]), None);
+    }
+
+    #[test]
+    fn message_events_rebuild_json_response() {
+        let body = serde_json::json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude",
+            "content": [
+                {"type": "text", "text": "Let me check."},
+                {"type": "tool_use", "id": "t1", "name": "get", "input": {"a": 1}},
+            ],
+            "stop_reason": "tool_use",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 10, "output_tokens": 5},
+        });
+        let events = build_message_events(&body).unwrap();
+        assert_eq!(events.len(), 9);
+        assert_eq!(events[0]["data"]["message"]["content"], serde_json::json!([]));
+        assert_eq!(events[0]["data"]["message"]["usage"]["input_tokens"], 10);
+        assert_eq!(events[7]["data"]["usage"]["output_tokens"], 5);
+
+        let blocks = reconstruct_content_blocks(&events);
+        assert_eq!(blocks, body["content"].as_array().unwrap().clone());
+        assert_eq!(find_stop_reason(&events).as_deref(), Some("tool_use"));
+    }
+
+    #[test]
+    fn load_response_events_prefers_recorded_events() {
+        let message = r#"{"type":"message","content":[],"stop_reason":"end_turn"}"#;
+        let recorded = r#"[{"event":"message_stop","data":{}}]"#;
+        assert_eq!(load_response_events(Some(recorded), Some(message)).len(), 1);
+        assert_eq!(load_response_events(Some("[]"), Some(message)).len(), 3);
+        assert!(load_response_events(Some("[]"), Some(r#"{"type":"error"}"#)).is_empty());
+    }
+
+    fn build_message_stream(content_events: Vec<Value>) -> Vec<Value> {
+        let mut events = vec![build_event(
+            "message_start",
+            serde_json::json!({"type": "message_start", "message": {"content": []}}),
+        )];
+        events.extend(content_events);
+        events.push(build_event(
+            "message_delta",
+            serde_json::json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}}),
+        ));
+        events.push(build_event(
+            "message_stop",
+            serde_json::json!({"type": "message_stop"}),
+        ));
+        events
+    }
+
+    #[test]
+    fn protocol_issues_empty_for_valid_streams() {
+        let body = serde_json::json!({
+            "type": "message",
+            "content": [{"type": "tool_use", "id": "t1", "name": "get", "input": {"a": 1}}],
+            "stop_reason": "tool_use",
+        });
+        assert!(find_protocol_issues(&build_message_events(&body).unwrap()).is_empty());
+        let chat_completion = vec![serde_json::json!({"data": {"choices": []}})];
+        assert!(find_protocol_issues(&chat_completion).is_empty());
+    }
+
+    #[test]
+    fn protocol_issues_flag_unbalanced_blocks_and_late_deltas() {
+        let text_delta = serde_json::json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "text_delta", "text": "hi"},
+        });
+        let events = build_message_stream(vec![
+            build_event(
+                "content_block_start",
+                serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text"}}),
+            ),
+            build_event(
+                "content_block_stop",
+                serde_json::json!({"type": "content_block_stop", "index": 0}),
+            ),
+            build_event("content_block_delta", text_delta),
+            build_event(
+                "content_block_start",
+                serde_json::json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text"}}),
+            ),
+        ]);
+        assert_eq!(
+            find_protocol_issues(&events),
+            vec![
+                "event 3: delta for block 0 after its content_block_stop",
+                "event 5: message_delta while block 1 is still open",
+            ]
+        );
+    }
+
+    #[test]
+    fn protocol_issues_flag_events_before_message_start_and_bad_tool_json() {
+        let mut events = build_message_stream(vec![
+            build_event(
+                "content_block_start",
+                serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use"}}),
+            ),
+            build_event(
+                "content_block_delta",
+                serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": "{\"a\": "}}),
+            ),
+            build_event(
+                "content_block_stop",
+                serde_json::json!({"type": "content_block_stop", "index": 0}),
+            ),
+        ]);
+        events.remove(0);
+        let protocol_issues = find_protocol_issues(&events);
+        assert_eq!(protocol_issues.len(), 2);
+        assert_eq!(
+            protocol_issues[0],
+            "event 0: content_block_start before message_start"
+        );
+        assert!(
+            protocol_issues[1].starts_with("event 2: block 0 input_json_delta is not valid JSON")
+        );
+    }
+}
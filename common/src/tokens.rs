@@ -0,0 +1,203 @@
+//! Local prompt token estimates. These approximate the model's tokenizer;
+//! callers pass a `Tokenizer`, so a closer one can replace the heuristic.
+
+use serde_json::Value;
+
+/// Fixed per-message overhead for role markers and turn separators.
+const MESSAGE_OVERHEAD_TOKENS: i64 = 3;
+
+/// Fixed per-tool overhead for the tool definition wrapper.
+const TOOL_OVERHEAD_TOKENS: i64 = 8;
+
+/// Counts the tokens of a piece of prompt text.
+pub trait Tokenizer {
+    fn count_tokens(&self, text: &str) -> i64;
+}
+
+/// The built-in tokenizer, see `count_text_tokens`.
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> i64 {
+        count_text_tokens(text)
+    }
+}
+
+/// Count tokens in a text with a simple word-piece approximation: each run of
+/// alphanumeric characters costs one token per four characters, and every
+/// other non-whitespace character costs one token.
+pub fn count_text_tokens(text: &str) -> i64 {
+    let mut token_count = 0;
+    let mut word_len = 0;
+    for character in text.chars() {
+        if character.is_alphanumeric() {
+            word_len += 1;
+            continue;
+        }
+        token_count += (word_len + 3) / 4;
+        word_len = 0;
+        if !character.is_whitespace() {
+            token_count += 1;
+        }
+    }
+    token_count + (word_len + 3) / 4
+}
+
+/// Estimated prompt tokens by what they are spent on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextBreakdown {
+    pub system: i64,
+    pub tools: i64,
+    /// Message text, thinking and tool calls, with per-message overhead.
+    pub messages: i64,
+    /// Content returned to the model in tool_result blocks.
+    pub tool_results: i64,
+}
+
+impl ContextBreakdown {
+    pub fn total(&self) -> i64 {
+        self.system + self.tools + self.messages + self.tool_results
+    }
+}
+
+/// Split a content value, a plain string or an array of content blocks, into
+/// (message tokens, tool_result tokens).
+fn count_content_tokens(tokenizer: &dyn Tokenizer, content: &Value) -> (i64, i64) {
+    match content {
+        Value::String(text) => (tokenizer.count_tokens(text), 0),
+        Value::Array(blocks) => blocks
+            .iter()
+            .fold((0, 0), |(messages, tool_results), block| {
+                let (block_messages, block_tool_results) =
+                    count_content_block_tokens(tokenizer, block);
+                (messages + block_messages, tool_results + block_tool_results)
+            }),
+        _ => (0, 0),
+    }
+}
+
+fn count_content_block_tokens(tokenizer: &dyn Tokenizer, block: &Value) -> (i64, i64) {
+    match block.get("type").and_then(|field| field.as_str()) {
+        Some("text") => (count_field_tokens(tokenizer, block, "text"), 0),
+        Some("thinking") => (count_field_tokens(tokenizer, block, "thinking"), 0),
+        Some("tool_use") => {
+            let input_tokens = block
+                .get("input")
+                .map(|input| tokenizer.count_tokens(&input.to_string()))
+                .unwrap_or(0);
+            (
+                count_field_tokens(tokenizer, block, "name") + input_tokens,
+                0,
+            )
+        }
+        Some("tool_result") => {
+            let (messages, tool_results) = block
+                .get("content")
+                .map(|content| count_content_tokens(tokenizer, content))
+                .unwrap_or_default();
+            (0, messages + tool_results)
+        }
+        _ => (0, 0),
+    }
+}
+
+fn count_field_tokens(tokenizer: &dyn Tokenizer, value: &Value, field_name: &str) -> i64 {
+    value
+        .get(field_name)
+        .and_then(|field| field.as_str())
+        .map(|text| tokenizer.count_tokens(text))
+        .unwrap_or(0)
+}
+
+fn count_tool_tokens(tokenizer: &dyn Tokenizer, tool: &Value) -> i64 {
+    TOOL_OVERHEAD_TOKENS
+        + count_field_tokens(tokenizer, tool, "name")
+        + count_field_tokens(tokenizer, tool, "description")
+        + tool
+            .get("input_schema")
+            .map(|schema| tokenizer.count_tokens(&schema.to_string()))
+            .unwrap_or(0)
+}
+
+/// Estimate the prompt tokens of a Messages request's `system`, `tools` and
+/// `messages` values, broken down by component.
+pub fn estimate_context_breakdown(
+    tokenizer: &dyn Tokenizer,
+    system: Option<&Value>,
+    tools: Option<&Value>,
+    messages: Option<&Value>,
+) -> ContextBreakdown {
+    let mut context_breakdown = ContextBreakdown {
+        system: system
+            .map(|system| count_content_tokens(tokenizer, system).0)
+            .unwrap_or(0),
+        tools: tools
+            .and_then(|tools| tools.as_array())
+            .map(|tools| {
+                tools
+                    .iter()
+                    .map(|tool| count_tool_tokens(tokenizer, tool))
+                    .sum()
+            })
+            .unwrap_or(0),
+        ..ContextBreakdown::default()
+    };
+    for message in messages
+        .and_then(|messages| messages.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let (message_tokens, tool_result_tokens) = message
+            .get("content")
+            .map(|content| count_content_tokens(tokenizer, content))
+            .unwrap_or_default();
+        context_breakdown.messages += MESSAGE_OVERHEAD_TOKENS + message_tokens;
+        context_breakdown.tool_results += tool_result_tokens;
+    }
+    context_breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_tokens_split_words_and_punctuation() {
+        assert_eq!(count_text_tokens(""), 0);
+        assert_eq!(count_text_tokens("hi"), 1);
+        assert_eq!(count_text_tokens("hello, world!"), 6);
+        assert_eq!(count_text_tokens("internationalization"), 5);
+    }
+
+    #[test]
+    fn context_breakdown_separates_tool_results() {
+        let messages = serde_json::json!([
+            {"role": "user", "content": "hi"},
+            {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "get", "input": {}}
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": "hello, world!"}
+            ]}
+        ]);
+        let tools = serde_json::json!([{"name": "get", "input_schema": {}}]);
+        let system = serde_json::json!("Be brief.");
+        let context_breakdown = estimate_context_breakdown(
+            &HeuristicTokenizer,
+            Some(&system),
+            Some(&tools),
+            Some(&messages),
+        );
+        // messages: (3 + 1) + (3 + 1 + 2) + 3
+        assert_eq!(
+            context_breakdown,
+            ContextBreakdown {
+                system: 4,
+                tools: 11,
+                messages: 13,
+                tool_results: 6,
+            }
+        );
+        assert_eq!(context_breakdown.total(), 34);
+    }
+}
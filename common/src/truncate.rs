@@ -1,24 +1,327 @@
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value;
+use std::sync::RwLock;
+
+/// String length limit used when neither a field limit nor the profile sets one.
+pub const DEFAULT_TRUNCATE_MAX_CHARS: usize = 100;
+
+/// Name of the profile built from `truncate_max_chars` and
+/// `truncate_field_limits`; it produces the stored truncated body.
+pub const DEFAULT_TRUNCATION_PROFILE: &str = "default";
+
+static TRUNCATION_PROFILES: RwLock<Vec<TruncationProfile>> = RwLock::new(Vec::new());
+
+/// String length limit for one field and everything nested under it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct FieldLimit {
+    /// Object keys joined with `.`, with `[]` for each array element,
+    /// e.g. `messages[].content` or `tools[].description`.
+    pub path: String,
+    /// 0 leaves the field's strings whole.
+    pub max_chars: usize,
+}
+
+/// A named set of truncation limits to view a request body with.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct TruncationProfile {
+    pub name: String,
+    /// Limit for fields without a field limit; 0 leaves them whole.
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    #[serde(default)]
+    pub field_limits: Vec<FieldLimit>,
+}
+
+/// Set the profiles `truncate_with_profile` picks from; the first is the default.
+pub fn set_truncation_profiles(profiles: Vec<TruncationProfile>) {
+    *TRUNCATION_PROFILES.write().unwrap() = profiles;
+}
+
+/// Names of the configured profiles, default first.
+pub fn list_truncation_profile_names() -> Vec<String> {
+    TRUNCATION_PROFILES
+        .read()
+        .unwrap()
+        .iter()
+        .map(|profile| profile.name.clone())
+        .collect()
+}
+
+/// Truncate with the named profile, or the default one when `profile_name` is
+/// `None` or unknown.
+pub fn truncate_with_profile(value: &Value, profile_name: Option<&str>) -> Value {
+    let mut truncated = value.clone();
+    truncate_with_profile_in_place(&mut truncated, profile_name);
+    truncated
+}
+
+fn truncate_with_profile_in_place(value: &mut Value, profile_name: Option<&str>) {
+    let profiles = TRUNCATION_PROFILES.read().unwrap();
+    match find_profile(&profiles, profile_name) {
+        Some(profile) => truncate_strings_at_path(value, profile, ""),
+        None => truncate_strings_in_place(value, DEFAULT_TRUNCATE_MAX_CHARS),
+    }
+}
+
+/// Serialize `value` compactly as `truncate_with_profile` would truncate it,
+/// without copying it first.
+pub fn to_truncated_json(value: &Value, profile_name: Option<&str>) -> serde_json::Result<String> {
+    let profiles = TRUNCATION_PROFILES.read().unwrap();
+    serialize_truncated(value, find_profile(&profiles, profile_name))
+}
+
+fn find_profile<'a>(
+    profiles: &'a [TruncationProfile],
+    profile_name: Option<&str>,
+) -> Option<&'a TruncationProfile> {
+    profile_name
+        .and_then(|name| profiles.iter().find(|profile| profile.name == name))
+        .or(profiles.first())
+}
+
+fn serialize_truncated(
+    value: &Value,
+    profile: Option<&TruncationProfile>,
+) -> serde_json::Result<String> {
+    serde_json::to_string(&TruncatedValue {
+        value,
+        profile,
+        path: String::new(),
+    })
+}
+
+/// A value that serializes with its strings cut to the profile's limits, or
+/// to `DEFAULT_TRUNCATE_MAX_CHARS` without a profile.
+struct TruncatedValue<'a> {
+    value: &'a Value,
+    profile: Option<&'a TruncationProfile>,
+    /// Field path of `value`; only tracked when there is a profile.
+    path: String,
+}
+
+impl<'a> TruncatedValue<'a> {
+    fn child(&self, value: &'a Value, path: impl FnOnce() -> String) -> Self {
+        TruncatedValue {
+            value,
+            profile: self.profile,
+            path: self.profile.map(|_| path()).unwrap_or_default(),
+        }
+    }
+}
+
+impl Serialize for TruncatedValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.value {
+            Value::String(string) => {
+                let max_len = self.profile.map_or(DEFAULT_TRUNCATE_MAX_CHARS, |profile| {
+                    find_field_max_chars(profile, &self.path)
+                });
+                match find_cut_index(string, max_len) {
+                    Some(cut_index) => {
+                        serializer.serialize_str(&format!("{}...", &string[..cut_index]))
+                    }
+                    None => serializer.serialize_str(string),
+                }
+            }
+            Value::Array(array) => {
+                let element_path = format!("{}[]", self.path);
+                serializer.collect_seq(
+                    array
+                        .iter()
+                        .map(|element| self.child(element, || element_path.clone())),
+                )
+            }
+            Value::Object(object) => serializer.collect_map(object.iter().map(|(key, child)| {
+                let child_value = self.child(child, || {
+                    if self.path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", self.path, key)
+                    }
+                });
+                (key, child_value)
+            })),
+            other => other.serialize(serializer),
+        }
+    }
+}
 
 pub fn truncate_strings(value: &Value, max_len: usize) -> Value {
+    let mut truncated = value.clone();
+    truncate_strings_in_place(&mut truncated, max_len);
+    truncated
+}
+
+fn truncate_strings_in_place(value: &mut Value, max_len: usize) {
+    match value {
+        Value::String(string) => truncate_string(string, max_len),
+        Value::Array(array) => array
+            .iter_mut()
+            .for_each(|element| truncate_strings_in_place(element, max_len)),
+        Value::Object(object) => object
+            .values_mut()
+            .for_each(|child| truncate_strings_in_place(child, max_len)),
+        _ => {}
+    }
+}
+
+/// Truncate each string at the limit of the longest field limit path that is
+/// its own path or a parent of it, falling back to the profile's `max_chars`.
+pub fn truncate_strings_with_profile(value: &Value, profile: &TruncationProfile) -> Value {
+    let mut truncated = value.clone();
+    truncate_strings_at_path(&mut truncated, profile, "");
+    truncated
+}
+
+fn truncate_strings_at_path(value: &mut Value, profile: &TruncationProfile, path: &str) {
     match value {
-        Value::String(string) => {
-            if string.len() > max_len {
-                let truncated: String = string.chars().take(max_len).collect();
-                Value::String(format!("{}...", truncated))
-            } else {
-                value.clone()
+        Value::String(string) => truncate_string(string, find_field_max_chars(profile, path)),
+        Value::Array(array) => {
+            let element_path = format!("{}[]", path);
+            array
+                .iter_mut()
+                .for_each(|element| truncate_strings_at_path(element, profile, &element_path));
+        }
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                truncate_strings_at_path(child, profile, &child_path);
             }
         }
-        Value::Array(array) => {
-            Value::Array(array.iter().map(|element| truncate_strings(element, max_len)).collect())
+        _ => {}
+    }
+}
+
+fn find_field_max_chars(profile: &TruncationProfile, path: &str) -> usize {
+    profile
+        .field_limits
+        .iter()
+        .filter(|field_limit| is_path_within(path, &field_limit.path))
+        .max_by_key(|field_limit| field_limit.path.len())
+        .map(|field_limit| field_limit.max_chars)
+        .unwrap_or_else(|| profile.max_chars.unwrap_or(DEFAULT_TRUNCATE_MAX_CHARS))
+}
+
+fn is_path_within(path: &str, parent_path: &str) -> bool {
+    match path.strip_prefix(parent_path) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+        None => false,
+    }
+}
+
+/// Byte index to cut a string at to keep `max_len` chars, or `None` when it
+/// is short enough; 0 means no limit.
+fn find_cut_index(string: &str, max_len: usize) -> Option<usize> {
+    if max_len > 0 && string.len() > max_len {
+        Some(
+            string
+                .char_indices()
+                .nth(max_len)
+                .map_or(string.len(), |(index, _)| index),
+        )
+    } else {
+        None
+    }
+}
+
+/// Cut a string to `max_len` chars; 0 means no limit.
+fn truncate_string(string: &mut String, max_len: usize) {
+    if let Some(cut_index) = find_cut_index(string, max_len) {
+        string.truncate(cut_index);
+        string.push_str("...");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn build_test_profile() -> TruncationProfile {
+        TruncationProfile {
+            name: "prompts".to_string(),
+            max_chars: Some(3),
+            field_limits: vec![
+                FieldLimit {
+                    path: "messages[].content".to_string(),
+                    max_chars: 0,
+                },
+                FieldLimit {
+                    path: "messages[].content[].signature".to_string(),
+                    max_chars: 2,
+                },
+            ],
         }
-        Value::Object(object) => Value::Object(
-            object
-                .iter()
-                .map(|(key, child)| (key.clone(), truncate_strings(child, max_len)))
-                .collect(),
-        ),
-        _ => value.clone(),
+    }
+
+    #[test]
+    fn field_limits_override_profile_limit() {
+        let body = json!({
+            "model": "claude-sonnet",
+            "messages": [
+                {"role": "user", "content": "hello there"},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "long thought", "signature": "abcdef"}
+                ]}
+            ]
+        });
+        let truncated = truncate_strings_with_profile(&body, &build_test_profile());
+        assert_eq!(
+            truncated,
+            json!({
+                "model": "cla...",
+                "messages": [
+                    {"role": "use...", "content": "hello there"},
+                    {"role": "ass...", "content": [
+                        {"type": "thinking", "thinking": "long thought", "signature": "ab..."}
+                    ]}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_truncated_matches_truncating_a_copy() {
+        let body = json!({
+            "model": "claude-sonnet",
+            "max_tokens": 1024,
+            "messages": [
+                {"role": "user", "content": "hello there é".repeat(20)},
+                {"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "long thought", "signature": "abcdef"}
+                ]}
+            ]
+        });
+        let profile = build_test_profile();
+        assert_eq!(
+            serialize_truncated(&body, Some(&profile)).unwrap(),
+            serde_json::to_string(&truncate_strings_with_profile(&body, &profile)).unwrap()
+        );
+        assert_eq!(
+            serialize_truncated(&body, None).unwrap(),
+            serde_json::to_string(&truncate_strings(&body, DEFAULT_TRUNCATE_MAX_CHARS)).unwrap()
+        );
+    }
+
+    #[test]
+    fn field_limit_path_matches_whole_keys_only() {
+        let profile = TruncationProfile {
+            name: "models".to_string(),
+            max_chars: Some(3),
+            field_limits: vec![FieldLimit {
+                path: "model".to_string(),
+                max_chars: 0,
+            }],
+        };
+        let body = json!({"model": "abcdef", "model_name": "abcdef"});
+        let truncated = truncate_strings_with_profile(&body, &profile);
+        assert_eq!(
+            truncated,
+            json!({"model": "abcdef", "model_name": "abc..."})
+        );
     }
 }
@@ -0,0 +1,26 @@
+/// A starting configuration for a kind of upstream server.
+pub struct UpstreamPreset {
+    /// The key stored in the DB (e.g. "openai_compatible").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What the preset sets up.
+    pub description: &'static str,
+}
+
+/// Preset key of local OpenAI-compatible servers (Ollama, vLLM, LM Studio).
+pub const UPSTREAM_PRESET_OPENAI_COMPATIBLE: &str = "openai_compatible";
+
+/// All upstream presets.
+pub const UPSTREAM_PRESETS: &[UpstreamPreset] = &[UpstreamPreset {
+    key: UPSTREAM_PRESET_OPENAI_COMPATIBLE,
+    label: "Local OpenAI-compatible (Ollama, vLLM, LM Studio)",
+    description: "TLS verification off and no auth headers. Streamed chat-completions chunks \
+                  are decoded into response events, and the server's models are listed on \
+                  the session page.",
+}];
+
+/// Look up a known upstream preset by its key, or `None` if unknown.
+pub fn find_by_key(key: &str) -> Option<&'static UpstreamPreset> {
+    UPSTREAM_PRESETS.iter().find(|preset| preset.key == key)
+}
@@ -0,0 +1,126 @@
+/// Percent-encode everything except the unreserved characters, e.g. for a
+/// SigV4 path segment or a dashboard query value.
+pub fn encode_uri_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// What a masked query parameter value is stored and logged as.
+const MASKED_QUERY_VALUE: &str = "***";
+
+/// Read a session's query parameters, stored as one `name=value` per line.
+pub fn parse_query_params(text: Option<&str>) -> Vec<(String, String)> {
+    text.unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.split_once('=') {
+            Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+            None => (line.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Check query parameters from the session form: each line is `name=value`
+/// with a name made of letters, digits, `-`, `_`, `.` and `~`, so it reads the
+/// same encoded or not.
+pub fn validate_query_params(text: &str) -> Result<(), String> {
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some((name, _)) = line.split_once('=') else {
+            return Err(format!("expected name=value: {}", line));
+        };
+        let name = name.trim();
+        if name.is_empty() || encode_uri_component(name) != name {
+            return Err(format!("invalid parameter name: {}", name));
+        }
+    }
+    Ok(())
+}
+
+fn get_query_param_name(pair: &str) -> &str {
+    pair.split_once('=').map_or(pair, |(name, _)| name)
+}
+
+fn is_listed_query_param(pair: &str, query_params: &[(String, String)]) -> bool {
+    let name = get_query_param_name(pair);
+    query_params
+        .iter()
+        .any(|(param_name, _)| param_name == name)
+}
+
+/// Add `query_params` to a request's query string, replacing any the client
+/// sent with the same name. Other parameters are kept as sent.
+pub fn override_query_params(
+    query: Option<&str>,
+    query_params: &[(String, String)],
+) -> Option<String> {
+    if query_params.is_empty() {
+        return query.map(str::to_string);
+    }
+    let mut pairs: Vec<String> = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !is_listed_query_param(pair, query_params))
+        .map(str::to_string)
+        .collect();
+    pairs.extend(
+        query_params
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, encode_uri_component(value))),
+    );
+    Some(pairs.join("&"))
+}
+
+/// Mask the values of `query_params`' names in a path or URL's query string,
+/// so they aren't stored or logged.
+pub fn mask_query_params(url: &str, query_params: &[(String, String)]) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let pairs: Vec<String> = query
+        .split('&')
+        .map(|pair| {
+            if is_listed_query_param(pair, query_params) {
+                format!("{}={}", get_query_param_name(pair), MASKED_QUERY_VALUE)
+            } else {
+                pair.to_string()
+            }
+        })
+        .collect();
+    format!("{}?{}", base, pairs.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_uri_component_keeps_unreserved() {
+        assert_eq!(encode_uri_component("a-b_c.d~e"), "a-b_c.d~e");
+        assert_eq!(encode_uri_component("v1:0 /"), "v1%3A0%20%2F");
+    }
+
+    #[test]
+    fn override_query_params_replaces_client_values() {
+        let query_params = parse_query_params(Some("key=s3cr3t/+\n\nregion = eu"));
+        assert_eq!(
+            override_query_params(Some("alt=sse&key=client&beta=true"), &query_params).as_deref(),
+            Some("alt=sse&beta=true&key=s3cr3t%2F%2B&region=eu")
+        );
+        assert_eq!(override_query_params(None, &[]), None);
+        assert_eq!(
+            mask_query_params("/v1/messages?key=client&beta=true", &query_params),
+            "/v1/messages?key=***&beta=true"
+        );
+        assert!(validate_query_params("key=abc\n\napi-key=").is_ok());
+        assert!(validate_query_params("key").is_err());
+        assert!(validate_query_params("a b=c").is_err());
+    }
+}
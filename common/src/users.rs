@@ -0,0 +1,42 @@
+/// Cookie holding the dashboard user name. There are no passwords: the name
+/// only attributes notes, stars and approval decisions on a shared proxy.
+pub const USER_COOKIE: &str = "gateway_proxy_user";
+
+/// Recorded as the author when no name has been set.
+pub const ANONYMOUS_USER: &str = "anonymous";
+
+pub const MAX_USER_NAME_LEN: usize = 40;
+
+/// Check a user name before it is stored in the cookie: letters, digits,
+/// `.`, `_` and `-`, up to [`MAX_USER_NAME_LEN`] characters.
+pub fn validate_user_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("name is empty".to_string());
+    }
+    if name.len() > MAX_USER_NAME_LEN {
+        return Err(format!(
+            "name is longer than {} characters",
+            MAX_USER_NAME_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err("name may only contain letters, digits, '.', '_' and '-'".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_user_name_rejects_bad_names() {
+        assert!(validate_user_name("alice.w-2").is_ok());
+        assert!(validate_user_name("").is_err());
+        assert!(validate_user_name("alice smith").is_err());
+        assert!(validate_user_name(&"a".repeat(MAX_USER_NAME_LEN + 1)).is_err());
+    }
+}
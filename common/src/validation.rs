@@ -0,0 +1,28 @@
+/// How a session handles request bodies that fail schema validation.
+pub struct ValidationMode {
+    /// The key stored in the DB (e.g. "annotate").
+    pub key: &'static str,
+    /// Human-readable label for the UI.
+    pub label: &'static str,
+    /// What happens to a request that has violations.
+    pub description: &'static str,
+}
+
+/// All validation modes. A session with no mode set skips validation.
+pub const VALIDATION_MODES: &[ValidationMode] = &[
+    ValidationMode {
+        key: "annotate",
+        label: "Annotate",
+        description: "Forward the request and record violations in the request note.",
+    },
+    ValidationMode {
+        key: "reject",
+        label: "Reject",
+        description: "Return 400 invalid_request_error without forwarding upstream.",
+    },
+];
+
+/// Look up a known validation mode by its key, or `None` if unknown.
+pub fn find_by_key(key: &str) -> Option<&'static ValidationMode> {
+    VALIDATION_MODES.iter().find(|mode| mode.key == key)
+}
@@ -0,0 +1,237 @@
+use common::models::{AlertEvent, AlertRule};
+use sqlx::sqlite::SqlitePool;
+
+// -- Rules --
+
+const ALERT_RULE_COLUMNS: &str = "id, name, condition, condition_value, window_minutes, \
+     enabled, checked_until_ms, created_at, updated_at";
+
+/// When a request finished, in Unix ms.
+const REQUEST_FINISHED_MS: &str = "(r.started_at_ms + r.latency_ms)";
+
+/// A response's input tokens including cache reads and writes, as stored
+/// with the response.
+const REQUEST_PROMPT_TOKENS: &str = "\
+    (COALESCE(r.usage_input_tokens, 0) + COALESCE(r.usage_cache_creation_input_tokens, 0) \
+     + COALESCE(r.usage_cache_read_input_tokens, 0))";
+
+pub struct AlertRuleParams<'a> {
+    pub name: &'a str,
+    pub condition: &'a str,
+    pub condition_value: &'a str,
+    pub window_minutes: i64,
+}
+
+pub async fn list_alert_rules(pool: &SqlitePool) -> anyhow::Result<Vec<AlertRule>> {
+    Ok(sqlx::query_as::<_, AlertRule>(&format!(
+        "SELECT {} FROM alert_rules ORDER BY created_at, name",
+        ALERT_RULE_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn get_alert_rule(pool: &SqlitePool, id: &str) -> anyhow::Result<Option<AlertRule>> {
+    Ok(sqlx::query_as::<_, AlertRule>(&format!(
+        "SELECT {} FROM alert_rules WHERE id = ?",
+        ALERT_RULE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Add an enabled rule. Only requests finishing after it was created are
+/// checked against it.
+pub async fn create_alert_rule(
+    pool: &SqlitePool,
+    params: &AlertRuleParams<'_>,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO alert_rules \
+         (id, name, condition, condition_value, window_minutes, checked_until_ms) \
+         VALUES (?, ?, ?, ?, ?, CAST(strftime('%s', 'now') AS INTEGER) * 1000)",
+    )
+    .bind(&id)
+    .bind(params.name)
+    .bind(params.condition)
+    .bind(params.condition_value)
+    .bind(params.window_minutes)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn update_alert_rule(
+    pool: &SqlitePool,
+    id: &str,
+    params: &AlertRuleParams<'_>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE alert_rules SET name = ?, condition = ?, condition_value = ?, \
+         window_minutes = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(params.name)
+    .bind(params.condition)
+    .bind(params.condition_value)
+    .bind(params.window_minutes)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn set_alert_rule_enabled(
+    pool: &SqlitePool,
+    id: &str,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE alert_rules SET enabled = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?")
+        .bind(enabled)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Record that requests finished up to `checked_until_ms` have been checked.
+pub async fn set_alert_rule_checked_until(
+    pool: &SqlitePool,
+    id: &str,
+    checked_until_ms: i64,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE alert_rules SET checked_until_ms = ? WHERE id = ?")
+        .bind(checked_until_ms)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_alert_rule(pool: &SqlitePool, id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM alert_rules WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// -- Matching Requests --
+
+/// (finished, failed) requests started at or after `since_ms`. A request
+/// failed when the upstream call failed or returned a 4xx/5xx status.
+pub async fn count_failed_requests_since(
+    pool: &SqlitePool,
+    since_ms: i64,
+) -> anyhow::Result<(i64, i64)> {
+    Ok(sqlx::query_as(
+        "SELECT COUNT(*), \
+         COALESCE(SUM(r.error_kind IS NOT NULL OR r.response_status >= 400), 0) \
+         FROM requests r WHERE r.started_at_ms >= ? \
+         AND (r.response_status IS NOT NULL OR r.error_kind IS NOT NULL)",
+    )
+    .bind(since_ms)
+    .fetch_one(pool)
+    .await?)
+}
+
+/// (request id, session id, prompt tokens) of requests finished after
+/// `after_ms` and up to `until_ms` with more than `token_limit` prompt tokens.
+pub async fn list_requests_over_prompt_tokens(
+    pool: &SqlitePool,
+    after_ms: i64,
+    until_ms: i64,
+    token_limit: i64,
+    limit: i64,
+) -> anyhow::Result<Vec<(String, String, i64)>> {
+    Ok(sqlx::query_as(&format!(
+        "SELECT id, session_id, prompt_tokens FROM ( \
+         SELECT r.id, r.session_id, {} AS prompt_tokens, {} AS finished_ms FROM requests r \
+         WHERE {} > ? AND {} <= ?) \
+         WHERE prompt_tokens > ? ORDER BY finished_ms LIMIT ?",
+        REQUEST_PROMPT_TOKENS, REQUEST_FINISHED_MS, REQUEST_FINISHED_MS, REQUEST_FINISHED_MS
+    ))
+    .bind(after_ms)
+    .bind(until_ms)
+    .bind(token_limit)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// (request id, session id) of requests finished after `after_ms` and up to
+/// `until_ms` whose response stopped with `stop_reason`.
+pub async fn list_requests_with_stop_reason(
+    pool: &SqlitePool,
+    after_ms: i64,
+    until_ms: i64,
+    stop_reason: &str,
+    limit: i64,
+) -> anyhow::Result<Vec<(String, String)>> {
+    Ok(sqlx::query_as(&format!(
+        "SELECT r.id, r.session_id FROM requests r \
+         WHERE r.stop_reason = ? AND {} > ? AND {} <= ? ORDER BY {} LIMIT ?",
+        REQUEST_FINISHED_MS, REQUEST_FINISHED_MS, REQUEST_FINISHED_MS
+    ))
+    .bind(stop_reason)
+    .bind(after_ms)
+    .bind(until_ms)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+// -- Events --
+
+pub async fn create_alert_event(
+    pool: &SqlitePool,
+    rule_id: &str,
+    session_id: Option<&str>,
+    request_id: Option<&str>,
+    message: &str,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO alert_events (id, rule_id, session_id, request_id, message) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(rule_id)
+    .bind(session_id)
+    .bind(request_id)
+    .bind(message)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Whether the rule fired in the last `minutes` minutes.
+pub async fn has_recent_alert_event(
+    pool: &SqlitePool,
+    rule_id: &str,
+    minutes: i64,
+) -> anyhow::Result<bool> {
+    let row: (bool,) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM alert_events \
+         WHERE rule_id = ? AND created_at >= datetime('now', ?))",
+    )
+    .bind(rule_id)
+    .bind(format!("-{} minutes", minutes))
+    .fetch_one(pool)
+    .await?;
+    Ok(row.0)
+}
+
+/// The newest alert events across all rules, newest first.
+pub async fn list_alert_events(pool: &SqlitePool, limit: i64) -> anyhow::Result<Vec<AlertEvent>> {
+    Ok(sqlx::query_as::<_, AlertEvent>(
+        "SELECT e.id, e.rule_id, a.name AS rule_name, e.session_id, e.request_id, \
+         e.message, e.created_at \
+         FROM alert_events e JOIN alert_rules a ON a.id = e.rule_id \
+         ORDER BY e.created_at DESC, e.rowid DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
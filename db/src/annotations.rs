@@ -0,0 +1,70 @@
+use common::models::RequestNote;
+use sqlx::sqlite::SqlitePool;
+
+const REQUEST_NOTE_COLUMNS: &str = "id, request_id, author, note, created_at";
+
+pub async fn create_request_note(
+    pool: &SqlitePool,
+    request_id: &str,
+    author: &str,
+    note: &str,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO request_notes (id, request_id, author, note) VALUES (?, ?, ?, ?)")
+        .bind(&id)
+        .bind(request_id)
+        .bind(author)
+        .bind(note)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_request_notes(
+    pool: &SqlitePool,
+    request_id: &str,
+) -> anyhow::Result<Vec<RequestNote>> {
+    Ok(sqlx::query_as::<_, RequestNote>(&format!(
+        "SELECT {} FROM request_notes WHERE request_id = ? ORDER BY created_at, rowid",
+        REQUEST_NOTE_COLUMNS
+    ))
+    .bind(request_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Star the request for `author`, or remove their star. Returns whether the
+/// request is now starred by them.
+pub async fn toggle_request_star(
+    pool: &SqlitePool,
+    request_id: &str,
+    author: &str,
+) -> anyhow::Result<bool> {
+    let removed = sqlx::query("DELETE FROM request_stars WHERE request_id = ? AND author = ?")
+        .bind(request_id)
+        .bind(author)
+        .execute(pool)
+        .await?;
+    if removed.rows_affected() > 0 {
+        return Ok(false);
+    }
+    sqlx::query("INSERT INTO request_stars (request_id, author) VALUES (?, ?)")
+        .bind(request_id)
+        .bind(author)
+        .execute(pool)
+        .await?;
+    Ok(true)
+}
+
+/// Names of the users who starred the request, earliest first.
+pub async fn list_request_star_authors(
+    pool: &SqlitePool,
+    request_id: &str,
+) -> anyhow::Result<Vec<String>> {
+    Ok(sqlx::query_scalar::<_, String>(
+        "SELECT author FROM request_stars WHERE request_id = ? ORDER BY created_at, rowid",
+    )
+    .bind(request_id)
+    .fetch_all(pool)
+    .await?)
+}
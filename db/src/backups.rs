@@ -0,0 +1,159 @@
+use common::models::DatabaseSnapshot;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Connection;
+use std::path::Path;
+
+/// Snapshot files are named `snapshot-YYYYMMDD-HHMMSSmmm-<id>.db`, so sorting
+/// by name sorts them by age, and snapshots taken in the same millisecond
+/// still get their own file.
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const SNAPSHOT_SUFFIX: &str = ".db";
+
+/// Whether `file_name` is a snapshot file name (and not a path).
+pub fn is_snapshot_file_name(file_name: &str) -> bool {
+    file_name.starts_with(SNAPSHOT_PREFIX)
+        && file_name.ends_with(SNAPSHOT_SUFFIX)
+        && !file_name.contains(['/', '\\'])
+        && !file_name.contains("..")
+}
+
+/// Write a consistent copy of the live database into `backup_dir` with
+/// `VACUUM INTO`, which reads it in one transaction alongside other
+/// connections. Returns the snapshot's file name.
+pub async fn snapshot_database(pool: &SqlitePool, backup_dir: &Path) -> anyhow::Result<String> {
+    std::fs::create_dir_all(backup_dir)?;
+    let (timestamp,): (String,) = sqlx::query_as(
+        "SELECT strftime('%Y%m%d-%H%M%S', 'now') || substr(strftime('%f', 'now'), 4)",
+    )
+    .fetch_one(pool)
+    .await?;
+    let snapshot_id = uuid::Uuid::new_v4().simple().to_string();
+    let file_name = format!(
+        "{}{}-{}{}",
+        SNAPSHOT_PREFIX,
+        timestamp,
+        &snapshot_id[..8],
+        SNAPSHOT_SUFFIX
+    );
+    let path = backup_dir.join(&file_name);
+    anyhow::ensure!(!path.exists(), "snapshot {} already exists", file_name);
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().as_ref())
+        .execute(pool)
+        .await?;
+    Ok(file_name)
+}
+
+/// The snapshots in `backup_dir`, newest first. A missing directory has none.
+pub fn list_database_snapshots(backup_dir: &Path) -> anyhow::Result<Vec<DatabaseSnapshot>> {
+    let entries = match std::fs::read_dir(backup_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if !is_snapshot_file_name(&file_name) {
+            continue;
+        }
+        snapshots.push(DatabaseSnapshot {
+            file_name,
+            size_bytes: entry.metadata()?.len(),
+        });
+    }
+    snapshots.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(snapshots)
+}
+
+/// Delete all but the newest `keep` snapshots. Returns how many were deleted.
+pub fn prune_database_snapshots(backup_dir: &Path, keep: usize) -> anyhow::Result<usize> {
+    let snapshots = list_database_snapshots(backup_dir)?;
+    let mut deleted_count = 0;
+    for snapshot in snapshots.iter().skip(keep) {
+        std::fs::remove_file(backup_dir.join(&snapshot.file_name))?;
+        deleted_count += 1;
+    }
+    Ok(deleted_count)
+}
+
+/// Replace the contents of every table with the snapshot's, in one
+/// transaction. The snapshot must be at the same migration as the live
+/// database, so both have the same tables and columns.
+///
+/// Foreign keys are off on the connection while the tables are copied, as
+/// SQLite miscounts deferred violations with a same-named schema attached;
+/// the copy is checked with `foreign_key_check` before it commits.
+pub async fn restore_database_snapshot(
+    pool: &SqlitePool,
+    snapshot_path: &Path,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(snapshot_path.is_file(), "snapshot not found");
+    let mut conn = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS snapshot")
+        .bind(snapshot_path.to_string_lossy().as_ref())
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await?;
+    let result = copy_snapshot_tables(&mut conn).await;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DETACH DATABASE snapshot")
+        .execute(&mut *conn)
+        .await?;
+    result
+}
+
+async fn copy_snapshot_tables(conn: &mut sqlx::SqliteConnection) -> anyhow::Result<()> {
+    let (live_version,): (Option<i64>,) =
+        sqlx::query_as("SELECT MAX(version) FROM main._sqlx_migrations")
+            .fetch_one(&mut *conn)
+            .await?;
+    let (snapshot_version,): (Option<i64>,) =
+        sqlx::query_as("SELECT MAX(version) FROM snapshot._sqlx_migrations")
+            .fetch_one(&mut *conn)
+            .await?;
+    anyhow::ensure!(
+        live_version == snapshot_version,
+        "snapshot is at migration {:?}, the database at {:?}",
+        snapshot_version,
+        live_version
+    );
+    let table_names: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM main.sqlite_master WHERE type = 'table' \
+         AND name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations' ORDER BY name",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut tx = conn.begin().await?;
+    for (name,) in &table_names {
+        // Table names come from sqlite_master, not from the user.
+        sqlx::query(&format!("DELETE FROM main.\"{}\"", name))
+            .execute(&mut *tx)
+            .await?;
+    }
+    for (name,) in &table_names {
+        sqlx::query(&format!(
+            "INSERT INTO main.\"{}\" SELECT * FROM snapshot.\"{}\"",
+            name, name
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+    let violations: Vec<(String, Option<i64>, String, i64)> =
+        sqlx::query_as("PRAGMA main.foreign_key_check")
+            .fetch_all(&mut *tx)
+            .await?;
+    anyhow::ensure!(
+        violations.is_empty(),
+        "snapshot has {} rows with a missing parent row",
+        violations.len()
+    );
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use common::models::{MessageBatch, MessageBatchItem};
+use sqlx::sqlite::SqlitePool;
+
+const BATCH_COLUMNS: &str = "\
+    id, session_id, request_id, upstream_batch_id, base_path, processing_status, \
+    created_at, updated_at";
+const BATCH_ITEM_COLUMNS: &str =
+    "id, batch_id, request_id, custom_id, result_type, created_at, updated_at";
+
+pub struct CreateMessageBatchParams<'a> {
+    pub session_id: &'a str,
+    pub request_id: &'a str,
+    pub upstream_batch_id: &'a str,
+    pub base_path: &'a str,
+    pub processing_status: &'a str,
+}
+
+// -- Message Batches --
+
+pub async fn create_message_batch(
+    pool: &SqlitePool,
+    params: &CreateMessageBatchParams<'_>,
+) -> anyhow::Result<String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO message_batches (id, session_id, request_id, upstream_batch_id, \
+         base_path, processing_status) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(params.session_id)
+    .bind(params.request_id)
+    .bind(params.upstream_batch_id)
+    .bind(params.base_path)
+    .bind(params.processing_status)
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+pub async fn list_message_batches(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<MessageBatch>> {
+    Ok(sqlx::query_as::<_, MessageBatch>(&format!(
+        "SELECT {} FROM message_batches WHERE session_id = ? ORDER BY created_at DESC",
+        BATCH_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// List batches across all sessions whose results have not been attached yet.
+pub async fn list_pending_message_batches(pool: &SqlitePool) -> anyhow::Result<Vec<MessageBatch>> {
+    Ok(sqlx::query_as::<_, MessageBatch>(&format!(
+        "SELECT {} FROM message_batches WHERE processing_status != 'ended' \
+         ORDER BY created_at ASC",
+        BATCH_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn set_message_batch_processing_status(
+    pool: &SqlitePool,
+    id: &str,
+    processing_status: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE message_batches SET processing_status = ? WHERE id = ?")
+        .bind(processing_status)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// -- Message Batch Items --
+
+pub async fn create_message_batch_item(
+    pool: &SqlitePool,
+    batch_id: &str,
+    request_id: &str,
+    custom_id: &str,
+) -> anyhow::Result<String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO message_batch_items (id, batch_id, request_id, custom_id) \
+         VALUES (?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(batch_id)
+    .bind(request_id)
+    .bind(custom_id)
+    .execute(pool)
+    .await?;
+    Ok(id)
+}
+
+pub async fn list_message_batch_items(
+    pool: &SqlitePool,
+    batch_id: &str,
+) -> anyhow::Result<Vec<MessageBatchItem>> {
+    Ok(sqlx::query_as::<_, MessageBatchItem>(&format!(
+        "SELECT {} FROM message_batch_items WHERE batch_id = ? ORDER BY created_at ASC",
+        BATCH_ITEM_COLUMNS
+    ))
+    .bind(batch_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn set_message_batch_item_result_type(
+    pool: &SqlitePool,
+    id: &str,
+    result_type: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE message_batch_items SET result_type = ? WHERE id = ?")
+        .bind(result_type)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,215 @@
+//! Content-addressed storage for the request fields that repeat turn after
+//! turn. Agent loops resend the same system prompt and tool list with every
+//! request, so each distinct one is stored once in `blobs`, keyed by its
+//! SHA-256, and requests point at it. Blobs are deleted with the last
+//! request using them, by a trigger on `requests`.
+//!
+//! The stored `body_json` leaves out the fields kept in their own columns
+//! (the system prompt, tools and messages), and they are added back when the
+//! request is loaded.
+
+use common::models::ProxyRequest;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+use serde_json::value::RawValue;
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fmt;
+
+use crate::compression::{compress_large_text, decompress_request_bodies};
+
+/// Rows moved into `blobs` per transaction by `backfill_request_blobs`.
+const BACKFILL_BATCH_SIZE: i64 = 50;
+
+/// Store `content` unless an identical blob exists. Returns its hash.
+pub(crate) async fn insert_blob(
+    conn: &mut SqliteConnection,
+    content: Option<&str>,
+) -> anyhow::Result<Option<String>> {
+    let Some(content) = content else {
+        return Ok(None);
+    };
+    let hash = hex::encode(Sha256::digest(content.as_bytes()));
+    sqlx::query("INSERT OR IGNORE INTO blobs (hash, content) VALUES (?, ?)")
+        .bind(&hash)
+        .bind(content)
+        .execute(conn)
+        .await?;
+    Ok(Some(hash))
+}
+
+/// A JSON object's top-level fields in the order they were written, with
+/// their values left unparsed.
+struct RawObject<'a>(Vec<(String, &'a RawValue)>);
+
+impl<'de> Deserialize<'de> for RawObject<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RawObjectVisitor;
+
+        impl<'de> Visitor<'de> for RawObjectVisitor {
+            type Value = RawObject<'de>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut fields = Vec::new();
+                while let Some(field) = map.next_entry()? {
+                    fields.push(field);
+                }
+                Ok(RawObject(fields))
+            }
+        }
+
+        deserializer.deserialize_map(RawObjectVisitor)
+    }
+}
+
+impl Serialize for RawObject<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+    }
+}
+
+/// `(field, stored value)` for the body fields kept in their own columns.
+fn list_stored_fields<'a>(
+    system_json: Option<&'a str>,
+    tools_json: Option<&'a str>,
+    messages_json: Option<&'a str>,
+) -> [(&'static str, Option<&'a str>); 3] {
+    [
+        ("system", system_json),
+        ("tools", tools_json),
+        ("messages", messages_json),
+    ]
+}
+
+/// `body_json` without the fields whose stored value is set, or `None` when
+/// none was left out, e.g. for a body that isn't a JSON object.
+fn strip_stored_fields(body_json: &str, stored_fields: &[(&str, Option<&str>)]) -> Option<String> {
+    let RawObject(fields) = serde_json::from_str(body_json).ok()?;
+    let field_count = fields.len();
+    let kept_fields: Vec<_> = fields
+        .into_iter()
+        .filter(|(key, _)| {
+            !stored_fields
+                .iter()
+                .any(|(field, stored_value)| field == key && stored_value.is_some())
+        })
+        .collect();
+    if kept_fields.len() == field_count {
+        return None;
+    }
+    serde_json::to_string(&RawObject(kept_fields)).ok()
+}
+
+/// `body_json` with the stored fields it lacks added back, or `None` when it
+/// has them all, as bodies logged before they were left out do.
+fn restore_stored_fields(
+    body_json: &str,
+    stored_fields: &[(&str, Option<&str>)],
+) -> Option<String> {
+    let RawObject(mut fields) = serde_json::from_str(body_json).ok()?;
+    let field_count = fields.len();
+    for (field, stored_value) in stored_fields {
+        let Some(stored_value) = stored_value else {
+            continue;
+        };
+        if fields.iter().any(|(key, _)| key == field) {
+            continue;
+        }
+        if let Ok(raw_value) = serde_json::from_str::<&RawValue>(stored_value) {
+            fields.push((field.to_string(), raw_value));
+        }
+    }
+    if fields.len() == field_count {
+        return None;
+    }
+    serde_json::to_string(&RawObject(fields)).ok()
+}
+
+/// The body to store for a request: `body_json` without the fields stored in
+/// their own columns, or as given when it has none of them.
+pub(crate) fn build_stored_body<'a>(
+    body_json: Option<&'a str>,
+    system_json: Option<&str>,
+    tools_json: Option<&str>,
+    messages_json: Option<&str>,
+) -> Option<std::borrow::Cow<'a, str>> {
+    let body_json = body_json?;
+    let stored_fields = list_stored_fields(system_json, tools_json, messages_json);
+    Some(match strip_stored_fields(body_json, &stored_fields) {
+        Some(stripped_body) => stripped_body.into(),
+        None => body_json.into(),
+    })
+}
+
+/// Add the fields stored in their own columns back into a loaded request's
+/// `body_json`.
+pub(crate) fn restore_request_body(request: &mut ProxyRequest) {
+    let Some(ref body_json) = request.body_json else {
+        return;
+    };
+    let stored_fields = list_stored_fields(
+        request.system_json.as_deref(),
+        request.tools_json.as_deref(),
+        request.messages_json.as_deref(),
+    );
+    if let Some(restored_body) = restore_stored_fields(body_json, &stored_fields) {
+        request.body_json = Some(restored_body);
+    }
+}
+
+/// Move the system prompts and tool lists of requests logged before `blobs`
+/// existed into it, and leave them and the messages out of those requests'
+/// bodies. Returns the number of requests moved.
+pub(crate) async fn backfill_request_blobs(pool: &SqlitePool) -> anyhow::Result<u64> {
+    let mut moved_count = 0;
+    loop {
+        let requests = sqlx::query_as::<_, ProxyRequest>(&format!(
+            "SELECT {} FROM requests INDEXED BY idx_requests_inline_blobs \
+             WHERE system_json IS NOT NULL OR tools_json IS NOT NULL LIMIT ?",
+            crate::requests::REQUEST_COLUMNS
+        ))
+        .bind(BACKFILL_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        if requests.is_empty() {
+            if moved_count > 0 {
+                log::info!(
+                    "Moved {} requests' system prompts and tools into blobs",
+                    moved_count
+                );
+            }
+            return Ok(moved_count);
+        }
+        let mut tx = pool.begin().await?;
+        for mut request in requests {
+            decompress_request_bodies(&mut request)?;
+            let system_blob_hash = insert_blob(&mut tx, request.system_json.as_deref()).await?;
+            let tools_blob_hash = insert_blob(&mut tx, request.tools_json.as_deref()).await?;
+            let stored_body = build_stored_body(
+                request.body_json.as_deref(),
+                request.system_json.as_deref(),
+                request.tools_json.as_deref(),
+                request.messages_json.as_deref(),
+            );
+            let body_json_zstd = compress_large_text(stored_body.as_deref())?;
+            sqlx::query(
+                "UPDATE requests SET system_json = NULL, tools_json = NULL, \
+                 system_blob_hash = ?, tools_blob_hash = ?, body_json = ?, body_json_zstd = ? \
+                 WHERE id = ?",
+            )
+            .bind(system_blob_hash)
+            .bind(tools_blob_hash)
+            .bind(stored_body.as_deref().filter(|_| body_json_zstd.is_none()))
+            .bind(body_json_zstd)
+            .bind(request.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+            moved_count += 1;
+        }
+        tx.commit().await?;
+    }
+}
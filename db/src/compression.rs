@@ -0,0 +1,146 @@
+use common::models::{BodyCompressionStats, ProxyRequest};
+use sqlx::sqlite::SqlitePool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BODY_COMPRESSION_THRESHOLD: AtomicUsize = AtomicUsize::new(128 * 1024);
+
+/// zstd level bodies are compressed at; higher levels save little on JSON
+/// and SSE text for a lot more time on the writer task.
+const ZSTD_LEVEL: i32 = 3;
+
+/// Rows compressed per transaction by `compress_stored_bodies`.
+const COMPRESS_BATCH_SIZE: i64 = 50;
+
+/// Set the size in bytes above which request and response bodies are
+/// stored compressed; 0 stores them all as text.
+pub fn set_body_compression_threshold(threshold: usize) {
+    BODY_COMPRESSION_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
+
+fn get_body_compression_threshold() -> usize {
+    BODY_COMPRESSION_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// `text` compressed, when it is over the compression threshold.
+pub(crate) fn compress_large_text(text: Option<&str>) -> anyhow::Result<Option<Vec<u8>>> {
+    let threshold = get_body_compression_threshold();
+    match text {
+        Some(text) if threshold > 0 && text.len() > threshold => {
+            Ok(Some(zstd::encode_all(text.as_bytes(), ZSTD_LEVEL)?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn decompress_text(compressed: &[u8]) -> anyhow::Result<String> {
+    Ok(String::from_utf8(zstd::decode_all(compressed)?)?)
+}
+
+/// Move a loaded request's compressed bodies back into `body_json` and
+/// `response_body`.
+pub(crate) fn decompress_request_bodies(request: &mut ProxyRequest) -> anyhow::Result<()> {
+    if let Some(body_json_zstd) = request.body_json_zstd.take() {
+        request.body_json = Some(decompress_text(&body_json_zstd)?);
+    }
+    if let Some(response_body_zstd) = request.response_body_zstd.take() {
+        request.response_body = Some(decompress_text(&response_body_zstd)?);
+    }
+    Ok(())
+}
+
+/// Compress the bodies of `column` over the threshold into `zstd_column`, a
+/// batch at a time. Returns the number of bodies compressed.
+async fn compress_stored_column(
+    pool: &SqlitePool,
+    column: &str,
+    zstd_column: &str,
+    size_column: &str,
+    threshold: usize,
+) -> anyhow::Result<u64> {
+    let mut compressed_count = 0;
+    loop {
+        // Column names are fixed by the caller, not taken from the user.
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, {0} FROM requests WHERE {0} IS NOT NULL AND {1} IS NULL \
+             AND COALESCE({2}, length(CAST({0} AS BLOB))) > ? LIMIT ?",
+            column, zstd_column, size_column
+        ))
+        .bind(threshold as i64)
+        .bind(COMPRESS_BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        if rows.is_empty() {
+            return Ok(compressed_count);
+        }
+        let mut tx = pool.begin().await?;
+        for (id, text) in rows {
+            let compressed = zstd::encode_all(text.as_bytes(), ZSTD_LEVEL)?;
+            sqlx::query(&format!(
+                "UPDATE requests SET {} = ?, {} = NULL WHERE id = ?",
+                zstd_column, column
+            ))
+            .bind(compressed)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+            compressed_count += 1;
+        }
+        tx.commit().await?;
+    }
+}
+
+/// Compress the request and response bodies logged before compression was
+/// turned on, or under a higher threshold. Returns the number of bodies
+/// compressed; the file only shrinks after a VACUUM.
+pub async fn compress_stored_bodies(pool: &SqlitePool) -> anyhow::Result<u64> {
+    let threshold = get_body_compression_threshold();
+    if threshold == 0 {
+        return Ok(0);
+    }
+    let body_count = compress_stored_column(
+        pool,
+        "body_json",
+        "body_json_zstd",
+        "request_bytes",
+        threshold,
+    )
+    .await?;
+    let response_count = compress_stored_column(
+        pool,
+        "response_body",
+        "response_body_zstd",
+        "response_bytes",
+        threshold,
+    )
+    .await?;
+    Ok(body_count + response_count)
+}
+
+/// Counts and sizes of the compressed bodies. Original sizes come from
+/// `request_bytes` and `response_bytes`, so no body is read.
+pub(crate) async fn get_body_compression_stats(
+    pool: &SqlitePool,
+) -> anyhow::Result<BodyCompressionStats> {
+    let threshold = get_body_compression_threshold() as i64;
+    let row: (i64, i64, i64, i64) = sqlx::query_as(
+        "SELECT COUNT(body_json_zstd) + COUNT(response_body_zstd), \
+         COALESCE(SUM(length(body_json_zstd)), 0) + COALESCE(SUM(length(response_body_zstd)), 0), \
+         COALESCE(SUM(CASE WHEN body_json_zstd IS NOT NULL THEN request_bytes END), 0) \
+         + COALESCE(SUM(CASE WHEN response_body_zstd IS NOT NULL THEN response_bytes END), 0), \
+         COALESCE(SUM(? > 0 AND body_json IS NOT NULL AND request_bytes > ?), 0) \
+         + COALESCE(SUM(? > 0 AND response_body IS NOT NULL AND response_bytes > ?), 0) \
+         FROM requests",
+    )
+    .bind(threshold)
+    .bind(threshold)
+    .bind(threshold)
+    .bind(threshold)
+    .fetch_one(pool)
+    .await?;
+    Ok(BodyCompressionStats {
+        compressed_count: row.0,
+        compressed_bytes: row.1,
+        original_bytes: row.2,
+        pending_count: row.3,
+    })
+}
@@ -0,0 +1,39 @@
+use common::models::SessionEvent;
+use sqlx::sqlite::SqlitePool;
+
+const SESSION_EVENT_COLUMNS: &str = "id, session_id, kind, detail, duration_ms, created_at";
+
+pub async fn create_session_event(
+    pool: &SqlitePool,
+    session_id: &str,
+    kind: &str,
+    detail: &str,
+    duration_ms: Option<i64>,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO session_events (id, session_id, kind, detail, duration_ms) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(session_id)
+    .bind(kind)
+    .bind(detail)
+    .bind(duration_ms)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_session_events(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<SessionEvent>> {
+    Ok(sqlx::query_as::<_, SessionEvent>(&format!(
+        "SELECT {} FROM session_events WHERE session_id = ? ORDER BY created_at",
+        SESSION_EVENT_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,165 @@
+use common::models::WebfetchCacheEntry;
+use sqlx::sqlite::SqlitePool;
+
+const WEBFETCH_CACHE_COLUMNS: &str =
+    "id, session_id, url, content, original_length, bytes_read, truncated, fetched_at";
+
+pub struct WebfetchCacheEntryParams<'a> {
+    pub url: &'a str,
+    pub content: &'a str,
+    pub original_length: Option<i64>,
+    pub bytes_read: i64,
+    pub truncated: bool,
+}
+
+pub async fn list_webfetch_cache_entries(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<WebfetchCacheEntry>> {
+    Ok(sqlx::query_as::<_, WebfetchCacheEntry>(&format!(
+        "SELECT {} FROM webfetch_cache WHERE session_id = ? ORDER BY fetched_at DESC",
+        WEBFETCH_CACHE_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn get_webfetch_cache_entry(
+    pool: &SqlitePool,
+    id: &str,
+) -> anyhow::Result<Option<WebfetchCacheEntry>> {
+    Ok(sqlx::query_as::<_, WebfetchCacheEntry>(&format!(
+        "SELECT {} FROM webfetch_cache WHERE id = ?",
+        WEBFETCH_CACHE_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Find the session's cache entry for `url` if it was fetched after `fetched_after`
+/// ("YYYY-MM-DD HH:MM:SS", UTC).
+pub async fn find_fresh_webfetch_cache_entry(
+    pool: &SqlitePool,
+    session_id: &str,
+    url: &str,
+    fetched_after: &str,
+) -> anyhow::Result<Option<WebfetchCacheEntry>> {
+    Ok(sqlx::query_as::<_, WebfetchCacheEntry>(&format!(
+        "SELECT {} FROM webfetch_cache WHERE session_id = ? AND url = ? AND fetched_at > ?",
+        WEBFETCH_CACHE_COLUMNS
+    ))
+    .bind(session_id)
+    .bind(url)
+    .bind(fetched_after)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Insert or replace the session's cache entry for the URL, resetting its fetch time.
+pub async fn upsert_webfetch_cache_entry(
+    pool: &SqlitePool,
+    session_id: &str,
+    params: &WebfetchCacheEntryParams<'_>,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO webfetch_cache \
+         (id, session_id, url, content, original_length, bytes_read, truncated) \
+         VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT (session_id, url) DO UPDATE SET \
+         content = excluded.content, original_length = excluded.original_length, \
+         bytes_read = excluded.bytes_read, truncated = excluded.truncated, \
+         fetched_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&id)
+    .bind(session_id)
+    .bind(params.url)
+    .bind(params.content)
+    .bind(params.original_length)
+    .bind(params.bytes_read)
+    .bind(params.truncated)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Delete the session's entries fetched at or before `fetched_before` ("YYYY-MM-DD HH:MM:SS", UTC).
+pub async fn delete_stale_webfetch_cache_entries(
+    pool: &SqlitePool,
+    session_id: &str,
+    fetched_before: &str,
+) -> anyhow::Result<u64> {
+    let result = sqlx::query("DELETE FROM webfetch_cache WHERE session_id = ? AND fetched_at <= ?")
+        .bind(session_id)
+        .bind(fetched_before)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn delete_webfetch_cache_entry(pool: &SqlitePool, id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_cache WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn clear_webfetch_cache(pool: &SqlitePool, session_id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_cache WHERE session_id = ?")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The session's stored robots.txt for `origin` (e.g. "https://example.com"), if
+/// it has been looked up. An empty string means the site has none.
+pub async fn get_webfetch_robots_txt(
+    pool: &SqlitePool,
+    session_id: &str,
+    origin: &str,
+) -> anyhow::Result<Option<String>> {
+    Ok(sqlx::query_scalar::<_, String>(
+        "SELECT robots_txt FROM webfetch_robots_cache WHERE session_id = ? AND origin = ?",
+    )
+    .bind(session_id)
+    .bind(origin)
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn upsert_webfetch_robots_txt(
+    pool: &SqlitePool,
+    session_id: &str,
+    origin: &str,
+    robots_txt: &str,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO webfetch_robots_cache (id, session_id, origin, robots_txt) \
+         VALUES (?, ?, ?, ?) \
+         ON CONFLICT (session_id, origin) DO UPDATE SET \
+         robots_txt = excluded.robots_txt, fetched_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&id)
+    .bind(session_id)
+    .bind(origin)
+    .bind(robots_txt)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn clear_webfetch_robots_cache(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_robots_cache WHERE session_id = ?")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
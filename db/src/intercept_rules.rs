@@ -0,0 +1,47 @@
+use common::models::InterceptRule;
+use sqlx::sqlite::SqlitePool;
+
+const INTERCEPT_RULE_COLUMNS: &str = "id, session_id, field, value, created_at";
+
+pub async fn list_intercept_rules(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<InterceptRule>> {
+    Ok(sqlx::query_as::<_, InterceptRule>(&format!(
+        "SELECT {} FROM intercept_rules WHERE session_id = ? ORDER BY created_at, rowid",
+        INTERCEPT_RULE_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn create_intercept_rule(
+    pool: &SqlitePool,
+    session_id: &str,
+    field: &str,
+    value: &str,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO intercept_rules (id, session_id, field, value) VALUES (?, ?, ?, ?)")
+        .bind(&id)
+        .bind(session_id)
+        .bind(field)
+        .bind(value)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_intercept_rule(
+    pool: &SqlitePool,
+    session_id: &str,
+    id: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM intercept_rules WHERE id = ? AND session_id = ?")
+        .bind(id)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -1,25 +1,104 @@
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::migrate::Migrator;
+use sqlx::sqlite::{
+    SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
 use std::str::FromStr;
+use std::time::Duration;
 
+mod alerts;
+mod annotations;
+mod backups;
+mod batches;
+mod blobs;
+mod compression;
+mod events;
+mod fetch_cache;
+mod maintenance;
 mod filters;
+mod intercept_rules;
+mod policies;
 mod requests;
 mod sessions;
+mod stats;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod whitelist;
+mod writer;
 
+pub use alerts::*;
+pub use annotations::*;
+pub use backups::*;
+pub use batches::*;
+pub use compression::*;
+pub use events::*;
+pub use fetch_cache::*;
+pub use maintenance::*;
 pub use filters::*;
+pub use intercept_rules::*;
+pub use policies::*;
 pub use requests::*;
 pub use sessions::*;
+pub use stats::*;
+pub use whitelist::*;
+pub use writer::*;
 
-pub async fn init_pool(db_path: &str) -> anyhow::Result<SqlitePool> {
-    let opts = SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=rwc", db_path))?
-        .pragma("foreign_keys", "ON");
+/// How long a connection waits on a locked database before failing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open the database and run migrations. WAL lets readers run alongside the
+/// writer, and `synchronous = NORMAL` is durable enough in WAL mode while
+/// skipping an fsync per commit.
+///
+/// With `read_only` the file is opened `mode=ro` and left as it is: no
+/// journal mode change, migrations or default rows. The schema must already
+/// be up to date.
+pub async fn init_pool(
+    db_path: &str,
+    max_connections: u32,
+    read_only: bool,
+) -> anyhow::Result<SqlitePool> {
+    let mode = if read_only { "ro" } else { "rwc" };
+    let mut opts = SqliteConnectOptions::from_str(&format!("sqlite:{}?mode={}", db_path, mode))?
+        .pragma("foreign_keys", "ON")
+        .busy_timeout(BUSY_TIMEOUT);
+    if !read_only {
+        opts = opts
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal);
+    }
     let pool = SqlitePoolOptions::new()
-        .max_connections(5)
+        .max_connections(max_connections)
         .connect_with(opts)
         .await?;
 
-    sqlx::migrate!("../migrations").run(&pool).await?;
+    let migrator = sqlx::migrate!("../migrations");
+    if read_only {
+        ensure_schema_current(&pool, &migrator).await?;
+        return Ok(pool);
+    }
+    migrator.run(&pool).await?;
 
     ensure_default_filter_profile(&pool).await?;
+    blobs::backfill_request_blobs(&pool).await?;
 
     Ok(pool)
 }
+
+/// Fail if the database hasn't had every migration applied, since a
+/// read-only pool can't apply them itself.
+async fn ensure_schema_current(pool: &SqlitePool, migrator: &Migrator) -> anyhow::Result<()> {
+    let expected = migrator.iter().map(|m| m.version).max().unwrap_or(0);
+    let applied: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+            .fetch_one(pool)
+            .await
+            .unwrap_or(None);
+    let applied = applied.unwrap_or(0);
+    anyhow::ensure!(
+        applied >= expected,
+        "database schema is at version {} but this build needs {}; start once without --read-only to migrate it",
+        applied,
+        expected
+    );
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use common::models::DatabaseStats;
+use sqlx::sqlite::SqlitePool;
+
+use crate::compression::get_body_compression_stats;
+
+async fn read_pragma(pool: &SqlitePool, pragma: &str) -> anyhow::Result<i64> {
+    let row: (i64,) = sqlx::query_as(&format!("PRAGMA {}", pragma))
+        .fetch_one(pool)
+        .await?;
+    Ok(row.0)
+}
+
+/// Path of the main database file; empty for an in-memory database.
+async fn get_database_path(pool: &SqlitePool) -> anyhow::Result<String> {
+    let row: (i64, String, String) = sqlx::query_as("PRAGMA database_list")
+        .fetch_one(pool)
+        .await?;
+    Ok(row.2)
+}
+
+fn read_file_size(path: &str) -> u64 {
+    std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+}
+
+pub async fn get_database_stats(pool: &SqlitePool) -> anyhow::Result<DatabaseStats> {
+    let path = get_database_path(pool).await?;
+    let table_names: Vec<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' \
+         ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+    let mut table_row_counts = Vec::with_capacity(table_names.len());
+    for (name,) in table_names {
+        // Table names come from sqlite_master, not from the user.
+        let row: (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM \"{}\"", name))
+            .fetch_one(pool)
+            .await?;
+        table_row_counts.push((name, row.0));
+    }
+
+    Ok(DatabaseStats {
+        file_size_bytes: read_file_size(&path),
+        wal_size_bytes: read_file_size(&format!("{}-wal", path)),
+        page_size: read_pragma(pool, "page_size").await?,
+        page_count: read_pragma(pool, "page_count").await?,
+        freelist_count: read_pragma(pool, "freelist_count").await?,
+        table_row_counts,
+        body_compression: get_body_compression_stats(pool).await?,
+    })
+}
+
+/// Rebuild the database file to reclaim free pages, then fold the WAL back
+/// into it so the file on disk shrinks.
+pub async fn vacuum_database(pool: &SqlitePool) -> anyhow::Result<()> {
+    sqlx::query("VACUUM").execute(pool).await?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Run SQLite's integrity check. Returns `["ok"]` for a healthy database,
+/// otherwise one line per problem found.
+pub async fn check_database_integrity(pool: &SqlitePool) -> anyhow::Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|row| row.0).collect())
+}
@@ -0,0 +1,161 @@
+use common::models::WebfetchPolicy;
+use sqlx::sqlite::SqlitePool;
+
+// -- Auto-Decision Policies --
+
+const WEBFETCH_POLICY_COLUMNS: &str = "id, session_id, position, tool_name, condition, \
+     condition_value, action, created_at, updated_at";
+
+pub struct WebfetchPolicyParams<'a> {
+    pub tool_name: Option<&'a str>,
+    pub condition: &'a str,
+    pub condition_value: Option<&'a str>,
+    pub action: &'a str,
+}
+
+pub async fn list_webfetch_policies(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<WebfetchPolicy>> {
+    Ok(sqlx::query_as::<_, WebfetchPolicy>(&format!(
+        "SELECT {} FROM webfetch_policies WHERE session_id = ? ORDER BY position",
+        WEBFETCH_POLICY_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Add a policy after the session's existing ones.
+pub async fn create_webfetch_policy(
+    pool: &SqlitePool,
+    session_id: &str,
+    params: &WebfetchPolicyParams<'_>,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO webfetch_policies \
+         (id, session_id, position, tool_name, condition, condition_value, action) \
+         VALUES (?, ?, \
+         (SELECT COALESCE(MAX(position), 0) + 1 FROM webfetch_policies WHERE session_id = ?), \
+         ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(session_id)
+    .bind(session_id)
+    .bind(params.tool_name)
+    .bind(params.condition)
+    .bind(params.condition_value)
+    .bind(params.action)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Swap a policy with the one checked just before it, if any.
+pub async fn move_webfetch_policy_up(
+    pool: &SqlitePool,
+    session_id: &str,
+    id: &str,
+) -> anyhow::Result<()> {
+    let policies = list_webfetch_policies(pool, session_id).await?;
+    let Some(index) = policies
+        .iter()
+        .position(|policy| policy.id.to_string() == id)
+    else {
+        return Ok(());
+    };
+    let Some(previous) = index.checked_sub(1).map(|previous| &policies[previous]) else {
+        return Ok(());
+    };
+    let policy = &policies[index];
+    let mut tx = pool.begin().await?;
+    for (policy_id, position) in [
+        (policy.id.to_string(), previous.position),
+        (previous.id.to_string(), policy.position),
+    ] {
+        sqlx::query("UPDATE webfetch_policies SET position = ? WHERE id = ?")
+            .bind(position)
+            .bind(policy_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn delete_webfetch_policy(pool: &SqlitePool, id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_policies WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// -- Approved Calls --
+
+/// How many times the user accepted this exact tool call in the session.
+pub async fn get_webfetch_approval_count(
+    pool: &SqlitePool,
+    session_id: &str,
+    tool_name: &str,
+    input_json: &str,
+) -> anyhow::Result<i64> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT approval_count FROM webfetch_approved_calls \
+         WHERE session_id = ? AND tool_name = ? AND input_json = ?",
+    )
+    .bind(session_id)
+    .bind(tool_name)
+    .bind(input_json)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map_or(0, |row| row.0))
+}
+
+/// Count one more user approval of this exact tool call.
+pub async fn record_webfetch_approval(
+    pool: &SqlitePool,
+    session_id: &str,
+    tool_name: &str,
+    input_json: &str,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO webfetch_approved_calls \
+         (id, session_id, tool_name, input_json, approval_count) \
+         VALUES (?, ?, ?, ?, 1) \
+         ON CONFLICT (session_id, tool_name, input_json) DO UPDATE SET \
+         approval_count = approval_count + 1, last_approved_at = CURRENT_TIMESTAMP",
+    )
+    .bind(&id)
+    .bind(session_id)
+    .bind(tool_name)
+    .bind(input_json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn count_webfetch_approved_calls(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<i64> {
+    let row: (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM webfetch_approved_calls WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_one(pool)
+            .await?;
+    Ok(row.0)
+}
+
+pub async fn clear_webfetch_approved_calls(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_approved_calls WHERE session_id = ?")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
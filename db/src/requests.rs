@@ -1,14 +1,44 @@
-use common::models::ProxyRequest;
-use sqlx::sqlite::SqlitePool;
+use common::models::{DuplicateGroup, ProxyRequest, RequestSummary};
+use sqlx::sqlite::{Sqlite, SqlitePool};
+use sqlx::{Acquire, SqliteExecutor};
 
-/// All columns for the `requests` table, used in SELECT queries.
-const REQUEST_COLUMNS: &str = "\
-    id, session_id, method, path, headers_json, body_json, \
-    truncated_json, model, tools_json, messages_json, system_json, params_json, \
+use crate::blobs::{build_stored_body, insert_blob, restore_request_body};
+use crate::compression::{compress_large_text, decompress_request_bodies};
+
+/// All columns for the `requests` table, used in SELECT queries. The system
+/// prompt and tools are read from `blobs` when the request points at one.
+pub(crate) const REQUEST_COLUMNS: &str = "\
+    id, session_id, method, path, headers_json, body_json, truncated_json, model, \
+    COALESCE(tools_json, \
+        (SELECT content FROM blobs WHERE blobs.hash = requests.tools_blob_hash)) AS tools_json, \
+    messages_json, \
+    COALESCE(system_json, \
+        (SELECT content FROM blobs WHERE blobs.hash = requests.system_blob_hash)) AS system_json, \
+    params_json, \
     note, created_at, updated_at, response_status, response_headers_json, response_body, \
     response_events_json, webfetch_first_response_body, webfetch_first_response_events_json, \
-    webfetch_followup_body_json, webfetch_rounds_json";
+    webfetch_followup_body_json, webfetch_rounds_json, webfetch_followup_error, \
+    estimated_input_tokens, \
+    counted_input_tokens, error_kind, request_bytes, forwarded_bytes, response_bytes, \
+    applied_filters_json, anthropic_beta, title, body_hash, stop_reason, \
+    client_user_agent, client_app, client_addr, parent_request_id, \
+    body_json_zstd, response_body_zstd";
+
+/// Columns of a `RequestSummary`. Only the message count and the last user
+/// message are read out of `messages_json`, which grows with every turn.
+const REQUEST_SUMMARY_COLUMNS: &str = "\
+    id, session_id, method, path, model, title, created_at, response_status, stop_reason, \
+    request_bytes, forwarded_bytes, response_bytes, parent_request_id, \
+    CASE WHEN json_valid(messages_json) THEN json_array_length(messages_json) END \
+        AS message_count, \
+    CASE WHEN json_valid(messages_json) THEN COALESCE( \
+        (SELECT message.value FROM json_each(messages_json) AS message \
+         WHERE json_extract(message.value, '$.role') = 'user' \
+         ORDER BY message.key DESC LIMIT 1), \
+        json_extract(messages_json, '$[#-1]')) END AS last_message_json, \
+    response_events_json";
 
+#[derive(Default)]
 pub struct CreateRequestParams<'a> {
     pub session_id: &'a str,
     pub method: &'a str,
@@ -22,131 +52,602 @@ pub struct CreateRequestParams<'a> {
     pub system_json: Option<&'a str>,
     pub params_json: Option<&'a str>,
     pub note: Option<&'a str>,
+    pub estimated_input_tokens: Option<i64>,
+    /// When the proxy received the request, in Unix milliseconds.
+    pub started_at_ms: Option<i64>,
+    /// Size of the body as the client sent it.
+    pub request_bytes: Option<i64>,
+    pub body_hash: Option<&'a str>,
+    /// JSON array of the tool calls the request answers.
+    pub tool_calls_json: Option<&'a str>,
+    pub client_user_agent: Option<&'a str>,
+    pub client_app: Option<&'a str>,
+    pub client_addr: Option<&'a str>,
+    pub parent_request_id: Option<&'a str>,
 }
 
 pub async fn list_requests(
     pool: &SqlitePool,
     session_id: &str,
 ) -> anyhow::Result<Vec<ProxyRequest>> {
-    Ok(sqlx::query_as::<_, ProxyRequest>(&format!(
+    let requests = sqlx::query_as::<_, ProxyRequest>(&format!(
         "SELECT {} FROM requests WHERE session_id = ? ORDER BY created_at DESC",
         REQUEST_COLUMNS
     ))
     .bind(session_id)
     .fetch_all(pool)
-    .await?)
+    .await?;
+    load_requests_bodies(requests)
 }
 
-pub async fn count_requests(pool: &SqlitePool, session_id: &str) -> anyhow::Result<i64> {
-    let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM requests WHERE session_id = ?")
-        .bind(session_id)
-        .fetch_one(pool)
-        .await?;
+/// Count the session's requests, only those with `stop_reason` and from
+/// `client_user_agent` when given, and only WebFetch-intercepted ones when
+/// `intercepted_only` is set.
+pub async fn count_requests(
+    pool: &SqlitePool,
+    session_id: &str,
+    stop_reason: Option<&str>,
+    client_user_agent: Option<&str>,
+    intercepted_only: bool,
+) -> anyhow::Result<i64> {
+    let row: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM requests WHERE session_id = ? AND parent_request_id IS NULL \
+         AND (? IS NULL OR stop_reason = ?) AND (? IS NULL OR client_user_agent = ?) \
+         AND (? = 0 OR webfetch_intercepted = 1)",
+    )
+    .bind(session_id)
+    .bind(stop_reason)
+    .bind(stop_reason)
+    .bind(client_user_agent)
+    .bind(client_user_agent)
+    .bind(intercepted_only)
+    .fetch_one(pool)
+    .await?;
     Ok(row.0)
 }
 
+/// A page of the session's requests, newest first, filtered like
+/// `count_requests`.
 pub async fn list_requests_paginated(
     pool: &SqlitePool,
     session_id: &str,
+    stop_reason: Option<&str>,
+    client_user_agent: Option<&str>,
+    intercepted_only: bool,
     limit: i64,
     offset: i64,
 ) -> anyhow::Result<Vec<ProxyRequest>> {
-    Ok(sqlx::query_as::<_, ProxyRequest>(&format!(
-        "SELECT {} FROM requests WHERE session_id = ? ORDER BY created_at DESC LIMIT ? OFFSET ?",
+    let requests = sqlx::query_as::<_, ProxyRequest>(&format!(
+        "SELECT {} FROM requests WHERE session_id = ? AND parent_request_id IS NULL \
+         AND (? IS NULL OR stop_reason = ?) AND (? IS NULL OR client_user_agent = ?) \
+         AND (? = 0 OR webfetch_intercepted = 1) \
+         ORDER BY created_at DESC LIMIT ? OFFSET ?",
         REQUEST_COLUMNS
     ))
     .bind(session_id)
+    .bind(stop_reason)
+    .bind(stop_reason)
+    .bind(client_user_agent)
+    .bind(client_user_agent)
+    .bind(intercepted_only)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    load_requests_bodies(requests)
+}
+
+/// Summaries of a page of the session's requests, for the requests list;
+/// the same rows as `list_requests_paginated`.
+pub async fn list_request_summaries_paginated(
+    pool: &SqlitePool,
+    session_id: &str,
+    stop_reason: Option<&str>,
+    client_user_agent: Option<&str>,
+    intercepted_only: bool,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<RequestSummary>> {
+    Ok(sqlx::query_as::<_, RequestSummary>(&format!(
+        "SELECT {} FROM requests WHERE session_id = ? AND parent_request_id IS NULL \
+         AND (? IS NULL OR stop_reason = ?) AND (? IS NULL OR client_user_agent = ?) \
+         AND (? = 0 OR webfetch_intercepted = 1) \
+         ORDER BY created_at DESC LIMIT ? OFFSET ?",
+        REQUEST_SUMMARY_COLUMNS
+    ))
+    .bind(session_id)
+    .bind(stop_reason)
+    .bind(stop_reason)
+    .bind(client_user_agent)
+    .bind(client_user_agent)
+    .bind(intercepted_only)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Summaries of the WebFetch follow-up rounds of the requests on a page of
+/// `list_request_summaries_paginated`, oldest first.
+pub async fn list_round_request_summaries_paginated(
+    pool: &SqlitePool,
+    session_id: &str,
+    stop_reason: Option<&str>,
+    client_user_agent: Option<&str>,
+    intercepted_only: bool,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<RequestSummary>> {
+    Ok(sqlx::query_as::<_, RequestSummary>(&format!(
+        "SELECT {} FROM requests WHERE parent_request_id IN (SELECT id FROM requests \
+         WHERE session_id = ? AND parent_request_id IS NULL \
+         AND (? IS NULL OR stop_reason = ?) AND (? IS NULL OR client_user_agent = ?) \
+         AND (? = 0 OR webfetch_intercepted = 1) \
+         ORDER BY created_at DESC LIMIT ? OFFSET ?) ORDER BY created_at ASC",
+        REQUEST_SUMMARY_COLUMNS
+    ))
+    .bind(session_id)
+    .bind(stop_reason)
+    .bind(stop_reason)
+    .bind(client_user_agent)
+    .bind(client_user_agent)
+    .bind(intercepted_only)
     .bind(limit)
     .bind(offset)
     .fetch_all(pool)
     .await?)
 }
 
+/// IDs of the WebFetch follow-up rounds logged for a request, oldest first.
+pub async fn list_round_request_ids(
+    pool: &SqlitePool,
+    parent_request_id: &str,
+) -> anyhow::Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT id FROM requests WHERE parent_request_id = ? ORDER BY created_at ASC",
+    )
+    .bind(parent_request_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(|(id,)| id).collect())
+}
+
+/// The distinct `User-Agent`s the session's requests came from, most used first.
+pub async fn list_request_clients(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<String>> {
+    let rows: Vec<(String,)> = sqlx::query_as(
+        "SELECT client_user_agent FROM requests \
+         WHERE session_id = ? AND client_user_agent IS NOT NULL \
+         GROUP BY client_user_agent ORDER BY COUNT(*) DESC, client_user_agent",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(client_user_agent,)| client_user_agent)
+        .collect())
+}
+
+/// Decompress a loaded request's bodies and add the fields stored apart
+/// from `body_json` back into it.
+fn load_request_bodies(request: &mut ProxyRequest) -> anyhow::Result<()> {
+    decompress_request_bodies(request)?;
+    restore_request_body(request);
+    Ok(())
+}
+
+fn load_requests_bodies(mut requests: Vec<ProxyRequest>) -> anyhow::Result<Vec<ProxyRequest>> {
+    for request in &mut requests {
+        load_request_bodies(request)?;
+    }
+    Ok(requests)
+}
+
 pub async fn get_request(
     pool: &SqlitePool,
     request_id: &str,
 ) -> anyhow::Result<Option<ProxyRequest>> {
-    Ok(sqlx::query_as::<_, ProxyRequest>(&format!(
+    let requests = sqlx::query_as::<_, ProxyRequest>(&format!(
         "SELECT {} FROM requests WHERE id = ?",
         REQUEST_COLUMNS
     ))
     .bind(request_id)
     .fetch_all(pool)
-    .await?
-    .pop())
+    .await?;
+    Ok(load_requests_bodies(requests)?.pop())
 }
 
-pub async fn create_request(
+/// A request's summary, for looking it up without loading its bodies.
+pub async fn get_request_summary(
     pool: &SqlitePool,
+    request_id: &str,
+) -> anyhow::Result<Option<RequestSummary>> {
+    Ok(sqlx::query_as::<_, RequestSummary>(&format!(
+        "SELECT {} FROM requests WHERE id = ?",
+        REQUEST_SUMMARY_COLUMNS
+    ))
+    .bind(request_id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Milliseconds from a request's start to its response, when measured.
+pub async fn get_request_latency_ms(
+    pool: &SqlitePool,
+    request_id: &str,
+) -> anyhow::Result<Option<i64>> {
+    let latency_ms: Option<(Option<i64>,)> =
+        sqlx::query_as("SELECT latency_ms FROM requests WHERE id = ?")
+            .bind(request_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(latency_ms.and_then(|(latency_ms,)| latency_ms))
+}
+
+/// Insert a request row under an id chosen by the caller. The system prompt
+/// and tools go to `blobs`, shared with the requests that sent the same ones,
+/// and `body_json` is stored without them and the messages, compressed when
+/// over the compression threshold.
+pub async fn insert_request<'e>(
+    connection: impl Acquire<'e, Database = Sqlite>,
+    id: &str,
     params: &CreateRequestParams<'_>,
-) -> anyhow::Result<String> {
-    let id = uuid::Uuid::new_v4().to_string();
+) -> anyhow::Result<()> {
+    let stored_body = build_stored_body(
+        params.body_json,
+        params.system_json,
+        params.tools_json,
+        params.messages_json,
+    );
+    let body_json_zstd = compress_large_text(stored_body.as_deref())?;
+    let mut tx = connection.begin().await?;
+    let tools_blob_hash = insert_blob(&mut tx, params.tools_json).await?;
+    let system_blob_hash = insert_blob(&mut tx, params.system_json).await?;
     sqlx::query(
         "INSERT INTO requests (id, session_id, method, path, headers_json, body_json, \
-         truncated_json, model, tools_json, messages_json, system_json, params_json, note) \
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+         body_json_zstd, truncated_json, model, tools_blob_hash, messages_json, \
+         system_blob_hash, params_json, note, estimated_input_tokens, started_at_ms, \
+         request_bytes, body_hash, tool_calls_json, client_user_agent, client_app, \
+         client_addr, parent_request_id) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
-    .bind(&id)
+    .bind(id)
     .bind(params.session_id)
     .bind(params.method)
     .bind(params.path)
     .bind(params.headers_json)
-    .bind(params.body_json)
+    .bind(stored_body.as_deref().filter(|_| body_json_zstd.is_none()))
+    .bind(body_json_zstd)
     .bind(params.truncated_json)
     .bind(params.model)
-    .bind(params.tools_json)
+    .bind(tools_blob_hash)
     .bind(params.messages_json)
-    .bind(params.system_json)
+    .bind(system_blob_hash)
     .bind(params.params_json)
     .bind(params.note)
-    .execute(pool)
+    .bind(params.estimated_input_tokens)
+    .bind(params.started_at_ms)
+    .bind(params.request_bytes)
+    .bind(params.body_hash)
+    .bind(params.tool_calls_json)
+    .bind(params.client_user_agent)
+    .bind(params.client_app)
+    .bind(params.client_addr)
+    .bind(params.parent_request_id)
+    .execute(&mut *tx)
     .await?;
-    Ok(id)
+    tx.commit().await?;
+    Ok(())
 }
 
-pub async fn set_request_response(
-    pool: &SqlitePool,
+/// SQL reading one usage count of the response being stored from its events
+/// (?6) or JSON body (?3).
+fn build_usage_field_sql(event_type: &str, event_path: &str, body_field: &str) -> String {
+    format!(
+        "COALESCE( \
+            (SELECT MAX(json_extract(event.value, '$.data.{}')) FROM json_each(?6) AS event \
+             WHERE json_extract(event.value, '$.event') = '{}'), \
+            CASE WHEN json_valid(?3) THEN json_extract(?3, '$.usage.{}') END)",
+        event_path, event_type, body_field
+    )
+}
+
+/// Store a request's response and its size, compressing a body over the
+/// compression threshold. `responded_at_ms` (Unix milliseconds) sets its
+/// latency when the request has a start time.
+pub async fn set_request_response<'e>(
+    executor: impl SqliteExecutor<'e>,
     request_id: &str,
     response_status: i64,
     response_headers_json: Option<&str>,
     response_body: Option<&str>,
     response_events_json: Option<&str>,
+    responded_at_ms: Option<i64>,
 ) -> anyhow::Result<()> {
-    sqlx::query(
-        "UPDATE requests SET response_status = ?, response_headers_json = ?, \
-         response_body = ?, response_events_json = ? WHERE id = ?",
-    )
+    let response_body_zstd = compress_large_text(response_body)?;
+    // ?3 is the body as sent, for reading usage; it's only stored as text
+    // when it wasn't compressed into ?4.
+    sqlx::query(&format!(
+        "UPDATE requests SET response_status = ?1, response_headers_json = ?2, \
+         response_body = CASE WHEN ?4 IS NULL THEN ?3 END, response_body_zstd = ?4, \
+         response_bytes = ?5, \
+         response_events_json = ?6, latency_ms = ?7 - started_at_ms, \
+         usage_input_tokens = {}, usage_output_tokens = {}, \
+         usage_cache_creation_input_tokens = {}, usage_cache_read_input_tokens = {} \
+         WHERE id = ?8",
+        build_usage_field_sql(
+            "message_start",
+            "message.usage.input_tokens",
+            "input_tokens"
+        ),
+        build_usage_field_sql("message_delta", "usage.output_tokens", "output_tokens"),
+        build_usage_field_sql(
+            "message_start",
+            "message.usage.cache_creation_input_tokens",
+            "cache_creation_input_tokens"
+        ),
+        build_usage_field_sql(
+            "message_start",
+            "message.usage.cache_read_input_tokens",
+            "cache_read_input_tokens"
+        ),
+    ))
     .bind(response_status)
     .bind(response_headers_json)
     .bind(response_body)
+    .bind(response_body_zstd)
+    .bind(response_body.map(|response_body| response_body.len() as i64))
     .bind(response_events_json)
+    .bind(responded_at_ms)
     .bind(request_id)
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(())
 }
 
+pub async fn set_request_counted_input_tokens<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    counted_input_tokens: i64,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET counted_input_tokens = ? WHERE id = ?")
+        .bind(counted_input_tokens)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
 pub async fn clear_requests(pool: &SqlitePool, session_id: &str) -> anyhow::Result<()> {
     sqlx::query("DELETE FROM requests WHERE session_id = ?")
         .bind(session_id)
         .execute(pool)
         .await?;
+    sqlx::query("DELETE FROM session_events WHERE session_id = ?")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
     Ok(())
 }
 
-pub async fn set_request_note(
-    pool: &SqlitePool,
+/// Delete requests created more than `days` days ago across all sessions.
+/// Returns the number of deleted rows.
+pub async fn delete_requests_older_than(pool: &SqlitePool, days: u64) -> anyhow::Result<u64> {
+    let result = sqlx::query("DELETE FROM requests WHERE created_at < datetime('now', ?)")
+        .bind(format!("-{} days", days))
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM session_events WHERE created_at < datetime('now', ?)")
+        .bind(format!("-{} days", days))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn set_request_note<'e>(
+    executor: impl SqliteExecutor<'e>,
     request_id: &str,
     note: &str,
 ) -> anyhow::Result<()> {
     sqlx::query("UPDATE requests SET note = ? WHERE id = ?")
         .bind(note)
         .bind(request_id)
-        .execute(pool)
+        .execute(executor)
         .await?;
     Ok(())
 }
 
+/// Record why a WebFetch follow-up request failed.
+pub async fn set_request_webfetch_followup_error<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    webfetch_followup_error: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET webfetch_followup_error = ? WHERE id = ?")
+        .bind(webfetch_followup_error)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+pub async fn set_request_error_kind<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    error_kind: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET error_kind = ? WHERE id = ?")
+        .bind(error_kind)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Record the size of the body sent upstream, after filters were applied.
+pub async fn set_request_forwarded_bytes<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    forwarded_bytes: i64,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET forwarded_bytes = ? WHERE id = ?")
+        .bind(forwarded_bytes)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Record what the filter profile removed or renamed, as a JSON array of
+/// `AppliedFilter`s.
+pub async fn set_request_applied_filters<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    applied_filters_json: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET applied_filters_json = ? WHERE id = ?")
+        .bind(applied_filters_json)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Record the `anthropic-beta` header as forwarded upstream.
+pub async fn set_request_anthropic_beta<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    anthropic_beta: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET anthropic_beta = ? WHERE id = ?")
+        .bind(anthropic_beta)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Record the `stop_reason` of a request's response.
+pub async fn set_request_stop_reason<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    stop_reason: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET stop_reason = ? WHERE id = ?")
+        .bind(stop_reason)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Record a request's generated title.
+pub async fn set_request_title<'e>(
+    executor: impl SqliteExecutor<'e>,
+    request_id: &str,
+    title: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE requests SET title = ? WHERE id = ?")
+        .bind(title)
+        .bind(request_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Oldest Messages requests that have not been given a title yet.
+pub async fn list_untitled_requests(
+    pool: &SqlitePool,
+    limit: i64,
+) -> anyhow::Result<Vec<ProxyRequest>> {
+    let requests = sqlx::query_as::<_, ProxyRequest>(&format!(
+        "SELECT {} FROM requests WHERE title IS NULL AND messages_json IS NOT NULL \
+         ORDER BY created_at ASC LIMIT ?",
+        REQUEST_COLUMNS
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    load_requests_bodies(requests)
+}
+
+/// The title of an earlier request in the same conversation: one in the
+/// session whose first message matches the first of `messages_json`.
+pub async fn find_conversation_title(
+    pool: &SqlitePool,
+    session_id: &str,
+    messages_json: &str,
+) -> anyhow::Result<Option<String>> {
+    let row: Option<(String,)> = sqlx::query_as(
+        "SELECT title FROM requests WHERE session_id = ? AND title IS NOT NULL AND title != '' \
+         AND json_extract(messages_json, '$[0]') = json_extract(?, '$[0]') \
+         ORDER BY created_at ASC LIMIT 1",
+    )
+    .bind(session_id)
+    .bind(messages_json)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|row| row.0))
+}
+
+/// `(request id, id of the earliest request with the same body)` for each
+/// request on a page of `list_requests_paginated` that repeats an earlier one.
+pub async fn list_duplicate_request_ids(
+    pool: &SqlitePool,
+    session_id: &str,
+    stop_reason: Option<&str>,
+    client_user_agent: Option<&str>,
+    intercepted_only: bool,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<(String, String)>> {
+    Ok(sqlx::query_as(
+        "SELECT r.id, (SELECT o.id FROM requests o \
+         WHERE o.session_id = r.session_id AND o.body_hash = r.body_hash \
+         AND o.created_at < r.created_at ORDER BY o.created_at ASC LIMIT 1) AS first_id \
+         FROM requests r WHERE r.id IN (SELECT id FROM requests WHERE session_id = ? \
+         AND parent_request_id IS NULL \
+         AND (? IS NULL OR stop_reason = ?) AND (? IS NULL OR client_user_agent = ?) \
+         AND (? = 0 OR webfetch_intercepted = 1) \
+         ORDER BY created_at DESC LIMIT ? OFFSET ?) \
+         AND r.body_hash IS NOT NULL AND first_id IS NOT NULL",
+    )
+    .bind(session_id)
+    .bind(stop_reason)
+    .bind(stop_reason)
+    .bind(client_user_agent)
+    .bind(client_user_agent)
+    .bind(intercepted_only)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Bodies sent more than once in the session, most repeated first.
+pub async fn list_duplicate_groups(
+    pool: &SqlitePool,
+    session_id: &str,
+    limit: i64,
+) -> anyhow::Result<Vec<DuplicateGroup>> {
+    Ok(sqlx::query_as::<_, DuplicateGroup>(
+        "SELECT body_hash, COUNT(*) AS request_count, \
+         (SELECT o.id FROM requests o WHERE o.session_id = r.session_id \
+         AND o.body_hash = r.body_hash ORDER BY o.created_at ASC LIMIT 1) AS first_request_id, \
+         MAX(model) AS model, MAX(title) AS title, MAX(created_at) AS last_seen_at \
+         FROM requests r WHERE session_id = ? AND body_hash IS NOT NULL \
+         GROUP BY body_hash HAVING COUNT(*) > 1 \
+         ORDER BY request_count DESC, last_seen_at DESC LIMIT ?",
+    )
+    .bind(session_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
 pub async fn get_prev_request_id(
     pool: &SqlitePool,
     session_id: &str,
@@ -177,8 +678,31 @@ pub async fn get_next_request_id(
     Ok(row.map(|row| row.0))
 }
 
-pub async fn set_request_webfetch_data(
+/// Get the closest older request in the session sent to the same model.
+pub async fn get_older_request_for_model(
     pool: &SqlitePool,
+    session_id: &str,
+    created_at: &str,
+    model: Option<&str>,
+) -> anyhow::Result<Option<ProxyRequest>> {
+    let mut request = sqlx::query_as::<_, ProxyRequest>(&format!(
+        "SELECT {} FROM requests WHERE session_id = ? AND created_at < ? AND model IS ? \
+         ORDER BY created_at DESC LIMIT 1",
+        REQUEST_COLUMNS
+    ))
+    .bind(session_id)
+    .bind(created_at)
+    .bind(model)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(request) = &mut request {
+        load_request_bodies(request)?;
+    }
+    Ok(request)
+}
+
+pub async fn set_request_webfetch_data<'e>(
+    executor: impl SqliteExecutor<'e>,
     request_id: &str,
     webfetch_first_response_body: Option<&str>,
     webfetch_first_response_events_json: Option<&str>,
@@ -187,14 +711,15 @@ pub async fn set_request_webfetch_data(
 ) -> anyhow::Result<()> {
     sqlx::query(
         "UPDATE requests SET webfetch_first_response_body = ?, webfetch_first_response_events_json = ?, \
-         webfetch_followup_body_json = ?, webfetch_rounds_json = ? WHERE id = ?",
+         webfetch_followup_body_json = ?, webfetch_rounds_json = ?, webfetch_intercepted = 1 \
+         WHERE id = ?",
     )
     .bind(webfetch_first_response_body)
     .bind(webfetch_first_response_events_json)
     .bind(webfetch_followup_body_json)
     .bind(webfetch_rounds_json)
     .bind(request_id)
-    .execute(pool)
+    .execute(executor)
     .await?;
     Ok(())
 }
@@ -1,12 +1,27 @@
 use common::models::Session;
 use sqlx::sqlite::SqlitePool;
 
-/// All columns for the `sessions` table, including a computed request_count.
+/// All columns for the `sessions` table, including computed request counts.
 const SESSION_SELECT: &str = "\
     SELECT s.id, s.name, s.target_url, s.tls_verify_disabled, s.auth_header, \
-    s.x_api_key, s.profile_id, s.error_inject, s.webfetch_intercept, \
-    s.webfetch_whitelist, s.created_at, s.updated_at, \
-    COALESCE((SELECT COUNT(*) FROM requests r WHERE r.session_id = s.id), 0) as request_count \
+    s.x_api_key, s.profile_id, s.aws_region, s.aws_access_key_id, \
+    s.aws_secret_access_key, s.aws_session_token, s.upstream_preset, \
+    s.max_concurrent_requests, s.concurrency_overflow, s.max_body_bytes, \
+    s.forward_header_allow, s.forward_header_deny, s.response_header_allow, \
+    s.response_header_deny, s.auth_query_params, s.header_preset, s.beta_inject, \
+    s.beta_strip, s.completion_webhook_url, s.hostname, s.is_default, s.error_inject, \
+    s.breakpoint_enabled, s.webfetch_intercept, \
+    s.webfetch_allow_private_targets, s.webfetch_max_content_bytes, \
+    s.webfetch_fetch_timeout_secs, s.webfetch_extraction_mode, \
+    s.webfetch_respect_robots_txt, s.webfetch_raw_mode, s.webfetch_agent_url, \
+    s.webfetch_agent_model, s.webfetch_agent_api_key, s.mcp_server_transport, s.mcp_server_target, \
+    s.mcp_server_status, s.mcp_server_status_detail, s.mcp_server_checked_at, \
+    s.validation_mode, s.validation_schema, \
+    s.created_at, s.updated_at, \
+    COALESCE((SELECT COUNT(*) FROM requests r WHERE r.session_id = s.id), 0) as request_count, \
+    COALESCE((SELECT COUNT(*) FROM requests r \
+        WHERE r.session_id = s.id AND r.webfetch_intercepted = 1), 0) as intercepted_request_count, \
+    COALESCE((SELECT COUNT(*) FROM message_batches b WHERE b.session_id = s.id), 0) as batch_count \
     FROM sessions s";
 
 pub async fn count_sessions(pool: &SqlitePool) -> anyhow::Result<i64> {
@@ -48,6 +63,66 @@ pub async fn get_session(pool: &SqlitePool, id: &str) -> anyhow::Result<Option<S
     )
 }
 
+/// The oldest session with this name, for callers that key sessions by name.
+pub async fn find_session_by_name(
+    pool: &SqlitePool,
+    name: &str,
+) -> anyhow::Result<Option<Session>> {
+    Ok(sqlx::query_as::<_, Session>(&format!(
+        "{} WHERE s.name = ? ORDER BY s.created_at LIMIT 1",
+        SESSION_SELECT
+    ))
+    .bind(name)
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// The id of the session routed from `hostname`, if any.
+pub async fn find_session_id_by_hostname(
+    pool: &SqlitePool,
+    hostname: &str,
+) -> anyhow::Result<Option<String>> {
+    Ok(
+        sqlx::query_scalar::<_, String>("SELECT id FROM sessions WHERE hostname = ?")
+            .bind(hostname)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// The id of the session requests without a session prefix are routed to,
+/// if one is set.
+pub async fn find_default_session_id(pool: &SqlitePool) -> anyhow::Result<Option<String>> {
+    Ok(
+        sqlx::query_scalar::<_, String>("SELECT id FROM sessions WHERE is_default = 1")
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Make the session the default session, replacing any other, or stop it
+/// being the default.
+pub async fn set_default_session(
+    pool: &SqlitePool,
+    session_id: &str,
+    is_default: bool,
+) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    if is_default {
+        sqlx::query("UPDATE sessions SET is_default = 0 WHERE is_default = 1")
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query("UPDATE sessions SET is_default = ? WHERE id = ?")
+        .bind(is_default)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[derive(Default)]
 pub struct SessionParams<'a> {
     pub id: &'a str,
     pub name: &'a str,
@@ -56,11 +131,35 @@ pub struct SessionParams<'a> {
     pub auth_header: Option<&'a str>,
     pub x_api_key: Option<&'a str>,
     pub profile_id: Option<&'a str>,
+    pub aws_region: Option<&'a str>,
+    pub aws_access_key_id: Option<&'a str>,
+    pub aws_secret_access_key: Option<&'a str>,
+    pub aws_session_token: Option<&'a str>,
+    pub upstream_preset: Option<&'a str>,
+    pub max_concurrent_requests: Option<i64>,
+    pub concurrency_overflow: Option<&'a str>,
+    pub max_body_bytes: Option<i64>,
+    pub forward_header_allow: Option<&'a str>,
+    pub forward_header_deny: Option<&'a str>,
+    pub response_header_allow: Option<&'a str>,
+    pub response_header_deny: Option<&'a str>,
+    pub auth_query_params: Option<&'a str>,
+    pub header_preset: Option<&'a str>,
+    pub beta_inject: Option<&'a str>,
+    pub beta_strip: Option<&'a str>,
+    pub completion_webhook_url: Option<&'a str>,
+    pub hostname: Option<&'a str>,
 }
 
 pub async fn create_session(pool: &SqlitePool, params: &SessionParams<'_>) -> anyhow::Result<()> {
     sqlx::query(
-        "INSERT INTO sessions (id, name, target_url, tls_verify_disabled, auth_header, x_api_key, profile_id) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO sessions (id, name, target_url, tls_verify_disabled, auth_header, x_api_key, \
+         profile_id, aws_region, aws_access_key_id, aws_secret_access_key, aws_session_token, \
+         upstream_preset, max_concurrent_requests, concurrency_overflow, max_body_bytes, \
+         forward_header_allow, forward_header_deny, response_header_allow, \
+         response_header_deny, auth_query_params, header_preset, beta_inject, beta_strip, \
+         completion_webhook_url, hostname) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(params.id)
     .bind(params.name)
@@ -69,6 +168,24 @@ pub async fn create_session(pool: &SqlitePool, params: &SessionParams<'_>) -> an
     .bind(params.auth_header)
     .bind(params.x_api_key)
     .bind(params.profile_id)
+    .bind(params.aws_region)
+    .bind(params.aws_access_key_id)
+    .bind(params.aws_secret_access_key)
+    .bind(params.aws_session_token)
+    .bind(params.upstream_preset)
+    .bind(params.max_concurrent_requests)
+    .bind(params.concurrency_overflow)
+    .bind(params.max_body_bytes)
+    .bind(params.forward_header_allow)
+    .bind(params.forward_header_deny)
+    .bind(params.response_header_allow)
+    .bind(params.response_header_deny)
+    .bind(params.auth_query_params)
+    .bind(params.header_preset)
+    .bind(params.beta_inject)
+    .bind(params.beta_strip)
+    .bind(params.completion_webhook_url)
+    .bind(params.hostname)
     .execute(pool)
     .await?;
     Ok(())
@@ -87,6 +204,137 @@ pub async fn set_session_error_inject(
     Ok(())
 }
 
+pub async fn set_session_webfetch_allow_private_targets(
+    pool: &SqlitePool,
+    session_id: &str,
+    allowed: bool,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE sessions SET webfetch_allow_private_targets = ? WHERE id = ?")
+        .bind(allowed)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Set the session's fetch size cap and timeout; `None` falls back to the config defaults.
+pub async fn set_session_webfetch_fetch_limits(
+    pool: &SqlitePool,
+    session_id: &str,
+    max_content_bytes: Option<i64>,
+    fetch_timeout_secs: Option<i64>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE sessions SET webfetch_max_content_bytes = ?, webfetch_fetch_timeout_secs = ? \
+         WHERE id = ?",
+    )
+    .bind(max_content_bytes)
+    .bind(fetch_timeout_secs)
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn set_session_webfetch_agent_backend(
+    pool: &SqlitePool,
+    session_id: &str,
+    agent_url: Option<&str>,
+    agent_model: Option<&str>,
+    agent_api_key: Option<&str>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE sessions SET webfetch_agent_url = ?, webfetch_agent_model = ?, \
+         webfetch_agent_api_key = ? WHERE id = ?",
+    )
+    .bind(agent_url)
+    .bind(agent_model)
+    .bind(agent_api_key)
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn set_session_webfetch_extraction_mode(
+    pool: &SqlitePool,
+    session_id: &str,
+    extraction_mode: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE sessions SET webfetch_extraction_mode = ? WHERE id = ?")
+        .bind(extraction_mode)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn set_session_webfetch_respect_robots_txt(
+    pool: &SqlitePool,
+    session_id: &str,
+    respect_robots_txt: bool,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE sessions SET webfetch_respect_robots_txt = ? WHERE id = ?")
+        .bind(respect_robots_txt)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn set_session_webfetch_raw_mode(
+    pool: &SqlitePool,
+    session_id: &str,
+    raw_mode: bool,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE sessions SET webfetch_raw_mode = ? WHERE id = ?")
+        .bind(raw_mode)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Set or clear (with `None`) the session's MCP server. Its health status is
+/// reset, since it described the previous server.
+pub async fn set_session_mcp_server(
+    pool: &SqlitePool,
+    session_id: &str,
+    transport: Option<&str>,
+    target: Option<&str>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE sessions SET mcp_server_transport = ?, mcp_server_target = ?, \
+         mcp_server_status = NULL, mcp_server_status_detail = NULL, \
+         mcp_server_checked_at = NULL WHERE id = ?",
+    )
+    .bind(transport)
+    .bind(target)
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record the outcome of an MCP server check or call.
+pub async fn set_session_mcp_server_status(
+    pool: &SqlitePool,
+    session_id: &str,
+    status: &str,
+    detail: &str,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE sessions SET mcp_server_status = ?, mcp_server_status_detail = ?, \
+         mcp_server_checked_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(status)
+    .bind(detail)
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 pub async fn set_session_webfetch_intercept(
     pool: &SqlitePool,
     session_id: &str,
@@ -100,21 +348,21 @@ pub async fn set_session_webfetch_intercept(
     Ok(())
 }
 
-pub async fn delete_session(pool: &SqlitePool, session_id: &str) -> anyhow::Result<()> {
-    sqlx::query("DELETE FROM sessions WHERE id = ?")
+pub async fn set_session_breakpoint(
+    pool: &SqlitePool,
+    session_id: &str,
+    enabled: bool,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE sessions SET breakpoint_enabled = ? WHERE id = ?")
+        .bind(enabled)
         .bind(session_id)
         .execute(pool)
         .await?;
     Ok(())
 }
 
-pub async fn set_session_webfetch_whitelist(
-    pool: &SqlitePool,
-    session_id: &str,
-    whitelist: Option<&str>,
-) -> anyhow::Result<()> {
-    sqlx::query("UPDATE sessions SET webfetch_whitelist = ? WHERE id = ?")
-        .bind(whitelist)
+pub async fn delete_session(pool: &SqlitePool, session_id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM sessions WHERE id = ?")
         .bind(session_id)
         .execute(pool)
         .await?;
@@ -123,7 +371,13 @@ pub async fn set_session_webfetch_whitelist(
 
 pub async fn update_session(pool: &SqlitePool, params: &SessionParams<'_>) -> anyhow::Result<()> {
     sqlx::query(
-        "UPDATE sessions SET name = ?, target_url = ?, tls_verify_disabled = ?, auth_header = ?, x_api_key = ?, profile_id = ? WHERE id = ?",
+        "UPDATE sessions SET name = ?, target_url = ?, tls_verify_disabled = ?, auth_header = ?, \
+         x_api_key = ?, profile_id = ?, aws_region = ?, aws_access_key_id = ?, \
+         aws_secret_access_key = ?, aws_session_token = ?, upstream_preset = ?, \
+         max_concurrent_requests = ?, concurrency_overflow = ?, max_body_bytes = ?, \
+         forward_header_allow = ?, forward_header_deny = ?, response_header_allow = ?, \
+         response_header_deny = ?, auth_query_params = ?, header_preset = ?, beta_inject = ?, \
+         beta_strip = ?, completion_webhook_url = ?, hostname = ? WHERE id = ?",
     )
     .bind(params.name)
     .bind(params.target_url)
@@ -131,8 +385,41 @@ pub async fn update_session(pool: &SqlitePool, params: &SessionParams<'_>) -> an
     .bind(params.auth_header)
     .bind(params.x_api_key)
     .bind(params.profile_id)
+    .bind(params.aws_region)
+    .bind(params.aws_access_key_id)
+    .bind(params.aws_secret_access_key)
+    .bind(params.aws_session_token)
+    .bind(params.upstream_preset)
+    .bind(params.max_concurrent_requests)
+    .bind(params.concurrency_overflow)
+    .bind(params.max_body_bytes)
+    .bind(params.forward_header_allow)
+    .bind(params.forward_header_deny)
+    .bind(params.response_header_allow)
+    .bind(params.response_header_deny)
+    .bind(params.auth_query_params)
+    .bind(params.header_preset)
+    .bind(params.beta_inject)
+    .bind(params.beta_strip)
+    .bind(params.completion_webhook_url)
+    .bind(params.hostname)
     .bind(params.id)
     .execute(pool)
     .await?;
     Ok(())
 }
+
+pub async fn set_session_validation(
+    pool: &SqlitePool,
+    session_id: &str,
+    mode: Option<&str>,
+    schema: Option<&str>,
+) -> anyhow::Result<()> {
+    sqlx::query("UPDATE sessions SET validation_mode = ?, validation_schema = ? WHERE id = ?")
+        .bind(mode)
+        .bind(schema)
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
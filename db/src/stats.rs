@@ -0,0 +1,222 @@
+use common::models::{ErrorSummary, ModelStats, RecentRequest, RequestStats, ToolCallStats};
+use sqlx::sqlite::SqlitePool;
+
+/// Most models listed in the requests-per-model counts.
+const MAX_MODEL_REQUEST_COUNTS: i64 = 10;
+
+/// Columns of a `RecentRequest`, over `requests r` joined to `sessions s`.
+const RECENT_REQUEST_COLUMNS: &str = "\
+    r.id, r.session_id, s.name AS session_name, r.method, r.path, r.model, \
+    r.response_status, r.error_kind, r.created_at";
+
+/// Per-request usage, as stored with each response.
+const REQUEST_USAGE_SELECT: &str = "\
+    SELECT r.model, r.latency_ms, r.created_at, \
+    r.usage_input_tokens AS input_tokens, r.usage_output_tokens AS output_tokens \
+    FROM requests r WHERE r.model IS NOT NULL";
+
+pub async fn get_request_stats(pool: &SqlitePool) -> anyhow::Result<RequestStats> {
+    let (requests_today,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM requests WHERE created_at >= date('now')")
+            .fetch_one(pool)
+            .await?;
+    let (responded_count, error_count): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(response_status), COALESCE(SUM(response_status >= 400), 0) FROM requests",
+    )
+    .fetch_one(pool)
+    .await?;
+    let (intercepted_tool_calls,): (i64,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(json_array_length(round.value, '$.tool_names')), 0) \
+         FROM requests r, json_each(r.webfetch_rounds_json) AS round \
+         WHERE r.webfetch_rounds_json IS NOT NULL",
+    )
+    .fetch_one(pool)
+    .await?;
+    let model_request_counts: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT model, COUNT(*) AS request_count FROM requests WHERE model IS NOT NULL \
+         GROUP BY model ORDER BY request_count DESC, model LIMIT ?",
+    )
+    .bind(MAX_MODEL_REQUEST_COUNTS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(RequestStats {
+        requests_today,
+        responded_count,
+        error_count,
+        intercepted_tool_calls,
+        model_request_counts,
+    })
+}
+
+/// The newest requests across all sessions, newest first.
+pub async fn list_recent_requests(
+    pool: &SqlitePool,
+    limit: i64,
+) -> anyhow::Result<Vec<RecentRequest>> {
+    Ok(sqlx::query_as::<_, RecentRequest>(&format!(
+        "SELECT {} FROM requests r JOIN sessions s ON s.id = r.session_id \
+         ORDER BY r.created_at DESC, r.rowid DESC LIMIT ?",
+        RECENT_REQUEST_COLUMNS
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Every model seen across sessions, most recently seen first.
+pub async fn list_model_stats(pool: &SqlitePool) -> anyhow::Result<Vec<ModelStats>> {
+    Ok(sqlx::query_as::<_, ModelStats>(&format!(
+        "SELECT model, COUNT(*) AS request_count, \
+         COALESCE(SUM(input_tokens), 0) AS input_tokens, \
+         COALESCE(SUM(output_tokens), 0) AS output_tokens, \
+         AVG(latency_ms) AS avg_latency_ms, MAX(created_at) AS last_seen_at \
+         FROM ({}) GROUP BY model ORDER BY last_seen_at DESC, model",
+        REQUEST_USAGE_SELECT
+    ))
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn count_models(pool: &SqlitePool) -> anyhow::Result<i64> {
+    let (model_count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(DISTINCT model) FROM requests WHERE model IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    Ok(model_count)
+}
+
+pub async fn count_model_requests(pool: &SqlitePool, model: &str) -> anyhow::Result<i64> {
+    let (request_count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM requests WHERE model = ?")
+        .bind(model)
+        .fetch_one(pool)
+        .await?;
+    Ok(request_count)
+}
+
+/// A page of `model`'s requests across sessions, newest first.
+pub async fn list_model_requests_paginated(
+    pool: &SqlitePool,
+    model: &str,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<RecentRequest>> {
+    Ok(sqlx::query_as::<_, RecentRequest>(&format!(
+        "SELECT {} FROM requests r JOIN sessions s ON s.id = r.session_id WHERE r.model = ? \
+         ORDER BY r.created_at DESC, r.rowid DESC LIMIT ? OFFSET ?",
+        RECENT_REQUEST_COLUMNS
+    ))
+    .bind(model)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Failed upstream calls from the last `days` days, grouped by session and
+/// kind, most recent first.
+pub async fn list_error_summaries(
+    pool: &SqlitePool,
+    days: i64,
+) -> anyhow::Result<Vec<ErrorSummary>> {
+    Ok(sqlx::query_as::<_, ErrorSummary>(
+        "SELECT r.session_id, s.name AS session_name, r.error_kind, \
+         COUNT(*) AS error_count, MAX(r.created_at) AS last_seen_at \
+         FROM requests r JOIN sessions s ON s.id = r.session_id \
+         WHERE r.error_kind IS NOT NULL AND r.created_at >= datetime('now', ?) \
+         GROUP BY r.session_id, r.error_kind ORDER BY last_seen_at DESC",
+    )
+    .bind(format!("-{} days", days))
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn count_recent_failures(pool: &SqlitePool, days: i64) -> anyhow::Result<i64> {
+    let (failure_count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM requests \
+         WHERE error_kind IS NOT NULL AND created_at >= datetime('now', ?)",
+    )
+    .bind(format!("-{} days", days))
+    .fetch_one(pool)
+    .await?;
+    Ok(failure_count)
+}
+
+/// The newest failed upstream calls across all sessions, newest first.
+pub async fn list_recent_failures(
+    pool: &SqlitePool,
+    limit: i64,
+) -> anyhow::Result<Vec<RecentRequest>> {
+    Ok(sqlx::query_as::<_, RecentRequest>(&format!(
+        "SELECT {} FROM requests r JOIN sessions s ON s.id = r.session_id \
+         WHERE r.error_kind IS NOT NULL ORDER BY r.created_at DESC, r.rowid DESC LIMIT ?",
+        RECENT_REQUEST_COLUMNS
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Every tool called in the session, most called first.
+pub async fn list_tool_call_stats(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<ToolCallStats>> {
+    Ok(sqlx::query_as::<_, ToolCallStats>(
+        "SELECT json_extract(tool_call.value, '$.name') AS tool_name, \
+         COUNT(DISTINCT json_extract(tool_call.value, '$.id')) AS call_count, \
+         COUNT(DISTINCT CASE WHEN json_extract(tool_call.value, '$.is_error') \
+             THEN json_extract(tool_call.value, '$.id') END) AS error_count, \
+         AVG(json_extract(tool_call.value, '$.input_bytes')) AS avg_input_bytes, \
+         COUNT(DISTINCT r.id) AS request_count \
+         FROM requests r, json_each(r.tool_calls_json) AS tool_call \
+         WHERE r.session_id = ? AND r.tool_calls_json IS NOT NULL \
+         GROUP BY tool_name ORDER BY call_count DESC, tool_name",
+    )
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Count the session's requests sending back results of `tool_name` calls.
+pub async fn count_tool_call_requests(
+    pool: &SqlitePool,
+    session_id: &str,
+    tool_name: &str,
+) -> anyhow::Result<i64> {
+    let (request_count,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM requests r WHERE r.session_id = ? AND EXISTS \
+         (SELECT 1 FROM json_each(r.tool_calls_json) AS tool_call \
+          WHERE json_extract(tool_call.value, '$.name') = ?)",
+    )
+    .bind(session_id)
+    .bind(tool_name)
+    .fetch_one(pool)
+    .await?;
+    Ok(request_count)
+}
+
+/// A page of the session's requests sending back results of `tool_name`
+/// calls, newest first.
+pub async fn list_tool_call_requests_paginated(
+    pool: &SqlitePool,
+    session_id: &str,
+    tool_name: &str,
+    limit: i64,
+    offset: i64,
+) -> anyhow::Result<Vec<RecentRequest>> {
+    Ok(sqlx::query_as::<_, RecentRequest>(&format!(
+        "SELECT {} FROM requests r JOIN sessions s ON s.id = r.session_id \
+         WHERE r.session_id = ? AND EXISTS \
+         (SELECT 1 FROM json_each(r.tool_calls_json) AS tool_call \
+          WHERE json_extract(tool_call.value, '$.name') = ?) \
+         ORDER BY r.created_at DESC, r.rowid DESC LIMIT ? OFFSET ?",
+        RECENT_REQUEST_COLUMNS
+    ))
+    .bind(session_id)
+    .bind(tool_name)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?)
+}
@@ -0,0 +1,73 @@
+//! Helpers for tests that run against a throwaway database file. Enabled by
+//! the `test-util` feature, which the workspace's test builds turn on.
+
+use sqlx::SqlitePool;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::{create_session, init_pool, CreateRequestParams, SessionParams};
+
+/// A migrated database in the temp directory. Its files are deleted on drop.
+pub struct TempDatabase {
+    pub pool: SqlitePool,
+    path: PathBuf,
+}
+
+impl TempDatabase {
+    /// Path of the database file, for opening it again.
+    pub fn path(&self) -> &str {
+        self.path.to_str().unwrap()
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
+
+/// Open a fresh database with a pool of two connections.
+pub async fn open_temp_pool() -> TempDatabase {
+    open_temp_pool_with_size(2).await
+}
+
+/// Open a fresh database with a pool of `max_connections`.
+pub async fn open_temp_pool_with_size(max_connections: u32) -> TempDatabase {
+    let path = std::env::temp_dir().join(format!("gateway-proxy-{}.db", Uuid::new_v4()));
+    let pool = init_pool(path.to_str().unwrap(), max_connections, false)
+        .await
+        .unwrap();
+    TempDatabase { pool, path }
+}
+
+/// Params for a session named `name` whose upstream refuses connections.
+pub fn build_session_params<'a>(id: &'a str, name: &'a str) -> SessionParams<'a> {
+    SessionParams {
+        id,
+        name,
+        target_url: "http://127.0.0.1:1",
+        ..Default::default()
+    }
+}
+
+/// Create a session from `build_session_params`, returning its id.
+pub async fn create_test_session(pool: &SqlitePool, name: &str) -> String {
+    let session_id = Uuid::new_v4().to_string();
+    create_session(pool, &build_session_params(&session_id, name))
+        .await
+        .unwrap();
+    session_id
+}
+
+/// Params for a `POST /v1/messages` request with an empty JSON body.
+pub fn build_request_params(session_id: &str) -> CreateRequestParams<'_> {
+    CreateRequestParams {
+        session_id,
+        method: "POST",
+        path: "/v1/messages",
+        body_json: Some("{}"),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,129 @@
+use common::models::{WebfetchDenyRule, WebfetchWhitelistRule};
+use sqlx::sqlite::SqlitePool;
+
+// -- Whitelist Rules --
+
+const WEBFETCH_WHITELIST_COLUMNS: &str =
+    "id, session_id, domain, allow_subdomains, path_prefix, expires_at, created_at, updated_at";
+
+pub struct WebfetchWhitelistRuleParams<'a> {
+    pub domain: &'a str,
+    pub allow_subdomains: bool,
+    pub path_prefix: Option<&'a str>,
+    pub expires_at: Option<&'a str>,
+}
+
+pub async fn list_webfetch_whitelist_rules(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<WebfetchWhitelistRule>> {
+    Ok(sqlx::query_as::<_, WebfetchWhitelistRule>(&format!(
+        "SELECT {} FROM webfetch_whitelist WHERE session_id = ? ORDER BY domain, created_at",
+        WEBFETCH_WHITELIST_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn get_webfetch_whitelist_rule(
+    pool: &SqlitePool,
+    id: &str,
+) -> anyhow::Result<Option<WebfetchWhitelistRule>> {
+    Ok(sqlx::query_as::<_, WebfetchWhitelistRule>(&format!(
+        "SELECT {} FROM webfetch_whitelist WHERE id = ?",
+        WEBFETCH_WHITELIST_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await?)
+}
+
+pub async fn create_webfetch_whitelist_rule(
+    pool: &SqlitePool,
+    session_id: &str,
+    params: &WebfetchWhitelistRuleParams<'_>,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO webfetch_whitelist \
+         (id, session_id, domain, allow_subdomains, path_prefix, expires_at) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(session_id)
+    .bind(params.domain)
+    .bind(params.allow_subdomains)
+    .bind(params.path_prefix)
+    .bind(params.expires_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn update_webfetch_whitelist_rule(
+    pool: &SqlitePool,
+    id: &str,
+    params: &WebfetchWhitelistRuleParams<'_>,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "UPDATE webfetch_whitelist \
+         SET domain = ?, allow_subdomains = ?, path_prefix = ?, expires_at = ? WHERE id = ?",
+    )
+    .bind(params.domain)
+    .bind(params.allow_subdomains)
+    .bind(params.path_prefix)
+    .bind(params.expires_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_webfetch_whitelist_rule(pool: &SqlitePool, id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_whitelist WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// -- Deny Rules --
+
+const WEBFETCH_DENYLIST_COLUMNS: &str = "id, session_id, pattern, created_at, updated_at";
+
+pub async fn list_webfetch_deny_rules(
+    pool: &SqlitePool,
+    session_id: &str,
+) -> anyhow::Result<Vec<WebfetchDenyRule>> {
+    Ok(sqlx::query_as::<_, WebfetchDenyRule>(&format!(
+        "SELECT {} FROM webfetch_denylist WHERE session_id = ? ORDER BY pattern",
+        WEBFETCH_DENYLIST_COLUMNS
+    ))
+    .bind(session_id)
+    .fetch_all(pool)
+    .await?)
+}
+
+pub async fn create_webfetch_deny_rule(
+    pool: &SqlitePool,
+    session_id: &str,
+    pattern: &str,
+) -> anyhow::Result<()> {
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query("INSERT INTO webfetch_denylist (id, session_id, pattern) VALUES (?, ?, ?)")
+        .bind(&id)
+        .bind(session_id)
+        .bind(pattern)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_webfetch_deny_rule(pool: &SqlitePool, id: &str) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM webfetch_denylist WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,335 @@
+//! Background writer for request logging. Proxied requests queue their
+//! inserts and updates here instead of awaiting SQLite on the client's path;
+//! the writer applies them in order, one transaction per batch. Readers that
+//! need the latest rows call `flush_request_writes` first. The queue is
+//! bounded, so if SQLite stalls, request logging waits for room instead of
+//! piling up writes in memory.
+
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use std::{sync::OnceLock, time::Duration};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::requests::{
+    insert_request, set_request_anthropic_beta, set_request_applied_filters,
+    set_request_counted_input_tokens, set_request_error_kind, set_request_forwarded_bytes,
+    set_request_note, set_request_response, set_request_stop_reason, set_request_webfetch_data,
+    set_request_webfetch_followup_error, CreateRequestParams,
+};
+
+/// How long the writer keeps gathering writes after the first one arrives.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Most writes applied in one transaction.
+const MAX_BATCH_WRITES: usize = 100;
+
+/// Most messages waiting for the writer before senders wait for room.
+const MAX_QUEUED_MESSAGES: usize = 10_000;
+
+/// A request row to insert; the owned form of `CreateRequestParams`.
+#[derive(Debug, Clone, Default)]
+pub struct NewRequest {
+    pub session_id: String,
+    pub method: String,
+    pub path: String,
+    pub headers_json: Option<String>,
+    pub body_json: Option<String>,
+    pub truncated_json: Option<String>,
+    pub model: Option<String>,
+    pub tools_json: Option<String>,
+    pub messages_json: Option<String>,
+    pub system_json: Option<String>,
+    pub params_json: Option<String>,
+    pub note: Option<String>,
+    pub estimated_input_tokens: Option<i64>,
+    pub started_at_ms: Option<i64>,
+    pub request_bytes: Option<i64>,
+    pub body_hash: Option<String>,
+    pub tool_calls_json: Option<String>,
+    pub client_user_agent: Option<String>,
+    pub client_app: Option<String>,
+    pub client_addr: Option<String>,
+    pub parent_request_id: Option<String>,
+}
+
+impl NewRequest {
+    fn build_create_request_params(&self) -> CreateRequestParams<'_> {
+        CreateRequestParams {
+            session_id: &self.session_id,
+            method: &self.method,
+            path: &self.path,
+            headers_json: self.headers_json.as_deref(),
+            body_json: self.body_json.as_deref(),
+            truncated_json: self.truncated_json.as_deref(),
+            model: self.model.as_deref(),
+            tools_json: self.tools_json.as_deref(),
+            messages_json: self.messages_json.as_deref(),
+            system_json: self.system_json.as_deref(),
+            params_json: self.params_json.as_deref(),
+            note: self.note.as_deref(),
+            estimated_input_tokens: self.estimated_input_tokens,
+            started_at_ms: self.started_at_ms,
+            request_bytes: self.request_bytes,
+            body_hash: self.body_hash.as_deref(),
+            tool_calls_json: self.tool_calls_json.as_deref(),
+            client_user_agent: self.client_user_agent.as_deref(),
+            client_app: self.client_app.as_deref(),
+            client_addr: self.client_addr.as_deref(),
+            parent_request_id: self.parent_request_id.as_deref(),
+        }
+    }
+}
+
+/// One insert or update of a request row.
+#[derive(Debug, Clone)]
+pub enum RequestWrite {
+    Create {
+        id: String,
+        request: Box<NewRequest>,
+    },
+    Response {
+        request_id: String,
+        status: i64,
+        headers_json: Option<String>,
+        body: Option<String>,
+        events_json: Option<String>,
+        /// When the response finished, in Unix milliseconds.
+        responded_at_ms: Option<i64>,
+    },
+    WebfetchData {
+        request_id: String,
+        first_response_body: Option<String>,
+        first_response_events_json: Option<String>,
+        followup_body_json: Option<String>,
+        rounds_json: Option<String>,
+    },
+    Note {
+        request_id: String,
+        note: String,
+    },
+    WebfetchFollowupError {
+        request_id: String,
+        error: String,
+    },
+    CountedInputTokens {
+        request_id: String,
+        counted_input_tokens: i64,
+    },
+    ErrorKind {
+        request_id: String,
+        error_kind: String,
+    },
+    ForwardedBytes {
+        request_id: String,
+        forwarded_bytes: i64,
+    },
+    AppliedFilters {
+        request_id: String,
+        applied_filters_json: String,
+    },
+    AnthropicBeta {
+        request_id: String,
+        anthropic_beta: String,
+    },
+    StopReason {
+        request_id: String,
+        stop_reason: String,
+    },
+}
+
+enum WriterMessage {
+    Write(RequestWrite),
+    /// Answered once every write queued before it is applied.
+    Flush(oneshot::Sender<()>),
+}
+
+/// The running writer. Started once, for the server's pool.
+static REQUEST_WRITER: OnceLock<mpsc::Sender<WriterMessage>> = OnceLock::new();
+
+/// Start the background writer for `pool`. Until it is started, and after it
+/// stops, writes are applied directly.
+pub fn start_request_writer(pool: SqlitePool) {
+    let (sender, receiver) = mpsc::channel(MAX_QUEUED_MESSAGES);
+    if REQUEST_WRITER.set(sender).is_ok() {
+        tokio::spawn(run_request_writer(pool, receiver));
+    }
+}
+
+/// Queue a write for the background writer, waiting while its queue is full,
+/// or apply it now when none is running.
+pub async fn write_request(pool: &SqlitePool, request_write: RequestWrite) -> anyhow::Result<()> {
+    let Some(sender) = REQUEST_WRITER.get() else {
+        return apply_single_write(pool, &request_write).await;
+    };
+    match sender.send(WriterMessage::Write(request_write)).await {
+        Ok(()) => Ok(()),
+        Err(mpsc::error::SendError(WriterMessage::Write(request_write))) => {
+            apply_single_write(pool, &request_write).await
+        }
+        Err(_) => Ok(()),
+    }
+}
+
+/// Wait until every write queued so far is in the database.
+pub async fn flush_request_writes() {
+    let Some(sender) = REQUEST_WRITER.get() else {
+        return;
+    };
+    let (flush_done, flush_finished) = oneshot::channel();
+    if sender.send(WriterMessage::Flush(flush_done)).await.is_ok() {
+        let _ = flush_finished.await;
+    }
+}
+
+/// Apply one write on a connection of its own, outside any batch.
+async fn apply_single_write(pool: &SqlitePool, request_write: &RequestWrite) -> anyhow::Result<()> {
+    let mut conn = pool.acquire().await?;
+    apply_write(&mut conn, request_write).await
+}
+
+async fn apply_write(
+    executor: &mut SqliteConnection,
+    request_write: &RequestWrite,
+) -> anyhow::Result<()> {
+    match request_write {
+        RequestWrite::Create { id, request } => {
+            insert_request(executor, id, &request.build_create_request_params()).await
+        }
+        RequestWrite::Response {
+            request_id,
+            status,
+            headers_json,
+            body,
+            events_json,
+            responded_at_ms,
+        } => {
+            set_request_response(
+                executor,
+                request_id,
+                *status,
+                headers_json.as_deref(),
+                body.as_deref(),
+                events_json.as_deref(),
+                *responded_at_ms,
+            )
+            .await
+        }
+        RequestWrite::WebfetchData {
+            request_id,
+            first_response_body,
+            first_response_events_json,
+            followup_body_json,
+            rounds_json,
+        } => {
+            set_request_webfetch_data(
+                executor,
+                request_id,
+                first_response_body.as_deref(),
+                first_response_events_json.as_deref(),
+                followup_body_json.as_deref(),
+                rounds_json.as_deref(),
+            )
+            .await
+        }
+        RequestWrite::Note { request_id, note } => {
+            set_request_note(executor, request_id, note).await
+        }
+        RequestWrite::WebfetchFollowupError { request_id, error } => {
+            set_request_webfetch_followup_error(executor, request_id, error).await
+        }
+        RequestWrite::CountedInputTokens {
+            request_id,
+            counted_input_tokens,
+        } => set_request_counted_input_tokens(executor, request_id, *counted_input_tokens).await,
+        RequestWrite::ErrorKind {
+            request_id,
+            error_kind,
+        } => set_request_error_kind(executor, request_id, error_kind).await,
+        RequestWrite::ForwardedBytes {
+            request_id,
+            forwarded_bytes,
+        } => set_request_forwarded_bytes(executor, request_id, *forwarded_bytes).await,
+        RequestWrite::AppliedFilters {
+            request_id,
+            applied_filters_json,
+        } => set_request_applied_filters(executor, request_id, applied_filters_json).await,
+        RequestWrite::AnthropicBeta {
+            request_id,
+            anthropic_beta,
+        } => set_request_anthropic_beta(executor, request_id, anthropic_beta).await,
+        RequestWrite::StopReason {
+            request_id,
+            stop_reason,
+        } => set_request_stop_reason(executor, request_id, stop_reason).await,
+    }
+}
+
+/// Apply a batch in one transaction. A failed write is logged and skipped.
+async fn apply_batch(pool: &SqlitePool, writes: &[RequestWrite]) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    for request_write in writes {
+        if let Err(e) = apply_write(&mut tx, request_write).await {
+            log::warn!("request writer: failed to apply write: {}", e);
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Writes and flush requests gathered for one transaction.
+#[derive(Default)]
+struct WriterBatch {
+    writes: Vec<RequestWrite>,
+    flushes: Vec<oneshot::Sender<()>>,
+}
+
+/// Gather messages after `first_message` until the flush interval ends, the
+/// batch is full, or a reader asks for a flush.
+async fn gather_writer_batch(
+    receiver: &mut mpsc::Receiver<WriterMessage>,
+    first_message: WriterMessage,
+) -> WriterBatch {
+    let deadline = tokio::time::Instant::now() + FLUSH_INTERVAL;
+    let mut writer_batch = WriterBatch::default();
+    let mut next_message = Some(first_message);
+    while let Some(writer_message) = next_message.take() {
+        match writer_message {
+            WriterMessage::Write(request_write) => writer_batch.writes.push(request_write),
+            WriterMessage::Flush(flush_done) => writer_batch.flushes.push(flush_done),
+        }
+        if writer_batch.writes.len() >= MAX_BATCH_WRITES || !writer_batch.flushes.is_empty() {
+            break;
+        }
+        next_message = tokio::time::timeout_at(deadline, receiver.recv())
+            .await
+            .ok()
+            .flatten();
+    }
+    writer_batch
+}
+
+/// Apply a batch, falling back to one write at a time if its transaction fails.
+async fn store_writer_batch(pool: &SqlitePool, writes: &[RequestWrite]) {
+    if let Err(e) = apply_batch(pool, writes).await {
+        log::warn!(
+            "request writer: batch failed, applying writes one by one: {}",
+            e
+        );
+        for request_write in writes {
+            if let Err(e) = apply_single_write(pool, request_write).await {
+                log::error!("request writer: dropped a write: {}", e);
+            }
+        }
+    }
+}
+
+async fn run_request_writer(pool: SqlitePool, mut receiver: mpsc::Receiver<WriterMessage>) {
+    while let Some(first_message) = receiver.recv().await {
+        let writer_batch = gather_writer_batch(&mut receiver, first_message).await;
+        if !writer_batch.writes.is_empty() {
+            store_writer_batch(&pool, &writer_batch.writes).await;
+        }
+        for flush_done in writer_batch.flushes {
+            let _ = flush_done.send(());
+        }
+    }
+}
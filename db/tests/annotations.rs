@@ -0,0 +1,53 @@
+//! Notes and stars left on a request by dashboard users.
+
+use db::test_util::{build_request_params, create_test_session, open_temp_pool};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn notes_and_stars_record_authors() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "shared").await;
+    let request_id = Uuid::new_v4().to_string();
+    let params = build_request_params(&session_id);
+    db::insert_request(pool, &request_id, &params)
+        .await
+        .unwrap();
+
+    db::create_request_note(pool, &request_id, "alice", "Looks cached")
+        .await
+        .unwrap();
+    db::create_request_note(pool, &request_id, "bob", "Agreed")
+        .await
+        .unwrap();
+    let notes = db::list_request_notes(pool, &request_id).await.unwrap();
+    let authors: Vec<_> = notes.iter().map(|note| note.author.as_str()).collect();
+    assert_eq!(authors, ["alice", "bob"]);
+    assert_eq!(notes[0].note, "Looks cached");
+
+    assert!(db::toggle_request_star(pool, &request_id, "alice")
+        .await
+        .unwrap());
+    assert!(db::toggle_request_star(pool, &request_id, "bob")
+        .await
+        .unwrap());
+    assert!(!db::toggle_request_star(pool, &request_id, "alice")
+        .await
+        .unwrap());
+    let star_authors = db::list_request_star_authors(pool, &request_id)
+        .await
+        .unwrap();
+    assert_eq!(star_authors, ["bob"]);
+
+    // Deleting the session's requests removes their annotations.
+    db::clear_requests(pool, &session_id).await.unwrap();
+    assert!(db::list_request_notes(pool, &request_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(db::list_request_star_authors(pool, &request_id)
+        .await
+        .unwrap()
+        .is_empty());
+}
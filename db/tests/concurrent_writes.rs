@@ -0,0 +1,53 @@
+//! Parallel writers against a file-backed pool must not hit "database is locked".
+
+use db::test_util::{build_request_params, create_test_session, open_temp_pool_with_size};
+use db::CreateRequestParams;
+use uuid::Uuid;
+
+const WRITERS: usize = 16;
+const INSERTS_PER_WRITER: usize = 50;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn parallel_inserts_all_land() {
+    let temp_db = open_temp_pool_with_size(8).await;
+    let pool = &temp_db.pool;
+    let session_id = create_test_session(pool, "load").await;
+
+    let writers: Vec<_> = (0..WRITERS)
+        .map(|writer| {
+            let pool = pool.clone();
+            let session_id = session_id.clone();
+            tokio::spawn(async move {
+                for insert in 0..INSERTS_PER_WRITER {
+                    let id = Uuid::new_v4().to_string();
+                    let note = format!("writer {} insert {}", writer, insert);
+                    let params = CreateRequestParams {
+                        model: Some("test"),
+                        note: Some(&note),
+                        ..build_request_params(&session_id)
+                    };
+                    db::insert_request(&pool, &id, &params).await?;
+                    db::set_request_response(&pool, &id, 200, None, Some("{}"), Some("[]"), None)
+                        .await?;
+                    db::count_requests(&pool, &session_id, None, None, false).await?;
+                }
+                anyhow::Ok(())
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.await.unwrap().unwrap();
+    }
+
+    assert_eq!(
+        db::count_requests(pool, &session_id, None, None, false)
+            .await
+            .unwrap(),
+        (WRITERS * INSERTS_PER_WRITER) as i64
+    );
+    let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(journal_mode, "wal");
+}
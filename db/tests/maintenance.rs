@@ -0,0 +1,146 @@
+//! Database stats, VACUUM, the integrity check and snapshots on a fresh
+//! database.
+
+use db::test_util::{build_request_params, create_test_session, open_temp_pool};
+use db::CreateRequestParams;
+use std::env;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn maintenance_on_fresh_database() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let database_stats = db::get_database_stats(pool).await.unwrap();
+    assert!(database_stats.file_size_bytes > 0);
+    assert!(database_stats.page_count > 0);
+    // The default filter profile is created on startup.
+    assert!(database_stats
+        .table_row_counts
+        .iter()
+        .any(|(name, count)| name == "filter_profiles" && *count == 1));
+    assert!(database_stats
+        .table_row_counts
+        .iter()
+        .all(|(name, _)| !name.starts_with("sqlite_")));
+
+    db::vacuum_database(pool).await.unwrap();
+    assert_eq!(
+        db::get_database_stats(pool).await.unwrap().freelist_count,
+        0
+    );
+    assert_eq!(db::check_database_integrity(pool).await.unwrap(), ["ok"]);
+}
+
+#[tokio::test]
+async fn snapshot_restore_and_prune() {
+    let backup_dir = env::temp_dir().join(format!("gateway-proxy-backups-{}", Uuid::new_v4()));
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    db::set_setting(pool, "retention_days", "7").await.unwrap();
+    let alert_rule_params = db::AlertRuleParams {
+        name: "truncations",
+        condition: "stop_reason",
+        condition_value: "max_tokens",
+        window_minutes: 5,
+    };
+    db::create_alert_rule(pool, &alert_rule_params)
+        .await
+        .unwrap();
+    let rule_id = db::list_alert_rules(pool).await.unwrap()[0].id.to_string();
+    db::create_alert_event(pool, &rule_id, None, None, "stop_reason max_tokens")
+        .await
+        .unwrap();
+    let file_name = db::snapshot_database(pool, &backup_dir).await.unwrap();
+    assert!(db::is_snapshot_file_name(&file_name));
+    let next_file_name = db::snapshot_database(pool, &backup_dir).await.unwrap();
+    assert_ne!(next_file_name, file_name);
+    std::fs::remove_file(backup_dir.join(&next_file_name)).unwrap();
+    db::set_setting(pool, "retention_days", "30").await.unwrap();
+
+    db::restore_database_snapshot(pool, &backup_dir.join(&file_name))
+        .await
+        .unwrap();
+    assert_eq!(
+        db::get_setting(pool, "retention_days")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("7")
+    );
+    assert_eq!(db::list_alert_events(pool, 10).await.unwrap().len(), 1);
+    assert_eq!(db::check_database_integrity(pool).await.unwrap(), ["ok"]);
+
+    std::fs::write(backup_dir.join("snapshot-00000000-000000.db"), b"").unwrap();
+    let snapshots = db::list_database_snapshots(&backup_dir).unwrap();
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[0].file_name, file_name);
+    assert_eq!(db::prune_database_snapshots(&backup_dir, 1).unwrap(), 1);
+    assert_eq!(db::list_database_snapshots(&backup_dir).unwrap().len(), 1);
+    assert!(!db::is_snapshot_file_name("../snapshot-1.db"));
+
+    let _ = std::fs::remove_dir_all(&backup_dir);
+}
+
+#[tokio::test]
+async fn large_bodies_stored_compressed() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let session_id = create_test_session(pool, "compression").await;
+    let body_json = format!(r#"{{"messages": ["{}"]}}"#, "long message ".repeat(1000));
+    let response_body = format!(r#"{{"content": ["{}"]}}"#, "long answer ".repeat(1000));
+    let params = CreateRequestParams {
+        body_json: Some(&body_json),
+        request_bytes: Some(body_json.len() as i64),
+        ..build_request_params(&session_id)
+    };
+
+    // Logged with compression off, then compressed in place.
+    db::set_body_compression_threshold(0);
+    let text_id = Uuid::new_v4().to_string();
+    db::insert_request(pool, &text_id, &params).await.unwrap();
+    db::set_request_response(pool, &text_id, 200, None, Some(&response_body), None, None)
+        .await
+        .unwrap();
+    db::set_body_compression_threshold(1024);
+    let body_compression = db::get_database_stats(pool).await.unwrap().body_compression;
+    assert_eq!(body_compression.compressed_count, 0);
+    assert_eq!(body_compression.pending_count, 2);
+    assert_eq!(db::compress_stored_bodies(pool).await.unwrap(), 2);
+
+    // Logged with compression on.
+    let compressed_id = Uuid::new_v4().to_string();
+    db::insert_request(pool, &compressed_id, &params)
+        .await
+        .unwrap();
+    db::set_request_response(
+        pool,
+        &compressed_id,
+        200,
+        None,
+        Some(&response_body),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    for request_id in [&text_id, &compressed_id] {
+        let request = db::get_request(pool, request_id).await.unwrap().unwrap();
+        assert_eq!(request.body_json.as_deref(), Some(body_json.as_str()));
+        assert_eq!(
+            request.response_body.as_deref(),
+            Some(response_body.as_str())
+        );
+        assert!(request.body_json_zstd.is_none());
+    }
+    let body_compression = db::get_database_stats(pool).await.unwrap().body_compression;
+    assert_eq!(body_compression.compressed_count, 4);
+    assert_eq!(body_compression.pending_count, 0);
+    assert_eq!(
+        body_compression.original_bytes,
+        2 * (body_json.len() + response_body.len()) as i64
+    );
+    assert!(body_compression.compressed_bytes < body_compression.original_bytes / 10);
+}
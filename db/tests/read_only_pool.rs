@@ -0,0 +1,32 @@
+//! Opening the database with `--read-only`.
+
+use db::test_util::{build_request_params, create_test_session, open_temp_pool};
+
+#[tokio::test]
+async fn read_only_pool_reads_but_refuses_writes() {
+    let temp_db = open_temp_pool().await;
+    let session_id = create_test_session(&temp_db.pool, "shared").await;
+
+    let pool = db::init_pool(temp_db.path(), 1, true).await.unwrap();
+    let session = db::get_session(&pool, &session_id).await.unwrap();
+    assert_eq!(session.unwrap().name, "shared");
+
+    let params = build_request_params(&session_id);
+    assert!(db::insert_request(&pool, "request-1", &params)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn read_only_pool_fails_when_migrations_are_missing() {
+    let temp_db = open_temp_pool().await;
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(&temp_db.pool)
+    .await
+    .unwrap();
+
+    let err = db::init_pool(temp_db.path(), 1, true).await.unwrap_err();
+    assert!(err.to_string().contains("without --read-only"), "{}", err);
+}
@@ -0,0 +1,609 @@
+//! Dashboard aggregates over requests from more than one session.
+
+use db::test_util::{build_request_params, create_test_session, open_temp_pool};
+use db::CreateRequestParams;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+fn build_test_request_params<'a>(session_id: &'a str, model: &'a str) -> CreateRequestParams<'a> {
+    CreateRequestParams {
+        model: Some(model),
+        ..build_request_params(session_id)
+    }
+}
+
+async fn create_test_request(
+    pool: &SqlitePool,
+    session_id: &str,
+    model: &str,
+    response_status: i64,
+) -> String {
+    let id = Uuid::new_v4().to_string();
+    let params = build_test_request_params(session_id, model);
+    db::insert_request(pool, &id, &params).await.unwrap();
+    db::set_request_response(pool, &id, response_status, None, Some("{}"), None, None)
+        .await
+        .unwrap();
+    id
+}
+
+#[tokio::test]
+async fn request_stats_across_sessions() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let first_session_id = create_test_session(pool, "first").await;
+    let second_session_id = create_test_session(pool, "second").await;
+    create_test_request(pool, &first_session_id, "claude-haiku", 200).await;
+    create_test_request(pool, &first_session_id, "claude-sonnet", 529).await;
+    let intercepted_id = create_test_request(pool, &second_session_id, "claude-sonnet", 200).await;
+    let rounds_json = r#"[{"tool_names": ["WebFetch", "WebFetch"]}, {"tool_names": ["Grep"]}]"#;
+    db::set_request_webfetch_data(pool, &intercepted_id, None, None, None, Some(rounds_json))
+        .await
+        .unwrap();
+
+    let request_stats = db::get_request_stats(pool).await.unwrap();
+    assert_eq!(request_stats.requests_today, 3);
+    assert_eq!(request_stats.responded_count, 3);
+    assert_eq!(request_stats.error_count, 1);
+    assert_eq!(request_stats.intercepted_tool_calls, 3);
+    assert_eq!(
+        request_stats.model_request_counts,
+        [
+            ("claude-sonnet".to_string(), 2),
+            ("claude-haiku".to_string(), 1)
+        ]
+    );
+
+    let recent_requests = db::list_recent_requests(pool, 2).await.unwrap();
+    assert_eq!(recent_requests.len(), 2);
+    assert_eq!(recent_requests[0].id.to_string(), intercepted_id);
+    assert_eq!(recent_requests[0].session_name, "second");
+}
+
+#[tokio::test]
+async fn model_stats_across_sessions() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let first_session_id = create_test_session(pool, "first").await;
+    let second_session_id = create_test_session(pool, "second").await;
+    let json_id = Uuid::new_v4().to_string();
+    let params = CreateRequestParams {
+        started_at_ms: Some(1_000),
+        ..build_test_request_params(&first_session_id, "claude-sonnet")
+    };
+    db::insert_request(pool, &json_id, &params).await.unwrap();
+    let json_body = r#"{"usage": {"input_tokens": 10, "output_tokens": 4}}"#;
+    db::set_request_response(
+        pool,
+        &json_id,
+        200,
+        None,
+        Some(json_body),
+        None,
+        Some(1_300),
+    )
+    .await
+    .unwrap();
+    let streamed_id = Uuid::new_v4().to_string();
+    let params = CreateRequestParams {
+        started_at_ms: Some(2_000),
+        ..build_test_request_params(&second_session_id, "claude-sonnet")
+    };
+    db::insert_request(pool, &streamed_id, &params)
+        .await
+        .unwrap();
+    let events_json = r#"[
+        {"event": "message_start", "data": {"message": {"usage": {"input_tokens": 20, "output_tokens": 1}}}},
+        {"event": "message_delta", "data": {"usage": {"output_tokens": 6}}}
+    ]"#;
+    db::set_request_response(
+        pool,
+        &streamed_id,
+        200,
+        None,
+        Some("event: message_start"),
+        Some(events_json),
+        Some(2_100),
+    )
+    .await
+    .unwrap();
+    create_test_request(pool, &first_session_id, "claude-haiku", 200).await;
+
+    let model_stats = db::list_model_stats(pool).await.unwrap();
+    assert_eq!(db::count_models(pool).await.unwrap(), 2);
+    let sonnet_stats = model_stats
+        .iter()
+        .find(|model_stats| model_stats.model == "claude-sonnet")
+        .unwrap();
+    assert_eq!(sonnet_stats.request_count, 2);
+    assert_eq!(sonnet_stats.input_tokens, 30);
+    assert_eq!(sonnet_stats.output_tokens, 10);
+    assert_eq!(sonnet_stats.avg_latency_ms, Some(200.0));
+    let haiku_stats = model_stats
+        .iter()
+        .find(|model_stats| model_stats.model == "claude-haiku")
+        .unwrap();
+    assert_eq!(haiku_stats.input_tokens, 0);
+    assert_eq!(haiku_stats.avg_latency_ms, None);
+
+    assert_eq!(
+        db::count_model_requests(pool, "claude-sonnet")
+            .await
+            .unwrap(),
+        2
+    );
+    let model_requests = db::list_model_requests_paginated(pool, "claude-sonnet", 1, 1)
+        .await
+        .unwrap();
+    assert_eq!(model_requests.len(), 1);
+    assert_eq!(model_requests[0].id.to_string(), json_id);
+}
+
+#[tokio::test]
+async fn error_summaries_by_session_and_kind() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "flaky").await;
+    for error_kind in ["connect", "connect", "status"] {
+        let request_id = create_test_request(pool, &session_id, "claude-sonnet", 502).await;
+        db::set_request_error_kind(pool, &request_id, error_kind)
+            .await
+            .unwrap();
+    }
+    create_test_request(pool, &session_id, "claude-sonnet", 200).await;
+
+    let error_summaries = db::list_error_summaries(pool, 7).await.unwrap();
+    let mut error_counts: Vec<(String, i64)> = error_summaries
+        .iter()
+        .map(|error_summary| (error_summary.error_kind.clone(), error_summary.error_count))
+        .collect();
+    error_counts.sort();
+    assert_eq!(
+        error_counts,
+        [("connect".to_string(), 2), ("status".to_string(), 1)]
+    );
+    assert_eq!(error_summaries[0].session_name, "flaky");
+    assert_eq!(db::count_recent_failures(pool, 7).await.unwrap(), 3);
+    assert_eq!(db::list_recent_failures(pool, 10).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn alert_matches_across_sessions() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let first_session_id = create_test_session(pool, "first").await;
+    let second_session_id = create_test_session(pool, "second").await;
+    let streamed_events = r#"[{"event": "message_start", "data": {"message": {"usage":
+        {"input_tokens": 10, "cache_read_input_tokens": 160000}}}}]"#;
+    let mut request_ids = Vec::new();
+    for (session_id, response_status, response_body, events_json) in [
+        (
+            &first_session_id,
+            200,
+            r#"{"usage": {"input_tokens": 500}}"#,
+            None,
+        ),
+        (&first_session_id, 200, "", Some(streamed_events)),
+        (&second_session_id, 529, "{}", None),
+    ] {
+        let id = Uuid::new_v4().to_string();
+        let mut params = build_test_request_params(session_id, "claude-sonnet");
+        params.started_at_ms = Some(1_000);
+        db::insert_request(pool, &id, &params).await.unwrap();
+        db::set_request_response(
+            pool,
+            &id,
+            response_status,
+            None,
+            Some(response_body),
+            events_json,
+            Some(2_000),
+        )
+        .await
+        .unwrap();
+        request_ids.push(id);
+    }
+    db::set_request_stop_reason(pool, &request_ids[0], "max_tokens")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        db::count_failed_requests_since(pool, 1_000).await.unwrap(),
+        (3, 1)
+    );
+    assert_eq!(
+        db::list_requests_over_prompt_tokens(pool, 0, 2_000, 150_000, 10)
+            .await
+            .unwrap(),
+        [(request_ids[1].clone(), first_session_id.clone(), 160_010)]
+    );
+    assert_eq!(
+        db::list_requests_with_stop_reason(pool, 0, 2_000, "max_tokens", 10)
+            .await
+            .unwrap(),
+        [(request_ids[0].clone(), first_session_id.clone())]
+    );
+    assert!(
+        db::list_requests_with_stop_reason(pool, 2_000, 3_000, "max_tokens", 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn requests_filtered_by_client() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "shared").await;
+    for client_user_agent in ["claude-cli/1.0.0", "claude-cli/1.0.0", "curl/8.5.0"] {
+        let params = CreateRequestParams {
+            client_user_agent: Some(client_user_agent),
+            client_app: Some("cli"),
+            client_addr: Some("127.0.0.1:54321"),
+            ..build_test_request_params(&session_id, "claude-sonnet")
+        };
+        db::insert_request(pool, &Uuid::new_v4().to_string(), &params)
+            .await
+            .unwrap();
+    }
+    create_test_request(pool, &session_id, "claude-sonnet", 200).await;
+
+    assert_eq!(
+        db::list_request_clients(pool, &session_id).await.unwrap(),
+        ["claude-cli/1.0.0", "curl/8.5.0"]
+    );
+    assert_eq!(
+        db::count_requests(pool, &session_id, None, Some("claude-cli/1.0.0"), false)
+            .await
+            .unwrap(),
+        2
+    );
+    let curl_requests =
+        db::list_requests_paginated(pool, &session_id, None, Some("curl/8.5.0"), false, 10, 0)
+            .await
+            .unwrap();
+    assert_eq!(curl_requests.len(), 1);
+    assert_eq!(
+        curl_requests[0].client_addr.as_deref(),
+        Some("127.0.0.1:54321")
+    );
+    assert_eq!(
+        db::count_requests(pool, &session_id, None, None, false)
+            .await
+            .unwrap(),
+        4
+    );
+}
+
+#[tokio::test]
+async fn intercepted_requests_counted_and_filtered() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "webfetch").await;
+    create_test_request(pool, &session_id, "claude-sonnet", 200).await;
+    let intercepted_id = create_test_request(pool, &session_id, "claude-sonnet", 200).await;
+    db::set_request_webfetch_data(pool, &intercepted_id, None, None, None, Some("[]"))
+        .await
+        .unwrap();
+
+    let session = db::get_session(pool, &session_id).await.unwrap().unwrap();
+    assert_eq!(session.request_count, 2);
+    assert_eq!(session.intercepted_request_count, 1);
+    assert_eq!(
+        db::count_requests(pool, &session_id, None, None, true)
+            .await
+            .unwrap(),
+        1
+    );
+    let intercepted_requests =
+        db::list_request_summaries_paginated(pool, &session_id, None, None, true, 10, 0)
+            .await
+            .unwrap();
+    assert_eq!(intercepted_requests.len(), 1);
+    assert_eq!(intercepted_requests[0].id.to_string(), intercepted_id);
+}
+
+#[tokio::test]
+async fn tool_call_stats_count_each_call_once() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "tools").await;
+    let read_calls_json = r#"[
+        {"id": "t1", "name": "Read", "input_bytes": 10, "is_error": false},
+        {"id": "t2", "name": "Read", "input_bytes": 30, "is_error": true}
+    ]"#;
+    let bash_calls_json = r#"[{"id": "t3", "name": "Bash", "input_bytes": 5, "is_error": false}]"#;
+    // A resend of the first request answers the same calls again.
+    for tool_calls_json in [read_calls_json, read_calls_json, bash_calls_json] {
+        let params = CreateRequestParams {
+            tool_calls_json: Some(tool_calls_json),
+            ..build_test_request_params(&session_id, "claude-sonnet")
+        };
+        db::insert_request(pool, &Uuid::new_v4().to_string(), &params)
+            .await
+            .unwrap();
+    }
+
+    let tool_call_stats = db::list_tool_call_stats(pool, &session_id).await.unwrap();
+    assert_eq!(tool_call_stats.len(), 2);
+    assert_eq!(tool_call_stats[0].tool_name, "Read");
+    assert_eq!(tool_call_stats[0].call_count, 2);
+    assert_eq!(tool_call_stats[0].error_count, 1);
+    assert_eq!(tool_call_stats[0].avg_input_bytes, 20.0);
+    assert_eq!(tool_call_stats[0].request_count, 2);
+    assert_eq!(tool_call_stats[1].tool_name, "Bash");
+
+    let bash_requests = db::list_tool_call_requests_paginated(pool, &session_id, "Bash", 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(bash_requests.len(), 1);
+    assert_eq!(
+        db::count_tool_call_requests(pool, &session_id, "Read")
+            .await
+            .unwrap(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn round_requests_grouped_under_their_parent() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "rounds").await;
+    let parent_request_id = Uuid::new_v4().to_string();
+    let parent_params = CreateRequestParams {
+        messages_json: Some(
+            r#"[{"role": "user", "content": "Fetch it"}, {"role": "assistant", "content": "On it"}]"#,
+        ),
+        ..build_test_request_params(&session_id, "claude-sonnet")
+    };
+    db::insert_request(pool, &parent_request_id, &parent_params)
+        .await
+        .unwrap();
+    let round_params = CreateRequestParams {
+        parent_request_id: Some(&parent_request_id),
+        ..build_test_request_params(&session_id, "claude-sonnet")
+    };
+    let round_request_id = Uuid::new_v4().to_string();
+    db::insert_request(pool, &round_request_id, &round_params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        db::count_requests(pool, &session_id, None, None, false)
+            .await
+            .unwrap(),
+        1
+    );
+    let requests =
+        db::list_request_summaries_paginated(pool, &session_id, None, None, false, 10, 0)
+            .await
+            .unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].id.to_string(), parent_request_id);
+    assert_eq!(requests[0].message_count, Some(2));
+    // The last user message, not the assistant's reply after it.
+    assert!(requests[0]
+        .last_message_json
+        .as_deref()
+        .unwrap()
+        .contains("Fetch it"));
+    let round_requests =
+        db::list_round_request_summaries_paginated(pool, &session_id, None, None, false, 10, 0)
+            .await
+            .unwrap();
+    assert_eq!(round_requests.len(), 1);
+    assert_eq!(
+        round_requests[0].parent_request_id.as_deref(),
+        Some(parent_request_id.as_str())
+    );
+    assert_eq!(
+        db::list_round_request_ids(pool, &parent_request_id)
+            .await
+            .unwrap(),
+        [round_request_id]
+    );
+}
+
+async fn count_blobs(pool: &SqlitePool) -> i64 {
+    db::get_database_stats(pool)
+        .await
+        .unwrap()
+        .table_row_counts
+        .into_iter()
+        .find(|(name, _)| name == "blobs")
+        .unwrap()
+        .1
+}
+
+#[tokio::test]
+async fn repeated_system_and_tools_stored_once() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+
+    let session_id = create_test_session(pool, "blobs").await;
+    let system_json = r#"[{"type": "text", "text": "You are an agent."}]"#;
+    let tools_json = r#"[{"name": "Read", "input_schema": {"type": "object"}}]"#;
+    let params = CreateRequestParams {
+        system_json: Some(system_json),
+        tools_json: Some(tools_json),
+        ..build_test_request_params(&session_id, "claude-sonnet")
+    };
+    let mut request_ids = Vec::new();
+    for _ in 0..3 {
+        let request_id = Uuid::new_v4().to_string();
+        db::insert_request(pool, &request_id, &params)
+            .await
+            .unwrap();
+        request_ids.push(request_id);
+    }
+    assert_eq!(count_blobs(pool).await, 2);
+    for request in db::list_requests(pool, &session_id).await.unwrap() {
+        assert_eq!(request.system_json.as_deref(), Some(system_json));
+        assert_eq!(request.tools_json.as_deref(), Some(tools_json));
+    }
+    let request = db::get_request(pool, &request_ids[0])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(request.system_json.as_deref(), Some(system_json));
+
+    // Blobs go with the last request using them.
+    db::delete_session(pool, &session_id).await.unwrap();
+    assert_eq!(count_blobs(pool).await, 0);
+}
+
+const AGENT_BODY: &str = r#"{"model": "claude-sonnet", "system": [{"type": "text", "text": "You are an agent."}], "tools": [{"name": "Read"}], "messages": [{"role": "user", "content": "hi"}]}"#;
+
+fn parse_json(json: &str) -> serde_json::Value {
+    serde_json::from_str(json).unwrap()
+}
+
+async fn get_stored_body(pool: &SqlitePool, request_id: &str) -> String {
+    sqlx::query_scalar("SELECT body_json FROM requests WHERE id = ?")
+        .bind(request_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn body_stored_without_its_separate_fields() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let session_id = create_test_session(pool, "stripped").await;
+    let request_id = Uuid::new_v4().to_string();
+    let params = CreateRequestParams {
+        body_json: Some(AGENT_BODY),
+        system_json: Some(r#"[{"type": "text", "text": "You are an agent."}]"#),
+        tools_json: Some(r#"[{"name": "Read"}]"#),
+        messages_json: Some(r#"[{"role": "user", "content": "hi"}]"#),
+        ..build_test_request_params(&session_id, "claude-sonnet")
+    };
+    db::insert_request(pool, &request_id, &params)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_stored_body(pool, &request_id).await,
+        r#"{"model":"claude-sonnet"}"#
+    );
+    let request = db::get_request(pool, &request_id).await.unwrap().unwrap();
+    assert_eq!(
+        parse_json(request.body_json.as_deref().unwrap()),
+        parse_json(AGENT_BODY)
+    );
+}
+
+#[tokio::test]
+async fn inline_system_and_tools_moved_into_blobs_on_startup() {
+    let temp_db = open_temp_pool().await;
+    let session_id = create_test_session(&temp_db.pool, "legacy").await;
+    let request_id = Uuid::new_v4().to_string();
+    let params = CreateRequestParams {
+        body_json: Some(AGENT_BODY),
+        messages_json: Some(r#"[{"role": "user", "content": "hi"}]"#),
+        ..build_test_request_params(&session_id, "claude-sonnet")
+    };
+    db::insert_request(&temp_db.pool, &request_id, &params)
+        .await
+        .unwrap();
+    // As logged before blobs: the whole body, with the system prompt and
+    // tools inline.
+    sqlx::query("UPDATE requests SET body_json = ?, system_json = ?, tools_json = ? WHERE id = ?")
+        .bind(AGENT_BODY)
+        .bind(r#"[{"type": "text", "text": "You are an agent."}]"#)
+        .bind(r#"[{"name": "Read"}]"#)
+        .bind(&request_id)
+        .execute(&temp_db.pool)
+        .await
+        .unwrap();
+
+    let pool = db::init_pool(temp_db.path(), 1, false).await.unwrap();
+    assert_eq!(count_blobs(&pool).await, 2);
+    assert_eq!(
+        get_stored_body(&pool, &request_id).await,
+        r#"{"model":"claude-sonnet"}"#
+    );
+    let request = db::get_request(&pool, &request_id).await.unwrap().unwrap();
+    assert_eq!(
+        parse_json(request.body_json.as_deref().unwrap()),
+        parse_json(AGENT_BODY)
+    );
+    assert_eq!(request.tools_json.as_deref(), Some(r#"[{"name": "Read"}]"#));
+}
+
+#[tokio::test]
+async fn usage_counted_after_body_compression() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let session_id = create_test_session(pool, "compressed").await;
+    let response_body = format!(
+        r#"{{"content": [{{"type": "text", "text": "{}"}}], "usage": {{"input_tokens": 1200, "output_tokens": 30, "cache_read_input_tokens": 800}}}}"#,
+        "long answer ".repeat(1000)
+    );
+    let params = CreateRequestParams {
+        started_at_ms: Some(1_000),
+        ..build_test_request_params(&session_id, "claude-opus")
+    };
+
+    // One logged as text then compressed in place, one logged compressed.
+    db::set_body_compression_threshold(0);
+    let text_id = Uuid::new_v4().to_string();
+    db::insert_request(pool, &text_id, &params).await.unwrap();
+    db::set_request_response(
+        pool,
+        &text_id,
+        200,
+        None,
+        Some(&response_body),
+        None,
+        Some(2_000),
+    )
+    .await
+    .unwrap();
+    db::set_body_compression_threshold(1024);
+    assert_eq!(db::compress_stored_bodies(pool).await.unwrap(), 1);
+    let compressed_id = Uuid::new_v4().to_string();
+    db::insert_request(pool, &compressed_id, &params)
+        .await
+        .unwrap();
+    db::set_request_response(
+        pool,
+        &compressed_id,
+        200,
+        None,
+        Some(&response_body),
+        None,
+        Some(2_000),
+    )
+    .await
+    .unwrap();
+    let database_stats = db::get_database_stats(pool).await.unwrap();
+    assert_eq!(database_stats.body_compression.compressed_count, 2);
+
+    let model_stats = db::list_model_stats(pool).await.unwrap();
+    let opus_stats = model_stats
+        .iter()
+        .find(|model_stats| model_stats.model == "claude-opus")
+        .unwrap();
+    assert_eq!(opus_stats.input_tokens, 2400);
+    assert_eq!(opus_stats.output_tokens, 60);
+
+    let over_limit = db::list_requests_over_prompt_tokens(pool, 0, i64::MAX, 1500, 10)
+        .await
+        .unwrap();
+    assert_eq!(over_limit.len(), 2);
+    assert!(over_limit
+        .iter()
+        .all(|(_, _, prompt_tokens)| *prompt_tokens == 2000));
+}
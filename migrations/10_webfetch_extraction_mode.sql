@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN webfetch_extraction_mode TEXT NOT NULL DEFAULT 'text';
@@ -0,0 +1,10 @@
+ALTER TABLE sessions ADD COLUMN webfetch_respect_robots_txt INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS webfetch_robots_cache (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    origin TEXT NOT NULL,
+    robots_txt TEXT NOT NULL,
+    fetched_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (session_id, origin)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE sessions ADD COLUMN mcp_server_transport TEXT;
+ALTER TABLE sessions ADD COLUMN mcp_server_target TEXT;
+ALTER TABLE sessions ADD COLUMN mcp_server_status TEXT;
+ALTER TABLE sessions ADD COLUMN mcp_server_status_detail TEXT;
+ALTER TABLE sessions ADD COLUMN mcp_server_checked_at DATETIME;
@@ -0,0 +1,29 @@
+CREATE TABLE IF NOT EXISTS webfetch_policies (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    tool_name TEXT,
+    condition TEXT NOT NULL,
+    condition_value TEXT,
+    action TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_webfetch_policies_session_id ON webfetch_policies(session_id);
+
+CREATE TRIGGER IF NOT EXISTS webfetch_policies_updated_at
+AFTER UPDATE ON webfetch_policies
+BEGIN
+    UPDATE webfetch_policies SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+CREATE TABLE IF NOT EXISTS webfetch_approved_calls (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    tool_name TEXT NOT NULL,
+    input_json TEXT NOT NULL,
+    approval_count INTEGER NOT NULL DEFAULT 0,
+    last_approved_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (session_id, tool_name, input_json)
+);
@@ -0,0 +1,3 @@
+ALTER TABLE sessions ADD COLUMN webfetch_agent_url TEXT;
+ALTER TABLE sessions ADD COLUMN webfetch_agent_model TEXT;
+ALTER TABLE sessions ADD COLUMN webfetch_agent_api_key TEXT;
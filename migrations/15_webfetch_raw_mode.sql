@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN webfetch_raw_mode INTEGER NOT NULL DEFAULT 0;
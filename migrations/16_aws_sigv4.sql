@@ -0,0 +1,4 @@
+ALTER TABLE sessions ADD COLUMN aws_region TEXT;
+ALTER TABLE sessions ADD COLUMN aws_access_key_id TEXT;
+ALTER TABLE sessions ADD COLUMN aws_secret_access_key TEXT;
+ALTER TABLE sessions ADD COLUMN aws_session_token TEXT;
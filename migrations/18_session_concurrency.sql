@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN max_concurrent_requests INTEGER;
+ALTER TABLE sessions ADD COLUMN concurrency_overflow TEXT;
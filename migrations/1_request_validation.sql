@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN validation_mode TEXT;
+ALTER TABLE sessions ADD COLUMN validation_schema TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE requests ADD COLUMN started_at_ms INTEGER;
+ALTER TABLE requests ADD COLUMN latency_ms INTEGER;
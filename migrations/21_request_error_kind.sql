@@ -0,0 +1,3 @@
+ALTER TABLE requests ADD COLUMN error_kind TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_requests_error_kind ON requests(error_kind);
@@ -0,0 +1,3 @@
+ALTER TABLE requests ADD COLUMN request_bytes INTEGER;
+ALTER TABLE requests ADD COLUMN forwarded_bytes INTEGER;
+ALTER TABLE requests ADD COLUMN response_bytes INTEGER;
@@ -0,0 +1 @@
+ALTER TABLE requests ADD COLUMN applied_filters_json TEXT;
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS request_notes (
+    id TEXT PRIMARY KEY,
+    request_id TEXT NOT NULL REFERENCES requests(id) ON DELETE CASCADE,
+    author TEXT NOT NULL,
+    note TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_notes_request_id ON request_notes(request_id);
+
+CREATE TABLE IF NOT EXISTS request_stars (
+    request_id TEXT NOT NULL REFERENCES requests(id) ON DELETE CASCADE,
+    author TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (request_id, author)
+);
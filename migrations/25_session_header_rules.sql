@@ -0,0 +1,4 @@
+ALTER TABLE sessions ADD COLUMN forward_header_allow TEXT;
+ALTER TABLE sessions ADD COLUMN forward_header_deny TEXT;
+ALTER TABLE sessions ADD COLUMN response_header_allow TEXT;
+ALTER TABLE sessions ADD COLUMN response_header_deny TEXT;
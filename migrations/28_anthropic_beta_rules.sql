@@ -0,0 +1,3 @@
+ALTER TABLE sessions ADD COLUMN beta_inject TEXT;
+ALTER TABLE sessions ADD COLUMN beta_strip TEXT;
+ALTER TABLE requests ADD COLUMN anthropic_beta TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE requests ADD COLUMN estimated_input_tokens INTEGER;
+ALTER TABLE requests ADD COLUMN counted_input_tokens INTEGER;
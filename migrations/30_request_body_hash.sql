@@ -0,0 +1,2 @@
+ALTER TABLE requests ADD COLUMN body_hash TEXT;
+CREATE INDEX IF NOT EXISTS idx_requests_session_body_hash ON requests(session_id, body_hash);
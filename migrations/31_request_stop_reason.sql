@@ -0,0 +1,13 @@
+ALTER TABLE requests ADD COLUMN stop_reason TEXT;
+CREATE INDEX IF NOT EXISTS idx_requests_session_stop_reason ON requests(session_id, stop_reason);
+
+UPDATE requests SET stop_reason = json_extract(response_body, '$.stop_reason')
+WHERE json_valid(response_body);
+
+UPDATE requests SET stop_reason = (
+    SELECT json_extract(value, '$.data.delta.stop_reason') FROM json_each(response_events_json)
+    WHERE json_extract(value, '$.event') = 'message_delta'
+    AND json_extract(value, '$.data.delta.stop_reason') IS NOT NULL
+    LIMIT 1
+)
+WHERE stop_reason IS NULL AND json_valid(response_events_json);
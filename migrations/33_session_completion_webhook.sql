@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN completion_webhook_url TEXT;
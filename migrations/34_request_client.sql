@@ -0,0 +1,9 @@
+ALTER TABLE requests ADD COLUMN client_user_agent TEXT;
+ALTER TABLE requests ADD COLUMN client_app TEXT;
+ALTER TABLE requests ADD COLUMN client_addr TEXT;
+CREATE INDEX IF NOT EXISTS idx_requests_session_client ON requests(session_id, client_user_agent);
+
+UPDATE requests SET
+    client_user_agent = json_extract(headers_json, '$."user-agent"'),
+    client_app = json_extract(headers_json, '$."x-app"')
+WHERE json_valid(headers_json);
@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN hostname TEXT;
+CREATE UNIQUE INDEX idx_sessions_hostname ON sessions(hostname);
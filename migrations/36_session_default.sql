@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN is_default INTEGER NOT NULL DEFAULT 0;
+CREATE UNIQUE INDEX idx_sessions_default ON sessions(is_default) WHERE is_default = 1;
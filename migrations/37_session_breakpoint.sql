@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN breakpoint_enabled INTEGER NOT NULL DEFAULT 0;
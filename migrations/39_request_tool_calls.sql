@@ -0,0 +1 @@
+ALTER TABLE requests ADD COLUMN tool_calls_json TEXT;
@@ -0,0 +1,32 @@
+CREATE TABLE IF NOT EXISTS message_batches (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    request_id TEXT NOT NULL REFERENCES requests(id) ON DELETE CASCADE,
+    upstream_batch_id TEXT NOT NULL,
+    base_path TEXT NOT NULL,
+    processing_status TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS message_batch_items (
+    id TEXT PRIMARY KEY,
+    batch_id TEXT NOT NULL REFERENCES message_batches(id) ON DELETE CASCADE,
+    request_id TEXT NOT NULL REFERENCES requests(id) ON DELETE CASCADE,
+    custom_id TEXT NOT NULL,
+    result_type TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER IF NOT EXISTS message_batches_updated_at
+AFTER UPDATE ON message_batches
+BEGIN
+    UPDATE message_batches SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS message_batch_items_updated_at
+AFTER UPDATE ON message_batch_items
+BEGIN
+    UPDATE message_batch_items SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
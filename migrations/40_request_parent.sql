@@ -0,0 +1,2 @@
+ALTER TABLE requests ADD COLUMN parent_request_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_requests_parent_request_id ON requests(parent_request_id);
@@ -0,0 +1,2 @@
+ALTER TABLE requests ADD COLUMN body_json_zstd BLOB;
+ALTER TABLE requests ADD COLUMN response_body_zstd BLOB;
@@ -0,0 +1,20 @@
+CREATE TABLE IF NOT EXISTS blobs (
+    hash TEXT PRIMARY KEY,
+    content TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE requests ADD COLUMN system_blob_hash TEXT;
+ALTER TABLE requests ADD COLUMN tools_blob_hash TEXT;
+CREATE INDEX IF NOT EXISTS idx_requests_system_blob_hash ON requests(system_blob_hash);
+CREATE INDEX IF NOT EXISTS idx_requests_tools_blob_hash ON requests(tools_blob_hash);
+
+-- Drop a request's blobs once no other request uses them.
+CREATE TRIGGER IF NOT EXISTS requests_delete_unused_blobs
+AFTER DELETE ON requests
+BEGIN
+    DELETE FROM blobs
+    WHERE hash IN (OLD.system_blob_hash, OLD.tools_blob_hash)
+    AND NOT EXISTS (SELECT 1 FROM requests WHERE system_blob_hash = blobs.hash)
+    AND NOT EXISTS (SELECT 1 FROM requests WHERE tools_blob_hash = blobs.hash);
+END;
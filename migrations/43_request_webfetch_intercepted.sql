@@ -0,0 +1,6 @@
+-- Set once a request's WebFetch interception data is stored, so the
+-- intercepted requests can be counted and listed per session.
+ALTER TABLE requests ADD COLUMN webfetch_intercepted INTEGER NOT NULL DEFAULT 0;
+UPDATE requests SET webfetch_intercepted = 1 WHERE webfetch_rounds_json IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_requests_session_webfetch_intercepted
+    ON requests(session_id, webfetch_intercepted);
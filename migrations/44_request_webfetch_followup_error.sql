@@ -0,0 +1 @@
+ALTER TABLE requests ADD COLUMN webfetch_followup_error TEXT;
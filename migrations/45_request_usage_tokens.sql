@@ -0,0 +1,32 @@
+-- Token usage of each response, kept apart from the body so stats and
+-- alerts still count responses whose body is stored compressed.
+ALTER TABLE requests ADD COLUMN usage_input_tokens INTEGER;
+ALTER TABLE requests ADD COLUMN usage_output_tokens INTEGER;
+ALTER TABLE requests ADD COLUMN usage_cache_creation_input_tokens INTEGER;
+ALTER TABLE requests ADD COLUMN usage_cache_read_input_tokens INTEGER;
+UPDATE requests SET
+    usage_input_tokens = COALESCE(
+        (SELECT MAX(json_extract(event.value, '$.data.message.usage.input_tokens'))
+         FROM json_each(response_events_json) AS event
+         WHERE json_extract(event.value, '$.event') = 'message_start'),
+        CASE WHEN json_valid(response_body)
+            THEN json_extract(response_body, '$.usage.input_tokens') END),
+    usage_output_tokens = COALESCE(
+        (SELECT MAX(json_extract(event.value, '$.data.usage.output_tokens'))
+         FROM json_each(response_events_json) AS event
+         WHERE json_extract(event.value, '$.event') = 'message_delta'),
+        CASE WHEN json_valid(response_body)
+            THEN json_extract(response_body, '$.usage.output_tokens') END),
+    usage_cache_creation_input_tokens = COALESCE(
+        (SELECT MAX(json_extract(event.value, '$.data.message.usage.cache_creation_input_tokens'))
+         FROM json_each(response_events_json) AS event
+         WHERE json_extract(event.value, '$.event') = 'message_start'),
+        CASE WHEN json_valid(response_body)
+            THEN json_extract(response_body, '$.usage.cache_creation_input_tokens') END),
+    usage_cache_read_input_tokens = COALESCE(
+        (SELECT MAX(json_extract(event.value, '$.data.message.usage.cache_read_input_tokens'))
+         FROM json_each(response_events_json) AS event
+         WHERE json_extract(event.value, '$.event') = 'message_start'),
+        CASE WHEN json_valid(response_body)
+            THEN json_extract(response_body, '$.usage.cache_read_input_tokens') END)
+WHERE response_status IS NOT NULL;
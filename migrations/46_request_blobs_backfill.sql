@@ -0,0 +1,5 @@
+-- Requests logged before blobs still hold their system prompt and tools
+-- inline; `backfill_request_blobs` moves them at startup and finds them
+-- through this index, which stays empty once they are moved.
+CREATE INDEX IF NOT EXISTS idx_requests_inline_blobs ON requests(id)
+WHERE system_json IS NOT NULL OR tools_json IS NOT NULL;
@@ -0,0 +1,37 @@
+CREATE TABLE IF NOT EXISTS webfetch_whitelist (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    domain TEXT NOT NULL,
+    allow_subdomains INTEGER NOT NULL DEFAULT 1,
+    path_prefix TEXT,
+    expires_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_webfetch_whitelist_session_id ON webfetch_whitelist(session_id);
+
+CREATE TRIGGER IF NOT EXISTS webfetch_whitelist_updated_at
+AFTER UPDATE ON webfetch_whitelist
+BEGIN
+    UPDATE webfetch_whitelist SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+-- Carry over the old newline-separated whitelist: one rule per domain, matching subdomains.
+WITH RECURSIVE split_lines(session_id, line, rest) AS (
+    SELECT id, '', webfetch_whitelist || char(10)
+    FROM sessions
+    WHERE webfetch_whitelist IS NOT NULL
+    UNION ALL
+    SELECT session_id,
+           substr(rest, 1, instr(rest, char(10)) - 1),
+           substr(rest, instr(rest, char(10)) + 1)
+    FROM split_lines
+    WHERE rest <> ''
+)
+INSERT INTO webfetch_whitelist (id, session_id, domain, allow_subdomains)
+SELECT lower(hex(randomblob(16))), session_id, lower(trim(line, ' ' || char(9) || char(13))), 1
+FROM split_lines
+WHERE trim(line, ' ' || char(9) || char(13)) <> '';
+
+ALTER TABLE sessions DROP COLUMN webfetch_whitelist;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS webfetch_denylist (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    pattern TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_webfetch_denylist_session_id ON webfetch_denylist(session_id);
+
+CREATE TRIGGER IF NOT EXISTS webfetch_denylist_updated_at
+AFTER UPDATE ON webfetch_denylist
+BEGIN
+    UPDATE webfetch_denylist SET updated_at = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
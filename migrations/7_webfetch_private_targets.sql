@@ -0,0 +1 @@
+ALTER TABLE sessions ADD COLUMN webfetch_allow_private_targets INTEGER NOT NULL DEFAULT 0;
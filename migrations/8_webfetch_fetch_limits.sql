@@ -0,0 +1,2 @@
+ALTER TABLE sessions ADD COLUMN webfetch_max_content_bytes INTEGER;
+ALTER TABLE sessions ADD COLUMN webfetch_fetch_timeout_secs INTEGER;
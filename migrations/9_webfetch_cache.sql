@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS webfetch_cache (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    content TEXT NOT NULL,
+    original_length INTEGER,
+    bytes_read INTEGER NOT NULL DEFAULT 0,
+    truncated INTEGER NOT NULL DEFAULT 0,
+    fetched_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (session_id, url)
+);
@@ -0,0 +1,206 @@
+use common::alerts::{find_alert_condition_by_key, ALERT_CONDITIONS};
+use common::models::{AlertEvent, AlertRule};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+const ALERTS_URL: &str = "/_dashboard/alerts";
+
+fn format_condition_label(condition: &str) -> String {
+    find_alert_condition_by_key(condition).map_or(condition.to_string(), |alert_condition| {
+        alert_condition.label.to_string()
+    })
+}
+
+fn render_alert_rules_table(rules: &[AlertRule]) -> impl IntoView {
+    if rules.is_empty() {
+        return Either::Left(view! { <p>"No alert rules configured."</p> });
+    }
+    let rows: Vec<_> = rules
+        .iter()
+        .map(|rule| {
+            let edit_href = format!("{}/{}/edit", ALERTS_URL, rule.id);
+            let toggle_action = format!("{}/{}/toggle", ALERTS_URL, rule.id);
+            let delete_action = format!("{}/{}/delete", ALERTS_URL, rule.id);
+            let toggle_label = if rule.enabled { "Disable" } else { "Enable" };
+            let status = if rule.enabled { "Enabled" } else { "Disabled" };
+            view! {
+                <tr>
+                    <td><a href={edit_href}>{rule.name.clone()}</a></td>
+                    <td>{format_condition_label(&rule.condition)}</td>
+                    <td><code>{rule.condition_value.clone()}</code></td>
+                    <td>{format!("{} min", rule.window_minutes)}</td>
+                    <td>{status}</td>
+                    <td>
+                        <form method="POST" action={toggle_action}>
+                            <button type="submit">{toggle_label}</button>
+                        </form>
+                        " "
+                        <form method="POST" action={delete_action}>
+                            <button type="submit">"Delete"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Name"</th>
+                <th>"Condition"</th>
+                <th>"Value"</th>
+                <th>"Window"</th>
+                <th>"Status"</th>
+                <th></th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+fn render_alert_events_table(events: &[AlertEvent]) -> impl IntoView {
+    if events.is_empty() {
+        return Either::Left(view! { <p>"No alerts fired yet."</p> });
+    }
+    let rows: Vec<_> = events
+        .iter()
+        .map(|event| {
+            let request_link = match (&event.session_id, &event.request_id) {
+                (Some(session_id), Some(request_id)) => {
+                    let href = format!(
+                        "/_dashboard/sessions/{}/requests/{}",
+                        session_id, request_id
+                    );
+                    let label = request_id.get(..8).unwrap_or(request_id).to_string();
+                    Some(view! { <a href={href}>{label}</a> })
+                }
+                _ => None,
+            };
+            view! {
+                <tr>
+                    <td>{event.created_at.clone()}</td>
+                    <td>{event.rule_name.clone()}</td>
+                    <td>{event.message.clone()}</td>
+                    <td>{request_link}</td>
+                </tr>
+            }
+        })
+        .collect();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Time"</th>
+                <th>"Rule"</th>
+                <th>"Alert"</th>
+                <th>"Request"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// The alerts page: the rules, then the newest alert events.
+pub fn render_alerts_view(rules: &[AlertRule], events: &[AlertEvent]) -> String {
+    let content = view! {
+        <h2>"Alert Rules"</h2>
+        <p>"Enabled rules are checked every minute against the requests logged since the last check. Each alert is listed below and posted to the notification webhooks."</p>
+        {render_alert_rules_table(rules)}
+        <h2>"Recent Alerts"</h2>
+        {render_alert_events_table(events)}
+    };
+
+    Page {
+        title: "Gateway Proxy - Alerts".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("Alerts"),
+        ],
+        nav_links: vec![
+            NavLink::new("New Rule", format!("{}/new", ALERTS_URL)),
+            NavLink::back(),
+        ],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// The new and edit rule form, filled in from `rule` when editing.
+fn render_alert_rule_form(rule: Option<&AlertRule>) -> impl IntoView {
+    let form_action = match rule {
+        Some(rule) => format!("{}/{}/edit", ALERTS_URL, rule.id),
+        None => ALERTS_URL.to_string(),
+    };
+    let current_condition = rule.map(|rule| rule.condition.clone());
+    let condition_options: Vec<_> = ALERT_CONDITIONS
+        .iter()
+        .map(|condition| {
+            let label = format!("{} — {}", condition.label, condition.description);
+            let selected = current_condition.as_deref() == Some(condition.key);
+            view! { <option value={condition.key} selected={selected}>{label}</option> }
+        })
+        .collect();
+    let name = rule.map(|rule| rule.name.clone()).unwrap_or_default();
+    let condition_value = rule
+        .map(|rule| rule.condition_value.clone())
+        .unwrap_or_default();
+    let window_minutes = rule.map_or(5, |rule| rule.window_minutes).to_string();
+    let submit_label = if rule.is_some() { "Save" } else { "Add Rule" };
+
+    view! {
+        <form method="POST" action={form_action}>
+            <table>
+                <tr>
+                    <td><label>"Name"</label></td>
+                    <td><input type="text" name="name" required size="40" value={name}/></td>
+                </tr>
+                <tr>
+                    <td><label>"Condition"</label></td>
+                    <td><select name="condition">{condition_options}</select></td>
+                </tr>
+                <tr>
+                    <td><label>"Value"</label></td>
+                    <td><input type="text" name="condition_value" size="40" placeholder="10, 150000 or max_tokens" value={condition_value}/></td>
+                </tr>
+                <tr>
+                    <td><label>"Window (minutes)"</label></td>
+                    <td><input type="number" name="window_minutes" min="1" value={window_minutes}/></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value={submit_label}/></td>
+                </tr>
+            </table>
+        </form>
+    }
+}
+
+fn build_alert_rule_form_page(heading: &'static str, rule: Option<&AlertRule>) -> String {
+    let content = view! {
+        <h2>{heading}</h2>
+        {render_alert_rule_form(rule)}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - {}", heading),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Alerts", ALERTS_URL),
+            Breadcrumb::current(if rule.is_some() { "Edit" } else { "New" }),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_new_alert_rule_form() -> String {
+    build_alert_rule_form_page("New Alert Rule", None)
+}
+
+pub fn render_edit_alert_rule_form(rule: &AlertRule) -> String {
+    build_alert_rule_form_page("Edit Alert Rule", Some(rule))
+}
@@ -0,0 +1,112 @@
+use common::models::PendingApprovalInfo;
+use leptos::{either::Either, prelude::*};
+use std::collections::HashMap;
+use templates::{Breadcrumb, NavLink, Page};
+
+use crate::webfetch::format_tool_decision;
+
+/// Accept, Accept raw, Mock and Fail buttons deciding a whole round from the overview.
+fn render_overview_decision_buttons(approval_id: &str) -> impl IntoView {
+    let decide_action = format!("/_dashboard/approvals/{}", approval_id);
+    let buttons: Vec<_> = [
+        ("accept", "Accept"),
+        ("accept_raw", "Accept raw"),
+        ("mock", "Mock"),
+        ("fail", "Fail"),
+    ]
+    .into_iter()
+    .map(|(decision, label)| {
+        view! {
+            <form method="POST" action={decide_action.clone()}>
+                <input type="hidden" name="decision" value={decision}/>
+                <button type="submit">{label}</button>
+            </form>
+            " "
+        }
+    })
+    .collect();
+    buttons
+}
+
+/// Every pending approval across sessions, oldest first. `session_names`
+/// maps session ids to names; unknown sessions are shown by id.
+pub fn render_all_approvals_view(
+    pending: &[PendingApprovalInfo],
+    session_names: &HashMap<String, String>,
+    approval_timeout_secs: u64,
+) -> String {
+    let rows: Vec<_> = pending
+        .iter()
+        .map(|approval| {
+            let session_href = format!(
+                "/_dashboard/sessions/{}/tool-intercept/approvals",
+                approval.session_id
+            );
+            let session_name = session_names
+                .get(&approval.session_id)
+                .cloned()
+                .unwrap_or_else(|| approval.session_id.clone());
+            let tool_lines: Vec<_> = approval
+                .tools
+                .iter()
+                .map(|tool| {
+                    let decision = format_tool_decision(tool)
+                        .map(|decision| format!(" (decided: {})", decision))
+                        .unwrap_or_default();
+                    view! {
+                        <div>
+                            <code>{tool.name.clone()}</code>
+                            " "
+                            {tool.input_summary.clone()}
+                            <em>{decision}</em>
+                        </div>
+                    }
+                })
+                .collect();
+            let waiting = format!("{}s / {}s", approval.waiting_secs, approval_timeout_secs);
+            view! {
+                <tr>
+                    <td><a href={session_href}>{session_name}</a></td>
+                    <td>{tool_lines}</td>
+                    <td>{waiting}</td>
+                    <td>{render_overview_decision_buttons(&approval.approval_id)}</td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let content = view! {
+        <meta http-equiv="refresh" content="5" />
+        <h2>"Pending Approvals"</h2>
+        <p>"Approval rounds waiting in every session. Buttons here decide all calls of a round; open the session to decide calls one by one or accept with edits. Undecided rounds fail when they reach the approval timeout."</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No pending approvals."</p> })
+        } else {
+            Either::Right(view! {
+                <p>{format!("{} pending", rows.len())}</p>
+                <table>
+                    <tr>
+                        <th>"Session"</th>
+                        <th>"Tool calls"</th>
+                        <th>"Waiting"</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Gateway Proxy - Pending Approvals".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("Pending Approvals"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
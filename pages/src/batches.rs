@@ -0,0 +1,94 @@
+use common::models::{MessageBatch, MessageBatchItem, Session};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+fn render_batch_items_table(batch: &MessageBatch, items: &[MessageBatchItem]) -> impl IntoView {
+    let session_id = batch.session_id;
+    let item_rows: Vec<_> = items
+        .iter()
+        .map(|item| {
+            let href = format!(
+                "/_dashboard/sessions/{}/requests/{}",
+                session_id, item.request_id
+            );
+            let result_type = item
+                .result_type
+                .clone()
+                .unwrap_or_else(|| "pending".to_string());
+            view! {
+                <tr>
+                    <td><a href={href}>{item.custom_id.clone()}</a></td>
+                    <td>{result_type}</td>
+                </tr>
+            }
+        })
+        .collect();
+    view! {
+        <table>
+            <tr>
+                <th>"Custom ID"</th>
+                <th>"Result"</th>
+            </tr>
+            {item_rows}
+        </table>
+    }
+}
+
+fn render_batch_section(batch: &MessageBatch, items: &[MessageBatchItem]) -> impl IntoView {
+    let create_href = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        batch.session_id, batch.request_id
+    );
+    let summary = format!(
+        "{} items, status: {}, created {}",
+        items.len(),
+        batch.processing_status,
+        batch.created_at
+    );
+    let items_table = render_batch_items_table(batch, items);
+    view! {
+        <h3><a href={create_href}>{batch.upstream_batch_id.clone()}</a></h3>
+        <p>{summary}</p>
+        {items_table}
+    }
+}
+
+pub fn render_batches_view(
+    session: &Session,
+    batches: &[(MessageBatch, Vec<MessageBatchItem>)],
+) -> String {
+    let batch_sections: Vec<_> = batches
+        .iter()
+        .map(|(batch, items)| render_batch_section(batch, items))
+        .collect();
+
+    let content = view! {
+        <h2>"Message Batches"</h2>
+        <p>"Batches created through this session. Unfinished batches are polled every minute and their results are attached to the item requests once they end."</p>
+        {if batch_sections.is_empty() {
+            Either::Left(view! {
+                <p>"No batches yet."</p>
+            })
+        } else {
+            Either::Right(batch_sections)
+        }}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Batches", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session.id),
+            ),
+            Breadcrumb::current("Batches"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,109 @@
+use common::models::{HeldRequestInfo, Session};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+fn render_held_request_row(
+    session_id: &str,
+    breakpoints_url: &str,
+    held_request: &HeldRequestInfo,
+) -> impl IntoView {
+    let request_url = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        session_id, held_request.request_id
+    );
+    let forward_action = format!("{}/{}/forward", breakpoints_url, held_request.request_id);
+    let reject_action = format!("{}/{}/reject", breakpoints_url, held_request.request_id);
+    let body_json = serde_json::to_string_pretty(&held_request.body).unwrap_or_default();
+    let request_id = held_request.request_id.clone();
+    let waiting = format!("{}s", held_request.waiting_secs);
+
+    view! {
+        <tr>
+            <td><a href={request_url}>{request_id}</a></td>
+            <td>{waiting}</td>
+            <td>
+                <form method="POST" action={forward_action}>
+                    <textarea name="body" rows="20" cols="80">{body_json}</textarea>
+                    <br/>
+                    <button type="submit">"Forward"</button>
+                </form>
+                " "
+                <form method="POST" action={reject_action}>
+                    <button type="submit">"Reject"</button>
+                </form>
+            </td>
+        </tr>
+    }
+}
+
+pub fn render_breakpoints_view(session: &Session, held_requests: &[HeldRequestInfo]) -> String {
+    let session_id = session.id.to_string();
+    let breakpoints_url = format!("/_dashboard/sessions/{}/breakpoints", session_id);
+    let disable_action = format!("{}/clear", breakpoints_url);
+    let held_count = if held_requests.is_empty() {
+        "No held requests.".to_string()
+    } else {
+        format!("{} held", held_requests.len())
+    };
+    let held_request_rows: Vec<_> = held_requests
+        .iter()
+        .map(|held_request| render_held_request_row(&session_id, &breakpoints_url, held_request))
+        .collect();
+
+    let content = view! {
+        <h2>"Breakpoints"</h2>
+        {if session.breakpoint_enabled {
+            Either::Left(view! {
+                <p>
+                    "Breakpoint mode is "
+                    <strong>"enabled"</strong>
+                    ". Incoming requests are held until they are forwarded or rejected. Disabling forwards the held requests unchanged."
+                    " "
+                    <form method="POST" action={disable_action}>
+                        <button type="submit">"Disable"</button>
+                    </form>
+                </p>
+            })
+        } else {
+            Either::Right(view! {
+                <p>
+                    "Breakpoint mode is disabled."
+                    " "
+                    <form method="POST" action={breakpoints_url.clone()}>
+                        <button type="submit">"Enable"</button>
+                    </form>
+                </p>
+            })
+        }}
+        <h3>"Held Requests"</h3>
+        <p>
+            {held_count}
+            " "
+            <small>"Requests not decided within the approval timeout are forwarded unchanged. Reload to refresh."</small>
+        </p>
+        <table>
+            <thead>
+                <tr><th>"Request"</th><th>"Waiting"</th><th>"Body"</th></tr>
+            </thead>
+            {held_request_rows}
+        </table>
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Breakpoints", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session_id),
+            ),
+            Breadcrumb::current("Breakpoints"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
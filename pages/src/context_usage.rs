@@ -0,0 +1,188 @@
+//! Estimated prompt tokens by component: a stacked bar for one request and a
+//! chart of a session's requests, to show what fills the context window.
+
+use common::models::{ProxyRequest, Session};
+use common::tokens::{estimate_context_breakdown, ContextBreakdown, HeuristicTokenizer};
+use leptos::{either::Either, prelude::*};
+use serde_json::Value;
+use templates::{Breadcrumb, NavLink, Page};
+
+/// Tallest column in the session chart, in pixels.
+const CHART_HEIGHT_PX: f64 = 160.0;
+
+/// (label, CSS class, tokens) of each component, in stacking order.
+fn list_context_components(
+    context_breakdown: &ContextBreakdown,
+) -> [(&'static str, &'static str, i64); 4] {
+    [
+        ("System", "context-system", context_breakdown.system),
+        ("Tools", "context-tools", context_breakdown.tools),
+        ("Messages", "context-messages", context_breakdown.messages),
+        (
+            "Tool Results",
+            "context-tool-results",
+            context_breakdown.tool_results,
+        ),
+    ]
+}
+
+/// Estimate a recorded request's context breakdown, or `None` when it has no
+/// messages (e.g. a non-Messages endpoint).
+pub fn estimate_request_context(req: &ProxyRequest) -> Option<ContextBreakdown> {
+    let parse_json = |json: &Option<String>| {
+        json.as_deref()
+            .and_then(|json| serde_json::from_str::<Value>(json).ok())
+    };
+    let messages = parse_json(&req.messages_json)?;
+    Some(estimate_context_breakdown(
+        &HeuristicTokenizer,
+        parse_json(&req.system_json).as_ref(),
+        parse_json(&req.tools_json).as_ref(),
+        Some(&messages),
+    ))
+}
+
+fn compute_percent(token_count: i64, total: i64) -> f64 {
+    if total > 0 {
+        token_count as f64 * 100.0 / total as f64
+    } else {
+        0.0
+    }
+}
+
+/// A bar split by component, with a legend of token counts and shares.
+pub fn render_context_bar(context_breakdown: &ContextBreakdown) -> impl IntoView {
+    let total = context_breakdown.total();
+    let components = list_context_components(context_breakdown);
+    let segments: Vec<_> = components
+        .iter()
+        .filter(|(_, _, token_count)| *token_count > 0)
+        .map(|&(label, class, token_count)| {
+            let style = format!("width: {:.2}%", compute_percent(token_count, total));
+            let title = format!("{}: {} tokens", label, token_count);
+            view! { <div class={class} style={style} title={title}></div> }
+        })
+        .collect();
+    let legend: Vec<_> = components
+        .iter()
+        .map(|&(label, class, token_count)| {
+            let swatch_class = format!("context-swatch {}", class);
+            let text = format!(
+                "{} {} ({:.1}%)",
+                label,
+                token_count,
+                compute_percent(token_count, total)
+            );
+            view! { <span><span class={swatch_class}></span>{text}" "</span> }
+        })
+        .collect();
+    let total_text = format!("~{} tokens", total);
+    view! {
+        <div class="context-bar">{segments}</div>
+        <div>{legend}{total_text}</div>
+    }
+}
+
+/// One chart column per request, scaled to the largest request, linking to
+/// the request.
+fn render_context_column(
+    session: &Session,
+    req: &ProxyRequest,
+    context_breakdown: &ContextBreakdown,
+    max_total: i64,
+) -> impl IntoView {
+    let segments: Vec<_> = list_context_components(context_breakdown)
+        .into_iter()
+        .filter(|(_, _, token_count)| *token_count > 0)
+        .map(|(_, class, token_count)| {
+            let height = token_count as f64 * CHART_HEIGHT_PX / max_total as f64;
+            let style = format!("height: {:.1}px", height);
+            view! { <div class={class} style={style}></div> }
+        })
+        .collect();
+    let href = format!("/_dashboard/sessions/{}/requests/{}", session.id, req.id);
+    let title = format!(
+        "#{} {}: ~{} tokens",
+        req.id,
+        req.created_at.get(11..19).unwrap_or(&req.created_at),
+        context_breakdown.total()
+    );
+    view! {
+        <a class="context-column" href={href} title={title}>{segments}</a>
+    }
+}
+
+/// The context usage page: each request's estimated breakdown, oldest first.
+pub fn render_context_usage_view(session: &Session, requests: &[ProxyRequest]) -> String {
+    let estimates: Vec<_> = requests
+        .iter()
+        .filter_map(|req| Some((req, estimate_request_context(req)?)))
+        .collect();
+    let max_total = estimates
+        .iter()
+        .map(|(_, context_breakdown)| context_breakdown.total())
+        .max()
+        .unwrap_or(0);
+
+    let content = if max_total == 0 {
+        Either::Left(view! { <p>"No Messages requests recorded yet."</p> })
+    } else {
+        let columns: Vec<_> = estimates
+            .iter()
+            .map(|(req, context_breakdown)| {
+                render_context_column(session, req, context_breakdown, max_total)
+            })
+            .collect();
+        let latest = estimates
+            .last()
+            .map(|(_, context_breakdown)| *context_breakdown);
+        let summary = format!(
+            "Estimated prompt tokens of the last {} requests, oldest first. The latest request:",
+            estimates.len()
+        );
+        Either::Right(view! {
+            <div class="context-chart">{columns}</div>
+            <p>{summary}</p>
+            {latest.map(|context_breakdown| render_context_bar(&context_breakdown))}
+        })
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Context Usage", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session.id),
+            ),
+            Breadcrumb::current("Context Usage"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content: view! { <h2>"Context Usage"</h2> {content} },
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_context_bar_shows_component_shares() {
+        let context_breakdown = ContextBreakdown {
+            system: 25,
+            tools: 25,
+            messages: 50,
+            tool_results: 0,
+        };
+        let html = render_context_bar(&context_breakdown).to_html();
+        assert!(html.contains("width: 50.00%"));
+        assert!(html.contains("Messages 50 (50.0%)"));
+        assert!(html.contains("Tool Results 0 (0.0%)"));
+        assert!(!html.contains("title=\"Tool Results"));
+        assert!(html.contains("~100 tokens"));
+    }
+}
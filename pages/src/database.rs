@@ -0,0 +1,194 @@
+use common::models::{BodyCompressionStats, DatabaseSnapshot, DatabaseStats};
+use leptos::{either::Either, prelude::*};
+use std::path::Path;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+use crate::detail::format_byte_count;
+
+const DATABASE_URL: &str = "/_dashboard/admin/db";
+
+fn render_integrity_results(integrity_results: &[String]) -> impl IntoView {
+    if integrity_results == ["ok"] {
+        Either::Left(view! { <p>"Integrity check passed."</p> })
+    } else {
+        let problems = integrity_results
+            .iter()
+            .map(|problem| view! { <li><code>{problem.clone()}</code></li> })
+            .collect::<Vec<_>>();
+        Either::Right(view! {
+            <p>{format!("Integrity check found {} problems:", integrity_results.len())}</p>
+            <ul>{problems}</ul>
+        })
+    }
+}
+
+/// The snapshots in the backup directory, each with a Restore button, and a
+/// button to take one now.
+fn render_backups_section(
+    backup_dir: Option<&Path>,
+    snapshots: &[DatabaseSnapshot],
+) -> impl IntoView {
+    let Some(backup_dir) = backup_dir else {
+        return Either::Left(view! {
+            <p>"Backups are off. Set " <code>"backup_dir"</code> " in the config file or pass " <code>"--backup-dir"</code> " to snapshot the database periodically."</p>
+        });
+    };
+    let backup_action = format!("{}/backups", DATABASE_URL);
+    let restore_action = format!("{}/backups/restore", DATABASE_URL);
+    let rows = snapshots
+        .iter()
+        .map(|snapshot| {
+            view! {
+                <tr>
+                    <td><code>{snapshot.file_name.clone()}</code></td>
+                    <td>{format_byte_count(snapshot.size_bytes as usize)}</td>
+                    <td>
+                        <form method="POST" action={restore_action.clone()}>
+                            <input type="hidden" name="file_name" value={snapshot.file_name.clone()}/>
+                            <button type="submit">"Restore"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    let summary = format!(
+        "{} snapshots in {}. Restoring replaces every table with the snapshot's rows; the current contents are snapshotted first.",
+        snapshots.len(),
+        backup_dir.display()
+    );
+    Either::Right(view! {
+        <p>{summary}</p>
+        <form method="POST" action={backup_action}>
+            <button type="submit">"Back Up Now"</button>
+        </form>
+        {(!rows.is_empty()).then(|| view! {
+            <table>
+                <tr>
+                    <th>"Snapshot"</th>
+                    <th>"Size"</th>
+                    <th></th>
+                </tr>
+                {rows}
+            </table>
+        })}
+    })
+}
+
+fn build_database_info_rows(database_stats: &DatabaseStats) -> Vec<InfoRow> {
+    let free_bytes = (database_stats.freelist_count * database_stats.page_size).max(0) as usize;
+    vec![
+        InfoRow::new(
+            "File Size",
+            &format_byte_count(database_stats.file_size_bytes as usize),
+        ),
+        InfoRow::new(
+            "WAL Size",
+            &format_byte_count(database_stats.wal_size_bytes as usize),
+        ),
+        InfoRow::new(
+            "Pages",
+            &format!(
+                "{} of {} bytes",
+                database_stats.page_count, database_stats.page_size
+            ),
+        ),
+        InfoRow::new(
+            "Free Pages",
+            &format!(
+                "{} ({} reclaimable by VACUUM)",
+                database_stats.freelist_count,
+                format_byte_count(free_bytes)
+            ),
+        ),
+        InfoRow::new(
+            "Compressed Bodies",
+            &format_body_compression(&database_stats.body_compression),
+        ),
+    ]
+}
+
+fn format_body_compression(body_compression: &BodyCompressionStats) -> String {
+    if body_compression.compressed_count == 0 {
+        return "None".to_string();
+    }
+    format!(
+        "{} ({} stored, {} before compression)",
+        body_compression.compressed_count,
+        format_byte_count(body_compression.compressed_bytes.max(0) as usize),
+        format_byte_count(body_compression.original_bytes.max(0) as usize)
+    )
+}
+
+/// Database size and row counts, with VACUUM, body compression and
+/// integrity check buttons and the backup snapshots. `integrity_results` holds the result lines of a
+/// check just run.
+pub fn render_database_view(
+    database_stats: &DatabaseStats,
+    integrity_results: Option<&[String]>,
+    backup_dir: Option<&Path>,
+    snapshots: &[DatabaseSnapshot],
+) -> String {
+    let info_rows = build_database_info_rows(database_stats);
+
+    let rows = database_stats
+        .table_row_counts
+        .iter()
+        .map(|(name, count)| {
+            view! {
+                <tr>
+                    <td><code>{name.clone()}</code></td>
+                    <td>{count.to_string()}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    let integrity_section = integrity_results.map(render_integrity_results);
+    let vacuum_action = format!("{}/vacuum", DATABASE_URL);
+    let compress_action = format!("{}/compress", DATABASE_URL);
+    let compress_summary = format!(
+        "{} request and response bodies over the compression threshold are stored as text, e.g. logged before compression was turned on. Compressing them frees pages for VACUUM to give back.",
+        database_stats.body_compression.pending_count
+    );
+    let backups_section = render_backups_section(backup_dir, snapshots);
+
+    let content = view! {
+        <h2>"Tables"</h2>
+        <table>
+            <tr>
+                <th>"Table"</th>
+                <th>"Rows"</th>
+            </tr>
+            {rows}
+        </table>
+        <h2>"Maintenance"</h2>
+        <p>"VACUUM rewrites the database file without its free pages. It blocks other writes while it runs."</p>
+        <form method="POST" action={vacuum_action}>
+            <button type="submit">"Vacuum"</button>
+        </form>
+        <p>{compress_summary}</p>
+        <form method="POST" action={compress_action}>
+            <button type="submit">"Compress Stored Bodies"</button>
+        </form>
+        <form method="GET" action={DATABASE_URL}>
+            <input type="hidden" name="integrity_check" value="1"/>
+            <button type="submit">"Run Integrity Check"</button>
+        </form>
+        {integrity_section}
+        <h2>"Backups"</h2>
+        {backups_section}
+    };
+
+    Page {
+        title: "Gateway Proxy - Database".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("Database"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,106 @@
+use ::common::models::RequestNote;
+use leptos::{either::Either, prelude::*};
+
+/// Notes and stars on a request, and the dashboard user viewing it.
+pub struct RequestAnnotations<'a> {
+    pub notes: &'a [RequestNote],
+    pub star_authors: &'a [String],
+    pub author: &'a str,
+}
+
+/// Who starred the request, e.g. `"alice, bob"`, or `"—"` when nobody has.
+pub(super) fn format_star_authors(star_authors: &[String]) -> String {
+    if star_authors.is_empty() {
+        return "—".to_string();
+    }
+    star_authors.join(", ")
+}
+
+fn render_star_form(base: &str, annotations: &RequestAnnotations<'_>) -> impl IntoView {
+    let is_starred = annotations
+        .star_authors
+        .iter()
+        .any(|star_author| star_author == annotations.author);
+    let button_label = if is_starred { "Unstar" } else { "Star" };
+    view! {
+        <form method="POST" action={format!("{}/star", base)}>
+            <input type="submit" value={button_label}/>
+        </form>
+    }
+}
+
+fn render_request_notes(notes: &[RequestNote]) -> impl IntoView {
+    if notes.is_empty() {
+        return Either::Left(view! { <p>"No notes yet."</p> });
+    }
+    let rows = notes
+        .iter()
+        .map(|note| {
+            view! {
+                <tr>
+                    <td>{note.author.clone()}</td>
+                    <td>{note.created_at.clone()}</td>
+                    <td><pre>{note.note.clone()}</pre></td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Author"</th>
+                <th>"Time"</th>
+                <th>"Note"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// The star button, the notes left on the request and a form to add one as
+/// the current dashboard user.
+pub(super) fn render_request_annotations(
+    base: &str,
+    annotations: &RequestAnnotations<'_>,
+) -> impl IntoView {
+    view! {
+        {render_star_form(base, annotations)}
+        <h2>"Notes"</h2>
+        {render_request_notes(annotations.notes)}
+        <form method="POST" action={format!("{}/notes", base)}>
+            <textarea name="note" rows="3" cols="80"></textarea>
+            <br/>
+            <input type="submit" value={format!("Add Note as {}", annotations.author)}/>
+            " "
+            <a href="/_dashboard/user">"Change user"</a>
+        </form>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_request_annotations_shows_notes_and_own_star() {
+        let notes = vec![RequestNote {
+            id: "00000000-0000-0000-0000-000000000003".parse().unwrap(),
+            request_id: "00000000-0000-0000-0000-000000000002".parse().unwrap(),
+            author: "alice".to_string(),
+            note: "Check the cache hit".to_string(),
+            created_at: "2026-01-01 00:00:00".to_string(),
+        }];
+        let star_authors = vec!["alice".to_string()];
+        let annotations = RequestAnnotations {
+            notes: &notes,
+            star_authors: &star_authors,
+            author: "alice",
+        };
+        let html = render_request_annotations("/base", &annotations).to_html();
+        assert!(html.contains(r#"value="Unstar""#));
+        assert!(html.contains("Check the cache hit"));
+        assert!(html.contains(r#"value="Add Note as alice""#));
+        assert_eq!(format_star_authors(&star_authors), "alice");
+        assert_eq!(format_star_authors(&[]), "—");
+    }
+}
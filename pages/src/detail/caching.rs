@@ -0,0 +1,374 @@
+use common::models::{ProxyRequest, Session};
+use common::sse::{collect_usage, load_response_events};
+use leptos::{either::Either, prelude::*};
+use serde_json::Value;
+use templates::Page;
+
+use super::{build_detail_breadcrumbs, build_detail_nav_links, RequestNeighbors};
+
+const USAGE_KEYS: &[&str] = &[
+    "input_tokens",
+    "cache_creation_input_tokens",
+    "cache_read_input_tokens",
+    "output_tokens",
+];
+
+/// One cacheable unit of the prompt, in the order the API builds the cache
+/// prefix: tools, then system, then messages.
+struct PrefixSegment {
+    label: String,
+    /// The segment with `cache_control` removed, so moving a breakpoint does
+    /// not count as a content change.
+    content: Value,
+    cache_control: Option<Value>,
+}
+
+fn build_prefix_segment(label: String, value: &Value, role: Option<&str>) -> PrefixSegment {
+    let mut content = value.clone();
+    let cache_control = content
+        .as_object_mut()
+        .and_then(|object| object.remove("cache_control"));
+    let content = match role {
+        Some(role) => serde_json::json!({"role": role, "block": content}),
+        None => content,
+    };
+    PrefixSegment {
+        label,
+        content,
+        cache_control,
+    }
+}
+
+fn build_tool_segments(tools_json: &str) -> Vec<PrefixSegment> {
+    let tools: Vec<Value> = serde_json::from_str(tools_json).unwrap_or_default();
+    tools
+        .iter()
+        .enumerate()
+        .map(|(index, tool)| {
+            let name = tool.get("name").and_then(|field| field.as_str()).unwrap_or("");
+            build_prefix_segment(format!("tools[{}] {}", index, name), tool, None)
+        })
+        .collect()
+}
+
+fn build_system_segments(system_json: &str) -> Vec<PrefixSegment> {
+    match serde_json::from_str::<Value>(system_json) {
+        Ok(Value::Array(blocks)) => blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| build_prefix_segment(format!("system[{}]", index), block, None))
+            .collect(),
+        Ok(system) => vec![build_prefix_segment("system".to_string(), &system, None)],
+        Err(_) => vec![],
+    }
+}
+
+fn build_message_segments(messages_json: &str) -> Vec<PrefixSegment> {
+    let messages: Vec<Value> = serde_json::from_str(messages_json).unwrap_or_default();
+    let mut segments = Vec::new();
+    for (message_index, message) in messages.iter().enumerate() {
+        let role = message.get("role").and_then(|field| field.as_str()).unwrap_or("");
+        match message.get("content") {
+            Some(Value::Array(blocks)) => {
+                segments.extend(blocks.iter().enumerate().map(|(block_index, block)| {
+                    let label = format!(
+                        "messages[{}].content[{}] ({})",
+                        message_index, block_index, role
+                    );
+                    build_prefix_segment(label, block, Some(role))
+                }));
+            }
+            Some(content) => {
+                let label = format!("messages[{}] ({})", message_index, role);
+                segments.push(build_prefix_segment(label, content, Some(role)));
+            }
+            None => {}
+        }
+    }
+    segments
+}
+
+fn build_prefix_segments(req: &ProxyRequest) -> Vec<PrefixSegment> {
+    let mut segments = Vec::new();
+    if let Some(ref tools_json) = req.tools_json {
+        segments.extend(build_tool_segments(tools_json));
+    }
+    if let Some(ref system_json) = req.system_json {
+        segments.extend(build_system_segments(system_json));
+    }
+    if let Some(ref messages_json) = req.messages_json {
+        segments.extend(build_message_segments(messages_json));
+    }
+    segments
+}
+
+/// Count the `cache_control` breakpoints set in a request.
+pub fn count_cache_breakpoints(req: &ProxyRequest) -> usize {
+    build_prefix_segments(req)
+        .iter()
+        .filter(|segment| segment.cache_control.is_some())
+        .count()
+}
+
+/// How the current request's cache prefix relates to the previous request's.
+#[derive(Debug, PartialEq)]
+enum PrefixComparison {
+    /// The previous request set no breakpoint, so there was nothing to reuse.
+    NoPreviousBreakpoint,
+    /// Everything up to the previous request's last breakpoint is unchanged.
+    Preserved,
+    /// The prefix diverged at `divergence_index`, before the previous
+    /// request's last breakpoint, so its cache entry cannot be read.
+    Busted { divergence_index: usize },
+}
+
+fn find_first_divergence(current: &[PrefixSegment], previous: &[PrefixSegment]) -> Option<usize> {
+    let shared_len = current.len().min(previous.len());
+    (0..shared_len)
+        .find(|&index| current[index].content != previous[index].content)
+        .or((current.len() < previous.len()).then_some(shared_len))
+}
+
+fn compare_prefixes(current: &[PrefixSegment], previous: &[PrefixSegment]) -> PrefixComparison {
+    let Some(last_breakpoint) = previous
+        .iter()
+        .rposition(|segment| segment.cache_control.is_some())
+    else {
+        return PrefixComparison::NoPreviousBreakpoint;
+    };
+    match find_first_divergence(current, previous) {
+        Some(divergence_index) if divergence_index <= last_breakpoint => {
+            PrefixComparison::Busted { divergence_index }
+        }
+        _ => PrefixComparison::Preserved,
+    }
+}
+
+/// Collect token usage from the `message_start` and `message_delta` events of
+/// the response, rebuilt from the body for non-streaming responses.
+fn extract_response_usage(req: &ProxyRequest) -> serde_json::Map<String, Value> {
+    let events = load_response_events(
+        req.response_events_json.as_deref(),
+        req.response_body.as_deref(),
+    );
+    collect_usage(&events)
+}
+
+fn render_breakpoints_section(segments: &[PrefixSegment]) -> impl IntoView {
+    let rows: Vec<_> = segments
+        .iter()
+        .filter_map(|segment| {
+            let cache_control = segment.cache_control.as_ref()?;
+            let ttl = cache_control
+                .get("ttl")
+                .and_then(|field| field.as_str())
+                .unwrap_or("5m")
+                .to_string();
+            Some(view! {
+                <tr>
+                    <td>{segment.label.clone()}</td>
+                    <td>{ttl}</td>
+                </tr>
+            })
+        })
+        .collect();
+    view! {
+        <h3>"Breakpoints"</h3>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No cache_control breakpoints."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr><th>"Location"</th><th>"TTL"</th></tr>
+                    {rows}
+                </table>
+            })
+        }}
+    }
+}
+
+fn render_usage_section(usage: &serde_json::Map<String, Value>) -> impl IntoView {
+    let rows: Vec<_> = USAGE_KEYS
+        .iter()
+        .filter_map(|key| {
+            let tokens = usage.get(*key)?.as_i64()?.to_string();
+            Some(view! { <tr><td>{*key}</td><td>{tokens}</td></tr> })
+        })
+        .collect();
+    let token_count = |key: &str| usage.get(key).and_then(|field| field.as_i64()).unwrap_or(0);
+    let cache_read_tokens = token_count("cache_read_input_tokens");
+    let total_input_tokens = token_count("input_tokens")
+        + token_count("cache_creation_input_tokens")
+        + cache_read_tokens;
+    let hit_rate = (total_input_tokens > 0).then(|| {
+        format!(
+            "Cache read: {:.1}% of input tokens",
+            cache_read_tokens as f64 * 100.0 / total_input_tokens as f64
+        )
+    });
+    view! {
+        <h3>"Usage"</h3>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No usage in response."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr><th>"Field"</th><th>"Tokens"</th></tr>
+                    {rows}
+                </table>
+                <p>{hit_rate}</p>
+            })
+        }}
+    }
+}
+
+fn render_prefix_section(
+    segments: &[PrefixSegment],
+    previous_req: Option<&ProxyRequest>,
+) -> impl IntoView {
+    let Some(previous_req) = previous_req else {
+        return Either::Left(view! {
+            <h3>"Prefix vs Previous Request"</h3>
+            <p>"No earlier request to the same model in this session."</p>
+        });
+    };
+    let previous_href = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        previous_req.session_id, previous_req.id
+    );
+    let previous_segments = build_prefix_segments(previous_req);
+    let verdict = match compare_prefixes(segments, &previous_segments) {
+        PrefixComparison::NoPreviousBreakpoint => {
+            "The previous request set no breakpoints, so there was no cache entry to reuse."
+                .to_string()
+        }
+        PrefixComparison::Preserved => {
+            "Prefix unchanged up to the previous request's last breakpoint.".to_string()
+        }
+        PrefixComparison::Busted { divergence_index } => {
+            let label = segments
+                .get(divergence_index)
+                .or_else(|| previous_segments.get(divergence_index))
+                .map(|segment| segment.label.as_str())
+                .unwrap_or("");
+            format!(
+                "Cache busted: prefix changed at {} before the previous request's last breakpoint.",
+                label
+            )
+        }
+    };
+    Either::Right(view! {
+        <h3>"Prefix vs Previous Request"</h3>
+        <p>"Compared with "<a href={previous_href}>{format!("Request #{}", previous_req.id)}</a></p>
+        <p><strong>{verdict}</strong></p>
+    })
+}
+
+pub fn render_request_caching_view(
+    req: &ProxyRequest,
+    session: &Session,
+    previous_req: Option<&ProxyRequest>,
+    neighbors: &RequestNeighbors<'_>,
+) -> String {
+    let segments = build_prefix_segments(req);
+    let usage = extract_response_usage(req);
+
+    let content = view! {
+        <h2>"Caching"</h2>
+        {render_breakpoints_section(&segments)}
+        {render_usage_section(&usage)}
+        {render_prefix_section(&segments, previous_req)}
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Request #{} - Caching",
+            session.name, req.id
+        ),
+        breadcrumbs: build_detail_breadcrumbs(session, req, Some("Caching")),
+        nav_links: build_detail_nav_links(req, neighbors),
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_segments(messages: Value) -> Vec<PrefixSegment> {
+        build_message_segments(&messages.to_string())
+    }
+
+    #[test]
+    fn cache_control_is_not_part_of_segment_content() {
+        let with_breakpoint = build_segments(serde_json::json!([
+            {"role": "user", "content": [{"type": "text", "text": "hi", "cache_control": {"type": "ephemeral"}}]}
+        ]));
+        let without_breakpoint = build_segments(serde_json::json!([
+            {"role": "user", "content": [{"type": "text", "text": "hi"}]}
+        ]));
+        assert!(with_breakpoint[0].cache_control.is_some());
+        assert_eq!(with_breakpoint[0].content, without_breakpoint[0].content);
+    }
+
+    #[test]
+    fn appended_messages_preserve_prefix() {
+        let previous = build_segments(serde_json::json!([
+            {"role": "user", "content": [{"type": "text", "text": "a", "cache_control": {"type": "ephemeral"}}]}
+        ]));
+        let current = build_segments(serde_json::json!([
+            {"role": "user", "content": [{"type": "text", "text": "a"}]},
+            {"role": "assistant", "content": "b"}
+        ]));
+        assert_eq!(compare_prefixes(&current, &previous), PrefixComparison::Preserved);
+    }
+
+    #[test]
+    fn edit_before_breakpoint_busts_cache() {
+        let previous = build_segments(serde_json::json!([
+            {"role": "user", "content": "a"},
+            {"role": "user", "content": [{"type": "text", "text": "b", "cache_control": {"type": "ephemeral"}}]}
+        ]));
+        let current = build_segments(serde_json::json!([
+            {"role": "user", "content": "changed"},
+            {"role": "user", "content": [{"type": "text", "text": "b"}]}
+        ]));
+        assert_eq!(
+            compare_prefixes(&current, &previous),
+            PrefixComparison::Busted { divergence_index: 0 }
+        );
+    }
+
+    #[test]
+    fn edit_after_breakpoint_preserves_prefix() {
+        let previous = build_segments(serde_json::json!([
+            {"role": "user", "content": [{"type": "text", "text": "a", "cache_control": {"type": "ephemeral"}}]},
+            {"role": "user", "content": "b"}
+        ]));
+        let current = build_segments(serde_json::json!([
+            {"role": "user", "content": [{"type": "text", "text": "a"}]},
+            {"role": "user", "content": "changed"}
+        ]));
+        assert_eq!(compare_prefixes(&current, &previous), PrefixComparison::Preserved);
+    }
+
+    #[test]
+    fn previous_without_breakpoint_has_nothing_to_reuse() {
+        let previous = build_segments(serde_json::json!([{"role": "user", "content": "a"}]));
+        let current = build_segments(serde_json::json!([{"role": "user", "content": "b"}]));
+        assert_eq!(
+            compare_prefixes(&current, &previous),
+            PrefixComparison::NoPreviousBreakpoint
+        );
+    }
+
+    #[test]
+    fn system_string_is_one_segment() {
+        let segments = build_system_segments("\"You are helpful.\"");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].label, "system");
+    }
+}
@@ -1,12 +1,24 @@
-use common::models::ProxyRequest;
+use common::models::{AppliedFilter, ProxyRequest};
+use common::sse::load_response_events;
+use common::truncate::{
+    list_truncation_profile_names, truncate_with_profile, DEFAULT_TRUNCATION_PROFILE,
+};
 use leptos::prelude::*;
 use std::collections::HashMap;
-use templates::Subpage;
+use templates::{copy_target_button, Subpage};
 
 use crate::collapsible_block;
 
 use super::{
-    messages::render_messages, sse::render_response_sse, system::render_system, tools::render_tools,
+    highlight::render_highlighted_json_text,
+    image::format_byte_count,
+    markdown::{is_markdown_enabled, render_markdown_toggle, toggle_markdown_param},
+    messages::{render_messages, MessagesViewOptions},
+    raw_bytes::render_response_raw_bytes,
+    rendered::render_response_message,
+    sse::{render_response_sse, SseViewOptions},
+    system::render_system,
+    tools::render_tools,
 };
 
 pub fn render_kv_table(json_str: &str) -> AnyView {
@@ -76,6 +88,37 @@ pub fn render_response_headers(req: &ProxyRequest) -> AnyView {
     }
 }
 
+/// What the filter profile removed or renamed before the request was forwarded.
+fn render_applied_filters(applied_filters_json: &str) -> AnyView {
+    let Ok(applied_filters) = serde_json::from_str::<Vec<AppliedFilter>>(applied_filters_json)
+    else {
+        let s = applied_filters_json.to_string();
+        return view! { <pre>{s}</pre> }.into_any();
+    };
+
+    let rows: Vec<AnyView> = applied_filters
+        .into_iter()
+        .map(|applied_filter| {
+            view! {
+                <tr>
+                    <td>{applied_filter.kind}</td>
+                    <td><code>{applied_filter.rule}</code></td>
+                    <td>{applied_filter.detail}</td>
+                </tr>
+            }
+            .into_any()
+        })
+        .collect();
+
+    view! {
+        <table>
+            <tr><th>"Kind"</th><th>"Rule"</th><th>"Change"</th></tr>
+            {rows}
+        </table>
+    }
+    .into_any()
+}
+
 pub fn count_json_array(json: Option<&str>) -> Option<usize> {
     json.and_then(|string| serde_json::from_str::<serde_json::Value>(string).ok())
         .and_then(|value| value.as_array().map(|array| array.len()))
@@ -96,6 +139,19 @@ pub fn count_json_items(json: Option<&str>) -> Option<usize> {
         })
 }
 
+/// Count the recorded SSE events, or the events rebuilt from a JSON message
+/// response when nothing was streamed.
+fn count_response_events(req: &ProxyRequest) -> Option<usize> {
+    req.response_events_json.as_ref()?;
+    Some(
+        load_response_events(
+            req.response_events_json.as_deref(),
+            req.response_body.as_deref(),
+        )
+        .len(),
+    )
+}
+
 /// Build the standard subpage definitions for a request detail view.
 /// When `include_webfetch` is true, includes the WebFetch Intercept subpage.
 pub fn build_request_subpage_defs(
@@ -139,14 +195,28 @@ pub fn build_request_subpage_defs(
                 .unwrap_or_default(),
         ),
         ("full_json", "Full JSON", true, String::new()),
+        (
+            "applied_filters",
+            "Filters Applied",
+            req.applied_filters_json.is_some(),
+            count_json_array(req.applied_filters_json.as_deref())
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+        ),
         (
             "response_sse",
             "Response SSE",
             req.response_events_json.is_some(),
-            count_json_array(req.response_events_json.as_deref())
+            count_response_events(req)
                 .map(|count| count.to_string())
                 .unwrap_or_default(),
         ),
+        (
+            "response_rendered",
+            "Response (rendered)",
+            has_response,
+            String::new(),
+        ),
         (
             "headers",
             "Request Headers",
@@ -163,6 +233,15 @@ pub fn build_request_subpage_defs(
                 .map(|count| count.to_string())
                 .unwrap_or_default(),
         ),
+        (
+            "response_raw",
+            "Raw Bytes",
+            req.response_body.is_some(),
+            req.response_body
+                .as_ref()
+                .map(|response_body| format_byte_count(response_body.len()))
+                .unwrap_or_default(),
+        ),
     ];
 
     if include_webfetch {
@@ -183,6 +262,61 @@ pub fn build_request_subpage_defs(
         .collect()
 }
 
+/// The request body for the Full JSON page: whole or truncated with the
+/// chosen profile, pretty-printed since the body is stored as the client sent
+/// it, or the stored truncated body when the full one wasn't kept.
+fn build_full_json_text(req: &ProxyRequest, truncate: bool, profile_name: Option<&str>) -> String {
+    let body_json = req.body_json.as_deref();
+    let body =
+        body_json.and_then(|json_str| serde_json::from_str::<serde_json::Value>(json_str).ok());
+    if !truncate {
+        return match body {
+            Some(body) => serde_json::to_string_pretty(&body).unwrap_or_default(),
+            None => body_json.or(req.note.as_deref()).unwrap_or("").to_string(),
+        };
+    }
+    match body {
+        Some(body) => serde_json::to_string_pretty(&truncate_with_profile(&body, profile_name))
+            .unwrap_or_default(),
+        None => req
+            .truncated_json
+            .as_deref()
+            .or(req.note.as_deref())
+            .unwrap_or("")
+            .to_string(),
+    }
+}
+
+/// Selector for the truncation profile, shown when more than the default
+/// profile is configured.
+fn render_truncation_profile_form(base_url: &str, profile_name: Option<&str>) -> Option<AnyView> {
+    let profile_names = list_truncation_profile_names();
+    if profile_names.len() < 2 {
+        return None;
+    }
+    let current_name = profile_name.unwrap_or(DEFAULT_TRUNCATION_PROFILE);
+    let profile_options: Vec<_> = profile_names
+        .into_iter()
+        .map(|name| {
+            let selected = name == current_name;
+            let label = name.clone();
+            view! { <option value={name} selected={selected}>{label}</option> }
+        })
+        .collect();
+    Some(
+        view! {
+            <form method="GET" action={format!("{}/full_json", base_url)}>
+                <input type="hidden" name="truncate" value="on"/>
+                <label>"Truncation profile "</label>
+                <select name="profile">{profile_options}</select>
+                " "
+                <input type="submit" value="Apply"/>
+            </form>
+        }
+        .into_any(),
+    )
+}
+
 /// Rendered detail page content — controls, main content, and total count views.
 pub struct DetailPageContent {
     pub controls_view: AnyView,
@@ -204,6 +338,8 @@ pub fn render_detail_page_content(
         .get("order")
         .cloned()
         .unwrap_or_else(|| "desc".to_string());
+    let render_markdown = is_markdown_enabled(query);
+    let markdown_param = if render_markdown { "on" } else { "off" };
 
     let mut controls_view: AnyView = ().into_any();
 
@@ -211,7 +347,19 @@ pub fn render_detail_page_content(
         "messages" => {
             if let Some(ref json_str) = req.messages_json {
                 let toggle_order = if order == "desc" { "asc" } else { "desc" };
-                let toggle_href = format!("{}/messages?order={}", base_url, toggle_order);
+                let toggle_href = format!(
+                    "{}/messages?order={}&markdown={}",
+                    base_url, toggle_order, markdown_param
+                );
+                let markdown_toggle = render_markdown_toggle(
+                    format!(
+                        "{}/messages?order={}&markdown={}",
+                        base_url,
+                        order,
+                        toggle_markdown_param(render_markdown)
+                    ),
+                    render_markdown,
+                );
                 let showing = if order == "desc" {
                     "newest first"
                 } else {
@@ -223,10 +371,22 @@ pub fn render_detail_page_content(
                     "newest first"
                 };
                 controls_view = view! {
-                    <div>"Showing: "{showing}" | "<a href={toggle_href}>"Switch to "{switch_to}</a></div>
+                    <div>"Showing: "{showing}" | "<a href={toggle_href}>"Switch to "{switch_to}</a>" | "{markdown_toggle}</div>
                 }
                 .into_any();
-                render_messages(json_str, &order, keep_tool_pairs)
+                let blocks_url = format!(
+                    "/_dashboard/sessions/{}/requests/{}/blocks",
+                    req.session_id, req.id
+                );
+                render_messages(
+                    json_str,
+                    &MessagesViewOptions {
+                        order: &order,
+                        keep_tool_pairs,
+                        render_markdown,
+                        blocks_url: Some(&blocks_url),
+                    },
+                )
             } else {
                 view! { <p>"No messages."</p> }.into_any()
             }
@@ -251,39 +411,53 @@ pub fn render_detail_page_content(
             render_kv_table(h)
         }
         "full_json" => {
-            let json = if truncate {
-                req.truncated_json
-                    .as_deref()
-                    .or(req.note.as_deref())
-                    .unwrap_or("")
-            } else {
-                req.body_json
-                    .as_deref()
-                    .or(req.note.as_deref())
-                    .unwrap_or("")
-            };
+            let profile_name = query.get("profile").map(|field| field.as_str());
+            let json = build_full_json_text(req, truncate, profile_name);
             let toggle_href = format!(
-                "{}/full_json?truncate={}",
+                "{}/full_json?truncate={}&profile={}",
                 base_url,
-                if truncate { "off" } else { "on" }
+                if truncate { "off" } else { "on" },
+                profile_name.unwrap_or(DEFAULT_TRUNCATION_PROFILE)
             );
             let toggle_label = if truncate {
                 "Show full strings"
             } else {
                 "Show truncated"
             };
+            let copy_view = copy_target_button("full-json", "Copy Full JSON");
+            let profile_form =
+                truncate.then(|| render_truncation_profile_form(base_url, profile_name));
             controls_view = view! {
-                <p><a href={toggle_href}>{toggle_label}</a></p>
+                <p><a href={toggle_href}>{toggle_label}</a>" "{copy_view}</p>
+                {profile_form}
             }
             .into_any();
-            let json = json.to_string();
-            view! {
-                <textarea readonly rows="30" cols="80" wrap="off">{json}</textarea>
-            }
-            .into_any()
+            let json_view = render_highlighted_json_text(&json);
+            view! { <div id="full-json">{json_view}</div> }.into_any()
         }
+        "applied_filters" => req
+            .applied_filters_json
+            .as_deref()
+            .map(render_applied_filters)
+            .unwrap_or_else(|| view! { <p>"No filters applied."</p> }.into_any()),
         "response_headers" => render_response_headers(req),
-        "response_sse" => render_response_sse(req),
+        "response_sse" => {
+            let sse_base_url = format!("{}/response_sse", base_url);
+            render_response_sse(req, &SseViewOptions::from_query(&sse_base_url, query))
+        }
+        "response_raw" => render_response_raw_bytes(req, base_url, truncate),
+        "response_rendered" => {
+            let markdown_toggle = render_markdown_toggle(
+                format!(
+                    "{}/response_rendered?markdown={}",
+                    base_url,
+                    toggle_markdown_param(render_markdown)
+                ),
+                render_markdown,
+            );
+            controls_view = view! { <p>{markdown_toggle}</p> }.into_any();
+            render_response_message(req, render_markdown)
+        }
         _ => view! { <p>"Unknown tab"</p> }.into_any(),
     };
 
@@ -293,8 +467,9 @@ pub fn render_detail_page_content(
         "tools" => count_json_items(req.tools_json.as_deref()),
         "params" => count_json_items(req.params_json.as_deref()),
         "headers" => count_json_items(req.headers_json.as_deref()),
+        "applied_filters" => count_json_items(req.applied_filters_json.as_deref()),
         "response_headers" => count_json_items(req.response_headers_json.as_deref()),
-        "response_sse" => count_json_items(req.response_events_json.as_deref()),
+        "response_sse" => count_response_events(req),
         _ => None,
     };
     let total_view: AnyView = total_count
@@ -390,4 +565,32 @@ mod tests {
     fn count_json_items_number_returns_none() {
         assert_eq!(count_json_items(Some("42")), None);
     }
+
+    // --- applied filters tests ---
+
+    #[test]
+    fn applied_filters_subpage_only_when_recorded() {
+        let build_request = |applied_filters_json: Option<&str>| -> ProxyRequest {
+            serde_json::from_value(serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000002",
+                "session_id": "00000000-0000-0000-0000-000000000001",
+                "method": "POST",
+                "path": "/v1/messages",
+                "created_at": "2026-01-01 00:00:00",
+                "updated_at": "2026-01-01 00:00:00",
+                "applied_filters_json": applied_filters_json,
+            }))
+            .unwrap()
+        };
+        let find_subpage = |req: &ProxyRequest| {
+            build_request_subpage_defs(req, "/base", false)
+                .into_iter()
+                .find(|subpage| subpage.href == "/base/applied_filters")
+        };
+        assert!(find_subpage(&build_request(None)).is_none());
+        let applied_filters_json = r#"[{"kind":"tool","rule":"WebSearch","detail":"Removed"}]"#;
+        let subpage = find_subpage(&build_request(Some(applied_filters_json))).unwrap();
+        assert_eq!(subpage.label, "Filters Applied");
+        assert_eq!(subpage.count, "1");
+    }
 }
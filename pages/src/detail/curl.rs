@@ -0,0 +1,49 @@
+use common::models::{ProxyRequest, Session};
+use serde_json::Value;
+
+/// Headers recomputed by curl itself and left out of the exported command.
+const SKIPPED_CURL_HEADERS: &[&str] = &["host", "content-length", "connection", "accept-encoding"];
+
+fn quote_shell(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Build a curl command that replays the request directly against the
+/// session's target, with the recorded headers and body.
+pub fn build_curl_command(req: &ProxyRequest, session: &Session) -> String {
+    let url = format!("{}{}", session.target_url.trim_end_matches('/'), req.path);
+    let mut parts = vec![format!("curl -X {} {}", req.method, quote_shell(&url))];
+
+    let headers: Value = req
+        .headers_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or(Value::Null);
+    if let Some(headers) = headers.as_object() {
+        for (name, value) in headers {
+            if SKIPPED_CURL_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                continue;
+            }
+            let value = value.as_str().unwrap_or("");
+            parts.push(format!(
+                "-H {}",
+                quote_shell(&format!("{}: {}", name, value))
+            ));
+        }
+    }
+    if let Some(body) = req.body_json.as_deref().filter(|body| !body.is_empty()) {
+        parts.push(format!("--data-binary {}", quote_shell(body)));
+    }
+    parts.join(" \\\n  ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_shell_escapes_single_quotes() {
+        assert_eq!(quote_shell("it's"), r"'it'\''s'");
+        assert_eq!(quote_shell("plain"), "'plain'");
+    }
+}
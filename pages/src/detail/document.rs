@@ -0,0 +1,162 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use leptos::{either::Either, prelude::*};
+use regex::bytes::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+use super::image::format_byte_count;
+
+/// Matches PDF page objects (`/Type /Page`) but not the page tree (`/Type /Pages`).
+static PDF_PAGE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/Type\s*/Page([^s]|$)").unwrap());
+
+/// The raw bytes carried by a content block's `source`.
+pub struct DecodedBlockSource {
+    pub media_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Decode the `source` of a document or image block: base64 data is decoded,
+/// plain text data is returned as-is. URL and file sources carry no bytes.
+pub fn decode_block_source(block: &Value) -> Result<DecodedBlockSource, String> {
+    let source = block.get("source").ok_or("missing source")?;
+    let source_type = source
+        .get("type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("");
+    let media_type = source
+        .get("media_type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let data = source.get("data").and_then(|field| field.as_str());
+    let bytes = match (source_type, data) {
+        ("base64", Some(data)) => STANDARD
+            .decode(data)
+            .map_err(|e| format!("invalid base64: {}", e))?,
+        ("text", Some(data)) => data.as_bytes().to_vec(),
+        _ => return Err(format!("{} source has no inline data", source_type)),
+    };
+    Ok(DecodedBlockSource { media_type, bytes })
+}
+
+/// Count the pages of a PDF by scanning for page objects. Returns `None` for
+/// non-PDF data or when no page objects are visible (e.g. compressed object streams).
+pub fn count_pdf_pages(bytes: &[u8]) -> Option<usize> {
+    if !bytes.starts_with(b"%PDF") {
+        return None;
+    }
+    let page_count = PDF_PAGE_REGEX.find_iter(bytes).count();
+    (page_count > 0).then_some(page_count)
+}
+
+/// Find a content block by its position among all message content blocks,
+/// counted across messages in request order.
+pub fn find_message_block(messages_json: &str, block_index: usize) -> Option<Value> {
+    let messages: Vec<Value> = serde_json::from_str(messages_json).ok()?;
+    messages
+        .iter()
+        .filter_map(|message| message.get("content").and_then(|field| field.as_array()))
+        .flatten()
+        .nth(block_index)
+        .cloned()
+}
+
+/// File extension for a downloaded block, based on its media type.
+pub fn get_block_file_extension(media_type: &str) -> &str {
+    match media_type {
+        "application/pdf" => "pdf",
+        "text/plain" => "txt",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        _ => "bin",
+    }
+}
+
+/// Render a document block as a summary card with media type, size, page
+/// count and a download link, instead of the raw base64 data.
+pub fn render_document_card(block: &Value, download_href: Option<String>) -> AnyView {
+    let title = block
+        .get("title")
+        .and_then(|field| field.as_str())
+        .map(|title| title.to_string());
+    let title_view = title.map(|title| view! { <div><strong>{title}</strong></div> });
+
+    let (summary, download_href) = match decode_block_source(block) {
+        Ok(decoded_source) => {
+            let mut summary = format!(
+                "{}, {}",
+                decoded_source.media_type,
+                format_byte_count(decoded_source.bytes.len())
+            );
+            if let Some(page_count) = count_pdf_pages(&decoded_source.bytes) {
+                let unit = if page_count == 1 { "page" } else { "pages" };
+                summary.push_str(&format!(", {} {}", page_count, unit));
+            }
+            (summary, download_href)
+        }
+        Err(reason) => (reason, None),
+    };
+
+    let download_view = match download_href {
+        Some(href) => Either::Left(view! { " | " <a href={href}>"Download"</a> }),
+        None => Either::Right(()),
+    };
+    view! {
+        {title_view}
+        <div>{summary}{download_view}</div>
+    }
+    .into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_pages_in_pdf() {
+        let pdf = b"%PDF-1.4\n1 0 obj << /Type /Pages /Count 2 >>\n2 0 obj << /Type /Page >>\n3 0 obj << /Type/Page>>";
+        assert_eq!(count_pdf_pages(pdf), Some(2));
+        assert_eq!(count_pdf_pages(b"not a pdf"), None);
+    }
+
+    #[test]
+    fn decode_base64_and_text_sources() {
+        let base64_block = serde_json::json!({
+            "type": "document",
+            "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERg=="}
+        });
+        let decoded_source = decode_block_source(&base64_block).unwrap();
+        assert_eq!(decoded_source.bytes, b"%PDF");
+        assert_eq!(decoded_source.media_type, "application/pdf");
+
+        let text_block = serde_json::json!({
+            "type": "document",
+            "source": {"type": "text", "media_type": "text/plain", "data": "hello"}
+        });
+        assert_eq!(decode_block_source(&text_block).unwrap().bytes, b"hello");
+
+        let url_block = serde_json::json!({
+            "type": "document",
+            "source": {"type": "url", "url": "https://example.com/a.pdf"}
+        });
+        assert!(decode_block_source(&url_block).is_err());
+    }
+
+    #[test]
+    fn find_block_across_messages() {
+        let messages_json = r#"[
+            {"role": "user", "content": [{"type": "text", "text": "a"}]},
+            {"role": "assistant", "content": "plain"},
+            {"role": "user", "content": [{"type": "text", "text": "b"}, {"type": "document"}]}
+        ]"#;
+        assert_eq!(find_message_block(messages_json, 1).unwrap()["text"], "b");
+        assert_eq!(
+            find_message_block(messages_json, 2).unwrap()["type"],
+            "document"
+        );
+        assert!(find_message_block(messages_json, 3).is_none());
+    }
+}
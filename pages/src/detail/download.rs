@@ -0,0 +1,87 @@
+use common::models::ProxyRequest;
+use leptos::prelude::*;
+
+/// A stored request column offered for download as a file.
+pub struct RequestDownload<'a> {
+    pub file_name: &'static str,
+    pub label: &'static str,
+    pub content_type: &'static str,
+    pub content: &'a str,
+}
+
+/// List the downloadable columns of a request, skipping ones that were not recorded.
+/// Streamed responses are offered as `.sse.txt`, others as `.json`.
+pub fn list_request_downloads(req: &ProxyRequest) -> Vec<RequestDownload<'_>> {
+    let (response_file_name, response_content_type) = if req.response_events_json.is_some() {
+        ("response.sse.txt", "text/plain; charset=utf-8")
+    } else {
+        ("response.json", "application/json")
+    };
+    let candidates = [
+        (
+            "request.json",
+            "Request",
+            "application/json",
+            req.body_json.as_deref(),
+        ),
+        (
+            response_file_name,
+            "Response",
+            response_content_type,
+            req.response_body.as_deref(),
+        ),
+        (
+            "request_headers.json",
+            "Request Headers",
+            "application/json",
+            req.headers_json.as_deref(),
+        ),
+        (
+            "response_headers.json",
+            "Response Headers",
+            "application/json",
+            req.response_headers_json.as_deref(),
+        ),
+    ];
+    candidates
+        .into_iter()
+        .filter_map(|(file_name, label, content_type, content)| {
+            content.map(|content| RequestDownload {
+                file_name,
+                label,
+                content_type,
+                content,
+            })
+        })
+        .collect()
+}
+
+/// Find the download for `file_name`, if the request has it.
+pub fn find_request_download<'a>(
+    req: &'a ProxyRequest,
+    file_name: &str,
+) -> Option<RequestDownload<'a>> {
+    list_request_downloads(req)
+        .into_iter()
+        .find(|request_download| request_download.file_name == file_name)
+}
+
+/// Render the download links shown on the request detail page.
+pub fn render_download_links(req: &ProxyRequest, base_url: &str) -> AnyView {
+    let links: Vec<AnyView> = list_request_downloads(req)
+        .into_iter()
+        .enumerate()
+        .map(|(index, request_download)| {
+            let separator = if index == 0 { "" } else { " | " };
+            let href = format!("{}/download/{}", base_url, request_download.file_name);
+            let label = request_download.label;
+            view! { {separator}<a href={href}>{label}</a> }.into_any()
+        })
+        .collect();
+    let fixture_link = req.body_json.is_some().then(|| {
+        let separator = if links.is_empty() { "" } else { " | " };
+        let href = format!("{}/fixture", base_url);
+        view! { {separator}<a href={href}>"Test Fixture"</a> }
+    });
+    view! { <span>{links}{fixture_link}</span> }.into_any()
+}
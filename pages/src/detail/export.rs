@@ -0,0 +1,154 @@
+use ::common::models::{ProxyRequest, Session};
+use leptos::prelude::*;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use templates::{InfoRow, Page};
+
+use super::common::render_detail_page_content;
+use super::get_page_label;
+use super::sse::{render_response_sse, SseViewOptions};
+
+/// Subpages included in an export, in order.
+const EXPORT_PAGES: &[&str] = &[
+    "messages",
+    "system",
+    "tools",
+    "params",
+    "headers",
+    "response_headers",
+    "response_sse",
+    "response_rendered",
+];
+
+static HREF_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\shref="([^"]*)""#).unwrap());
+static FORM_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<form\b.*?</form>").unwrap());
+
+/// Filter patterns and tool-pair trimming of the session's profile, so the
+/// export marks the same rows as the dashboard does.
+pub struct ExportFilters<'a> {
+    pub system_filters: &'a [String],
+    pub tool_filters: &'a [String],
+    pub keep_tool_pairs: i64,
+}
+
+/// Drop forms and every link that would leave the file, keeping in-page
+/// anchors and inline `data:` downloads. Link text stays in place.
+fn strip_live_links(html: &str) -> String {
+    let html = FORM_REGEX.replace_all(html, "");
+    HREF_REGEX
+        .replace_all(&html, |captures: &regex::Captures| {
+            let href = &captures[1];
+            if href.starts_with('#') || href.starts_with("data:") {
+                captures[0].to_string()
+            } else {
+                String::new()
+            }
+        })
+        .into_owned()
+}
+
+fn render_export_section(
+    req: &ProxyRequest,
+    base: &str,
+    page: &'static str,
+    export_filters: &ExportFilters<'_>,
+) -> AnyView {
+    let content_view = if page == "response_sse" {
+        let sse_base_url = format!("{}/response_sse", base);
+        render_response_sse(req, &SseViewOptions::unpaged(&sse_base_url))
+    } else {
+        let filters = match page {
+            "system" => export_filters.system_filters,
+            "tools" => export_filters.tool_filters,
+            _ => &[],
+        };
+        let detail_page_content = render_detail_page_content(
+            req,
+            base,
+            page,
+            &HashMap::new(),
+            filters,
+            export_filters.keep_tool_pairs,
+        );
+        view! {
+            {detail_page_content.total_view}
+            {detail_page_content.content_view}
+        }
+        .into_any()
+    };
+    view! {
+        <h2 id={page}>{get_page_label(page)}</h2>
+        {content_view}
+    }
+    .into_any()
+}
+
+/// The whole request on one self-contained page: every subpage rendered in
+/// full, with the styles inlined and no links back to the dashboard.
+pub fn render_request_export(
+    req: &ProxyRequest,
+    session: &Session,
+    export_filters: &ExportFilters<'_>,
+) -> String {
+    let base = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        req.session_id, req.id
+    );
+    let contents: Vec<_> = EXPORT_PAGES
+        .iter()
+        .map(|page| {
+            let href = format!("#{}", page);
+            view! { <a href={href}>{get_page_label(page)}</a>" " }
+        })
+        .collect();
+    let sections: Vec<_> = EXPORT_PAGES
+        .iter()
+        .map(|page| render_export_section(req, &base, page, export_filters))
+        .collect();
+    let content = view! {
+        <p>{contents}</p>
+        {sections}
+    };
+
+    let response_status = req
+        .response_status
+        .map(|status| status.to_string())
+        .unwrap_or_default();
+    let html = Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Request #{} - Export",
+            session.name, req.id
+        ),
+        breadcrumbs: vec![],
+        nav_links: vec![],
+        info_rows: vec![
+            InfoRow::new("Session", &session.name),
+            InfoRow::new("Request", &req.id.to_string()),
+            InfoRow::new("Method", &req.method),
+            InfoRow::new("Path", &req.path),
+            InfoRow::new("Model", req.model.as_deref().unwrap_or("")),
+            InfoRow::new("Time", &req.created_at),
+            InfoRow::new("Status", &response_status),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render();
+    strip_live_links(&html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_live_links_keeps_anchors_and_data_urls() {
+        let html = r##"<a href="/_dashboard/x">x</a><a href="#msg-1">#</a><a href="data:image/png;base64,AA" download="image">d</a><form method="POST" action="/star"><button>Star</button></form>"##;
+        assert_eq!(
+            strip_live_links(html),
+            r##"<a>x</a><a href="#msg-1">#</a><a href="data:image/png;base64,AA" download="image">d</a>"##
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use leptos::prelude::*;
+
+/// CSS classes emitted by the highlighters; the markdown sanitizer allows only these.
+pub const HIGHLIGHT_CLASSES: &[&str] = &[
+    "hl-key",
+    "hl-string",
+    "hl-number",
+    "hl-literal",
+    "hl-keyword",
+    "hl-comment",
+];
+
+const CODE_KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "default",
+    "do",
+    "elif",
+    "else",
+    "enum",
+    "except",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "fn",
+    "for",
+    "from",
+    "func",
+    "function",
+    "if",
+    "impl",
+    "import",
+    "in",
+    "interface",
+    "let",
+    "match",
+    "mod",
+    "mut",
+    "new",
+    "nil",
+    "None",
+    "null",
+    "pub",
+    "raise",
+    "return",
+    "self",
+    "static",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "trait",
+    "true",
+    "True",
+    "False",
+    "try",
+    "type",
+    "use",
+    "var",
+    "where",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Languages whose line comments start with `#` rather than `//`.
+const HASH_COMMENT_LANGUAGES: &[&str] = &[
+    "python", "py", "sh", "bash", "shell", "zsh", "ruby", "rb", "yaml", "yml", "toml",
+];
+
+fn push_escaped(html: &mut String, text: &str) {
+    for character in text.chars() {
+        match character {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            _ => html.push(character),
+        }
+    }
+}
+
+fn push_span(html: &mut String, class: &str, text: &str) {
+    html.push_str("<span class=\"");
+    html.push_str(class);
+    html.push_str("\">");
+    push_escaped(html, text);
+    html.push_str("</span>");
+}
+
+/// Length in bytes of the quoted string starting at `start`, honouring backslash escapes.
+/// Unterminated strings run to the end of the text.
+fn measure_quoted(text: &str, start: usize, quote: char) -> usize {
+    let mut escaped = false;
+    for (offset, character) in text[start + quote.len_utf8()..].char_indices() {
+        if escaped {
+            escaped = false;
+        } else if character == '\\' {
+            escaped = true;
+        } else if character == quote {
+            return quote.len_utf8() + offset + character.len_utf8();
+        }
+    }
+    text.len() - start
+}
+
+fn measure_while(text: &str, start: usize, predicate: impl Fn(char) -> bool) -> usize {
+    text[start..]
+        .char_indices()
+        .find(|(_, character)| !predicate(*character))
+        .map(|(offset, _)| offset)
+        .unwrap_or(text.len() - start)
+}
+
+fn is_followed_by_colon(text: &str, end: usize) -> bool {
+    text[end..].trim_start().starts_with(':')
+}
+
+/// Highlight JSON text. Works token by token, so truncated or otherwise
+/// invalid JSON is still highlighted as far as it goes.
+pub fn highlight_json(text: &str) -> String {
+    let mut html = String::with_capacity(text.len() * 2);
+    let mut position = 0;
+    while let Some(character) = text[position..].chars().next() {
+        let length = match character {
+            '"' => {
+                let length = measure_quoted(text, position, '"');
+                let class = if is_followed_by_colon(text, position + length) {
+                    "hl-key"
+                } else {
+                    "hl-string"
+                };
+                push_span(&mut html, class, &text[position..position + length]);
+                length
+            }
+            '-' | '0'..='9' => {
+                let length = measure_while(text, position, |character| {
+                    character.is_ascii_digit() || matches!(character, '-' | '+' | '.' | 'e' | 'E')
+                });
+                push_span(&mut html, "hl-number", &text[position..position + length]);
+                length
+            }
+            'a'..='z' => {
+                let length =
+                    measure_while(text, position, |character| character.is_ascii_alphabetic());
+                let word = &text[position..position + length];
+                if matches!(word, "true" | "false" | "null") {
+                    push_span(&mut html, "hl-literal", word);
+                } else {
+                    push_escaped(&mut html, word);
+                }
+                length
+            }
+            _ => {
+                push_escaped(&mut html, &text[position..position + character.len_utf8()]);
+                character.len_utf8()
+            }
+        };
+        position += length;
+    }
+    html
+}
+
+/// Highlight a fenced code block. JSON gets the JSON highlighter; anything
+/// else gets a generic pass over strings, comments, numbers and keywords.
+pub fn highlight_code(code: &str, language: &str) -> String {
+    let language = language.trim().to_ascii_lowercase();
+    if language == "json" {
+        return highlight_json(code);
+    }
+    let comment_prefix = if HASH_COMMENT_LANGUAGES.contains(&language.as_str()) {
+        "#"
+    } else {
+        "//"
+    };
+
+    let mut html = String::with_capacity(code.len() * 2);
+    let mut position = 0;
+    while let Some(character) = code[position..].chars().next() {
+        let length = if code[position..].starts_with(comment_prefix) {
+            let length = measure_while(code, position, |character| character != '\n');
+            push_span(&mut html, "hl-comment", &code[position..position + length]);
+            length
+        } else if matches!(character, '"' | '\'' | '`') {
+            let length = measure_quoted(code, position, character);
+            push_span(&mut html, "hl-string", &code[position..position + length]);
+            length
+        } else if character.is_ascii_digit() {
+            let length = measure_while(code, position, |character| {
+                character.is_ascii_alphanumeric() || matches!(character, '.' | '_')
+            });
+            push_span(&mut html, "hl-number", &code[position..position + length]);
+            length
+        } else if character.is_alphabetic() || character == '_' {
+            let length = measure_while(code, position, |character| {
+                character.is_alphanumeric() || character == '_'
+            });
+            let word = &code[position..position + length];
+            if CODE_KEYWORDS.contains(&word) {
+                push_span(&mut html, "hl-keyword", word);
+            } else {
+                push_escaped(&mut html, word);
+            }
+            length
+        } else {
+            push_escaped(&mut html, &code[position..position + character.len_utf8()]);
+            character.len_utf8()
+        };
+        position += length;
+    }
+    html
+}
+
+/// Render a JSON value pretty-printed and highlighted.
+pub fn render_highlighted_json(value: &serde_json::Value) -> AnyView {
+    let html = highlight_json(&serde_json::to_string_pretty(value).unwrap_or_default());
+    view! { <pre class="code" inner_html={html}></pre> }.into_any()
+}
+
+/// Render JSON text highlighted, pretty-printing it first when it parses.
+pub fn render_highlighted_json_text(text: &str) -> AnyView {
+    let pretty = serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| text.to_string());
+    let html = highlight_json(&pretty);
+    view! { <pre class="code" inner_html={html}></pre> }.into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_keys_strings_numbers_literals() {
+        let html = highlight_json(r#"{"a": "b", "n": -1.5e3, "t": true, "z": null}"#);
+        assert!(html.contains(r#"<span class="hl-key">&quot;a&quot;</span>"#));
+        assert!(html.contains(r#"<span class="hl-string">&quot;b&quot;</span>"#));
+        assert!(html.contains(r#"<span class="hl-number">-1.5e3</span>"#));
+        assert!(html.contains(r#"<span class="hl-literal">true</span>"#));
+        assert!(html.contains(r#"<span class="hl-literal">null</span>"#));
+    }
+
+    #[test]
+    fn json_escapes_html_and_handles_truncation() {
+        let html = highlight_json(r#"{"x": "<script>\"more"#);
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn code_keywords_strings_comments() {
+        let html = highlight_code("let s = \"hi\"; // note\n", "rust");
+        assert!(html.contains(r#"<span class="hl-keyword">let</span>"#));
+        assert!(html.contains(r#"<span class="hl-string">&quot;hi&quot;</span>"#));
+        assert!(html.contains(r#"<span class="hl-comment">// note</span>"#));
+    }
+
+    #[test]
+    fn code_hash_comments_for_python() {
+        let html = highlight_code("x = 1  # one", "python");
+        assert!(html.contains(r#"<span class="hl-comment"># one</span>"#));
+        assert!(html.contains(r#"<span class="hl-number">1</span>"#));
+    }
+}
@@ -0,0 +1,133 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use leptos::{either::Either, prelude::*};
+
+/// Images larger than this are not inlined; only the download link is shown.
+const IMAGE_PREVIEW_MAX_BYTES: usize = 5 * 1024 * 1024;
+
+/// The decoded facts about a base64 image block needed to preview it.
+#[derive(Debug, PartialEq)]
+pub struct ImagePreview {
+    pub media_type: String,
+    pub byte_count: usize,
+    pub data_url: String,
+}
+
+/// Decode the base64 source of an image block. Returns `Err` with a short
+/// reason when the block has no base64 data or it does not decode.
+pub fn decode_image_preview(block: &serde_json::Value) -> Result<ImagePreview, String> {
+    let source = block.get("source").ok_or("missing source")?;
+    let source_type = source
+        .get("type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("");
+    if source_type != "base64" {
+        return Err(format!("{} source", source_type));
+    }
+    let media_type = source
+        .get("media_type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("")
+        .to_string();
+    if !media_type.starts_with("image/") {
+        return Err(format!("unsupported media type {:?}", media_type));
+    }
+    let data = source
+        .get("data")
+        .and_then(|field| field.as_str())
+        .ok_or("missing data")?;
+    let bytes = STANDARD
+        .decode(data)
+        .map_err(|e| format!("invalid base64: {}", e))?;
+    Ok(ImagePreview {
+        data_url: format!("data:{};base64,{}", media_type, data),
+        media_type,
+        byte_count: bytes.len(),
+    })
+}
+
+pub fn format_byte_count(byte_count: usize) -> String {
+    if byte_count < 1024 {
+        format!("{} B", byte_count)
+    } else if byte_count < 1024 * 1024 {
+        format!("{:.1} KB", byte_count as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", byte_count as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn render_image_source_url(block: &serde_json::Value) -> Option<AnyView> {
+    let url = block
+        .get("source")
+        .filter(|source| source.get("type").and_then(|field| field.as_str()) == Some("url"))
+        .and_then(|source| source.get("url"))
+        .and_then(|field| field.as_str())?
+        .to_string();
+    let href = url.clone();
+    Some(view! { <a href={href}>{url}</a> }.into_any())
+}
+
+/// Render an image content block as a lazy-loaded thumbnail with its size
+/// and a download link instead of the raw base64 string.
+pub fn render_image_preview(block: &serde_json::Value) -> AnyView {
+    if let Some(url_view) = render_image_source_url(block) {
+        return url_view;
+    }
+    let image_preview = match decode_image_preview(block) {
+        Ok(image_preview) => image_preview,
+        Err(reason) => return view! { <div>"Image not shown: "{reason}</div> }.into_any(),
+    };
+
+    let size_info = format!(
+        "{}, {}",
+        image_preview.media_type,
+        format_byte_count(image_preview.byte_count)
+    );
+    let download_href = image_preview.data_url.clone();
+    let thumbnail = if image_preview.byte_count <= IMAGE_PREVIEW_MAX_BYTES {
+        Either::Left(view! {
+            <img class="image-preview" loading="lazy" src={image_preview.data_url} />
+        })
+    } else {
+        Either::Right(view! { <div>"Too large to preview."</div> })
+    };
+    view! {
+        {thumbnail}
+        <div>{size_info}" | "<a href={download_href} download="image">"Download"</a></div>
+    }
+    .into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_base64_png() {
+        let block = serde_json::json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": "image/png", "data": "aGVsbG8="}
+        });
+        let image_preview = decode_image_preview(&block).unwrap();
+        assert_eq!(image_preview.byte_count, 5);
+        assert_eq!(image_preview.data_url, "data:image/png;base64,aGVsbG8=");
+    }
+
+    #[test]
+    fn decode_rejects_invalid_base64_and_media_type() {
+        let invalid_data = serde_json::json!({
+            "source": {"type": "base64", "media_type": "image/png", "data": "!!!"}
+        });
+        assert!(decode_image_preview(&invalid_data).is_err());
+        let invalid_media = serde_json::json!({
+            "source": {"type": "base64", "media_type": "text/html", "data": "aGVsbG8="}
+        });
+        assert!(decode_image_preview(&invalid_media).is_err());
+    }
+
+    #[test]
+    fn byte_count_formatting() {
+        assert_eq!(format_byte_count(512), "512 B");
+        assert_eq!(format_byte_count(2048), "2.0 KB");
+        assert_eq!(format_byte_count(3 * 1024 * 1024), "3.0 MB");
+    }
+}
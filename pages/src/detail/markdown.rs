@@ -0,0 +1,100 @@
+use leptos::prelude::*;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::collections::HashMap;
+
+use super::highlight::{highlight_code, HIGHLIGHT_CLASSES};
+
+/// Replace fenced and indented code blocks with syntax-highlighted HTML.
+fn highlight_code_blocks<'a>(events: impl Iterator<Item = Event<'a>>) -> Vec<Event<'a>> {
+    let mut highlighted_events = Vec::new();
+    let mut code_block: Option<(String, String)> = None;
+    for event in events {
+        match (event, &mut code_block) {
+            (Event::Start(Tag::CodeBlock(kind)), _) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code_block = Some((language, String::new()));
+            }
+            (Event::Text(text), Some((_, code))) => code.push_str(&text),
+            (Event::End(TagEnd::CodeBlock), Some((language, code))) => {
+                let html = format!("<pre><code>{}</code></pre>", highlight_code(code, language));
+                highlighted_events.push(Event::Html(CowStr::from(html)));
+                code_block = None;
+            }
+            (event, _) => highlighted_events.push(event),
+        }
+    }
+    highlighted_events
+}
+
+/// Convert markdown to HTML and strip anything unsafe (scripts, event
+/// handlers, javascript: links) before it is embedded in the dashboard.
+pub fn render_markdown_html(text: &str) -> String {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(text, options);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, highlight_code_blocks(parser).into_iter());
+    ammonia::Builder::default()
+        .add_allowed_classes("span", HIGHLIGHT_CLASSES)
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+pub fn render_markdown_block(text: &str) -> AnyView {
+    let html = render_markdown_html(text);
+    view! { <div class="markdown" inner_html={html}></div> }.into_any()
+}
+
+/// Whether the `markdown=on` query toggle is set for the current view.
+pub fn is_markdown_enabled(query: &HashMap<String, String>) -> bool {
+    query.get("markdown").map(|field| field.as_str()) == Some("on")
+}
+
+/// Link that switches the current view between raw text and rendered markdown.
+pub fn render_markdown_toggle(toggle_href: String, render_markdown: bool) -> AnyView {
+    let toggle_label = if render_markdown {
+        "Show raw text"
+    } else {
+        "Render markdown"
+    };
+    view! { <a href={toggle_href}>{toggle_label}</a> }.into_any()
+}
+
+/// Value of the `markdown` query parameter that flips the current setting.
+pub fn toggle_markdown_param(render_markdown: bool) -> &'static str {
+    if render_markdown {
+        "off"
+    } else {
+        "on"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_renders_code_fence_list_and_table() {
+        let text =
+            "- one\n- two\n\n```rust\nfn main() {}\n```\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+        let html = render_markdown_html(text);
+        assert!(html.contains("<li>one</li>"));
+        assert!(html.contains("<pre><code"));
+        assert!(html.contains("<table>"));
+        assert!(html.contains(r#"<span class="hl-keyword">fn</span>"#));
+    }
+
+    #[test]
+    fn markdown_strips_scripts_and_handlers() {
+        let text = "hi <script>alert(1)</script> <img src=x onerror=\"alert(1)\"> [x](javascript:alert(1))";
+        let html = render_markdown_html(text);
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("javascript:"));
+    }
+}
@@ -3,6 +3,28 @@ use std::collections::HashSet;
 use leptos::{either::Either, prelude::*};
 
 use crate::collapsible_block;
+use templates::copy_button;
+
+use super::{
+    document::render_document_card,
+    highlight::render_highlighted_json,
+    image::render_image_preview,
+    markdown::render_markdown_block,
+    tool_views::{
+        is_code_execution_result, render_code_execution_result, render_tool_result_content,
+        render_tool_use_summary,
+    },
+};
+
+/// How a messages table is rendered.
+pub struct MessagesViewOptions<'a> {
+    pub order: &'a str,
+    pub keep_tool_pairs: i64,
+    pub render_markdown: bool,
+    /// Base URL for block downloads (`{blocks_url}/{index}/download`), when the
+    /// messages come from a stored request.
+    pub blocks_url: Option<&'a str>,
+}
 
 /// Collect tool_use IDs that should be marked as filtered (all except the last `keep` pairs).
 fn collect_filtered_tool_ids(msgs: &[serde_json::Value], keep_tool_pairs: i64) -> HashSet<String> {
@@ -29,42 +51,138 @@ fn collect_filtered_tool_ids(msgs: &[serde_json::Value], keep_tool_pairs: i64) -
     }
 }
 
-fn render_text_block(block: &serde_json::Value, role_cell: String) -> AnyView {
+/// Render message text as sanitized markdown when enabled, otherwise as raw text.
+fn render_message_text(text: &str, render_markdown: bool) -> AnyView {
+    if render_markdown {
+        render_markdown_block(text)
+    } else {
+        collapsible_block(text, "")
+    }
+}
+
+/// Cells shared by every block row: the role (first block of a message only)
+/// and the row's stable anchor id.
+struct BlockRow {
+    role_cell: AnyView,
+    anchor_id: String,
+}
+
+/// Stable anchor id of a content block, by message and block position in request order.
+fn format_block_anchor_id(message_index: usize, block_index: usize) -> String {
+    format!("msg-{}-block-{}", message_index, block_index)
+}
+
+fn render_anchor_link(anchor_id: &str) -> AnyView {
+    let href = format!("#{}", anchor_id);
+    view! { <a class="anchor-link" href={href} title="Link to this block">"#"</a> }.into_any()
+}
+
+/// Role cell for the first row of a message, carrying the message's `msg-N` anchor.
+fn render_role_cell(role: &str, message_index: usize) -> AnyView {
+    let message_anchor_id = format!("msg-{}", message_index);
+    let role = role.to_string();
+    view! { <span id={message_anchor_id}>{role}</span> }.into_any()
+}
+
+fn render_text_block(
+    block: &serde_json::Value,
+    block_row: BlockRow,
+    render_markdown: bool,
+) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
     let text = block.get("text").and_then(|field| field.as_str()).unwrap_or("");
     let cache_info = format_cache_control_label(block);
     let type_label = format!("text{}", cache_info);
-    let cb = collapsible_block(text, "");
+    let cb = render_message_text(text, render_markdown);
+    let copy_view = copy_button(text, "Copy");
     view! {
-        <tr>
+        <tr id={anchor_id}>
             <td>{role_cell}</td>
-            <td>{type_label}</td>
+            <td>{anchor_link}" "{type_label}" "{copy_view}</td>
             <td>{cb}</td>
         </tr>
     }
     .into_any()
 }
 
-fn render_thinking_block(block: &serde_json::Value, role_cell: String) -> AnyView {
+fn render_thinking_block(block: &serde_json::Value, block_row: BlockRow) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
     let text = block.get("thinking").and_then(|field| field.as_str()).unwrap_or("");
     let cache_info = format_cache_control_label(block);
     let type_label = format!("thinking{}", cache_info);
     let cb = collapsible_block(text, "");
+    let copy_view = copy_button(text, "Copy");
     view! {
-        <tr>
+        <tr id={anchor_id}>
             <td>{role_cell}</td>
-            <td>{type_label}</td>
+            <td>{anchor_link}" "{type_label}" "{copy_view}</td>
             <td>{cb}</td>
         </tr>
     }
     .into_any()
 }
 
+fn render_image_block(block: &serde_json::Value, block_row: BlockRow) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
+    let cache_info = format_cache_control_label(block);
+    let type_label = format!("image{}", cache_info);
+    let image_view = render_image_preview(block);
+    view! {
+        <tr id={anchor_id}>
+            <td>{role_cell}</td>
+            <td>{anchor_link}" "{type_label}</td>
+            <td>{image_view}</td>
+        </tr>
+    }
+    .into_any()
+}
+
+fn render_document_block(
+    block: &serde_json::Value,
+    block_row: BlockRow,
+    download_href: Option<String>,
+) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
+    let cache_info = format_cache_control_label(block);
+    let type_label = format!("document{}", cache_info);
+    let document_view = render_document_card(block, download_href);
+    view! {
+        <tr id={anchor_id}>
+            <td>{role_cell}</td>
+            <td>{anchor_link}" "{type_label}</td>
+            <td>{document_view}</td>
+        </tr>
+    }
+    .into_any()
+}
+
 fn render_tool_use_block(
     block: &serde_json::Value,
-    role_cell: String,
+    block_row: BlockRow,
     row_class: &str,
     filtered_badge: Either<impl IntoView + 'static, ()>,
 ) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
     let name = block
         .get("name")
         .and_then(|field| field.as_str())
@@ -85,13 +203,11 @@ fn render_tool_use_block(
             input
                 .iter()
                 .map(|(key, value)| {
-                    let val_str = if value.is_string() {
-                        value.as_str().unwrap_or("").to_string()
-                    } else {
-                        serde_json::to_string(value).unwrap_or_default()
+                    let cb = match value.as_str() {
+                        Some(string) => collapsible_block(string, ""),
+                        None => render_highlighted_json(value),
                     };
                     let key = key.clone();
-                    let cb = collapsible_block(&val_str, "");
                     view! {
                         <tr><td>{key}</td><td>{cb}</td></tr>
                     }
@@ -101,6 +217,7 @@ fn render_tool_use_block(
         })
         .unwrap_or_default();
 
+    let summary_view = render_tool_use_summary(block);
     let params_view = if !params_rows.is_empty() {
         Either::Left(view! {
             <table>
@@ -112,12 +229,18 @@ fn render_tool_use_block(
         Either::Right(())
     };
 
+    let input_json = block
+        .get("input")
+        .and_then(|input| serde_json::to_string_pretty(input).ok())
+        .unwrap_or_default();
+    let copy_view = copy_button(&input_json, "Copy input");
+
     let row_class = row_class.to_string();
     view! {
-        <tr class={row_class}>
+        <tr id={anchor_id} class={row_class}>
             <td>{role_cell}</td>
-            <td>{type_label}{filtered_badge}": "{name}" "{id}</td>
-            <td>{params_view}</td>
+            <td>{anchor_link}" "{type_label}{filtered_badge}": "{name}" "{id}" "{copy_view}</td>
+            <td>{summary_view}{params_view}</td>
         </tr>
     }
     .into_any()
@@ -125,10 +248,15 @@ fn render_tool_use_block(
 
 fn render_tool_result_block(
     block: &serde_json::Value,
-    role_cell: String,
+    block_row: BlockRow,
     row_class: &str,
     filtered_badge: Either<impl IntoView + 'static, ()>,
 ) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
     let tool_use_id = block
         .get("tool_use_id")
         .and_then(|field| field.as_str())
@@ -136,43 +264,84 @@ fn render_tool_result_block(
         .to_string();
     let cache_info = format_cache_control_label(block);
     let type_label = format!("tool_result{}", cache_info);
-    let result_text = if let Some(string) = block.get("content").and_then(|field| field.as_str()) {
-        string.to_string()
-    } else if let Some(arr) = block.get("content").and_then(|field| field.as_array()) {
-        arr.iter()
-            .filter_map(|element| element.get("text").and_then(|field| field.as_str()))
-            .collect::<Vec<_>>()
-            .join("\n")
-    } else {
-        String::new()
-    };
-    let cb = collapsible_block(&result_text, "");
+    let is_error = block.get("is_error").and_then(|field| field.as_bool()) == Some(true);
+    let error_label = if is_error { " (error)" } else { "" };
+    let content_view = render_tool_result_content(block.get("content"));
     let row_class = row_class.to_string();
     view! {
-        <tr class={row_class}>
+        <tr id={anchor_id} class={row_class}>
             <td>{role_cell}</td>
-            <td>{type_label}{filtered_badge}" "{tool_use_id}</td>
-            <td>{cb}</td>
+            <td>{anchor_link}" "{type_label}{error_label}{filtered_badge}" "{tool_use_id}</td>
+            <td>{content_view}</td>
+        </tr>
+    }
+    .into_any()
+}
+
+/// A server-side code execution result, e.g. `bash_code_execution_tool_result`.
+fn render_code_execution_result_block(block: &serde_json::Value, block_row: BlockRow) -> AnyView {
+    let BlockRow {
+        role_cell,
+        anchor_id,
+    } = block_row;
+    let anchor_link = render_anchor_link(&anchor_id);
+    let block_type = block
+        .get("type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("");
+    let tool_use_id = block
+        .get("tool_use_id")
+        .and_then(|field| field.as_str())
+        .unwrap_or("")
+        .to_string();
+    let type_label = format!("{}{}", block_type, format_cache_control_label(block));
+    let content_view = render_code_execution_result(block.get("content"));
+    view! {
+        <tr id={anchor_id}>
+            <td>{role_cell}</td>
+            <td>{anchor_link}" "{type_label}" "{tool_use_id}</td>
+            <td>{content_view}</td>
         </tr>
     }
     .into_any()
 }
 
-pub fn render_messages(json_str: &str, order: &str, keep_tool_pairs: i64) -> AnyView {
-    let Ok(mut msgs) = serde_json::from_str::<Vec<serde_json::Value>>(json_str) else {
+/// Index of each message's first content block among all blocks, in request order.
+fn collect_block_offsets(msgs: &[serde_json::Value]) -> Vec<usize> {
+    msgs.iter()
+        .scan(0, |offset, msg| {
+            let start = *offset;
+            *offset += msg
+                .get("content")
+                .and_then(|field| field.as_array())
+                .map_or(0, |blocks| blocks.len());
+            Some(start)
+        })
+        .collect()
+}
+
+pub fn render_messages(json_str: &str, options: &MessagesViewOptions) -> AnyView {
+    let Ok(msgs) = serde_json::from_str::<Vec<serde_json::Value>>(json_str) else {
         let s = json_str.to_string();
         return view! { <pre>{s}</pre> }.into_any();
     };
 
-    let filtered_ids = collect_filtered_tool_ids(&msgs, keep_tool_pairs);
+    let filtered_ids = collect_filtered_tool_ids(&msgs, options.keep_tool_pairs);
+    let render_markdown = options.render_markdown;
 
-    if order == "desc" {
-        msgs.reverse();
+    let mut indexed_msgs: Vec<(usize, usize, &serde_json::Value)> = collect_block_offsets(&msgs)
+        .into_iter()
+        .zip(&msgs)
+        .enumerate()
+        .map(|(message_index, (block_offset, msg))| (message_index, block_offset, msg))
+        .collect();
+    if options.order == "desc" {
+        indexed_msgs.reverse();
     }
 
-    let rows: Vec<AnyView> = msgs
-        .iter()
-        .flat_map(|msg| {
+    let rows: Vec<AnyView> = indexed_msgs
+        .into_iter()
+        .flat_map(|(message_index, block_offset, msg)| {
             let role = msg
                 .get("role")
                 .and_then(|field| field.as_str())
@@ -180,10 +349,16 @@ pub fn render_messages(json_str: &str, order: &str, keep_tool_pairs: i64) -> Any
 
             let content = &msg["content"];
             if let Some(string) = content.as_str() {
-                let role = role.to_string();
-                let cb = collapsible_block(string, "");
+                let anchor_id = format_block_anchor_id(message_index, 0);
+                let anchor_link = render_anchor_link(&anchor_id);
+                let role_cell = render_role_cell(role, message_index);
+                let cb = render_message_text(string, render_markdown);
                 vec![view! {
-                    <tr><td>{role}</td><td>"text"</td><td>{cb}</td></tr>
+                    <tr id={anchor_id}>
+                        <td>{role_cell}</td>
+                        <td>{anchor_link}" text"</td>
+                        <td>{cb}</td>
+                    </tr>
                 }
                 .into_any()]
             } else if let Some(blocks) = content.as_array() {
@@ -193,10 +368,13 @@ pub fn render_messages(json_str: &str, order: &str, keep_tool_pairs: i64) -> Any
                     .filter_map(|(index, block)| {
                         let block_type =
                             block.get("type").and_then(|field| field.as_str()).unwrap_or("");
-                        let role_cell = if index == 0 {
-                            role.to_string()
-                        } else {
-                            String::new()
+                        let block_row = BlockRow {
+                            role_cell: if index == 0 {
+                                render_role_cell(role, message_index)
+                            } else {
+                                ().into_any()
+                            },
+                            anchor_id: format_block_anchor_id(message_index, index),
                         };
 
                         // Determine if this block is filtered
@@ -225,28 +403,38 @@ pub fn render_messages(json_str: &str, order: &str, keep_tool_pairs: i64) -> Any
                         };
 
                         match block_type {
-                            "text" => Some(render_text_block(block, role_cell)),
-                            "thinking" => Some(render_thinking_block(block, role_cell)),
-                            "tool_use" => Some(render_tool_use_block(
+                            "text" => Some(render_text_block(block, block_row, render_markdown)),
+                            "thinking" => Some(render_thinking_block(block, block_row)),
+                            "image" => Some(render_image_block(block, block_row)),
+                            "document" => {
+                                let download_href = options.blocks_url.map(|blocks_url| {
+                                    format!("{}/{}/download", blocks_url, block_offset + index)
+                                });
+                                Some(render_document_block(block, block_row, download_href))
+                            }
+                            "tool_use" | "server_tool_use" => Some(render_tool_use_block(
                                 block,
-                                role_cell,
+                                block_row,
                                 row_class,
                                 filtered_badge,
                             )),
                             "tool_result" => Some(render_tool_result_block(
                                 block,
-                                role_cell,
+                                block_row,
                                 row_class,
                                 filtered_badge,
                             )),
+                            block_type if is_code_execution_result(block_type) => {
+                                Some(render_code_execution_result_block(block, block_row))
+                            }
                             _ => None,
                         }
                     })
                     .collect()
             } else {
-                let role = role.to_string();
+                let role_cell = render_role_cell(role, message_index);
                 vec![view! {
-                    <tr><td>{role}</td><td></td><td></td></tr>
+                    <tr><td>{role_cell}</td><td></td><td></td></tr>
                 }
                 .into_any()]
             }
@@ -360,4 +548,45 @@ mod tests {
         let msgs: Vec<serde_json::Value> = vec![];
         assert!(collect_filtered_tool_ids(&msgs, 1).is_empty());
     }
+
+    #[test]
+    fn render_messages_anchors_follow_request_order() {
+        let json = r#"[
+            {"role": "user", "content": "hi"},
+            {"role": "assistant", "content": [{"type": "text", "text": "a"}, {"type": "text", "text": "b"}]}
+        ]"#;
+        let options = MessagesViewOptions {
+            order: "desc",
+            keep_tool_pairs: 0,
+            render_markdown: false,
+            blocks_url: None,
+        };
+        let html = render_messages(json, &options).to_html();
+        assert!(html.contains(r#"id="msg-0-block-0""#));
+        assert!(html.contains(r#"id="msg-1""#));
+        assert!(html.contains(r#"id="msg-1-block-1""#));
+        assert!(html.contains(r##"href="#msg-1-block-1""##));
+    }
+
+    #[test]
+    fn render_messages_shows_tool_result_screenshots_and_code_execution() {
+        let json = r#"[
+            {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "t1", "content": [
+                {"type": "text", "text": "done"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "aGVsbG8="}}
+            ]}]},
+            {"role": "assistant", "content": [{"type": "bash_code_execution_tool_result", "tool_use_id": "t2",
+                "content": {"type": "bash_code_execution_result", "stdout": "hi", "stderr": "", "return_code": 0}}]}
+        ]"#;
+        let options = MessagesViewOptions {
+            order: "asc",
+            keep_tool_pairs: 0,
+            render_markdown: false,
+            blocks_url: None,
+        };
+        let html = render_messages(json, &options).to_html();
+        assert!(html.contains(r#"src="data:image/png;base64,aGVsbG8=""#));
+        assert!(html.contains("done"));
+        assert!(html.contains("exit code 0"));
+    }
 }
@@ -1,16 +1,45 @@
+mod annotations;
+mod caching;
 mod common;
+mod curl;
+mod document;
+mod download;
+mod export;
+mod highlight;
+mod image;
+mod markdown;
 mod messages;
+mod protocol;
+mod raw_bytes;
+mod rendered;
+mod share;
 mod sse;
 mod system;
+mod tool_views;
 mod tools;
 mod webfetch;
 
+use self::annotations::{format_star_authors, render_request_annotations};
 use self::common::{build_request_subpage_defs, render_detail_page_content};
+use self::curl::build_curl_command;
+use self::download::render_download_links;
+use self::protocol::render_protocol_issues;
+use self::share::render_share_form;
+pub use self::annotations::RequestAnnotations;
+pub use self::caching::*;
+pub(crate) use self::image::format_byte_count;
+pub(crate) use self::protocol::find_recorded_protocol_issues;
+pub use self::download::find_request_download;
+pub use self::document::{decode_block_source, find_message_block, get_block_file_extension};
+pub use self::export::{render_request_export, ExportFilters};
+pub use self::share::render_share_link_view;
 pub use self::webfetch::*;
+use crate::context_usage::{estimate_request_context, render_context_bar};
 use ::common::models::{ProxyRequest, Session};
-use leptos::prelude::*;
+use ::common::url::encode_uri_component;
+use leptos::{either::Either, prelude::*};
 use std::collections::HashMap;
-use templates::{Breadcrumb, InfoRow, NavLink, Page};
+use templates::{copy_button, Breadcrumb, InfoRow, NavLink, Page, Subpage};
 
 fn build_detail_breadcrumbs(
     session: &Session,
@@ -44,29 +73,231 @@ fn build_detail_breadcrumbs(
     crumbs
 }
 
+/// IDs of the adjacent requests in the same session, for Newer/Older links.
+pub struct RequestNeighbors<'a> {
+    pub prev_id: Option<&'a str>,
+    pub next_id: Option<&'a str>,
+}
+
+fn build_detail_nav_links(req: &ProxyRequest, neighbors: &RequestNeighbors<'_>) -> Vec<NavLink> {
+    let mut nav_links = vec![];
+    if let Some(id) = neighbors.prev_id {
+        let href = format!("/_dashboard/sessions/{}/requests/{}", req.session_id, id);
+        nav_links.push(NavLink::new("← Newer", href));
+    }
+    if let Some(id) = neighbors.next_id {
+        let href = format!("/_dashboard/sessions/{}/requests/{}", req.session_id, id);
+        nav_links.push(NavLink::new("Older →", href));
+    }
+    nav_links.push(NavLink::back());
+    nav_links
+}
+
+/// Token Counting rows, shown only for count_tokens requests.
+fn build_input_token_rows(req: &ProxyRequest) -> Vec<InfoRow> {
+    let format_tokens = |tokens: Option<i64>| {
+        tokens
+            .map(|token_count| token_count.to_string())
+            .unwrap_or_default()
+    };
+    if req.estimated_input_tokens.is_none() && req.counted_input_tokens.is_none() {
+        return vec![];
+    }
+    vec![
+        InfoRow::new("Input Tokens (API)", &format_tokens(req.counted_input_tokens)),
+        InfoRow::new("Input Tokens (Estimate)", &format_tokens(req.estimated_input_tokens)),
+    ]
+}
+
+/// Bytes the filter profile trimmed from a request body, e.g. `"2.0 KB (25.0%)"`.
+fn format_filter_savings(request_bytes: i64, forwarded_bytes: i64) -> String {
+    let saved_bytes = request_bytes - forwarded_bytes;
+    if saved_bytes < 0 {
+        return format!("none (grew by {})", format_byte_count(-saved_bytes as usize));
+    }
+    let saved_percent = if request_bytes > 0 {
+        saved_bytes as f64 * 100.0 / request_bytes as f64
+    } else {
+        0.0
+    };
+    format!(
+        "{} ({:.1}%)",
+        format_byte_count(saved_bytes as usize),
+        saved_percent
+    )
+}
+
+/// Body size rows, shown once a request's sizes were recorded.
+fn build_size_rows(req: &ProxyRequest) -> Vec<InfoRow> {
+    let format_size = |byte_count: Option<i64>| {
+        byte_count
+            .map(|byte_count| format_byte_count(byte_count as usize))
+            .unwrap_or_default()
+    };
+    if req.request_bytes.is_none() && req.response_bytes.is_none() {
+        return vec![];
+    }
+    let mut size_rows = vec![
+        InfoRow::new("Request Size", &format_size(req.request_bytes)),
+        InfoRow::new("Forwarded Size", &format_size(req.forwarded_bytes)),
+    ];
+    if let (Some(request_bytes), Some(forwarded_bytes)) = (req.request_bytes, req.forwarded_bytes) {
+        size_rows.push(InfoRow::new(
+            "Filter Savings",
+            &format_filter_savings(request_bytes, forwarded_bytes),
+        ));
+    }
+    size_rows.push(InfoRow::new("Response Size", &format_size(req.response_bytes)));
+    size_rows
+}
+
+/// Who sent the request, e.g. `"claude-cli/1.0.0 (x-app: cli) from 127.0.0.1:54321"`.
+fn describe_client(req: &ProxyRequest) -> Option<String> {
+    let mut client = req.client_user_agent.clone().unwrap_or_default();
+    if let Some(client_app) = &req.client_app {
+        client.push_str(&format!(" (x-app: {})", client_app));
+    }
+    if let Some(client_addr) = &req.client_addr {
+        client.push_str(&format!(" from {}", client_addr));
+    }
+    let client = client.trim_start().to_string();
+    (!client.is_empty()).then_some(client)
+}
+
+/// The request's client and, when it sent a `User-Agent`, a link to the
+/// session's other requests from it.
+fn render_client(req: &ProxyRequest, client: String) -> impl IntoView {
+    let client_requests_link = req.client_user_agent.as_deref().map(|client_user_agent| {
+        let href = format!(
+            "/_dashboard/sessions/{}/requests?client={}",
+            req.session_id,
+            encode_uri_component(client_user_agent)
+        );
+        view! { " " <a href={href}>"All requests from this client"</a> }
+    });
+    view! { {client}{client_requests_link} }
+}
+
+/// The request's generated title and a button asking for a new one.
+fn render_title_form(req: &ProxyRequest, base: &str) -> impl IntoView {
+    let title = match req.title.as_deref() {
+        None => "(not generated yet)",
+        Some("") => "(generation failed)",
+        Some(title) => title,
+    };
+    view! {
+        {format!("{} ", title)}
+        <form method="POST" action={format!("{}/title", base)}>
+            <input type="submit" value="Regenerate"/>
+        </form>
+    }
+}
+
+/// Links between the original request of a WebFetch turn and its follow-up
+/// rounds, e.g. "Original › Round 1 › Round 2", with this request in bold.
+fn render_round_trail(req: &ProxyRequest, round_ids: &[String]) -> impl IntoView {
+    let request_id = req.id.to_string();
+    let parent_request_id = req.parent_request_id.as_deref().unwrap_or(&request_id);
+    let round_links: Vec<_> = std::iter::once(parent_request_id)
+        .chain(round_ids.iter().map(String::as_str))
+        .enumerate()
+        .map(|(round_index, round_id)| {
+            let separator = (round_index > 0).then_some(" › ");
+            let label = if round_index == 0 {
+                "Original".to_string()
+            } else {
+                format!("Round {}", round_index)
+            };
+            if round_id == request_id {
+                Either::Left(view! { {separator}<strong>{label}</strong> })
+            } else {
+                let href = format!(
+                    "/_dashboard/sessions/{}/requests/{}",
+                    req.session_id, round_id
+                );
+                Either::Right(view! { {separator}<a href={href}>{label}</a> })
+            }
+        })
+        .collect();
+    view! { {round_links} }
+}
+
+/// `round_ids` are the follow-up rounds of the request's WebFetch turn,
+/// empty when it had none.
 pub fn render_request_detail_view(
     req: &ProxyRequest,
     session: &Session,
-    prev_id: Option<&str>,
-    next_id: Option<&str>,
+    neighbors: &RequestNeighbors<'_>,
+    round_ids: &[String],
+    annotations: &RequestAnnotations<'_>,
 ) -> String {
     let base = format!(
         "/_dashboard/sessions/{}/requests/{}",
         req.session_id, req.id
     );
 
-    let subpages = build_request_subpage_defs(req, &base, true);
+    let mut subpages = build_request_subpage_defs(req, &base, true);
+    if req.messages_json.is_some() {
+        subpages.push(Subpage::new(
+            "Caching",
+            format!("{}/caching", base),
+            count_cache_breakpoints(req),
+        ));
+    }
 
-    let mut nav_links = vec![];
-    if let Some(id) = prev_id {
-        let href = format!("/_dashboard/sessions/{}/requests/{}", req.session_id, id);
-        nav_links.push(NavLink::new("← Newer", href));
+    let nav_links = build_detail_nav_links(req, neighbors);
+
+    let mut info_rows = vec![
+        InfoRow::new("Method", &req.method),
+        InfoRow::new("Path", &req.path),
+    ];
+    if req.messages_json.is_some() {
+        info_rows.push(InfoRow::view("Title", render_title_form(req, &base)));
     }
-    if let Some(id) = next_id {
-        let href = format!("/_dashboard/sessions/{}/requests/{}", req.session_id, id);
-        nav_links.push(NavLink::new("Older →", href));
+    info_rows.extend([
+        InfoRow::new("Model", req.model.as_deref().unwrap_or("")),
+        InfoRow::new("Time", req.created_at.get(11..19).unwrap_or(&req.created_at)),
+    ]);
+    if !round_ids.is_empty() {
+        info_rows.push(InfoRow::view("Rounds", render_round_trail(req, round_ids)));
     }
-    nav_links.push(NavLink::back());
+    if let Some(client) = describe_client(req) {
+        info_rows.push(InfoRow::view("Client", render_client(req, client)));
+    }
+    if let Some(error_kind) = &req.error_kind {
+        info_rows.push(InfoRow::new("Upstream Error", error_kind));
+    }
+    if let Some(webfetch_followup_error) = &req.webfetch_followup_error {
+        info_rows.push(InfoRow::new("WebFetch Interception Error", webfetch_followup_error));
+    }
+    if let Some(anthropic_beta) = &req.anthropic_beta {
+        info_rows.push(InfoRow::new("Anthropic Beta", anthropic_beta));
+    }
+    if let Some(context_breakdown) = estimate_request_context(req) {
+        info_rows.push(InfoRow::view(
+            "Context Usage",
+            render_context_bar(&context_breakdown),
+        ));
+    }
+    info_rows.extend(build_input_token_rows(req));
+    info_rows.extend(build_size_rows(req));
+    info_rows.push(InfoRow::view("Download", render_download_links(req, &base)));
+    info_rows.push(InfoRow::view(
+        "Export",
+        view! { <a href={format!("{}/export", base)}>"Export as HTML"</a> },
+    ));
+    info_rows.push(InfoRow::view(
+        "Curl",
+        copy_button(&build_curl_command(req, session), "Copy curl command"),
+    ));
+    info_rows.push(InfoRow::view("Share", render_share_form(&base)));
+    info_rows.push(InfoRow::new(
+        "Starred By",
+        &format_star_authors(annotations.star_authors),
+    ));
+    let protocol_issues_view = render_protocol_issues(req, &base);
+    let annotations_view = render_request_annotations(&base, annotations);
+    let content = view! { {protocol_issues_view}{annotations_view} };
 
     Page {
         title: format!(
@@ -75,13 +306,8 @@ pub fn render_request_detail_view(
         ),
         breadcrumbs: build_detail_breadcrumbs(session, req, None),
         nav_links,
-        info_rows: vec![
-            InfoRow::new("Method", &req.method),
-            InfoRow::new("Path", &req.path),
-            InfoRow::new("Model", req.model.as_deref().unwrap_or("")),
-            InfoRow::new("Time", req.created_at.get(11..19).unwrap_or(&req.created_at)),
-        ],
-        content: (),
+        info_rows,
+        content,
         subpages,
     }
     .render()
@@ -95,8 +321,11 @@ fn get_page_label(page: &str) -> &str {
         "params" => "Params",
         "headers" => "Request Headers",
         "full_json" => "Full JSON",
+        "applied_filters" => "Filters Applied",
         "response_headers" => "Response Headers",
         "response_sse" => "Response SSE",
+        "response_rendered" => "Response (rendered)",
+        "response_raw" => "Raw Bytes",
         _ => "Unknown",
     }
 }
@@ -108,8 +337,7 @@ pub fn render_request_detail_page_view(
     query: &HashMap<String, String>,
     filters: &[String],
     keep_tool_pairs: i64,
-    prev_id: Option<&str>,
-    next_id: Option<&str>,
+    neighbors: &RequestNeighbors<'_>,
 ) -> String {
     let page_label = get_page_label(page);
 
@@ -128,16 +356,7 @@ pub fn render_request_detail_page_view(
         {detail_page_content.content_view}
     };
 
-    let mut nav_links = vec![];
-    if let Some(id) = prev_id {
-        let href = format!("/_dashboard/sessions/{}/requests/{}", req.session_id, id);
-        nav_links.push(NavLink::new("← Newer", href));
-    }
-    if let Some(id) = next_id {
-        let href = format!("/_dashboard/sessions/{}/requests/{}", req.session_id, id);
-        nav_links.push(NavLink::new("Older →", href));
-    }
-    nav_links.push(NavLink::back());
+    let nav_links = build_detail_nav_links(req, neighbors);
 
     Page {
         title: format!(
@@ -152,3 +371,49 @@ pub fn render_request_detail_page_view(
     }
     .render()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_filter_savings_shows_share_trimmed() {
+        assert_eq!(format_filter_savings(8192, 6144), "2.0 KB (25.0%)");
+        assert_eq!(format_filter_savings(0, 0), "0 B (0.0%)");
+        assert_eq!(format_filter_savings(100, 110), "none (grew by 10 B)");
+    }
+
+    #[test]
+    fn describe_client_joins_user_agent_app_and_address() {
+        let build_request = |client: serde_json::Value| -> ProxyRequest {
+            let mut request = serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000002",
+                "session_id": "00000000-0000-0000-0000-000000000001",
+                "method": "POST",
+                "path": "/v1/messages",
+                "created_at": "2026-01-01 00:00:00",
+                "updated_at": "2026-01-01 00:00:00",
+            });
+            request
+                .as_object_mut()
+                .unwrap()
+                .extend(client.as_object().unwrap().clone());
+            serde_json::from_value(request).unwrap()
+        };
+        let cli_request = build_request(serde_json::json!({
+            "client_user_agent": "claude-cli/1.0.0",
+            "client_app": "cli",
+            "client_addr": "127.0.0.1:54321",
+        }));
+        assert_eq!(
+            describe_client(&cli_request).as_deref(),
+            Some("claude-cli/1.0.0 (x-app: cli) from 127.0.0.1:54321")
+        );
+        let bare_request = build_request(serde_json::json!({"client_addr": "10.0.0.2:4000"}));
+        assert_eq!(
+            describe_client(&bare_request).as_deref(),
+            Some("from 10.0.0.2:4000")
+        );
+        assert_eq!(describe_client(&build_request(serde_json::json!({}))), None);
+    }
+}
@@ -0,0 +1,45 @@
+use common::models::ProxyRequest;
+use common::sse::find_protocol_issues;
+use leptos::prelude::*;
+
+/// Issues listed in the Protocol Issues section; a stream that is broken
+/// throughout would otherwise list one per event.
+const PROTOCOL_ISSUES_SHOWN: usize = 50;
+
+/// Violations of the Messages streaming protocol in the recorded response
+/// events. JSON responses have no recorded events and no issues.
+pub fn find_recorded_protocol_issues(response_events_json: Option<&str>) -> Vec<String> {
+    response_events_json
+        .and_then(|events_json| serde_json::from_str::<Vec<serde_json::Value>>(events_json).ok())
+        .map(|events| find_protocol_issues(&events))
+        .unwrap_or_default()
+}
+
+/// The Protocol Issues section of the request detail page, or nothing when
+/// the recorded stream follows the protocol.
+pub fn render_protocol_issues(req: &ProxyRequest, base_url: &str) -> Option<impl IntoView> {
+    let protocol_issues = find_recorded_protocol_issues(req.response_events_json.as_deref());
+    if protocol_issues.is_empty() {
+        return None;
+    }
+    let summary = format!(
+        "{} issue(s) in the recorded response stream. ",
+        protocol_issues.len()
+    );
+    let hidden_count = protocol_issues.len().saturating_sub(PROTOCOL_ISSUES_SHOWN);
+    let more_note =
+        (hidden_count > 0).then(|| view! { <p>{format!("… and {} more.", hidden_count)}</p> });
+    let sse_href = format!("{}/response_sse?deltas=expand", base_url);
+    Some(view! {
+        <h2>"Protocol Issues"</h2>
+        <p>{summary}<a href={sse_href}>"View Response SSE"</a></p>
+        <ul>
+            {protocol_issues
+                .into_iter()
+                .take(PROTOCOL_ISSUES_SHOWN)
+                .map(|protocol_issue| view! { <li>{protocol_issue}</li> })
+                .collect::<Vec<_>>()}
+        </ul>
+        {more_note}
+    })
+}
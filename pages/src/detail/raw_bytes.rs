@@ -0,0 +1,136 @@
+use common::models::ProxyRequest;
+use leptos::prelude::*;
+
+/// Bytes dumped before the "Show all bytes" toggle, so large responses
+/// don't produce multi-megabyte pages.
+const RAW_BYTES_PREVIEW_LIMIT: usize = 64 * 1024;
+
+/// Bytes per line of the hex dump.
+const HEX_DUMP_WIDTH: usize = 16;
+
+/// The bytes a stored body holds where the upstream sent invalid UTF-8: the
+/// response is stored as text, so each invalid sequence became U+FFFD.
+const REPLACEMENT_CHAR_BYTES: &[u8] = "\u{FFFD}".as_bytes();
+
+/// Format bytes as `hexdump -C` does: offset, sixteen hex bytes in two
+/// groups of eight, and the printable ASCII characters.
+pub fn format_hex_dump(bytes: &[u8]) -> String {
+    let mut hex_dump = String::new();
+    for (line_index, line) in bytes.chunks(HEX_DUMP_WIDTH).enumerate() {
+        hex_dump.push_str(&format!("{:08x}  ", line_index * HEX_DUMP_WIDTH));
+        for column in 0..HEX_DUMP_WIDTH {
+            match line.get(column) {
+                Some(byte) => hex_dump.push_str(&format!("{:02x} ", byte)),
+                None => hex_dump.push_str("   "),
+            }
+            if column == HEX_DUMP_WIDTH / 2 - 1 {
+                hex_dump.push(' ');
+            }
+        }
+        let ascii: String = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        hex_dump.push_str(&format!(" |{}|\n", ascii));
+    }
+    hex_dump.push_str(&format!("{:08x}\n", bytes.len()));
+    hex_dump
+}
+
+/// How many invalid UTF-8 sequences were replaced when the body was stored.
+fn count_replacement_chars(bytes: &[u8]) -> usize {
+    bytes
+        .windows(REPLACEMENT_CHAR_BYTES.len())
+        .filter(|window| *window == REPLACEMENT_CHAR_BYTES)
+        .count()
+}
+
+/// The response's `Content-Type` header, if it was stored.
+fn find_response_content_type(req: &ProxyRequest) -> Option<String> {
+    let headers: serde_json::Value =
+        serde_json::from_str(req.response_headers_json.as_deref()?).ok()?;
+    headers
+        .as_object()?
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .and_then(|(_, value)| value.as_str())
+        .map(|content_type| content_type.to_string())
+}
+
+/// The stored response body as a hex and ASCII dump, with its length and
+/// content type. Only the first 64 KB are shown when `truncate` is set.
+pub fn render_response_raw_bytes(req: &ProxyRequest, base_url: &str, truncate: bool) -> AnyView {
+    let Some(ref response_body) = req.response_body else {
+        return view! { <p>"No response body."</p> }.into_any();
+    };
+    let bytes = response_body.as_bytes();
+    let shown_bytes = if truncate {
+        &bytes[..bytes.len().min(RAW_BYTES_PREVIEW_LIMIT)]
+    } else {
+        bytes
+    };
+    let length = format!("{} bytes", bytes.len());
+    let content_type = find_response_content_type(req).unwrap_or_else(|| "(none)".to_string());
+    let replacement_count = count_replacement_chars(bytes);
+    let replacement_note = (replacement_count > 0).then(|| {
+        let note = format!(
+            "{} invalid UTF-8 sequence(s) were replaced with U+FFFD (ef bf bd) when the response was stored.",
+            replacement_count
+        );
+        view! { <p>{note}</p> }
+    });
+    let truncation_toggle = (bytes.len() > RAW_BYTES_PREVIEW_LIMIT).then(|| {
+        let (toggle_param, toggle_label) = if truncate {
+            ("off", "Show all bytes")
+        } else {
+            ("on", "Show first 64 KB")
+        };
+        let toggle_href = format!("{}/response_raw?truncate={}", base_url, toggle_param);
+        view! { <p><a href={toggle_href}>{toggle_label}</a></p> }
+    });
+    let hex_dump = format_hex_dump(shown_bytes);
+
+    view! {
+        <table>
+            <tr><td>"Length"</td><td>{length}</td></tr>
+            <tr><td>"Content-Type"</td><td>{content_type}</td></tr>
+        </table>
+        {replacement_note}
+        {truncation_toggle}
+        <pre>{hex_dump}</pre>
+    }
+    .into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_hex_dump_pads_the_last_line() {
+        let hex_dump = format_hex_dump(b"event: ping\ndata: {}\n");
+        let lines: Vec<&str> = hex_dump.lines().collect();
+        assert_eq!(
+            lines[0],
+            "00000000  65 76 65 6e 74 3a 20 70  69 6e 67 0a 64 61 74 61  |event: ping.data|"
+        );
+        assert_eq!(
+            lines[1],
+            "00000010  3a 20 7b 7d 0a                                    |: {}.|"
+        );
+        assert_eq!(lines[2], "00000015");
+    }
+
+    #[test]
+    fn count_replacement_chars_finds_lossy_bytes() {
+        let body = String::from_utf8_lossy(b"ok\xff\xfe!");
+        assert_eq!(count_replacement_chars(body.as_bytes()), 2);
+        assert_eq!(count_replacement_chars(b"plain"), 0);
+    }
+}
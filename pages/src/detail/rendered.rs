@@ -0,0 +1,105 @@
+use common::models::ProxyRequest;
+use common::sse::{find_stop_reason, reconstruct_content_blocks};
+use leptos::prelude::*;
+use serde_json::Value;
+
+use super::messages::{render_messages, MessagesViewOptions};
+
+/// The final assistant message and its stop reason, rebuilt from a response.
+pub struct ResponseMessage {
+    pub content_blocks: Vec<Value>,
+    pub stop_reason: Option<String>,
+}
+
+/// Build the final assistant message from the recorded SSE events, falling
+/// back to the `content` of a non-streaming JSON response body.
+pub fn build_response_message(
+    events_json: Option<&str>,
+    response_body: Option<&str>,
+) -> Option<ResponseMessage> {
+    let events = events_json
+        .and_then(|json| serde_json::from_str::<Vec<Value>>(json).ok())
+        .filter(|events| !events.is_empty());
+    if let Some(events) = events {
+        return Some(ResponseMessage {
+            content_blocks: reconstruct_content_blocks(&events),
+            stop_reason: find_stop_reason(&events),
+        });
+    }
+
+    let body: Value = serde_json::from_str(response_body?).ok()?;
+    let content_blocks = body.get("content")?.as_array()?.clone();
+    let stop_reason = body
+        .get("stop_reason")
+        .and_then(|field| field.as_str())
+        .map(|string| string.to_string());
+    Some(ResponseMessage {
+        content_blocks,
+        stop_reason,
+    })
+}
+
+pub fn render_response_message(req: &ProxyRequest, render_markdown: bool) -> AnyView {
+    let Some(response_message) = build_response_message(
+        req.response_events_json.as_deref(),
+        req.response_body.as_deref(),
+    ) else {
+        return view! { <p>"No assistant message in the response."</p> }.into_any();
+    };
+
+    let stop_reason = response_message
+        .stop_reason
+        .unwrap_or_else(|| "-".to_string());
+    let messages_json = serde_json::json!([{
+        "role": "assistant",
+        "content": response_message.content_blocks,
+    }])
+    .to_string();
+    let messages_view = render_messages(
+        &messages_json,
+        &MessagesViewOptions {
+            order: "asc",
+            keep_tool_pairs: 0,
+            render_markdown,
+            blocks_url: None,
+        },
+    );
+
+    view! {
+        <div><strong>"Stop reason:"</strong>" "{stop_reason}</div>
+        {messages_view}
+    }
+    .into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_message_from_events() {
+        let events = serde_json::json!([
+            {"event": "content_block_start", "data": {"index": 0, "content_block": {"type": "text", "text": ""}}},
+            {"event": "content_block_delta", "data": {"index": 0, "delta": {"type": "text_delta", "text": "Hello"}}},
+            {"event": "content_block_stop", "data": {"index": 0}},
+            {"event": "message_delta", "data": {"delta": {"stop_reason": "end_turn"}}}
+        ])
+        .to_string();
+        let response_message = build_response_message(Some(&events), None).unwrap();
+        assert_eq!(response_message.content_blocks[0]["text"], "Hello");
+        assert_eq!(response_message.stop_reason.as_deref(), Some("end_turn"));
+    }
+
+    #[test]
+    fn response_message_from_json_body() {
+        let body = r#"{"content": [{"type": "text", "text": "Hi"}], "stop_reason": "max_tokens"}"#;
+        let response_message = build_response_message(None, Some(body)).unwrap();
+        assert_eq!(response_message.content_blocks.len(), 1);
+        assert_eq!(response_message.stop_reason.as_deref(), Some("max_tokens"));
+    }
+
+    #[test]
+    fn response_message_missing() {
+        assert!(build_response_message(None, Some("not json")).is_none());
+    }
+}
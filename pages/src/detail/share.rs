@@ -0,0 +1,52 @@
+use common::models::{ProxyRequest, Session};
+use common::share::SHARE_LINK_EXPIRIES;
+use leptos::prelude::*;
+use templates::{copy_button, InfoRow, NavLink, Page};
+
+use super::build_detail_breadcrumbs;
+
+/// A form creating a share link for the request, with a choice of expiry.
+pub(super) fn render_share_form(base: &str) -> impl IntoView {
+    let expiry_options: Vec<_> = SHARE_LINK_EXPIRIES
+        .iter()
+        .map(|(hours, label)| {
+            let selected = *hours == 24;
+            view! { <option value={hours.to_string()} selected={selected}>{*label}</option> }
+        })
+        .collect();
+    view! {
+        <form method="POST" action={format!("{}/share", base)}>
+            "Expires after "
+            <select name="expires_hours">{expiry_options}</select>
+            " "
+            <input type="submit" value="Create Link"/>
+        </form>
+    }
+}
+
+/// The page showing a newly created share link.
+pub fn render_share_link_view(
+    req: &ProxyRequest,
+    session: &Session,
+    share_url: &str,
+    expires_label: &str,
+) -> String {
+    let content = view! {
+        <h2>"Share Link"</h2>
+        <p>"Anyone with this link can view this request's pages, without dashboard credentials, until it expires. Links can't be revoked one at a time, so pick the shortest expiry that works."</p>
+        <p><code>{share_url.to_string()}</code>" "{copy_button(share_url, "Copy link")}</p>
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Request #{} - Share",
+            session.name, req.id
+        ),
+        breadcrumbs: build_detail_breadcrumbs(session, req, Some("Share")),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![InfoRow::new("Expires", expires_label)],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -1,6 +1,8 @@
 use common::models::ProxyRequest;
+use common::sse::load_response_events;
 use leptos::prelude::*;
 use std::collections::HashMap;
+use templates::{pagination_nav, Pagination};
 
 use crate::collapsible_block;
 
@@ -98,73 +100,199 @@ fn render_sse_block_summary(
     .into_any()
 }
 
-pub fn render_response_sse(req: &ProxyRequest) -> AnyView {
-    // SSE events
+/// Events shown per page of the Response SSE view.
+const SSE_EVENTS_PER_PAGE: i64 = 200;
+
+/// Paging and delta collapsing for the Response SSE view, read from the query string.
+pub struct SseViewOptions<'a> {
+    /// URL of the page rendering the view, used for paging and toggle links.
+    pub base_url: &'a str,
+    pub page: i64,
+    /// Hide `content_block_delta` rows and show only the reconstructed block.
+    pub collapse_deltas: bool,
+    /// Events per page, or `None` to show every event on one page.
+    pub per_page: Option<i64>,
+}
+
+impl<'a> SseViewOptions<'a> {
+    pub fn from_query(base_url: &'a str, query: &HashMap<String, String>) -> Self {
+        let page = query
+            .get("page")
+            .and_then(|page_str| page_str.parse().ok())
+            .unwrap_or(1)
+            .max(1);
+        let collapse_deltas = query.get("deltas").map(|field| field.as_str()) != Some("expand");
+        Self {
+            base_url,
+            page,
+            collapse_deltas,
+            per_page: Some(SSE_EVENTS_PER_PAGE),
+        }
+    }
+
+    /// Every event on one page with deltas collapsed, for exports.
+    pub fn unpaged(base_url: &'a str) -> Self {
+        Self {
+            base_url,
+            page: 1,
+            collapse_deltas: true,
+            per_page: None,
+        }
+    }
+
+    fn count_per_page(&self, event_count: usize) -> i64 {
+        self.per_page.unwrap_or(event_count.max(1) as i64)
+    }
+
+    fn build_extra_params(&self) -> &'static str {
+        if self.collapse_deltas {
+            ""
+        } else {
+            "&deltas=expand"
+        }
+    }
+}
+
+fn render_sse_event_row(event_index: usize, event_type: &str, data: &serde_json::Value) -> AnyView {
+    let summary = summarize_sse_event(event_type, data);
+    let raw = serde_json::to_string_pretty(data).unwrap_or_default();
+    let event_number = (event_index + 1).to_string();
+    let event_type_str = event_type.to_string();
+    view! {
+        <tr>
+            <td>{event_number}</td>
+            <td>{event_type_str}</td>
+            <td>{summary}</td>
+            <td>
+                <details class="collapsible">
+                    <summary><span class="show-more">"show raw"</span></summary>
+                    <pre class="collapsible-full">{raw}</pre>
+                </details>
+            </td>
+        </tr>
+    }
+    .into_any()
+}
+
+/// Rows for the current page, plus the number of visible events across all pages.
+struct SseRows {
+    rows: Vec<AnyView>,
+    visible_count: i64,
+}
+
+/// Build table rows for the events on the requested page. Block state is
+/// accumulated over every event so reconstructed blocks stay complete even
+/// when their deltas fall on earlier pages or are collapsed.
+fn build_sse_rows(sse_events: &[serde_json::Value], options: &SseViewOptions<'_>) -> SseRows {
+    let mut block_text: HashMap<i64, String> = HashMap::new();
+    let mut block_json: HashMap<i64, String> = HashMap::new();
+    let mut block_names: HashMap<i64, String> = HashMap::new();
+    let mut block_types: HashMap<i64, String> = HashMap::new();
+
+    let per_page = options.count_per_page(sse_events.len());
+    let page_start = (options.page - 1) * per_page;
+    let page_end = page_start + per_page;
+    let mut rows: Vec<AnyView> = Vec::new();
+    let mut visible_count: i64 = 0;
+
+    for (event_index, event) in sse_events.iter().enumerate() {
+        // Lines the parser couldn't read are stored with their raw text as data.
+        let event_type = if event.get("unparsed").is_some() {
+            "(unparsed)"
+        } else {
+            event.get("event").and_then(|field| field.as_str()).unwrap_or("")
+        };
+        let data = &event["data"];
+
+        accumulate_sse_block_state(
+            event_type,
+            data,
+            &mut block_text,
+            &mut block_json,
+            &mut block_names,
+            &mut block_types,
+        );
+
+        if options.collapse_deltas && event_type == "content_block_delta" {
+            continue;
+        }
+        let on_page = (page_start..page_end).contains(&visible_count);
+        visible_count += 1;
+        if !on_page {
+            continue;
+        }
+
+        rows.push(render_sse_event_row(event_index, event_type, data));
+
+        // Insert summary row after content_block_stop
+        if event_type == "content_block_stop" {
+            let index = data.get("index").and_then(|field| field.as_i64()).unwrap_or(0);
+            rows.push(render_sse_block_summary(
+                &block_types,
+                &block_names,
+                &block_json,
+                &block_text,
+                index,
+            ));
+        }
+    }
+
+    SseRows {
+        rows,
+        visible_count,
+    }
+}
+
+fn render_sse_delta_toggle(options: &SseViewOptions<'_>) -> AnyView {
+    let (showing, toggle_query, toggle_label) = if options.collapse_deltas {
+        ("collapsed", "?deltas=expand", "Show delta events")
+    } else {
+        ("expanded", "", "Collapse delta events")
+    };
+    let toggle_href = format!("{}{}", options.base_url, toggle_query);
+    view! {
+        <div>"Deltas: "{showing}" | "<a href={toggle_href}>{toggle_label}</a></div>
+    }
+    .into_any()
+}
+
+pub fn render_response_sse(req: &ProxyRequest, options: &SseViewOptions<'_>) -> AnyView {
+    // SSE events, or the events a JSON message response would have streamed
     if let Some(ref events_json) = req.response_events_json {
-        if let Ok(sse_events) = serde_json::from_str::<Vec<serde_json::Value>>(events_json) {
-            let count = sse_events.len().to_string();
-
-            // Track accumulated text/json per content block index
-            let mut block_text: HashMap<i64, String> = HashMap::new();
-            let mut block_json: HashMap<i64, String> = HashMap::new();
-            let mut block_names: HashMap<i64, String> = HashMap::new();
-            let mut block_types: HashMap<i64, String> = HashMap::new();
-
-            let mut rows: Vec<AnyView> = Vec::new();
-
-            for (event_index, event) in sse_events.iter().enumerate() {
-                let event_type = event.get("event").and_then(|field| field.as_str()).unwrap_or("");
-                let data = &event["data"];
-
-                accumulate_sse_block_state(
-                    event_type,
-                    data,
-                    &mut block_text,
-                    &mut block_json,
-                    &mut block_names,
-                    &mut block_types,
-                );
-
-                let summary = summarize_sse_event(event_type, data);
-                let raw = serde_json::to_string_pretty(data).unwrap_or_default();
-                let event_number = (event_index + 1).to_string();
-                let event_type_str = event_type.to_string();
-                rows.push(
-                    view! {
-                        <tr>
-                            <td>{event_number}</td>
-                            <td>{event_type_str}</td>
-                            <td>{summary}</td>
-                            <td>
-                                <details class="collapsible">
-                                    <summary><span class="show-more">"show raw"</span></summary>
-                                    <pre class="collapsible-full">{raw}</pre>
-                                </details>
-                            </td>
-                        </tr>
-                    }
-                    .into_any(),
-                );
-
-                // Insert summary row after content_block_stop
-                if event_type == "content_block_stop" {
-                    let index = data.get("index").and_then(|field| field.as_i64()).unwrap_or(0);
-                    rows.push(render_sse_block_summary(
-                        &block_types,
-                        &block_names,
-                        &block_json,
-                        &block_text,
-                        index,
-                    ));
+        if let Ok(mut sse_events) = serde_json::from_str::<Vec<serde_json::Value>>(events_json) {
+            let mut count_label = "SSE events";
+            if sse_events.is_empty() {
+                let rebuilt = load_response_events(None, req.response_body.as_deref());
+                if !rebuilt.is_empty() {
+                    sse_events = rebuilt;
+                    count_label = "events rebuilt from the JSON response";
                 }
             }
+            let count = format!("{} {}", sse_events.len(), count_label);
+            let SseRows {
+                rows,
+                visible_count,
+            } = build_sse_rows(&sse_events, options);
+            let pagination = Pagination::new(
+                options.page,
+                visible_count,
+                options.count_per_page(sse_events.len()),
+                options.base_url,
+                options.build_extra_params(),
+            );
+            let delta_toggle = render_sse_delta_toggle(options);
+            let nav_top = pagination_nav(&pagination);
+            let nav_bottom = pagination_nav(&pagination);
 
             return view! {
-                {count}" SSE events"
+                {count}
+                {delta_toggle}
+                {nav_top}
                 <table>
                     <tr><th>"#"</th><th>"Event"</th><th>"Data"</th><th>"Raw"</th></tr>
                     {rows}
                 </table>
+                {nav_bottom}
             }
             .into_any();
         }
@@ -511,6 +639,57 @@ mod tests {
         assert!(block_text.is_empty());
     }
 
+    // --- build_sse_rows tests ---
+
+    fn build_block_events(delta_count: usize) -> Vec<serde_json::Value> {
+        let mut events = vec![serde_json::json!({
+            "event": "content_block_start",
+            "data": {"index": 0, "content_block": {"type": "text"}}
+        })];
+        events.extend((0..delta_count).map(|_| {
+            serde_json::json!({
+                "event": "content_block_delta",
+                "data": {"index": 0, "delta": {"type": "text_delta", "text": "x"}}
+            })
+        }));
+        events.push(serde_json::json!({"event": "content_block_stop", "data": {"index": 0}}));
+        events
+    }
+
+    #[test]
+    fn collapsed_view_hides_delta_rows() {
+        let events = build_block_events(3);
+        let options = SseViewOptions::from_query("/sse", &HashMap::new());
+        let sse_rows = build_sse_rows(&events, &options);
+        assert_eq!(sse_rows.visible_count, 2);
+        // start, stop, and the reconstructed block summary
+        assert_eq!(sse_rows.rows.len(), 3);
+    }
+
+    #[test]
+    fn expanded_view_shows_delta_rows() {
+        let events = build_block_events(3);
+        let query = HashMap::from([("deltas".to_string(), "expand".to_string())]);
+        let options = SseViewOptions::from_query("/sse", &query);
+        let sse_rows = build_sse_rows(&events, &options);
+        assert_eq!(sse_rows.visible_count, 5);
+        assert_eq!(sse_rows.rows.len(), 6);
+    }
+
+    #[test]
+    fn rows_limited_to_requested_page() {
+        let events = build_block_events(SSE_EVENTS_PER_PAGE as usize);
+        let query = HashMap::from([
+            ("deltas".to_string(), "expand".to_string()),
+            ("page".to_string(), "2".to_string()),
+        ]);
+        let options = SseViewOptions::from_query("/sse", &query);
+        let sse_rows = build_sse_rows(&events, &options);
+        assert_eq!(sse_rows.visible_count, SSE_EVENTS_PER_PAGE + 2);
+        // the last delta, the stop event, and the block summary
+        assert_eq!(sse_rows.rows.len(), 3);
+    }
+
     // --- summarize_message_start tests ---
 
     #[test]
@@ -0,0 +1,254 @@
+//! Views for the blocks of Anthropic-defined tools (computer use, bash, the
+//! text editor and code execution), which otherwise show as opaque JSON.
+
+use leptos::{either::Either, prelude::*};
+use serde_json::Value;
+
+use crate::collapsible_block;
+
+use super::{highlight::render_highlighted_json, image::render_image_preview};
+
+/// Text editor commands whose input names a file.
+const FILE_EDIT_COMMANDS: &[&str] = &["view", "create", "str_replace", "insert", "undo_edit"];
+
+fn get_str<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|field| field.as_str())
+}
+
+/// A computer-use action in a line, e.g. `left_click at (120, 48)` or
+/// `type "hello"`.
+fn describe_computer_action(input: &Value) -> Option<String> {
+    let action = get_str(input, "action")?;
+    let mut description = action.to_string();
+    if let Some(coordinate) = input.get("coordinate").and_then(|field| field.as_array()) {
+        let coordinates = coordinate
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        description.push_str(&format!(" at ({})", coordinates));
+    }
+    if let Some(text) = get_str(input, "text") {
+        description.push_str(&format!(" {:?}", text));
+    }
+    Some(description)
+}
+
+/// A file edit in a line, e.g. `str_replace /repo/src/main.rs`.
+fn describe_file_edit(input: &Value) -> Option<String> {
+    let command =
+        get_str(input, "command").filter(|command| FILE_EDIT_COMMANDS.contains(command))?;
+    let path = get_str(input, "path")?;
+    let mut description = format!("{} {}", command, path);
+    if let Some(insert_line) = input.get("insert_line").and_then(|field| field.as_i64()) {
+        description.push_str(&format!(" after line {}", insert_line));
+    }
+    Some(description)
+}
+
+/// Lines prefixed `-` for removed text and `+` for added text.
+fn format_edit_diff(old_text: Option<&str>, new_text: Option<&str>) -> String {
+    let removed = old_text
+        .into_iter()
+        .flat_map(str::lines)
+        .map(|line| format!("-{}", line));
+    let added = new_text
+        .into_iter()
+        .flat_map(str::lines)
+        .map(|line| format!("+{}", line));
+    removed.chain(added).collect::<Vec<_>>().join("\n")
+}
+
+/// What a known tool call does, shown above its params: the shell command of
+/// a bash call, the action of a computer-use call, or the file and changed
+/// lines of a text editor call.
+pub fn render_tool_use_summary(block: &Value) -> Option<AnyView> {
+    let name = get_str(block, "name").unwrap_or("");
+    let input = block.get("input")?;
+    if name.contains("bash") {
+        let command_line = format!("$ {}", get_str(input, "command")?);
+        return Some(view! { <pre class="code">{command_line}</pre> }.into_any());
+    }
+    if let Some(description) = describe_file_edit(input) {
+        let diff = format_edit_diff(
+            get_str(input, "old_str"),
+            get_str(input, "new_str").or(get_str(input, "file_text")),
+        );
+        let diff_view = (!diff.is_empty()).then(|| view! { <pre class="code">{diff}</pre> });
+        return Some(view! { <div>{description}</div> {diff_view} }.into_any());
+    }
+    let description = describe_computer_action(input)?;
+    Some(view! { <div>{description}</div> }.into_any())
+}
+
+/// A tool_result's content: text parts as text and image parts, such as
+/// computer-use screenshots, inline.
+pub fn render_tool_result_content(content: Option<&Value>) -> AnyView {
+    let Some(content) = content else {
+        return ().into_any();
+    };
+    if let Some(text) = content.as_str() {
+        return collapsible_block(text, "");
+    }
+    let Some(parts) = content.as_array() else {
+        return render_highlighted_json(content);
+    };
+    parts
+        .iter()
+        .map(|part| match get_str(part, "type") {
+            Some("text") => collapsible_block(get_str(part, "text").unwrap_or(""), ""),
+            Some("image") => render_image_preview(part),
+            _ => render_highlighted_json(part),
+        })
+        .collect::<Vec<_>>()
+        .into_any()
+}
+
+/// Whether a block is the result of a server-side code execution tool, e.g.
+/// `bash_code_execution_tool_result`.
+pub fn is_code_execution_result(block_type: &str) -> bool {
+    block_type.ends_with("code_execution_tool_result")
+}
+
+/// What a `text_editor_code_execution` call did to the file, e.g.
+/// `replaced 2 lines at line 3 with 1`.
+fn describe_text_editor_result(content: &Value) -> Option<String> {
+    let get_count = |key: &str| content.get(key).and_then(|field| field.as_i64());
+    match get_str(content, "type")? {
+        "text_editor_code_execution_view_result" => Some(match get_count("num_lines") {
+            Some(num_lines) => format!("viewed {} lines", num_lines),
+            None => "viewed file".to_string(),
+        }),
+        "text_editor_code_execution_create_result" => {
+            let is_file_update = content
+                .get("is_file_update")
+                .and_then(|field| field.as_bool())
+                .unwrap_or(false);
+            let description = if is_file_update {
+                "updated file"
+            } else {
+                "created file"
+            };
+            Some(description.to_string())
+        }
+        "text_editor_code_execution_str_replace_result" => Some(format!(
+            "replaced {} lines at line {} with {}",
+            get_count("old_lines")?,
+            get_count("old_start")?,
+            get_count("new_lines")?
+        )),
+        _ => None,
+    }
+}
+
+/// Stdout, stderr and exit code of a code execution result, or the summary
+/// and text of a text editor result. Errors show their code.
+pub fn render_code_execution_result(content: Option<&Value>) -> AnyView {
+    let Some(content) = content else {
+        return ().into_any();
+    };
+    let content_type = get_str(content, "type").unwrap_or("");
+    if content_type.ends_with("_error") {
+        let error_code = get_str(content, "error_code").unwrap_or("unknown");
+        let error_line = format!("error: {}", error_code);
+        return view! { <div>{error_line}</div> }.into_any();
+    }
+    if let Some(return_code) = content.get("return_code").and_then(|field| field.as_i64()) {
+        let stdout = get_str(content, "stdout").unwrap_or("");
+        let stderr = get_str(content, "stderr").unwrap_or("");
+        let stdout_view = (!stdout.is_empty()).then(|| collapsible_block(stdout, ""));
+        let stderr_view = (!stderr.is_empty()).then(|| collapsible_block(stderr, "stderr"));
+        let exit_line = format!("exit code {}", return_code);
+        return view! {
+            <div>{exit_line}</div>
+            {stdout_view}
+            {stderr_view}
+        }
+        .into_any();
+    }
+    let Some(description) = describe_text_editor_result(content) else {
+        return render_highlighted_json(content);
+    };
+    let text = get_str(content, "content")
+        .map(str::to_string)
+        .or_else(|| {
+            let lines = content.get("lines").and_then(|field| field.as_array())?;
+            Some(
+                lines
+                    .iter()
+                    .filter_map(|line| line.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        })
+        .filter(|text| !text.is_empty());
+    let text_view = match text {
+        Some(text) => Either::Left(collapsible_block(&text, "")),
+        None => Either::Right(()),
+    };
+    view! { <div>{description}</div> {text_view} }.into_any()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn describe_computer_action_with_coordinate_and_text() {
+        assert_eq!(
+            describe_computer_action(&json!({"action": "left_click", "coordinate": [120, 48]}))
+                .as_deref(),
+            Some("left_click at (120, 48)")
+        );
+        assert_eq!(
+            describe_computer_action(&json!({"action": "type", "text": "hello"})).as_deref(),
+            Some("type \"hello\"")
+        );
+        assert_eq!(describe_computer_action(&json!({"command": "ls"})), None);
+    }
+
+    #[test]
+    fn render_tool_use_summary_shows_bash_command_and_edit_diff() {
+        let bash = json!({"name": "bash", "input": {"command": "ls -la"}});
+        let html = render_tool_use_summary(&bash).unwrap().to_html();
+        assert!(html.contains("$ ls -la"));
+        let edit = json!({
+            "name": "str_replace_based_edit_tool",
+            "input": {"command": "str_replace", "path": "/a.rs", "old_str": "x", "new_str": "y"}
+        });
+        let html = render_tool_use_summary(&edit).unwrap().to_html();
+        assert!(html.contains("str_replace /a.rs"));
+        assert!(html.contains("-x\n+y"));
+        assert!(render_tool_use_summary(&json!({"name": "get", "input": {}})).is_none());
+    }
+
+    #[test]
+    fn render_code_execution_result_shows_streams_and_edits() {
+        let bash_result = json!({
+            "type": "bash_code_execution_result",
+            "stdout": "ok",
+            "stderr": "warn",
+            "return_code": 1
+        });
+        let html = render_code_execution_result(Some(&bash_result)).to_html();
+        assert!(html.contains("exit code 1"));
+        assert!(html.contains("stderr"));
+        let str_replace_result = json!({
+            "type": "text_editor_code_execution_str_replace_result",
+            "old_start": 3,
+            "old_lines": 2,
+            "new_start": 3,
+            "new_lines": 1,
+            "lines": ["-a", "-b", "+c"]
+        });
+        assert_eq!(
+            describe_text_editor_result(&str_replace_result).as_deref(),
+            Some("replaced 2 lines at line 3 with 1")
+        );
+        assert!(is_code_execution_result(
+            "text_editor_code_execution_tool_result"
+        ));
+        assert!(!is_code_execution_result("tool_result"));
+    }
+}
@@ -1,5 +1,7 @@
 use super::common::{build_request_subpage_defs, count_json_array, render_detail_page_content};
-use super::sse::render_response_sse;
+use super::messages::{render_messages, MessagesViewOptions};
+use super::rendered::{build_response_message, render_response_message};
+use super::sse::{render_response_sse, SseViewOptions};
 use common::models::{ProxyRequest, Session};
 use leptos::prelude::*;
 use std::collections::HashMap;
@@ -10,6 +12,167 @@ pub fn parse_rounds(json: Option<&str>) -> Option<Vec<serde_json::Value>> {
     json.and_then(|json_str| serde_json::from_str::<Vec<serde_json::Value>>(json_str).ok())
 }
 
+/// The conversation the model went through across the rounds: the
+/// intercepted response, each round's tool_results and the response to them,
+/// ending with the response the client got. The client's own messages are
+/// left out. Empty when no round was recorded.
+pub fn build_merged_transcript(rounds: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    let (Some(first_round), Some(last_round)) = (rounds.first(), rounds.last()) else {
+        return Vec::new();
+    };
+    let followup_messages = |round: &serde_json::Value| {
+        round
+            .pointer("/followup_body/messages")
+            .and_then(|field| field.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    // Each follow-up adds an assistant turn and a tool_results turn to the
+    // messages it was sent after, so the first one ends two past the client's.
+    let client_message_count = followup_messages(first_round).len().saturating_sub(2);
+    let mut transcript: Vec<_> = followup_messages(last_round)
+        .into_iter()
+        .skip(client_message_count)
+        .collect();
+
+    let events_json = last_round
+        .get("response_events")
+        .map(|events| events.to_string());
+    let response_body = last_round
+        .get("response_body")
+        .and_then(|field| field.as_str());
+    if let Some(response_message) = build_response_message(events_json.as_deref(), response_body) {
+        transcript.push(serde_json::json!({
+            "role": "assistant",
+            "content": response_message.content_blocks,
+        }));
+    }
+    transcript
+}
+
+/// An agent sub-request made to answer one tool call of an interception round.
+#[derive(Debug, PartialEq)]
+pub struct AgentCall<'a> {
+    /// 1-based round number.
+    pub round_number: usize,
+    pub tool_name: &'a str,
+    /// The fetched URL, when the round recorded one.
+    pub url: Option<&'a str>,
+    pub agent_request_id: &'a str,
+}
+
+/// Every agent sub-request of the rounds, in round and call order.
+pub fn collect_agent_calls(rounds: &[serde_json::Value]) -> Vec<AgentCall<'_>> {
+    let mut agent_calls = Vec::new();
+    for (round_index, round) in rounds.iter().enumerate() {
+        let Some(agent_request_ids) = round
+            .get("agent_request_ids")
+            .and_then(|field| field.as_array())
+        else {
+            continue;
+        };
+        for (call_index, agent_request_id) in agent_request_ids.iter().enumerate() {
+            let Some(agent_request_id) = agent_request_id.as_str() else {
+                continue;
+            };
+            let tool_name = round
+                .get("tool_names")
+                .and_then(|field| field.get(call_index))
+                .and_then(|field| field.as_str())
+                .unwrap_or("");
+            let url = round
+                .get("fetches")
+                .and_then(|field| field.get(call_index))
+                .and_then(|fetch| fetch.get("url"))
+                .and_then(|field| field.as_str());
+            agent_calls.push(AgentCall {
+                round_number: round_index + 1,
+                tool_name,
+                url,
+                agent_request_id,
+            });
+        }
+    }
+    agent_calls
+}
+
+/// The "Agent Calls" section: each agent sub-request's prompt and response
+/// inline, with links to its intercept view and its full request row.
+fn render_agent_calls(
+    req: &ProxyRequest,
+    base: &str,
+    agent_calls: &[AgentCall<'_>],
+    agent_requests: &[ProxyRequest],
+) -> AnyView {
+    if agent_calls.is_empty() {
+        return ().into_any();
+    }
+    let agent_call_views: Vec<_> = agent_calls
+        .iter()
+        .map(|agent_call| {
+            let heading = format!(
+                "Round {} - {}{}",
+                agent_call.round_number,
+                agent_call.tool_name,
+                agent_call
+                    .url
+                    .map(|url| format!(" - {}", url))
+                    .unwrap_or_default()
+            );
+            let agent_href = format!("{}/agent/{}", base, agent_call.agent_request_id);
+            let request_href = format!(
+                "/_dashboard/sessions/{}/requests/{}",
+                req.session_id, agent_call.agent_request_id
+            );
+            let agent_request = agent_requests
+                .iter()
+                .find(|agent_request| agent_request.id.to_string() == agent_call.agent_request_id);
+            let body_view = match agent_request {
+                Some(agent_request) => {
+                    let status = agent_request
+                        .response_status
+                        .map(|status| status.to_string())
+                        .unwrap_or_else(|| "-".to_string());
+                    let model = agent_request.model.clone().unwrap_or_default();
+                    let prompt_view = render_messages(
+                        agent_request.messages_json.as_deref().unwrap_or("[]"),
+                        &MessagesViewOptions {
+                            order: "asc",
+                            keep_tool_pairs: 0,
+                            render_markdown: false,
+                            blocks_url: None,
+                        },
+                    );
+                    view! {
+                        <p>{format!("Model: {} | Status: {}", model, status)}</p>
+                        <h4>"Prompt"</h4>
+                        {prompt_view}
+                        <h4>"Response"</h4>
+                        {render_response_message(agent_request, false)}
+                    }
+                    .into_any()
+                }
+                None => view! { <p>"The agent request row was not found."</p> }.into_any(),
+            };
+            view! {
+                <h3>{heading}</h3>
+                <p>
+                    <a href={agent_href}>"Agent request view"</a>
+                    " | "
+                    <a href={request_href}>"Full request row"</a>
+                </p>
+                {body_view}
+            }
+        })
+        .collect();
+    view! {
+        <h2>"Agent Calls"</h2>
+        <p>"Accepted WebFetch calls are summarized by a separate request to the model. Each one is shown with its prompt and response."</p>
+        {agent_call_views}
+    }
+    .into_any()
+}
+
 /// Build breadcrumbs for WebFetch Intercept pages.
 /// `trail` contains `(label, href)` pairs; the last entry with `None` href is the current page.
 fn build_webfetch_breadcrumbs(
@@ -47,8 +210,15 @@ fn build_webfetch_breadcrumbs(
     crumbs
 }
 
-/// WebFetch Intercept hub — shows intercepted response SSE inline + agent request subpage links.
-pub fn render_webfetch_intercept_hub(req: &ProxyRequest, session: &Session) -> String {
+/// WebFetch Intercept hub — shows intercepted response SSE inline, the agent
+/// calls with their prompts and responses, and agent request subpage links.
+/// `agent_requests` holds the logged agent sub-requests of the rounds.
+pub fn render_webfetch_intercept_hub(
+    req: &ProxyRequest,
+    session: &Session,
+    query: &HashMap<String, String>,
+    agent_requests: &[ProxyRequest],
+) -> String {
     let base = format!(
         "/_dashboard/sessions/{}/requests/{}/webfetch_intercept",
         req.session_id, req.id
@@ -59,7 +229,7 @@ pub fn render_webfetch_intercept_hub(req: &ProxyRequest, session: &Session) -> S
         let mut sse_req = req.clone();
         sse_req.response_events_json = req.webfetch_first_response_events_json.clone();
         sse_req.response_body = req.webfetch_first_response_body.clone();
-        let sse_view = render_response_sse(&sse_req);
+        let sse_view = render_response_sse(&sse_req, &SseViewOptions::from_query(&base, query));
 
         let total_count = count_json_array(req.webfetch_first_response_events_json.as_deref());
         let total_view: AnyView = total_count
@@ -79,32 +249,98 @@ pub fn render_webfetch_intercept_hub(req: &ProxyRequest, session: &Session) -> S
         ().into_any()
     };
 
-    // Collect all agent_request_ids from all rounds
     let rounds = parse_rounds(req.webfetch_rounds_json.as_deref()).unwrap_or_default();
-    let agent_ids: Vec<&str> = rounds
+    let agent_calls = collect_agent_calls(&rounds);
+    let agent_calls_view = render_agent_calls(req, &base, &agent_calls, agent_requests);
+
+    // Fetched pages with their original length and how much of them was read
+    let fetch_rows: Vec<_> = rounds
         .iter()
-        .flat_map(|round| {
-            round.get("agent_request_ids")
-                .and_then(|field| field.as_array())
-                .map(|array| array.iter().filter_map(|field| field.as_str()).collect::<Vec<_>>())
-                .unwrap_or_default()
+        .filter_map(|round| round.get("fetches").and_then(|field| field.as_array()))
+        .flatten()
+        .filter(|fetch| fetch.is_object())
+        .map(|fetch| {
+            let url = fetch
+                .get("url")
+                .and_then(|field| field.as_str())
+                .unwrap_or("")
+                .to_string();
+            let original_length = fetch
+                .get("original_length")
+                .and_then(|field| field.as_u64())
+                .map(|length| length.to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            let bytes_read = fetch
+                .get("bytes_read")
+                .and_then(|field| field.as_u64())
+                .unwrap_or(0)
+                .to_string();
+            let is_truncated = fetch
+                .get("truncated")
+                .and_then(|field| field.as_bool())
+                .unwrap_or(false);
+            let truncated = if is_truncated { "yes" } else { "no" };
+            let is_cached = fetch
+                .get("cached")
+                .and_then(|field| field.as_bool())
+                .unwrap_or(false);
+            let cached = if is_cached { "yes" } else { "no" };
+            view! {
+                <tr>
+                    <td><code>{url}</code></td>
+                    <td>{original_length}</td>
+                    <td>{bytes_read}</td>
+                    <td>{truncated}</td>
+                    <td>{cached}</td>
+                </tr>
+            }
         })
         .collect();
+    let fetches_view: AnyView = if fetch_rows.is_empty() {
+        ().into_any()
+    } else {
+        view! {
+            <h2>"Fetches"</h2>
+            <table>
+                <tr>
+                    <th>"URL"</th>
+                    <th>"Original Length"</th>
+                    <th>"Bytes Read"</th>
+                    <th>"Truncated"</th>
+                    <th>"Cached"</th>
+                </tr>
+                {fetch_rows}
+            </table>
+        }
+        .into_any()
+    };
 
-    let subpages: Vec<Subpage> = agent_ids
-        .iter()
-        .map(|agent_id| {
-            let short = &agent_id[..8.min(agent_id.len())];
-            Subpage::new(
-                format!("Agent Request #{}", short),
-                format!("{}/agent/{}", base, agent_id),
-                String::new(),
-            )
-        })
+    let transcript = build_merged_transcript(&rounds);
+    let transcript_subpage = (!transcript.is_empty()).then(|| {
+        Subpage::new(
+            "Merged Transcript",
+            format!("{}/transcript", base),
+            transcript.len(),
+        )
+    });
+    let agent_subpages = agent_calls.iter().map(|agent_call| {
+        let agent_id = agent_call.agent_request_id;
+        let short = &agent_id[..8.min(agent_id.len())];
+        Subpage::new(
+            format!("Agent Request #{}", short),
+            format!("{}/agent/{}", base, agent_id),
+            String::new(),
+        )
+    });
+    let subpages: Vec<Subpage> = transcript_subpage
+        .into_iter()
+        .chain(agent_subpages)
         .collect();
 
     let content = view! {
         {intercepted_view}
+        {fetches_view}
+        {agent_calls_view}
     };
 
     Page {
@@ -126,6 +362,62 @@ pub fn render_webfetch_intercept_hub(req: &ProxyRequest, session: &Session) -> S
     .render()
 }
 
+/// Merged Transcript page: the rounds of an interception as one
+/// conversation, so it reads the way the model went through it.
+pub fn render_webfetch_transcript_view(req: &ProxyRequest, session: &Session) -> String {
+    let base = format!(
+        "/_dashboard/sessions/{}/requests/{}/webfetch_intercept",
+        req.session_id, req.id
+    );
+    let rounds = parse_rounds(req.webfetch_rounds_json.as_deref()).unwrap_or_default();
+    let transcript = build_merged_transcript(&rounds);
+
+    let content: AnyView = if transcript.is_empty() {
+        view! { <p>"No interception rounds were recorded for this request."</p> }.into_any()
+    } else {
+        let round_count = format!("{} round(s), {} message(s)", rounds.len(), transcript.len());
+        let messages_view = render_messages(
+            &serde_json::Value::Array(transcript).to_string(),
+            &MessagesViewOptions {
+                order: "asc",
+                keep_tool_pairs: 0,
+                render_markdown: false,
+                blocks_url: None,
+            },
+        );
+        view! {
+            <h2>"Merged Transcript"</h2>
+            <p>
+                {round_count}
+                " "
+                <small>"From the intercepted response to the one the client got, with the tool_results sent in between. The client's own messages are left out."</small>
+            </p>
+            {messages_view}
+        }
+        .into_any()
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Request #{} - WebFetch Intercept - Merged Transcript",
+            session.name, req.id
+        ),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            req,
+            &[
+                ("WebFetch Intercept", Some(base)),
+                ("Merged Transcript", None),
+            ],
+        ),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
 /// WebFetch agent request overview — like render_request_detail_view but with webfetch breadcrumbs.
 pub fn render_webfetch_agent_overview(
     req: &ProxyRequest,
@@ -242,3 +534,77 @@ pub fn render_webfetch_agent_page(
     }
     .render()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_transcript_joins_rounds_after_client_messages() {
+        let client_message = serde_json::json!({"role": "user", "content": "Read example.com"});
+        let first_tool_use = serde_json::json!({"role": "assistant", "content": [
+            {"type": "tool_use", "id": "toolu_1", "name": "WebFetch", "input": {}}
+        ]});
+        let first_results = serde_json::json!({"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "toolu_1", "content": "page 1"}
+        ]});
+        let second_tool_use = serde_json::json!({"role": "assistant", "content": [
+            {"type": "tool_use", "id": "toolu_2", "name": "WebFetch", "input": {}}
+        ]});
+        let second_results = serde_json::json!({"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "toolu_2", "content": "page 2"}
+        ]});
+        let rounds = vec![
+            serde_json::json!({
+                "followup_body": {"messages": [client_message, first_tool_use, first_results]},
+            }),
+            serde_json::json!({
+                "followup_body": {"messages": [
+                    client_message, first_tool_use, first_results, second_tool_use, second_results
+                ]},
+                "response_events": [],
+                "response_body": r#"{"content": [{"type": "text", "text": "Done"}]}"#,
+            }),
+        ];
+
+        let transcript = build_merged_transcript(&rounds);
+        assert_eq!(transcript.len(), 5);
+        assert_eq!(transcript[0], first_tool_use);
+        assert_eq!(transcript[3], second_results);
+        assert_eq!(transcript[4]["role"], "assistant");
+        assert_eq!(transcript[4]["content"][0]["text"], "Done");
+        assert!(build_merged_transcript(&[]).is_empty());
+    }
+
+    #[test]
+    fn agent_calls_follow_rounds_and_calls() {
+        let rounds = vec![
+            serde_json::json!({
+                "tool_names": ["WebFetch", "WebSearch"],
+                "agent_request_ids": ["agent-1", null],
+                "fetches": [{"url": "https://example.com/a"}, null],
+            }),
+            serde_json::json!({
+                "tool_names": ["WebFetch"],
+                "agent_request_ids": ["agent-2"],
+            }),
+        ];
+        assert_eq!(
+            collect_agent_calls(&rounds),
+            vec![
+                AgentCall {
+                    round_number: 1,
+                    tool_name: "WebFetch",
+                    url: Some("https://example.com/a"),
+                    agent_request_id: "agent-1",
+                },
+                AgentCall {
+                    round_number: 2,
+                    tool_name: "WebFetch",
+                    url: None,
+                    agent_request_id: "agent-2",
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use common::models::{DuplicateGroup, Session};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+fn render_duplicate_group_row(
+    session: &Session,
+    duplicate_group: &DuplicateGroup,
+) -> impl IntoView {
+    let first_request_id = duplicate_group.first_request_id.to_string();
+    let first_href = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        session.id, first_request_id
+    );
+    let first_label = format!(
+        "#{}",
+        first_request_id.get(..8).unwrap_or(&first_request_id)
+    );
+    let body_hash = duplicate_group
+        .body_hash
+        .get(..12)
+        .unwrap_or(&duplicate_group.body_hash)
+        .to_string();
+    view! {
+        <tr>
+            <td>{duplicate_group.request_count.to_string()}</td>
+            <td><a href={first_href}>{first_label}</a></td>
+            <td>{duplicate_group.title.clone().unwrap_or_default()}</td>
+            <td>{duplicate_group.model.clone().unwrap_or_default()}</td>
+            <td>{duplicate_group.last_seen_at.clone()}</td>
+            <td title={duplicate_group.body_hash.clone()}>{body_hash}</td>
+        </tr>
+    }
+}
+
+/// Bodies the session sent more than once, e.g. an agent resending the same
+/// request in a loop, most repeated first.
+pub fn render_duplicates_view(session: &Session, duplicate_groups: &[DuplicateGroup]) -> String {
+    let rows: Vec<_> = duplicate_groups
+        .iter()
+        .map(|duplicate_group| render_duplicate_group_row(session, duplicate_group))
+        .collect();
+
+    let content = view! {
+        <h2>"Top Duplicates"</h2>
+        <p>"Requests with identical bodies, ignoring key order and whitespace."</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No duplicate requests."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr>
+                        <th>"Count"</th>
+                        <th>"First Request"</th>
+                        <th>"Title"</th>
+                        <th>"Model"</th>
+                        <th>"Last Seen"</th>
+                        <th>"Body Hash"</th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Duplicates", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session.id),
+            ),
+            Breadcrumb::current("Duplicates"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,64 @@
+use common::models::{ErrorSummary, RecentRequest};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+use crate::home::render_recent_requests;
+
+fn render_error_summaries(error_summaries: &[ErrorSummary]) -> impl IntoView {
+    if error_summaries.is_empty() {
+        return Either::Left(view! { <p>"No failed upstream calls."</p> });
+    }
+    let rows = error_summaries
+        .iter()
+        .map(|error_summary| {
+            let session_href = format!("/_dashboard/sessions/{}", error_summary.session_id);
+            view! {
+                <tr>
+                    <td><a href={session_href}>{error_summary.session_name.clone()}</a></td>
+                    <td>{error_summary.error_kind.clone()}</td>
+                    <td>{error_summary.error_count.to_string()}</td>
+                    <td>{error_summary.last_seen_at.clone()}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Session"</th>
+                <th>"Kind"</th>
+                <th>"Count"</th>
+                <th>"Last Seen"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// Failed upstream calls: connection errors, timeouts, broken streams and
+/// non-2xx responses, counted by session and kind over the last `days` days.
+pub fn render_errors_view(
+    days: i64,
+    error_summaries: &[ErrorSummary],
+    recent_failures: &[RecentRequest],
+) -> String {
+    let content = view! {
+        <h2>{format!("Failures by Session (last {} days)", days)}</h2>
+        {render_error_summaries(error_summaries)}
+        <h2>"Recent Failures"</h2>
+        {render_recent_requests(recent_failures)}
+    };
+
+    Page {
+        title: "Gateway Proxy - Errors".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("Errors"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,143 @@
+use crate::whitelist::build_webfetch_breadcrumbs;
+use common::models::{Session, WebfetchCacheEntry};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+fn build_fetch_cache_url(session: &Session) -> String {
+    format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/cache",
+        session.id
+    )
+}
+
+fn format_original_length(entry: &WebfetchCacheEntry) -> String {
+    entry
+        .original_length
+        .map(|length| length.to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// List the session's cached pages. Entries fetched at or before `fresh_after`
+/// ("YYYY-MM-DD HH:MM:SS", UTC) are shown as expired.
+pub fn render_fetch_cache_view(
+    session: &Session,
+    entries: &[WebfetchCacheEntry],
+    ttl_secs: u64,
+    fresh_after: &str,
+) -> String {
+    let cache_url = build_fetch_cache_url(session);
+    let clear_action = format!("{}/clear", cache_url);
+    let total = entries.len();
+
+    let rows: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let view_href = format!("{}/{}", cache_url, entry.id);
+            let delete_action = format!("{}/{}/delete", cache_url, entry.id);
+            let truncated = if entry.truncated { "yes" } else { "no" };
+            let status = if ttl_secs > 0 && entry.fetched_at.as_str() > fresh_after {
+                "fresh"
+            } else {
+                "expired"
+            };
+            view! {
+                <tr>
+                    <td><a href={view_href}><code>{entry.url.clone()}</code></a></td>
+                    <td>{entry.fetched_at.clone()}</td>
+                    <td>{format_original_length(entry)}</td>
+                    <td>{entry.bytes_read.to_string()}</td>
+                    <td>{truncated}</td>
+                    <td>{status}</td>
+                    <td>
+                        <form method="POST" action={delete_action}>
+                            <button type="submit">"Delete"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let ttl_text = if ttl_secs == 0 {
+        "The cache is disabled (TTL 0); existing entries are not reused.".to_string()
+    } else {
+        format!(
+            "Accepted WebFetch calls reuse a page fetched by this session within the last {} seconds instead of fetching it again. Cached tool_results are marked as such.",
+            ttl_secs
+        )
+    };
+
+    let content = view! {
+        <h2>"Fetch Cache"</h2>
+        <p>{ttl_text}</p>
+        <p>{format!("Total: {}", total)}</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No cached pages."</p> })
+        } else {
+            Either::Right(view! {
+                <form method="POST" action={clear_action}>
+                    <button type="submit">"Clear Cache"</button>
+                </form>
+                <table>
+                    <tr>
+                        <th>"URL"</th>
+                        <th>"Fetched (UTC)"</th>
+                        <th>"Original Length"</th>
+                        <th>"Bytes Read"</th>
+                        <th>"Truncated"</th>
+                        <th>"Status"</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Fetch Cache", session.name),
+        breadcrumbs: build_webfetch_breadcrumbs(session, vec![Breadcrumb::current("Fetch Cache")]),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_fetch_cache_entry_view(session: &Session, entry: &WebfetchCacheEntry) -> String {
+    let delete_action = format!("{}/{}/delete", build_fetch_cache_url(session), entry.id);
+
+    let content = view! {
+        <h2>"Cached Text"</h2>
+        <pre>{entry.content.clone()}</pre>
+        <form method="POST" action={delete_action}>
+            <button type="submit">"Delete"</button>
+        </form>
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Fetch Cache - {}",
+            session.name, entry.url
+        ),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![
+                Breadcrumb::link("Fetch Cache", build_fetch_cache_url(session)),
+                Breadcrumb::current("Entry"),
+            ],
+        ),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![
+            InfoRow::new("URL", &entry.url),
+            InfoRow::new("Fetched (UTC)", &entry.fetched_at),
+            InfoRow::new("Original Length", &format_original_length(entry)),
+            InfoRow::new("Bytes Read", &entry.bytes_read.to_string()),
+            InfoRow::new("Truncated", if entry.truncated { "yes" } else { "no" }),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -1,14 +1,188 @@
-use templates::{Breadcrumb, Page, Subpage};
+use common::models::{RecentRequest, RequestStats};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, InfoRow, Page, Subpage};
+
+use crate::models::build_model_requests_href;
+
+/// Share of stored responses with an error status, e.g. `"25.0% (1 of 4)"`.
+fn format_error_rate(request_stats: &RequestStats) -> String {
+    if request_stats.responded_count == 0 {
+        return "No responses yet".to_string();
+    }
+    format!(
+        "{:.1}% ({} of {})",
+        request_stats.error_count as f64 * 100.0 / request_stats.responded_count as f64,
+        request_stats.error_count,
+        request_stats.responded_count
+    )
+}
+
+fn build_home_info_rows(request_stats: &RequestStats) -> Vec<InfoRow> {
+    vec![
+        InfoRow::new("Requests Today", &request_stats.requests_today.to_string()),
+        InfoRow::new("Error Rate", &format_error_rate(request_stats)),
+        InfoRow::new(
+            "Intercepted Tool Calls",
+            &request_stats.intercepted_tool_calls.to_string(),
+        ),
+    ]
+}
+
+fn render_model_request_counts(model_request_counts: &[(String, i64)]) -> impl IntoView {
+    if model_request_counts.is_empty() {
+        return Either::Left(view! { <p>"No requests yet."</p> });
+    }
+    let rows = model_request_counts
+        .iter()
+        .map(|(model, request_count)| {
+            let requests_href = build_model_requests_href(model);
+            view! {
+                <tr>
+                    <td><a href={requests_href}>{model.clone()}</a></td>
+                    <td>{request_count.to_string()}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Model"</th>
+                <th>"Requests"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// A listed request's status, with the kind of upstream failure if any, e.g. `"502 (connect)"`.
+fn format_request_status(recent_request: &RecentRequest) -> String {
+    let status = recent_request
+        .response_status
+        .map(|status| status.to_string())
+        .unwrap_or_default();
+    match &recent_request.error_kind {
+        Some(error_kind) => format!("{} ({})", status, error_kind),
+        None => status,
+    }
+}
+
+pub(crate) fn render_recent_requests(recent_requests: &[RecentRequest]) -> impl IntoView {
+    if recent_requests.is_empty() {
+        return Either::Left(view! { <p>"No requests yet."</p> });
+    }
+    let rows = recent_requests
+        .iter()
+        .map(|recent_request| {
+            let detail_href = format!(
+                "/_dashboard/sessions/{}/requests/{}",
+                recent_request.session_id, recent_request.id
+            );
+            let session_href = format!("/_dashboard/sessions/{}", recent_request.session_id);
+            let status = format_request_status(recent_request);
+            view! {
+                <tr>
+                    <td><a href={detail_href}>{recent_request.id.to_string()}</a></td>
+                    <td><a href={session_href}>{recent_request.session_name.clone()}</a></td>
+                    <td>{recent_request.method.clone()}</td>
+                    <td>{recent_request.path.clone()}</td>
+                    <td>{recent_request.model.clone().unwrap_or_default()}</td>
+                    <td>{status}</td>
+                    <td>{recent_request.created_at.clone()}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"ID"</th>
+                <th>"Session"</th>
+                <th>"Method"</th>
+                <th>"Path"</th>
+                <th>"Model"</th>
+                <th>"Status"</th>
+                <th>"Time"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// The dashboard home: links to each section, traffic across all sessions,
+/// and a box to find a request by its `x-proxy-request-id`.
+pub fn render_home_view(
+    session_count: i64,
+    profile_count: i64,
+    model_count: i64,
+    failure_count: i64,
+    pending_count: usize,
+    request_stats: &RequestStats,
+    recent_requests: &[RecentRequest],
+) -> String {
+    let content = view! {
+        <form method="GET" action="/_dashboard/find">
+            <input type="text" name="id" size="40" placeholder="x-proxy-request-id"/>
+            <input type="submit" value="Find Request"/>
+        </form>
+        <h2>"Requests by Model"</h2>
+        {render_model_request_counts(&request_stats.model_request_counts)}
+        <h2>"Recent Requests"</h2>
+        {render_recent_requests(recent_requests)}
+    };
 
-pub fn render_home_view(session_count: i64, profile_count: i64) -> String {
     Page {
         title: "Gateway Proxy - Home".to_string(),
         breadcrumbs: vec![Breadcrumb::current("Home")],
+        nav_links: vec![],
+        info_rows: build_home_info_rows(request_stats),
+        content,
         subpages: vec![
             Subpage::new("Sessions", "/_dashboard/sessions", session_count),
             Subpage::new("Profiles", "/_dashboard/filters", profile_count),
+            Subpage::new("Models", "/_dashboard/models", model_count),
+            Subpage::new("Errors", "/_dashboard/errors", failure_count),
+            Subpage::new("Pending Approvals", "/_dashboard/approvals", pending_count),
+            Subpage::new("Alerts", "/_dashboard/alerts", ""),
+            Subpage::new("Settings", "/_dashboard/settings", ""),
+            Subpage::new("User", "/_dashboard/user", ""),
+            Subpage::new("Database", "/_dashboard/admin/db", ""),
         ],
-        ..Default::default()
     }
     .render()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_error_rate_handles_no_responses() {
+        assert_eq!(
+            format_error_rate(&RequestStats::default()),
+            "No responses yet"
+        );
+        let request_stats = RequestStats {
+            responded_count: 8,
+            error_count: 2,
+            ..RequestStats::default()
+        };
+        assert_eq!(format_error_rate(&request_stats), "25.0% (2 of 8)");
+    }
+
+    #[test]
+    fn format_request_status_adds_error_kind() {
+        let recent_request = RecentRequest {
+            id: "00000000-0000-0000-0000-000000000002".parse().unwrap(),
+            session_id: "00000000-0000-0000-0000-000000000001".parse().unwrap(),
+            session_name: "s1".to_string(),
+            method: "POST".to_string(),
+            path: "/v1/messages".to_string(),
+            model: None,
+            response_status: Some(502),
+            error_kind: Some("connect".to_string()),
+            created_at: "2026-01-01 00:00:00".to_string(),
+        };
+        assert_eq!(format_request_status(&recent_request), "502 (connect)");
+    }
+}
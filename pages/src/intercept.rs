@@ -1,8 +1,79 @@
-use common::models::Session;
+use common::models::{PendingToolInfo, RunningInterceptInfo, Session};
+use leptos::prelude::*;
 use templates::{Breadcrumb, NavLink, Page, Subpage};
 
-pub fn render_intercept_view(session: &Session, pending_count: usize) -> String {
+use crate::webfetch::render_pending_approvals_section;
+
+fn render_running_intercept_row(
+    session_id: &str,
+    running_intercept: &RunningInterceptInfo,
+) -> impl IntoView {
+    let request_url = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        session_id, running_intercept.request_id
+    );
+    let cancel_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/running/{}/cancel",
+        session_id, running_intercept.request_id
+    );
+    let request_id = running_intercept.request_id.clone();
+    let round = running_intercept.round.to_string();
+    let running = format!("{}s", running_intercept.running_secs);
+
+    view! {
+        <tr>
+            <td><a href={request_url}>{request_id}</a></td>
+            <td>{round}</td>
+            <td>{running}</td>
+            <td>
+                <form method="POST" action={cancel_action}>
+                    <button type="submit">"Cancel"</button>
+                </form>
+            </td>
+        </tr>
+    }
+}
+
+fn render_running_intercepts_section(
+    session_id: &str,
+    running_intercepts: &[RunningInterceptInfo],
+) -> impl IntoView {
+    let running_count = if running_intercepts.is_empty() {
+        "No running interceptions.".to_string()
+    } else {
+        format!("{} running", running_intercepts.len())
+    };
+    let running_intercept_rows: Vec<_> = running_intercepts
+        .iter()
+        .map(|running_intercept| render_running_intercept_row(session_id, running_intercept))
+        .collect();
+
+    view! {
+        <h2>"Running Interceptions"</h2>
+        <p>
+            {running_count}
+            " "
+            <small>"Cancelling stops the rounds in flight and answers the client with the last good response, or an error when the follow-up fallback is \"error\". Reload to refresh."</small>
+        </p>
+        <table>
+            <thead>
+                <tr><th>"Request"</th><th>"Round"</th><th>"Running"</th><th></th></tr>
+            </thead>
+            {running_intercept_rows}
+        </table>
+    }
+}
+
+pub fn render_intercept_view(
+    session: &Session,
+    pending: &[(String, Vec<PendingToolInfo>)],
+    running_intercepts: &[RunningInterceptInfo],
+) -> String {
     let session_id = session.id.to_string();
+    let content = view! {
+        {render_pending_approvals_section(&session_id, pending)}
+        {render_running_intercepts_section(&session_id, running_intercepts)}
+    };
 
     Page {
         title: format!("Gateway Proxy - Session {} - Tool Intercept", session.name),
@@ -17,7 +88,7 @@ pub fn render_intercept_view(session: &Session, pending_count: usize) -> String
         ],
         nav_links: vec![NavLink::back()],
         info_rows: vec![],
-        content: (),
+        content,
         subpages: vec![
             Subpage::new(
                 "WebFetch Intercept",
@@ -31,13 +102,22 @@ pub fn render_intercept_view(session: &Session, pending_count: usize) -> String
                     "off"
                 },
             ),
+            Subpage::new(
+                "MCP Server",
+                format!("/_dashboard/sessions/{}/tool-intercept/mcp", session_id),
+                if session.mcp_server_transport.is_some() {
+                    "on"
+                } else {
+                    "off"
+                },
+            ),
             Subpage::new(
                 "Pending Approvals",
                 format!(
                     "/_dashboard/sessions/{}/tool-intercept/approvals",
                     session_id
                 ),
-                pending_count,
+                pending.len(),
             ),
         ],
     }
@@ -0,0 +1,91 @@
+use common::intercept_rules::{find_field_by_key, INTERCEPT_RULE_FIELDS};
+use common::models::{InterceptRule, Session};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+pub fn render_intercept_rules_view(session: &Session, rules: &[InterceptRule]) -> String {
+    let session_id = session.id.to_string();
+    let rules_url = format!("/_dashboard/sessions/{}/intercept-rules", session_id);
+    let field_options: Vec<_> = INTERCEPT_RULE_FIELDS
+        .iter()
+        .map(|field| {
+            let label = format!("{} — {}", field.label, field.description);
+            view! { <option value={field.key}>{label}</option> }
+        })
+        .collect();
+    let rows: Vec<_> = rules
+        .iter()
+        .map(|rule| {
+            let delete_action = format!("{}/{}/delete", rules_url, rule.id);
+            let field_label = find_field_by_key(&rule.field)
+                .map_or(rule.field.clone(), |field| field.label.to_string());
+            view! {
+                <tr>
+                    <td>{field_label}</td>
+                    <td><code>{rule.value.clone()}</code></td>
+                    <td>{rule.created_at.clone()}</td>
+                    <td>
+                        <form method="POST" action={delete_action}>
+                            <button type="submit">"Delete"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Intercept Rules"</h2>
+        <p>"Scope WebFetch interception and breakpoints to matching requests. When rules are set, a request is only intercepted when it matches every rule; without rules every request is."</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No rules configured."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr>
+                        <th>"Field"</th>
+                        <th>"Value"</th>
+                        <th>"Created"</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+        <h3>"New Rule"</h3>
+        <form method="POST" action={rules_url}>
+            <table>
+                <tr>
+                    <td><label>"Field"</label></td>
+                    <td><select name="field">{field_options}</select></td>
+                </tr>
+                <tr>
+                    <td><label>"Value"</label></td>
+                    <td><input type="text" name="value" size="40" required placeholder="claude-sonnet-4-5 or (?i)fetch"/></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Add"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Intercept Rules", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session_id),
+            ),
+            Breadcrumb::current("Intercept Rules"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -1,11 +1,36 @@
+pub mod alerts;
+pub mod approvals;
+pub mod batches;
+pub mod breakpoints;
+pub mod context_usage;
+pub mod database;
 pub mod detail;
+pub mod duplicates;
 pub mod error_inject;
+pub mod errors;
+pub mod fetch_cache;
 pub mod filters;
 pub mod home;
 pub mod intercept;
+pub mod intercept_rules;
+pub mod mcp;
+pub mod models;
+pub mod policies;
+pub mod presentation;
 pub mod requests;
 pub mod session_show;
 pub mod sessions;
+pub mod settings;
+pub mod timeline;
+pub mod tools;
+pub mod users;
+pub mod validation;
 pub mod webfetch;
+pub mod whitelist;
 
 pub use templates::collapsible_block;
+
+#[cfg(test)]
+mod snapshot_tests;
+#[cfg(test)]
+mod test_fixtures;
@@ -0,0 +1,120 @@
+use common::mcp::{find_by_key, MCP_STATUS_OK, MCP_TRANSPORTS};
+use common::models::Session;
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+/// One-line health of the session's MCP server, e.g. "OK: Called echo (checked ...)".
+pub fn describe_mcp_server_status(session: &Session) -> String {
+    let Some(status) = session.mcp_server_status.as_deref() else {
+        return "Not checked yet".to_string();
+    };
+    let status_label = if status == MCP_STATUS_OK {
+        "OK"
+    } else {
+        "Error"
+    };
+    format!(
+        "{}: {} (checked {} UTC)",
+        status_label,
+        session.mcp_server_status_detail.as_deref().unwrap_or(""),
+        session.mcp_server_checked_at.as_deref().unwrap_or("")
+    )
+}
+
+/// The session's MCP server as "transport: target", if one is set.
+pub fn describe_mcp_server(session: &Session) -> Option<String> {
+    let transport = session.mcp_server_transport.as_deref()?;
+    let target = session.mcp_server_target.as_deref()?;
+    let transport_label = find_by_key(transport).map_or(transport, |transport| transport.label);
+    Some(format!("{}: {}", transport_label, target))
+}
+
+pub fn render_mcp_server_view(session: &Session) -> String {
+    let session_id = session.id.to_string();
+    let form_action = format!("/_dashboard/sessions/{}/tool-intercept/mcp", session_id);
+    let check_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/mcp/check",
+        session_id
+    );
+    let clear_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/mcp/clear",
+        session_id
+    );
+    let active_transport = session.mcp_server_transport.clone().unwrap_or_default();
+    let target_value = session.mcp_server_target.clone().unwrap_or_default();
+    let transport_options: Vec<_> = MCP_TRANSPORTS
+        .iter()
+        .map(|transport| {
+            let selected = transport.key == active_transport;
+            let label = format!("{} — {}", transport.label, transport.description);
+            view! {
+                <option value={transport.key} selected={selected}>{label}</option>
+            }
+        })
+        .collect();
+    let server_description = describe_mcp_server(session);
+    let status_description = describe_mcp_server_status(session);
+
+    let content = view! {
+        <h2>"MCP Server"</h2>
+        <p>"Accepted calls to tools with an " <code>"mcp"</code> " executor in the config file are forwarded to this server with " <code>"tools/call"</code> ", and its result becomes the tool_result."</p>
+        {match server_description {
+            Some(server_description) => Either::Left(view! {
+                <p>
+                    "Server: " <code>{server_description}</code>
+                    <br/>
+                    "Health: " <strong>{status_description}</strong>
+                </p>
+                <p>
+                    <form method="POST" action={check_action}>
+                        <button type="submit">"Check Now"</button>
+                    </form>
+                    " "
+                    <form method="POST" action={clear_action}>
+                        <button type="submit">"Remove"</button>
+                    </form>
+                </p>
+            }),
+            None => Either::Right(view! { <p>"No MCP server is configured for this session."</p> }),
+        }}
+        <h3>"Server"</h3>
+        <form method="POST" action={form_action}>
+            <table>
+                <tr>
+                    <td><label>"Transport"</label></td>
+                    <td><select name="transport">{transport_options}</select></td>
+                </tr>
+                <tr>
+                    <td><label>"Command or URL"</label></td>
+                    <td><input type="text" name="target" size="80" value={target_value} placeholder="npx -y @modelcontextprotocol/server-filesystem /srv/docs"/></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Save"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - MCP Server", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session_id),
+            ),
+            Breadcrumb::link(
+                "Tool Intercept",
+                format!("/_dashboard/sessions/{}/tool-intercept", session_id),
+            ),
+            Breadcrumb::current("MCP Server"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,133 @@
+use common::models::{ModelStats, RecentRequest};
+use common::url::encode_uri_component;
+use leptos::{either::Either, prelude::*};
+use templates::{pagination_nav, Breadcrumb, NavLink, Page, Pagination};
+
+use crate::home::render_recent_requests;
+
+/// The requests page for one model, e.g. `/_dashboard/models/requests?model=claude-sonnet-4`.
+pub(crate) fn build_model_requests_href(model: &str) -> String {
+    format!(
+        "/_dashboard/models/requests?model={}",
+        encode_uri_component(model)
+    )
+}
+
+/// A mean latency as milliseconds, or seconds from one second up.
+fn format_latency(avg_latency_ms: Option<f64>) -> String {
+    match avg_latency_ms {
+        None => String::new(),
+        Some(latency_ms) if latency_ms < 1000.0 => format!("{:.0} ms", latency_ms),
+        Some(latency_ms) => format!("{:.2} s", latency_ms / 1000.0),
+    }
+}
+
+fn render_model_stats_table(model_stats: &[ModelStats]) -> impl IntoView {
+    if model_stats.is_empty() {
+        return Either::Left(view! { <p>"No models seen yet."</p> });
+    }
+    let rows = model_stats
+        .iter()
+        .map(|model_stats| {
+            let requests_href = build_model_requests_href(&model_stats.model);
+            view! {
+                <tr>
+                    <td><a href={requests_href}>{model_stats.model.clone()}</a></td>
+                    <td>{model_stats.request_count.to_string()}</td>
+                    <td>{model_stats.input_tokens.to_string()}</td>
+                    <td>{model_stats.output_tokens.to_string()}</td>
+                    <td>{format_latency(model_stats.avg_latency_ms)}</td>
+                    <td>{model_stats.last_seen_at.clone()}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Model"</th>
+                <th>"Requests"</th>
+                <th>"Input Tokens"</th>
+                <th>"Output Tokens"</th>
+                <th>"Avg Latency"</th>
+                <th>"Last Seen"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// Every model seen across sessions. Token totals come from the usage in
+/// stored responses; latency is measured from receiving a request to the end
+/// of its response.
+pub fn render_models_view(model_stats: &[ModelStats]) -> String {
+    let content = view! {
+        <h2>"Models"</h2>
+        {render_model_stats_table(model_stats)}
+    };
+
+    Page {
+        title: "Gateway Proxy - Models".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("Models"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// One model's requests across sessions, newest first.
+pub fn render_model_requests_view(
+    model: &str,
+    model_requests: &[RecentRequest],
+    pagination: &Pagination,
+) -> String {
+    let nav_top = pagination_nav(pagination);
+    let nav_bottom = pagination_nav(pagination);
+
+    let content = view! {
+        <h2>"Requests"</h2>
+        <p>{format!("Total: {}", pagination.total_items)}</p>
+        {nav_top}
+        {render_recent_requests(model_requests)}
+        {nav_bottom}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - {}", model),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Models", "/_dashboard/models"),
+            Breadcrumb::current(model),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_latency_switches_to_seconds() {
+        assert_eq!(format_latency(None), "");
+        assert_eq!(format_latency(Some(340.4)), "340 ms");
+        assert_eq!(format_latency(Some(1250.0)), "1.25 s");
+    }
+
+    #[test]
+    fn build_model_requests_href_encodes_model() {
+        assert_eq!(
+            build_model_requests_href("us.anthropic.claude-haiku-4-5-20251001-v1:0"),
+            "/_dashboard/models/requests?model=us.anthropic.claude-haiku-4-5-20251001-v1%3A0"
+        );
+    }
+}
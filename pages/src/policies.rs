@@ -0,0 +1,178 @@
+use common::models::{Session, WebfetchPolicy};
+use common::policy::{
+    find_action_by_key, find_condition_by_key, POLICY_ACTIONS, POLICY_CONDITIONS,
+};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+use crate::whitelist::build_webfetch_breadcrumbs;
+
+fn build_policies_url(session: &Session) -> String {
+    format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/policies",
+        session.id
+    )
+}
+
+pub fn render_policies_view(
+    session: &Session,
+    policies: &[WebfetchPolicy],
+    approved_call_count: i64,
+) -> String {
+    let policies_url = build_policies_url(session);
+    let clear_approvals_action = format!("{}/approvals/clear", policies_url);
+    let total = policies.len();
+
+    let rows: Vec<_> = policies
+        .iter()
+        .enumerate()
+        .map(|(index, policy)| {
+            let move_up_action = format!("{}/{}/move-up", policies_url, policy.id);
+            let delete_action = format!("{}/{}/delete", policies_url, policy.id);
+            let condition_label = find_condition_by_key(&policy.condition)
+                .map_or(policy.condition.clone(), |condition| {
+                    condition.label.to_string()
+                });
+            let action_label = find_action_by_key(&policy.action)
+                .map_or(policy.action.clone(), |action| action.label.to_string());
+            view! {
+                <tr>
+                    <td>{index + 1}</td>
+                    <td>
+                        {match policy.tool_name.clone() {
+                            Some(tool_name) => Either::Left(view! { <code>{tool_name}</code> }),
+                            None => Either::Right(view! { <em>"any tool"</em> }),
+                        }}
+                    </td>
+                    <td>{condition_label}</td>
+                    <td>{policy.condition_value.clone().unwrap_or_default()}</td>
+                    <td><strong>{action_label}</strong></td>
+                    <td>{policy.created_at.clone()}</td>
+                    <td>
+                        {if index > 0 {
+                            Either::Left(view! {
+                                <form method="POST" action={move_up_action}>
+                                    <button type="submit">"Move up"</button>
+                                </form>
+                                " "
+                            })
+                        } else {
+                            Either::Right(())
+                        }}
+                        <form method="POST" action={delete_action}>
+                            <button type="submit">"Delete"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Auto-Decision Policies"</h2>
+        <p>"Each intercepted tool call is checked against the policies from top to bottom, after the deny rules and before the whitelist; the first matching policy decides the call. A round is decided without asking only when every call in it matches a policy. " <strong>"Ask"</strong> " queues the round for you even if the whitelist would accept it."</p>
+        <p>{format!("Total: {}", total)}</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No policies configured."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr>
+                        <th>"#"</th>
+                        <th>"Tool"</th>
+                        <th>"Condition"</th>
+                        <th>"Value"</th>
+                        <th>"Action"</th>
+                        <th>"Created"</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+        <h3>"Approval History"</h3>
+        <p>{format!("Distinct calls you accepted in this session: {}. These are counted by \"After N identical approvals\" policies.", approved_call_count)}</p>
+        <form method="POST" action={clear_approvals_action}>
+            <button type="submit">"Clear History"</button>
+        </form>
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Auto-Decision Policies",
+            session.name
+        ),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![Breadcrumb::current("Auto-Decision Policies")],
+        ),
+        nav_links: vec![
+            NavLink::new("New Policy", format!("{}/new", policies_url)),
+            NavLink::back(),
+        ],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_new_policy_form(session: &Session) -> String {
+    let form_action = build_policies_url(session);
+    let condition_options: Vec<_> = POLICY_CONDITIONS
+        .iter()
+        .map(|condition| {
+            let label = format!("{} — {}", condition.label, condition.description);
+            view! { <option value={condition.key}>{label}</option> }
+        })
+        .collect();
+    let action_options: Vec<_> = POLICY_ACTIONS
+        .iter()
+        .map(|action| view! { <option value={action.key}>{action.label}</option> })
+        .collect();
+
+    let form = view! {
+        <h2>"New Policy"</h2>
+        <p>"New policies are added at the bottom of the list."</p>
+        <form method="POST" action={form_action}>
+            <table>
+                <tr>
+                    <td><label>"Tool name"</label></td>
+                    <td><input type="text" name="tool_name" size="40" placeholder="WebFetch (empty for any tool)"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Condition"</label></td>
+                    <td><select name="condition">{condition_options}</select></td>
+                </tr>
+                <tr>
+                    <td><label>"Value"</label></td>
+                    <td><input type="text" name="condition_value" size="40" placeholder="09:00-17:00 or 3"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Action"</label></td>
+                    <td><select name="action">{action_options}</select></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Add Policy"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - New Policy", session.name),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![
+                Breadcrumb::link("Auto-Decision Policies", build_policies_url(session)),
+                Breadcrumb::current("New"),
+            ],
+        ),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content: form,
+        subpages: vec![],
+    }
+    .render()
+}
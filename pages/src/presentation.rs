@@ -0,0 +1,190 @@
+use leptos::prelude::*;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// What a masked match is shown as.
+pub const REDACTED_TEXT: &str = "[redacted]";
+
+/// Anthropic and OpenAI style keys, AWS access key ids, bearer tokens and
+/// email addresses.
+const BUILTIN_REDACT_PATTERNS: &[&str] = &[
+    r"sk-ant-[A-Za-z0-9_-]{8,}",
+    r"\bsk-[A-Za-z0-9_-]{16,}",
+    r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+    r"(?i)\bbearer\s+[A-Za-z0-9._~+/-]{8,}=*",
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+];
+
+static BUILTIN_REDACT_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    BUILTIN_REDACT_PATTERNS
+        .iter()
+        .map(|pattern| Regex::new(pattern).unwrap())
+        .collect()
+});
+
+/// Elements whose content is code, not page text, and is left as is.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Mask matches of the built-in patterns and `extra_regexes` in `text`.
+fn redact_text(text: &str, extra_regexes: &[Regex]) -> String {
+    let mut redacted = text.to_string();
+    for redact_regex in BUILTIN_REDACT_REGEXES.iter().chain(extra_regexes) {
+        if redact_regex.is_match(&redacted) {
+            redacted = redact_regex
+                .replace_all(&redacted, REDACTED_TEXT)
+                .into_owned();
+        }
+    }
+    redacted
+}
+
+/// Attributes whose values are shown on the page or copied from it.
+const DISPLAYED_ATTRIBUTES: &[&str] = &["value", "title", "placeholder", "alt", "data-copy"];
+
+/// Mask the displayed attribute values of one tag, e.g. an input's value.
+/// Leptos quotes every attribute value with `"` and escapes quotes inside it.
+fn redact_tag(tag: &str, extra_regexes: &[Regex]) -> String {
+    let mut redacted = String::with_capacity(tag.len());
+    let mut segments = tag.split('"');
+    let mut attribute_name = String::new();
+    if let Some(first) = segments.next() {
+        redacted.push_str(first);
+        attribute_name = get_attribute_name(first);
+    }
+    for (index, segment) in segments.enumerate() {
+        redacted.push('"');
+        let is_value = index % 2 == 0;
+        if is_value && DISPLAYED_ATTRIBUTES.contains(&attribute_name.as_str()) {
+            redacted.push_str(&redact_text(segment, extra_regexes));
+        } else {
+            redacted.push_str(segment);
+        }
+        if !is_value {
+            attribute_name = get_attribute_name(segment);
+        }
+    }
+    redacted
+}
+
+/// The attribute a value belongs to, from the markup before it, e.g.
+/// `value` from `<input type="text" value=`.
+fn get_attribute_name(markup: &str) -> String {
+    markup
+        .trim_end()
+        .trim_end_matches('=')
+        .rsplit(|c: char| c.is_ascii_whitespace() || c == '<')
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// The element name of an opening tag such as `<script type="module">`.
+fn get_tag_name(tag: &str) -> &str {
+    let name = tag.trim_start_matches('<');
+    let end = name
+        .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+        .unwrap_or(name.len());
+    &name[..end]
+}
+
+/// Mask keys, emails and matches of `extra_regexes` in a rendered page's text
+/// and displayed attribute values. Markup, links, scripts and styles are kept
+/// intact, so the page still works; only what is displayed changes.
+pub fn redact_page_html(html: &str, extra_regexes: &[Regex]) -> String {
+    let mut redacted = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(tag_start) = rest.find('<') {
+        redacted.push_str(&redact_text(&rest[..tag_start], extra_regexes));
+        let Some(tag_len) = rest[tag_start..].find('>') else {
+            rest = &rest[tag_start..];
+            break;
+        };
+        let tag = &rest[tag_start..tag_start + tag_len + 1];
+        redacted.push_str(&redact_tag(tag, extra_regexes));
+        rest = &rest[tag_start + tag_len + 1..];
+
+        let tag_name = get_tag_name(tag).to_ascii_lowercase();
+        if RAW_TEXT_ELEMENTS.contains(&tag_name.as_str()) {
+            let closing_tag = format!("</{}", tag_name);
+            let raw_len = rest.find(&closing_tag).unwrap_or(rest.len());
+            redacted.push_str(&rest[..raw_len]);
+            rest = &rest[raw_len..];
+        }
+    }
+    redacted.push_str(&redact_text(rest, extra_regexes));
+    redacted
+}
+
+/// Shown at the top of every page while presentation mode is on, with a
+/// button to turn it off.
+pub fn render_presentation_banner() -> String {
+    view! {
+        <div class="presentation-banner">
+            <form method="POST" action="/_dashboard/settings">
+                "Presentation mode is on: keys, emails and configured patterns are masked. "
+                "Turn it off before saving forms with masked values. "
+                <input type="hidden" name="presentation_mode" value="false"/>
+                <input type="submit" value="Turn off"/>
+            </form>
+        </div>
+    }
+    .to_html()
+}
+
+/// Put the presentation banner at the start of the page body.
+pub fn insert_presentation_banner(html: &str) -> String {
+    let banner = render_presentation_banner();
+    let Some(body_start) = html.find("<body") else {
+        return format!("{}{}", banner, html);
+    };
+    let insert_at = html[body_start..]
+        .find('>')
+        .map(|body_tag_len| body_start + body_tag_len + 1)
+        .unwrap_or(body_start);
+    format!("{}{}{}", &html[..insert_at], banner, &html[insert_at..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_page_html_masks_text_and_attributes() {
+        let html = concat!(
+            r#"<p>Contact jane.doe@example.com</p>"#,
+            r#"<input type="text" name="x_api_key" value="sk-ant-api03-abcdefghijkl"/>"#,
+        );
+        let redacted = redact_page_html(html, &[]);
+        assert_eq!(
+            redacted,
+            concat!(
+                r#"<p>Contact [redacted]</p>"#,
+                r#"<input type="text" name="x_api_key" value="[redacted]"/>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn redact_page_html_keeps_markup_scripts_and_styles() {
+        let html = r#"<style>.acme { color: red; }</style><p class="acme">Acme Corp</p>"#;
+        let redacted = redact_page_html(html, &[Regex::new("(?i)acme").unwrap()]);
+        assert_eq!(
+            redacted,
+            r#"<style>.acme { color: red; }</style><p class="acme">[redacted] Corp</p>"#
+        );
+    }
+
+    #[test]
+    fn get_attribute_name_reads_name_before_value() {
+        assert_eq!(get_attribute_name("<input type="), "type");
+        assert_eq!(get_attribute_name(" VALUE="), "value");
+        assert_eq!(get_attribute_name(" data-copy="), "data-copy");
+    }
+
+    #[test]
+    fn insert_presentation_banner_after_body_tag() {
+        let html = insert_presentation_banner("<html><body class=\"page\"><p>hi</p></body></html>");
+        assert!(html.starts_with("<html><body class=\"page\"><div class=\"presentation-banner\">"));
+        assert!(html.ends_with("<p>hi</p></body></html>"));
+    }
+}
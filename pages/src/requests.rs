@@ -1,23 +1,90 @@
-use common::models::{ProxyRequest, Session};
+use common::{
+    models::{RequestSummary, Session},
+    url::encode_uri_component,
+};
 use leptos::{either::Either, prelude::*};
 use std::collections::HashMap;
 use templates::{pagination_nav, Breadcrumb, NavLink, Page, Pagination};
 
+use crate::detail::{find_recorded_protocol_issues, format_byte_count};
+
+/// Stop reasons flagged on the requests list: a response cut off by the
+/// token limit, or one the model declined to give.
+const FLAGGED_STOP_REASONS: &[(&str, &str)] =
+    &[("max_tokens", "Truncated"), ("refusal", "Refused")];
+
+/// The filters narrowing the requests list, read from its query string.
+#[derive(Clone, Copy, Default)]
+pub struct RequestListFilters<'a> {
+    pub stop_reason: Option<&'a str>,
+    /// The `User-Agent` of the client whose requests are shown.
+    pub client: Option<&'a str>,
+    /// Show only the requests whose WebFetch tool calls the proxy answered.
+    pub intercepted: bool,
+}
+
+impl RequestListFilters<'_> {
+    /// The filters as query parameters, e.g.
+    /// `"&stop_reason=refusal&client=curl%2F8.5.0&intercepted=on"`.
+    pub fn to_query_params(&self) -> String {
+        let mut query_params = String::new();
+        if let Some(stop_reason) = self.stop_reason {
+            query_params.push_str(&format!(
+                "&stop_reason={}",
+                encode_uri_component(stop_reason)
+            ));
+        }
+        if let Some(client) = self.client {
+            query_params.push_str(&format!("&client={}", encode_uri_component(client)));
+        }
+        if self.intercepted {
+            query_params.push_str("&intercepted=on");
+        }
+        query_params
+    }
+}
+
+/// The rows of one page of the requests list.
+pub struct RequestListRows<'a> {
+    pub requests: &'a [RequestSummary],
+    /// WebFetch follow-up rounds of `requests`, listed under their parent.
+    pub round_requests: &'a [RequestSummary],
+    /// Maps the id of each request that repeats an earlier body to the id of
+    /// the earliest request with that body.
+    pub duplicate_ids: &'a HashMap<String, String>,
+}
+
 pub fn render_requests_view(
     session: &Session,
-    requests: &[ProxyRequest],
+    request_list_rows: &RequestListRows<'_>,
+    request_list_filters: &RequestListFilters<'_>,
+    clients: &[String],
     auto_refresh: bool,
     pagination: &Pagination,
 ) -> String {
     let session = session.clone();
-    let requests = requests.to_vec();
+    let grouped_requests =
+        group_round_requests(request_list_rows.requests, request_list_rows.round_requests);
+    let duplicate_ids = request_list_rows.duplicate_ids;
     let total = pagination.total_items;
 
+    let filter_params = request_list_filters.to_query_params();
     let refresh_href = if auto_refresh {
-        format!("/_dashboard/sessions/{}/requests?refresh=off", session.id)
+        format!(
+            "/_dashboard/sessions/{}/requests?refresh=off{}",
+            session.id, filter_params
+        )
     } else {
-        format!("/_dashboard/sessions/{}/requests?refresh=on", session.id)
+        format!(
+            "/_dashboard/sessions/{}/requests?refresh=on{}",
+            session.id, filter_params
+        )
     };
+    let stop_reason_filter =
+        render_stop_reason_filter(&session, request_list_filters, auto_refresh);
+    let client_filter = render_client_filter(&session, request_list_filters, clients, auto_refresh);
+    let intercepted_filter =
+        render_intercepted_filter(&session, request_list_filters, auto_refresh);
     let refresh_label = if auto_refresh {
         "Disable auto-refresh"
     } else {
@@ -36,8 +103,11 @@ pub fn render_requests_view(
         <h2>"Requests"</h2>
         <p>{format!("Total: {}", total)}</p>
         <a href={refresh_href}>{refresh_label}</a>
+        {stop_reason_filter}
+        {client_filter}
+        {intercepted_filter}
         {nav_top}
-        {if requests.is_empty() {
+        {if grouped_requests.is_empty() {
             Either::Left(view! {
                 <p>"No requests yet."</p>
             })
@@ -45,36 +115,58 @@ pub fn render_requests_view(
             Either::Right(view! {
                 <table>
                     <tr>
+                        <th></th>
                         <th>"ID"</th>
                         <th>"Method"</th>
-                        <th>"Path"</th>
+                        <th>"Title"</th>
                         <th>"Model"</th>
                         <th>"Time"</th>
                         <th>"Messages"</th>
                         <th>"Last Message"</th>
                         <th>"Response"</th>
                         <th>"Last Block"</th>
+                        <th>"Stop"</th>
+                        <th>"Request Size"</th>
+                        <th>"Response Size"</th>
+                        <th>"Duplicate"</th>
                     </tr>
-                    {requests.into_iter().map(|request| {
+                    {grouped_requests.into_iter().map(|(request, round_number)| {
                         let detail_href = format!("/_dashboard/sessions/{}/requests/{}", request.session_id, request.id);
                         let messages_href = format!("/_dashboard/sessions/{}/requests/{}/messages", request.session_id, request.id);
                         let sse_href = format!("/_dashboard/sessions/{}/requests/{}/response_sse", request.session_id, request.id);
                         let (msg_count, preview) = get_message_preview(&request);
                         let (block_count, response_summary) = get_response_summary(&request);
+                        let title = format_request_title(&request);
+                        let stop_view = render_stop_reason(request.stop_reason.as_deref());
+                        let protocol_badge = render_protocol_badge(&request);
                         let model = request.model.clone().unwrap_or_default();
                         let id_str = request.id.to_string();
+                        let round_label = round_number.map(|round_number| format!("↳ Round {} ", round_number));
+                        let duplicate_view = duplicate_ids
+                            .get(&id_str)
+                            .map(|first_id| render_duplicate_link(&request, first_id));
                         let time = request.created_at.get(11..19).unwrap_or(&request.created_at).to_string();
+                        let request_size = format_request_size(&request);
+                        let response_size = request
+                            .response_bytes
+                            .map(|response_bytes| format_byte_count(response_bytes as usize))
+                            .unwrap_or_default();
                         view! {
                             <tr>
-                                <td><a href={detail_href}>{id_str}</a></td>
+                                <td><input type="checkbox" name={format!("request:{}", id_str)} value="1" form="jsonl-export"/></td>
+                                <td>{round_label}<a href={detail_href}>{id_str}</a></td>
                                 <td>{request.method}</td>
-                                <td>{request.path}</td>
+                                <td title={request.path}>{title}</td>
                                 <td>{model}</td>
                                 <td>{time}</td>
                                 <td><a href={messages_href}>{msg_count}</a></td>
                                 <td>{preview}</td>
                                 <td><a href={sse_href}>{block_count}</a></td>
                                 <td>{response_summary}</td>
+                                <td>{stop_view}{protocol_badge}</td>
+                                <td>{request_size}</td>
+                                <td>{response_size}</td>
+                                <td>{duplicate_view}</td>
                             </tr>
                         }
                     }).collect::<Vec<_>>()}
@@ -82,6 +174,7 @@ pub fn render_requests_view(
             })
         }}
         {nav_bottom}
+        {render_jsonl_export_form(&session)}
     };
 
     Page {
@@ -103,6 +196,210 @@ pub fn render_requests_view(
     .render()
 }
 
+/// Each request followed by its WebFetch follow-up rounds, numbered from 1.
+fn group_round_requests(
+    requests: &[RequestSummary],
+    round_requests: &[RequestSummary],
+) -> Vec<(RequestSummary, Option<usize>)> {
+    let mut grouped_requests = Vec::new();
+    for request in requests {
+        grouped_requests.push((request.clone(), None));
+        let request_id = request.id.to_string();
+        let rounds = round_requests.iter().filter(|round_request| {
+            round_request.parent_request_id.as_deref() == Some(&request_id)
+        });
+        for (round_index, round_request) in rounds.enumerate() {
+            grouped_requests.push((round_request.clone(), Some(round_index + 1)));
+        }
+    }
+    grouped_requests
+}
+
+/// Export the checked requests, or the whole session when none are checked,
+/// as a JSONL dataset.
+fn render_jsonl_export_form(session: &Session) -> impl IntoView {
+    let export_action = format!("/_dashboard/sessions/{}/export/jsonl", session.id);
+    view! {
+        <h2>"Export Dataset"</h2>
+        <p>"One JSON line per request with its messages and the completion rebuilt from the response. Exports the checked requests, or the whole session when none are checked."</p>
+        <form id="jsonl-export" method="GET" action={export_action}>
+            <label><input type="checkbox" name="exclude_system" value="1"/>" Exclude system prompts"</label>
+            " "
+            <label><input type="checkbox" name="strip_tool_use" value="1"/>" Strip tool use turns"</label>
+            " "
+            <input type="submit" value="Export JSONL"/>
+        </form>
+    }
+}
+
+/// Links filtering the list to all requests or to one flagged stop reason,
+/// with the current filter shown as plain text.
+fn render_stop_reason_filter(
+    session: &Session,
+    request_list_filters: &RequestListFilters<'_>,
+    auto_refresh: bool,
+) -> impl IntoView {
+    let refresh_param = if auto_refresh { "&refresh=on" } else { "" };
+    let options = std::iter::once((None, "All")).chain(
+        FLAGGED_STOP_REASONS
+            .iter()
+            .map(|&(value, label)| (Some(value), label)),
+    );
+    let links: Vec<_> = options
+        .map(|(value, label)| {
+            let filter_params = RequestListFilters {
+                stop_reason: value,
+                ..*request_list_filters
+            }
+            .to_query_params();
+            let href = format!(
+                "/_dashboard/sessions/{}/requests?{}{}",
+                session.id,
+                filter_params.trim_start_matches('&'),
+                refresh_param
+            );
+            if value == request_list_filters.stop_reason {
+                Either::Left(view! { " " <strong>{label}</strong> })
+            } else {
+                Either::Right(view! { " " <a href={href}>{label}</a> })
+            }
+        })
+        .collect();
+    view! { <p>"Stop reason:" {links}</p> }
+}
+
+/// Links filtering the list to all clients or to one `User-Agent`, shown
+/// once the session has seen more than one client.
+fn render_client_filter(
+    session: &Session,
+    request_list_filters: &RequestListFilters<'_>,
+    clients: &[String],
+    auto_refresh: bool,
+) -> Option<impl IntoView> {
+    if clients.len() < 2 && request_list_filters.client.is_none() {
+        return None;
+    }
+    let refresh_param = if auto_refresh { "&refresh=on" } else { "" };
+    let options = std::iter::once(None).chain(clients.iter().map(|client| Some(client.as_str())));
+    let links: Vec<_> = options
+        .map(|client| {
+            let label = client.unwrap_or("All").to_string();
+            let filter_params = RequestListFilters {
+                client,
+                ..*request_list_filters
+            }
+            .to_query_params();
+            let href = format!(
+                "/_dashboard/sessions/{}/requests?{}{}",
+                session.id,
+                filter_params.trim_start_matches('&'),
+                refresh_param
+            );
+            if client == request_list_filters.client {
+                Either::Left(view! { " " <strong>{label}</strong> })
+            } else {
+                Either::Right(view! { " " <a href={href}>{label}</a> })
+            }
+        })
+        .collect();
+    Some(view! { <p>"Client:" {links}</p> })
+}
+
+/// Links filtering the list to all requests or to the WebFetch-intercepted
+/// ones, shown once the session has intercepted a request.
+fn render_intercepted_filter(
+    session: &Session,
+    request_list_filters: &RequestListFilters<'_>,
+    auto_refresh: bool,
+) -> Option<impl IntoView> {
+    if session.intercepted_request_count == 0 && !request_list_filters.intercepted {
+        return None;
+    }
+    let refresh_param = if auto_refresh { "&refresh=on" } else { "" };
+    let links: Vec<_> = [(false, "All"), (true, "Intercepted only")]
+        .into_iter()
+        .map(|(intercepted, label)| {
+            let filter_params = RequestListFilters {
+                intercepted,
+                ..*request_list_filters
+            }
+            .to_query_params();
+            let href = format!(
+                "/_dashboard/sessions/{}/requests?{}{}",
+                session.id,
+                filter_params.trim_start_matches('&'),
+                refresh_param
+            );
+            if intercepted == request_list_filters.intercepted {
+                Either::Left(view! { " " <strong>{label}</strong> })
+            } else {
+                Either::Right(view! { " " <a href={href}>{label}</a> })
+            }
+        })
+        .collect();
+    Some(view! { <p>"WebFetch:" {links}</p> })
+}
+
+/// A request's stop reason, highlighted when it is a flagged one.
+fn render_stop_reason(stop_reason: Option<&str>) -> impl IntoView {
+    let stop_reason = stop_reason.unwrap_or_default().to_string();
+    let flagged = FLAGGED_STOP_REASONS
+        .iter()
+        .any(|&(value, _)| value == stop_reason);
+    let class = if flagged { "stop-alert" } else { "" };
+    view! { <span class={class}>{stop_reason}</span> }
+}
+
+/// A badge linking to the detail page's Protocol Issues section, shown when
+/// the recorded stream breaks the Messages streaming protocol.
+fn render_protocol_badge(request: &RequestSummary) -> Option<impl IntoView> {
+    let protocol_issues = find_recorded_protocol_issues(request.response_events_json.as_deref());
+    if protocol_issues.is_empty() {
+        return None;
+    }
+    let href = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        request.session_id, request.id
+    );
+    let title = format!("{} protocol issue(s)", protocol_issues.len());
+    Some(view! { " " <a href={href} title={title} class="stop-alert">"[PROTOCOL]"</a> })
+}
+
+/// A link to the earliest request with the same body, e.g. "same as #1a2b3c4d".
+fn render_duplicate_link(request: &RequestSummary, first_id: &str) -> impl IntoView {
+    let href = format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        request.session_id, first_id
+    );
+    let label = format!("same as #{}", first_id.get(..8).unwrap_or(first_id));
+    view! { <a href={href}>{label}</a> }
+}
+
+/// A request's generated title, or its path until it has one.
+fn format_request_title(request: &RequestSummary) -> String {
+    request
+        .title
+        .clone()
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| request.path.clone())
+}
+
+/// A request's body size, and the size sent upstream when a filter changed
+/// it, e.g. `"12.0 KB → 9.5 KB"`.
+fn format_request_size(request: &RequestSummary) -> String {
+    match (request.request_bytes, request.forwarded_bytes) {
+        (Some(request_bytes), Some(forwarded_bytes)) if forwarded_bytes != request_bytes => {
+            format!(
+                "{} → {}",
+                format_byte_count(request_bytes as usize),
+                format_byte_count(forwarded_bytes as usize)
+            )
+        }
+        (Some(request_bytes), _) => format_byte_count(request_bytes as usize),
+        (None, _) => String::new(),
+    }
+}
+
 /// Extract a preview string from a single content block.
 fn extract_block_preview(block: &serde_json::Value) -> String {
     match block.get("type").and_then(|field| field.as_str()) {
@@ -151,26 +448,19 @@ fn extract_block_preview(block: &serde_json::Value) -> String {
     }
 }
 
-fn get_message_preview(proxy_request: &ProxyRequest) -> (String, String) {
-    let Some(ref msgs_str) = proxy_request.messages_json else {
+fn get_message_preview(request_summary: &RequestSummary) -> (String, String) {
+    let Some(message_count) = request_summary.message_count.filter(|&count| count > 0) else {
         return (String::new(), String::new());
     };
-    let Ok(msgs) = serde_json::from_str::<Vec<serde_json::Value>>(msgs_str) else {
-        return (String::new(), String::new());
-    };
-    if msgs.is_empty() {
-        return (String::new(), String::new());
-    }
-    let count = format!("{}", msgs.len());
-    let last = match msgs
-        .iter()
-        .rfind(|msg| msg.get("role").and_then(|field| field.as_str()) == Some("user"))
-    {
-        Some(msg) => msg,
-        None => match msgs.last() {
-            Some(msg) => msg,
-            None => return (count, String::new()),
-        },
+    let count = format!("{}", message_count);
+    let Some(last) = request_summary
+        .last_message_json
+        .as_deref()
+        .and_then(|last_message_json| {
+            serde_json::from_str::<serde_json::Value>(last_message_json).ok()
+        })
+    else {
+        return (count, String::new());
     };
 
     let content = &last["content"];
@@ -323,9 +613,9 @@ fn format_last_block_summary(
     }
 }
 
-fn get_response_summary(proxy_request: &ProxyRequest) -> (String, String) {
-    let Some(ref events_json) = proxy_request.response_events_json else {
-        return match proxy_request.response_status {
+fn get_response_summary(request_summary: &RequestSummary) -> (String, String) {
+    let Some(ref events_json) = request_summary.response_events_json else {
+        return match request_summary.response_status {
             Some(status) => (String::new(), format!("{}", status)),
             None => (String::new(), String::new()),
         };
@@ -350,6 +640,15 @@ fn get_response_summary(proxy_request: &ProxyRequest) -> (String, String) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn render_stop_reason_flags_truncations_and_refusals() {
+        let html = render_stop_reason(Some("max_tokens")).to_html();
+        assert!(html.contains("stop-alert"));
+        assert!(html.contains("max_tokens"));
+        let html = render_stop_reason(Some("end_turn")).to_html();
+        assert!(!html.contains("stop-alert"));
+    }
+
     // --- extract_block_preview tests ---
 
     #[test]
@@ -594,4 +893,68 @@ mod tests {
         // Should use index 1 (highest)
         assert_eq!(format_last_block_summary(&types, &names, &text), "output");
     }
+
+    #[test]
+    fn render_protocol_badge_flags_broken_streams() {
+        let build_request = |response_events: serde_json::Value| -> RequestSummary {
+            serde_json::from_value(serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000002",
+                "session_id": "00000000-0000-0000-0000-000000000001",
+                "method": "POST",
+                "path": "/v1/messages",
+                "created_at": "2026-01-01 00:00:00",
+                "updated_at": "2026-01-01 00:00:00",
+                "response_events_json": response_events.to_string(),
+            }))
+            .unwrap()
+        };
+        let broken_stream = build_request(serde_json::json!([
+            {"event": "content_block_stop", "data": {"type": "content_block_stop", "index": 0}},
+        ]));
+        let html = render_protocol_badge(&broken_stream).unwrap().to_html();
+        assert!(html.contains("[PROTOCOL]"));
+        let valid_stream = build_request(serde_json::json!([
+            {"event": "message_start", "data": {"type": "message_start", "message": {}}},
+            {"event": "message_stop", "data": {"type": "message_stop"}},
+        ]));
+        assert!(render_protocol_badge(&valid_stream).is_none());
+    }
+
+    #[test]
+    fn format_request_size_shows_filtered_size() {
+        let build_request = |forwarded_bytes: i64| -> RequestSummary {
+            serde_json::from_value(serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000002",
+                "session_id": "00000000-0000-0000-0000-000000000001",
+                "method": "POST",
+                "path": "/v1/messages",
+                "created_at": "2026-01-01 00:00:00",
+                "updated_at": "2026-01-01 00:00:00",
+                "request_bytes": 4096,
+                "forwarded_bytes": forwarded_bytes,
+            }))
+            .unwrap()
+        };
+        assert_eq!(format_request_size(&build_request(4096)), "4.0 KB");
+        assert_eq!(format_request_size(&build_request(2048)), "4.0 KB → 2.0 KB");
+    }
+
+    #[test]
+    fn format_request_title_falls_back_to_path() {
+        let build_request = |title: Option<&str>| -> RequestSummary {
+            serde_json::from_value(serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000002",
+                "session_id": "00000000-0000-0000-0000-000000000001",
+                "method": "POST",
+                "path": "/v1/messages",
+                "created_at": "2026-01-01 00:00:00",
+                "updated_at": "2026-01-01 00:00:00",
+                "title": title,
+            }))
+            .unwrap()
+        };
+        assert_eq!(format_request_title(&build_request(Some("Fix login"))), "Fix login");
+        assert_eq!(format_request_title(&build_request(Some(""))), "/v1/messages");
+        assert_eq!(format_request_title(&build_request(None)), "/v1/messages");
+    }
 }
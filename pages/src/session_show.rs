@@ -1,31 +1,259 @@
-use common::models::Session;
-use leptos::prelude::*;
-use templates::{Breadcrumb, InfoRow, NavLink, Page, Subpage};
+use common::{
+    betas::BetaRules,
+    concurrency::SessionLoad,
+    header_presets::HeaderPreset,
+    headers::{HeaderRules, SKIPPED_FORWARD_HEADERS, SKIPPED_RESPONSE_HEADERS},
+    models::Session,
+    url::parse_query_params,
+    validation::find_by_key,
+};
+use leptos::{either::Either, prelude::*};
+use templates::{copy_button, Breadcrumb, InfoRow, NavLink, Page, Subpage};
+
+use crate::mcp::{describe_mcp_server, describe_mcp_server_status};
 
 fn render_copy_link(url: &str) -> impl IntoView {
-    let onclick = format!("navigator.clipboard.writeText('{}')", url);
-    let url = url.to_string();
-    view! {
-        {url}
-        " " <a href="javascript:void(0)" onclick={onclick}>"Copy"</a>
+    let url_view = url.to_string();
+    let copy_view = copy_button(url, "Copy");
+    view! { {url_view}" "{copy_view} }
+}
+
+/// A badge counting the session's WebFetch-intercepted requests, e.g.
+/// "12 intercepted requests", linking to the list of only those.
+fn render_intercepted_requests_link(session: &Session) -> impl IntoView {
+    let href = format!(
+        "/_dashboard/sessions/{}/requests?intercepted=on",
+        session.id
+    );
+    let label = match session.intercepted_request_count {
+        1 => "1 intercepted request".to_string(),
+        count => format!("{} intercepted requests", count),
+    };
+    view! { <a href={href}>{label}</a> }
+}
+
+/// The models an OpenAI-compatible upstream listed, or why it couldn't.
+fn describe_discovered_models(discovered_models: &Result<Vec<String>, String>) -> String {
+    match discovered_models {
+        Ok(models) if models.is_empty() => "none reported".to_string(),
+        Ok(models) => models.join(", "),
+        Err(e) => format!("unavailable: {}", e),
+    }
+}
+
+/// Requests in flight against the session's limit, e.g. "2 of 4 (1 queued)".
+fn describe_session_load(session_load: &SessionLoad) -> String {
+    let in_flight = match session_load.limit {
+        Some(limit) => format!("{} of {}", session_load.in_flight, limit),
+        None => format!("{} (no limit)", session_load.in_flight),
+    };
+    if session_load.queued > 0 {
+        format!("{} ({} queued)", in_flight, session_load.queued)
+    } else {
+        in_flight
+    }
+}
+
+/// A header preset's headers, e.g. `"anthropic-version: 2023-06-01, x-app: cli"`.
+pub fn describe_header_preset(header_preset: &HeaderPreset) -> String {
+    header_preset
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The betas a session injects and strips, e.g.
+/// `"inject output-128k-2025-02-19; strip prompt-caching-2024-07-31"`.
+fn describe_beta_rules(beta_rules: &BetaRules) -> String {
+    let mut parts = vec![];
+    if !beta_rules.inject.is_empty() {
+        parts.push(format!("inject {}", beta_rules.inject.join(", ")));
+    }
+    if !beta_rules.strip.is_empty() {
+        parts.push(format!("strip {}", beta_rules.strip.join(", ")));
     }
+    parts.join("; ")
 }
 
-pub fn render_session_view(session: &Session, port: u16, profile_name: Option<&str>) -> String {
-    let proxy_url = format!("http://localhost:{}/_proxy/{}/", port, session.id);
-    let bedrock_url = format!("http://localhost:{}/_bedrock/{}/", port, session.id);
+/// Which headers pass under a session's rules, e.g.
+/// `"only anthropic-*, content-type; never host, anthropic-beta"`.
+fn describe_header_rules(header_rules: &HeaderRules, skipped_headers: &[&str]) -> String {
+    let passed = if header_rules.allow.is_empty() {
+        "all".to_string()
+    } else {
+        format!("only {}", header_rules.allow.join(", "))
+    };
+    let never_passed: Vec<&str> = skipped_headers
+        .iter()
+        .copied()
+        .chain(header_rules.deny.iter().map(String::as_str))
+        .collect();
+    format!("{}; never {}", passed, never_passed.join(", "))
+}
+
+/// Whether un-prefixed requests go to this session, with a button to change it.
+fn render_default_session_toggle(session: &Session) -> impl IntoView {
+    if session.is_default {
+        let clear_action = format!("/_dashboard/sessions/{}/default/clear", session.id);
+        Either::Left(view! {
+            "yes, requests without /_proxy/{id}/ are routed here "
+            <form method="POST" action={clear_action}>
+                <button type="submit">"Unset"</button>
+            </form>
+        })
+    } else {
+        let set_action = format!("/_dashboard/sessions/{}/default", session.id);
+        Either::Right(view! {
+            "no "
+            <form method="POST" action={set_action}>
+                <button type="submit">"Make Default"</button>
+            </form>
+        })
+    }
+}
+
+pub fn render_session_view(
+    session: &Session,
+    proxy_origin: &str,
+    hostname_origin: Option<&str>,
+    profile_name: Option<&str>,
+    discovered_models: Option<&Result<Vec<String>, String>>,
+    session_load: &SessionLoad,
+) -> String {
+    let proxy_url = format!("{}/_proxy/{}/", proxy_origin, session.id);
+    let bedrock_url = format!("{}/_bedrock/{}/", proxy_origin, session.id);
+    let vertex_url = format!("{}/_vertex/{}/v1", proxy_origin, session.id);
 
     let mut info_rows = vec![
         InfoRow::new("Name", &session.name),
         InfoRow::view("Proxy URL", render_copy_link(&proxy_url)),
         InfoRow::view("Bedrock URL", render_copy_link(&bedrock_url)),
+        InfoRow::view("Vertex URL", render_copy_link(&vertex_url)),
         InfoRow::new("Target", &session.target_url),
+        InfoRow::new("In-flight Requests", &describe_session_load(session_load)),
+        InfoRow::new(
+            "Forwarded Headers",
+            &describe_header_rules(
+                &HeaderRules::parse(
+                    session.forward_header_allow.as_deref(),
+                    session.forward_header_deny.as_deref(),
+                ),
+                SKIPPED_FORWARD_HEADERS,
+            ),
+        ),
+        InfoRow::new(
+            "Returned Headers",
+            &describe_header_rules(
+                &HeaderRules::parse(
+                    session.response_header_allow.as_deref(),
+                    session.response_header_deny.as_deref(),
+                ),
+                SKIPPED_RESPONSE_HEADERS,
+            ),
+        ),
     ];
 
     if let Some(name) = profile_name {
         info_rows.push(InfoRow::new("Filter Profile", name));
     }
 
+    if let Some(preset) = session
+        .upstream_preset
+        .as_deref()
+        .and_then(common::upstream::find_by_key)
+    {
+        info_rows.push(InfoRow::new("Upstream Preset", preset.label));
+    }
+
+    if let Some(header_preset) = session
+        .header_preset
+        .as_deref()
+        .and_then(common::header_presets::find_by_key)
+    {
+        info_rows.push(InfoRow::new(
+            "Header Preset",
+            &format!(
+                "{} ({})",
+                header_preset.label,
+                describe_header_preset(header_preset)
+            ),
+        ));
+    }
+
+    let beta_rules = BetaRules::parse(
+        session.beta_inject.as_deref(),
+        session.beta_strip.as_deref(),
+    );
+    if !beta_rules.is_empty() {
+        info_rows.push(InfoRow::new(
+            "Anthropic Betas",
+            &describe_beta_rules(&beta_rules),
+        ));
+    }
+
+    if let Some(discovered_models) = discovered_models {
+        info_rows.push(InfoRow::new(
+            "Models",
+            &describe_discovered_models(discovered_models),
+        ));
+    }
+
+    let auth_query_params = parse_query_params(session.auth_query_params.as_deref());
+    if !auth_query_params.is_empty() {
+        let names = auth_query_params
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        info_rows.push(InfoRow::new("Query Auth Parameters", &names));
+    }
+
+    if let Some(ref aws_region) = session.aws_region {
+        let credential_source = if session.aws_access_key_id.is_some() {
+            "session keys"
+        } else {
+            "environment credentials"
+        };
+        info_rows.push(InfoRow::new(
+            "Bedrock Signing",
+            &format!("SigV4, {} ({})", aws_region, credential_source),
+        ));
+    }
+
+    info_rows.push(InfoRow::view(
+        "Default Session",
+        render_default_session_toggle(session),
+    ));
+
+    if let Some(hostname_origin) = hostname_origin {
+        let hostname_url = format!("{}/", hostname_origin);
+        info_rows.push(InfoRow::view(
+            "Hostname URL",
+            render_copy_link(&hostname_url),
+        ));
+    }
+
+    if session.intercepted_request_count > 0 {
+        info_rows.push(InfoRow::view(
+            "WebFetch",
+            render_intercepted_requests_link(session),
+        ));
+    }
+
+    if let Some(ref completion_webhook_url) = session.completion_webhook_url {
+        info_rows.push(InfoRow::new("Completion Webhook", completion_webhook_url));
+    }
+
+    if let Some(mcp_server) = describe_mcp_server(session) {
+        info_rows.push(InfoRow::new("MCP Server", &mcp_server));
+        info_rows.push(InfoRow::new(
+            "MCP Server Health",
+            &describe_mcp_server_status(session),
+        ));
+    }
+
     Page {
         title: format!("Gateway Proxy - Session {}", session.name),
         breadcrumbs: vec![
@@ -48,6 +276,45 @@ pub fn render_session_view(session: &Session, port: u16, profile_name: Option<&s
                 format!("/_dashboard/sessions/{}/requests", session.id),
                 session.request_count,
             ),
+            Subpage::new(
+                "Batches",
+                format!("/_dashboard/sessions/{}/batches", session.id),
+                session.batch_count,
+            ),
+            Subpage::new(
+                "Timeline",
+                format!("/_dashboard/sessions/{}/timeline", session.id),
+                "",
+            ),
+            Subpage::new(
+                "Tools",
+                format!("/_dashboard/sessions/{}/tools", session.id),
+                "",
+            ),
+            Subpage::new(
+                "Context Usage",
+                format!("/_dashboard/sessions/{}/context", session.id),
+                "",
+            ),
+            Subpage::new(
+                "Duplicates",
+                format!("/_dashboard/sessions/{}/duplicates", session.id),
+                "",
+            ),
+            Subpage::new(
+                "Breakpoints",
+                format!("/_dashboard/sessions/{}/breakpoints", session.id),
+                if session.breakpoint_enabled {
+                    "on"
+                } else {
+                    "off"
+                },
+            ),
+            Subpage::new(
+                "Intercept Rules",
+                format!("/_dashboard/sessions/{}/intercept-rules", session.id),
+                "",
+            ),
             Subpage::new(
                 "Error Injection",
                 format!("/_dashboard/sessions/{}/error-inject", session.id),
@@ -57,6 +324,16 @@ pub fn render_session_view(session: &Session, port: u16, profile_name: Option<&s
                     "on"
                 },
             ),
+            Subpage::new(
+                "Validation",
+                format!("/_dashboard/sessions/{}/validation", session.id),
+                session
+                    .validation_mode
+                    .as_deref()
+                    .and_then(find_by_key)
+                    .map(|mode| mode.key)
+                    .unwrap_or("off"),
+            ),
             Subpage::new(
                 "Tool Intercept",
                 format!("/_dashboard/sessions/{}/tool-intercept", session.id),
@@ -73,3 +350,22 @@ pub fn render_session_view(session: &Session, port: u16, profile_name: Option<&s
     }
     .render()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_header_rules_lists_allowed_and_never_passed() {
+        assert_eq!(
+            describe_header_rules(&HeaderRules::default(), SKIPPED_FORWARD_HEADERS),
+            "all; never host"
+        );
+        let header_rules =
+            HeaderRules::parse(Some("anthropic-*\ncontent-type"), Some("anthropic-beta"));
+        assert_eq!(
+            describe_header_rules(&header_rules, SKIPPED_FORWARD_HEADERS),
+            "only anthropic-*, content-type; never host, anthropic-beta"
+        );
+    }
+}
@@ -1,7 +1,13 @@
+use common::betas::{parse_beta_values, KNOWN_BETAS};
+use common::concurrency::OVERFLOW_MODES;
+use common::header_presets::HEADER_PRESETS;
 use common::models::{FilterProfile, Session};
+use common::upstream::UPSTREAM_PRESETS;
 use leptos::{either::Either, prelude::*};
 use templates::{pagination_nav, Breadcrumb, InfoRow, NavLink, Page, Pagination, Subpage};
 
+use crate::session_show::describe_header_preset;
+
 pub fn render_sessions_view(sessions: &[Session], pagination: &Pagination) -> String {
     let sessions = sessions.to_vec();
     let empty = sessions.is_empty();
@@ -37,7 +43,7 @@ pub fn render_sessions_view(sessions: &[Session], pagination: &Pagination) -> St
                         view! {
                             <tr>
                                 <td><a href={href}>{id_str}</a></td>
-                                <td>{session.name}</td>
+                                <td>{session.name}{session.is_default.then_some(" (default)")}</td>
                                 <td>{session.target_url}</td>
                                 <td>{session.request_count}</td>
                                 <td>{session.created_at.clone()}</td>
@@ -76,6 +82,135 @@ pub fn render_sessions_view(sessions: &[Session], pagination: &Pagination) -> St
     .render()
 }
 
+/// The header preset select, with the session's preset selected when editing.
+fn render_header_preset_row(session: Option<&Session>) -> impl IntoView {
+    let current_header_preset = session
+        .and_then(|session| session.header_preset.clone())
+        .unwrap_or_default();
+    view! {
+        <tr>
+            <td><label>"Header Preset"</label></td>
+            <td>
+                <select name="header_preset">
+                    <option value="">"None (client headers as sent)"</option>
+                    {HEADER_PRESETS.iter().map(|preset| {
+                        let selected = preset.key == current_header_preset;
+                        view! {
+                            <option value={preset.key} title={describe_header_preset(preset)} selected={selected}>{preset.label}</option>
+                        }
+                    }).collect::<Vec<_>>()}
+                </select>
+                " Replaces these headers in forwarded requests."
+            </td>
+        </tr>
+    }
+}
+
+/// Checkboxes of known `anthropic-beta` values plus a field for others, for
+/// the betas the session injects and strips, filled from `session` when editing.
+fn render_beta_rows(session: Option<&Session>) -> impl IntoView {
+    let beta_fields = [
+        (
+            "beta_inject",
+            "Inject Betas",
+            session.and_then(|session| session.beta_inject.clone()),
+        ),
+        (
+            "beta_strip",
+            "Strip Betas",
+            session.and_then(|session| session.beta_strip.clone()),
+        ),
+    ];
+    beta_fields
+        .into_iter()
+        .map(|(key, label, value)| {
+            let betas = parse_beta_values(value.as_deref());
+            let checkboxes = KNOWN_BETAS
+                .iter()
+                .map(|known_beta| {
+                    let checked = betas.iter().any(|beta| beta == known_beta.value);
+                    view! {
+                        <label title={known_beta.value}>
+                            <input type="checkbox" name={format!("{}:{}", key, known_beta.value)} value="1" checked={checked}/>
+                            {known_beta.label}
+                        </label>
+                        " "
+                    }
+                })
+                .collect::<Vec<_>>();
+            let other_betas = betas
+                .iter()
+                .filter(|beta| {
+                    !KNOWN_BETAS
+                        .iter()
+                        .any(|known_beta| known_beta.value == beta.as_str())
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            view! {
+                <tr>
+                    <td><label>{label}</label></td>
+                    <td>
+                        {checkboxes}
+                        <br/>
+                        <input type="text" name={key} value={other_betas} placeholder="other betas, comma-separated" size="60"/>
+                    </td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Textareas for the session's header rules, filled from `session` when editing.
+fn render_header_rule_rows(session: Option<&Session>) -> impl IntoView {
+    let header_rule_fields = [
+        (
+            "forward_header_allow",
+            "Forwarded Headers: Allow",
+            session.and_then(|session| session.forward_header_allow.clone()),
+        ),
+        (
+            "forward_header_deny",
+            "Forwarded Headers: Deny",
+            session.and_then(|session| session.forward_header_deny.clone()),
+        ),
+        (
+            "response_header_allow",
+            "Returned Headers: Allow",
+            session.and_then(|session| session.response_header_allow.clone()),
+        ),
+        (
+            "response_header_deny",
+            "Returned Headers: Deny",
+            session.and_then(|session| session.response_header_deny.clone()),
+        ),
+    ];
+    let rows = header_rule_fields
+        .into_iter()
+        .map(|(name, label, value)| {
+            view! {
+                <tr>
+                    <td><label>{label}</label></td>
+                    <td>
+                        <textarea name={name} rows="3" cols="40">{value.unwrap_or_default()}</textarea>
+                    </td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    view! {
+        <tr>
+            <td></td>
+            <td>
+                "Header name patterns, one per line; " <code>"*"</code> " matches anything. "
+                "An empty allow list passes every header; deny wins over allow."
+            </td>
+        </tr>
+        {rows}
+    }
+}
+
 pub fn render_new_session_form(profiles: &[FilterProfile], default_profile_id: &str) -> String {
     let profiles = profiles.to_vec();
     let default_profile_id = default_profile_id.to_string();
@@ -111,6 +246,18 @@ pub fn render_new_session_form(profiles: &[FilterProfile], default_profile_id: &
                         </select>
                     </td>
                 </tr>
+                <tr>
+                    <td><label>"Upstream Preset"</label></td>
+                    <td>
+                        <select name="upstream_preset">
+                            <option value="">"None (Anthropic API)"</option>
+                            {UPSTREAM_PRESETS.iter().map(|preset| view! {
+                                <option value={preset.key} title={preset.description}>{preset.label}</option>
+                            }).collect::<Vec<_>>()}
+                        </select>
+                        " Overrides the TLS and auth settings below."
+                    </td>
+                </tr>
                 <tr>
                     <td><label>"Disable TLS Verify"</label></td>
                     <td><input type="checkbox" name="tls_verify_disabled" value="1"/></td>
@@ -123,6 +270,57 @@ pub fn render_new_session_form(profiles: &[FilterProfile], default_profile_id: &
                     <td><label>"X-API-Key Header"</label></td>
                     <td><input type="text" name="x_api_key" placeholder="sk-..." size="60"/></td>
                 </tr>
+                <tr>
+                    <td><label>"Query Auth Parameters"</label></td>
+                    <td>
+                        <textarea name="auth_query_params" rows="2" cols="40" placeholder="key=..."></textarea>
+                    </td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Region"</label></td>
+                    <td><input type="text" name="aws_region" placeholder="us-east-1 (SigV4-signs Bedrock requests)" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Access Key ID"</label></td>
+                    <td><input type="text" name="aws_access_key_id" placeholder="AKIA... (empty for the proxy's environment)" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Secret Access Key"</label></td>
+                    <td><input type="password" name="aws_secret_access_key" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Session Token"</label></td>
+                    <td><input type="password" name="aws_session_token" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Max Concurrent Requests"</label></td>
+                    <td><input type="number" name="max_concurrent_requests" min="1" placeholder="unlimited"/></td>
+                </tr>
+                <tr>
+                    <td><label>"When At Limit"</label></td>
+                    <td>
+                        <select name="concurrency_overflow">
+                            {OVERFLOW_MODES.iter().map(|mode| view! {
+                                <option value={mode.key}>{mode.label}</option>
+                            }).collect::<Vec<_>>()}
+                        </select>
+                    </td>
+                </tr>
+                <tr>
+                    <td><label>"Max Body Bytes"</label></td>
+                    <td><input type="number" name="max_body_bytes" min="1" placeholder="global limit"/></td>
+                </tr>
+                {render_header_preset_row(None)}
+                {render_beta_rows(None)}
+                {render_header_rule_rows(None)}
+                <tr>
+                    <td><label>"Completion Webhook"</label></td>
+                    <td><input type="text" name="completion_webhook_url" placeholder="https://... (POSTed a summary of each request)" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Hostname"</label></td>
+                    <td><input type="text" name="hostname" placeholder="work.proxy.localhost (route by Host instead of /_proxy/{id}/)" size="60"/></td>
+                </tr>
                 <tr>
                     <td></td>
                     <td><input type="submit" value="Create"/></td>
@@ -148,17 +346,37 @@ pub fn render_new_session_form(profiles: &[FilterProfile], default_profile_id: &
 
 pub fn render_edit_session_form(
     session: &Session,
-    port: u16,
+    proxy_origin: &str,
     profiles: &[FilterProfile],
 ) -> String {
     let session = session.clone();
     let session_name = session.name.clone();
     let edit_action = format!("/_dashboard/sessions/{}/edit", session.id);
-    let proxy_url = format!("http://localhost:{}/_proxy/{}/", port, session.id);
+    let proxy_url = format!("{}/_proxy/{}/", proxy_origin, session.id);
     let tls_disabled = session.tls_verify_disabled;
     let auth_header_val = session.auth_header.clone().unwrap_or_default();
     let x_api_key_val = session.x_api_key.clone().unwrap_or_default();
+    let auth_query_params_val = session.auth_query_params.clone().unwrap_or_default();
+    let aws_region_val = session.aws_region.clone().unwrap_or_default();
+    let aws_access_key_id_val = session.aws_access_key_id.clone().unwrap_or_default();
+    let aws_secret_access_key_val = session.aws_secret_access_key.clone().unwrap_or_default();
+    let aws_session_token_val = session.aws_session_token.clone().unwrap_or_default();
     let current_profile_id = session.profile_id.clone().unwrap_or_default();
+    let current_upstream_preset = session.upstream_preset.clone().unwrap_or_default();
+    let max_concurrent_requests_val = session
+        .max_concurrent_requests
+        .map(|limit| limit.to_string())
+        .unwrap_or_default();
+    let current_concurrency_overflow = session.concurrency_overflow.clone().unwrap_or_default();
+    let max_body_bytes_val = session
+        .max_body_bytes
+        .map(|max_body_bytes| max_body_bytes.to_string())
+        .unwrap_or_default();
+    let completion_webhook_url_val = session.completion_webhook_url.clone().unwrap_or_default();
+    let hostname_val = session.hostname.clone().unwrap_or_default();
+    let header_preset_row = render_header_preset_row(Some(&session));
+    let beta_rows = render_beta_rows(Some(&session));
+    let header_rule_rows = render_header_rule_rows(Some(&session));
     let profiles = profiles.to_vec();
 
     let form = view! {
@@ -192,6 +410,20 @@ pub fn render_edit_session_form(
                         </select>
                     </td>
                 </tr>
+                <tr>
+                    <td><label>"Upstream Preset"</label></td>
+                    <td>
+                        <select name="upstream_preset">
+                            <option value="">"None (Anthropic API)"</option>
+                            {UPSTREAM_PRESETS.iter().map(|preset| {
+                                let selected = preset.key == current_upstream_preset;
+                                view! {
+                                    <option value={preset.key} title={preset.description} selected={selected}>{preset.label}</option>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </select>
+                    </td>
+                </tr>
                 <tr>
                     <td><label>"Disable TLS Verify"</label></td>
                     <td><input type="checkbox" name="tls_verify_disabled" value="1" checked={tls_disabled}/></td>
@@ -204,6 +436,60 @@ pub fn render_edit_session_form(
                     <td><label>"X-API-Key Header"</label></td>
                     <td><input type="text" name="x_api_key" value={x_api_key_val} size="60"/></td>
                 </tr>
+                <tr>
+                    <td><label>"Query Auth Parameters"</label></td>
+                    <td>
+                        <textarea name="auth_query_params" rows="2" cols="40">{auth_query_params_val}</textarea>
+                    </td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Region"</label></td>
+                    <td><input type="text" name="aws_region" value={aws_region_val} placeholder="us-east-1 (SigV4-signs Bedrock requests)" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Access Key ID"</label></td>
+                    <td><input type="text" name="aws_access_key_id" value={aws_access_key_id_val} placeholder="AKIA... (empty for the proxy's environment)" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Secret Access Key"</label></td>
+                    <td><input type="password" name="aws_secret_access_key" value={aws_secret_access_key_val} size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"AWS Session Token"</label></td>
+                    <td><input type="password" name="aws_session_token" value={aws_session_token_val} size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Max Concurrent Requests"</label></td>
+                    <td><input type="number" name="max_concurrent_requests" min="1" value={max_concurrent_requests_val} placeholder="unlimited"/></td>
+                </tr>
+                <tr>
+                    <td><label>"When At Limit"</label></td>
+                    <td>
+                        <select name="concurrency_overflow">
+                            {OVERFLOW_MODES.iter().map(|mode| {
+                                let selected = mode.key == current_concurrency_overflow;
+                                view! {
+                                    <option value={mode.key} selected={selected}>{mode.label}</option>
+                                }
+                            }).collect::<Vec<_>>()}
+                        </select>
+                    </td>
+                </tr>
+                <tr>
+                    <td><label>"Max Body Bytes"</label></td>
+                    <td><input type="number" name="max_body_bytes" min="1" value={max_body_bytes_val} placeholder="global limit"/></td>
+                </tr>
+                {header_preset_row}
+                {beta_rows}
+                {header_rule_rows}
+                <tr>
+                    <td><label>"Completion Webhook"</label></td>
+                    <td><input type="text" name="completion_webhook_url" value={completion_webhook_url_val} placeholder="https://... (POSTed a summary of each request)" size="60"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Hostname"</label></td>
+                    <td><input type="text" name="hostname" value={hostname_val} placeholder="work.proxy.localhost (route by Host instead of /_proxy/{id}/)" size="60"/></td>
+                </tr>
                 <tr>
                     <td></td>
                     <td><input type="submit" value="Save"/></td>
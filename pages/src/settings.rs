@@ -0,0 +1,82 @@
+use common::config::{AppConfig, RuntimeSetting, RUNTIME_SETTINGS};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+fn render_setting_row(setting: &RuntimeSetting, config: &AppConfig) -> impl IntoView {
+    let name = setting.key.to_string();
+    let label = if setting.requires_restart {
+        format!("{} (restart required)", setting.label)
+    } else {
+        setting.label.to_string()
+    };
+    let value = config.get_runtime_setting(setting.key).unwrap_or_default();
+    let input = if setting.multiline {
+        Either::Left(view! {
+            <textarea name={name} rows="10" cols="80">{value}</textarea>
+        })
+    } else {
+        Either::Right(view! {
+            <input type="text" name={name} value={value} size="60"/>
+        })
+    };
+    view! {
+        <tr>
+            <td><label>{label}</label></td>
+            <td>{input}</td>
+        </tr>
+    }
+}
+
+/// One-click switch for presentation mode, which masks keys, emails and the
+/// extra patterns on every other dashboard page.
+fn render_presentation_toggle(config: &AppConfig) -> impl IntoView {
+    let (state, next_value, button_label) = if config.presentation_mode {
+        ("on", "false", "Turn off")
+    } else {
+        ("off", "true", "Turn on")
+    };
+    view! {
+        <div>
+            <form method="POST" action="/_dashboard/settings">
+                {format!("Presentation mode is {}. ", state)}
+                <input type="hidden" name="presentation_mode" value={next_value}/>
+                <input type="submit" value={button_label}/>
+            </form>
+        </div>
+    }
+}
+
+pub fn render_settings_view(config: &AppConfig) -> String {
+    let rows = RUNTIME_SETTINGS
+        .iter()
+        .map(|setting| render_setting_row(setting, config))
+        .collect::<Vec<_>>();
+
+    let content = view! {
+        <h2>"Settings"</h2>
+        <p>"Changes take effect immediately and are saved to the database, overriding values from the config file."</p>
+        {render_presentation_toggle(config)}
+        <form method="POST" action="/_dashboard/settings">
+            <table>
+                {rows}
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Save"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: "Gateway Proxy - Settings".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("Settings"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,288 @@
+//! Snapshots of the rendered pages, so markup changes are reviewed as a diff.
+//! Update them with `INSTA_UPDATE=always cargo test -p pages` (or
+//! `cargo insta review`) and check the `.snap` changes in.
+
+use common::concurrency::SessionLoad;
+use common::config::AppConfig;
+use common::models::ProxyRequest;
+use std::collections::HashMap;
+use templates::Pagination;
+
+use crate::detail::{
+    render_request_detail_page_view, render_request_detail_view, render_share_link_view,
+    render_webfetch_agent_page, render_webfetch_intercept_hub, render_webfetch_transcript_view,
+    RequestAnnotations, RequestNeighbors,
+};
+use crate::filters::{
+    render_edit_profile_form, render_edit_system_filter_form, render_edit_tool_filter_form,
+    render_edit_tool_name_override_form, render_filters_view, render_message_filters_view,
+    render_new_profile_form, render_new_system_filter_form, render_new_tool_filter_form,
+    render_new_tool_name_override_form, render_profile_view, render_system_filters_view,
+    render_tool_filters_view, render_tool_name_overrides_view,
+};
+use crate::requests::{render_requests_view, RequestListFilters, RequestListRows};
+use crate::session_show::render_session_view;
+use crate::sessions::{render_edit_session_form, render_new_session_form, render_sessions_view};
+use crate::settings::render_settings_view;
+use crate::test_fixtures::{
+    build_test_profile, build_test_request, build_test_request_summary, build_test_session,
+    build_test_system_filter, build_test_tool_filter, build_test_tool_name_override,
+    format_snapshot_html, TEST_PROFILE_ID, TEST_REQUEST_ID,
+};
+
+const DETAIL_PAGES: &[&str] = &[
+    "messages",
+    "system",
+    "tools",
+    "params",
+    "headers",
+    "full_json",
+    "applied_filters",
+    "response_headers",
+    "response_sse",
+    "response_rendered",
+    "response_raw",
+];
+
+const TEST_PROXY_ORIGIN: &str = "http://127.0.0.1:8080";
+
+fn build_test_neighbors() -> RequestNeighbors<'static> {
+    RequestNeighbors {
+        prev_id: Some("00000000-0000-0000-0000-000000000010"),
+        next_id: Some("00000000-0000-0000-0000-000000000011"),
+    }
+}
+
+#[test]
+fn snapshot_request_detail_view() {
+    let star_authors = vec!["alice".to_string()];
+    let annotations = RequestAnnotations {
+        notes: &[],
+        star_authors: &star_authors,
+        author: "alice",
+    };
+    let round_ids = vec![
+        TEST_REQUEST_ID.to_string(),
+        "00000000-0000-0000-0000-000000000012".to_string(),
+    ];
+    let html = render_request_detail_view(
+        &build_test_request(),
+        &build_test_session(),
+        &build_test_neighbors(),
+        &round_ids,
+        &annotations,
+    );
+    insta::assert_snapshot!(format_snapshot_html(&html));
+}
+
+#[test]
+fn snapshot_request_detail_pages() {
+    let request = build_test_request();
+    let session = build_test_session();
+    let filters = vec!["Answer briefly.".to_string()];
+    for page in DETAIL_PAGES {
+        let html = render_request_detail_page_view(
+            &request,
+            &session,
+            page,
+            &HashMap::new(),
+            &filters,
+            1,
+            &build_test_neighbors(),
+        );
+        insta::assert_snapshot!(
+            format!("request_detail_page_{}", page),
+            format_snapshot_html(&html)
+        );
+    }
+}
+
+#[test]
+fn snapshot_webfetch_pages() {
+    let request = build_test_request();
+    let session = build_test_session();
+    let hub_html = render_webfetch_intercept_hub(
+        &request,
+        &session,
+        &HashMap::new(),
+        std::slice::from_ref(&request),
+    );
+    insta::assert_snapshot!("webfetch_intercept_hub", format_snapshot_html(&hub_html));
+    let agent_html = render_webfetch_agent_page(
+        &request,
+        &session,
+        &request,
+        "messages",
+        &HashMap::new(),
+        &[],
+        0,
+    );
+    insta::assert_snapshot!("webfetch_agent_messages", format_snapshot_html(&agent_html));
+}
+
+#[test]
+fn snapshot_webfetch_transcript_view() {
+    let tool_use = serde_json::json!({"role": "assistant", "content": [
+        {"type": "tool_use", "id": "toolu_01", "name": "WebFetch",
+         "input": {"url": "https://example.com", "prompt": "Summarize"}}
+    ]});
+    let tool_results = serde_json::json!({"role": "user", "content": [
+        {"type": "tool_result", "tool_use_id": "toolu_01", "content": "Example Domain"}
+    ]});
+    let rounds = serde_json::json!([{
+        "followup_body": {"messages": [
+            {"role": "user", "content": "What is on example.com?"}, tool_use, tool_results
+        ]},
+        "response_events": [],
+        "response_body": r#"{"content": [{"type": "text", "text": "A placeholder page."}]}"#,
+    }]);
+    let request = ProxyRequest {
+        webfetch_rounds_json: Some(rounds.to_string()),
+        ..build_test_request()
+    };
+    let html = render_webfetch_transcript_view(&request, &build_test_session());
+    insta::assert_snapshot!(format_snapshot_html(&html));
+}
+
+#[test]
+fn snapshot_share_link_view() {
+    let html = render_share_link_view(
+        &build_test_request(),
+        &build_test_session(),
+        "http://127.0.0.1:8080/_share/token",
+        "1 hour",
+    );
+    insta::assert_snapshot!(format_snapshot_html(&html));
+}
+
+#[test]
+fn snapshot_requests_view() {
+    let request = build_test_request_summary();
+    let mut round_request = build_test_request_summary();
+    round_request.id = "00000000-0000-0000-0000-000000000012".parse().unwrap();
+    round_request.parent_request_id = Some(TEST_REQUEST_ID.to_string());
+    let requests = vec![request];
+    let round_requests = vec![round_request];
+    let duplicate_ids = HashMap::new();
+    let request_list_rows = RequestListRows {
+        requests: &requests,
+        round_requests: &round_requests,
+        duplicate_ids: &duplicate_ids,
+    };
+    let request_list_filters = RequestListFilters {
+        stop_reason: Some("end_turn"),
+        client: None,
+        intercepted: false,
+    };
+    let session = build_test_session();
+    let pagination = Pagination::new(
+        1,
+        1,
+        50,
+        format!("/_dashboard/sessions/{}/requests", session.id),
+        request_list_filters.to_query_params(),
+    );
+    let html = render_requests_view(
+        &session,
+        &request_list_rows,
+        &request_list_filters,
+        &["claude-cli/1.0.0".to_string()],
+        false,
+        &pagination,
+    );
+    insta::assert_snapshot!(format_snapshot_html(&html));
+}
+
+#[test]
+fn snapshot_session_pages() {
+    let session = build_test_session();
+    let profiles = vec![build_test_profile()];
+    let pagination = Pagination::new(1, 1, 50, "/_dashboard/sessions", "");
+    let sessions_html = render_sessions_view(std::slice::from_ref(&session), &pagination);
+    insta::assert_snapshot!("sessions_view", format_snapshot_html(&sessions_html));
+    let new_html = render_new_session_form(&profiles, TEST_PROFILE_ID);
+    insta::assert_snapshot!("new_session_form", format_snapshot_html(&new_html));
+    let edit_html = render_edit_session_form(&session, TEST_PROXY_ORIGIN, &profiles);
+    insta::assert_snapshot!("edit_session_form", format_snapshot_html(&edit_html));
+    let discovered_models = Ok(vec!["claude-sonnet-4-5".to_string()]);
+    let session_load = SessionLoad {
+        in_flight: 1,
+        queued: 0,
+        limit: Some(4),
+    };
+    let show_html = render_session_view(
+        &session,
+        TEST_PROXY_ORIGIN,
+        None,
+        Some("trimmed"),
+        Some(&discovered_models),
+        &session_load,
+    );
+    insta::assert_snapshot!("session_view", format_snapshot_html(&show_html));
+}
+
+#[test]
+fn snapshot_filter_pages() {
+    let profile = build_test_profile();
+    let system_filter = build_test_system_filter();
+    let tool_filter = build_test_tool_filter();
+    let tool_name_override = build_test_tool_name_override();
+    let pages = [
+        (
+            "filters_view",
+            render_filters_view(std::slice::from_ref(&profile)),
+        ),
+        ("new_profile_form", render_new_profile_form()),
+        ("profile_view", render_profile_view(&profile, 1, 1, 2, 1)),
+        ("edit_profile_form", render_edit_profile_form(&profile)),
+        (
+            "system_filters_view",
+            render_system_filters_view(&profile, std::slice::from_ref(&system_filter)),
+        ),
+        (
+            "new_system_filter_form",
+            render_new_system_filter_form(&profile, std::slice::from_ref(&system_filter)),
+        ),
+        (
+            "edit_system_filter_form",
+            render_edit_system_filter_form(&profile, &system_filter),
+        ),
+        (
+            "tool_filters_view",
+            render_tool_filters_view(&profile, std::slice::from_ref(&tool_filter)),
+        ),
+        (
+            "new_tool_filter_form",
+            render_new_tool_filter_form(&profile, std::slice::from_ref(&tool_filter)),
+        ),
+        (
+            "edit_tool_filter_form",
+            render_edit_tool_filter_form(&profile, &tool_filter),
+        ),
+        (
+            "tool_name_overrides_view",
+            render_tool_name_overrides_view(&profile, std::slice::from_ref(&tool_name_override)),
+        ),
+        (
+            "new_tool_name_override_form",
+            render_new_tool_name_override_form(&profile),
+        ),
+        (
+            "edit_tool_name_override_form",
+            render_edit_tool_name_override_form(&profile, &tool_name_override),
+        ),
+        (
+            "message_filters_view",
+            render_message_filters_view(&profile, 2),
+        ),
+    ];
+    for (name, html) in pages {
+        insta::assert_snapshot!(name, format_snapshot_html(&html));
+    }
+}
+
+#[test]
+fn snapshot_settings_view() {
+    let html = render_settings_view(&AppConfig::default());
+    insta::assert_snapshot!(format_snapshot_html(&html));
+}
@@ -0,0 +1,259 @@
+//! Fixed sessions, requests and profiles for the page snapshot tests, so a
+//! render change shows up as a snapshot diff rather than a changed id or date.
+
+use common::models::{
+    FilterProfile, ProxyRequest, RequestSummary, Session, SystemFilter, ToolFilter,
+    ToolNameOverride,
+};
+use serde_json::{json, Value};
+
+pub(crate) const TEST_SESSION_ID: &str = "00000000-0000-0000-0000-000000000001";
+pub(crate) const TEST_REQUEST_ID: &str = "00000000-0000-0000-0000-000000000002";
+pub(crate) const TEST_PROFILE_ID: &str = "00000000-0000-0000-0000-000000000003";
+const TEST_TIMESTAMP: &str = "2026-01-01 00:00:00";
+
+/// 1x1 transparent PNG.
+const TEST_PNG_BASE64: &str =
+    "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+pub(crate) fn build_test_session() -> Session {
+    serde_json::from_value(json!({
+        "id": TEST_SESSION_ID,
+        "name": "snapshot",
+        "target_url": "https://api.anthropic.com",
+        "tls_verify_disabled": false,
+        "profile_id": TEST_PROFILE_ID,
+        "max_concurrent_requests": 4,
+        "is_default": false,
+        "breakpoint_enabled": false,
+        "webfetch_intercept": true,
+        "webfetch_allow_private_targets": false,
+        "webfetch_extraction_mode": "readability",
+        "webfetch_respect_robots_txt": true,
+        "webfetch_raw_mode": false,
+        "created_at": TEST_TIMESTAMP,
+        "updated_at": TEST_TIMESTAMP,
+        "request_count": 3,
+        "intercepted_request_count": 1,
+        "batch_count": 0,
+    }))
+    .unwrap()
+}
+
+/// A streamed `/v1/messages` request whose messages hold every content block
+/// type, with its response events, headers and applied filters.
+pub(crate) fn build_test_request() -> ProxyRequest {
+    let system = json!([
+        {"type": "text", "text": "You are a helpful assistant."},
+        {"type": "text", "text": "Answer briefly.", "cache_control": {"type": "ephemeral"}}
+    ]);
+    let tools = json!([
+        {
+            "name": "WebFetch",
+            "description": "Fetch a URL",
+            "input_schema": {
+                "type": "object",
+                "properties": {"url": {"type": "string"}, "prompt": {"type": "string"}},
+                "required": ["url", "prompt"]
+            }
+        },
+        {"type": "web_search_20250305", "name": "web_search", "max_uses": 2}
+    ]);
+    let params = json!({"max_tokens": 1024, "stream": true, "temperature": 0.5});
+    let mut body = json!({
+        "model": "claude-sonnet-4-5",
+        "messages": build_test_messages(),
+        "system": system,
+        "tools": tools,
+    });
+    body.as_object_mut()
+        .unwrap()
+        .extend(params.as_object().unwrap().clone());
+    let headers = json!({
+        "content-type": "application/json",
+        "anthropic-version": "2023-06-01",
+        "user-agent": "claude-cli/1.0.0",
+    });
+    let response_headers = json!({
+        "content-type": "text/event-stream",
+        "request-id": "req_snapshot",
+    });
+    let response_events = build_test_response_events();
+    let response_body: String = response_events
+        .iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {}\n\n",
+                event["event"].as_str().unwrap(),
+                event["data"]
+            )
+        })
+        .collect();
+    let applied_filters = json!([
+        {"kind": "system", "rule": "Answer briefly.", "detail": "Removed 1 block"},
+        {"kind": "tool", "rule": "WebSearch", "detail": "Removed"}
+    ]);
+    serde_json::from_value(json!({
+        "id": TEST_REQUEST_ID,
+        "session_id": TEST_SESSION_ID,
+        "method": "POST",
+        "path": "/v1/messages",
+        "headers_json": headers.to_string(),
+        "body_json": body.to_string(),
+        "model": "claude-sonnet-4-5",
+        "tools_json": tools.to_string(),
+        "messages_json": build_test_messages().to_string(),
+        "system_json": system.to_string(),
+        "params_json": params.to_string(),
+        "note": "snapshot fixture",
+        "created_at": TEST_TIMESTAMP,
+        "updated_at": TEST_TIMESTAMP,
+        "response_status": 200,
+        "response_headers_json": response_headers.to_string(),
+        "response_body": response_body,
+        "response_events_json": Value::Array(response_events).to_string(),
+        "estimated_input_tokens": 120,
+        "counted_input_tokens": 118,
+        "request_bytes": 4096,
+        "forwarded_bytes": 3072,
+        "response_bytes": 1024,
+        "applied_filters_json": applied_filters.to_string(),
+        "anthropic_beta": "prompt-caching-2024-07-31",
+        "title": "Summarize the example page",
+        "stop_reason": "end_turn",
+        "client_user_agent": "claude-cli/1.0.0",
+        "client_app": "cli",
+        "client_addr": "127.0.0.1:54321",
+    }))
+    .unwrap()
+}
+
+/// `build_test_request` as the requests list loads it.
+pub(crate) fn build_test_request_summary() -> RequestSummary {
+    let messages = build_test_messages();
+    let last_message = messages
+        .as_array()
+        .unwrap()
+        .iter()
+        .rfind(|message| message["role"] == "user")
+        .unwrap();
+    let mut request_summary = serde_json::to_value(build_test_request()).unwrap();
+    request_summary["message_count"] = json!(messages.as_array().unwrap().len());
+    request_summary["last_message_json"] = json!(last_message.to_string());
+    serde_json::from_value(request_summary).unwrap()
+}
+
+fn build_test_messages() -> Value {
+    json!([
+        {"role": "user", "content": "Summarize https://example.com for me."},
+        {"role": "user", "content": [
+            {"type": "text", "text": "Here is a **screenshot** and the spec:"},
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": TEST_PNG_BASE64}},
+            {"type": "document", "title": "spec.pdf", "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERi0xLjQK"}}
+        ]},
+        {"role": "assistant", "content": [
+            {"type": "thinking", "thinking": "I should fetch the page first.", "signature": "c2lnbmF0dXJl"},
+            {"type": "redacted_thinking", "data": "cmVkYWN0ZWQ="},
+            {"type": "text", "text": "Fetching the page."},
+            {"type": "tool_use", "id": "toolu_01", "name": "WebFetch", "input": {"url": "https://example.com", "prompt": "Summarize"}},
+            {"type": "server_tool_use", "id": "srvtoolu_01", "name": "web_search", "input": {"query": "example domain"}},
+            {"type": "web_search_tool_result", "tool_use_id": "srvtoolu_01", "content": [
+                {"type": "web_search_result", "title": "Example Domain", "url": "https://example.com", "encrypted_content": "ZW5j"}
+            ]}
+        ]},
+        {"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "toolu_01", "content": "Example Domain: for use in illustrative examples."},
+            {"type": "tool_result", "tool_use_id": "toolu_02", "is_error": true, "content": [
+                {"type": "text", "text": "robots.txt disallows fetching"}
+            ]}
+        ]}
+    ])
+}
+
+fn build_test_response_events() -> Vec<Value> {
+    vec![
+        json!({"event": "message_start", "data": {"type": "message_start", "message": {
+            "id": "msg_01", "type": "message", "role": "assistant", "model": "claude-sonnet-4-5",
+            "content": [], "usage": {"input_tokens": 118, "output_tokens": 1}
+        }}}),
+        json!({"event": "content_block_start", "data": {"type": "content_block_start", "index": 0,
+            "content_block": {"type": "thinking", "thinking": ""}}}),
+        json!({"event": "content_block_delta", "data": {"type": "content_block_delta", "index": 0,
+            "delta": {"type": "thinking_delta", "thinking": "The page is short."}}}),
+        json!({"event": "content_block_stop", "data": {"type": "content_block_stop", "index": 0}}),
+        json!({"event": "content_block_start", "data": {"type": "content_block_start", "index": 1,
+            "content_block": {"type": "text", "text": ""}}}),
+        json!({"event": "content_block_delta", "data": {"type": "content_block_delta", "index": 1,
+            "delta": {"type": "text_delta", "text": "It is an **example** domain."}}}),
+        json!({"event": "content_block_stop", "data": {"type": "content_block_stop", "index": 1}}),
+        json!({"event": "content_block_start", "data": {"type": "content_block_start", "index": 2,
+            "content_block": {"type": "tool_use", "id": "toolu_03", "name": "WebFetch", "input": {}}}}),
+        json!({"event": "content_block_delta", "data": {"type": "content_block_delta", "index": 2,
+            "delta": {"type": "input_json_delta", "partial_json": "{\"url\": \"https://example.org\"}"}}}),
+        json!({"event": "content_block_stop", "data": {"type": "content_block_stop", "index": 2}}),
+        json!({"event": "message_delta", "data": {"type": "message_delta",
+            "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 24}}}),
+        json!({"event": "message_stop", "data": {"type": "message_stop"}}),
+    ]
+}
+
+pub(crate) fn build_test_profile() -> FilterProfile {
+    serde_json::from_value(json!({
+        "id": TEST_PROFILE_ID,
+        "name": "trimmed",
+        "is_default": true,
+        "created_at": TEST_TIMESTAMP,
+        "updated_at": TEST_TIMESTAMP,
+    }))
+    .unwrap()
+}
+
+pub(crate) fn build_test_system_filter() -> SystemFilter {
+    serde_json::from_value(json!({
+        "id": "00000000-0000-0000-0000-000000000004",
+        "profile_id": TEST_PROFILE_ID,
+        "pattern": "Answer briefly.",
+        "created_at": TEST_TIMESTAMP,
+        "updated_at": TEST_TIMESTAMP,
+    }))
+    .unwrap()
+}
+
+pub(crate) fn build_test_tool_filter() -> ToolFilter {
+    serde_json::from_value(json!({
+        "id": "00000000-0000-0000-0000-000000000005",
+        "profile_id": TEST_PROFILE_ID,
+        "name": "WebSearch",
+        "created_at": TEST_TIMESTAMP,
+        "updated_at": TEST_TIMESTAMP,
+    }))
+    .unwrap()
+}
+
+pub(crate) fn build_test_tool_name_override() -> ToolNameOverride {
+    serde_json::from_value(json!({
+        "id": "00000000-0000-0000-0000-000000000006",
+        "profile_id": TEST_PROFILE_ID,
+        "original_name": "WebFetch",
+        "override_name": "fetch_url",
+        "created_at": TEST_TIMESTAMP,
+        "updated_at": TEST_TIMESTAMP,
+    }))
+    .unwrap()
+}
+
+/// The page's title and body, without the stylesheet and scripts every page
+/// shares, broken after each tag so snapshot diffs point at the element
+/// that changed instead of one long line.
+pub(crate) fn format_snapshot_html(html: &str) -> String {
+    let title = html
+        .split_once("<title>")
+        .and_then(|(_, rest)| rest.split_once("</title>"))
+        .map(|(title, _)| title)
+        .unwrap_or_default();
+    let body = html
+        .find("<body")
+        .map(|start| &html[start..])
+        .unwrap_or(html);
+    format!("<title>{}</title>\n{}", title, body.replace("><", ">\n<"))
+}
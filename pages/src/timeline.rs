@@ -0,0 +1,354 @@
+use chrono::NaiveDateTime;
+use common::models::{
+    ProxyRequest, Session, SessionEvent, SESSION_EVENT_APPROVAL, SESSION_EVENT_BREAKPOINT,
+    SESSION_EVENT_ERROR_INJECTED,
+};
+use leptos::{either::Either, prelude::*};
+use serde_json::Value;
+use std::collections::HashMap;
+use templates::{Breadcrumb, NavLink, Page};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEntryKind {
+    Request,
+    WebfetchRound,
+    Approval,
+    Breakpoint,
+    InjectedError,
+    Other,
+}
+
+impl TimelineEntryKind {
+    fn label(self) -> &'static str {
+        match self {
+            TimelineEntryKind::Request => "request",
+            TimelineEntryKind::WebfetchRound => "webfetch round",
+            TimelineEntryKind::Approval => "approval",
+            TimelineEntryKind::Breakpoint => "breakpoint",
+            TimelineEntryKind::InjectedError => "injected error",
+            TimelineEntryKind::Other => "event",
+        }
+    }
+}
+
+/// One row of the session timeline.
+#[derive(Debug)]
+pub struct TimelineEntry {
+    pub at: String,
+    pub kind: TimelineEntryKind,
+    pub summary: String,
+    pub duration_ms: Option<i64>,
+    pub href: Option<String>,
+}
+
+/// Milliseconds between two stored timestamps, if both parse.
+fn measure_duration_ms(start: &str, end: &str) -> Option<i64> {
+    let start = NaiveDateTime::parse_from_str(start, TIMESTAMP_FORMAT).ok()?;
+    let end = NaiveDateTime::parse_from_str(end, TIMESTAMP_FORMAT).ok()?;
+    Some((end - start).num_milliseconds())
+}
+
+fn format_duration_ms(duration_ms: i64) -> String {
+    if duration_ms < 1000 {
+        format!("{}ms", duration_ms)
+    } else {
+        format!("{:.1}s", duration_ms as f64 / 1000.0)
+    }
+}
+
+fn build_request_href(req: &ProxyRequest, request_id: &str) -> String {
+    format!(
+        "/_dashboard/sessions/{}/requests/{}",
+        req.session_id, request_id
+    )
+}
+
+fn build_request_entry(req: &ProxyRequest) -> TimelineEntry {
+    let mut summary = format!("{} {}", req.method, req.path);
+    if let Some(status) = req.response_status {
+        summary.push_str(&format!(" → {}", status));
+    }
+    if let Some(ref model) = req.model {
+        summary.push_str(&format!(" ({})", model));
+    }
+    if let Some(ref note) = req.note {
+        summary.push_str(&format!(" — {}", note));
+    }
+    TimelineEntry {
+        at: req.created_at.clone(),
+        kind: TimelineEntryKind::Request,
+        summary,
+        duration_ms: req
+            .response_status
+            .and_then(|_| measure_duration_ms(&req.created_at, &req.updated_at)),
+        href: Some(build_request_href(req, &req.id.to_string())),
+    }
+}
+
+/// Entries for the webfetch rounds recorded on an intercepted request. Each
+/// round is placed at the time its follow-up request was logged.
+fn build_webfetch_round_entries(
+    req: &ProxyRequest,
+    created_at_by_id: &HashMap<String, &str>,
+) -> Vec<TimelineEntry> {
+    let Some(rounds) = req
+        .webfetch_rounds_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<Vec<Value>>(json).ok())
+    else {
+        return vec![];
+    };
+    rounds
+        .iter()
+        .enumerate()
+        .map(|(round_index, round)| {
+            let decision = round
+                .get("decision")
+                .and_then(|field| field.as_str())
+                .unwrap_or("");
+            let mut tool_names: Vec<String> = round
+                .get("tool_names")
+                .and_then(|field| field.as_array())
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|name| name.as_str())
+                        .map(|name| name.to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            // Rounds decided per tool call name each call's own decision.
+            let tool_decisions: Vec<&str> = round
+                .get("tool_decisions")
+                .and_then(|field| field.as_array())
+                .map(|labels| labels.iter().filter_map(|label| label.as_str()).collect())
+                .unwrap_or_default();
+            if tool_decisions.len() == tool_names.len()
+                && tool_decisions.iter().any(|label| *label != decision)
+            {
+                for (tool_name, label) in tool_names.iter_mut().zip(&tool_decisions) {
+                    *tool_name = format!("{}: {}", tool_name, label);
+                }
+            }
+            let round_request_id = round.get("request_id").and_then(|field| field.as_str());
+            let at = round_request_id
+                .and_then(|request_id| created_at_by_id.get(request_id))
+                .map(|created_at| created_at.to_string())
+                .unwrap_or_else(|| req.created_at.clone());
+            TimelineEntry {
+                at,
+                kind: TimelineEntryKind::WebfetchRound,
+                summary: format!(
+                    "Round {}: {} ({})",
+                    round_index + 1,
+                    decision,
+                    tool_names.join(", ")
+                ),
+                duration_ms: None,
+                href: Some(build_request_href(
+                    req,
+                    round_request_id.unwrap_or(&req.id.to_string()),
+                )),
+            }
+        })
+        .collect()
+}
+
+fn build_event_entry(event: &SessionEvent) -> TimelineEntry {
+    let kind = match event.kind.as_str() {
+        SESSION_EVENT_APPROVAL => TimelineEntryKind::Approval,
+        SESSION_EVENT_BREAKPOINT => TimelineEntryKind::Breakpoint,
+        SESSION_EVENT_ERROR_INJECTED => TimelineEntryKind::InjectedError,
+        _ => TimelineEntryKind::Other,
+    };
+    TimelineEntry {
+        at: event.created_at.clone(),
+        kind,
+        summary: event.detail.clone(),
+        duration_ms: event.duration_ms,
+        href: None,
+    }
+}
+
+/// Interleave requests, their webfetch rounds and session events in chronological order.
+/// `requests` is newest first, as returned by `db::list_requests`.
+pub fn build_timeline_entries(
+    requests: &[ProxyRequest],
+    events: &[SessionEvent],
+) -> Vec<TimelineEntry> {
+    let created_at_by_id: HashMap<String, &str> = requests
+        .iter()
+        .map(|req| (req.id.to_string(), req.created_at.as_str()))
+        .collect();
+
+    let mut entries: Vec<TimelineEntry> =
+        requests.iter().rev().map(build_request_entry).collect();
+    entries.extend(
+        requests
+            .iter()
+            .flat_map(|req| build_webfetch_round_entries(req, &created_at_by_id)),
+    );
+    entries.extend(events.iter().map(build_event_entry));
+    // Stable sort keeps requests before rounds and events logged in the same second.
+    entries.sort_by(|left, right| left.at.cmp(&right.at));
+    entries
+}
+
+fn render_timeline_row(entry: &TimelineEntry) -> impl IntoView {
+    let time = entry.at.get(11..19).unwrap_or(&entry.at).to_string();
+    let kind = entry.kind.label();
+    let duration = entry
+        .duration_ms
+        .map(format_duration_ms)
+        .unwrap_or_default();
+    let summary = entry.summary.clone();
+    let summary_view = match entry.href.clone() {
+        Some(href) => Either::Left(view! { <a href={href}>{summary}</a> }),
+        None => Either::Right(summary),
+    };
+    view! {
+        <tr>
+            <td>{time}</td>
+            <td>{kind}</td>
+            <td>{duration}</td>
+            <td>{summary_view}</td>
+        </tr>
+    }
+}
+
+pub fn render_timeline_view(session: &Session, entries: &[TimelineEntry]) -> String {
+    let rows: Vec<_> = entries.iter().map(render_timeline_row).collect();
+
+    let content = view! {
+        <h2>"Timeline"</h2>
+        <p>"Requests, webfetch rounds, approval decisions and injected errors in chronological order."</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"Nothing recorded yet."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr>
+                        <th>"Time"</th>
+                        <th>"Kind"</th>
+                        <th>"Duration"</th>
+                        <th>"Summary"</th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Timeline", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session.id),
+            ),
+            Breadcrumb::current("Timeline"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION_ID: &str = "00000000-0000-0000-0000-000000000001";
+
+    fn build_request(id: &str, created_at: &str, rounds_json: Option<&str>) -> ProxyRequest {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "session_id": SESSION_ID,
+            "method": "POST",
+            "path": "/v1/messages",
+            "created_at": created_at,
+            "updated_at": "2026-01-01 00:00:05",
+            "response_status": 200,
+            "webfetch_rounds_json": rounds_json,
+        }))
+        .unwrap()
+    }
+
+    fn build_event(kind: &str, created_at: &str) -> SessionEvent {
+        serde_json::from_value(serde_json::json!({
+            "id": "00000000-0000-0000-0000-0000000000e1",
+            "session_id": SESSION_ID,
+            "kind": kind,
+            "detail": "Accept: WebFetch",
+            "duration_ms": 1500,
+            "created_at": created_at,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn duration_between_timestamps() {
+        assert_eq!(
+            measure_duration_ms("2026-01-01 00:00:01", "2026-01-01 00:00:03"),
+            Some(2000)
+        );
+        assert_eq!(measure_duration_ms("bad", "2026-01-01 00:00:03"), None);
+        assert_eq!(format_duration_ms(250), "250ms");
+        assert_eq!(format_duration_ms(1500), "1.5s");
+    }
+
+    #[test]
+    fn entries_interleave_chronologically() {
+        let round_request_id = "00000000-0000-0000-0000-0000000000a2";
+        let rounds_json = format!(
+            r#"[{{"decision": "Accept", "tool_names": ["WebFetch"], "request_id": "{}"}}]"#,
+            round_request_id
+        );
+        let requests = vec![
+            build_request(round_request_id, "2026-01-01 00:00:04", None),
+            build_request(
+                "00000000-0000-0000-0000-0000000000a1",
+                "2026-01-01 00:00:01",
+                Some(&rounds_json),
+            ),
+        ];
+        let events = vec![build_event(SESSION_EVENT_APPROVAL, "2026-01-01 00:00:03")];
+        let entries = build_timeline_entries(&requests, &events);
+        let kinds: Vec<TimelineEntryKind> = entries.iter().map(|entry| entry.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TimelineEntryKind::Request,
+                TimelineEntryKind::Approval,
+                TimelineEntryKind::Request,
+                TimelineEntryKind::WebfetchRound,
+            ]
+        );
+        assert_eq!(entries[0].duration_ms, Some(4000));
+        assert_eq!(entries[3].summary, "Round 1: Accept (WebFetch)");
+    }
+
+    #[test]
+    fn per_tool_round_names_each_decision() {
+        let rounds_json = r#"[
+            {"decision": "Per-tool", "tool_names": ["WebFetch", "WebFetch"],
+             "tool_decisions": ["Accept", "Mock"]},
+            {"decision": "Fail", "tool_names": ["WebFetch"], "tool_decisions": ["Fail"]}
+        ]"#;
+        let requests = vec![build_request(
+            "00000000-0000-0000-0000-0000000000a1",
+            "2026-01-01 00:00:01",
+            Some(rounds_json),
+        )];
+        let entries = build_timeline_entries(&requests, &[]);
+        let summaries: Vec<&str> = entries.iter().map(|entry| entry.summary.as_str()).collect();
+        assert!(summaries.contains(&"Round 1: Per-tool (WebFetch: Accept, WebFetch: Mock)"));
+        assert!(summaries.contains(&"Round 2: Fail (WebFetch)"));
+    }
+}
@@ -0,0 +1,148 @@
+use common::models::{RecentRequest, Session, ToolCallStats};
+use common::url::encode_uri_component;
+use leptos::{either::Either, prelude::*};
+use templates::{pagination_nav, Breadcrumb, NavLink, Page, Pagination};
+
+use crate::home::render_recent_requests;
+
+fn build_tools_url(session: &Session) -> String {
+    format!("/_dashboard/sessions/{}/tools", session.id)
+}
+
+/// The share of calls that failed, e.g. "25%".
+fn format_error_rate(tool_call_stats: &ToolCallStats) -> String {
+    if tool_call_stats.call_count == 0 {
+        return String::new();
+    }
+    format!(
+        "{:.0}%",
+        100.0 * tool_call_stats.error_count as f64 / tool_call_stats.call_count as f64
+    )
+}
+
+fn render_tool_call_stats_table(
+    session: &Session,
+    tool_call_stats: &[ToolCallStats],
+) -> impl IntoView {
+    if tool_call_stats.is_empty() {
+        return Either::Left(view! { <p>"No tool results seen yet."</p> });
+    }
+    let tools_url = build_tools_url(session);
+    let rows = tool_call_stats
+        .iter()
+        .map(|tool_call_stats| {
+            let requests_href = format!(
+                "{}/requests?tool={}",
+                tools_url,
+                encode_uri_component(&tool_call_stats.tool_name)
+            );
+            view! {
+                <tr>
+                    <td><a href={requests_href}>{tool_call_stats.tool_name.clone()}</a></td>
+                    <td>{tool_call_stats.call_count.to_string()}</td>
+                    <td>{tool_call_stats.error_count.to_string()}</td>
+                    <td>{format_error_rate(tool_call_stats)}</td>
+                    <td>{format!("{:.0}", tool_call_stats.avg_input_bytes)}</td>
+                    <td>{tool_call_stats.request_count.to_string()}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    Either::Right(view! {
+        <table>
+            <tr>
+                <th>"Tool"</th>
+                <th>"Calls"</th>
+                <th>"Errors"</th>
+                <th>"Error Rate"</th>
+                <th>"Avg Input Bytes"</th>
+                <th>"Requests"</th>
+            </tr>
+            {rows}
+        </table>
+    })
+}
+
+/// The tools called in a session. A call is counted when the client sends
+/// its `tool_result` back, so calls still waiting for a result are missing.
+pub fn render_tools_view(session: &Session, tool_call_stats: &[ToolCallStats]) -> String {
+    let content = view! {
+        <h2>"Tools"</h2>
+        <p>"Tool calls are counted from the requests sending their results back; an error is a result with " <code>"is_error"</code> " set."</p>
+        {render_tool_call_stats_table(session, tool_call_stats)}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Tools", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session.id),
+            ),
+            Breadcrumb::current("Tools"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// The session's requests sending back results of one tool's calls, newest
+/// first.
+pub fn render_tool_requests_view(
+    session: &Session,
+    tool_name: &str,
+    tool_requests: &[RecentRequest],
+    pagination: &Pagination,
+) -> String {
+    let nav_top = pagination_nav(pagination);
+    let nav_bottom = pagination_nav(pagination);
+
+    let content = view! {
+        <h2>"Requests"</h2>
+        <p>{format!("Total: {}", pagination.total_items)}</p>
+        {nav_top}
+        {render_recent_requests(tool_requests)}
+        {nav_bottom}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - {}", session.name, tool_name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session.id),
+            ),
+            Breadcrumb::link("Tools", build_tools_url(session)),
+            Breadcrumb::current(tool_name),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_error_rate_rounds_to_whole_percent() {
+        let tool_call_stats = ToolCallStats {
+            tool_name: "Read".to_string(),
+            call_count: 3,
+            error_count: 1,
+            avg_input_bytes: 12.0,
+            request_count: 2,
+        };
+        assert_eq!(format_error_rate(&tool_call_stats), "33%");
+    }
+}
@@ -0,0 +1,44 @@
+use common::users::{ANONYMOUS_USER, MAX_USER_NAME_LEN};
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+/// Pick the name this browser records on notes, stars and approval decisions.
+pub fn render_user_view(user_name: Option<&str>) -> String {
+    let value = user_name.unwrap_or_default().to_string();
+    let content = view! {
+        <h2>"User"</h2>
+        <p>
+            "Notes, stars and approval decisions made from this browser are recorded under this name. "
+            "There is no password; clear the name to act as "
+            <code>{ANONYMOUS_USER}</code>
+            "."
+        </p>
+        <form method="POST" action="/_dashboard/user">
+            <input
+                type="text"
+                name="name"
+                value={value}
+                size="30"
+                maxlength={MAX_USER_NAME_LEN.to_string()}
+                placeholder="alice"
+            />
+            <input type="submit" value="Save"/>
+        </form>
+    };
+
+    Page {
+        title: "Gateway Proxy - User".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::current("User"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![InfoRow::new(
+            "Current User",
+            user_name.unwrap_or(ANONYMOUS_USER),
+        )],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,66 @@
+use common::{models::Session, validation::VALIDATION_MODES};
+use leptos::prelude::*;
+use templates::{Breadcrumb, NavLink, Page};
+
+pub fn render_validation_view(session: &Session) -> String {
+    let session_id = session.id.to_string();
+    let form_action = format!("/_dashboard/sessions/{}/validation", session_id);
+    let active_mode = session.validation_mode.clone().unwrap_or_default();
+    let schema_value = session.validation_schema.clone().unwrap_or_default();
+
+    let mode_options: Vec<_> = VALIDATION_MODES
+        .iter()
+        .map(|mode| {
+            let selected = mode.key == active_mode;
+            let label = format!("{} — {}", mode.label, mode.description);
+            view! {
+                <option value={mode.key} selected={selected}>{label}</option>
+            }
+        })
+        .collect();
+    let off_selected = active_mode.is_empty();
+
+    let content = view! {
+        <h2>"Request Validation"</h2>
+        <p>"Check incoming request bodies against a JSON Schema before forwarding. When no custom schema is set, requests to " <code>"/messages"</code> " endpoints are checked against the built-in Anthropic Messages schema."</p>
+        <form method="POST" action={form_action}>
+            <table>
+                <tr>
+                    <td><label>"Mode"</label></td>
+                    <td>
+                        <select name="mode">
+                            <option value="" selected={off_selected}>"Off"</option>
+                            {mode_options}
+                        </select>
+                    </td>
+                </tr>
+                <tr>
+                    <td><label>"Custom JSON Schema"</label></td>
+                    <td><textarea name="schema" rows="20" cols="80" placeholder="Leave empty to use the built-in Messages schema">{schema_value}</textarea></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Save"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Validation", session.name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session_id),
+            ),
+            Breadcrumb::current("Validation"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
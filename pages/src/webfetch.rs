@@ -1,8 +1,17 @@
+use common::config::AppConfig;
+use common::extraction::EXTRACTION_MODES;
 use common::models::{PendingToolInfo, Session};
 use leptos::{either::Either, prelude::*};
-use templates::{Breadcrumb, NavLink, Page};
+use templates::{Breadcrumb, NavLink, Page, Subpage};
 
-pub fn render_webfetch_view(session: &Session) -> String {
+pub fn render_webfetch_view(
+    session: &Session,
+    config: &AppConfig,
+    whitelist_rule_count: usize,
+    deny_rule_count: usize,
+    policy_count: usize,
+    cache_entry_count: usize,
+) -> String {
     let session_id = session.id.to_string();
     let wf_enable_action = format!(
         "/_dashboard/sessions/{}/tool-intercept/webfetch",
@@ -12,21 +21,84 @@ pub fn render_webfetch_view(session: &Session) -> String {
         "/_dashboard/sessions/{}/tool-intercept/webfetch/clear",
         session_id
     );
-    let whitelist_save_action = format!(
-        "/_dashboard/sessions/{}/tool-intercept/webfetch/whitelist",
+    let private_targets_allow_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/private-targets",
         session_id
     );
-    let whitelist_clear_action = format!(
-        "/_dashboard/sessions/{}/tool-intercept/webfetch/whitelist/clear",
+    let private_targets_block_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/private-targets/clear",
         session_id
     );
-
+    let robots_txt_respect_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/robots-txt",
+        session_id
+    );
+    let robots_txt_ignore_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/robots-txt/clear",
+        session_id
+    );
+    let fetch_limits_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/fetch-limits",
+        session_id
+    );
+    let max_content_bytes = session
+        .webfetch_max_content_bytes
+        .map(|max_content_bytes| max_content_bytes.to_string())
+        .unwrap_or_default();
+    let fetch_timeout_secs = session
+        .webfetch_fetch_timeout_secs
+        .map(|timeout_secs| timeout_secs.to_string())
+        .unwrap_or_default();
+    let extraction_mode_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/extraction-mode",
+        session_id
+    );
+    let extraction_mode_options: Vec<_> = EXTRACTION_MODES
+        .iter()
+        .map(|mode| {
+            let selected = mode.key == session.webfetch_extraction_mode;
+            let label = format!("{} — {}", mode.label, mode.description);
+            view! {
+                <option value={mode.key} selected={selected}>{label}</option>
+            }
+        })
+        .collect();
+    let raw_mode_enable_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/raw-mode",
+        session_id
+    );
+    let raw_mode_disable_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/raw-mode/clear",
+        session_id
+    );
+    let raw_mode_enabled = session.webfetch_raw_mode;
+    let agent_backend_action = format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/agent-backend",
+        session_id
+    );
+    let agent_url = session.webfetch_agent_url.clone().unwrap_or_default();
+    let agent_model = session.webfetch_agent_model.clone().unwrap_or_default();
+    let agent_api_key = session.webfetch_agent_api_key.clone().unwrap_or_default();
+    let default_agent_model = config.webfetch_agent_model.clone();
+    let default_max_content_bytes = config.webfetch_max_content_bytes.to_string();
+    let default_fetch_timeout_secs = config.webfetch_fetch_timeout_secs.to_string();
     let wf_active = session.webfetch_intercept;
-    let whitelist_value = session.webfetch_whitelist.clone().unwrap_or_default();
-    let has_whitelist = session
-        .webfetch_whitelist
-        .as_ref()
-        .is_some_and(|whitelist| !whitelist.trim().is_empty());
+    let private_targets_allowed = session.webfetch_allow_private_targets;
+    let robots_txt_respected = session.webfetch_respect_robots_txt;
+    let tool_executor_rows: Vec<_> = config
+        .tool_executors
+        .iter()
+        .map(|executor| {
+            let tool_name = executor.tool_name.clone();
+            let description = executor.describe();
+            view! {
+                <tr>
+                    <td>{tool_name}</td>
+                    <td>{description}</td>
+                </tr>
+            }
+        })
+        .collect();
 
     let content = view! {
         <h2>"WebFetch Intercept"</h2>
@@ -54,28 +126,147 @@ pub fn render_webfetch_view(session: &Session) -> String {
             })
         }}
 
-        <h3>"Domain Whitelist"</h3>
-        <p>"WebFetch calls to whitelisted domains are auto-accepted without manual approval. One domain per line. A domain like " <code>"github.com"</code> " matches " <code>"github.com"</code> " and any subdomain (e.g. " <code>"api.github.com"</code> ")."</p>
-        <form method="POST" action={whitelist_save_action}>
+        <h3>"Private Network Targets"</h3>
+        {if private_targets_allowed {
+            Either::Left(view! {
+                <p>
+                    "Accepted WebFetch calls "
+                    <strong>"may"</strong>
+                    " reach private, loopback and link-local addresses. Only allow this for trusted setups."
+                    " "
+                    <form method="POST" action={private_targets_block_action}>
+                        <button type="submit">"Block"</button>
+                    </form>
+                </p>
+            })
+        } else {
+            Either::Right(view! {
+                <p>
+                    "Accepted WebFetch calls to hosts resolving to private, loopback or link-local addresses are refused."
+                    " "
+                    <form method="POST" action={private_targets_allow_action}>
+                        <button type="submit">"Allow"</button>
+                    </form>
+                </p>
+            })
+        }}
+
+        <h3>"robots.txt"</h3>
+        {if robots_txt_respected {
+            Either::Left(view! {
+                <p>
+                    "Accepted WebFetch calls "
+                    <strong>"honor"</strong>
+                    " the target site's robots.txt; disallowed URLs get an error tool_result. Each site's robots.txt is looked up once per session."
+                    " "
+                    <form method="POST" action={robots_txt_ignore_action}>
+                        <button type="submit">"Ignore"</button>
+                    </form>
+                </p>
+            })
+        } else {
+            Either::Right(view! {
+                <p>
+                    "Accepted WebFetch calls do not check robots.txt."
+                    " "
+                    <form method="POST" action={robots_txt_respect_action}>
+                        <button type="submit">"Respect"</button>
+                    </form>
+                </p>
+            })
+        }}
+
+        <h3>"Page Extraction"</h3>
+        <p>"How accepted WebFetch calls turn a fetched page into text. Changing the mode clears the fetch cache."</p>
+        <form method="POST" action={extraction_mode_action}>
+            <select name="mode">{extraction_mode_options}</select>
+            " "
+            <input type="submit" value="Save"/>
+        </form>
+
+        <h3>"Fetch Limits"</h3>
+        <p>"Accepted WebFetch calls stop reading a page after the size cap and give up after the timeout. Leave a field empty to use the default from the settings."</p>
+        <form method="POST" action={fetch_limits_action}>
             <table>
                 <tr>
-                    <td><label>"Domains"</label></td>
-                    <td><textarea name="whitelist" rows="6" cols="60">{whitelist_value.clone()}</textarea></td>
+                    <td><label>"Max Content Bytes"</label></td>
+                    <td><input type="number" name="max_content_bytes" min="1" value={max_content_bytes} placeholder={default_max_content_bytes}/></td>
+                </tr>
+                <tr>
+                    <td><label>"Timeout (seconds)"</label></td>
+                    <td><input type="number" name="fetch_timeout_secs" min="1" value={fetch_timeout_secs} placeholder={default_fetch_timeout_secs}/></td>
                 </tr>
                 <tr>
                     <td></td>
-                    <td><input type="submit" value="Save" /></td>
+                    <td><input type="submit" value="Save"/></td>
                 </tr>
             </table>
         </form>
-        {if has_whitelist {
+
+        <h3>"Raw Mode"</h3>
+        {if raw_mode_enabled {
             Either::Left(view! {
-                <form method="POST" action={whitelist_clear_action}>
-                    <button type="submit">"Clear Whitelist"</button>
-                </form>
+                <p>
+                    "Raw mode is "
+                    <strong>"on"</strong>
+                    ": accepted pages are returned as extracted text without the agent summary."
+                    " "
+                    <form method="POST" action={raw_mode_disable_action}>
+                        <button type="submit">"Disable"</button>
+                    </form>
+                </p>
             })
         } else {
-            Either::Right(())
+            Either::Right(view! {
+                <p>
+                    "Accepted pages are summarized by the agent. Use \"Accept raw\" on an approval to skip it once."
+                    " "
+                    <form method="POST" action={raw_mode_enable_action}>
+                        <button type="submit">"Enable"</button>
+                    </form>
+                </p>
+            })
+        }}
+
+        <h3>"Agent Backend"</h3>
+        <p>"Accepted pages are summarized by an agent request, sent to the session's upstream by default. Point it at another Messages endpoint, such as a cheap local model, to keep summaries off your main quota. The client's credentials are not sent to a custom URL; set an API key if it needs one. Leave a field empty to use the default."</p>
+        <form method="POST" action={agent_backend_action}>
+            <table>
+                <tr>
+                    <td><label>"URL"</label></td>
+                    <td><input type="text" name="agent_url" size="60" value={agent_url} placeholder="http://localhost:4000/v1/messages"/></td>
+                </tr>
+                <tr>
+                    <td><label>"Model"</label></td>
+                    <td><input type="text" name="agent_model" size="40" value={agent_model} placeholder={default_agent_model}/></td>
+                </tr>
+                <tr>
+                    <td><label>"API Key"</label></td>
+                    <td><input type="password" name="agent_api_key" size="60" value={agent_api_key} placeholder="sk-..."/></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Save"/></td>
+                </tr>
+            </table>
+        </form>
+
+        <h3>"Tool Executors"</h3>
+        {if tool_executor_rows.is_empty() {
+            Either::Left(view! {
+                <p>"No tool executors are configured. Accept runs WebFetch calls only; add [[tool_executors]] to the config file to execute other tools."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <p>"Accepted calls to these tools are run by the proxy. Set in the config file."</p>
+                <table>
+                    <tr>
+                        <th>"Tool"</th>
+                        <th>"Executor"</th>
+                    </tr>
+                    {tool_executor_rows}
+                </table>
+            })
         }}
     };
 
@@ -100,82 +291,228 @@ pub fn render_webfetch_view(session: &Session) -> String {
         nav_links: vec![NavLink::back()],
         info_rows: vec![],
         content,
-        subpages: vec![],
+        subpages: vec![
+            Subpage::new(
+                "Domain Whitelist",
+                format!(
+                    "/_dashboard/sessions/{}/tool-intercept/webfetch/whitelist",
+                    session_id
+                ),
+                whitelist_rule_count,
+            ),
+            Subpage::new(
+                "Deny Rules",
+                format!(
+                    "/_dashboard/sessions/{}/tool-intercept/webfetch/denylist",
+                    session_id
+                ),
+                deny_rule_count,
+            ),
+            Subpage::new(
+                "Auto-Decision Policies",
+                format!(
+                    "/_dashboard/sessions/{}/tool-intercept/webfetch/policies",
+                    session_id
+                ),
+                policy_count,
+            ),
+            Subpage::new(
+                "Fetch Cache",
+                format!(
+                    "/_dashboard/sessions/{}/tool-intercept/webfetch/cache",
+                    session_id
+                ),
+                cache_entry_count,
+            ),
+        ],
     }
     .render()
 }
 
-pub fn render_approvals_view(
-    session: &Session,
-    pending: &[(String, Vec<PendingToolInfo>)],
-) -> String {
-    let session_id = session.id.to_string();
-    let either_active = session.webfetch_intercept;
+/// Accept, Accept raw, Accept with edits, Fail and Mock buttons that decide a
+/// whole approval round.
+fn render_round_decision_buttons(approvals_url: &str, approval_id: &str) -> impl IntoView {
+    let accept_action = format!("{}/accept/{}", approvals_url, approval_id);
+    let accept_raw_action = format!("{}/accept-raw/{}", approvals_url, approval_id);
+    let edit_href = format!("{}/edit/{}", approvals_url, approval_id);
+    let fail_action = format!("{}/fail/{}", approvals_url, approval_id);
+    let mock_action = format!("{}/mock/{}", approvals_url, approval_id);
+    view! {
+        <form method="POST" action={accept_action}>
+            <button type="submit">"Accept"</button>
+        </form>
+        " "
+        <form method="POST" action={accept_raw_action}>
+            <button type="submit">"Accept raw"</button>
+        </form>
+        " "
+        <a href={edit_href}>"Accept with edits"</a>
+        " "
+        <form method="POST" action={fail_action}>
+            <button type="submit">"Fail"</button>
+        </form>
+        " "
+        <form method="POST" action={mock_action}>
+            <button type="submit">"Mock"</button>
+        </form>
+    }
+}
 
-    let pending_rows: Vec<_> = pending
-        .iter()
-        .flat_map(|(approval_id, tools)| {
-            let session_id = session_id.clone();
-            tools.iter().map(move |tool| {
-                let fail_action = format!(
-                    "/_dashboard/sessions/{}/tool-intercept/approvals/fail/{}",
-                    session_id, approval_id
-                );
-                let mock_action = format!(
-                    "/_dashboard/sessions/{}/tool-intercept/approvals/mock/{}",
-                    session_id, approval_id
-                );
-                let accept_action = format!(
-                    "/_dashboard/sessions/{}/tool-intercept/approvals/accept/{}",
-                    session_id, approval_id
-                );
-                let name = tool.name.clone();
-                let input_summary = tool.input_summary.clone();
+/// Accept, Accept raw, Fail and Mock buttons for a single tool call of an approval round.
+fn render_tool_decision_buttons(
+    approvals_url: &str,
+    approval_id: &str,
+    tool_use_id: &str,
+) -> impl IntoView {
+    let tool_action = format!("{}/{}/tools/{}", approvals_url, approval_id, tool_use_id);
+    let buttons: Vec<_> = [
+        ("accept", "Accept"),
+        ("accept_raw", "Accept raw"),
+        ("fail", "Fail"),
+        ("mock", "Mock"),
+    ]
+    .into_iter()
+    .map(|(decision, label)| {
+        view! {
+            <form method="POST" action={tool_action.clone()}>
+                <input type="hidden" name="decision" value={decision}/>
+                <button type="submit">{label}</button>
+            </form>
+            " "
+        }
+    })
+    .collect();
+    buttons
+}
+
+/// The decision already made for one tool call and who made it, e.g.
+/// `"Mock by alice"`.
+pub(crate) fn format_tool_decision(tool: &PendingToolInfo) -> Option<String> {
+    let decision = tool.decision.as_ref()?;
+    Some(match &tool.decided_by {
+        Some(decided_by) => format!("{} by {}", decision, decided_by),
+        None => decision.clone(),
+    })
+}
+
+fn build_approvals_url(session_id: &str) -> String {
+    format!(
+        "/_dashboard/sessions/{}/tool-intercept/approvals",
+        session_id
+    )
+}
+
+/// Table rows of one pending approval, in a `<tbody>` the live approvals
+/// script replaces or removes as the approval changes.
+fn render_pending_approval_rows(
+    approvals_url: &str,
+    approval_id: &str,
+    tools: &[PendingToolInfo],
+) -> impl IntoView {
+    let round_buttons = render_round_decision_buttons(approvals_url, approval_id);
+    let rows: Vec<AnyView> = if let [tool] = tools {
+        vec![view! {
+            <tr>
+                <td><code>{tool.name.clone()}</code></td>
+                <td>{tool.input_summary.clone()}</td>
+                <td>{round_buttons}</td>
+            </tr>
+        }
+        .into_any()]
+    } else {
+        // Several calls: decide each one, or all of them at once.
+        let mut rows: Vec<AnyView> = tools
+            .iter()
+            .map(|tool| {
+                let decision_view = match format_tool_decision(tool) {
+                    Some(decision) => Either::Left(view! {
+                        <em>{format!("Decided: {}", decision)}</em>
+                    }),
+                    None => Either::Right(render_tool_decision_buttons(
+                        approvals_url,
+                        approval_id,
+                        &tool.tool_use_id,
+                    )),
+                };
                 view! {
                     <tr>
-                        <td><code>{name}</code></td>
-                        <td>{input_summary}</td>
-                        <td>
-                            <form method="POST" action={accept_action}>
-                                <button type="submit">"Accept"</button>
-                            </form>
-                            " "
-                            <form method="POST" action={fail_action}>
-                                <button type="submit">"Fail"</button>
-                            </form>
-                            " "
-                            <form method="POST" action={mock_action}>
-                                <button type="submit">"Mock"</button>
-                            </form>
-                        </td>
+                        <td><code>{tool.name.clone()}</code></td>
+                        <td>{tool.input_summary.clone()}</td>
+                        <td>{decision_view}</td>
                     </tr>
                 }
+                .into_any()
             })
+            .collect();
+        rows.push(
+            view! {
+                <tr>
+                    <td colspan="2"><em>{format!("All {} calls", tools.len())}</em></td>
+                    <td>{round_buttons}</td>
+                </tr>
+            }
+            .into_any(),
+        );
+        rows
+    };
+    let approval_id = approval_id.to_string();
+    view! { <tbody data-approval-id={approval_id}>{rows}</tbody> }
+}
+
+/// HTML of one pending approval's rows, as pushed on the approval event stream.
+pub fn render_pending_approval_html(
+    session_id: &str,
+    approval_id: &str,
+    tools: &[PendingToolInfo],
+) -> String {
+    render_pending_approval_rows(&build_approvals_url(session_id), approval_id, tools).to_html()
+}
+
+/// A session's pending approvals, kept current by the approval event stream
+/// and decided without reloading the page.
+pub fn render_pending_approvals_section(
+    session_id: &str,
+    pending: &[(String, Vec<PendingToolInfo>)],
+) -> impl IntoView {
+    let approvals_url = build_approvals_url(session_id);
+    let events_url = format!("{}/events", approvals_url);
+    let pending_count = if pending.is_empty() {
+        "No pending approvals.".to_string()
+    } else {
+        format!("{} pending", pending.len())
+    };
+    let table_class = if pending.is_empty() { "hidden" } else { "" };
+    let approval_rows: Vec<_> = pending
+        .iter()
+        .map(|(approval_id, tools)| {
+            render_pending_approval_rows(&approvals_url, approval_id, tools)
         })
         .collect();
 
-    let content = view! {
-        {if either_active {
-            Some(view! { <meta http-equiv="refresh" content="2" /> })
-        } else {
-            None
-        }}
-
-        <h2>"Pending Approvals"</h2>
-        {if pending.is_empty() {
-            Either::Left(view! {
-                <p>"No pending approvals."</p>
-            })
-        } else {
-            Either::Right(view! {
-                <p>{format!("{} pending", pending.len())}</p>
-                <table>
+    view! {
+        <div data-approval-events={events_url}>
+            <h2>"Pending Approvals"</h2>
+            <p>
+                <span data-approval-count>{pending_count}</span>
+                " "
+                <small data-approval-status>"(live updates need JavaScript; reload to refresh)"</small>
+            </p>
+            <table data-approval-table class={table_class}>
+                <thead>
                     <tr><th>"Tool"</th><th>"Input"</th><th></th></tr>
-                    {pending_rows}
-                </table>
-            })
-        }}
-    };
+                </thead>
+                {approval_rows}
+            </table>
+        </div>
+    }
+}
+
+pub fn render_approvals_view(
+    session: &Session,
+    pending: &[(String, Vec<PendingToolInfo>)],
+) -> String {
+    let session_id = session.id.to_string();
+    let content = render_pending_approvals_section(&session_id, pending);
 
     Page {
         title: format!(
@@ -202,3 +539,68 @@ pub fn render_approvals_view(
     }
     .render()
 }
+
+pub fn render_edit_approval_view(
+    session: &Session,
+    approval_id: &str,
+    tools: &[PendingToolInfo],
+) -> String {
+    let session_id = session.id.to_string();
+    let approvals_url = build_approvals_url(&session_id);
+    let edit_action = format!("{}/edit/{}", approvals_url, approval_id);
+
+    let input_rows: Vec<_> = tools
+        .iter()
+        .map(|tool| {
+            let field_name = format!("input_{}", tool.tool_use_id);
+            let input_json = serde_json::to_string_pretty(&tool.input).unwrap_or_default();
+            view! {
+                <tr>
+                    <td><code>{tool.name.clone()}</code><br/><small>{tool.tool_use_id.clone()}</small></td>
+                    <td><textarea name={field_name} rows="10" cols="80">{input_json}</textarea></td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Accept with Edits"</h2>
+        <p>"Edit the tool input JSON, e.g. the URL or prompt, then accept. The proxy fetches with the edited input; deny rules and the private target check still apply."</p>
+        <form method="POST" action={edit_action}>
+            <table>
+                <tr><th>"Tool"</th><th>"Input"</th></tr>
+                {input_rows}
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Accept with Edits"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Accept with Edits",
+            session.name
+        ),
+        breadcrumbs: vec![
+            Breadcrumb::link("Home", "/_dashboard"),
+            Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+            Breadcrumb::link(
+                format!("Session {}", session.name),
+                format!("/_dashboard/sessions/{}", session_id),
+            ),
+            Breadcrumb::link(
+                "Tool Intercept",
+                format!("/_dashboard/sessions/{}/tool-intercept", session_id),
+            ),
+            Breadcrumb::link("Pending Approvals", approvals_url),
+            Breadcrumb::current("Accept with Edits"),
+        ],
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
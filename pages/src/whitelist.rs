@@ -0,0 +1,376 @@
+use chrono::Utc;
+use common::models::{
+    Session, WebfetchDenyRule, WebfetchWhitelistRule, DEFAULT_WEBFETCH_DENY_SUGGESTIONS,
+};
+use leptos::{either::Either, prelude::*};
+use templates::{Breadcrumb, NavLink, Page};
+
+fn build_whitelist_url(session: &Session) -> String {
+    format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/whitelist",
+        session.id
+    )
+}
+
+fn build_denylist_url(session: &Session) -> String {
+    format!(
+        "/_dashboard/sessions/{}/tool-intercept/webfetch/denylist",
+        session.id
+    )
+}
+
+/// Breadcrumbs down to the WebFetch Intercept page, followed by `trail`.
+pub(crate) fn build_webfetch_breadcrumbs(
+    session: &Session,
+    trail: Vec<Breadcrumb>,
+) -> Vec<Breadcrumb> {
+    let session_id = session.id.to_string();
+    let mut breadcrumbs = vec![
+        Breadcrumb::link("Home", "/_dashboard"),
+        Breadcrumb::link("Sessions", "/_dashboard/sessions"),
+        Breadcrumb::link(
+            format!("Session {}", session.name),
+            format!("/_dashboard/sessions/{}", session_id),
+        ),
+        Breadcrumb::link(
+            "Tool Intercept",
+            format!("/_dashboard/sessions/{}/tool-intercept", session_id),
+        ),
+        Breadcrumb::link(
+            "WebFetch Intercept",
+            format!(
+                "/_dashboard/sessions/{}/tool-intercept/webfetch",
+                session_id
+            ),
+        ),
+    ];
+    breadcrumbs.extend(trail);
+    breadcrumbs
+}
+
+/// Whether a rule's expiry is at or before `now` ("YYYY-MM-DD HH:MM:SS", UTC).
+fn is_whitelist_rule_expired(rule: &WebfetchWhitelistRule, now: &str) -> bool {
+    rule.expires_at
+        .as_deref()
+        .is_some_and(|expires_at| expires_at <= now)
+}
+
+/// Convert a stored "YYYY-MM-DD HH:MM:SS" expiry into a `datetime-local` input value.
+fn format_expiry_input(expires_at: Option<&str>) -> String {
+    expires_at
+        .map(|expires_at| expires_at.replacen(' ', "T", 1))
+        .unwrap_or_default()
+}
+
+/// Form fields shared by the new and edit rule pages, prefilled from `rule` when editing.
+fn render_whitelist_rule_fields(rule: Option<&WebfetchWhitelistRule>) -> impl IntoView {
+    let domain = rule.map(|rule| rule.domain.clone()).unwrap_or_default();
+    let allow_subdomains = rule.is_none_or(|rule| rule.allow_subdomains);
+    let path_prefix = rule
+        .and_then(|rule| rule.path_prefix.clone())
+        .unwrap_or_default();
+    let expires_at = format_expiry_input(rule.and_then(|rule| rule.expires_at.as_deref()));
+    view! {
+        <tr>
+            <td><label>"Domain"</label></td>
+            <td><input type="text" name="domain" required value={domain} size="60" placeholder="github.com"/></td>
+        </tr>
+        <tr>
+            <td><label>"Allow Subdomains"</label></td>
+            <td><input type="checkbox" name="allow_subdomains" value="1" checked={allow_subdomains}/></td>
+        </tr>
+        <tr>
+            <td><label>"Path Prefix"</label></td>
+            <td><input type="text" name="path_prefix" value={path_prefix} size="60" placeholder="/docs/"/></td>
+        </tr>
+        <tr>
+            <td><label>"Expires (UTC)"</label></td>
+            <td><input type="datetime-local" name="expires_at" step="1" value={expires_at}/></td>
+        </tr>
+    }
+}
+
+pub fn render_whitelist_view(session: &Session, rules: &[WebfetchWhitelistRule]) -> String {
+    let session_id = session.id.to_string();
+    let whitelist_url = build_whitelist_url(session);
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let total = rules.len();
+
+    let rows: Vec<_> = rules
+        .iter()
+        .map(|rule| {
+            let edit_href = format!("{}/{}/edit", whitelist_url, rule.id);
+            let delete_action = format!("{}/{}/delete", whitelist_url, rule.id);
+            let subdomains = if rule.allow_subdomains { "yes" } else { "no" };
+            let path_prefix = rule.path_prefix.clone().unwrap_or_else(|| "-".to_string());
+            let expires_at = rule
+                .expires_at
+                .clone()
+                .unwrap_or_else(|| "never".to_string());
+            let status = if is_whitelist_rule_expired(rule, &now) {
+                "expired"
+            } else {
+                "active"
+            };
+            view! {
+                <tr>
+                    <td><code>{rule.domain.clone()}</code></td>
+                    <td>{subdomains}</td>
+                    <td>{path_prefix}</td>
+                    <td>{expires_at}</td>
+                    <td>{status}</td>
+                    <td>
+                        <a href={edit_href}>"Edit"</a>
+                        " "
+                        <form method="POST" action={delete_action}>
+                            <button type="submit">"Delete"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Domain Whitelist"</h2>
+        <p>"WebFetch calls matching an active rule are auto-accepted without manual approval. A rule matches when the URL host is the domain (or a subdomain of it, if allowed) and the URL path starts with the path prefix, if one is set."</p>
+        <p>{format!("Total: {}", total)}</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No whitelist rules configured."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr>
+                        <th>"Domain"</th>
+                        <th>"Subdomains"</th>
+                        <th>"Path Prefix"</th>
+                        <th>"Expires"</th>
+                        <th>"Status"</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Domain Whitelist",
+            session.name
+        ),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![Breadcrumb::current("Domain Whitelist")],
+        ),
+        nav_links: vec![
+            NavLink::new(
+                "New Rule",
+                format!(
+                    "/_dashboard/sessions/{}/tool-intercept/webfetch/whitelist/new",
+                    session_id
+                ),
+            ),
+            NavLink::back(),
+        ],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_new_whitelist_rule_form(session: &Session) -> String {
+    let form_action = build_whitelist_url(session);
+
+    let form = view! {
+        <h2>"New Whitelist Rule"</h2>
+        <form method="POST" action={form_action}>
+            <table>
+                {render_whitelist_rule_fields(None)}
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Add Rule"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - New Whitelist Rule",
+            session.name
+        ),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![
+                Breadcrumb::link("Domain Whitelist", build_whitelist_url(session)),
+                Breadcrumb::current("New"),
+            ],
+        ),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content: form,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_edit_whitelist_rule_form(session: &Session, rule: &WebfetchWhitelistRule) -> String {
+    let edit_action = format!("{}/{}/edit", build_whitelist_url(session), rule.id);
+
+    let form = view! {
+        <h2>"Edit Whitelist Rule"</h2>
+        <form method="POST" action={edit_action}>
+            <table>
+                {render_whitelist_rule_fields(Some(rule))}
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Save"/></td>
+                </tr>
+            </table>
+        </form>
+    };
+
+    Page {
+        title: format!(
+            "Gateway Proxy - Session {} - Edit Whitelist Rule",
+            session.name
+        ),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![
+                Breadcrumb::link("Domain Whitelist", build_whitelist_url(session)),
+                Breadcrumb::current("Edit"),
+            ],
+        ),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content: form,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_denylist_view(session: &Session, rules: &[WebfetchDenyRule]) -> String {
+    let denylist_url = build_denylist_url(session);
+    let total = rules.len();
+
+    let rows: Vec<_> = rules
+        .iter()
+        .map(|rule| {
+            let delete_action = format!("{}/{}/delete", denylist_url, rule.id);
+            view! {
+                <tr>
+                    <td><code>{rule.pattern.clone()}</code></td>
+                    <td>{rule.created_at.clone()}</td>
+                    <td>
+                        <form method="POST" action={delete_action}>
+                            <button type="submit">"Delete"</button>
+                        </form>
+                    </td>
+                </tr>
+            }
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Deny Rules"</h2>
+        <p>"WebFetch calls to matching hosts are failed immediately, before the whitelist and the approval queue. A pattern is an IP range such as " <code>"10.0.0.0/8"</code> ", a single IP, or a domain, which also matches its subdomains. IP ranges only match URLs with a literal IP host."</p>
+        <p>{format!("Total: {}", total)}</p>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No deny rules configured."</p> })
+        } else {
+            Either::Right(view! {
+                <table>
+                    <tr>
+                        <th>"Pattern"</th>
+                        <th>"Created"</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - Deny Rules", session.name),
+        breadcrumbs: build_webfetch_breadcrumbs(session, vec![Breadcrumb::current("Deny Rules")]),
+        nav_links: vec![
+            NavLink::new("New Rule", format!("{}/new", denylist_url)),
+            NavLink::back(),
+        ],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_new_deny_rule_form(session: &Session, rules: &[WebfetchDenyRule]) -> String {
+    let form_action = build_denylist_url(session);
+
+    let existing_patterns: Vec<&str> = rules.iter().map(|rule| rule.pattern.as_str()).collect();
+    let deny_suggestions: Vec<&str> = DEFAULT_WEBFETCH_DENY_SUGGESTIONS
+        .iter()
+        .copied()
+        .filter(|suggestion| !existing_patterns.contains(suggestion))
+        .collect();
+    let has_suggestions = !deny_suggestions.is_empty();
+
+    let form = view! {
+        <h2>"New Deny Rule"</h2>
+        <form method="POST" action={form_action.clone()}>
+            <table>
+                <tr>
+                    <td><label>"Pattern"</label></td>
+                    <td><input type="text" name="pattern" required size="60" placeholder="169.254.0.0/16"/></td>
+                </tr>
+                <tr>
+                    <td></td>
+                    <td><input type="submit" value="Add Rule"/></td>
+                </tr>
+            </table>
+        </form>
+        {if has_suggestions {
+            Either::Left(view! {
+                <h2>"Suggested Deny Rules"</h2>
+                <table>
+                    {deny_suggestions.into_iter().map(|suggestion| {
+                        let pattern = suggestion.to_string();
+                        view! {
+                            <tr>
+                                <td><code>{pattern.clone()}</code></td>
+                                <td>
+                                    <form method="POST" action={form_action.clone()}>
+                                        <input type="hidden" name="pattern" value={pattern}/>
+                                        <button type="submit">"Add"</button>
+                                    </form>
+                                </td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        } else {
+            Either::Right(())
+        }}
+    };
+
+    Page {
+        title: format!("Gateway Proxy - Session {} - New Deny Rule", session.name),
+        breadcrumbs: build_webfetch_breadcrumbs(
+            session,
+            vec![
+                Breadcrumb::link("Deny Rules", build_denylist_url(session)),
+                Breadcrumb::current("New"),
+            ],
+        ),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content: form,
+        subpages: vec![],
+    }
+    .render()
+}
@@ -0,0 +1,193 @@
+//! Benchmarks for the functions every proxied request goes through, on
+//! payloads the size of a long agent session (a few hundred KB).
+//! Run with `cargo bench -p proxy`.
+
+use common::truncate::{
+    truncate_strings, truncate_strings_with_profile, FieldLimit, TruncationProfile,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use proxy::filter::apply_filters;
+use proxy::shared::parse_body_fields;
+use proxy::sse::{parse_sse_events, serialize_sse_event};
+use proxy::webfetch::extract_webfetch_from_sse;
+use serde_json::{json, Value};
+
+const TOOL_PAIR_COUNT: usize = 200;
+const TEXT_DELTA_COUNT: usize = 4000;
+
+/// Filler text of about `len` chars, so truncation has work to do.
+fn build_filler_text(seed: usize, len: usize) -> String {
+    let sentence = format!(
+        "Line {} of the fetched page with some ordinary prose. ",
+        seed
+    );
+    sentence.repeat(len / sentence.len() + 1)[..len].to_string()
+}
+
+/// A Messages API body with a long tool-use history, many tools and a
+/// multi-block system prompt.
+fn build_large_request_body() -> Value {
+    let system: Vec<Value> = (0..20)
+        .map(|index| json!({"type": "text", "text": build_filler_text(index, 2000)}))
+        .collect();
+    let tools: Vec<Value> = (0..40)
+        .map(|index| {
+            json!({
+                "name": format!("Tool{}", index),
+                "description": build_filler_text(index, 1500),
+                "input_schema": {"type": "object", "properties": {"url": {"type": "string"}}}
+            })
+        })
+        .collect();
+    let mut messages = vec![json!({"role": "user", "content": "Research the linked pages."})];
+    for index in 0..TOOL_PAIR_COUNT {
+        let tool_use_id = format!("toolu_{:04}", index);
+        messages.push(json!({"role": "assistant", "content": [
+            {"type": "text", "text": build_filler_text(index, 200)},
+            {"type": "tool_use", "id": tool_use_id, "name": "WebFetch",
+             "input": {"url": format!("https://example.com/{}", index), "prompt": "Summarize"}}
+        ]}));
+        messages.push(json!({"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": tool_use_id, "content": build_filler_text(index, 1500)}
+        ]}));
+    }
+    json!({
+        "model": "claude-sonnet-4-5",
+        "max_tokens": 8192,
+        "stream": true,
+        "system": system,
+        "tools": tools,
+        "messages": messages,
+    })
+}
+
+/// A streamed response with a long text block followed by a WebFetch call.
+fn build_large_sse_body() -> String {
+    let mut events = vec![
+        (
+            "message_start",
+            json!({"type": "message_start", "message": {
+                "id": "msg_bench", "type": "message", "role": "assistant", "content": [],
+                "model": "claude-sonnet-4-5", "usage": {"input_tokens": 90000, "output_tokens": 1}
+            }}),
+        ),
+        (
+            "content_block_start",
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        ),
+    ];
+    for index in 0..TEXT_DELTA_COUNT {
+        events.push((
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": 0,
+                   "delta": {"type": "text_delta", "text": build_filler_text(index, 60)}}),
+        ));
+    }
+    let tool_input = json!({"url": "https://example.com/next", "prompt": "Summarize"}).to_string();
+    events.extend([
+        (
+            "content_block_stop",
+            json!({"type": "content_block_stop", "index": 0}),
+        ),
+        (
+            "content_block_start",
+            json!({"type": "content_block_start", "index": 1,
+                   "content_block": {"type": "tool_use", "id": "toolu_next", "name": "WebFetch", "input": {}}}),
+        ),
+        (
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": 1,
+                   "delta": {"type": "input_json_delta", "partial_json": tool_input}}),
+        ),
+        (
+            "content_block_stop",
+            json!({"type": "content_block_stop", "index": 1}),
+        ),
+        (
+            "message_delta",
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 4000}}),
+        ),
+        ("message_stop", json!({"type": "message_stop"})),
+    ]);
+    events
+        .iter()
+        .map(|(event_type, data)| serialize_sse_event(event_type, &data.to_string()))
+        .collect()
+}
+
+fn bench_sse(c: &mut Criterion) {
+    let sse_body = build_large_sse_body();
+    let events = parse_sse_events(&sse_body);
+    let webfetch_names = vec!["WebFetch".to_string()];
+    let mut group = c.benchmark_group("sse");
+    group.throughput(Throughput::Bytes(sse_body.len() as u64));
+    group.bench_function("parse_sse_events", |b| {
+        b.iter(|| parse_sse_events(&sse_body))
+    });
+    group.bench_function("extract_webfetch_from_sse", |b| {
+        b.iter(|| extract_webfetch_from_sse(&events, &webfetch_names))
+    });
+    group.finish();
+}
+
+fn bench_parse_body(c: &mut Criterion) {
+    let body = build_large_request_body().to_string().into_bytes();
+    let mut group = c.benchmark_group("body");
+    group.throughput(Throughput::Bytes(body.len() as u64));
+    group.bench_function("parse_body_fields", |b| {
+        b.iter(|| parse_body_fields(&body, "v1/messages", None))
+    });
+    group.finish();
+}
+
+fn bench_filters(c: &mut Criterion) {
+    let body = build_large_request_body();
+    let system_filters = vec!["Line 3 of".to_string(), "Line 7 of".to_string()];
+    let tool_filters = vec!["Tool5".to_string(), "Tool12".to_string()];
+    let mut group = c.benchmark_group("filters");
+    group.throughput(Throughput::Bytes(body.to_string().len() as u64));
+    group.bench_function("apply_filters", |b| {
+        b.iter_batched(
+            || body.clone(),
+            |mut body| apply_filters(&mut body, &system_filters, &tool_filters, 10),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn bench_truncate(c: &mut Criterion) {
+    let body = build_large_request_body();
+    let profile = TruncationProfile {
+        name: "bench".to_string(),
+        max_chars: Some(100),
+        field_limits: vec![
+            FieldLimit {
+                path: "messages[].content".to_string(),
+                max_chars: 500,
+            },
+            FieldLimit {
+                path: "tools[].description".to_string(),
+                max_chars: 0,
+            },
+        ],
+    };
+    let mut group = c.benchmark_group("truncate");
+    group.throughput(Throughput::Bytes(body.to_string().len() as u64));
+    group.bench_function("truncate_strings", |b| {
+        b.iter(|| truncate_strings(&body, 100))
+    });
+    group.bench_function("truncate_strings_with_profile", |b| {
+        b.iter(|| truncate_strings_with_profile(&body, &profile))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_sse,
+    bench_parse_body,
+    bench_filters,
+    bench_truncate
+);
+criterion_main!(benches);
@@ -0,0 +1,177 @@
+//! Alert rules evaluated against the logged requests by a background job.
+//! Each firing is stored as an alert event and posted to the notification
+//! webhooks.
+
+use chrono::Utc;
+use common::alerts::{
+    ALERT_CONDITION_ERROR_RATE, ALERT_CONDITION_PROMPT_TOKENS, ALERT_CONDITION_STOP_REASON,
+};
+use common::models::AlertRule;
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+use crate::notify::send_webhook_notifications;
+
+/// Requests are checked once they have been finished this long, so the
+/// writes that follow a response (e.g. its stop reason) have landed.
+const ALERT_SETTLE_MS: i64 = 5_000;
+
+/// Most events one rule fires per run; further matches are skipped.
+const ALERT_MAX_EVENTS_PER_RUN: i64 = 20;
+
+/// A rule firing, before it is stored and sent.
+struct AlertFiring {
+    session_id: Option<String>,
+    request_id: Option<String>,
+    message: String,
+}
+
+/// Fire when more than the rule's percentage of the requests started in its
+/// window failed, unless it already fired within the window.
+async fn check_error_rate(
+    pool: &SqlitePool,
+    rule: &AlertRule,
+    now_ms: i64,
+) -> anyhow::Result<Vec<AlertFiring>> {
+    let max_percent: f64 = rule.condition_value.parse()?;
+    let since_ms = now_ms - rule.window_minutes * 60_000;
+    let (finished_count, failed_count) = db::count_failed_requests_since(pool, since_ms).await?;
+    if finished_count == 0 {
+        return Ok(Vec::new());
+    }
+    let percent = failed_count as f64 * 100.0 / finished_count as f64;
+    if percent <= max_percent
+        || db::has_recent_alert_event(pool, &rule.id.to_string(), rule.window_minutes).await?
+    {
+        return Ok(Vec::new());
+    }
+    Ok(vec![AlertFiring {
+        session_id: None,
+        request_id: None,
+        message: format!(
+            "{} of {} requests ({:.1}%) failed in the last {} min",
+            failed_count, finished_count, percent, rule.window_minutes
+        ),
+    }])
+}
+
+/// Fire once for each request finished since the last run with more prompt
+/// tokens than the rule allows.
+async fn check_prompt_tokens(
+    pool: &SqlitePool,
+    rule: &AlertRule,
+    until_ms: i64,
+) -> anyhow::Result<Vec<AlertFiring>> {
+    let token_limit: i64 = rule.condition_value.parse()?;
+    let requests = db::list_requests_over_prompt_tokens(
+        pool,
+        rule.checked_until_ms,
+        until_ms,
+        token_limit,
+        ALERT_MAX_EVENTS_PER_RUN,
+    )
+    .await?;
+    Ok(requests
+        .into_iter()
+        .map(|(request_id, session_id, prompt_tokens)| AlertFiring {
+            session_id: Some(session_id),
+            request_id: Some(request_id),
+            message: format!("{} prompt tokens (limit {})", prompt_tokens, token_limit),
+        })
+        .collect())
+}
+
+/// Fire once for each request finished since the last run whose response
+/// stopped with the rule's stop reason.
+async fn check_stop_reason(
+    pool: &SqlitePool,
+    rule: &AlertRule,
+    until_ms: i64,
+) -> anyhow::Result<Vec<AlertFiring>> {
+    let requests = db::list_requests_with_stop_reason(
+        pool,
+        rule.checked_until_ms,
+        until_ms,
+        &rule.condition_value,
+        ALERT_MAX_EVENTS_PER_RUN,
+    )
+    .await?;
+    Ok(requests
+        .into_iter()
+        .map(|(request_id, session_id)| AlertFiring {
+            session_id: Some(session_id),
+            request_id: Some(request_id),
+            message: format!("stop_reason {}", rule.condition_value),
+        })
+        .collect())
+}
+
+fn build_alert_payload(rule: &AlertRule, alert_firing: &AlertFiring) -> Value {
+    serde_json::json!({
+        "event": "alert",
+        "rule_id": rule.id,
+        "rule_name": rule.name,
+        "condition": rule.condition,
+        "session_id": alert_firing.session_id,
+        "request_id": alert_firing.request_id,
+        "message": alert_firing.message,
+    })
+}
+
+/// Check one rule, store and send its firings, and move its cursor past
+/// the requests it has seen.
+async fn evaluate_alert_rule(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    webhook_urls: &[String],
+    rule: &AlertRule,
+    now_ms: i64,
+) -> anyhow::Result<()> {
+    let until_ms = now_ms - ALERT_SETTLE_MS;
+    let alert_firings = match rule.condition.as_str() {
+        ALERT_CONDITION_ERROR_RATE => check_error_rate(pool, rule, now_ms).await?,
+        ALERT_CONDITION_PROMPT_TOKENS => check_prompt_tokens(pool, rule, until_ms).await?,
+        ALERT_CONDITION_STOP_REASON => check_stop_reason(pool, rule, until_ms).await?,
+        condition => anyhow::bail!("unknown condition {:?}", condition),
+    };
+    let rule_id = rule.id.to_string();
+    for alert_firing in &alert_firings {
+        db::create_alert_event(
+            pool,
+            &rule_id,
+            alert_firing.session_id.as_deref(),
+            alert_firing.request_id.as_deref(),
+            &alert_firing.message,
+        )
+        .await?;
+        log::info!("alerts: {} fired: {}", rule.name, alert_firing.message);
+        send_webhook_notifications(
+            client,
+            webhook_urls,
+            &build_alert_payload(rule, alert_firing),
+        );
+    }
+    db::set_alert_rule_checked_until(pool, &rule_id, until_ms.max(rule.checked_until_ms)).await
+}
+
+/// Check every enabled alert rule against the requests logged since its
+/// last run.
+pub async fn evaluate_alert_rules(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    webhook_urls: &[String],
+) {
+    let rules = match db::list_alert_rules(pool).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::warn!("alerts: failed to list alert rules: {}", e);
+            return;
+        }
+    };
+    let now_ms = Utc::now().timestamp_millis();
+    for rule in rules.iter().filter(|rule| rule.enabled) {
+        if let Err(e) = evaluate_alert_rule(pool, client, webhook_urls, rule, now_ms).await {
+            log::warn!("alerts: failed to check rule {}: {}", rule.name, e);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use common::models::{MessageBatch, MessageBatchItem, Session};
+use common::url::parse_query_params;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use crate::shared::{
+    build_replay_headers, build_target_url, effective_client, extract_request_fields, log_request,
+    store_response, RequestMeta,
+};
+
+/// Whether the request path targets the Message Batches create endpoint.
+pub fn is_batch_create_path(path: &str) -> bool {
+    path.trim_end_matches('/').ends_with("messages/batches")
+}
+
+/// The batch-creating request, as seen by the proxy handler.
+pub struct BatchCreation<'a> {
+    pub pool: &'a SqlitePool,
+    pub session_id: &'a str,
+    pub request_id: &'a str,
+    /// Upstream path of the create endpoint, used to build poll URLs.
+    pub base_path: &'a str,
+    pub stored_path: &'a str,
+    pub headers_json: Option<&'a str>,
+    pub request_body: &'a [u8],
+    pub response_body: &'a str,
+}
+
+/// Extract `(custom_id, params)` pairs from a create-batch request body.
+fn extract_batch_entries(data: &Value) -> Vec<(&str, &Value)> {
+    data.get("requests")
+        .and_then(|field| field.as_array())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let custom_id = entry.get("custom_id")?.as_str()?;
+                    Some((custom_id, entry.get("params")?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Record a newly created batch and log each of its requests as a child
+/// request row, so results can be attached to them once the batch ends.
+pub async fn store_message_batch(creation: &BatchCreation<'_>) -> anyhow::Result<()> {
+    let response: Value = serde_json::from_str(creation.response_body)?;
+    let upstream_batch_id = response
+        .get("id")
+        .and_then(|field| field.as_str())
+        .ok_or_else(|| anyhow::anyhow!("batch response has no id"))?;
+    let processing_status = response
+        .get("processing_status")
+        .and_then(|field| field.as_str())
+        .unwrap_or("in_progress");
+    // The batch and its items reference request rows that may still be queued.
+    db::flush_request_writes().await;
+    let batch_id = db::create_message_batch(
+        creation.pool,
+        &db::CreateMessageBatchParams {
+            session_id: creation.session_id,
+            request_id: creation.request_id,
+            upstream_batch_id,
+            base_path: creation.base_path,
+            processing_status,
+        },
+    )
+    .await?;
+
+    let data: Value = serde_json::from_slice(creation.request_body)?;
+    for (custom_id, params) in extract_batch_entries(&data) {
+        store_message_batch_item(creation, &batch_id, custom_id, params).await?;
+    }
+    Ok(())
+}
+
+async fn store_message_batch_item(
+    creation: &BatchCreation<'_>,
+    batch_id: &str,
+    custom_id: &str,
+    params: &Value,
+) -> anyhow::Result<()> {
+    let fields = extract_request_fields(params, None)?;
+    let note = format!("batch item {}", custom_id);
+    let request_id = log_request(
+        &RequestMeta {
+            id: None,
+            pool: creation.pool,
+            session_id: creation.session_id,
+            method: "POST",
+            path: creation.stored_path,
+            headers_json: creation.headers_json,
+            note: Some(&note),
+            client: None,
+            parent_request_id: None,
+        },
+        fields,
+    )
+    .await?;
+    db::flush_request_writes().await;
+    db::create_message_batch_item(creation.pool, batch_id, &request_id, custom_id).await?;
+    Ok(())
+}
+
+/// Poll every unfinished batch once, attaching results to the item requests
+/// of batches that have ended.
+pub async fn poll_message_batches(pool: &SqlitePool, client: &reqwest::Client) {
+    let batches = match db::list_pending_message_batches(pool).await {
+        Ok(batches) => batches,
+        Err(e) => {
+            log::warn!("batches: failed to list pending batches: {}", e);
+            return;
+        }
+    };
+    for batch in &batches {
+        if let Err(e) = poll_message_batch(pool, client, batch).await {
+            log::warn!("batches: failed to poll {}: {}", batch.upstream_batch_id, e);
+        }
+    }
+}
+
+async fn poll_message_batch(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    batch: &MessageBatch,
+) -> anyhow::Result<()> {
+    let session = db::get_session(pool, &batch.session_id.to_string())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("session {} not found", batch.session_id))?;
+    let poll_headers = build_poll_headers(pool, batch, &session).await?;
+    let client = effective_client(&session, client);
+    let batch_path = format!(
+        "{}/{}",
+        batch.base_path.trim_end_matches('/'),
+        batch.upstream_batch_id
+    );
+
+    let auth_query_params = parse_query_params(session.auth_query_params.as_deref());
+    let status_url = build_target_url(&session.target_url, &batch_path, None, &auth_query_params);
+    let status_body = fetch_upstream_text(client, &status_url, &poll_headers).await?;
+    let status: Value = serde_json::from_str(&status_body)?;
+    let processing_status = status
+        .get("processing_status")
+        .and_then(|field| field.as_str())
+        .ok_or_else(|| anyhow::anyhow!("batch status has no processing_status"))?;
+
+    if processing_status == "ended" {
+        let results_path = format!("{}/results", batch_path);
+        let results_url =
+            build_target_url(&session.target_url, &results_path, None, &auth_query_params);
+        let results_body = fetch_upstream_text(client, &results_url, &poll_headers).await?;
+        attach_batch_results(pool, batch, &results_body).await?;
+    }
+    if processing_status != batch.processing_status {
+        db::set_message_batch_processing_status(pool, &batch.id.to_string(), processing_status)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Rebuild upstream headers from the batch-creating request, with the
+/// session's auth overrides applied on top.
+async fn build_poll_headers(
+    pool: &SqlitePool,
+    batch: &MessageBatch,
+    session: &Session,
+) -> anyhow::Result<reqwest::header::HeaderMap> {
+    let create_request = db::get_request(pool, &batch.request_id.to_string())
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("request {} not found", batch.request_id))?;
+    build_replay_headers(create_request.headers_json.as_deref(), session)
+}
+
+async fn fetch_upstream_text(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &reqwest::header::HeaderMap,
+) -> anyhow::Result<String> {
+    Ok(client
+        .get(url)
+        .headers(headers.clone())
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?)
+}
+
+/// Attach each line of a batch results JSONL body to the matching item request.
+async fn attach_batch_results(
+    pool: &SqlitePool,
+    batch: &MessageBatch,
+    results_body: &str,
+) -> anyhow::Result<()> {
+    let items = db::list_message_batch_items(pool, &batch.id.to_string()).await?;
+    let items_by_custom_id: HashMap<&str, &MessageBatchItem> = items
+        .iter()
+        .map(|item| (item.custom_id.as_str(), item))
+        .collect();
+    for line in results_body.lines().filter(|line| !line.trim().is_empty()) {
+        let result_line: Value = serde_json::from_str(line)?;
+        let Some(item) = result_line
+            .get("custom_id")
+            .and_then(|field| field.as_str())
+            .and_then(|custom_id| items_by_custom_id.get(custom_id))
+        else {
+            continue;
+        };
+        if let Some(result) = result_line.get("result") {
+            attach_batch_result(pool, item, result).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn attach_batch_result(
+    pool: &SqlitePool,
+    item: &MessageBatchItem,
+    result: &Value,
+) -> anyhow::Result<()> {
+    let result_type = result
+        .get("type")
+        .and_then(|field| field.as_str())
+        .unwrap_or("unknown");
+    let (status, response_body) = build_batch_result_response(result)?;
+    store_response(pool, &item.request_id.to_string(), status, None, &response_body).await?;
+    db::set_message_batch_item_result_type(pool, &item.id.to_string(), result_type).await
+}
+
+/// Build the `(status, body)` stored for an item request: the message for
+/// succeeded results, otherwise the result object itself. Errored results
+/// map to 400 for invalid requests and 500 otherwise; canceled and expired
+/// results also use 500 since they never produced a message.
+fn build_batch_result_response(result: &Value) -> anyhow::Result<(u16, String)> {
+    if result.get("type").and_then(|field| field.as_str()) == Some("succeeded") {
+        let message = result.get("message").unwrap_or(&Value::Null);
+        return Ok((200, serde_json::to_string_pretty(message)?));
+    }
+    let error_type = result
+        .pointer("/error/error/type")
+        .and_then(|field| field.as_str());
+    let status = if error_type == Some("invalid_request_error") {
+        400
+    } else {
+        500
+    };
+    Ok((status, serde_json::to_string_pretty(result)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_create_path_detected() {
+        assert!(is_batch_create_path("v1/messages/batches"));
+        assert!(!is_batch_create_path("v1/messages/batches/msgbatch_1"));
+        assert!(!is_batch_create_path("v1/messages"));
+    }
+
+    #[test]
+    fn batch_entries_extracted_in_order() {
+        let data = serde_json::json!({
+            "requests": [
+                {"custom_id": "a", "params": {"model": "m", "messages": []}},
+                {"custom_id": "b", "params": {"model": "n", "messages": []}},
+                {"params": {"model": "missing-id"}}
+            ]
+        });
+        let entries = extract_batch_entries(&data);
+        let custom_ids: Vec<&str> = entries.iter().map(|(custom_id, _)| *custom_id).collect();
+        assert_eq!(custom_ids, vec!["a", "b"]);
+        assert_eq!(entries[1].1["model"], "n");
+    }
+
+    #[test]
+    fn succeeded_result_stores_message() {
+        let result = serde_json::json!({
+            "type": "succeeded",
+            "message": {"id": "msg_1", "content": []}
+        });
+        let (status, body) = build_batch_result_response(&result).unwrap();
+        assert_eq!(status, 200);
+        let message: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(message["id"], "msg_1");
+    }
+
+    #[test]
+    fn errored_result_maps_error_type_to_status() {
+        let result = serde_json::json!({
+            "type": "errored",
+            "error": {"type": "error", "error": {"type": "invalid_request_error"}}
+        });
+        assert_eq!(build_batch_result_response(&result).unwrap().0, 400);
+        let result = serde_json::json!({"type": "expired"});
+        assert_eq!(build_batch_result_response(&result).unwrap().0, 500);
+    }
+}
@@ -1,18 +1,37 @@
+use actix_web::rt::task::JoinHandle;
 use actix_web::{
-    error::{ErrorBadGateway, ErrorBadRequest, ErrorInternalServerError},
+    error::{ErrorBadRequest, ErrorInternalServerError},
     web, HttpRequest, HttpResponse,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use bytes::Bytes;
+use chrono::Utc;
+use common::config::SharedConfig;
+use common::models::{
+    Session, REQUEST_ERROR_STATUS, REQUEST_ERROR_STREAM, REQUEST_ERROR_WEBFETCH_FOLLOWUP,
+};
+use common::url::encode_uri_component;
 use futures::StreamExt;
 use sqlx::SqlitePool;
 
 use crate::{
+    client::ClientInfo,
+    filter, record_injected_error,
     shared::{
-        actix_headers_iter, effective_client, extract_request_fields, get_session_or_error,
-        headers_to_json, load_filters_for_profile, log_request, to_actix_status, RequestMeta,
+        actix_headers_iter, classify_upstream_error, effective_client, extract_request_fields,
+        get_session_or_error, headers_to_json, log_request, reject_hooked_request,
+        reject_upstream_failure, store_error_kind, store_forwarded_bytes, store_response,
+        to_actix_status, ParsedRequestBody, RequestMeta,
+    },
+    correlation::{get_proxy_request_id, set_proxy_session_header},
+    hooks::{
+        notify_request_completed, notify_request_streamed, BufferedResponse, HookContext,
+        HookRegistry, OutgoingRequest,
     },
-    sse::{parse_sse_events, SseParser},
+    intercept_rules::{check_intercept_rules, InterceptSubject},
+    sigv4::{sign_request, AwsCredentials, SigningParams},
+    sse::{serialize_sse_event, SseParser},
+    store_intercepted_response, webfetch,
 };
 
 // --- AWS Event Stream binary protocol encoding ---
@@ -55,6 +74,122 @@ fn encode_event_stream_message(headers: &[(&str, &str)], payload: &[u8]) -> Vec<
     message
 }
 
+/// Read a big-endian integer of `N` bytes at `offset`.
+fn read_be_bytes<const N: usize>(bytes: &[u8], offset: usize) -> Option<[u8; N]> {
+    bytes.get(offset..offset + N)?.try_into().ok()
+}
+
+/// Decode the string headers of an Event Stream message. Headers of other
+/// types are skipped.
+fn decode_event_stream_headers(mut headers_buf: &[u8]) -> Vec<(String, String)> {
+    let mut headers = Vec::new();
+    while let Some((&name_length, rest)) = headers_buf.split_first() {
+        let name_length = name_length as usize;
+        let Some(name) = rest.get(..name_length) else {
+            break;
+        };
+        let Some(&value_type) = rest.get(name_length) else {
+            break;
+        };
+        let value_start = name_length + 1;
+        let value_length = match value_type {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => match read_be_bytes::<2>(rest, value_start) {
+                Some(length) => 2 + u16::from_be_bytes(length) as usize,
+                None => break,
+            },
+            _ => break,
+        };
+        let Some(value) = rest.get(value_start..value_start + value_length) else {
+            break;
+        };
+        if value_type == 7 {
+            headers.push((
+                String::from_utf8_lossy(name).to_string(),
+                String::from_utf8_lossy(&value[2..]).to_string(),
+            ));
+        }
+        headers_buf = &rest[value_start + value_length..];
+    }
+    headers
+}
+
+/// A decoded AWS Event Stream message.
+struct EventStreamMessage {
+    headers: Vec<(String, String)>,
+    payload: Vec<u8>,
+}
+
+impl EventStreamMessage {
+    fn find_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Split a buffer of AWS Event Stream binary messages. Decoding stops at the
+/// first incomplete message.
+fn decode_event_stream_messages(mut bytes: &[u8]) -> Vec<EventStreamMessage> {
+    let mut messages = Vec::new();
+    while let (Some(total_length), Some(headers_length)) =
+        (read_be_bytes::<4>(bytes, 0), read_be_bytes::<4>(bytes, 4))
+    {
+        let total_length = u32::from_be_bytes(total_length) as usize;
+        let headers_length = u32::from_be_bytes(headers_length) as usize;
+        if total_length < 16 + headers_length || bytes.len() < total_length {
+            break;
+        }
+        let headers = decode_event_stream_headers(&bytes[12..12 + headers_length]);
+        let payload = bytes[12 + headers_length..total_length - 4].to_vec();
+        messages.push(EventStreamMessage { headers, payload });
+        bytes = &bytes[total_length..];
+    }
+    messages
+}
+
+/// Turn a Bedrock Event Stream response back into Anthropic SSE text, so
+/// responses relayed from AWS are stored like translated ones.
+fn convert_event_stream_to_sse(bytes: &[u8]) -> String {
+    let mut sse = String::new();
+    for message in decode_event_stream_messages(bytes) {
+        if message.find_header(":message-type") != Some("event") {
+            let error_type = message.find_header(":exception-type").unwrap_or("error");
+            let error_json = serde_json::json!({
+                "type": "error",
+                "error": {
+                    "type": error_type,
+                    "message": String::from_utf8_lossy(&message.payload),
+                },
+            });
+            sse.push_str(&format!("event: error\ndata: {}\n\n", error_json));
+            continue;
+        }
+        let data_json = serde_json::from_slice::<serde_json::Value>(&message.payload)
+            .ok()
+            .and_then(|chunk| {
+                let encoded = chunk.get("bytes")?.as_str()?.to_string();
+                BASE64.decode(encoded).ok()
+            })
+            .map(|decoded| String::from_utf8_lossy(&decoded).to_string());
+        let Some(data_json) = data_json else {
+            continue;
+        };
+        let event_type = serde_json::from_str::<serde_json::Value>(&data_json)
+            .ok()
+            .and_then(|data| data.get("type")?.as_str().map(str::to_string))
+            .unwrap_or_default();
+        sse.push_str(&format!("event: {}\ndata: {}\n\n", event_type, data_json));
+    }
+    sse
+}
+
 /// Convert an Anthropic SSE event data JSON string into a Bedrock Event Stream chunk frame.
 fn encode_bedrock_chunk(data_json: &str) -> Vec<u8> {
     let b64 = BASE64.encode(data_json.as_bytes());
@@ -158,6 +293,55 @@ fn translate_bedrock_request(
     Ok((body, headers))
 }
 
+/// Build a request for AWS itself: the Bedrock body is forwarded untranslated to
+/// `{target_url}/model/{model_id}/invoke-with-response-stream` and SigV4-signed.
+/// Returns `(url, headers, body)`.
+fn build_signed_bedrock_request(
+    session: &Session,
+    aws_region: &str,
+    model_id: &str,
+    data: &serde_json::Value,
+) -> Result<(String, reqwest::header::HeaderMap, Vec<u8>), actix_web::Error> {
+    let credentials = AwsCredentials::resolve(session).ok_or_else(|| {
+        ErrorInternalServerError(
+            "No AWS credentials for SigV4 signing: set them on the session or in \
+             AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY",
+        )
+    })?;
+    let body = serde_json::to_vec(data)
+        .map_err(|e| ErrorInternalServerError(format!("Failed to serialize body: {}", e)))?;
+    let url = url::Url::parse(&format!(
+        "{}/model/{}/invoke-with-response-stream",
+        session.target_url.trim_end_matches('/'),
+        encode_uri_component(model_id)
+    ))
+    .map_err(|e| ErrorBadRequest(format!("Invalid target URL: {}", e)))?;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::CONTENT_TYPE,
+        reqwest::header::HeaderValue::from_static("application/json"),
+    );
+    headers.insert(
+        reqwest::header::ACCEPT,
+        reqwest::header::HeaderValue::from_static("application/vnd.amazon.eventstream"),
+    );
+    sign_request(
+        "POST",
+        &url,
+        &mut headers,
+        &body,
+        &SigningParams {
+            credentials: &credentials,
+            region: aws_region,
+            service: "bedrock",
+            time: Utc::now(),
+        },
+    )
+    .map_err(|e| ErrorInternalServerError(format!("Failed to sign request: {}", e)))?;
+    Ok((url.to_string(), headers, body))
+}
+
 /// Return an injected error response if error injection is active for this session.
 fn build_bedrock_error_response(error_type: &str) -> Option<HttpResponse> {
     if error_type.is_empty() {
@@ -190,19 +374,25 @@ async fn log_bedrock_request(
     let stored_path = format!("/model/{}/invoke-with-response-stream", model_id);
     let req_headers_json =
         headers_to_json(actix_headers_iter(req)).map_err(ErrorInternalServerError)?;
-    let fields = extract_request_fields(&original_data, Some(model_id.to_string()))
-        .map_err(ErrorInternalServerError)?;
+    let fields = ParsedRequestBody {
+        request_bytes: Some(body.len() as i64),
+        ..extract_request_fields(&original_data, Some(model_id.to_string()))
+            .map_err(ErrorInternalServerError)?
+    };
 
     let request_id = log_request(
         &RequestMeta {
+            id: get_proxy_request_id(req).as_deref(),
             pool,
             session_id,
             method: "POST",
             path: &stored_path,
             headers_json: Some(&req_headers_json),
             note: None,
+            client: Some(&ClientInfo::from_request(req)),
+            parent_request_id: None,
         },
-        &fields,
+        fields,
     )
     .await
     .map_err(ErrorInternalServerError)?;
@@ -210,24 +400,8 @@ async fn log_bedrock_request(
     Ok((request_id, original_data))
 }
 
-/// Apply filters to request data before forwarding.
-async fn apply_bedrock_filters(
-    pool: &SqlitePool,
-    profile_id: Option<&str>,
-    mut data: serde_json::Value,
-) -> serde_json::Value {
-    if let Some(filters) = load_filters_for_profile(pool, profile_id).await {
-        crate::filter::apply_filters(
-            &mut data,
-            &filters.system_filters,
-            &filters.tool_filters,
-            filters.keep_tool_pairs,
-        );
-    }
-    data
-}
-
-/// Store a non-200 error response and return it as an HttpResponse.
+/// Store a non-200 error response as a failed upstream call and return it
+/// as an HttpResponse.
 async fn store_bedrock_error_response(
     pool: &SqlitePool,
     request_id: &str,
@@ -236,56 +410,87 @@ async fn store_bedrock_error_response(
     upstream: reqwest::Response,
 ) -> Result<HttpResponse, actix_web::Error> {
     let actix_status = to_actix_status(status)?;
-    let error_body = upstream
-        .bytes()
-        .await
-        .map_err(|e| ErrorBadGateway(format!("Failed to read error body: {}", e)))?;
+    let error_body = match upstream.bytes().await {
+        Ok(error_body) => error_body,
+        Err(e) => {
+            let message = format!("Failed to read error body: {}", e);
+            return Ok(reject_upstream_failure(
+                pool,
+                request_id,
+                classify_upstream_error(&e),
+                &message,
+            )
+            .await);
+        }
+    };
 
     let body_str = String::from_utf8_lossy(&error_body);
-    let sse_events = parse_sse_events(&body_str);
-    let sse_events_json = serde_json::to_string(&sse_events).unwrap_or_else(|_| "[]".to_string());
-
-    if let Err(e) = db::set_request_response(
-        pool,
-        request_id,
-        status as i64,
-        Some(resp_headers_json),
-        Some(&body_str),
-        Some(&sse_events_json),
-    )
-    .await
+    if let Err(e) =
+        store_response(pool, request_id, status, Some(resp_headers_json), &body_str).await
     {
         log::warn!("bedrock: failed to store error response: {}", e);
     }
+    store_error_kind(pool, request_id, REQUEST_ERROR_STATUS).await;
 
     Ok(HttpResponse::build(actix_status)
         .insert_header((actix_web::http::header::CONTENT_TYPE, "application/json"))
         .body(error_body))
 }
 
+/// Restore overridden tool names in one SSE event, append it to the stored
+/// SSE text and encode it as a Bedrock chunk frame.
+fn encode_bedrock_event(
+    event_type: &str,
+    data: &str,
+    overrides: &[(String, String)],
+    accumulated: &mut Vec<u8>,
+) -> Vec<u8> {
+    let patched = filter::reverse_tool_name_in_sse_event(event_type, data, overrides);
+    accumulated.extend_from_slice(serialize_sse_event(event_type, &patched).as_bytes());
+    encode_bedrock_chunk(&patched)
+}
+
+/// Encode a whole buffered SSE response as Bedrock chunk frames. Also returns
+/// the SSE text with tool names restored, for storage.
+fn encode_bedrock_body(body_str: &str, overrides: &[(String, String)]) -> (Vec<u8>, String) {
+    let mut parser = SseParser::new();
+    let mut events = parser.feed(body_str);
+    events.extend(parser.flush());
+    let mut accumulated = Vec::new();
+    let frames = events
+        .iter()
+        .flat_map(|(event_type, data)| {
+            encode_bedrock_event(event_type, data, overrides, &mut accumulated)
+        })
+        .collect();
+    (frames, String::from_utf8_lossy(&accumulated).into_owned())
+}
+
 /// Spawn a background task that reads the upstream SSE stream, converts to
-/// Bedrock Event Stream format, and stores the accumulated response in the DB.
+/// Bedrock Event Stream format, and stores the accumulated response in the DB,
+/// including a partial one when the stream breaks off. Returns the task.
 fn spawn_bedrock_stream_converter(
     byte_stream: impl futures::Stream<Item = Result<Bytes, reqwest::Error>> + 'static,
     tx: futures::channel::mpsc::UnboundedSender<Result<Bytes, actix_web::Error>>,
+    overrides: Vec<(String, String)>,
     pool: web::Data<SqlitePool>,
     request_id: String,
     resp_headers_json: String,
     status: u16,
-) {
+) -> JoinHandle<()> {
     actix_web::rt::spawn(async move {
         let mut accumulated = Vec::new();
         let mut parser = SseParser::new();
         let mut byte_stream = std::pin::pin!(byte_stream);
+        let mut stream_failed = false;
 
         while let Some(chunk_result) = byte_stream.next().await {
             match chunk_result {
                 Ok(chunk) => {
-                    accumulated.extend_from_slice(&chunk);
-
                     let chunk_str = String::from_utf8_lossy(&chunk);
-                    for (_event_type, data) in parser.feed(&chunk_str) {
-                        let frame = encode_bedrock_chunk(&data);
+                    for (event_type, data) in parser.feed(&chunk_str) {
+                        let frame =
+                            encode_bedrock_event(&event_type, &data, &overrides, &mut accumulated);
                         if tx.unbounded_send(Ok(Bytes::from(frame))).is_err() {
                             return; // Client disconnected
                         }
@@ -296,37 +501,82 @@ fn spawn_bedrock_stream_converter(
                         "Upstream stream error: {}",
                         e
                     ))));
-                    return;
+                    stream_failed = true;
+                    break;
                 }
             }
         }
 
-        if let Some((_event_type, data)) = parser.flush() {
-            let frame = encode_bedrock_chunk(&data);
+        if let Some((event_type, data)) = parser.flush() {
+            let frame = encode_bedrock_event(&event_type, &data, &overrides, &mut accumulated);
             let _ = tx.unbounded_send(Ok(Bytes::from(frame)));
         }
 
-        // Store accumulated response to DB
-        let store: anyhow::Result<()> = async {
-            let body_str = String::from_utf8_lossy(&accumulated);
-            let sse_events = parse_sse_events(&body_str);
-            let sse_events_json = serde_json::to_string(&sse_events)?;
-            db::set_request_response(
-                pool.get_ref(),
-                &request_id,
-                status as i64,
-                Some(&resp_headers_json),
-                Some(&body_str),
-                Some(&sse_events_json),
-            )
-            .await?;
-            Ok(())
+        let body_str = String::from_utf8_lossy(&accumulated);
+        store_bedrock_stream_response(&pool, &request_id, status, &resp_headers_json, &body_str)
+            .await;
+        if stream_failed {
+            store_error_kind(&pool, &request_id, REQUEST_ERROR_STREAM).await;
         }
-        .await;
-        if let Err(e) = store {
-            log::error!("Failed to store response: {}", e);
+    })
+}
+
+/// Spawn a background task that relays an Event Stream response from AWS to
+/// the client unchanged and stores it in the DB as SSE. Returns the task.
+fn spawn_bedrock_stream_relay(
+    byte_stream: impl futures::Stream<Item = Result<Bytes, reqwest::Error>> + 'static,
+    tx: futures::channel::mpsc::UnboundedSender<Result<Bytes, actix_web::Error>>,
+    pool: web::Data<SqlitePool>,
+    request_id: String,
+    resp_headers_json: String,
+    status: u16,
+) -> JoinHandle<()> {
+    actix_web::rt::spawn(async move {
+        let mut accumulated = Vec::new();
+        let mut byte_stream = std::pin::pin!(byte_stream);
+        let mut stream_failed = false;
+
+        while let Some(chunk_result) = byte_stream.next().await {
+            match chunk_result {
+                Ok(chunk) => {
+                    accumulated.extend_from_slice(&chunk);
+                    if tx.unbounded_send(Ok(chunk)).is_err() {
+                        return; // Client disconnected
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.unbounded_send(Err(actix_web::error::ErrorBadGateway(format!(
+                        "Upstream stream error: {}",
+                        e
+                    ))));
+                    stream_failed = true;
+                    break;
+                }
+            }
         }
-    });
+
+        let body_str = convert_event_stream_to_sse(&accumulated);
+        store_bedrock_stream_response(&pool, &request_id, status, &resp_headers_json, &body_str)
+            .await;
+        if stream_failed {
+            store_error_kind(&pool, &request_id, REQUEST_ERROR_STREAM).await;
+        }
+    })
+}
+
+/// Store an accumulated SSE response in the DB.
+async fn store_bedrock_stream_response(
+    pool: &SqlitePool,
+    request_id: &str,
+    status: u16,
+    resp_headers_json: &str,
+    body_str: &str,
+) {
+    if let Err(e) =
+        store_response(pool, request_id, status, Some(resp_headers_json), body_str).await
+    {
+        log::error!("Failed to store response: {}", e);
+    }
 }
 
 pub async fn bedrock_streaming_handler(
@@ -334,7 +584,11 @@ pub async fn bedrock_streaming_handler(
     body: web::Bytes,
     pool: web::Data<SqlitePool>,
     client: web::Data<reqwest::Client>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
 ) -> Result<HttpResponse, actix_web::Error> {
+    // Snapshot so settings edited mid-request don't change prompts between rounds.
+    let config = config.read().unwrap().clone();
     let session_id = req
         .match_info()
         .get("session_id")
@@ -349,6 +603,7 @@ pub async fn bedrock_streaming_handler(
     // Return injected error if error injection is active for this session.
     if let Some(ref error_type) = session.error_inject {
         if let Some(resp) = build_bedrock_error_response(error_type) {
+            record_injected_error(pool.get_ref(), session_id, error_type).await;
             return Ok(resp);
         }
     }
@@ -357,32 +612,120 @@ pub async fn bedrock_streaming_handler(
     let (request_id, original_data) =
         log_bedrock_request(&req, &body, pool.get_ref(), session_id, model_id).await?;
 
-    // Apply filters to the data before forwarding
-    let filtered_data =
-        apply_bedrock_filters(pool.get_ref(), session.profile_id.as_deref(), original_data).await;
+    // Let the hooks (e.g. the filter profile) change the data before forwarding
+    let intercept_rules_match = check_intercept_rules(
+        pool.get_ref(),
+        &session,
+        &InterceptSubject {
+            path: &format!("/model/{}/invoke-with-response-stream", model_id),
+            model: Some(model_id),
+            body: &body,
+        },
+    )
+    .await;
+    let hook_context = HookContext {
+        pool: pool.get_ref(),
+        config: &config,
+        session: &session,
+        request_id: &request_id,
+        intercept_rules_match,
+    };
+    let mut outgoing_request = OutgoingRequest {
+        body: original_data,
+        body_changed: false,
+        tool_name_overrides: vec![],
+        rejection: None,
+    };
+    hook_registry
+        .run_request_hooks(&hook_context, &mut outgoing_request)
+        .await;
+    let OutgoingRequest {
+        body: filtered_data,
+        tool_name_overrides,
+        rejection,
+        ..
+    } = outgoing_request;
+    if let Some(rejection) = rejection {
+        let resp = reject_hooked_request(pool.get_ref(), &request_id, &rejection).await;
+        notify_request_completed(&hook_registry, &hook_context);
+        return Ok(resp);
+    }
 
-    // Translate request and send upstream
-    let (translated_body, forward_headers) = translate_bedrock_request(
-        &req,
-        filtered_data,
-        model_id,
-        session.auth_header.as_deref(),
-        session.x_api_key.as_deref(),
-    )?;
+    // Interception replays the translated request, so it needs the Messages
+    // API path; a SigV4 request would have to be re-signed each round.
+    let intercepts = if session.aws_region.is_some() {
+        if session.webfetch_intercept {
+            log::warn!(
+                "{}: WebFetch intercept is not supported for SigV4-signed Bedrock requests",
+                session.name
+            );
+        }
+        false
+    } else {
+        hook_registry.wants_intercept(&hook_context)
+    };
+
+    // Sign for AWS, or translate to the Messages API, and send upstream
+    let (target_url, forward_headers, forward_body) = match session.aws_region.as_deref() {
+        Some(aws_region) => {
+            build_signed_bedrock_request(&session, aws_region, model_id, &filtered_data)?
+        }
+        None => {
+            let (translated_body, mut forward_headers) = translate_bedrock_request(
+                &req,
+                filtered_data,
+                model_id,
+                session.auth_header.as_deref(),
+                session.x_api_key.as_deref(),
+            )?;
+            if config.forward_proxy_session_header {
+                set_proxy_session_header(&mut forward_headers, session_id);
+            }
+            let target_url = format!("{}/v1/messages", session.target_url.trim_end_matches('/'));
+            (target_url, forward_headers, translated_body)
+        }
+    };
 
     let stored_path = format!("/model/{}/invoke-with-response-stream", model_id);
-    let target_url = format!("{}/v1/messages", session.target_url.trim_end_matches('/'));
     let effective_client = effective_client(&session, client.get_ref());
+    store_forwarded_bytes(pool.get_ref(), &request_id, forward_body.len()).await;
+
+    log::info!(
+        "{} POST {} -> {} [{}]",
+        session.name,
+        stored_path,
+        target_url,
+        request_id
+    );
 
-    log::info!("{} POST {} -> {}", session.name, stored_path, target_url);
+    // Save copies for intercept follow-ups before the upstream call consumes them
+    let intercept_context = if intercepts {
+        Some((forward_body.clone(), forward_headers.clone()))
+    } else {
+        None
+    };
 
-    let upstream = effective_client
+    let upstream = match effective_client
         .post(&target_url)
         .headers(forward_headers)
-        .body(translated_body)
+        .body(forward_body)
         .send()
         .await
-        .map_err(|e| ErrorBadGateway(format!("Upstream error: {}", e)))?;
+    {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let message = format!("Upstream error: {}", e);
+            let resp = reject_upstream_failure(
+                pool.get_ref(),
+                &request_id,
+                classify_upstream_error(&e),
+                &message,
+            )
+            .await;
+            notify_request_completed(&hook_registry, &hook_context);
+            return Ok(resp);
+        }
+    };
 
     let status = upstream.status().as_u16();
     let resp_headers_json = headers_to_json(
@@ -396,7 +739,7 @@ pub async fn bedrock_streaming_handler(
     // For non-200 responses, return the error body directly instead of
     // trying to parse it as SSE (upstream returns plain JSON for errors).
     if status != 200 {
-        return store_bedrock_error_response(
+        let resp = store_bedrock_error_response(
             pool.get_ref(),
             &request_id,
             status,
@@ -404,9 +747,11 @@ pub async fn bedrock_streaming_handler(
             upstream,
         )
         .await;
+        notify_request_completed(&hook_registry, &hook_context);
+        return resp;
     }
 
-    // Streaming SSE response — convert to Bedrock Event Stream format
+    // Streaming response — relayed from AWS, or converted from SSE to Bedrock Event Stream format
     let actix_status = to_actix_status(status)?;
     let mut builder = HttpResponse::build(actix_status);
     builder.insert_header((
@@ -414,16 +759,115 @@ pub async fn bedrock_streaming_handler(
         "application/vnd.amazon.eventstream",
     ));
 
+    // Interception needs the whole response, so buffer it and encode the
+    // final round as Bedrock frames once it is known.
+    if let Some((saved_body, saved_headers)) = intercept_context {
+        let response_body = match upstream.bytes().await {
+            Ok(response_body) => response_body,
+            Err(e) => {
+                let message = format!("Failed to read upstream response body: {}", e);
+                let resp = reject_upstream_failure(
+                    pool.get_ref(),
+                    &request_id,
+                    classify_upstream_error(&e),
+                    &message,
+                )
+                .await;
+                notify_request_completed(&hook_registry, &hook_context);
+                return Ok(resp);
+            }
+        };
+        let body_str = String::from_utf8_lossy(&response_body);
+        let intercept_result = hook_registry
+            .run_intercept_hooks(
+                &hook_context,
+                &BufferedResponse {
+                    response_body: &body_str,
+                    original_body: &saved_body,
+                    target_url: &target_url,
+                    forward_headers: &saved_headers,
+                    client: effective_client,
+                    stored_path: &stored_path,
+                },
+            )
+            .await;
+        let intercepted_response = match intercept_result {
+            Some(webfetch::InterceptResult::Intercepted(intercepted_response)) => {
+                intercepted_response
+            }
+            Some(webfetch::InterceptResult::FollowupFailed { message }) => {
+                let resp = reject_upstream_failure(
+                    pool.get_ref(),
+                    &request_id,
+                    REQUEST_ERROR_WEBFETCH_FOLLOWUP,
+                    &message,
+                )
+                .await;
+                notify_request_completed(&hook_registry, &hook_context);
+                return Ok(resp);
+            }
+            None => {
+                let (frames, patched) = encode_bedrock_body(&body_str, &tool_name_overrides);
+                store_bedrock_stream_response(
+                    pool.get_ref(),
+                    &request_id,
+                    status,
+                    &resp_headers_json,
+                    &patched,
+                )
+                .await;
+                notify_request_completed(&hook_registry, &hook_context);
+                return Ok(builder.body(frames));
+            }
+        };
+        store_intercepted_response(
+            pool.get_ref(),
+            &request_id,
+            &intercepted_response,
+            &body_str,
+            None,
+        )
+        .await?;
+        notify_request_completed(&hook_registry, &hook_context);
+
+        let webfetch::InterceptedResponse {
+            status: followup_status,
+            body: followup_body,
+            ..
+        } = intercepted_response;
+        if followup_status != 200 {
+            return Ok(HttpResponse::build(to_actix_status(followup_status)?)
+                .content_type("application/json")
+                .body(followup_body.to_vec()));
+        }
+        let followup_str = String::from_utf8_lossy(&followup_body);
+        let (frames, _) = encode_bedrock_body(&followup_str, &tool_name_overrides);
+        return Ok(builder.body(frames));
+    }
+
     let (tx, rx) = futures::channel::mpsc::unbounded::<Result<Bytes, actix_web::Error>>();
 
-    spawn_bedrock_stream_converter(
-        upstream.bytes_stream(),
-        tx,
-        pool.clone(),
-        request_id,
-        resp_headers_json,
-        status,
-    );
+    let stream_task = if session.aws_region.is_some() {
+        spawn_bedrock_stream_relay(
+            upstream.bytes_stream(),
+            tx,
+            pool.clone(),
+            request_id.clone(),
+            resp_headers_json,
+            status,
+        )
+    } else {
+        spawn_bedrock_stream_converter(
+            upstream.bytes_stream(),
+            tx,
+            tool_name_overrides,
+            pool.clone(),
+            request_id.clone(),
+            resp_headers_json,
+            status,
+        )
+    };
+    notify_request_streamed(&hook_registry, &hook_context, stream_task);
 
     Ok(builder.streaming(rx))
 }
@@ -606,4 +1050,55 @@ mod tests {
         let decoded = BASE64.decode(b64).unwrap();
         assert_eq!(std::str::from_utf8(&decoded).unwrap(), data);
     }
+
+    #[test]
+    fn bedrock_body_restores_overridden_tool_names() {
+        let overrides = vec![("mcp__fs__read".to_string(), "read".to_string())];
+        let body = "event: content_block_start\ndata: \
+                    {\"type\":\"content_block_start\",\"index\":0,\
+                    \"content_block\":{\"type\":\"tool_use\",\"id\":\"t\",\"name\":\"read\"}}\n\n\
+                    event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+        let (frames, patched) = encode_bedrock_body(body, &overrides);
+        assert_eq!(decode_event_stream_messages(&frames).len(), 2);
+        assert!(patched.contains("\"name\":\"mcp__fs__read\""));
+        assert!(patched.ends_with("event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"));
+        assert_eq!(convert_event_stream_to_sse(&frames), patched);
+    }
+
+    // --- Event Stream decoding tests ---
+
+    #[test]
+    fn event_stream_to_sse_roundtrip() {
+        let start = r#"{"type":"message_start","message":{"id":"m"}}"#;
+        let stop = r#"{"type":"message_stop"}"#;
+        let mut stream = encode_bedrock_chunk(start);
+        stream.extend(encode_bedrock_chunk(stop));
+        assert_eq!(
+            convert_event_stream_to_sse(&stream),
+            format!(
+                "event: message_start\ndata: {}\n\nevent: message_stop\ndata: {}\n\n",
+                start, stop
+            )
+        );
+
+        // An incomplete trailing message is ignored.
+        let partial = encode_bedrock_chunk(stop);
+        stream.extend_from_slice(&partial[..partial.len() - 1]);
+        assert_eq!(decode_event_stream_messages(&stream).len(), 2);
+    }
+
+    #[test]
+    fn event_stream_exception_becomes_error_event() {
+        let exception = encode_event_stream_message(
+            &[
+                (":message-type", "exception"),
+                (":exception-type", "throttlingException"),
+            ],
+            b"Too many requests",
+        );
+        let sse = convert_event_stream_to_sse(&exception);
+        assert!(sse.starts_with("event: error\ndata: "));
+        assert!(sse.contains("throttlingException"));
+        assert!(sse.contains("Too many requests"));
+    }
 }
@@ -0,0 +1,254 @@
+//! Breakpoint mode: a session's requests are held before they are forwarded
+//! until a dashboard user forwards them, optionally with an edited body, or
+//! rejects them.
+
+use common::models::{HeldRequestInfo, SESSION_EVENT_BREAKPOINT};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+use crate::hooks::{HookContext, OutgoingRequest};
+
+/// Message returned to the client for a rejected request.
+const REJECTED_MESSAGE: &str = "Request rejected at a gateway proxy breakpoint";
+
+/// What to do with a held request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakpointDecision {
+    /// Forward the request, with this body instead of the held one if given.
+    Forward(Option<Value>),
+    Reject,
+}
+
+/// A request waiting at a breakpoint.
+struct HeldRequest {
+    session_id: String,
+    body: Value,
+    sender: oneshot::Sender<BreakpointDecision>,
+    held_at: Instant,
+}
+
+/// Requests held at breakpoints, by request id.
+#[derive(Clone, Default)]
+pub struct BreakpointQueue {
+    held: Arc<Mutex<HashMap<String, HeldRequest>>>,
+}
+
+pub fn new_breakpoint_queue() -> BreakpointQueue {
+    BreakpointQueue::default()
+}
+
+/// The session's held requests, oldest first.
+pub fn list_held_requests(queue: &BreakpointQueue, session_id: &str) -> Vec<HeldRequestInfo> {
+    let held = queue.held.lock().unwrap();
+    let mut held_requests: Vec<_> = held
+        .iter()
+        .filter(|(_, held_request)| held_request.session_id == session_id)
+        .collect();
+    held_requests.sort_by_key(|(_, held_request)| held_request.held_at);
+    held_requests
+        .into_iter()
+        .map(|(request_id, held_request)| HeldRequestInfo {
+            request_id: request_id.clone(),
+            body: held_request.body.clone(),
+            waiting_secs: held_request.held_at.elapsed().as_secs(),
+        })
+        .collect()
+}
+
+/// Release a held request with `decision`. Returns false when the request
+/// isn't held, e.g. because it timed out.
+pub fn resolve_held_request(
+    queue: &BreakpointQueue,
+    request_id: &str,
+    decision: BreakpointDecision,
+) -> bool {
+    let Some(held_request) = queue.held.lock().unwrap().remove(request_id) else {
+        return false;
+    };
+    held_request.sender.send(decision).is_ok()
+}
+
+/// Forward every request the session holds unchanged, e.g. when its
+/// breakpoint is turned off.
+pub fn release_session_requests(queue: &BreakpointQueue, session_id: &str) {
+    let mut held = queue.held.lock().unwrap();
+    let request_ids: Vec<String> = held
+        .iter()
+        .filter(|(_, held_request)| held_request.session_id == session_id)
+        .map(|(request_id, _)| request_id.clone())
+        .collect();
+    for request_id in request_ids {
+        if let Some(held_request) = held.remove(&request_id) {
+            let _ = held_request.sender.send(BreakpointDecision::Forward(None));
+        }
+    }
+}
+
+/// Removes a held request from the queue once its waiter is gone, including
+/// when the client disconnects and the waiting future is dropped.
+struct HeldRequestGuard<'a> {
+    queue: &'a BreakpointQueue,
+    request_id: &'a str,
+}
+
+impl Drop for HeldRequestGuard<'_> {
+    fn drop(&mut self) {
+        self.queue.held.lock().unwrap().remove(self.request_id);
+    }
+}
+
+/// Hold the request until it is decided on the dashboard, then apply the
+/// decision. Undecided requests are forwarded unchanged after
+/// `approval_timeout_secs`.
+pub(crate) async fn hold_request(
+    queue: &BreakpointQueue,
+    context: &HookContext<'_>,
+    request: &mut OutgoingRequest,
+) {
+    let (sender, receiver) = oneshot::channel();
+    let held_at = Instant::now();
+    queue.held.lock().unwrap().insert(
+        context.request_id.to_string(),
+        HeldRequest {
+            session_id: context.session.id.to_string(),
+            body: request.body.clone(),
+            sender,
+            held_at,
+        },
+    );
+    let held_guard = HeldRequestGuard {
+        queue,
+        request_id: context.request_id,
+    };
+    log::info!(
+        "{}: holding {} at a breakpoint",
+        context.session.name,
+        context.request_id
+    );
+    let breakpoint_timeout = Duration::from_secs(context.config.approval_timeout_secs);
+    let detail = match tokio::time::timeout(breakpoint_timeout, receiver).await {
+        Ok(Ok(BreakpointDecision::Forward(Some(edited_body)))) if edited_body != request.body => {
+            request.body = edited_body;
+            request.body_changed = true;
+            "Forwarded (edited)"
+        }
+        Ok(Ok(BreakpointDecision::Forward(_))) => "Forwarded",
+        Ok(Ok(BreakpointDecision::Reject)) => {
+            request.rejection = Some(REJECTED_MESSAGE.to_string());
+            "Rejected"
+        }
+        _ => "Timed out (forwarded)",
+    };
+    drop(held_guard);
+    record_breakpoint_event(context, detail, held_at.elapsed()).await;
+}
+
+/// Record how a held request was decided on the session timeline.
+async fn record_breakpoint_event(context: &HookContext<'_>, detail: &str, waited: Duration) {
+    if let Err(e) = db::create_session_event(
+        context.pool,
+        &context.session.id.to_string(),
+        SESSION_EVENT_BREAKPOINT,
+        &format!("{}: {}", detail, context.request_id),
+        Some(waited.as_millis() as i64),
+    )
+    .await
+    {
+        log::warn!("breakpoint: failed to record event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::make_session;
+    use common::config::AppConfig;
+    use sqlx::SqlitePool;
+
+    fn insert_held_request(
+        queue: &BreakpointQueue,
+        request_id: &str,
+        session_id: &str,
+    ) -> oneshot::Receiver<BreakpointDecision> {
+        let (sender, receiver) = oneshot::channel();
+        queue.held.lock().unwrap().insert(
+            request_id.to_string(),
+            HeldRequest {
+                session_id: session_id.to_string(),
+                body: serde_json::json!({"model": "m"}),
+                sender,
+                held_at: Instant::now(),
+            },
+        );
+        receiver
+    }
+
+    #[test]
+    fn resolve_held_request_sends_the_decision_once() {
+        let queue = new_breakpoint_queue();
+        let mut receiver = insert_held_request(&queue, "r1", "s1");
+        assert_eq!(list_held_requests(&queue, "s1").len(), 1);
+        assert!(list_held_requests(&queue, "s2").is_empty());
+        assert!(resolve_held_request(
+            &queue,
+            "r1",
+            BreakpointDecision::Reject
+        ));
+        assert_eq!(receiver.try_recv().unwrap(), BreakpointDecision::Reject);
+        assert!(!resolve_held_request(
+            &queue,
+            "r1",
+            BreakpointDecision::Reject
+        ));
+    }
+
+    #[test]
+    fn release_session_requests_forwards_only_that_session() {
+        let queue = new_breakpoint_queue();
+        let mut released = insert_held_request(&queue, "r1", "s1");
+        let _kept = insert_held_request(&queue, "r2", "s2");
+        release_session_requests(&queue, "s1");
+        assert_eq!(
+            released.try_recv().unwrap(),
+            BreakpointDecision::Forward(None)
+        );
+        assert_eq!(list_held_requests(&queue, "s2").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn hold_request_removes_the_entry_when_its_waiter_is_dropped() {
+        let queue = new_breakpoint_queue();
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let config = AppConfig::default();
+        let session = make_session(false);
+        let context = HookContext {
+            pool: &pool,
+            config: &config,
+            session: &session,
+            request_id: "r1",
+            intercept_rules_match: true,
+        };
+        let mut request = OutgoingRequest {
+            body: serde_json::json!({"model": "m"}),
+            body_changed: false,
+            tool_name_overrides: vec![],
+            rejection: None,
+        };
+        let session_id = session.id.to_string();
+        let waiter = hold_request(&queue, &context, &mut request);
+        let held_while_waiting = async {
+            tokio::task::yield_now().await;
+            list_held_requests(&queue, &session_id).len()
+        };
+        let (waiter_result, held_count) = tokio::join!(
+            tokio::time::timeout(Duration::from_millis(50), waiter),
+            held_while_waiting
+        );
+        assert!(waiter_result.is_err());
+        assert_eq!(held_count, 1);
+        assert!(list_held_requests(&queue, &session_id).is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+//! Who sent a proxied request. Several tools can share one session; the
+//! User-Agent, `x-app` header and connection address tell them apart.
+
+use actix_web::HttpRequest;
+
+/// Header Anthropic clients name their app with, e.g. `cli` from Claude Code.
+const CLIENT_APP_HEADER: &str = "x-app";
+
+/// The client details recorded with a request.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub app: Option<String>,
+    /// The connection's `ip:port`.
+    pub addr: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let read_header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        ClientInfo {
+            user_agent: read_header(actix_web::http::header::USER_AGENT.as_str()),
+            app: read_header(CLIENT_APP_HEADER),
+            addr: req.peer_addr().map(|peer_addr| peer_addr.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_info_reads_headers_and_peer_address() {
+        let req = actix_web::test::TestRequest::post()
+            .insert_header(("user-agent", "claude-cli/1.0.0 (external, cli)"))
+            .insert_header(("x-app", "cli"))
+            .peer_addr("127.0.0.1:54321".parse().unwrap())
+            .to_http_request();
+        let client_info = ClientInfo::from_request(&req);
+        assert_eq!(
+            client_info.user_agent.as_deref(),
+            Some("claude-cli/1.0.0 (external, cli)")
+        );
+        assert_eq!(client_info.app.as_deref(), Some("cli"));
+        assert_eq!(client_info.addr.as_deref(), Some("127.0.0.1:54321"));
+
+        let bare_client_info =
+            ClientInfo::from_request(&actix_web::test::TestRequest::get().to_http_request());
+        assert!(bare_client_info.user_agent.is_none() && bare_client_info.addr.is_none());
+    }
+}
@@ -0,0 +1,330 @@
+//! Per-session limits on requests handled at once. Agent frameworks may fire
+//! many requests in parallel, and each can park in the approval queue; the
+//! limit keeps one session from tying up every worker.
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    HttpResponse,
+};
+use bytes::Bytes;
+use common::{
+    concurrency::{SessionLoad, OVERFLOW_REJECT},
+    models::Session,
+};
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Slots of one session. A limit change swaps in new slots; requests already
+/// holding the old ones finish against the old limit.
+struct SessionSlots {
+    limit: Option<usize>,
+    semaphore: Arc<Semaphore>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+impl SessionSlots {
+    fn new(limit: Option<usize>) -> Self {
+        SessionSlots {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit.unwrap_or(0))),
+            in_flight: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// In-flight requests per session id.
+#[derive(Clone)]
+pub struct SessionLimiter {
+    sessions: Arc<Mutex<HashMap<String, Arc<SessionSlots>>>>,
+}
+
+/// Create a new limiter with no sessions tracked.
+pub fn new_session_limiter() -> SessionLimiter {
+    SessionLimiter {
+        sessions: Arc::new(Mutex::new(HashMap::new())),
+    }
+}
+
+/// Held while a request is handled; frees the session's slot on drop.
+pub struct SessionSlot {
+    session_slots: Arc<SessionSlots>,
+    _semaphore_permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for SessionSlot {
+    fn drop(&mut self) {
+        self.session_slots.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Counts a request waiting for a slot, including one whose client gave up.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The session's limit; unset or non-positive means unlimited.
+fn read_session_limit(session: &Session) -> Option<usize> {
+    session
+        .max_concurrent_requests
+        .filter(|&limit| limit > 0)
+        .map(|limit| limit as usize)
+}
+
+fn get_session_slots(
+    limiter: &SessionLimiter,
+    session_id: &str,
+    limit: Option<usize>,
+) -> Arc<SessionSlots> {
+    let mut sessions = limiter.sessions.lock().unwrap();
+    match sessions.get(session_id) {
+        Some(session_slots) if session_slots.limit == limit => session_slots.clone(),
+        _ => {
+            let session_slots = Arc::new(SessionSlots::new(limit));
+            sessions.insert(session_id.to_string(), session_slots.clone());
+            session_slots
+        }
+    }
+}
+
+/// An Anthropic-style 429 for a request over the session's limit.
+fn build_limit_response(limit: usize) -> HttpResponse {
+    HttpResponse::TooManyRequests().json(serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "rate_limit_error",
+            "message": format!(
+                "Session is at the proxy's limit of {} concurrent requests",
+                limit
+            ),
+        }
+    }))
+}
+
+/// Take a permit from a full session's semaphore, counted as queued while waiting.
+async fn wait_for_permit(session_slots: &SessionSlots) -> OwnedSemaphorePermit {
+    session_slots.queued.fetch_add(1, Ordering::SeqCst);
+    let _queued = QueuedGuard(&session_slots.queued);
+    // The semaphore is never closed.
+    session_slots
+        .semaphore
+        .clone()
+        .acquire_owned()
+        .await
+        .unwrap()
+}
+
+/// Take a permit for a request over a limited session, waiting or rejecting
+/// by the session's overflow mode when it is full.
+async fn acquire_limited_permit(
+    session_slots: &SessionSlots,
+    session: &Session,
+    limit: usize,
+) -> Result<OwnedSemaphorePermit, HttpResponse> {
+    if let Ok(semaphore_permit) = session_slots.semaphore.clone().try_acquire_owned() {
+        return Ok(semaphore_permit);
+    }
+    if session.concurrency_overflow.as_deref() == Some(OVERFLOW_REJECT) {
+        log::warn!(
+            "Session {} is at its limit of {} requests",
+            session.id,
+            limit
+        );
+        return Err(build_limit_response(limit));
+    }
+    Ok(wait_for_permit(session_slots).await)
+}
+
+/// Take a slot for a request to `session`. With a limit set, a full session
+/// waits for a slot, or gets a 429 when its overflow mode is reject.
+pub async fn acquire_session_slot(
+    limiter: &SessionLimiter,
+    session: &Session,
+) -> Result<SessionSlot, HttpResponse> {
+    let limit = read_session_limit(session);
+    let session_slots = get_session_slots(limiter, &session.id.to_string(), limit);
+    let semaphore_permit = match limit {
+        Some(limit) => Some(acquire_limited_permit(&session_slots, session, limit).await?),
+        None => None,
+    };
+    session_slots.in_flight.fetch_add(1, Ordering::SeqCst);
+    Ok(SessionSlot {
+        session_slots,
+        _semaphore_permit: semaphore_permit,
+    })
+}
+
+/// A response body holding its request's slot until the last chunk is sent,
+/// so a streamed response keeps counting against the session until it ends.
+struct SlotBody {
+    body: BoxBody,
+    session_slot: Option<SessionSlot>,
+}
+
+impl MessageBody for SlotBody {
+    type Error = Box<dyn std::error::Error>;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.get_mut();
+        let chunk = Pin::new(&mut this.body).poll_next(cx);
+        if matches!(chunk, Poll::Ready(None) | Poll::Ready(Some(Err(_)))) {
+            this.session_slot = None;
+        }
+        chunk
+    }
+}
+
+/// Keep `session_slot` until `resp`'s body has been sent or dropped.
+pub fn hold_slot_for_body(resp: HttpResponse, session_slot: SessionSlot) -> HttpResponse {
+    resp.map_body(|_, body| SlotBody {
+        body,
+        session_slot: Some(session_slot),
+    })
+    .map_into_boxed_body()
+}
+
+/// Requests in flight and waiting for `session_id`.
+pub fn get_session_load(limiter: &SessionLimiter, session_id: &str) -> SessionLoad {
+    let sessions = limiter.sessions.lock().unwrap();
+    match sessions.get(session_id) {
+        Some(session_slots) => SessionLoad {
+            in_flight: session_slots.in_flight.load(Ordering::SeqCst),
+            queued: session_slots.queued.load(Ordering::SeqCst),
+            limit: session_slots.limit,
+        },
+        None => SessionLoad::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::make_session;
+
+    fn limited_session(limit: i64, overflow: Option<&str>) -> Session {
+        Session {
+            max_concurrent_requests: Some(limit),
+            concurrency_overflow: overflow.map(str::to_string),
+            ..make_session(false)
+        }
+    }
+
+    #[tokio::test]
+    async fn acquire_session_slot_rejects_over_limit() {
+        let limiter = new_session_limiter();
+        let session = limited_session(1, Some(OVERFLOW_REJECT));
+        let session_id = session.id.to_string();
+        let session_slot = acquire_session_slot(&limiter, &session).await.unwrap();
+        let resp = acquire_session_slot(&limiter, &session)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        assert_eq!(get_session_load(&limiter, &session_id).in_flight, 1);
+        drop(session_slot);
+        assert_eq!(get_session_load(&limiter, &session_id).in_flight, 0);
+        assert!(acquire_session_slot(&limiter, &session).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn acquire_session_slot_queues_until_a_slot_frees() {
+        let limiter = new_session_limiter();
+        let session = limited_session(1, None);
+        let session_id = session.id.to_string();
+        let session_slot = acquire_session_slot(&limiter, &session).await.unwrap();
+
+        let waiter = {
+            let limiter = limiter.clone();
+            let session = session.clone();
+            tokio::spawn(async move { acquire_session_slot(&limiter, &session).await.is_ok() })
+        };
+        while get_session_load(&limiter, &session_id).queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(
+            get_session_load(&limiter, &session_id),
+            SessionLoad {
+                in_flight: 1,
+                queued: 1,
+                limit: Some(1),
+            }
+        );
+        drop(session_slot);
+        assert!(waiter.await.unwrap());
+        assert_eq!(get_session_load(&limiter, &session_id).queued, 0);
+    }
+
+    #[actix_web::test]
+    async fn hold_slot_for_body_queues_requests_until_the_stream_ends() {
+        let limiter = new_session_limiter();
+        let session = limited_session(1, None);
+        let session_id = session.id.to_string();
+        let session_slot = acquire_session_slot(&limiter, &session).await.unwrap();
+        let (tx, rx) = futures::channel::mpsc::unbounded::<Result<Bytes, actix_web::Error>>();
+        let resp = hold_slot_for_body(HttpResponse::Ok().streaming(rx), session_slot);
+
+        let waiter = {
+            let limiter = limiter.clone();
+            let session = session.clone();
+            tokio::spawn(async move { acquire_session_slot(&limiter, &session).await.is_ok() })
+        };
+        while get_session_load(&limiter, &session_id).queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        tx.unbounded_send(Ok(Bytes::from_static(b"data: {}\n\n")))
+            .unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(
+            get_session_load(&limiter, &session_id),
+            SessionLoad {
+                in_flight: 1,
+                queued: 1,
+                limit: Some(1),
+            }
+        );
+
+        drop(tx);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(body, Bytes::from_static(b"data: {}\n\n"));
+        assert!(waiter.await.unwrap());
+        assert_eq!(get_session_load(&limiter, &session_id).queued, 0);
+    }
+
+    #[tokio::test]
+    async fn acquire_session_slot_counts_unlimited_sessions() {
+        let limiter = new_session_limiter();
+        let session = make_session(false);
+        let first = acquire_session_slot(&limiter, &session).await.unwrap();
+        let _second = acquire_session_slot(&limiter, &session).await.unwrap();
+        let session_load = get_session_load(&limiter, &session.id.to_string());
+        assert_eq!((session_load.in_flight, session_load.limit), (2, None));
+        drop(first);
+        assert_eq!(
+            get_session_load(&limiter, &session.id.to_string()).in_flight,
+            1
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Correlation between a client's request and its logged entry. Each proxied
+//! request gets an id up front; it becomes the logged request's id and is
+//! returned in `x-proxy-request-id`, so it can be looked up on the dashboard.
+
+use actix_web::{HttpMessage, HttpRequest};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+/// Response header carrying the logged request's id.
+pub const PROXY_REQUEST_ID_HEADER: &str = "x-proxy-request-id";
+
+/// Upstream header carrying the session id, when `forward_proxy_session_header` is set.
+pub const PROXY_SESSION_HEADER: &str = "x-proxy-session";
+
+/// The id assigned to a proxied request, kept in the request's extensions.
+#[derive(Debug, Clone)]
+pub struct ProxyRequestId(pub String);
+
+/// Assign a new id to `req` and return it.
+pub fn assign_proxy_request_id(req: &HttpRequest) -> String {
+    let proxy_request_id = uuid::Uuid::new_v4().to_string();
+    req.extensions_mut()
+        .insert(ProxyRequestId(proxy_request_id.clone()));
+    proxy_request_id
+}
+
+/// The id assigned to `req`, if any.
+pub fn get_proxy_request_id(req: &HttpRequest) -> Option<String> {
+    req.extensions()
+        .get::<ProxyRequestId>()
+        .map(|proxy_request_id| proxy_request_id.0.clone())
+}
+
+/// Add `x-proxy-session` to headers sent upstream.
+pub(crate) fn set_proxy_session_header(forward_headers: &mut HeaderMap, session_id: &str) {
+    if let Ok(session_header_value) = HeaderValue::from_str(session_id) {
+        forward_headers.insert(
+            HeaderName::from_static(PROXY_SESSION_HEADER),
+            session_header_value,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assign_proxy_request_id_is_read_back() {
+        let req = actix_web::test::TestRequest::post().to_http_request();
+        assert_eq!(get_proxy_request_id(&req), None);
+        let proxy_request_id = assign_proxy_request_id(&req);
+        assert_eq!(get_proxy_request_id(&req), Some(proxy_request_id));
+    }
+}
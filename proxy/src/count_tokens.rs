@@ -0,0 +1,65 @@
+use common::tokens::{estimate_context_breakdown, HeuristicTokenizer};
+use serde_json::Value;
+
+/// Whether the request path targets the Token Counting endpoint.
+pub fn is_count_tokens_path(path: &str) -> bool {
+    path.trim_end_matches('/').ends_with("messages/count_tokens")
+}
+
+/// Estimate the input tokens of a Messages-shaped request body locally, so it
+/// can be compared against the count returned by the API. This is an
+/// approximation; it does not reproduce the model's real tokenizer.
+pub fn estimate_input_tokens(data: &Value) -> i64 {
+    estimate_context_breakdown(
+        &HeuristicTokenizer,
+        data.get("system"),
+        data.get("tools"),
+        data.get("messages"),
+    )
+    .total()
+}
+
+/// Extract `input_tokens` from a count_tokens response body.
+pub fn parse_counted_input_tokens(response_body: &str) -> Option<i64> {
+    serde_json::from_str::<Value>(response_body)
+        .ok()?
+        .get("input_tokens")?
+        .as_i64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_tokens_path_detected() {
+        assert!(is_count_tokens_path("v1/messages/count_tokens"));
+        assert!(is_count_tokens_path("v1/messages/count_tokens/"));
+        assert!(!is_count_tokens_path("v1/messages"));
+    }
+
+    #[test]
+    fn estimate_covers_system_messages_and_tools() {
+        let data = serde_json::json!({
+            "system": "Be brief.",
+            "messages": [
+                {"role": "user", "content": "hi"},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t1", "name": "get", "input": {}}
+                ]}
+            ],
+            "tools": [{"name": "get", "input_schema": {}}]
+        });
+        // system: 4, messages: (3 + 1) + (3 + 1 + 2), tools: 8 + 1 + 2
+        assert_eq!(estimate_input_tokens(&data), 25);
+    }
+
+    #[test]
+    fn counted_input_tokens_parsed_from_response() {
+        assert_eq!(
+            parse_counted_input_tokens(r#"{"input_tokens": 42}"#),
+            Some(42)
+        );
+        assert_eq!(parse_counted_input_tokens("not json"), None);
+    }
+}
@@ -1,7 +1,14 @@
+use common::models::{
+    AppliedFilter, APPLIED_FILTER_SYSTEM, APPLIED_FILTER_TOOL, APPLIED_FILTER_TOOL_NAME_OVERRIDE,
+    APPLIED_FILTER_TOOL_PAIR,
+};
 use regex::Regex;
 use serde_json::Value;
 use std::collections::HashSet;
 
+/// Longest system text kept in an `AppliedFilter::detail`.
+const APPLIED_FILTER_PREVIEW_CHARS: usize = 80;
+
 /// Returns true if text matches the pattern (tried as regex first, then substring).
 fn matches_pattern(text: &str, pattern: &str) -> bool {
     match Regex::new(pattern) {
@@ -10,48 +17,79 @@ fn matches_pattern(text: &str, pattern: &str) -> bool {
     }
 }
 
+/// The start of a removed system text, for the provenance record.
+fn build_text_preview(text: &str) -> String {
+    let mut chars = text.chars();
+    let preview: String = chars.by_ref().take(APPLIED_FILTER_PREVIEW_CHARS).collect();
+    if chars.next().is_some() {
+        format!("{}…", preview)
+    } else {
+        preview
+    }
+}
+
 /// Apply all configured filters to a JSON request body in place.
 ///
 /// - **system_filters**: regex/substring patterns — matching system text blocks are removed.
 /// - **tool_filters**: tool names — matching tool entries are removed from the `tools` array.
 /// - **keep_tool_pairs**: if > 0, only the last N tool_use/tool_result pairs are kept in messages;
 ///   older pairs are removed. Messages whose content becomes empty are removed entirely.
+///
+/// Returns one `AppliedFilter` per removal, in the order they were made.
 pub fn apply_filters(
     body: &mut Value,
     system_filters: &[String],
     tool_filters: &[String],
     keep_tool_pairs: i64,
-) {
-    apply_system_filters(body, system_filters);
-    apply_tool_filters(body, tool_filters);
+) -> Vec<AppliedFilter> {
+    let mut applied_filters = apply_system_filters(body, system_filters);
+    applied_filters.extend(apply_tool_filters(body, tool_filters));
     if keep_tool_pairs > 0 {
-        apply_message_filters(body, keep_tool_pairs as usize);
+        applied_filters.extend(apply_message_filters(body, keep_tool_pairs as usize));
     }
+    applied_filters
 }
 
-fn apply_system_filters(body: &mut Value, filters: &[String]) {
+fn build_system_applied_filter(text: &str, filters: &[String]) -> Option<AppliedFilter> {
+    let filter = filters.iter().find(|filter| matches_pattern(text, filter))?;
+    Some(AppliedFilter {
+        kind: APPLIED_FILTER_SYSTEM.to_string(),
+        rule: filter.clone(),
+        detail: build_text_preview(text),
+    })
+}
+
+fn apply_system_filters(body: &mut Value, filters: &[String]) -> Vec<AppliedFilter> {
     if filters.is_empty() {
-        return;
+        return vec![];
     }
 
     let system = match body.get_mut("system") {
         Some(system) => system,
-        None => return,
+        None => return vec![],
     };
 
     if let Some(string) = system.as_str().map(|string| string.to_string()) {
-        if filters.iter().any(|filter| matches_pattern(&string, filter)) {
-            if let Some(obj) = body.as_object_mut() {
-                obj.remove("system");
-            }
+        let Some(applied_filter) = build_system_applied_filter(&string, filters) else {
+            return vec![];
+        };
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove("system");
         }
-        return;
+        return vec![applied_filter];
     }
 
+    let mut applied_filters = Vec::new();
     if let Some(arr) = system.as_array_mut() {
         arr.retain(|block| {
             let text = block.get("text").and_then(|field| field.as_str()).unwrap_or("");
-            !filters.iter().any(|filter| matches_pattern(text, filter))
+            match build_system_applied_filter(text, filters) {
+                Some(applied_filter) => {
+                    applied_filters.push(applied_filter);
+                    false
+                }
+                None => true,
+            }
         });
         if arr.is_empty() {
             if let Some(obj) = body.as_object_mut() {
@@ -59,22 +97,32 @@ fn apply_system_filters(body: &mut Value, filters: &[String]) {
             }
         }
     }
+    applied_filters
 }
 
-fn apply_tool_filters(body: &mut Value, filters: &[String]) {
+fn apply_tool_filters(body: &mut Value, filters: &[String]) -> Vec<AppliedFilter> {
     if filters.is_empty() {
-        return;
+        return vec![];
     }
 
     let tools = match body.get_mut("tools") {
         Some(tools) => tools,
-        None => return,
+        None => return vec![],
     };
 
+    let mut applied_filters = Vec::new();
     if let Some(arr) = tools.as_array_mut() {
         arr.retain(|tool| {
             let name = tool.get("name").and_then(|field| field.as_str()).unwrap_or("");
-            !filters.iter().any(|filter| filter == name)
+            if !filters.iter().any(|filter| filter == name) {
+                return true;
+            }
+            applied_filters.push(AppliedFilter {
+                kind: APPLIED_FILTER_TOOL.to_string(),
+                rule: name.to_string(),
+                detail: format!("Removed tool definition {}", name),
+            });
+            false
         });
         if arr.is_empty() {
             if let Some(obj) = body.as_object_mut() {
@@ -82,37 +130,39 @@ fn apply_tool_filters(body: &mut Value, filters: &[String]) {
             }
         }
     }
+    applied_filters
 }
 
-fn apply_message_filters(body: &mut Value, keep: usize) {
+fn apply_message_filters(body: &mut Value, keep: usize) -> Vec<AppliedFilter> {
     let messages = match body.get_mut("messages") {
         Some(Value::Array(arr)) => arr,
-        _ => return,
+        _ => return vec![],
     };
 
-    // 1. Collect all tool_use IDs in chronological order
-    let mut all_tool_ids: Vec<String> = Vec::new();
+    // 1. Collect all tool_use IDs and names in chronological order
+    let mut all_tool_uses: Vec<(String, String)> = Vec::new();
     for msg in messages.iter() {
         if let Some(blocks) = msg.get("content").and_then(|content| content.as_array()) {
             for block in blocks {
                 if block.get("type").and_then(|field| field.as_str()) == Some("tool_use") {
                     if let Some(id) = block.get("id").and_then(|field| field.as_str()) {
-                        all_tool_ids.push(id.to_string());
+                        let name = block.get("name").and_then(|field| field.as_str());
+                        all_tool_uses.push((id.to_string(), name.unwrap_or("").to_string()));
                     }
                 }
             }
         }
     }
 
-    if all_tool_ids.len() <= keep {
-        return;
+    if all_tool_uses.len() <= keep {
+        return vec![];
     }
 
     // 2. IDs to remove: all except the last `keep`
-    let remove_count = all_tool_ids.len() - keep;
-    let ids_to_remove: HashSet<&str> = all_tool_ids[..remove_count]
+    let remove_count = all_tool_uses.len() - keep;
+    let ids_to_remove: HashSet<&str> = all_tool_uses[..remove_count]
         .iter()
-        .map(|id| id.as_str())
+        .map(|(id, _)| id.as_str())
         .collect();
 
     // 3. Filter content blocks and remove empty messages
@@ -142,40 +192,85 @@ fn apply_message_filters(body: &mut Value, keep: usize) {
 
         !content.is_empty()
     });
+
+    let rule = format!("keep_tool_pairs = {}", keep);
+    all_tool_uses[..remove_count]
+        .iter()
+        .map(|(id, name)| AppliedFilter {
+            kind: APPLIED_FILTER_TOOL_PAIR.to_string(),
+            rule: rule.clone(),
+            detail: format!("Removed {} call {} and its result", name, id),
+        })
+        .collect()
 }
 
 /// Apply tool name overrides to outgoing request body.
 ///
 /// Renames tools in `body["tools"][*]["name"]` and in `tool_use` content blocks
-/// in `body["messages"][*]["content"][*]["name"]`.
-pub fn apply_tool_name_overrides(body: &mut Value, overrides: &[(String, String)]) {
+/// in `body["messages"][*]["content"][*]["name"]`. Returns one `AppliedFilter`
+/// per override that renamed anything.
+pub fn apply_tool_name_overrides(
+    body: &mut Value,
+    overrides: &[(String, String)],
+) -> Vec<AppliedFilter> {
     if overrides.is_empty() {
+        return vec![];
+    }
+    let mut rename_counts = vec![0usize; overrides.len()];
+    rename_tool_names_in_tools_array(body, overrides, &mut rename_counts);
+    rename_tool_names_in_messages(body, overrides, &mut rename_counts);
+    overrides
+        .iter()
+        .zip(rename_counts)
+        .filter(|(_, rename_count)| *rename_count > 0)
+        .map(|((original, override_name), rename_count)| AppliedFilter {
+            kind: APPLIED_FILTER_TOOL_NAME_OVERRIDE.to_string(),
+            rule: original.clone(),
+            detail: format!(
+                "Renamed to {} in {} place{}",
+                override_name,
+                rename_count,
+                if rename_count == 1 { "" } else { "s" }
+            ),
+        })
+        .collect()
+}
+
+/// Rename `name` by the first matching override, counting the rename.
+fn rename_tool_name(name: &mut Value, overrides: &[(String, String)], rename_counts: &mut [usize]) {
+    let Some(name_str) = name.as_str().map(|s| s.to_string()) else {
         return;
+    };
+    for (index, (original, override_name)) in overrides.iter().enumerate() {
+        if name_str == *original {
+            *name = Value::String(override_name.clone());
+            rename_counts[index] += 1;
+            break;
+        }
     }
-    rename_tool_names_in_tools_array(body, overrides);
-    rename_tool_names_in_messages(body, overrides);
 }
 
-fn rename_tool_names_in_tools_array(body: &mut Value, overrides: &[(String, String)]) {
+fn rename_tool_names_in_tools_array(
+    body: &mut Value,
+    overrides: &[(String, String)],
+    rename_counts: &mut [usize],
+) {
     let tools = match body.get_mut("tools").and_then(|v| v.as_array_mut()) {
         Some(arr) => arr,
         None => return,
     };
     for tool in tools.iter_mut() {
         if let Some(name) = tool.get_mut("name") {
-            if let Some(name_str) = name.as_str().map(|s| s.to_string()) {
-                for (original, override_name) in overrides {
-                    if name_str == *original {
-                        *name = Value::String(override_name.clone());
-                        break;
-                    }
-                }
-            }
+            rename_tool_name(name, overrides, rename_counts);
         }
     }
 }
 
-fn rename_tool_names_in_messages(body: &mut Value, overrides: &[(String, String)]) {
+fn rename_tool_names_in_messages(
+    body: &mut Value,
+    overrides: &[(String, String)],
+    rename_counts: &mut [usize],
+) {
     let messages = match body.get_mut("messages").and_then(|v| v.as_array_mut()) {
         Some(arr) => arr,
         None => return,
@@ -190,14 +285,7 @@ fn rename_tool_names_in_messages(body: &mut Value, overrides: &[(String, String)
                 continue;
             }
             if let Some(name) = block.get_mut("name") {
-                if let Some(name_str) = name.as_str().map(|s| s.to_string()) {
-                    for (original, override_name) in overrides {
-                        if name_str == *original {
-                            *name = Value::String(override_name.clone());
-                            break;
-                        }
-                    }
-                }
+                rename_tool_name(name, overrides, rename_counts);
             }
         }
     }
@@ -454,6 +542,67 @@ mod tests {
         assert_eq!(assistant[0]["id"].as_str().unwrap(), "tu2");
     }
 
+    #[test]
+    fn apply_filters_records_each_removal() {
+        let mut body = json!({
+            "system": [
+                {"type": "text", "text": "keep this"},
+                {"type": "text", "text": "remove this secret"}
+            ],
+            "tools": [{"name": "WebSearch"}, {"name": "Calc"}],
+            "messages": [
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "tu1", "name": "A", "input": {}},
+                    {"type": "tool_use", "id": "tu2", "name": "B", "input": {}}
+                ]}
+            ]
+        });
+        let applied_filters = apply_filters(
+            &mut body,
+            &["secret".to_string()],
+            &["WebSearch".to_string()],
+            1,
+        );
+        let summary: Vec<(&str, &str)> = applied_filters
+            .iter()
+            .map(|applied_filter| (applied_filter.kind.as_str(), applied_filter.rule.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (APPLIED_FILTER_SYSTEM, "secret"),
+                (APPLIED_FILTER_TOOL, "WebSearch"),
+                (APPLIED_FILTER_TOOL_PAIR, "keep_tool_pairs = 1"),
+            ]
+        );
+        assert_eq!(applied_filters[0].detail, "remove this secret");
+        assert_eq!(
+            applied_filters[2].detail,
+            "Removed A call tu1 and its result"
+        );
+    }
+
+    #[test]
+    fn apply_filters_records_nothing_when_unmatched() {
+        let mut body = json!({"system": "You are a helpful assistant.", "messages": []});
+        let applied_filters = apply_filters(
+            &mut body,
+            &["secret".to_string()],
+            &["WebSearch".to_string()],
+            3,
+        );
+        assert!(applied_filters.is_empty());
+    }
+
+    #[test]
+    fn build_text_preview_truncates_long_text() {
+        let text = "x".repeat(APPLIED_FILTER_PREVIEW_CHARS + 5);
+        let preview = build_text_preview(&text);
+        assert_eq!(preview.chars().count(), APPLIED_FILTER_PREVIEW_CHARS + 1);
+        assert!(preview.ends_with('…'));
+        assert_eq!(build_text_preview("short"), "short");
+    }
+
     #[test]
     fn apply_tool_name_overrides_renames_tools_array() {
         let mut body = json!({
@@ -484,6 +633,31 @@ mod tests {
         assert_eq!(name, "read");
     }
 
+    #[test]
+    fn apply_tool_name_overrides_counts_renames() {
+        let mut body = json!({
+            "tools": [{"name": "mcp__fs__read"}],
+            "messages": [
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "t1", "name": "mcp__fs__read", "input": {}}
+                ]}
+            ]
+        });
+        let overrides = vec![
+            ("mcp__fs__read".to_string(), "read".to_string()),
+            ("mcp__fs__write".to_string(), "write".to_string()),
+        ];
+        let applied_filters = apply_tool_name_overrides(&mut body, &overrides);
+        assert_eq!(
+            applied_filters,
+            vec![AppliedFilter {
+                kind: APPLIED_FILTER_TOOL_NAME_OVERRIDE.to_string(),
+                rule: "mcp__fs__read".to_string(),
+                detail: "Renamed to read in 2 places".to_string(),
+            }]
+        );
+    }
+
     #[test]
     fn apply_tool_name_overrides_no_op_when_empty() {
         let mut body = json!({"tools": [{"name": "my_tool"}]});
@@ -0,0 +1,78 @@
+//! Loads the request/response captures in `proxy/fixtures/`, saved from a
+//! request's "Test Fixture" download on the dashboard.
+
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::sse::serialize_sse_event;
+
+/// A captured request and its response.
+pub(crate) struct RequestFixture {
+    pub path: String,
+    pub request: Value,
+    pub response_status: Option<u16>,
+    /// Parsed `{"event", "data"}` objects of a streamed response.
+    pub response_events: Vec<Value>,
+    /// The body of a non-streamed response.
+    pub response_body: Option<Value>,
+}
+
+impl RequestFixture {
+    /// The streamed response in SSE wire format, as the upstream sent it.
+    pub fn build_response_sse_body(&self) -> String {
+        self.response_events
+            .iter()
+            .map(|event| {
+                let event_type = event.get("event").and_then(|field| field.as_str());
+                let data_str = match event.get("data") {
+                    Some(Value::String(data_str)) => data_str.clone(),
+                    Some(data) => data.to_string(),
+                    None => String::new(),
+                };
+                serialize_sse_event(event_type.unwrap_or(""), &data_str)
+            })
+            .collect()
+    }
+}
+
+/// Load `proxy/fixtures/{name}.json`. Panics when it is missing or malformed.
+pub(crate) fn load_fixture(name: &str) -> RequestFixture {
+    let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(format!("{}.json", name));
+    let fixture_json = std::fs::read_to_string(&fixture_path)
+        .unwrap_or_else(|e| panic!("failed to read {}: {}", fixture_path.display(), e));
+    let fixture: Value = serde_json::from_str(&fixture_json)
+        .unwrap_or_else(|e| panic!("failed to parse {}: {}", fixture_path.display(), e));
+    RequestFixture {
+        path: fixture["path"].as_str().unwrap_or("").to_string(),
+        request: fixture["request"].clone(),
+        response_status: fixture["response_status"]
+            .as_u64()
+            .map(|response_status| response_status as u16),
+        response_events: fixture["response_events"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default(),
+        response_body: fixture.get("response_body").cloned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sse::parse_sse_events;
+
+    #[test]
+    fn fixture_sse_body_parses_back_to_its_events() {
+        let fixture = load_fixture("webfetch_tool_use");
+        assert_eq!(fixture.path, "/v1/messages");
+        assert_eq!(fixture.response_status, Some(200));
+        assert_eq!(fixture.request["stream"], true);
+        assert!(fixture.response_body.is_none());
+        assert_eq!(
+            parse_sse_events(&fixture.build_response_sse_body()),
+            fixture.response_events
+        );
+    }
+}
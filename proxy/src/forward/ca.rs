@@ -0,0 +1,139 @@
+//! The CA the forward proxy signs per-host certificates with. Clients trust
+//! its certificate once, then accept the proxy for every intercepted host.
+
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, Issuer, KeyPair, KeyUsagePurpose};
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Common name of the generated CA certificate.
+const CA_COMMON_NAME: &str = "Gateway Proxy forward proxy CA";
+
+pub struct CertificateAuthority {
+    issuer: Issuer<'static, KeyPair>,
+    ca_cert_der: CertificateDer<'static>,
+    /// TLS configs already issued, by host.
+    server_configs: Mutex<HashMap<String, Arc<rustls::ServerConfig>>>,
+}
+
+/// The CA certificate's parameters. Loading an existing CA rebuilds its
+/// issuer from these, so they must not change between runs.
+fn build_ca_params() -> CertificateParams {
+    let mut ca_params = CertificateParams::default();
+    ca_params
+        .distinguished_name
+        .push(DnType::CommonName, CA_COMMON_NAME);
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    ca_params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    ca_params
+}
+
+impl CertificateAuthority {
+    /// A new CA held in memory only.
+    fn generate() -> anyhow::Result<(Self, String)> {
+        let key_pair = KeyPair::generate()?;
+        let ca_cert = build_ca_params().self_signed(&key_pair)?;
+        let ca_cert_pem = ca_cert.pem();
+        let certificate_authority = CertificateAuthority {
+            ca_cert_der: ca_cert.der().clone(),
+            issuer: Issuer::new(build_ca_params(), key_pair),
+            server_configs: Mutex::new(HashMap::new()),
+        };
+        Ok((certificate_authority, ca_cert_pem))
+    }
+
+    /// Load the CA from `cert_path` and `key_path`, generating and saving a
+    /// new one when the certificate doesn't exist yet. Returns whether a new
+    /// CA was generated.
+    pub fn load_or_generate(cert_path: &Path, key_path: &Path) -> anyhow::Result<(Self, bool)> {
+        if !cert_path.exists() {
+            let (certificate_authority, ca_cert_pem) = Self::generate()?;
+            write_private_file(
+                key_path,
+                &certificate_authority.issuer.key().serialize_pem(),
+            )?;
+            fs::write(cert_path, ca_cert_pem)?;
+            return Ok((certificate_authority, true));
+        }
+        let ca_cert_der = CertificateDer::from_pem_file(cert_path)
+            .map_err(|e| anyhow::anyhow!("reading {}: {}", cert_path.display(), e))?;
+        let key_pair = KeyPair::from_pem(&fs::read_to_string(key_path)?)
+            .map_err(|e| anyhow::anyhow!("reading {}: {}", key_path.display(), e))?;
+        let certificate_authority = CertificateAuthority {
+            ca_cert_der,
+            issuer: Issuer::new(build_ca_params(), key_pair),
+            server_configs: Mutex::new(HashMap::new()),
+        };
+        Ok((certificate_authority, false))
+    }
+
+    /// The TLS config presenting a certificate for `host`, issued on first
+    /// use and reused after.
+    pub fn get_server_config(&self, host: &str) -> anyhow::Result<Arc<rustls::ServerConfig>> {
+        if let Some(server_config) = self.server_configs.lock().unwrap().get(host) {
+            return Ok(server_config.clone());
+        }
+        let server_config = Arc::new(self.issue_server_config(host)?);
+        self.server_configs
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), server_config.clone());
+        Ok(server_config)
+    }
+
+    fn issue_server_config(&self, host: &str) -> anyhow::Result<rustls::ServerConfig> {
+        let mut host_params = CertificateParams::new(vec![host.to_string()])?;
+        host_params
+            .distinguished_name
+            .push(DnType::CommonName, host);
+        let key_pair = KeyPair::generate()?;
+        let host_cert = host_params.signed_by(&key_pair, &self.issuer)?;
+        let private_key =
+            PrivateKeyDer::try_from(key_pair.serialize_der()).map_err(anyhow::Error::msg)?;
+        let mut server_config = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![host_cert.der().clone(), self.ca_cert_der.clone()],
+                private_key,
+            )?;
+        // Intercepted tunnels are served over HTTP/1.1 only.
+        server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(server_config)
+    }
+}
+
+/// Write a file only its owner can read.
+fn write_private_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let mut open_options = fs::OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut open_options, 0o600);
+    open_options.open(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_server_config_reuses_the_issued_config() {
+        let (certificate_authority, ca_cert_pem) = CertificateAuthority::generate().unwrap();
+        assert!(ca_cert_pem.starts_with("-----BEGIN CERTIFICATE-----"));
+        let server_config = certificate_authority
+            .get_server_config("api.anthropic.com")
+            .unwrap();
+        assert_eq!(server_config.alpn_protocols, vec![b"http/1.1".to_vec()]);
+        let reused_config = certificate_authority
+            .get_server_config("api.anthropic.com")
+            .unwrap();
+        assert!(Arc::ptr_eq(&server_config, &reused_config));
+    }
+}
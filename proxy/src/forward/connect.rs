@@ -0,0 +1,136 @@
+//! Reading the CONNECT request that opens a forward proxy tunnel.
+
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+/// Largest request head accepted before the tunnel is refused.
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+
+/// The `host:port` a CONNECT request asks to be tunnelled to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectTarget {
+    pub host: String,
+    pub port: u16,
+}
+
+impl ConnectTarget {
+    /// `host:port`, or just `host` for the default HTTPS port.
+    pub fn display_authority(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == 443 {
+            host
+        } else {
+            format!("{}:{}", host, self.port)
+        }
+    }
+}
+
+/// Read the request head up to the blank line. Returns the head and any
+/// bytes the client sent after it.
+pub async fn read_request_head(tcp_stream: &mut TcpStream) -> anyhow::Result<(String, Vec<u8>)> {
+    let mut received = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        if let Some(head_end) = find_head_end(&received) {
+            let leftover = received.split_off(head_end);
+            return Ok((String::from_utf8_lossy(&received).into_owned(), leftover));
+        }
+        anyhow::ensure!(
+            received.len() < MAX_REQUEST_HEAD_BYTES,
+            "request head is over {} bytes",
+            MAX_REQUEST_HEAD_BYTES
+        );
+        let read_count = tcp_stream.read(&mut chunk).await?;
+        anyhow::ensure!(
+            read_count > 0,
+            "connection closed before the request head ended"
+        );
+        received.extend_from_slice(&chunk[..read_count]);
+    }
+}
+
+/// Offset just past the `\r\n\r\n` ending the request head.
+fn find_head_end(received: &[u8]) -> Option<usize> {
+    received
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|position| position + 4)
+}
+
+/// The tunnel target of a CONNECT request head, or why it can't be served.
+pub fn parse_connect_target(request_head: &str) -> Result<ConnectTarget, String> {
+    let request_line = request_head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    if method != "CONNECT" {
+        return Err(format!(
+            "{} is not supported; only CONNECT tunnels (HTTPS_PROXY) are",
+            method
+        ));
+    }
+    let authority = parts
+        .next()
+        .ok_or_else(|| "CONNECT request has no target".to_string())?;
+    let (host, port) = authority
+        .rsplit_once(':')
+        .ok_or_else(|| format!("CONNECT target {} has no port", authority))?;
+    let port = port
+        .parse()
+        .map_err(|_| format!("CONNECT target {} has an invalid port", authority))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Err(format!("CONNECT target {} has no host", authority));
+    }
+    Ok(ConnectTarget {
+        host: host.to_ascii_lowercase(),
+        port,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_connect_target_reads_host_and_port() {
+        let connect_target = parse_connect_target(
+            "CONNECT API.anthropic.com:443 HTTP/1.1\r\nHost: api.anthropic.com:443\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(
+            connect_target,
+            ConnectTarget {
+                host: "api.anthropic.com".to_string(),
+                port: 443,
+            }
+        );
+        assert_eq!(connect_target.display_authority(), "api.anthropic.com");
+        let ipv6_target = parse_connect_target("CONNECT [::1]:8443 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(ipv6_target.host, "::1");
+        assert_eq!(ipv6_target.display_authority(), "[::1]:8443");
+    }
+
+    #[test]
+    fn parse_connect_target_rejects_other_methods() {
+        assert!(
+            parse_connect_target("GET http://example.com/ HTTP/1.1\r\n\r\n")
+                .unwrap_err()
+                .contains("only CONNECT")
+        );
+        assert!(parse_connect_target("CONNECT example.com HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse_connect_target("CONNECT example.com:https HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn find_head_end_stops_at_the_blank_line() {
+        assert_eq!(
+            find_head_end(b"CONNECT a:1 HTTP/1.1\r\n\r\n\x16\x03"),
+            Some(24)
+        );
+        assert_eq!(find_head_end(b"CONNECT a:1 HTTP/1.1\r\n"), None);
+    }
+}
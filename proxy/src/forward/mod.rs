@@ -0,0 +1,309 @@
+//! HTTP forward proxy for tools that honor `HTTPS_PROXY` but can't change
+//! their base URL. CONNECT tunnels to the configured hosts are decrypted with
+//! a locally generated CA and each request is replayed through the session's
+//! `/_proxy/` route, so it's recorded like any other proxied request. Tunnels
+//! to other hosts are passed through untouched, unless they resolve to a
+//! private or loopback address.
+
+mod ca;
+mod connect;
+mod relay;
+
+use common::config::AppConfig;
+use sqlx::SqlitePool;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+use self::ca::CertificateAuthority;
+use self::connect::{parse_connect_target, read_request_head, ConnectTarget};
+use self::relay::Relay;
+use crate::webfetch::is_private_address;
+
+/// Sessions recording intercepted traffic are named this plus the host.
+pub const FORWARD_PROXY_SESSION_PREFIX: &str = "Forward proxy: ";
+
+pub struct ForwardProxy {
+    pool: SqlitePool,
+    mitm_hosts: Vec<String>,
+    certificate_authority: CertificateAuthority,
+    relay: Relay,
+    /// Held while finding or creating a host's session, so concurrent
+    /// tunnels to a new host don't create two.
+    session_lock: tokio::sync::Mutex<()>,
+}
+
+impl ForwardProxy {
+    /// Load or generate the CA and point the relay at the proxy listener.
+    pub fn new(pool: SqlitePool, config: &AppConfig) -> anyhow::Result<Self> {
+        let ca_cert_path = Path::new(&config.forward_proxy_ca_cert_path);
+        let (certificate_authority, generated) = CertificateAuthority::load_or_generate(
+            ca_cert_path,
+            Path::new(&config.forward_proxy_ca_key_path),
+        )?;
+        if generated {
+            log::info!(
+                "Generated the forward proxy CA at {}; clients must trust it",
+                ca_cert_path.display()
+            );
+        }
+        // The relay only talks to this process, which may serve a
+        // self-signed certificate, and must not loop through HTTPS_PROXY.
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .danger_accept_invalid_certs(true)
+            .no_proxy()
+            .build()?;
+        Ok(ForwardProxy {
+            pool,
+            mitm_hosts: config
+                .forward_proxy_mitm_hosts
+                .iter()
+                .map(|mitm_host| mitm_host.to_ascii_lowercase())
+                .collect(),
+            certificate_authority,
+            relay: Relay {
+                proxy_base_url: build_proxy_base_url(config),
+                client,
+            },
+            session_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    fn is_mitm_host(&self, host: &str) -> bool {
+        self.mitm_hosts.iter().any(|mitm_host| mitm_host == host)
+    }
+
+    /// The id of the session recording `connect_target`'s traffic, created
+    /// on the first tunnel to it.
+    async fn resolve_host_session(&self, connect_target: &ConnectTarget) -> anyhow::Result<String> {
+        let _session_guard = self.session_lock.lock().await;
+        let session_name = format!(
+            "{}{}",
+            FORWARD_PROXY_SESSION_PREFIX,
+            connect_target.display_authority()
+        );
+        if let Some(session) = db::find_session_by_name(&self.pool, &session_name).await? {
+            return Ok(session.id.to_string());
+        }
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let target_url = format!("https://{}", connect_target.display_authority());
+        db::create_session(
+            &self.pool,
+            &db::SessionParams {
+                id: &session_id,
+                name: &session_name,
+                target_url: &target_url,
+                tls_verify_disabled: false,
+                auth_header: None,
+                x_api_key: None,
+                profile_id: None,
+                aws_region: None,
+                aws_access_key_id: None,
+                aws_secret_access_key: None,
+                aws_session_token: None,
+                upstream_preset: None,
+                max_concurrent_requests: None,
+                concurrency_overflow: None,
+                max_body_bytes: None,
+                forward_header_allow: None,
+                forward_header_deny: None,
+                response_header_allow: None,
+                response_header_deny: None,
+                auth_query_params: None,
+                header_preset: None,
+                beta_inject: None,
+                beta_strip: None,
+                completion_webhook_url: None,
+                hostname: None,
+            },
+        )
+        .await?;
+        log::info!(
+            "Forward proxy created session {} for {}",
+            session_id,
+            target_url
+        );
+        Ok(session_id)
+    }
+}
+
+/// Origin of the proxy listener as reached from this machine.
+fn build_proxy_base_url(config: &AppConfig) -> String {
+    match config.bind_address.as_str() {
+        "0.0.0.0" | "::" => config.proxy_origin(),
+        bind_address => {
+            let scheme = if config.tls_enabled() {
+                "https"
+            } else {
+                "http"
+            };
+            format!("{}://{}:{}", scheme, bind_address, config.port)
+        }
+    }
+}
+
+/// Accept forward proxy connections until the listener fails.
+pub async fn run_forward_proxy(
+    forward_proxy: ForwardProxy,
+    std_listener: std::net::TcpListener,
+) -> std::io::Result<()> {
+    std_listener.set_nonblocking(true)?;
+    let listener = tokio::net::TcpListener::from_std(std_listener)?;
+    let forward_proxy = Arc::new(forward_proxy);
+    loop {
+        let (tcp_stream, peer_addr) = listener.accept().await?;
+        let forward_proxy = forward_proxy.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(&forward_proxy, tcp_stream).await {
+                log::debug!("Forward proxy connection from {} ended: {}", peer_addr, e);
+            }
+        });
+    }
+}
+
+async fn handle_connection(
+    forward_proxy: &ForwardProxy,
+    mut tcp_stream: TcpStream,
+) -> anyhow::Result<()> {
+    let (request_head, leftover) = read_request_head(&mut tcp_stream).await?;
+    let connect_target = match parse_connect_target(&request_head) {
+        Ok(connect_target) => connect_target,
+        Err(reason) => {
+            write_refusal(&mut tcp_stream, "405 Method Not Allowed", &reason).await?;
+            return Ok(());
+        }
+    };
+    if forward_proxy.is_mitm_host(&connect_target.host) {
+        intercept_tunnel(forward_proxy, tcp_stream, &leftover, &connect_target).await
+    } else {
+        pass_through_tunnel(tcp_stream, &leftover, &connect_target).await
+    }
+}
+
+/// Terminate TLS with a certificate for the host and relay the requests.
+async fn intercept_tunnel(
+    forward_proxy: &ForwardProxy,
+    mut tcp_stream: TcpStream,
+    leftover: &[u8],
+    connect_target: &ConnectTarget,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        leftover.is_empty(),
+        "client sent data before the tunnel was established"
+    );
+    let session_id = match forward_proxy.resolve_host_session(connect_target).await {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            write_refusal(&mut tcp_stream, "502 Bad Gateway", &e.to_string()).await?;
+            return Err(e);
+        }
+    };
+    let server_config = forward_proxy
+        .certificate_authority
+        .get_server_config(&connect_target.host)?;
+    tcp_stream
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await?;
+    let tls_stream = tokio_rustls::TlsAcceptor::from(server_config)
+        .accept(tcp_stream)
+        .await?;
+    forward_proxy
+        .relay
+        .serve_tunnel(tls_stream, &session_id)
+        .await
+}
+
+/// Resolve a pass-through target, refusing it when any of its addresses is
+/// private or loopback so the proxy can't be used to reach this machine or
+/// its network.
+async fn resolve_public_addresses(
+    connect_target: &ConnectTarget,
+) -> Result<Vec<SocketAddr>, String> {
+    let addresses: Vec<SocketAddr> =
+        tokio::net::lookup_host((connect_target.host.as_str(), connect_target.port))
+            .await
+            .map_err(|e| format!("failed to resolve host '{}': {}", connect_target.host, e))?
+            .collect();
+    if let Some(address) = addresses
+        .iter()
+        .find(|address| is_private_address(address.ip()))
+    {
+        return Err(format!(
+            "host '{}' resolves to private address {}",
+            connect_target.host,
+            address.ip()
+        ));
+    }
+    Ok(addresses)
+}
+
+/// Connect to the target's resolved public addresses and copy bytes both
+/// ways without inspecting them.
+async fn pass_through_tunnel(
+    mut tcp_stream: TcpStream,
+    leftover: &[u8],
+    connect_target: &ConnectTarget,
+) -> anyhow::Result<()> {
+    let upstream_addresses = match resolve_public_addresses(connect_target).await {
+        Ok(upstream_addresses) => upstream_addresses,
+        Err(reason) => {
+            write_refusal(&mut tcp_stream, "403 Forbidden", &reason).await?;
+            anyhow::bail!(reason);
+        }
+    };
+    let mut upstream_stream = match TcpStream::connect(upstream_addresses.as_slice()).await {
+        Ok(upstream_stream) => upstream_stream,
+        Err(e) => {
+            write_refusal(&mut tcp_stream, "502 Bad Gateway", &e.to_string()).await?;
+            return Err(e.into());
+        }
+    };
+    tcp_stream
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await?;
+    upstream_stream.write_all(leftover).await?;
+    tokio::io::copy_bidirectional(&mut tcp_stream, &mut upstream_stream).await?;
+    Ok(())
+}
+
+async fn write_refusal(
+    tcp_stream: &mut TcpStream,
+    status: &str,
+    reason: &str,
+) -> anyhow::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason.len(),
+        reason
+    );
+    tcp_stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_connect_target(host: &str) -> ConnectTarget {
+        ConnectTarget {
+            host: host.to_string(),
+            port: 443,
+        }
+    }
+
+    #[tokio::test]
+    async fn pass_through_refuses_private_targets() {
+        for host in ["127.0.0.1", "10.0.0.1", "::1", "169.254.169.254"] {
+            let result = resolve_public_addresses(&build_connect_target(host)).await;
+            assert!(result.unwrap_err().contains("private address"), "{}", host);
+        }
+        let addresses = resolve_public_addresses(&build_connect_target("93.184.216.34"))
+            .await
+            .unwrap();
+        assert_eq!(addresses, ["93.184.216.34:443".parse().unwrap()]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Serving a decrypted tunnel: each request is replayed against the proxy's
+//! own `/_proxy/{session}` route and the response streamed back.
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::header::HeaderName;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// Headers that describe one hop and aren't relayed.
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "proxy-connection",
+    "proxy-authorization",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "host",
+];
+
+type RelayBody = UnsyncBoxBody<Bytes, anyhow::Error>;
+
+/// Where decrypted requests are replayed.
+#[derive(Clone)]
+pub struct Relay {
+    /// Origin of the proxy's own listener, e.g. `http://127.0.0.1:8081`.
+    pub proxy_base_url: String,
+    pub client: reqwest::Client,
+}
+
+impl Relay {
+    /// Serve HTTP/1.1 requests from a decrypted tunnel until the client
+    /// closes it, recording them into `session_id`.
+    pub async fn serve_tunnel<S>(&self, tunnel_stream: S, session_id: &str) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let relay = self.clone();
+        let session_id = session_id.to_string();
+        let service = hyper::service::service_fn(move |request| {
+            let relay = relay.clone();
+            let session_id = session_id.clone();
+            async move { relay.forward_request(request, &session_id).await }
+        });
+        hyper::server::conn::http1::Builder::new()
+            .serve_connection(TokioIo::new(tunnel_stream), service)
+            .await?;
+        Ok(())
+    }
+
+    async fn forward_request(
+        &self,
+        request: Request<Incoming>,
+        session_id: &str,
+    ) -> Result<Response<RelayBody>, Infallible> {
+        match self.send_to_proxy(request, session_id).await {
+            Ok(response) => Ok(response),
+            Err(e) => {
+                log::warn!("Forward proxy relay failed: {}", e);
+                Ok(build_error_response(&e))
+            }
+        }
+    }
+
+    async fn send_to_proxy(
+        &self,
+        request: Request<Incoming>,
+        session_id: &str,
+    ) -> anyhow::Result<Response<RelayBody>> {
+        let (request_parts, incoming_body) = request.into_parts();
+        let request_body = incoming_body.collect().await?.to_bytes();
+        let path_and_query = request_parts
+            .uri
+            .path_and_query()
+            .map(|path_and_query| path_and_query.as_str())
+            .unwrap_or("/");
+        let proxy_url = format!(
+            "{}/_proxy/{}{}",
+            self.proxy_base_url, session_id, path_and_query
+        );
+        let mut request_builder = self
+            .client
+            .request(request_parts.method, proxy_url)
+            .body(request_body);
+        for (name, value) in request_parts.headers.iter() {
+            if !is_hop_by_hop(name) {
+                request_builder = request_builder.header(name, value);
+            }
+        }
+        let proxy_response = request_builder.send().await?;
+
+        let mut response_builder = Response::builder().status(proxy_response.status());
+        for (name, value) in proxy_response.headers() {
+            if !is_hop_by_hop(name) {
+                response_builder = response_builder.header(name, value);
+            }
+        }
+        let body_stream = proxy_response
+            .bytes_stream()
+            .map_ok(Frame::data)
+            .map_err(anyhow::Error::from);
+        Ok(response_builder.body(BodyExt::boxed_unsync(StreamBody::new(body_stream)))?)
+    }
+}
+
+fn is_hop_by_hop(name: &HeaderName) -> bool {
+    HOP_BY_HOP_HEADERS.contains(&name.as_str())
+}
+
+fn build_error_response(e: &anyhow::Error) -> Response<RelayBody> {
+    let error_body = Full::new(Bytes::from(format!("Forward proxy relay failed: {}", e)))
+        .map_err(|never| match never {})
+        .boxed_unsync();
+    let mut response = Response::new(error_body);
+    *response.status_mut() = StatusCode::BAD_GATEWAY;
+    response
+}
@@ -0,0 +1,536 @@
+//! Extension points around a proxied request. A `ProxyHook` can rewrite the
+//! body before it is forwarded, answer a buffered response in place of the
+//! upstream (as WebFetch interception does), and observe the stored result.
+//! Hooks are registered on a `HookRegistry` at startup and run in
+//! registration order; the filter profile, breakpoints, WebFetch
+//! interception and the completion webhook ship as built-in hooks.
+
+use actix_web::rt::task::JoinHandle;
+use actix_web::web;
+use async_trait::async_trait;
+use common::config::AppConfig;
+use common::models::{ProxyRequest, Session};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::breakpoint::{self, BreakpointQueue};
+use crate::collect_webfetch_names;
+use crate::filter;
+use crate::notify::send_completion_webhook;
+use crate::shared::{load_filters_for_profile, store_applied_filters};
+use crate::webfetch::{self, ApprovalQueue, InterceptResult};
+
+/// The request a hook runs for.
+pub struct HookContext<'a> {
+    pub pool: &'a SqlitePool,
+    /// Settings as they were when the request arrived.
+    pub config: &'a AppConfig,
+    pub session: &'a Session,
+    pub request_id: &'a str,
+    /// Whether the request matches the session's intercept rules. WebFetch
+    /// interception and breakpoints only engage when it does.
+    pub intercept_rules_match: bool,
+}
+
+/// A Messages API request body about to be forwarded, for `on_request`
+/// hooks to change.
+pub struct OutgoingRequest {
+    pub body: Value,
+    /// Set by a hook that changed `body`, so it is serialized again before
+    /// forwarding; otherwise the client's bytes are forwarded as sent.
+    pub body_changed: bool,
+    /// Tool renames made in the body, `(original, renamed)`, reversed in the
+    /// response before the client sees it.
+    pub tool_name_overrides: Vec<(String, String)>,
+    /// Set by a hook to refuse the request with this message; nothing is
+    /// forwarded and later hooks don't run.
+    pub rejection: Option<String>,
+}
+
+/// An upstream response buffered for `on_intercept` hooks, with what is
+/// needed to send follow-up requests to the same upstream.
+pub struct BufferedResponse<'a> {
+    pub response_body: &'a str,
+    /// The request body as forwarded upstream.
+    pub original_body: &'a [u8],
+    pub target_url: &'a str,
+    pub forward_headers: &'a reqwest::header::HeaderMap,
+    pub client: &'a reqwest::Client,
+    pub stored_path: &'a str,
+}
+
+/// Custom behavior around proxied requests. Every method defaults to doing
+/// nothing, so a hook only implements the stages it cares about.
+#[async_trait(?Send)]
+pub trait ProxyHook: Send + Sync {
+    /// Name used in logs.
+    fn name(&self) -> &'static str;
+
+    /// Change the request body before it is forwarded.
+    async fn on_request(&self, _context: &HookContext<'_>, _request: &mut OutgoingRequest) {}
+
+    /// Whether `on_intercept` may answer this request. The upstream response
+    /// is only buffered, rather than streamed, when some hook wants it.
+    fn wants_intercept(&self, _context: &HookContext<'_>) -> bool {
+        false
+    }
+
+    /// Answer a buffered upstream response with a different one, e.g. after
+    /// running tool calls and sending a follow-up request. The first hook
+    /// returning `Some` wins.
+    async fn on_intercept(
+        &self,
+        _context: &HookContext<'_>,
+        _response: &BufferedResponse<'_>,
+    ) -> Option<InterceptResult> {
+        None
+    }
+
+    /// Whether `on_response` should run for this request. The stored row is
+    /// only flushed and loaded when some hook wants it.
+    fn wants_response(&self, _context: &HookContext<'_>) -> bool {
+        false
+    }
+
+    /// Observe the request once its response, or its failure, is stored.
+    /// Runs in the background after the client has been answered, and only
+    /// when `wants_response` returned true.
+    async fn on_response(&self, _context: &HookContext<'_>, _request: &ProxyRequest) {}
+}
+
+/// The hooks run on every proxied request, in registration order.
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: Vec<Box<dyn ProxyHook>>,
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the built-in hooks: the filter profile, WebFetch
+    /// interception and the completion webhook.
+    pub fn with_builtin_hooks(
+        approval_queue: &web::Data<ApprovalQueue>,
+        breakpoint_queue: &web::Data<BreakpointQueue>,
+        client: &reqwest::Client,
+    ) -> Self {
+        let mut hook_registry = Self::new();
+        hook_registry.register(FilterHook);
+        hook_registry.register(BreakpointHook {
+            breakpoint_queue: breakpoint_queue.clone(),
+        });
+        hook_registry.register(WebfetchHook {
+            approval_queue: approval_queue.clone(),
+        });
+        hook_registry.register(CompletionWebhookHook {
+            client: client.clone(),
+        });
+        hook_registry
+    }
+
+    /// Add a hook, run after those already registered.
+    pub fn register(&mut self, hook: impl ProxyHook + 'static) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    pub async fn run_request_hooks(
+        &self,
+        context: &HookContext<'_>,
+        request: &mut OutgoingRequest,
+    ) {
+        for hook in &self.hooks {
+            if request.rejection.is_some() {
+                break;
+            }
+            hook.on_request(context, request).await;
+        }
+    }
+
+    pub fn wants_intercept(&self, context: &HookContext<'_>) -> bool {
+        self.hooks.iter().any(|hook| hook.wants_intercept(context))
+    }
+
+    pub async fn run_intercept_hooks(
+        &self,
+        context: &HookContext<'_>,
+        response: &BufferedResponse<'_>,
+    ) -> Option<InterceptResult> {
+        for hook in &self.hooks {
+            if !hook.wants_intercept(context) {
+                continue;
+            }
+            if let Some(intercept_result) = hook.on_intercept(context, response).await {
+                log::debug!("hooks: {} answered {}", hook.name(), context.request_id);
+                return Some(intercept_result);
+            }
+        }
+        None
+    }
+
+    pub fn wants_response(&self, context: &HookContext<'_>) -> bool {
+        self.hooks.iter().any(|hook| hook.wants_response(context))
+    }
+
+    /// Run the response hooks that want the stored request, once its pending
+    /// writes have landed. Requests without a response, e.g. when the client
+    /// went away mid-stream, are skipped.
+    pub async fn run_response_hooks(&self, context: &HookContext<'_>) -> anyhow::Result<()> {
+        if !self.wants_response(context) {
+            return Ok(());
+        }
+        db::flush_request_writes().await;
+        let Some(request) = db::get_request(context.pool, context.request_id).await? else {
+            return Ok(());
+        };
+        if request.response_status.is_none() && request.error_kind.is_none() {
+            return Ok(());
+        }
+        for hook in &self.hooks {
+            if hook.wants_response(context) {
+                hook.on_response(context, &request).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run the response hooks in the background once `finished` resolves.
+fn spawn_response_hooks(
+    hook_registry: &web::Data<HookRegistry>,
+    context: &HookContext<'_>,
+    finished: impl Future<Output = ()> + 'static,
+) {
+    if !hook_registry.wants_response(context) {
+        return;
+    }
+    let hook_registry = hook_registry.clone();
+    let pool = context.pool.clone();
+    let config = context.config.clone();
+    let session = context.session.clone();
+    let request_id = context.request_id.to_string();
+    let intercept_rules_match = context.intercept_rules_match;
+    actix_web::rt::spawn(async move {
+        finished.await;
+        let context = HookContext {
+            pool: &pool,
+            config: &config,
+            session: &session,
+            request_id: &request_id,
+            intercept_rules_match,
+        };
+        if let Err(e) = hook_registry.run_response_hooks(&context).await {
+            log::warn!("hooks: response hooks for {} failed: {}", request_id, e);
+        }
+    });
+}
+
+/// Run the response hooks in the background. Call once the response, or
+/// the failure, has been stored.
+pub(crate) fn notify_request_completed(
+    hook_registry: &web::Data<HookRegistry>,
+    context: &HookContext<'_>,
+) {
+    spawn_response_hooks(hook_registry, context, std::future::ready(()));
+}
+
+/// Like `notify_request_completed`, for a streamed response stored by
+/// `stream_task` once the stream ends.
+pub(crate) fn notify_request_streamed(
+    hook_registry: &web::Data<HookRegistry>,
+    context: &HookContext<'_>,
+    stream_task: JoinHandle<()>,
+) {
+    spawn_response_hooks(hook_registry, context, async move {
+        let _ = stream_task.await;
+    });
+}
+
+/// Applies the session's filter profile: removes filtered system blocks and
+/// tools, renames overridden tools, and records what changed.
+pub struct FilterHook;
+
+#[async_trait(?Send)]
+impl ProxyHook for FilterHook {
+    fn name(&self) -> &'static str {
+        "filter"
+    }
+
+    async fn on_request(&self, context: &HookContext<'_>, request: &mut OutgoingRequest) {
+        let Some(filters) =
+            load_filters_for_profile(context.pool, context.session.profile_id.as_deref()).await
+        else {
+            return;
+        };
+        let mut applied_filters = filter::apply_filters(
+            &mut request.body,
+            &filters.system_filters,
+            &filters.tool_filters,
+            filters.keep_tool_pairs,
+        );
+        applied_filters.extend(filter::apply_tool_name_overrides(
+            &mut request.body,
+            &filters.tool_name_overrides,
+        ));
+        request.body_changed |= !applied_filters.is_empty();
+        store_applied_filters(context.pool, context.request_id, &applied_filters).await;
+        request
+            .tool_name_overrides
+            .extend(filters.tool_name_overrides);
+    }
+}
+
+/// Runs the session's WebFetch tool calls, waiting for approval when
+/// needed, and answers with the follow-up response.
+pub struct WebfetchHook {
+    pub approval_queue: web::Data<ApprovalQueue>,
+}
+
+#[async_trait(?Send)]
+impl ProxyHook for WebfetchHook {
+    fn name(&self) -> &'static str {
+        "webfetch"
+    }
+
+    fn wants_intercept(&self, context: &HookContext<'_>) -> bool {
+        let config = context.config;
+        context.intercept_rules_match
+            && !collect_webfetch_names(
+                context.session,
+                &config.webfetch_tool_names,
+                &config.tool_executors,
+            )
+            .is_empty()
+    }
+
+    /// Load the session's whitelist, deny rules and policies and run WebFetch
+    /// interception on the buffered response.
+    async fn on_intercept(
+        &self,
+        context: &HookContext<'_>,
+        response: &BufferedResponse<'_>,
+    ) -> Option<InterceptResult> {
+        let (pool, config, session) = (context.pool, context.config, context.session);
+        let webfetch_names =
+            collect_webfetch_names(session, &config.webfetch_tool_names, &config.tool_executors);
+        let session_id = session.id.to_string();
+        let whitelist = db::list_webfetch_whitelist_rules(pool, &session_id)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("webfetch: failed to load whitelist rules: {}", e);
+                vec![]
+            });
+        let deny_rules = db::list_webfetch_deny_rules(pool, &session_id)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("webfetch: failed to load deny rules: {}", e);
+                vec![]
+            });
+        let policies = db::list_webfetch_policies(pool, &session_id)
+            .await
+            .unwrap_or_else(|e| {
+                log::warn!("webfetch: failed to load policies: {}", e);
+                vec![]
+            });
+
+        webfetch::maybe_intercept(&webfetch::InterceptParams {
+            response_body: response.response_body,
+            original_body: response.original_body,
+            target_url: response.target_url,
+            forward_headers: response.forward_headers,
+            client: response.client,
+            approval_queue: self.approval_queue.get_ref(),
+            session_id: &session_id,
+            request_id: context.request_id,
+            whitelist: &whitelist,
+            deny_rules: &deny_rules,
+            policies: &policies,
+            allow_private_targets: session.webfetch_allow_private_targets,
+            max_content_bytes: session
+                .webfetch_max_content_bytes
+                .map(|max_content_bytes| max_content_bytes as usize)
+                .unwrap_or(config.webfetch_max_content_bytes),
+            fetch_timeout: Duration::from_secs(
+                session
+                    .webfetch_fetch_timeout_secs
+                    .map(|timeout_secs| timeout_secs as u64)
+                    .unwrap_or(config.webfetch_fetch_timeout_secs),
+            ),
+            extraction_mode: &session.webfetch_extraction_mode,
+            respect_robots_txt: session.webfetch_respect_robots_txt,
+            raw_mode: session.webfetch_raw_mode,
+            mcp_server: webfetch::McpServer::from_session(session),
+            agent_backend: webfetch::AgentBackend::from_session(session),
+            pool,
+            stored_path: response.stored_path,
+            webfetch_names: &webfetch_names,
+            config,
+        })
+        .await
+    }
+}
+
+/// Holds the requests of sessions in breakpoint mode until they are
+/// forwarded or rejected on the dashboard.
+pub struct BreakpointHook {
+    pub breakpoint_queue: web::Data<BreakpointQueue>,
+}
+
+#[async_trait(?Send)]
+impl ProxyHook for BreakpointHook {
+    fn name(&self) -> &'static str {
+        "breakpoint"
+    }
+
+    async fn on_request(&self, context: &HookContext<'_>, request: &mut OutgoingRequest) {
+        if context.intercept_rules_match && context.session.breakpoint_enabled {
+            breakpoint::hold_request(&self.breakpoint_queue, context, request).await;
+        }
+    }
+}
+
+/// Posts a summary of each finished request to its session's completion
+/// webhook, if it has one.
+pub struct CompletionWebhookHook {
+    pub client: reqwest::Client,
+}
+
+#[async_trait(?Send)]
+impl ProxyHook for CompletionWebhookHook {
+    fn name(&self) -> &'static str {
+        "completion_webhook"
+    }
+
+    fn wants_response(&self, context: &HookContext<'_>) -> bool {
+        context.session.completion_webhook_url.is_some()
+    }
+
+    async fn on_response(&self, context: &HookContext<'_>, request: &ProxyRequest) {
+        let Some(ref webhook_url) = context.session.completion_webhook_url else {
+            return;
+        };
+        if let Err(e) =
+            send_completion_webhook(context.pool, &self.client, webhook_url, request).await
+        {
+            log::warn!(
+                "notify: completion webhook for {} failed: {}",
+                context.request_id,
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::make_session;
+
+    /// Appends its tag to the body's `tags` array.
+    struct TagHook(&'static str);
+
+    #[async_trait(?Send)]
+    impl ProxyHook for TagHook {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn on_request(&self, _context: &HookContext<'_>, request: &mut OutgoingRequest) {
+            request.body["tags"]
+                .as_array_mut()
+                .unwrap()
+                .push(Value::from(self.0));
+            request.body_changed = true;
+        }
+    }
+
+    #[tokio::test]
+    async fn run_request_hooks_in_registration_order() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let config = AppConfig::default();
+        let session = make_session(false);
+        let context = HookContext {
+            pool: &pool,
+            config: &config,
+            session: &session,
+            request_id: "r",
+            intercept_rules_match: true,
+        };
+        let mut hook_registry = HookRegistry::new();
+        hook_registry.register(TagHook("first"));
+        hook_registry.register(TagHook("second"));
+        let mut request = OutgoingRequest {
+            body: serde_json::json!({"tags": []}),
+            body_changed: false,
+            tool_name_overrides: vec![],
+            rejection: None,
+        };
+        hook_registry
+            .run_request_hooks(&context, &mut request)
+            .await;
+        assert_eq!(request.body["tags"], serde_json::json!(["first", "second"]));
+        assert!(request.body_changed);
+        assert!(!hook_registry.wants_intercept(&context));
+    }
+
+    #[tokio::test]
+    async fn webfetch_hook_wants_intercept_when_enabled() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let config = AppConfig::default();
+        let hook_registry = HookRegistry::with_builtin_hooks(
+            &web::Data::new(webfetch::new_approval_queue()),
+            &web::Data::new(breakpoint::new_breakpoint_queue()),
+            &reqwest::Client::new(),
+        );
+        for intercept in [false, true] {
+            let session = make_session(intercept);
+            let context = HookContext {
+                pool: &pool,
+                config: &config,
+                session: &session,
+                request_id: "r",
+                intercept_rules_match: true,
+            };
+            assert_eq!(hook_registry.wants_intercept(&context), intercept);
+        }
+    }
+
+    #[tokio::test]
+    async fn response_hooks_only_wanted_with_a_completion_webhook() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let config = AppConfig::default();
+        let hook_registry = HookRegistry::with_builtin_hooks(
+            &web::Data::new(webfetch::new_approval_queue()),
+            &web::Data::new(breakpoint::new_breakpoint_queue()),
+            &reqwest::Client::new(),
+        );
+        let mut session = make_session(false);
+        for webhook_url in [None, Some("http://127.0.0.1:1/done".to_string())] {
+            session.completion_webhook_url = webhook_url.clone();
+            let context = HookContext {
+                pool: &pool,
+                config: &config,
+                session: &session,
+                request_id: "r",
+                intercept_rules_match: true,
+            };
+            assert_eq!(
+                hook_registry.wants_response(&context),
+                webhook_url.is_some()
+            );
+        }
+        // Nothing wants the row, so the unmigrated pool is never queried.
+        session.completion_webhook_url = None;
+        let context = HookContext {
+            pool: &pool,
+            config: &config,
+            session: &session,
+            request_id: "r",
+            intercept_rules_match: true,
+        };
+        hook_registry.run_response_hooks(&context).await.unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+//! Routing proxied requests by virtual hostname, e.g. `work.proxy.localhost`,
+//! so the path a client sends is forwarded as is instead of being prefixed
+//! with `/_proxy/{session_id}/`.
+
+use std::net::IpAddr;
+
+/// Longest DNS label.
+const MAX_LABEL_LEN: usize = 63;
+
+/// Validate a session hostname from the dashboard form, lowercased and
+/// without a trailing dot. `*.localhost` names need no DNS setup.
+pub fn parse_session_hostname(value: &str) -> Result<String, String> {
+    let hostname = value.trim().trim_end_matches('.').to_ascii_lowercase();
+    if hostname == "localhost" || hostname.parse::<IpAddr>().is_ok() {
+        return Err("must be a name other than localhost or an IP address".to_string());
+    }
+    if !hostname.contains('.') {
+        return Err("must have at least two labels, e.g. work.proxy.localhost".to_string());
+    }
+    for label in hostname.split('.') {
+        let is_valid_label = !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-');
+        if !is_valid_label {
+            return Err(format!("{:?} is not a valid hostname label", label));
+        }
+    }
+    Ok(hostname)
+}
+
+/// The hostname of a `Host` header value, lowercased and without the port,
+/// or `None` when it can't name a session: localhost and IP addresses.
+pub fn find_request_hostname(host: &str) -> Option<String> {
+    if host.starts_with('[') {
+        return None;
+    }
+    let hostname = host
+        .rsplit_once(':')
+        .map_or(host, |(hostname, _)| hostname)
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    if hostname.is_empty() || hostname == "localhost" || hostname.parse::<IpAddr>().is_ok() {
+        return None;
+    }
+    Some(hostname)
+}
+
+/// The `/_proxy/` route a request to a session's hostname is handled by.
+pub fn build_hostname_route_path(session_id: &str, path_and_query: &str) -> String {
+    format!("/_proxy/{}{}", session_id, path_and_query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_session_hostname_normalizes_and_rejects_invalid_names() {
+        assert_eq!(
+            parse_session_hostname(" Work.Proxy.localhost. "),
+            Ok("work.proxy.localhost".to_string())
+        );
+        assert!(parse_session_hostname("localhost").is_err());
+        assert!(parse_session_hostname("127.0.0.1").is_err());
+        assert!(parse_session_hostname("work").is_err());
+        assert!(parse_session_hostname("work..localhost").is_err());
+        assert!(parse_session_hostname("-work.localhost").is_err());
+        assert!(parse_session_hostname("work_1.localhost").is_err());
+    }
+
+    #[test]
+    fn find_request_hostname_strips_the_port() {
+        assert_eq!(
+            find_request_hostname("Work.proxy.localhost:8081"),
+            Some("work.proxy.localhost".to_string())
+        );
+        assert_eq!(
+            find_request_hostname("work.proxy.localhost"),
+            Some("work.proxy.localhost".to_string())
+        );
+        assert_eq!(find_request_hostname("localhost:8081"), None);
+        assert_eq!(find_request_hostname("127.0.0.1:8081"), None);
+        assert_eq!(find_request_hostname("[::1]:8081"), None);
+    }
+
+    #[test]
+    fn build_hostname_route_path_keeps_the_query() {
+        assert_eq!(
+            build_hostname_route_path("abc", "/v1/messages?beta=true"),
+            "/_proxy/abc/v1/messages?beta=true"
+        );
+    }
+}
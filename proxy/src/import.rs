@@ -0,0 +1,313 @@
+//! Importing traffic captured outside the proxy into a new session, so it
+//! can be browsed on the dashboard like proxied requests. Two layouts are
+//! read:
+//!
+//! - a JSONL log with one exchange per line,
+//!   `{"request": {"method", "url", "headers", "body"}, "response":
+//!   {"status_code", "headers", "body" | "body_raw"}}`, as written by
+//!   request tracers such as claude-trace;
+//! - a directory of dumps, `NAME.request.json` with an optional
+//!   `NAME.response.json` or `NAME.response.sse` next to it. A request dump
+//!   is either the body itself or the `request` object of a log line.
+
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::path::Path;
+
+use crate::shared::{extract_request_fields, log_request, store_response, RequestMeta};
+
+/// Upstream recorded on an imported session whose captures carry no URL.
+const DEFAULT_IMPORT_TARGET_URL: &str = "https://api.anthropic.com";
+
+/// One captured request and its response, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedExchange {
+    pub method: String,
+    /// The request URL, or just its path when the capture has no host.
+    pub url: String,
+    pub request_headers: Option<Value>,
+    pub request_body: Value,
+    pub response_status: u16,
+    pub response_headers: Option<Value>,
+    /// The response body as sent: JSON, or SSE text for streamed responses.
+    pub response_body: Option<String>,
+}
+
+/// Read a request dump or the `request` object of a log line.
+fn parse_captured_request(request: &Value) -> CapturedExchange {
+    let is_wrapped = request.get("body").is_some()
+        && (request.get("url").is_some() || request.get("method").is_some());
+    let read_string = |key: &str, default: &str| {
+        request
+            .get(key)
+            .and_then(|field| field.as_str())
+            .filter(|_| is_wrapped)
+            .unwrap_or(default)
+            .to_string()
+    };
+    let request_body = if is_wrapped {
+        request["body"].clone()
+    } else {
+        request.clone()
+    };
+    CapturedExchange {
+        method: read_string("method", "POST").to_uppercase(),
+        url: read_string("url", "/v1/messages"),
+        request_headers: request.get("headers").filter(|_| is_wrapped).cloned(),
+        request_body,
+        response_status: 200,
+        response_headers: None,
+        response_body: None,
+    }
+}
+
+/// Fill in the `response` object of a log line: `body_raw` holds a streamed
+/// response's SSE text, `body` a parsed JSON one.
+fn apply_captured_response(captured_exchange: &mut CapturedExchange, response: &Value) {
+    if let Some(status) = response
+        .get("status_code")
+        .or_else(|| response.get("status"))
+        .and_then(|field| field.as_u64())
+    {
+        captured_exchange.response_status = status as u16;
+    }
+    captured_exchange.response_headers = response.get("headers").cloned();
+    captured_exchange.response_body = match (response.get("body_raw"), response.get("body")) {
+        (Some(Value::String(body_raw)), _) => Some(body_raw.clone()),
+        (_, Some(Value::String(body))) => Some(body.clone()),
+        (_, Some(body)) if !body.is_null() => Some(body.to_string()),
+        _ => None,
+    };
+}
+
+/// Parse a JSONL capture log. Blank lines are skipped; any other line that
+/// isn't an exchange is an error naming its line number.
+pub fn parse_capture_log(text: &str) -> anyhow::Result<Vec<CapturedExchange>> {
+    let mut captured_exchanges = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Value =
+            serde_json::from_str(line).map_err(|e| anyhow::anyhow!("line {}: {}", index + 1, e))?;
+        let Some(request) = entry.get("request").filter(|field| field.is_object()) else {
+            anyhow::bail!("line {}: no request object", index + 1);
+        };
+        let mut captured_exchange = parse_captured_request(request);
+        if let Some(response) = entry.get("response").filter(|field| field.is_object()) {
+            apply_captured_response(&mut captured_exchange, response);
+        }
+        captured_exchanges.push(captured_exchange);
+    }
+    Ok(captured_exchanges)
+}
+
+/// Read a directory of request and response dumps, in file name order.
+fn read_capture_dir(dir: &Path) -> anyhow::Result<Vec<CapturedExchange>> {
+    let mut request_paths: Vec<_> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    request_paths.retain(|path| path.to_string_lossy().ends_with(".request.json"));
+    request_paths.sort();
+
+    let mut captured_exchanges = Vec::new();
+    for request_path in request_paths {
+        let request_text = std::fs::read_to_string(&request_path)?;
+        let request: Value = serde_json::from_str(&request_text)
+            .map_err(|e| anyhow::anyhow!("{}: {}", request_path.display(), e))?;
+        let mut captured_exchange = parse_captured_request(&request);
+        let stem = request_path.to_string_lossy();
+        let stem = stem.trim_end_matches(".request.json");
+        for extension in ["response.json", "response.sse"] {
+            let response_path = format!("{}.{}", stem, extension);
+            if Path::new(&response_path).is_file() {
+                captured_exchange.response_body = Some(std::fs::read_to_string(response_path)?);
+                break;
+            }
+        }
+        captured_exchanges.push(captured_exchange);
+    }
+    Ok(captured_exchanges)
+}
+
+/// Read a JSONL capture log, or a directory of dumps.
+pub fn read_captured_exchanges(path: &Path) -> anyhow::Result<Vec<CapturedExchange>> {
+    if path.is_dir() {
+        read_capture_dir(path)
+    } else {
+        parse_capture_log(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// The path and query of a captured URL, and its scheme and host if it has one.
+fn split_captured_url(url: &str) -> (String, Option<String>) {
+    match url::Url::parse(url) {
+        Ok(parsed_url) if parsed_url.has_host() => {
+            let path = match parsed_url.query() {
+                Some(query) => format!("{}?{}", parsed_url.path(), query),
+                None => parsed_url.path().to_string(),
+            };
+            let origin = parsed_url.origin().ascii_serialization();
+            (path, Some(origin))
+        }
+        _ => (url.to_string(), None),
+    }
+}
+
+/// Headers as the proxy stores them: a pretty-printed object of strings.
+fn format_captured_headers(headers: Option<&Value>) -> anyhow::Result<Option<String>> {
+    let Some(Value::Object(headers)) = headers else {
+        return Ok(None);
+    };
+    let headers: serde_json::Map<String, Value> = headers
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(string) => string.clone(),
+                other => other.to_string(),
+            };
+            (name.to_lowercase(), Value::String(value))
+        })
+        .collect();
+    Ok(Some(serde_json::to_string_pretty(&headers)?))
+}
+
+async fn import_captured_exchange(
+    pool: &SqlitePool,
+    session_id: &str,
+    captured_exchange: &CapturedExchange,
+) -> anyhow::Result<()> {
+    let (path, _) = split_captured_url(&captured_exchange.url);
+    let headers_json = format_captured_headers(captured_exchange.request_headers.as_ref())?;
+    let fields = extract_request_fields(&captured_exchange.request_body, None)?;
+    let request_id = log_request(
+        &RequestMeta {
+            id: None,
+            pool,
+            session_id,
+            method: &captured_exchange.method,
+            path: &path,
+            headers_json: headers_json.as_deref(),
+            note: None,
+            client: None,
+            parent_request_id: None,
+        },
+        fields,
+    )
+    .await?;
+    if let Some(response_body) = &captured_exchange.response_body {
+        let response_headers_json =
+            format_captured_headers(captured_exchange.response_headers.as_ref())?;
+        store_response(
+            pool,
+            &request_id,
+            captured_exchange.response_status,
+            response_headers_json.as_deref(),
+            response_body,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Create a session named `session_name` holding the captured exchanges, in
+/// capture order. Its upstream is the host of the first captured URL.
+/// Returns the new session's id.
+pub async fn import_captured_exchanges(
+    pool: &SqlitePool,
+    session_name: &str,
+    captured_exchanges: &[CapturedExchange],
+) -> anyhow::Result<String> {
+    let target_url = captured_exchanges
+        .iter()
+        .find_map(|captured_exchange| split_captured_url(&captured_exchange.url).1)
+        .unwrap_or_else(|| DEFAULT_IMPORT_TARGET_URL.to_string());
+    let session_id = uuid::Uuid::new_v4().to_string();
+    db::create_session(
+        pool,
+        &db::SessionParams {
+            id: &session_id,
+            name: session_name,
+            target_url: &target_url,
+            tls_verify_disabled: false,
+            auth_header: None,
+            x_api_key: None,
+            profile_id: None,
+            aws_region: None,
+            aws_access_key_id: None,
+            aws_secret_access_key: None,
+            aws_session_token: None,
+            upstream_preset: None,
+            max_concurrent_requests: None,
+            concurrency_overflow: None,
+            max_body_bytes: None,
+            forward_header_allow: None,
+            forward_header_deny: None,
+            response_header_allow: None,
+            response_header_deny: None,
+            auth_query_params: None,
+            header_preset: None,
+            beta_inject: None,
+            beta_strip: None,
+            completion_webhook_url: None,
+            hostname: None,
+        },
+    )
+    .await?;
+    for captured_exchange in captured_exchanges {
+        import_captured_exchange(pool, &session_id, captured_exchange).await?;
+    }
+    Ok(session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_capture_log_reads_streamed_and_json_responses() {
+        let log = concat!(
+            r#"{"request": {"method": "post", "url": "https://api.anthropic.com/v1/messages?beta=true", "headers": {"Content-Type": "application/json"}, "body": {"model": "m", "messages": []}}, "response": {"status_code": 200, "body_raw": "event: message_stop\ndata: {}\n\n"}}"#,
+            "\n\n",
+            r#"{"request": {"method": "POST", "url": "https://api.anthropic.com/v1/messages/count_tokens", "body": {"model": "m"}}, "response": {"status_code": 429, "body": {"type": "error"}}}"#,
+            "\n",
+        );
+        let captured_exchanges = parse_capture_log(log).unwrap();
+        assert_eq!(captured_exchanges.len(), 2);
+        assert_eq!(captured_exchanges[0].method, "POST");
+        assert_eq!(
+            captured_exchanges[0].response_body.as_deref(),
+            Some("event: message_stop\ndata: {}\n\n")
+        );
+        assert_eq!(captured_exchanges[1].response_status, 429);
+        assert_eq!(
+            captured_exchanges[1].response_body.as_deref(),
+            Some(r#"{"type":"error"}"#)
+        );
+        assert!(parse_capture_log("{\"response\": {}}").is_err());
+    }
+
+    #[test]
+    fn parse_captured_request_accepts_bare_bodies() {
+        let captured_exchange =
+            parse_captured_request(&serde_json::json!({"model": "m", "messages": []}));
+        assert_eq!(captured_exchange.method, "POST");
+        assert_eq!(captured_exchange.url, "/v1/messages");
+        assert_eq!(captured_exchange.request_body["model"], "m");
+    }
+
+    #[test]
+    fn split_captured_url_keeps_path_and_query() {
+        assert_eq!(
+            split_captured_url("https://api.anthropic.com/v1/messages?beta=true"),
+            (
+                "/v1/messages?beta=true".to_string(),
+                Some("https://api.anthropic.com".to_string())
+            )
+        );
+        assert_eq!(
+            split_captured_url("/v1/messages"),
+            ("/v1/messages".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Intercept rules scope WebFetch interception and breakpoints to requests
+//! matching predicates on the model, path, `metadata.user_id` or the last
+//! user message.
+
+use common::intercept_rules::find_field_by_key;
+use common::models::{InterceptRule, Session};
+use regex::Regex;
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+/// The request properties intercept rules are checked against.
+pub struct InterceptSubject<'a> {
+    /// The API path after the session prefix.
+    pub path: &'a str,
+    pub model: Option<&'a str>,
+    pub body: &'a [u8],
+}
+
+/// Whether interception may engage for the request: true unless the session
+/// has intercept rules and one of them doesn't match. Rules are only loaded
+/// for sessions with WebFetch interception or breakpoints on.
+pub(crate) async fn check_intercept_rules(
+    pool: &SqlitePool,
+    session: &Session,
+    subject: &InterceptSubject<'_>,
+) -> bool {
+    if !session.webfetch_intercept && !session.breakpoint_enabled {
+        return true;
+    }
+    let rules = match db::list_intercept_rules(pool, &session.id.to_string()).await {
+        Ok(rules) => rules,
+        Err(e) => {
+            log::warn!("intercept rules: failed to load rules: {}", e);
+            return true;
+        }
+    };
+    match_intercept_rules(&rules, subject)
+}
+
+/// Whether the request matches every rule. No rules match every request.
+pub fn match_intercept_rules(rules: &[InterceptRule], subject: &InterceptSubject<'_>) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+    let body = serde_json::from_slice::<Value>(subject.body).unwrap_or_default();
+    rules
+        .iter()
+        .all(|rule| match_intercept_rule(rule, subject, &body))
+}
+
+fn match_intercept_rule(
+    rule: &InterceptRule,
+    subject: &InterceptSubject<'_>,
+    body: &Value,
+) -> bool {
+    match rule.field.as_str() {
+        "model" => subject.model == Some(rule.value.as_str()),
+        "path" => subject.path.trim_start_matches('/') == rule.value.trim_start_matches('/'),
+        "user_id" => {
+            body.pointer("/metadata/user_id").and_then(Value::as_str) == Some(rule.value.as_str())
+        }
+        "last_user_message" => {
+            let Some(last_user_text) = extract_last_user_text(body) else {
+                return false;
+            };
+            Regex::new(&rule.value).is_ok_and(|regex| regex.is_match(&last_user_text))
+        }
+        _ => false,
+    }
+}
+
+/// The text of the last user message: a string content, or its text blocks
+/// joined by newlines.
+fn extract_last_user_text(body: &Value) -> Option<String> {
+    let last_user_message = body
+        .get("messages")?
+        .as_array()?
+        .iter()
+        .rfind(|message| message.get("role").and_then(Value::as_str) == Some("user"))?;
+    match last_user_message.get("content")? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(blocks) => Some(
+            blocks
+                .iter()
+                .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+                .filter_map(|block| block.get("text").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => None,
+    }
+}
+
+/// Check a submitted rule: the field must be known and a message pattern
+/// must be a valid regular expression.
+pub fn validate_intercept_rule(field: &str, value: &str) -> Result<(), String> {
+    if find_field_by_key(field).is_none() {
+        return Err(format!("unknown field {:?}", field));
+    }
+    if value.is_empty() {
+        return Err("the value is required".to_string());
+    }
+    if field == "last_user_message" {
+        Regex::new(value).map_err(|e| format!("invalid regular expression: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_rule(field: &str, value: &str) -> InterceptRule {
+        InterceptRule {
+            id: uuid::Uuid::new_v4(),
+            session_id: uuid::Uuid::new_v4(),
+            field: field.to_string(),
+            value: value.to_string(),
+            created_at: String::new(),
+        }
+    }
+
+    fn check(rules: &[InterceptRule], body: Value) -> bool {
+        let body_bytes = body.to_string();
+        match_intercept_rules(
+            rules,
+            &InterceptSubject {
+                path: "v1/messages",
+                model: body["model"].as_str(),
+                body: body_bytes.as_bytes(),
+            },
+        )
+    }
+
+    #[test]
+    fn no_rules_match_every_request() {
+        assert!(check(&[], serde_json::json!({})));
+    }
+
+    #[test]
+    fn every_rule_must_match() {
+        let rules = [
+            make_rule("model", "claude-x"),
+            make_rule("path", "/v1/messages"),
+            make_rule("user_id", "u1"),
+        ];
+        let body = serde_json::json!({"model": "claude-x", "metadata": {"user_id": "u1"}});
+        assert!(check(&rules, body));
+        let body = serde_json::json!({"model": "claude-x", "metadata": {"user_id": "u2"}});
+        assert!(!check(&rules, body));
+    }
+
+    #[test]
+    fn last_user_message_rule_searches_the_last_user_text() {
+        let rules = [make_rule("last_user_message", "(?i)fetch https?://")];
+        let body = serde_json::json!({"messages": [
+            {"role": "user", "content": "Fetch https://example.com"},
+            {"role": "assistant", "content": "ok"},
+            {"role": "user", "content": [{"type": "text", "text": "thanks"}]},
+        ]});
+        assert!(!check(&rules, body));
+        let body = serde_json::json!({"messages": [
+            {"role": "user", "content": [{"type": "text", "text": "please FETCH http://a.b"}]},
+        ]});
+        assert!(check(&rules, body));
+    }
+
+    #[test]
+    fn validate_intercept_rule_rejects_bad_patterns() {
+        assert!(validate_intercept_rule("model", "claude-x").is_ok());
+        assert!(validate_intercept_rule("colour", "red").is_err());
+        assert!(validate_intercept_rule("model", "").is_err());
+        assert!(validate_intercept_rule("last_user_message", "(").is_err());
+    }
+}
@@ -1,60 +1,156 @@
+pub mod alerts;
+pub mod batches;
 pub mod bedrock;
+pub mod breakpoint;
+pub mod client;
+pub mod concurrency;
+pub mod correlation;
+pub mod count_tokens;
 pub mod filter;
-pub(crate) mod shared;
-pub(crate) mod sse;
+#[cfg(test)]
+mod fixtures;
+pub mod forward;
+pub mod hooks;
+pub mod hostname;
+pub mod import;
+pub mod intercept_rules;
+pub mod notify;
+pub mod openai;
+pub mod payload;
+pub mod shared;
+pub(crate) mod sigv4;
+pub mod sse;
+pub mod titles;
+pub mod tool_calls;
+pub mod validate;
+pub mod vertex;
 pub mod webfetch;
 
+use actix_web::rt::task::JoinHandle;
 use actix_web::{
-    error::{ErrorBadGateway, ErrorBadRequest, ErrorInternalServerError},
+    error::{ErrorBadRequest, ErrorInternalServerError},
     web, HttpRequest, HttpResponse,
 };
 use bytes::Bytes;
-use common::config::AppConfig;
+use chrono::Utc;
+use common::betas::BetaRules;
+use common::config::{SharedConfig, ToolExecutorConfig, ToolExecutorKind};
+use common::headers::HeaderRules;
+use common::models::{
+    REQUEST_ERROR_STATUS, REQUEST_ERROR_STREAM, REQUEST_ERROR_WEBFETCH_FOLLOWUP,
+    SESSION_EVENT_ERROR_INJECTED,
+};
+use common::upstream::UPSTREAM_PRESET_OPENAI_COMPATIBLE;
+use common::url::{mask_query_params, parse_query_params};
 use futures::StreamExt;
+use client::ClientInfo;
+use hooks::{
+    notify_request_completed, notify_request_streamed, BufferedResponse, HookContext, HookRegistry,
+    OutgoingRequest,
+};
+use intercept_rules::{check_intercept_rules, InterceptSubject};
 use shared::{
-    actix_headers_iter, build_forward_headers, build_injected_sse_error, build_stored_path,
-    build_target_url, effective_client, forward_response_headers, get_session_or_error,
-    headers_to_json, load_filters_for_profile, log_request, parse_body_fields, store_response,
-    to_actix_status, RequestMeta,
+    actix_headers_iter, apply_beta_rules, apply_header_preset, build_forward_headers,
+    build_injected_sse_error, build_stored_path, build_target_url, classify_upstream_error,
+    effective_client, forward_response_headers, get_session_or_error, headers_to_json, log_request,
+    parse_body_fields, reject_hooked_request, reject_upstream_failure, store_anthropic_beta,
+    store_chat_completion_response, store_error_kind, store_forwarded_bytes, store_response,
+    store_stop_reason, to_actix_status, RequestMeta,
 };
 use sqlx::SqlitePool;
 
-async fn apply_request_filters(
-    pool: &SqlitePool,
-    profile_id: Option<&str>,
+/// Run the request hooks on the parsed body. Returns the body to forward and
+/// the overrides to reverse in the response, or the message of a hook that
+/// rejected the request. A body that isn't JSON, or that no hook changed, is
+/// forwarded as sent.
+async fn apply_request_hooks(
+    hook_registry: &HookRegistry,
+    hook_context: &HookContext<'_>,
     body: &web::Bytes,
-) -> (Vec<u8>, Vec<(String, String)>) {
-    if let Some(filters) = load_filters_for_profile(pool, profile_id).await {
-        let tool_name_overrides = filters.tool_name_overrides.clone();
-        if let Ok(mut json_body) = serde_json::from_slice::<serde_json::Value>(body) {
-            filter::apply_filters(
-                &mut json_body,
-                &filters.system_filters,
-                &filters.tool_filters,
-                filters.keep_tool_pairs,
-            );
-            filter::apply_tool_name_overrides(&mut json_body, &filters.tool_name_overrides);
-            return (
-                serde_json::to_vec(&json_body).unwrap_or_else(|_| body.to_vec()),
-                tool_name_overrides,
-            );
+    parsed_body: Option<serde_json::Value>,
+) -> Result<(Vec<u8>, Vec<(String, String)>), String> {
+    let Some(parsed_body) = parsed_body else {
+        return Ok((body.to_vec(), vec![]));
+    };
+    let mut outgoing_request = OutgoingRequest {
+        body: parsed_body,
+        body_changed: false,
+        tool_name_overrides: vec![],
+        rejection: None,
+    };
+    hook_registry
+        .run_request_hooks(hook_context, &mut outgoing_request)
+        .await;
+    if let Some(rejection) = outgoing_request.rejection {
+        return Err(rejection);
+    }
+    let forward_body = if outgoing_request.body_changed {
+        serde_json::to_vec(&outgoing_request.body).unwrap_or_else(|_| body.to_vec())
+    } else {
+        body.to_vec()
+    };
+    Ok((forward_body, outgoing_request.tool_name_overrides))
+}
+
+/// Tool names handled by the URL fetcher: the configured WebFetch names plus
+/// tools mapped to a `fetch` executor.
+pub(crate) fn collect_webfetch_names(
+    session: &common::models::Session,
+    webfetch_tool_names: &[String],
+    tool_executors: &[ToolExecutorConfig],
+) -> Vec<String> {
+    if !session.webfetch_intercept {
+        return vec![];
+    }
+    let mut webfetch_names = webfetch_tool_names.to_vec();
+    for executor in tool_executors {
+        if executor.kind == ToolExecutorKind::Fetch && !webfetch_names.contains(&executor.tool_name)
+        {
+            webfetch_names.push(executor.tool_name.clone());
         }
-        return (body.to_vec(), tool_name_overrides);
     }
-    (body.to_vec(), vec![])
+    webfetch_names
 }
 
-fn collect_webfetch_names(session: &common::models::Session) -> Vec<String> {
-    if session.webfetch_intercept {
-        vec!["WebFetch".to_string()]
-    } else {
-        vec![]
+/// Run the session's schema validation on the request body, if enabled.
+fn collect_session_violations(
+    session: &common::models::Session,
+    body: &[u8],
+    path: &str,
+) -> Vec<String> {
+    match session.validation_mode.as_deref() {
+        Some(mode) if !mode.is_empty() => {
+            validate::collect_schema_violations(body, path, session.validation_schema.as_deref())
+        }
+        _ => vec![],
+    }
+}
+
+fn merge_notes(note: Option<String>, extra_note: Option<String>) -> Option<String> {
+    match (note, extra_note) {
+        (Some(note), Some(extra_note)) => Some(format!("{}; {}", note, extra_note)),
+        (note, extra_note) => note.or(extra_note),
+    }
+}
+
+/// Record the input token count returned by the Token Counting endpoint.
+async fn store_counted_input_tokens(pool: &SqlitePool, request_id: &str, body_str: &str) {
+    let Some(counted_input_tokens) = count_tokens::parse_counted_input_tokens(body_str) else {
+        return;
+    };
+    let write = db::RequestWrite::CountedInputTokens {
+        request_id: request_id.to_string(),
+        counted_input_tokens,
+    };
+    if let Err(e) = db::write_request(pool, write).await {
+        log::warn!("count_tokens: failed to store counted input tokens: {}", e);
     }
 }
 
 /// Spawn a task that streams the upstream response through the SSE channel,
 /// applying tool name reversal on `content_block_start` events, and stores
-/// the accumulated body to DB when done.
+/// the accumulated body to DB when done, or what arrived before the stream
+/// broke off. Returns the task, which finishes once the response is stored.
 fn stream_proxy_response(
     byte_stream: impl futures::Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
     tx: futures::channel::mpsc::UnboundedSender<Result<Bytes, actix_web::Error>>,
@@ -63,11 +159,12 @@ fn stream_proxy_response(
     request_id: String,
     resp_headers_json: String,
     status: u16,
-) {
+) -> JoinHandle<()> {
     actix_web::rt::spawn(async move {
         let mut accumulated: Vec<u8> = Vec::new();
         let mut parser = sse::SseParser::new();
         let mut byte_stream = std::pin::pin!(byte_stream);
+        let mut stream_failed = false;
 
         while let Some(chunk_result) = byte_stream.next().await {
             match chunk_result {
@@ -94,7 +191,8 @@ fn stream_proxy_response(
                         "Upstream stream error: {}",
                         e
                     ))));
-                    return;
+                    stream_failed = true;
+                    break;
                 }
             }
         }
@@ -111,22 +209,29 @@ fn stream_proxy_response(
             let body_str = String::from_utf8_lossy(&accumulated);
             let events = sse::parse_sse_events(&body_str);
             let events_json = serde_json::to_string(&events)?;
-            db::set_request_response(
+            db::write_request(
                 pool.get_ref(),
-                &request_id,
-                status as i64,
-                Some(&resp_headers_json),
-                Some(&body_str),
-                Some(&events_json),
+                db::RequestWrite::Response {
+                    request_id: request_id.clone(),
+                    status: status as i64,
+                    headers_json: Some(resp_headers_json),
+                    body: Some(body_str.to_string()),
+                    events_json: Some(events_json),
+                    responded_at_ms: Some(Utc::now().timestamp_millis()),
+                },
             )
             .await?;
+            store_stop_reason(pool.get_ref(), &request_id, &body_str, &events).await;
             Ok(())
         }
         .await;
         if let Err(e) = store {
             log::error!("Failed to store streaming response: {}", e);
         }
-    });
+        if stream_failed {
+            store_error_kind(pool.get_ref(), &request_id, REQUEST_ERROR_STREAM).await;
+        }
+    })
 }
 
 async fn store_webfetch_interception(
@@ -140,16 +245,14 @@ async fn store_webfetch_interception(
 ) {
     let first_events = sse::parse_sse_events(body_str);
     let first_events_json = serde_json::to_string(&first_events).unwrap_or_default();
-    if let Err(e) = db::set_request_webfetch_data(
-        pool,
-        request_id,
-        Some(body_str),
-        Some(&first_events_json),
-        Some(followup_body_json),
-        Some(rounds_json),
-    )
-    .await
-    {
+    let write = db::RequestWrite::WebfetchData {
+        request_id: request_id.to_string(),
+        first_response_body: Some(body_str.to_string()),
+        first_response_events_json: Some(first_events_json),
+        followup_body_json: Some(followup_body_json.to_string()),
+        rounds_json: Some(rounds_json.to_string()),
+    };
+    if let Err(e) = db::write_request(pool, write).await {
         log::warn!("webfetch: failed to store interception data: {}", e);
     }
 
@@ -157,61 +260,122 @@ async fn store_webfetch_interception(
         Some(n) => format!("{}; {}", n, webfetch_note),
         None => webfetch_note.to_string(),
     };
-    if let Err(e) = db::set_request_note(pool, request_id, &combined_note).await {
+    let write = db::RequestWrite::Note {
+        request_id: request_id.to_string(),
+        note: combined_note,
+    };
+    if let Err(e) = db::write_request(pool, write).await {
         log::warn!("webfetch: failed to store request note: {}", e);
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    fn make_session(intercept: bool) -> common::models::Session {
-        common::models::Session {
-            id: uuid::Uuid::nil(),
-            name: "test".to_string(),
-            target_url: "https://api.example.com".to_string(),
-            tls_verify_disabled: false,
-            auth_header: None,
-            x_api_key: None,
-            profile_id: None,
-            webfetch_intercept: intercept,
-            webfetch_whitelist: None,
-            error_inject: None,
-            created_at: String::new(),
-            updated_at: String::new(),
-            request_count: 0,
-        }
-    }
-
-    #[test]
-    fn collect_webfetch_names_intercept_off() {
-        let session = make_session(false);
-        assert!(collect_webfetch_names(&session).is_empty());
-    }
+/// Store the follow-up response of an intercepted request as its response,
+/// along with the intercepted first response and the rounds.
+pub(crate) async fn store_intercepted_response(
+    pool: &SqlitePool,
+    request_id: &str,
+    intercepted_response: &webfetch::InterceptedResponse,
+    body_str: &str,
+    note: Option<&str>,
+) -> Result<(), actix_web::Error> {
+    let webfetch::InterceptedResponse {
+        status: followup_status,
+        headers: followup_headers,
+        body: followup_body,
+        note: webfetch_note,
+        followup_body_json,
+        rounds_json,
+    } = intercepted_response;
+    let followup_resp_headers_json = headers_to_json(
+        followup_headers
+            .iter()
+            .filter_map(|(k, v)| v.to_str().ok().map(|s| (k.to_string(), s.to_string()))),
+    )
+    .map_err(ErrorInternalServerError)?;
+    let followup_body_str = String::from_utf8_lossy(followup_body);
+    store_response(
+        pool,
+        request_id,
+        *followup_status,
+        Some(&followup_resp_headers_json),
+        &followup_body_str,
+    )
+    .await
+    .map_err(ErrorInternalServerError)?;
 
-    #[test]
-    fn collect_webfetch_names_intercept_on() {
-        let session = make_session(true);
-        assert_eq!(collect_webfetch_names(&session), vec!["WebFetch"]);
-    }
+    // Store webfetch interception data: intercepted response + follow-up body
+    store_webfetch_interception(
+        pool,
+        request_id,
+        body_str,
+        followup_body_json,
+        rounds_json,
+        note,
+        webfetch_note,
+    )
+    .await;
+    Ok(())
+}
 
-    #[test]
-    fn collect_webfetch_names_single_name() {
-        let session = make_session(true);
-        assert_eq!(collect_webfetch_names(&session), vec!["WebFetch"]);
+/// Record an injected error on the session timeline. Failures are only logged.
+pub(crate) async fn record_injected_error(pool: &SqlitePool, session_id: &str, error_type: &str) {
+    if let Err(e) =
+        db::create_session_event(pool, session_id, SESSION_EVENT_ERROR_INJECTED, error_type, None)
+            .await
+    {
+        log::warn!("Failed to record injected error event: {}", e);
     }
 }
+/// Where a proxied request is sent and which Messages API endpoint it is
+/// handled as.
+pub(crate) struct ProxyRoute {
+    /// Path appended to the session's target URL, without the leading `/`.
+    pub upstream_path: String,
+    /// The Anthropic API path the request is parsed, validated and stored as.
+    pub api_path: String,
+    /// Model named in the URL instead of the request body.
+    pub url_model: Option<String>,
+}
 
 pub async fn proxy_handler(
     req: HttpRequest,
     body: web::Bytes,
     pool: web::Data<SqlitePool>,
     client: web::Data<reqwest::Client>,
-    approval_queue: web::Data<webfetch::ApprovalQueue>,
-    config: web::Data<AppConfig>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let full_path = req.match_info().get("tail").unwrap_or("");
+    let full_path = req.match_info().get("tail").unwrap_or("").to_string();
+    let url_model = {
+        let path_segments: Vec<&str> = full_path.split('/').collect();
+        path_segments
+            .iter()
+            .position(|&segment| segment == "model")
+            .and_then(|pos| path_segments.get(pos + 1).map(|segment| segment.to_string()))
+    };
+    let route = ProxyRoute {
+        upstream_path: full_path.clone(),
+        api_path: full_path,
+        url_model,
+    };
+    forward_proxy_request(req, body, pool, client, hook_registry, config, route).await
+}
+
+/// Log a Messages API request, run the request hooks and forward it, then
+/// run the intercept hooks, token counting and batch tracking on the response.
+pub(crate) async fn forward_proxy_request(
+    req: HttpRequest,
+    body: web::Bytes,
+    pool: web::Data<SqlitePool>,
+    client: web::Data<reqwest::Client>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
+    route: ProxyRoute,
+) -> Result<HttpResponse, actix_web::Error> {
+    // Snapshot so settings edited mid-request don't change prompts between rounds.
+    let config = config.read().unwrap().clone();
+    let full_path = route.api_path.as_str();
     let session_id = req
         .match_info()
         .get("session_id")
@@ -223,81 +387,161 @@ pub async fn proxy_handler(
     if let Some(ref error_type) = session.error_inject {
         if !error_type.is_empty() {
             if let Some(resp) = build_injected_sse_error(error_type) {
+                record_injected_error(pool.get_ref(), session_id, error_type).await;
                 return Ok(resp);
             }
         }
     }
 
     let query = req.uri().query();
-    let target_url = build_target_url(&session.target_url, full_path, query);
-    let stored_path = build_stored_path(full_path, query);
+    let auth_query_params = parse_query_params(session.auth_query_params.as_deref());
+    let target_url = build_target_url(
+        &session.target_url,
+        &route.upstream_path,
+        query,
+        &auth_query_params,
+    );
+    let stored_path = build_stored_path(&route.upstream_path, query, &auth_query_params);
     let method = req.method().to_string();
+    let proxy_request_id = correlation::get_proxy_request_id(&req);
 
     log::info!(
-        "{} {} -> {} {}",
+        "{} {} -> {} {} [{}]",
         session.name,
         method,
         stored_path,
-        target_url
+        mask_query_params(&target_url, &auth_query_params),
+        proxy_request_id.as_deref().unwrap_or("-")
     );
 
     // Log request to DB
     let req_headers_json =
         headers_to_json(actix_headers_iter(&req)).map_err(ErrorInternalServerError)?;
-    let url_model = {
-        let path_segments: Vec<&str> = full_path.split('/').collect();
-        path_segments
-            .iter()
-            .position(|&segment| segment == "model")
-            .and_then(|pos| path_segments.get(pos + 1).map(|segment| segment.to_string()))
-    };
-    let (fields, note) = parse_body_fields(&body, url_model).map_err(ErrorInternalServerError)?;
+    let (fields, parsed_body, note) =
+        parse_body_fields(&body, full_path, route.url_model).map_err(ErrorInternalServerError)?;
+    let violations = collect_session_violations(&session, &body, full_path);
+    let violations_note =
+        (!violations.is_empty()).then(|| validate::format_violations_note(&violations));
+    let note = merge_notes(note, violations_note);
+    let model = fields.model.clone();
     let request_id = log_request(
         &RequestMeta {
+            id: proxy_request_id.as_deref(),
             pool: pool.get_ref(),
             session_id,
             method: &method,
             path: &stored_path,
             headers_json: Some(&req_headers_json),
             note: note.as_deref(),
+            client: Some(&ClientInfo::from_request(&req)),
+            parent_request_id: None,
         },
-        &fields,
+        fields,
     )
     .await
     .map_err(ErrorInternalServerError)?;
+    let intercept_rules_match = check_intercept_rules(
+        pool.get_ref(),
+        &session,
+        &InterceptSubject {
+            path: &route.upstream_path,
+            model: model.as_deref(),
+            body: &body,
+        },
+    )
+    .await;
+    let hook_context = HookContext {
+        pool: pool.get_ref(),
+        config: &config,
+        session: &session,
+        request_id: &request_id,
+        intercept_rules_match,
+    };
+
+    if !violations.is_empty() && session.validation_mode.as_deref() == Some("reject") {
+        let error_body = validate::build_violations_error_body(&violations);
+        if let Err(e) = store_response(pool.get_ref(), &request_id, 400, None, &error_body).await {
+            log::warn!("validation: failed to store rejection response: {}", e);
+        }
+        notify_request_completed(&hook_registry, &hook_context);
+        return Ok(HttpResponse::BadRequest()
+            .content_type("application/json")
+            .body(error_body));
+    }
 
-    // Apply filters to the body before forwarding
+    // Let the hooks (e.g. the filter profile) change the body before forwarding
     let (forward_body, tool_name_overrides) =
-        apply_request_filters(pool.get_ref(), session.profile_id.as_deref(), &body).await;
+        match apply_request_hooks(&hook_registry, &hook_context, &body, parsed_body).await {
+            Ok(hooked_request) => hooked_request,
+            Err(rejection) => {
+                let resp = reject_hooked_request(pool.get_ref(), &request_id, &rejection).await;
+                notify_request_completed(&hook_registry, &hook_context);
+                return Ok(resp);
+            }
+        };
+    store_forwarded_bytes(pool.get_ref(), &request_id, forward_body.len()).await;
 
     // Forward the request upstream
-    let forward_headers = build_forward_headers(
+    let mut forward_headers = build_forward_headers(
         &req,
+        &HeaderRules::parse(
+            session.forward_header_allow.as_deref(),
+            session.forward_header_deny.as_deref(),
+        ),
         session.auth_header.as_deref(),
         session.x_api_key.as_deref(),
     );
+    apply_header_preset(&mut forward_headers, session.header_preset.as_deref());
+    let beta_rules = BetaRules::parse(
+        session.beta_inject.as_deref(),
+        session.beta_strip.as_deref(),
+    );
+    if let Some(anthropic_beta) = apply_beta_rules(&mut forward_headers, &beta_rules) {
+        store_anthropic_beta(pool.get_ref(), &request_id, &anthropic_beta).await;
+    }
+    // Filters can shorten the body; let reqwest set the length of what is sent.
+    forward_headers.remove(reqwest::header::CONTENT_LENGTH);
+    if config.forward_proxy_session_header {
+        correlation::set_proxy_session_header(&mut forward_headers, session_id);
+    }
     let effective_client = effective_client(&session, client.get_ref());
     let parsed_method = reqwest::Method::from_bytes(method.as_bytes())
         .map_err(|e| ErrorBadRequest(format!("Invalid HTTP method: {}", e)))?;
 
-    // Save copies for potential webfetch follow-up before the upstream call consumes them
-    let webfetch_names = collect_webfetch_names(&session);
-    let webfetch_context = if !webfetch_names.is_empty() {
+    // Save copies for intercept follow-ups before the upstream call consumes them
+    let intercept_context = if hook_registry.wants_intercept(&hook_context) {
         Some((forward_body.clone(), forward_headers.clone()))
     } else {
         None
     };
 
-    let upstream = effective_client
+    let upstream = match effective_client
         .request(parsed_method, &target_url)
         .headers(forward_headers)
         .body(forward_body)
         .send()
         .await
-        .map_err(|e| ErrorBadGateway(format!("Upstream error: {}", e)))?;
+    {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let message = format!("Upstream error: {}", e);
+            let resp = reject_upstream_failure(
+                pool.get_ref(),
+                &request_id,
+                classify_upstream_error(&e),
+                &message,
+            )
+            .await;
+            notify_request_completed(&hook_registry, &hook_context);
+            return Ok(resp);
+        }
+    };
 
     // Build response
     let status = upstream.status().as_u16();
+    if !upstream.status().is_success() {
+        store_error_kind(pool.get_ref(), &request_id, REQUEST_ERROR_STATUS).await;
+    }
     let resp_headers_json = headers_to_json(
         upstream
             .headers()
@@ -307,118 +551,262 @@ pub async fn proxy_handler(
     .map_err(ErrorInternalServerError)?;
     let actix_status = to_actix_status(status)?;
 
+    let response_header_rules = HeaderRules::parse(
+        session.response_header_allow.as_deref(),
+        session.response_header_deny.as_deref(),
+    );
     let mut builder = HttpResponse::build(actix_status);
-    forward_response_headers(&mut builder, upstream.headers());
-
-    // Streaming path: when tool name overrides are present and no webfetch interception needed.
-    // Webfetch interception requires the full buffered response, so those two are mutually exclusive.
-    if webfetch_context.is_none() && !tool_name_overrides.is_empty() {
+    forward_response_headers(&mut builder, upstream.headers(), &response_header_rules);
+
+    // Streaming path: when tool name overrides are present and no hook wants to intercept.
+    // Interception requires the full buffered response, so those two are mutually exclusive.
+    // Token Counting and batch creation responses are plain JSON, so they always take the
+    // buffered path.
+    let is_count_tokens = count_tokens::is_count_tokens_path(full_path);
+    let is_batch_create = method == "POST" && batches::is_batch_create_path(full_path);
+    if intercept_context.is_none()
+        && !tool_name_overrides.is_empty()
+        && !is_count_tokens
+        && !is_batch_create
+    {
         let (tx, rx) =
             futures::channel::mpsc::unbounded::<Result<Bytes, actix_web::Error>>();
-        stream_proxy_response(
+        let stream_task = stream_proxy_response(
             upstream.bytes_stream(),
             tx,
             tool_name_overrides,
             pool.clone(),
-            request_id,
+            request_id.clone(),
             resp_headers_json,
             status,
         );
+        notify_request_streamed(&hook_registry, &hook_context, stream_task);
         return Ok(builder.streaming(rx));
     }
 
-    let response_body = upstream
-        .bytes()
-        .await
-        .map_err(|e| ErrorBadGateway(format!("Failed to read upstream response body: {}", e)))?;
+    let response_body = match upstream.bytes().await {
+        Ok(response_body) => response_body,
+        Err(e) => {
+            let message = format!("Failed to read upstream response body: {}", e);
+            let resp = reject_upstream_failure(
+                pool.get_ref(),
+                &request_id,
+                classify_upstream_error(&e),
+                &message,
+            )
+            .await;
+            notify_request_completed(&hook_registry, &hook_context);
+            return Ok(resp);
+        }
+    };
 
     let body_str = String::from_utf8_lossy(&response_body);
 
-    // WebFetch interception: if enabled, check for tool_use and send follow-up request
-    if let Some((saved_body, saved_headers)) = webfetch_context {
-        let whitelist: Vec<String> = session
-            .webfetch_whitelist
-            .as_deref()
-            .unwrap_or("")
-            .lines()
-            .map(|line| line.trim().to_string())
-            .filter(|line| !line.is_empty())
-            .collect();
-
-        if let Some(result) = webfetch::maybe_intercept(&webfetch::InterceptParams {
-            response_body: &body_str,
-            original_body: &saved_body,
-            target_url: &target_url,
-            forward_headers: &saved_headers,
-            client: effective_client,
-            approval_queue: approval_queue.get_ref(),
-            session_id,
-            whitelist: &whitelist,
-            pool: pool.get_ref(),
-            stored_path: &stored_path,
-            webfetch_names: &webfetch_names,
-            config: config.get_ref(),
-        })
-        .await
-        {
-            let webfetch::InterceptResult::Intercepted {
-                status: followup_status,
-                headers: followup_headers,
-                body: followup_body,
-                note: webfetch_note,
-                followup_body_json,
-                rounds_json,
-            } = result;
-
-            // Use follow-up response's status, headers, and body
-            let followup_actix_status = to_actix_status(followup_status)?;
-            let followup_resp_headers_json = headers_to_json(
-                followup_headers
-                    .iter()
-                    .filter_map(|(k, v)| v.to_str().ok().map(|s| (k.to_string(), s.to_string()))),
+    // Interception (e.g. WebFetch): a hook may answer with a follow-up response instead
+    if let Some((saved_body, saved_headers)) = intercept_context {
+        let intercept_result = hook_registry
+            .run_intercept_hooks(
+                &hook_context,
+                &BufferedResponse {
+                    response_body: &body_str,
+                    original_body: &saved_body,
+                    target_url: &target_url,
+                    forward_headers: &saved_headers,
+                    client: effective_client,
+                    stored_path: &stored_path,
+                },
             )
-            .map_err(ErrorInternalServerError)?;
-
-            let mut followup_builder = HttpResponse::build(followup_actix_status);
-            forward_response_headers(&mut followup_builder, &followup_headers);
-
-            let followup_body_str = String::from_utf8_lossy(&followup_body);
-            store_response(
+            .await;
+        if let Some(webfetch::InterceptResult::FollowupFailed { message }) = &intercept_result {
+            let resp = reject_upstream_failure(
                 pool.get_ref(),
                 &request_id,
-                followup_status,
-                Some(&followup_resp_headers_json),
-                &followup_body_str,
+                REQUEST_ERROR_WEBFETCH_FOLLOWUP,
+                message,
             )
-            .await
-            .map_err(ErrorInternalServerError)?;
-
-            // Store webfetch interception data: intercepted response + follow-up body
-            store_webfetch_interception(
+            .await;
+            notify_request_completed(&hook_registry, &hook_context);
+            return Ok(resp);
+        }
+        if let Some(webfetch::InterceptResult::Intercepted(intercepted_response)) = intercept_result
+        {
+            store_intercepted_response(
                 pool.get_ref(),
                 &request_id,
+                &intercepted_response,
                 &body_str,
-                &followup_body_json,
-                &rounds_json,
                 note.as_deref(),
-                &webfetch_note,
             )
-            .await;
+            .await?;
+            notify_request_completed(&hook_registry, &hook_context);
 
+            // Use follow-up response's status, headers, and body
+            let webfetch::InterceptedResponse {
+                status: followup_status,
+                headers: followup_headers,
+                body: followup_body,
+                ..
+            } = intercepted_response;
+            let mut followup_builder = HttpResponse::build(to_actix_status(followup_status)?);
+            forward_response_headers(
+                &mut followup_builder,
+                &followup_headers,
+                &response_header_rules,
+            );
             return Ok(followup_builder.body(followup_body.to_vec()));
         }
     }
 
     // Default path: no interception, store and return original response
-    store_response(
-        pool.get_ref(),
-        &request_id,
-        status,
-        Some(&resp_headers_json),
-        &body_str,
-    )
-    .await
-    .map_err(ErrorInternalServerError)?;
+    let resp_headers_json = Some(resp_headers_json.as_str());
+    let stored = if session.upstream_preset.as_deref() == Some(UPSTREAM_PRESET_OPENAI_COMPATIBLE) {
+        store_chat_completion_response(
+            pool.get_ref(),
+            &request_id,
+            status,
+            resp_headers_json,
+            &body_str,
+        )
+        .await
+    } else {
+        store_response(pool.get_ref(), &request_id, status, resp_headers_json, &body_str).await
+    };
+    stored.map_err(ErrorInternalServerError)?;
+    if is_count_tokens {
+        store_counted_input_tokens(pool.get_ref(), &request_id, &body_str).await;
+    }
+    notify_request_completed(&hook_registry, &hook_context);
+    if is_batch_create && status == 200 {
+        let batch_creation = batches::BatchCreation {
+            pool: pool.get_ref(),
+            session_id,
+            request_id: &request_id,
+            base_path: &route.upstream_path,
+            stored_path: &stored_path,
+            headers_json: Some(&req_headers_json),
+            request_body: &body,
+            response_body: &body_str,
+        };
+        if let Err(e) = batches::store_message_batch(&batch_creation).await {
+            log::warn!("batches: failed to store message batch: {}", e);
+        }
+    }
 
     Ok(builder.body(response_body.to_vec()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn make_session(intercept: bool) -> common::models::Session {
+        common::models::Session {
+            id: uuid::Uuid::nil(),
+            name: "test".to_string(),
+            target_url: "https://api.example.com".to_string(),
+            tls_verify_disabled: false,
+            auth_header: None,
+            x_api_key: None,
+            profile_id: None,
+            aws_region: None,
+            aws_access_key_id: None,
+            aws_secret_access_key: None,
+            aws_session_token: None,
+            upstream_preset: None,
+            max_concurrent_requests: None,
+            concurrency_overflow: None,
+            max_body_bytes: None,
+            forward_header_allow: None,
+            forward_header_deny: None,
+            response_header_allow: None,
+            response_header_deny: None,
+            auth_query_params: None,
+            header_preset: None,
+            beta_inject: None,
+            beta_strip: None,
+            completion_webhook_url: None,
+            hostname: None,
+            is_default: false,
+            breakpoint_enabled: false,
+            webfetch_intercept: intercept,
+            webfetch_allow_private_targets: false,
+            webfetch_max_content_bytes: None,
+            webfetch_fetch_timeout_secs: None,
+            webfetch_extraction_mode: "text".to_string(),
+            webfetch_respect_robots_txt: false,
+            webfetch_raw_mode: false,
+            webfetch_agent_url: None,
+            webfetch_agent_model: None,
+            webfetch_agent_api_key: None,
+            mcp_server_transport: None,
+            mcp_server_target: None,
+            mcp_server_status: None,
+            mcp_server_status_detail: None,
+            mcp_server_checked_at: None,
+            validation_mode: None,
+            validation_schema: None,
+            error_inject: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            request_count: 0,
+            intercepted_request_count: 0,
+            batch_count: 0,
+        }
+    }
+
+    fn default_tool_names() -> Vec<String> {
+        vec!["WebFetch".to_string()]
+    }
+
+    #[test]
+    fn collect_webfetch_names_intercept_off() {
+        let session = make_session(false);
+        assert!(collect_webfetch_names(&session, &default_tool_names(), &[]).is_empty());
+    }
+
+    #[test]
+    fn collect_webfetch_names_intercept_on() {
+        let session = make_session(true);
+        assert_eq!(
+            collect_webfetch_names(&session, &default_tool_names(), &[]),
+            vec!["WebFetch"]
+        );
+    }
+
+    #[test]
+    fn collect_webfetch_names_single_name() {
+        let session = make_session(true);
+        assert_eq!(
+            collect_webfetch_names(&session, &default_tool_names(), &[]),
+            vec!["WebFetch"]
+        );
+    }
+
+    #[test]
+    fn collect_webfetch_names_configured_names() {
+        let session = make_session(true);
+        let names = vec!["WebFetch".to_string(), "fetch_url".to_string()];
+        assert_eq!(collect_webfetch_names(&session, &names, &[]), names);
+    }
+
+    #[test]
+    fn collect_webfetch_names_includes_fetch_executors() {
+        let session = make_session(true);
+        let tool_executors = vec![
+            ToolExecutorConfig {
+                tool_name: "ReadDocs".to_string(),
+                kind: ToolExecutorKind::Fetch,
+            },
+            ToolExecutorConfig {
+                tool_name: "Grep".to_string(),
+                kind: ToolExecutorKind::Command {
+                    command: "grep".to_string(),
+                },
+            },
+        ];
+        assert_eq!(
+            collect_webfetch_names(&session, &default_tool_names(), &tool_executors),
+            vec!["WebFetch", "ReadDocs"]
+        );
+    }
+}
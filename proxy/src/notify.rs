@@ -0,0 +1,187 @@
+use common::models::{AppliedFilter, ProxyRequest};
+use common::sse::{collect_usage, load_response_events};
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+/// POST `payload` to every webhook URL in the background. Failures are logged
+/// and never affect the proxied request.
+pub fn send_webhook_notifications(client: &reqwest::Client, webhook_urls: &[String], payload: &Value) {
+    let payload_bytes = serde_json::to_vec(payload).unwrap_or_default();
+    for webhook_url in webhook_urls {
+        let request = client
+            .post(webhook_url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(payload_bytes.clone());
+        let webhook_url = webhook_url.clone();
+        actix_web::rt::spawn(async move {
+            if let Err(e) = request.send().await {
+                log::warn!("notify: webhook {} failed: {}", webhook_url, e);
+            }
+        });
+    }
+}
+
+/// Summary of a finished request posted to its session's completion webhook.
+fn build_completion_payload(request: &ProxyRequest, latency_ms: Option<i64>) -> Value {
+    let events = load_response_events(
+        request.response_events_json.as_deref(),
+        request.response_body.as_deref(),
+    );
+    let mut usage = collect_usage(&events);
+    // JSON responses are stored with unparsed events, so read their own usage.
+    if usage.is_empty() {
+        if let Some(Value::Object(body_usage)) = request
+            .response_body
+            .as_deref()
+            .and_then(|body| serde_json::from_str::<Value>(body).ok())
+            .and_then(|body| body.get("usage").cloned())
+        {
+            usage = body_usage;
+        }
+    }
+    let webfetch_rounds: Vec<Value> = request
+        .webfetch_rounds_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    let tool_names: Vec<&Value> = webfetch_rounds
+        .iter()
+        .filter_map(|round| round.get("tool_names").and_then(|field| field.as_array()))
+        .flatten()
+        .collect();
+    let applied_filters: Vec<AppliedFilter> = request
+        .applied_filters_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default();
+    serde_json::json!({
+        "event": "request_completed",
+        "session_id": request.session_id,
+        "request_id": request.id,
+        "method": request.method,
+        "path": request.path,
+        "model": request.model,
+        "status": request.response_status,
+        "error_kind": request.error_kind,
+        "stop_reason": request.stop_reason,
+        "latency_ms": latency_ms,
+        "usage": usage,
+        "intercept": {
+            "webfetch_rounds": webfetch_rounds.len(),
+            "tool_names": tool_names,
+            "filters_applied": applied_filters.len(),
+        },
+    })
+}
+
+/// Post the summary of a finished request to `webhook_url`.
+pub(crate) async fn send_completion_webhook(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    webhook_url: &str,
+    request: &ProxyRequest,
+) -> anyhow::Result<()> {
+    let latency_ms = db::get_request_latency_ms(pool, &request.id.to_string()).await?;
+    send_webhook_notifications(
+        client,
+        &[webhook_url.to_string()],
+        &build_completion_payload(request, latency_ms),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_request() -> ProxyRequest {
+        ProxyRequest {
+            id: uuid::Uuid::nil(),
+            session_id: uuid::Uuid::nil(),
+            method: "POST".to_string(),
+            path: "/v1/messages".to_string(),
+            headers_json: None,
+            body_json: None,
+            truncated_json: None,
+            model: Some("claude-sonnet-4-5".to_string()),
+            tools_json: None,
+            messages_json: None,
+            system_json: None,
+            params_json: None,
+            note: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+            response_status: Some(200),
+            response_headers_json: None,
+            response_body: None,
+            response_events_json: None,
+            webfetch_first_response_body: None,
+            webfetch_first_response_events_json: None,
+            webfetch_followup_body_json: None,
+            webfetch_rounds_json: None,
+            webfetch_followup_error: None,
+            estimated_input_tokens: None,
+            counted_input_tokens: None,
+            error_kind: None,
+            request_bytes: None,
+            forwarded_bytes: None,
+            response_bytes: None,
+            applied_filters_json: None,
+            anthropic_beta: None,
+            title: None,
+            body_hash: None,
+            stop_reason: Some("end_turn".to_string()),
+            client_user_agent: None,
+            client_app: None,
+            client_addr: None,
+            parent_request_id: None,
+            body_json_zstd: None,
+            response_body_zstd: None,
+        }
+    }
+
+    #[test]
+    fn build_completion_payload_merges_streamed_usage() {
+        let mut request = make_request();
+        request.response_events_json = Some(
+            serde_json::json!([
+                {
+                    "event": "message_start",
+                    "data": {"message": {"usage": {"input_tokens": 12, "output_tokens": 1}}},
+                },
+                {"event": "message_delta", "data": {"usage": {"output_tokens": 7}}},
+            ])
+            .to_string(),
+        );
+        request.webfetch_rounds_json =
+            Some(r#"[{"tool_names": ["web_fetch", "web_fetch"]}]"#.to_string());
+        request.applied_filters_json =
+            Some(r#"[{"kind": "tool", "rule": "Bash", "detail": "Bash"}]"#.to_string());
+        let payload = build_completion_payload(&request, Some(840));
+        assert_eq!(payload["event"], "request_completed");
+        assert_eq!(payload["status"], 200);
+        assert_eq!(payload["latency_ms"], 840);
+        assert_eq!(payload["stop_reason"], "end_turn");
+        assert_eq!(payload["usage"]["input_tokens"], 12);
+        assert_eq!(payload["usage"]["output_tokens"], 7);
+        assert_eq!(payload["intercept"]["webfetch_rounds"], 1);
+        assert_eq!(
+            payload["intercept"]["tool_names"].as_array().unwrap().len(),
+            2
+        );
+        assert_eq!(payload["intercept"]["filters_applied"], 1);
+    }
+
+    #[test]
+    fn build_completion_payload_reads_json_body_usage() {
+        let mut request = make_request();
+        request.response_events_json = Some(r#"[{"unparsed": true}]"#.to_string());
+        request.response_body = Some(
+            r#"{"type": "error", "usage": {"input_tokens": 3, "output_tokens": 4}}"#.to_string(),
+        );
+        let payload = build_completion_payload(&request, None);
+        assert_eq!(payload["usage"]["output_tokens"], 4);
+        assert!(payload["latency_ms"].is_null());
+        assert_eq!(payload["intercept"]["webfetch_rounds"], 0);
+    }
+}
@@ -0,0 +1,67 @@
+//! OpenAI-compatible upstreams (Ollama, vLLM, LM Studio).
+
+use common::models::Session;
+use common::url::parse_query_params;
+use std::time::Duration;
+
+use crate::shared::{apply_session_auth_headers, build_target_url, effective_client};
+
+/// How long model discovery may take before the session page gives up on it.
+const LIST_MODELS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Read the model IDs from a `/v1/models` response body.
+pub fn parse_model_ids(body: &str) -> anyhow::Result<Vec<String>> {
+    let data: serde_json::Value = serde_json::from_str(body)?;
+    let models = data
+        .get("data")
+        .and_then(|field| field.as_array())
+        .ok_or_else(|| anyhow::anyhow!("response has no \"data\" array"))?;
+    Ok(models
+        .iter()
+        .filter_map(|model| model.get("id").and_then(|field| field.as_str()))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Ask the session's upstream for the models it serves.
+pub async fn list_models(
+    session: &Session,
+    client: &reqwest::Client,
+) -> anyhow::Result<Vec<String>> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    apply_session_auth_headers(
+        &mut headers,
+        session.auth_header.as_deref(),
+        session.x_api_key.as_deref(),
+    );
+    let response = effective_client(session, client)
+        .get(build_target_url(
+            &session.target_url,
+            "v1/models",
+            None,
+            &parse_query_params(session.auth_query_params.as_deref()),
+        ))
+        .headers(headers)
+        .timeout(LIST_MODELS_TIMEOUT)
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        anyhow::bail!("HTTP {}", status.as_u16());
+    }
+    parse_model_ids(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_model_ids_reads_data_ids() {
+        let body =
+            r#"{"object":"list","data":[{"id":"llama3:8b","object":"model"},{"id":"qwen2.5"}]}"#;
+        assert_eq!(parse_model_ids(body).unwrap(), vec!["llama3:8b", "qwen2.5"]);
+        assert!(parse_model_ids(r#"{"models":[]}"#).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Request body size limits. Proxied bodies are read here rather than by
+//! actix's `PayloadConfig`, so a session can carry its own limit and an
+//! oversized body gets a provider-shaped 413 and a logged request.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::models::Session;
+use common::url::parse_query_params;
+use sqlx::SqlitePool;
+
+use crate::client::ClientInfo;
+use crate::correlation::get_proxy_request_id;
+use crate::shared::{
+    actix_headers_iter, build_stored_path, headers_to_json, log_request, store_response,
+    ParsedRequestBody, RequestMeta,
+};
+
+/// The largest body accepted for `session`: its own limit when set, else the
+/// global `max_payload_bytes`.
+pub fn resolve_max_body_bytes(session: Option<&Session>, max_payload_bytes: usize) -> usize {
+    session
+        .and_then(|session| session.max_body_bytes)
+        .filter(|&max_body_bytes| max_body_bytes > 0)
+        .map(|max_body_bytes| max_body_bytes as usize)
+        .unwrap_or(max_payload_bytes)
+}
+
+/// An Anthropic-style `request_too_large` error body.
+fn build_too_large_json(max_body_bytes: usize) -> String {
+    serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "request_too_large",
+            "message": format!(
+                "Request exceeds the proxy's limit of {} bytes",
+                max_body_bytes
+            ),
+        }
+    })
+    .to_string()
+}
+
+/// The request path after `/_{route}/{session_id}`, as stored for the request.
+fn build_rejected_path(req: &HttpRequest, session: &Session) -> String {
+    let route_path = req.path().splitn(4, '/').nth(3).unwrap_or("");
+    build_stored_path(
+        route_path,
+        Some(req.query_string()).filter(|query| !query.is_empty()),
+        &parse_query_params(session.auth_query_params.as_deref()),
+    )
+}
+
+/// Log an oversized request, without its body, and the 413 it was answered with.
+async fn log_rejected_request(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    session: &Session,
+    max_body_bytes: usize,
+    too_large_json: &str,
+) -> anyhow::Result<()> {
+    let session_id = session.id.to_string();
+    let headers_json = headers_to_json(actix_headers_iter(req))?;
+    let note = format!("rejected: body over {} bytes", max_body_bytes);
+    let request_id = log_request(
+        &RequestMeta {
+            id: get_proxy_request_id(req).as_deref(),
+            pool,
+            session_id: &session_id,
+            method: req.method().as_str(),
+            path: &build_rejected_path(req, session),
+            headers_json: Some(&headers_json),
+            note: Some(&note),
+            client: Some(&ClientInfo::from_request(req)),
+            parent_request_id: None,
+        },
+        ParsedRequestBody::default(),
+    )
+    .await?;
+    store_response(pool, &request_id, 413, None, too_large_json).await
+}
+
+/// Answer an oversized request with a 413, logging it when its session exists.
+async fn reject_oversized_request(
+    req: &HttpRequest,
+    pool: &SqlitePool,
+    session: Option<&Session>,
+    max_body_bytes: usize,
+) -> HttpResponse {
+    let too_large_json = build_too_large_json(max_body_bytes);
+    log::warn!(
+        "Rejected {} {}: body over {} bytes",
+        req.method(),
+        req.path(),
+        max_body_bytes
+    );
+    if let Some(session) = session {
+        if let Err(e) =
+            log_rejected_request(req, pool, session, max_body_bytes, &too_large_json).await
+        {
+            log::warn!("Failed to log rejected request: {}", e);
+        }
+    }
+    HttpResponse::PayloadTooLarge()
+        .content_type("application/json")
+        .body(too_large_json)
+}
+
+/// Read a proxied request's body, up to `session`'s limit.
+pub async fn read_request_body(
+    req: &HttpRequest,
+    payload: web::Payload,
+    pool: &SqlitePool,
+    session: Option<&Session>,
+    max_payload_bytes: usize,
+) -> Result<web::Bytes, HttpResponse> {
+    let max_body_bytes = resolve_max_body_bytes(session, max_payload_bytes);
+    match payload.to_bytes_limited(max_body_bytes).await {
+        Ok(Ok(body)) => Ok(body),
+        Ok(Err(e)) => Err(e.error_response()),
+        Err(_) => Err(reject_oversized_request(req, pool, session, max_body_bytes).await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::make_session;
+
+    #[test]
+    fn resolve_max_body_bytes_prefers_session_limit() {
+        let session = Session {
+            max_body_bytes: Some(1024),
+            ..make_session(false)
+        };
+        assert_eq!(resolve_max_body_bytes(Some(&session), 4096), 1024);
+        assert_eq!(
+            resolve_max_body_bytes(Some(&make_session(false)), 4096),
+            4096
+        );
+        assert_eq!(resolve_max_body_bytes(None, 4096), 4096);
+    }
+
+    #[test]
+    fn build_rejected_path_strips_route_prefix_and_masks_query_auth() {
+        let req = actix_web::test::TestRequest::post()
+            .uri("/_proxy/abc/v1/messages?beta=true&key=client")
+            .to_http_request();
+        let session = Session {
+            auth_query_params: Some("key=upstream".to_string()),
+            ..make_session(false)
+        };
+        assert_eq!(
+            build_rejected_path(&req, &session),
+            "/v1/messages?beta=true&key=***"
+        );
+        let req = actix_web::test::TestRequest::post()
+            .uri("/_bedrock/abc/model/m/invoke-with-response-stream")
+            .to_http_request();
+        assert_eq!(
+            build_rejected_path(&req, &make_session(false)),
+            "/model/m/invoke-with-response-stream"
+        );
+    }
+}
@@ -3,13 +3,27 @@ use actix_web::{
     http::StatusCode,
     HttpRequest, HttpResponse, HttpResponseBuilder,
 };
-use common::truncate::truncate_strings;
+use chrono::Utc;
+use common::betas::BetaRules;
+use common::header_presets;
+use common::headers::{HeaderRules, SKIPPED_FORWARD_HEADERS, SKIPPED_RESPONSE_HEADERS};
+use common::models::{
+    AppliedFilter, REQUEST_ERROR_CONNECT, REQUEST_ERROR_TIMEOUT, REQUEST_ERROR_TRANSPORT,
+};
+use common::sse::find_stop_reason;
+use common::truncate::to_truncated_json;
+use common::url::{mask_query_params, override_query_params};
+use serde_json::value::RawValue;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use sqlx::SqlitePool;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::LazyLock;
 
-use crate::sse;
+use crate::{client::ClientInfo, count_tokens, sse, tool_calls::extract_answered_tool_calls};
+
+/// Header carrying the beta features a request opts into.
+const ANTHROPIC_BETA_HEADER: &str = "anthropic-beta";
 
 /// Loaded filter state for a profile.
 pub struct ActiveFilters {
@@ -82,8 +96,41 @@ pub struct ParsedRequestBody {
     pub messages_json: Option<String>,
     pub system_json: Option<String>,
     pub params_json: Option<String>,
+    pub estimated_input_tokens: Option<i64>,
+    /// Size of the body as the client sent it.
+    pub request_bytes: Option<i64>,
+    /// See `hash_request_body`.
+    pub body_hash: Option<String>,
+    /// JSON array of the `AnsweredToolCall`s, `None` when there are none.
+    pub tool_calls_json: Option<String>,
+}
+
+/// Hex SHA-256 of a JSON body serialized compactly with its keys sorted, so
+/// resends that differ only in key order or whitespace hash the same.
+pub fn hash_request_body(data: &Value) -> anyhow::Result<String> {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, data)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// The serialized body and the fields stored apart from it, which make up
+/// most of the per-request serialization work.
+struct SerializedBodyFields {
+    body_json: String,
+    truncated_json: Option<String>,
+    tools_json: Option<String>,
+    messages_json: Option<String>,
+    system_json: Option<String>,
+    params_json: Option<String>,
 }
 
+/// Top-level body fields stored in their own columns rather than with the
+/// request params.
+const SEPARATE_BODY_FIELDS: [&str; 3] = ["tools", "messages", "system"];
+
+/// Top-level fields of a JSON object body, as the client sent them.
+type RawBodyFields<'a> = BTreeMap<String, &'a RawValue>;
+
 /// Extract common fields (model, tools, messages, system, params, truncated body)
 /// from a parsed JSON value. If `model_override` is provided, it is used only when
 /// the body does not already contain a "model" field.
@@ -91,90 +138,181 @@ pub fn extract_request_fields(
     data: &Value,
     model_override: Option<String>,
 ) -> anyhow::Result<ParsedRequestBody> {
-    let truncated = truncate_strings(data, 100);
-
-    let model = data
-        .get("model")
-        .and_then(|field| field.as_str())
-        .map(|string| string.to_string())
-        .or(model_override);
-
-    let tools_json = data
-        .get("tools")
-        .filter(|field| field.is_array())
-        .map(serde_json::to_string)
-        .transpose()?;
-
-    let messages_json = data
-        .get("messages")
-        .filter(|field| field.is_array())
-        .map(serde_json::to_string)
-        .transpose()?;
-
-    let system_json = data
-        .get("system")
-        .map(serde_json::to_string_pretty)
-        .transpose()?;
-
     let other_params: serde_json::Map<String, Value> = data
         .as_object()
         .map(|obj| {
             obj.iter()
-                .filter(|(key, _)| !matches!(key.as_str(), "tools" | "messages" | "system"))
+                .filter(|(key, _)| !SEPARATE_BODY_FIELDS.contains(&key.as_str()))
                 .map(|(key, value)| (key.clone(), value.clone()))
                 .collect()
         })
         .unwrap_or_default();
-    let params_json = if other_params.is_empty() {
-        None
-    } else {
-        Some(serde_json::to_string_pretty(&Value::Object(other_params))?)
+    let serialized_fields = SerializedBodyFields {
+        body_json: serde_json::to_string_pretty(data)?,
+        truncated_json: Some(to_truncated_json(data, None)?),
+        tools_json: data
+            .get("tools")
+            .filter(|field| field.is_array())
+            .map(serde_json::to_string)
+            .transpose()?,
+        messages_json: data
+            .get("messages")
+            .filter(|field| field.is_array())
+            .map(serde_json::to_string)
+            .transpose()?,
+        system_json: data
+            .get("system")
+            .map(serde_json::to_string_pretty)
+            .transpose()?,
+        params_json: (!other_params.is_empty())
+            .then(|| serde_json::to_string_pretty(&Value::Object(other_params)))
+            .transpose()?,
     };
+    build_request_fields(data, model_override, serialized_fields)
+}
+
+/// Like `extract_request_fields`, but the body and its stored fields are
+/// kept as the client sent them instead of being serialized again.
+fn extract_raw_request_fields(
+    body_str: &str,
+    raw_fields: &RawBodyFields,
+    data: &Value,
+    model_override: Option<String>,
+) -> anyhow::Result<ParsedRequestBody> {
+    let other_params: BTreeMap<&str, &RawValue> = raw_fields
+        .iter()
+        .filter(|(key, _)| !SEPARATE_BODY_FIELDS.contains(&key.as_str()))
+        .map(|(key, raw_value)| (key.as_str(), *raw_value))
+        .collect();
+    let serialized_fields = SerializedBodyFields {
+        body_json: body_str.to_string(),
+        truncated_json: Some(to_truncated_json(data, None)?),
+        tools_json: find_raw_array(raw_fields, "tools"),
+        messages_json: find_raw_array(raw_fields, "messages"),
+        system_json: raw_fields
+            .get("system")
+            .map(|raw_value| raw_value.get().to_string()),
+        params_json: (!other_params.is_empty())
+            .then(|| serde_json::to_string(&other_params))
+            .transpose()?,
+    };
+    build_request_fields(data, model_override, serialized_fields)
+}
+
+fn find_raw_array(raw_fields: &RawBodyFields, key: &str) -> Option<String> {
+    raw_fields
+        .get(key)
+        .map(|raw_value| raw_value.get())
+        .filter(|json| json.starts_with('['))
+        .map(str::to_string)
+}
+
+/// Split a JSON object body into its top-level fields without parsing their
+/// values. `None` for bodies that are not a UTF-8 JSON object.
+fn parse_raw_body_fields(body: &[u8]) -> Option<(&str, RawBodyFields<'_>)> {
+    let body_str = std::str::from_utf8(body).ok()?;
+    let raw_fields = serde_json::from_str::<RawBodyFields>(body_str).ok()?;
+    Some((body_str, raw_fields))
+}
+
+/// Build the body's value from its raw fields, parsing each field once.
+fn build_body_value(raw_fields: &RawBodyFields) -> anyhow::Result<Value> {
+    let object = raw_fields
+        .iter()
+        .map(|(key, raw_value)| Ok((key.clone(), serde_json::from_str(raw_value.get())?)))
+        .collect::<anyhow::Result<serde_json::Map<String, Value>>>()?;
+    Ok(Value::Object(object))
+}
+
+fn build_request_fields(
+    data: &Value,
+    model_override: Option<String>,
+    serialized_fields: SerializedBodyFields,
+) -> anyhow::Result<ParsedRequestBody> {
+    let model = data
+        .get("model")
+        .and_then(|field| field.as_str())
+        .map(|string| string.to_string())
+        .or(model_override);
+
+    let answered_tool_calls = extract_answered_tool_calls(data);
+    let tool_calls_json = (!answered_tool_calls.is_empty())
+        .then(|| serde_json::to_string(&answered_tool_calls))
+        .transpose()?;
 
     Ok(ParsedRequestBody {
-        body_json: Some(serde_json::to_string_pretty(data)?),
-        truncated_json: Some(serde_json::to_string_pretty(&truncated)?),
+        body_json: Some(serialized_fields.body_json),
+        truncated_json: serialized_fields.truncated_json,
         model,
-        tools_json,
-        messages_json,
-        system_json,
-        params_json,
+        tools_json: serialized_fields.tools_json,
+        messages_json: serialized_fields.messages_json,
+        system_json: serialized_fields.system_json,
+        params_json: serialized_fields.params_json,
+        estimated_input_tokens: None,
+        request_bytes: None,
+        body_hash: Some(hash_request_body(data)?),
+        tool_calls_json,
     })
 }
 
 /// Metadata for a request log entry (everything except the parsed body fields).
 pub struct RequestMeta<'a> {
+    /// Id assigned before logging, e.g. the proxied request's correlation id;
+    /// a new one is generated when unset.
+    pub id: Option<&'a str>,
     pub pool: &'a SqlitePool,
     pub session_id: &'a str,
     pub method: &'a str,
     pub path: &'a str,
     pub headers_json: Option<&'a str>,
     pub note: Option<&'a str>,
+    /// The client that sent the request; unset for requests the proxy makes itself.
+    pub client: Option<&'a ClientInfo>,
+    /// The request a WebFetch follow-up round belongs to.
+    pub parent_request_id: Option<&'a str>,
 }
 
-/// Insert a request record into the DB. Returns the request ID on success, None on failure.
+/// Queue a request record for the DB writer. Returns the request ID.
 pub async fn log_request(
     meta: &RequestMeta<'_>,
-    fields: &ParsedRequestBody,
+    fields: ParsedRequestBody,
 ) -> anyhow::Result<String> {
-    db::create_request(
+    let id = meta
+        .id
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let request = db::NewRequest {
+        session_id: meta.session_id.to_string(),
+        method: meta.method.to_string(),
+        path: meta.path.to_string(),
+        headers_json: meta.headers_json.map(str::to_string),
+        body_json: fields.body_json,
+        truncated_json: fields.truncated_json,
+        model: fields.model,
+        tools_json: fields.tools_json,
+        messages_json: fields.messages_json,
+        system_json: fields.system_json,
+        params_json: fields.params_json,
+        estimated_input_tokens: fields.estimated_input_tokens,
+        note: meta.note.map(str::to_string),
+        started_at_ms: Some(Utc::now().timestamp_millis()),
+        request_bytes: fields.request_bytes,
+        body_hash: fields.body_hash,
+        tool_calls_json: fields.tool_calls_json,
+        client_user_agent: meta.client.and_then(|client| client.user_agent.clone()),
+        client_app: meta.client.and_then(|client| client.app.clone()),
+        client_addr: meta.client.and_then(|client| client.addr.clone()),
+        parent_request_id: meta.parent_request_id.map(str::to_string),
+    };
+    db::write_request(
         meta.pool,
-        &db::CreateRequestParams {
-            session_id: meta.session_id,
-            method: meta.method,
-            path: meta.path,
-            headers_json: meta.headers_json,
-            body_json: fields.body_json.as_deref(),
-            truncated_json: fields.truncated_json.as_deref(),
-            model: fields.model.as_deref(),
-            tools_json: fields.tools_json.as_deref(),
-            messages_json: fields.messages_json.as_deref(),
-            system_json: fields.system_json.as_deref(),
-            params_json: fields.params_json.as_deref(),
-            note: meta.note,
+        db::RequestWrite::Create {
+            id: id.clone(),
+            request: Box::new(request),
         },
     )
-    .await
+    .await?;
+    Ok(id)
 }
 
 /// Store a buffered response (with optional SSE event parsing) into the DB.
@@ -186,20 +324,191 @@ pub async fn store_response(
     response_body: &str,
 ) -> anyhow::Result<()> {
     let events = sse::parse_sse_events(response_body);
-    let events_json = serde_json::to_string(&events)?;
+    store_response_events(pool, request_id, status, resp_headers_json, response_body, &events).await
+}
+
+/// Store a buffered response from an OpenAI-compatible upstream, decoding
+/// chat-completions chunks into Messages API events.
+pub async fn store_chat_completion_response(
+    pool: &SqlitePool,
+    request_id: &str,
+    status: u16,
+    resp_headers_json: Option<&str>,
+    response_body: &str,
+) -> anyhow::Result<()> {
+    let events = sse::parse_chat_completion_events(response_body);
+    store_response_events(pool, request_id, status, resp_headers_json, response_body, &events).await
+}
 
-    db::set_request_response(
+async fn store_response_events(
+    pool: &SqlitePool,
+    request_id: &str,
+    status: u16,
+    resp_headers_json: Option<&str>,
+    response_body: &str,
+    events: &[Value],
+) -> anyhow::Result<()> {
+    let events_json = serde_json::to_string(events)?;
+    db::write_request(
         pool,
-        request_id,
-        status as i64,
-        resp_headers_json,
-        Some(response_body),
-        Some(&events_json),
+        db::RequestWrite::Response {
+            request_id: request_id.to_string(),
+            status: status as i64,
+            headers_json: resp_headers_json.map(str::to_string),
+            body: Some(response_body.to_string()),
+            events_json: Some(events_json),
+            responded_at_ms: Some(Utc::now().timestamp_millis()),
+        },
     )
     .await?;
+    store_stop_reason(pool, request_id, response_body, events).await;
     Ok(())
 }
 
+/// The `stop_reason` of a response: from its `message_delta` event when
+/// streamed, otherwise from the JSON body.
+fn extract_stop_reason(response_body: &str, events: &[Value]) -> Option<String> {
+    find_stop_reason(events).or_else(|| {
+        serde_json::from_str::<Value>(response_body)
+            .ok()?
+            .get("stop_reason")?
+            .as_str()
+            .map(str::to_string)
+    })
+}
+
+/// Record the `stop_reason` of a request's response, if it has one.
+pub async fn store_stop_reason(
+    pool: &SqlitePool,
+    request_id: &str,
+    response_body: &str,
+    events: &[Value],
+) {
+    let Some(stop_reason) = extract_stop_reason(response_body, events) else {
+        return;
+    };
+    let write = db::RequestWrite::StopReason {
+        request_id: request_id.to_string(),
+        stop_reason,
+    };
+    if let Err(e) = db::write_request(pool, write).await {
+        log::warn!("Failed to store stop reason: {}", e);
+    }
+}
+
+/// Record the size of a request's body as sent upstream.
+pub async fn store_forwarded_bytes(pool: &SqlitePool, request_id: &str, forwarded_bytes: usize) {
+    let write = db::RequestWrite::ForwardedBytes {
+        request_id: request_id.to_string(),
+        forwarded_bytes: forwarded_bytes as i64,
+    };
+    if let Err(e) = db::write_request(pool, write).await {
+        log::warn!("Failed to store forwarded size: {}", e);
+    }
+}
+
+/// Record the `anthropic-beta` header a request was forwarded with.
+pub async fn store_anthropic_beta(pool: &SqlitePool, request_id: &str, anthropic_beta: &str) {
+    let write = db::RequestWrite::AnthropicBeta {
+        request_id: request_id.to_string(),
+        anthropic_beta: anthropic_beta.to_string(),
+    };
+    if let Err(e) = db::write_request(pool, write).await {
+        log::warn!("Failed to store anthropic-beta: {}", e);
+    }
+}
+
+/// Record what the filter profile removed or renamed. Nothing is stored
+/// when the profile changed nothing.
+pub async fn store_applied_filters(
+    pool: &SqlitePool,
+    request_id: &str,
+    applied_filters: &[AppliedFilter],
+) {
+    if applied_filters.is_empty() {
+        return;
+    }
+    let applied_filters_json = match serde_json::to_string(applied_filters) {
+        Ok(applied_filters_json) => applied_filters_json,
+        Err(e) => {
+            log::warn!("Failed to serialize applied filters: {}", e);
+            return;
+        }
+    };
+    let write = db::RequestWrite::AppliedFilters {
+        request_id: request_id.to_string(),
+        applied_filters_json,
+    };
+    if let Err(e) = db::write_request(pool, write).await {
+        log::warn!("Failed to store applied filters: {}", e);
+    }
+}
+
+/// The `error_kind` for a failed upstream call.
+pub fn classify_upstream_error(e: &reqwest::Error) -> &'static str {
+    if e.is_timeout() {
+        REQUEST_ERROR_TIMEOUT
+    } else if e.is_connect() {
+        REQUEST_ERROR_CONNECT
+    } else {
+        REQUEST_ERROR_TRANSPORT
+    }
+}
+
+/// Record why a request's upstream call failed.
+pub async fn store_error_kind(pool: &SqlitePool, request_id: &str, error_kind: &str) {
+    let write = db::RequestWrite::ErrorKind {
+        request_id: request_id.to_string(),
+        error_kind: error_kind.to_string(),
+    };
+    if let Err(e) = db::write_request(pool, write).await {
+        log::warn!("Failed to store error kind: {}", e);
+    }
+}
+
+/// Answer a failed upstream call with an Anthropic-style `api_error` 502,
+/// storing it and the failure's kind on the request.
+pub async fn reject_upstream_failure(
+    pool: &SqlitePool,
+    request_id: &str,
+    error_kind: &str,
+    message: &str,
+) -> HttpResponse {
+    log::warn!("Upstream call failed ({}): {}", error_kind, message);
+    let error_body = serde_json::json!({
+        "type": "error",
+        "error": {"type": "api_error", "message": message},
+    })
+    .to_string();
+    if let Err(e) = store_response(pool, request_id, 502, None, &error_body).await {
+        log::warn!("Failed to store upstream failure: {}", e);
+    }
+    store_error_kind(pool, request_id, error_kind).await;
+    HttpResponse::BadGateway()
+        .content_type("application/json")
+        .body(error_body)
+}
+
+/// Answer a request a hook refused with an Anthropic-style
+/// `invalid_request_error` 400, storing it on the request.
+pub async fn reject_hooked_request(
+    pool: &SqlitePool,
+    request_id: &str,
+    message: &str,
+) -> HttpResponse {
+    let error_body = serde_json::json!({
+        "type": "error",
+        "error": {"type": "invalid_request_error", "message": message},
+    })
+    .to_string();
+    if let Err(e) = store_response(pool, request_id, 400, None, &error_body).await {
+        log::warn!("Failed to store hook rejection: {}", e);
+    }
+    HttpResponse::BadRequest()
+        .content_type("application/json")
+        .body(error_body)
+}
+
 /// Build an error injection response with the correct HTTP status code and JSON body.
 /// Returns `Some(HttpResponse)` if the key is a known error type, `None` otherwise.
 pub fn build_injected_sse_error(error_type: &str) -> Option<HttpResponse> {
@@ -219,14 +528,18 @@ pub fn to_actix_status(status: u16) -> Result<StatusCode, actix_web::Error> {
 }
 
 /// Copy upstream response headers into an actix HttpResponseBuilder,
-/// skipping transfer-encoding and content-encoding.
+/// skipping transfer-encoding, content-encoding and headers the session's
+/// response header rules don't allow.
 pub fn forward_response_headers(
     builder: &mut HttpResponseBuilder,
     upstream_headers: &reqwest::header::HeaderMap,
+    header_rules: &HeaderRules,
 ) {
     for (key, value) in upstream_headers {
         let header_name_lower = key.as_str().to_lowercase();
-        if header_name_lower == "transfer-encoding" || header_name_lower == "content-encoding" {
+        if SKIPPED_RESPONSE_HEADERS.contains(&header_name_lower.as_str())
+            || !header_rules.is_allowed(&header_name_lower)
+        {
             continue;
         }
         if let Ok(name) = actix_web::http::header::HeaderName::from_bytes(key.as_ref()) {
@@ -271,43 +584,57 @@ pub fn actix_headers_iter(
 }
 
 /// Build the full target URL from a session's base URL, the request path, and
-/// an optional query string.
-pub fn build_target_url(base_url: &str, path: &str, query: Option<&str>) -> String {
+/// an optional query string, with the session's query auth parameters added
+/// over any the client sent.
+pub fn build_target_url(
+    base_url: &str,
+    path: &str,
+    query: Option<&str>,
+    auth_query_params: &[(String, String)],
+) -> String {
     let target_path = if path.is_empty() {
         String::new()
     } else {
         format!("/{}", path)
     };
     let mut url = format!("{}{}", base_url.trim_end_matches('/'), target_path);
-    if let Some(qs) = query {
+    if let Some(qs) = override_query_params(query, auth_query_params) {
         url.push('?');
-        url.push_str(qs);
+        url.push_str(&qs);
     }
     url
 }
 
-/// Build the stored path shown in the UI (always prefixed with `/`).
-pub fn build_stored_path(path: &str, query: Option<&str>) -> String {
+/// Build the stored path shown in the UI (always prefixed with `/`), with the
+/// values of the session's query auth parameters masked.
+pub fn build_stored_path(
+    path: &str,
+    query: Option<&str>,
+    auth_query_params: &[(String, String)],
+) -> String {
     let p = format!("/{}", path);
     if let Some(qs) = query {
-        format!("{}?{}", p, qs)
+        mask_query_params(&format!("{}?{}", p, qs), auth_query_params)
     } else {
         p
     }
 }
 
 /// Copy headers from an actix HttpRequest into a reqwest HeaderMap, skipping
-/// the `Host` header. If `auth_header` is provided, it is injected as the
-/// `Authorization` header. If `x_api_key` is provided, it is injected as
-/// the `x-api-key` header.
+/// the `Host` header and headers the session's forward header rules don't
+/// allow. If `auth_header` is provided, it is injected as the `Authorization`
+/// header. If `x_api_key` is provided, it is injected as the `x-api-key`
+/// header.
 pub fn build_forward_headers(
     req: &HttpRequest,
+    header_rules: &HeaderRules,
     auth_header: Option<&str>,
     x_api_key: Option<&str>,
 ) -> reqwest::header::HeaderMap {
     let mut header_map = reqwest::header::HeaderMap::new();
     for (key, value) in req.headers() {
-        if key.as_str().eq_ignore_ascii_case("host") {
+        if SKIPPED_FORWARD_HEADERS.contains(&key.as_str()) || !header_rules.is_allowed(key.as_str())
+        {
             continue;
         }
         if let Ok(name) = reqwest::header::HeaderName::from_bytes(key.as_ref()) {
@@ -316,6 +643,16 @@ pub fn build_forward_headers(
             }
         }
     }
+    apply_session_auth_headers(&mut header_map, auth_header, x_api_key);
+    header_map
+}
+
+/// Inject a session's `Authorization` and `x-api-key` overrides into a header map.
+pub fn apply_session_auth_headers(
+    header_map: &mut reqwest::header::HeaderMap,
+    auth_header: Option<&str>,
+    x_api_key: Option<&str>,
+) {
     if let Some(auth_value) = auth_header {
         if let Ok(header_value) = reqwest::header::HeaderValue::from_str(auth_value) {
             header_map.insert(reqwest::header::AUTHORIZATION, header_value);
@@ -329,24 +666,137 @@ pub fn build_forward_headers(
             );
         }
     }
-    header_map
 }
 
-/// Parse the request body and extract fields for DB logging.
-/// Returns `(ParsedRequestBody, optional_note)`.
+/// Headers copied from a logged request onto requests the proxy makes on its
+/// own behalf, such as batch polls.
+const REPLAY_HEADER_NAMES: &[&str] = &[
+    "x-api-key",
+    "authorization",
+    "anthropic-version",
+    "anthropic-beta",
+];
+
+/// Rebuild upstream headers from a logged request's `headers_json`, with the
+/// session's auth overrides applied on top.
+pub fn build_replay_headers(
+    headers_json: Option<&str>,
+    session: &common::models::Session,
+) -> anyhow::Result<reqwest::header::HeaderMap> {
+    let stored_headers: HashMap<String, String> = headers_json
+        .map(serde_json::from_str)
+        .transpose()?
+        .unwrap_or_default();
+    let mut header_map = reqwest::header::HeaderMap::new();
+    for (key, value) in &stored_headers {
+        if !REPLAY_HEADER_NAMES.contains(&key.to_lowercase().as_str()) {
+            continue;
+        }
+        if let (Ok(name), Ok(header_value)) = (
+            reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            header_map.insert(name, header_value);
+        }
+    }
+    apply_session_auth_headers(
+        &mut header_map,
+        session.auth_header.as_deref(),
+        session.x_api_key.as_deref(),
+    );
+    Ok(header_map)
+}
+
+/// Merge the headers of the session's header preset into a header map,
+/// replacing any the client sent. Unknown preset keys are ignored.
+pub fn apply_header_preset(
+    header_map: &mut reqwest::header::HeaderMap,
+    header_preset: Option<&str>,
+) {
+    let Some(header_preset) = header_preset.and_then(header_presets::find_by_key) else {
+        return;
+    };
+    for (name, value) in header_preset.headers {
+        header_map.insert(
+            reqwest::header::HeaderName::from_static(name),
+            reqwest::header::HeaderValue::from_static(value),
+        );
+    }
+}
+
+/// Apply the session's beta rules to the forwarded `anthropic-beta` header,
+/// removing it when no betas are left. Returns the header as forwarded.
+pub fn apply_beta_rules(
+    header_map: &mut reqwest::header::HeaderMap,
+    beta_rules: &BetaRules,
+) -> Option<String> {
+    let anthropic_beta = header_map
+        .get(ANTHROPIC_BETA_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    if beta_rules.is_empty() {
+        return anthropic_beta;
+    }
+    let anthropic_beta = beta_rules.apply(anthropic_beta.as_deref());
+    match anthropic_beta
+        .as_deref()
+        .and_then(|beta| reqwest::header::HeaderValue::from_str(beta).ok())
+    {
+        Some(header_value) => {
+            header_map.insert(ANTHROPIC_BETA_HEADER, header_value);
+        }
+        None => {
+            header_map.remove(ANTHROPIC_BETA_HEADER);
+        }
+    }
+    anthropic_beta
+}
+
+/// Parse the request body and extract fields for DB logging. Requests to the
+/// Token Counting endpoint also get a local input token estimate.
+/// Returns `(ParsedRequestBody, parsed_body, optional_note)`; the parsed body
+/// is `None` unless the body is JSON, and is handed on to the request hooks.
 pub fn parse_body_fields(
     body: &[u8],
+    path: &str,
     url_model: Option<String>,
-) -> anyhow::Result<(ParsedRequestBody, Option<String>)> {
+) -> anyhow::Result<(ParsedRequestBody, Option<Value>, Option<String>)> {
+    let body_fields = ParsedRequestBody {
+        request_bytes: Some(body.len() as i64),
+        ..ParsedRequestBody::default()
+    };
     if body.is_empty() {
-        Ok((ParsedRequestBody::default(), Some("no body".to_string())))
-    } else if let Ok(data) = serde_json::from_slice::<Value>(body) {
-        Ok((extract_request_fields(&data, url_model)?, None))
-    } else {
-        Ok((
-            ParsedRequestBody::default(),
-            Some(format!("non-JSON body, {} bytes", body.len())),
-        ))
+        return Ok((body_fields, None, Some("no body".to_string())));
+    }
+    let (fields, data) = match parse_raw_body_fields(body) {
+        Some((body_str, raw_fields)) => {
+            let data = build_body_value(&raw_fields)?;
+            let fields = extract_raw_request_fields(body_str, &raw_fields, &data, url_model)?;
+            (fields, data)
+        }
+        None => match serde_json::from_slice::<Value>(body) {
+            Ok(data) => (extract_request_fields(&data, url_model)?, data),
+            Err(_) => {
+                return Ok((
+                    body_fields,
+                    None,
+                    Some(format!("non-JSON body, {} bytes", body.len())),
+                ))
+            }
+        },
+    };
+    let mut fields = ParsedRequestBody {
+        request_bytes: body_fields.request_bytes,
+        ..fields
+    };
+    add_token_estimate(&mut fields, path, &data);
+    Ok((fields, Some(data), None))
+}
+
+/// Requests to the Token Counting endpoint get a local input token estimate.
+fn add_token_estimate(fields: &mut ParsedRequestBody, path: &str, data: &Value) {
+    if count_tokens::is_count_tokens_path(path) {
+        fields.estimated_input_tokens = Some(count_tokens::estimate_input_tokens(data));
     }
 }
 
@@ -389,6 +839,77 @@ mod tests {
         assert_eq!(fields.model.as_deref(), Some("body-model"));
     }
 
+    #[test]
+    fn parse_body_fields_hashes_key_order_and_whitespace_alike() {
+        let (fields, _, _) =
+            parse_body_fields(br#"{"model": "m", "max_tokens": 1}"#, "v1/messages", None).unwrap();
+        let (reordered_fields, _, _) =
+            parse_body_fields(br#"{"max_tokens":1,"model":"m"}"#, "v1/messages", None).unwrap();
+        let (other_fields, _, _) =
+            parse_body_fields(br#"{"max_tokens":2,"model":"m"}"#, "v1/messages", None).unwrap();
+        assert!(fields.body_hash.is_some());
+        assert_eq!(fields.body_hash, reordered_fields.body_hash);
+        assert_ne!(fields.body_hash, other_fields.body_hash);
+    }
+
+    #[test]
+    fn parse_body_fields_keeps_large_fields_as_sent() {
+        let body = br#"{"model": "m", "messages": [{"role": "user", "content": "hi"}], "tools": [ {"name": "search"} ], "system": [ {"type": "text", "text": "s"} ], "metadata": { "user_id": "u" }}"#;
+        let (fields, parsed_body, _) = parse_body_fields(body, "v1/messages", None).unwrap();
+        assert_eq!(
+            fields.body_json.as_deref().map(str::as_bytes),
+            Some(&body[..])
+        );
+        assert_eq!(
+            fields.messages_json.as_deref(),
+            Some(r#"[{"role": "user", "content": "hi"}]"#)
+        );
+        assert_eq!(
+            fields.tools_json.as_deref(),
+            Some(r#"[ {"name": "search"} ]"#)
+        );
+        assert_eq!(
+            fields.system_json.as_deref(),
+            Some(r#"[ {"type": "text", "text": "s"} ]"#)
+        );
+        assert_eq!(
+            fields.params_json.as_deref(),
+            Some(r#"{"metadata":{ "user_id": "u" },"model":"m"}"#)
+        );
+        let data: Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(fields.body_hash, Some(hash_request_body(&data).unwrap()));
+        assert_eq!(parsed_body, Some(data));
+        let (array_fields, _, _) = parse_body_fields(b"[1, 2]", "v1/messages", None).unwrap();
+        assert!(array_fields.body_json.is_some());
+        assert!(array_fields.messages_json.is_none());
+    }
+
+    #[test]
+    fn extract_stop_reason_prefers_events_then_body() {
+        let events = vec![serde_json::json!({
+            "event": "message_delta",
+            "data": {"delta": {"stop_reason": "max_tokens"}}
+        })];
+        assert_eq!(
+            extract_stop_reason("", &events).as_deref(),
+            Some("max_tokens")
+        );
+        assert_eq!(
+            extract_stop_reason(r#"{"stop_reason":"refusal"}"#, &[]).as_deref(),
+            Some("refusal")
+        );
+        assert_eq!(extract_stop_reason("not json", &[]), None);
+    }
+
+    #[test]
+    fn parse_count_tokens_body_adds_estimate() {
+        let body = br#"{"model": "m", "messages": [{"role": "user", "content": "hi"}]}"#;
+        let (fields, _, _) = parse_body_fields(body, "v1/messages/count_tokens", None).unwrap();
+        assert_eq!(fields.estimated_input_tokens, Some(4));
+        let (fields, _, _) = parse_body_fields(body, "v1/messages", None).unwrap();
+        assert_eq!(fields.estimated_input_tokens, None);
+    }
+
     #[test]
     fn headers_to_json_basic() {
         let headers = vec![
@@ -407,4 +928,57 @@ mod tests {
         let parsed: HashMap<String, String> = serde_json::from_str(&json).unwrap();
         assert!(parsed.is_empty());
     }
+
+    #[test]
+    fn apply_header_preset_replaces_client_headers() {
+        for header_preset in header_presets::HEADER_PRESETS {
+            let mut header_map = reqwest::header::HeaderMap::new();
+            header_map.insert("user-agent", "my-client/1.0".parse().unwrap());
+            header_map.insert("content-type", "application/json".parse().unwrap());
+            apply_header_preset(&mut header_map, Some(header_preset.key));
+            for (name, value) in header_preset.headers {
+                assert_eq!(header_map.get(*name).unwrap(), value);
+            }
+            assert_eq!(header_map.get("content-type").unwrap(), "application/json");
+        }
+        let mut header_map = reqwest::header::HeaderMap::new();
+        apply_header_preset(&mut header_map, Some("unknown"));
+        assert!(header_map.is_empty());
+    }
+
+    #[test]
+    fn apply_beta_rules_rewrites_anthropic_beta() {
+        let beta_rules = BetaRules::parse(Some("output-128k-2025-02-19"), Some("old-beta"));
+        let mut header_map = reqwest::header::HeaderMap::new();
+        header_map.insert(
+            ANTHROPIC_BETA_HEADER,
+            "old-beta,tools-beta".parse().unwrap(),
+        );
+        assert_eq!(
+            apply_beta_rules(&mut header_map, &beta_rules).as_deref(),
+            Some("tools-beta,output-128k-2025-02-19")
+        );
+        assert_eq!(
+            header_map.get(ANTHROPIC_BETA_HEADER).unwrap(),
+            "tools-beta,output-128k-2025-02-19"
+        );
+        let beta_rules = BetaRules::parse(None, Some("old-beta"));
+        let mut header_map = reqwest::header::HeaderMap::new();
+        header_map.insert(ANTHROPIC_BETA_HEADER, "old-beta".parse().unwrap());
+        assert_eq!(apply_beta_rules(&mut header_map, &beta_rules), None);
+        assert!(header_map.get(ANTHROPIC_BETA_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn classify_upstream_error_refused_connection() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let e = reqwest::Client::new()
+            .get(&closed_url)
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(classify_upstream_error(&e), REQUEST_ERROR_CONNECT);
+    }
 }
@@ -0,0 +1,251 @@
+//! AWS Signature Version 4 signing for Bedrock sessions that forward to AWS directly.
+
+use chrono::{DateTime, Utc};
+use common::{models::Session, url::encode_uri_component};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use sha2::{Digest, Sha256};
+
+const SIGNING_ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Access keys used to sign a request.
+#[derive(Debug, Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Set for temporary credentials, e.g. from an assumed role.
+    pub session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    /// The session's keys, or `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+    /// `AWS_SESSION_TOKEN` from the environment when the session has none.
+    pub fn resolve(session: &Session) -> Option<Self> {
+        match (
+            session.aws_access_key_id.as_deref(),
+            session.aws_secret_access_key.as_deref(),
+        ) {
+            (Some(access_key_id), Some(secret_access_key)) => Some(AwsCredentials {
+                access_key_id: access_key_id.to_string(),
+                secret_access_key: secret_access_key.to_string(),
+                session_token: session.aws_session_token.clone(),
+            }),
+            _ => Self::from_env(),
+        }
+    }
+
+    fn from_env() -> Option<Self> {
+        let read_var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Some(AwsCredentials {
+            access_key_id: read_var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: read_var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: read_var("AWS_SESSION_TOKEN"),
+        })
+    }
+}
+
+/// Where and when a request is signed.
+pub struct SigningParams<'a> {
+    pub credentials: &'a AwsCredentials,
+    pub region: &'a str,
+    pub service: &'a str,
+    pub time: DateTime<Utc>,
+}
+
+fn hash_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Each segment of the path as sent, encoded again; non-S3 services sign the
+/// double-encoded path.
+fn build_canonical_uri(url: &url::Url) -> String {
+    let canonical_uri = url
+        .path()
+        .split('/')
+        .map(encode_uri_component)
+        .collect::<Vec<_>>()
+        .join("/");
+    if canonical_uri.is_empty() {
+        "/".to_string()
+    } else {
+        canonical_uri
+    }
+}
+
+fn build_canonical_query(url: &url::Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(key, value)| (encode_uri_component(&key), encode_uri_component(&value)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The canonical header block and the signed header list. Every header in
+/// the map is signed, along with `host`.
+fn build_canonical_headers(url: &url::Url, headers: &HeaderMap) -> (String, String) {
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or(""), port),
+        None => url.host_str().unwrap_or("").to_string(),
+    };
+    let mut entries: Vec<(String, String)> = vec![("host".to_string(), host)];
+    for name in headers.keys() {
+        let values: Vec<String> = headers
+            .get_all(name)
+            .iter()
+            .map(|value| {
+                String::from_utf8_lossy(value.as_bytes())
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        entries.push((name.as_str().to_lowercase(), values.join(",")));
+    }
+    entries.sort();
+    let canonical_headers: String = entries
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = entries
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    (canonical_headers, signed_headers)
+}
+
+/// Sign a request in place: adds `x-amz-date`, `x-amz-security-token` for
+/// temporary credentials, and the `Authorization` header.
+pub fn sign_request(
+    method: &str,
+    url: &url::Url,
+    headers: &mut HeaderMap,
+    body: &[u8],
+    params: &SigningParams<'_>,
+) -> anyhow::Result<()> {
+    let amz_date = params.time.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = params.time.format("%Y%m%d").to_string();
+    headers.insert(
+        HeaderName::from_static("x-amz-date"),
+        HeaderValue::from_str(&amz_date)?,
+    );
+    if let Some(session_token) = &params.credentials.session_token {
+        headers.insert(
+            HeaderName::from_static("x-amz-security-token"),
+            HeaderValue::from_str(session_token)?,
+        );
+    }
+
+    let (canonical_headers, signed_headers) = build_canonical_headers(url, headers);
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        build_canonical_uri(url),
+        build_canonical_query(url),
+        canonical_headers,
+        signed_headers,
+        hash_hex(body)
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, params.region, params.service);
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        SIGNING_ALGORITHM,
+        amz_date,
+        scope,
+        hash_hex(canonical_request.as_bytes())
+    );
+
+    let secret = format!("AWS4{}", params.credentials.secret_access_key);
+    let date_key = hmac_sha256(secret.as_bytes(), &date);
+    let region_key = hmac_sha256(&date_key, params.region);
+    let service_key = hmac_sha256(&region_key, params.service);
+    let signing_key = hmac_sha256(&service_key, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+
+    let authorization = format!(
+        "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+        SIGNING_ALGORITHM, params.credentials.access_key_id, scope, signed_headers, signature
+    );
+    headers.insert(AUTHORIZATION, HeaderValue::from_str(&authorization)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn example_credentials() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+        }
+    }
+
+    #[test]
+    fn sign_request_matches_aws_example() {
+        // The IAM ListUsers example from the AWS SigV4 documentation.
+        let url = url::Url::parse("https://iam.amazonaws.com/?Action=ListUsers&Version=2010-05-08")
+            .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            reqwest::header::CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded; charset=utf-8"),
+        );
+        let credentials = example_credentials();
+        let params = SigningParams {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "iam",
+            time: Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap(),
+        };
+        sign_request("GET", &url, &mut headers, b"", &params).unwrap();
+        assert_eq!(headers["x-amz-date"], "20150830T123600Z");
+        assert_eq!(
+            headers[AUTHORIZATION],
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn sign_request_adds_session_token_and_double_encodes_path() {
+        let url = url::Url::parse(
+            "https://bedrock-runtime.us-east-1.amazonaws.com/model/anthropic.claude-v2%3A1/invoke",
+        )
+        .unwrap();
+        assert_eq!(
+            build_canonical_uri(&url),
+            "/model/anthropic.claude-v2%253A1/invoke"
+        );
+        let credentials = AwsCredentials {
+            session_token: Some("token".to_string()),
+            ..example_credentials()
+        };
+        let mut headers = HeaderMap::new();
+        let params = SigningParams {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "bedrock",
+            time: Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+        };
+        sign_request("POST", &url, &mut headers, b"{}", &params).unwrap();
+        assert_eq!(headers["x-amz-security-token"], "token");
+        let authorization = headers[AUTHORIZATION].to_str().unwrap();
+        assert!(authorization.contains("/20260102/us-east-1/bedrock/aws4_request"));
+        assert!(authorization.contains("SignedHeaders=host;x-amz-date;x-amz-security-token"));
+    }
+}
@@ -1,3 +1,47 @@
+/// One line of an event stream, classified by the SSE line grammar.
+enum SseLine<'a> {
+    Blank,
+    /// A `:` line, e.g. a keepalive.
+    Comment,
+    Event(&'a str),
+    Data(&'a str),
+    /// `id` and `retry`, which the proxy has no use for.
+    Ignored,
+    /// An unknown field name.
+    Unparsed,
+}
+
+fn classify_sse_line(line: &str) -> SseLine<'_> {
+    if line.is_empty() {
+        return SseLine::Blank;
+    }
+    if line.starts_with(':') {
+        return SseLine::Comment;
+    }
+    // A line without a colon is a field name with an empty value.
+    let (name, value) = match line.split_once(':') {
+        Some((name, value)) => (name, value.strip_prefix(' ').unwrap_or(value)),
+        None => (line, ""),
+    };
+    match name {
+        "event" => SseLine::Event(value),
+        "data" => SseLine::Data(value),
+        "id" | "retry" => SseLine::Ignored,
+        _ => SseLine::Unparsed,
+    }
+}
+
+/// What the parser produced for a stretch of the stream.
+enum SseItem {
+    /// A dispatched `(event_type, data_str)` event.
+    Event(String, String),
+    /// A line that isn't part of the SSE grammar, kept verbatim.
+    Unparsed(String),
+}
+
+/// Incremental SSE parser. Lines may end in LF, CRLF or a lone CR, and a
+/// line or line ending may be split across chunks.
+#[derive(Default)]
 pub struct SseParser {
     buffer: String,
     current_event_type: String,
@@ -16,108 +60,119 @@ impl SseParser {
     /// Feed a chunk of text and return completed `(event_type, data_str)` pairs.
     /// `event_type` is `""` when absent.
     pub fn feed(&mut self, chunk: &str) -> Vec<(String, String)> {
+        only_events(self.feed_items(chunk))
+    }
+
+    /// Flush any remaining buffered event at end of stream.
+    pub fn flush(&mut self) -> Option<(String, String)> {
+        only_events(self.flush_items()).pop()
+    }
+
+    fn feed_items(&mut self, chunk: &str) -> Vec<SseItem> {
         self.buffer.push_str(chunk);
-        let mut events = Vec::new();
-
-        while let Some(pos) = self.buffer.find('\n') {
-            let line = self.buffer[..pos].trim_end_matches('\r').to_string();
-            self.buffer = self.buffer[pos + 1..].to_string();
-
-            if line.is_empty() {
-                if !self.current_data.is_empty() {
-                    events.push((
-                        self.current_event_type.clone(),
-                        self.current_data.join("\n"),
-                    ));
-                    self.current_data.clear();
-                    self.current_event_type.clear();
-                }
-            } else if let Some(rest) = line.strip_prefix("event:") {
-                self.current_event_type =
-                    rest.strip_prefix(' ').unwrap_or(rest).to_string();
-            } else if let Some(rest) = line.strip_prefix("data:") {
-                let data = rest.strip_prefix(' ').unwrap_or(rest);
-                self.current_data.push(data.to_string());
-            }
+        let mut items = Vec::new();
+        let mut consumed = 0;
+
+        while let Some(offset) = self.buffer[consumed..].find(['\r', '\n']) {
+            let line_end = consumed + offset;
+            let terminator_len = if self.buffer[line_end..].starts_with("\r\n") {
+                2
+            } else if self.buffer[line_end..] == *"\r" {
+                // The LF of a CRLF may arrive with the next chunk.
+                break;
+            } else {
+                1
+            };
+            let line = self.buffer[consumed..line_end].to_string();
+            consumed = line_end + terminator_len;
+            self.process_line(&line, &mut items);
         }
 
-        events
+        self.buffer.drain(..consumed);
+        items
     }
 
-    /// Flush any remaining buffered event at end of stream.
-    pub fn flush(&mut self) -> Option<(String, String)> {
-        if self.current_data.is_empty() {
-            None
-        } else {
-            let event_type = self.current_event_type.clone();
-            let data = self.current_data.join("\n");
+    fn flush_items(&mut self) -> Vec<SseItem> {
+        let mut items = Vec::new();
+        let rest = std::mem::take(&mut self.buffer);
+        let rest = rest.strip_suffix('\r').unwrap_or(&rest);
+        if !rest.is_empty() {
+            self.process_line(rest, &mut items);
+        }
+        self.dispatch(&mut items);
+        items
+    }
+
+    fn process_line(&mut self, line: &str, items: &mut Vec<SseItem>) {
+        match classify_sse_line(line) {
+            SseLine::Blank => self.dispatch(items),
+            SseLine::Event(event_type) => self.current_event_type = event_type.to_string(),
+            SseLine::Data(data) => self.current_data.push(data.to_string()),
+            SseLine::Comment | SseLine::Ignored => {}
+            SseLine::Unparsed => items.push(SseItem::Unparsed(line.to_string())),
+        }
+    }
+
+    fn dispatch(&mut self, items: &mut Vec<SseItem>) {
+        if !self.current_data.is_empty() {
+            items.push(SseItem::Event(
+                std::mem::take(&mut self.current_event_type),
+                self.current_data.join("\n"),
+            ));
             self.current_data.clear();
-            self.current_event_type.clear();
-            Some((event_type, data))
         }
+        self.current_event_type.clear();
     }
 }
 
-/// Re-serialise a parsed event back to SSE wire format.
+fn only_events(items: Vec<SseItem>) -> Vec<(String, String)> {
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            SseItem::Event(event_type, data) => Some((event_type, data)),
+            SseItem::Unparsed(_) => None,
+        })
+        .collect()
+}
+
+/// Re-serialise a parsed event back to SSE wire format, one `data:` line
+/// per line of data.
 pub fn serialize_sse_event(event_type: &str, data_str: &str) -> String {
-    if event_type.is_empty() {
-        format!("data: {}\n\n", data_str)
-    } else {
-        format!("event: {}\ndata: {}\n\n", event_type, data_str)
+    let mut wire = String::new();
+    if !event_type.is_empty() {
+        wire.push_str(&format!("event: {}\n", event_type));
     }
+    for line in data_str.split('\n') {
+        wire.push_str(&format!("data: {}\n", line));
+    }
+    wire.push('\n');
+    wire
 }
 
+/// Parse a whole SSE body into `{"event", "data"}` objects, with `data`
+/// decoded as JSON when it parses. Lines outside the SSE grammar are kept as
+/// `{"unparsed": true, "data": line}` rather than dropped.
 pub fn parse_sse_events(body: &str) -> Vec<serde_json::Value> {
-    let mut events = Vec::new();
-    let mut current_event_type = String::new();
-    let mut current_data = String::new();
-
-    for line in body.lines() {
-        if let Some(stripped) = line.strip_prefix("event:") {
-            current_event_type = stripped.trim().to_string();
-        } else if let Some(stripped) = line.strip_prefix("data:") {
-            if !current_data.is_empty() {
-                current_data.push('\n');
-            }
-            current_data.push_str(stripped.trim());
-        } else if line.trim().is_empty() && !current_data.is_empty() {
-            // Empty line = end of event
-            let data_value = match serde_json::from_str::<serde_json::Value>(&current_data) {
-                Ok(parsed) => parsed,
-                Err(_) => serde_json::Value::String(current_data.clone()),
-            };
-            let mut event = serde_json::Map::new();
-            if !current_event_type.is_empty() {
-                event.insert(
-                    "event".to_string(),
-                    serde_json::Value::String(current_event_type.clone()),
-                );
-            }
-            event.insert("data".to_string(), data_value);
-            events.push(serde_json::Value::Object(event));
-            current_data.clear();
-            current_event_type.clear();
-        }
-    }
-
-    // Handle trailing event without final blank line
-    if !current_data.is_empty() {
-        let data_value = match serde_json::from_str::<serde_json::Value>(&current_data) {
-            Ok(v) => v,
-            Err(_) => serde_json::Value::String(current_data.clone()),
-        };
-        let mut event = serde_json::Map::new();
-        if !current_event_type.is_empty() {
-            event.insert(
-                "event".to_string(),
-                serde_json::Value::String(current_event_type),
-            );
-        }
-        event.insert("data".to_string(), data_value);
-        events.push(serde_json::Value::Object(event));
-    }
+    let mut parser = SseParser::new();
+    let mut items = parser.feed_items(body);
+    items.extend(parser.flush_items());
 
-    events
+    items
+        .into_iter()
+        .map(|item| match item {
+            SseItem::Event(event_type, data) => {
+                let data_value = serde_json::from_str::<serde_json::Value>(&data)
+                    .unwrap_or(serde_json::Value::String(data));
+                let mut event = serde_json::Map::new();
+                if !event_type.is_empty() {
+                    event.insert("event".to_string(), serde_json::Value::String(event_type));
+                }
+                event.insert("data".to_string(), data_value);
+                serde_json::Value::Object(event)
+            }
+            SseItem::Unparsed(line) => serde_json::json!({"unparsed": true, "data": line}),
+        })
+        .collect()
 }
 
 /// Extract all text from `text_delta` events in a parsed SSE event list.
@@ -136,6 +191,175 @@ pub fn extract_text_from_events(events: &[serde_json::Value]) -> String {
     text
 }
 
+/// The content block a chat-completions stream is currently filling.
+#[derive(PartialEq)]
+enum OpenBlock {
+    Text,
+    /// A tool call, by its index in `delta.tool_calls`.
+    ToolCall(u64),
+}
+
+/// Turns OpenAI chat-completions chunks into the Messages API events the
+/// dashboard renders: text and tool call deltas become content blocks, and
+/// the finish reason and token usage become a `message_delta`.
+#[derive(Default)]
+struct ChatCompletionDecoder {
+    events: Vec<serde_json::Value>,
+    started: bool,
+    open_block: Option<OpenBlock>,
+    next_index: u64,
+}
+
+impl ChatCompletionDecoder {
+    fn push(&mut self, event_type: &str, mut data: serde_json::Value) {
+        data["type"] = serde_json::Value::String(event_type.to_string());
+        self.events
+            .push(serde_json::json!({"event": event_type, "data": data}));
+    }
+
+    fn close_block(&mut self) {
+        if self.open_block.take().is_some() {
+            let index = self.next_index;
+            self.next_index += 1;
+            self.push("content_block_stop", serde_json::json!({"index": index}));
+        }
+    }
+
+    fn open_block(&mut self, block: OpenBlock, content_block: serde_json::Value) {
+        self.close_block();
+        self.open_block = Some(block);
+        let index = self.next_index;
+        self.push(
+            "content_block_start",
+            serde_json::json!({"index": index, "content_block": content_block}),
+        );
+    }
+
+    fn feed_chunk(&mut self, chunk: &serde_json::Value) {
+        if !self.started {
+            self.started = true;
+            self.push(
+                "message_start",
+                serde_json::json!({"message": {
+                    "id": chunk.get("id"),
+                    "model": chunk.get("model"),
+                    "role": "assistant",
+                    "content": [],
+                }}),
+            );
+        }
+        let choice = chunk.get("choices").and_then(|choices| choices.get(0));
+        let delta = choice.and_then(|choice| choice.get("delta"));
+
+        let text = delta
+            .and_then(|delta| delta.get("content"))
+            .and_then(|field| field.as_str())
+            .filter(|text| !text.is_empty());
+        if let Some(text) = text {
+            if self.open_block != Some(OpenBlock::Text) {
+                self.open_block(
+                    OpenBlock::Text,
+                    serde_json::json!({"type": "text", "text": ""}),
+                );
+            }
+            let index = self.next_index;
+            self.push(
+                "content_block_delta",
+                serde_json::json!({"index": index, "delta": {"type": "text_delta", "text": text}}),
+            );
+        }
+
+        let tool_calls = delta
+            .and_then(|delta| delta.get("tool_calls"))
+            .and_then(|field| field.as_array());
+        for tool_call in tool_calls.into_iter().flatten() {
+            let tool_index = tool_call
+                .get("index")
+                .and_then(|field| field.as_u64())
+                .unwrap_or(0);
+            let function = tool_call.get("function");
+            if self.open_block != Some(OpenBlock::ToolCall(tool_index)) {
+                self.open_block(
+                    OpenBlock::ToolCall(tool_index),
+                    serde_json::json!({
+                        "type": "tool_use",
+                        "id": tool_call.get("id"),
+                        "name": function.and_then(|function| function.get("name")),
+                        "input": {},
+                    }),
+                );
+            }
+            let arguments = function
+                .and_then(|function| function.get("arguments"))
+                .and_then(|field| field.as_str())
+                .filter(|arguments| !arguments.is_empty());
+            if let Some(arguments) = arguments {
+                let index = self.next_index;
+                self.push(
+                    "content_block_delta",
+                    serde_json::json!({
+                        "index": index,
+                        "delta": {"type": "input_json_delta", "partial_json": arguments},
+                    }),
+                );
+            }
+        }
+
+        let finish_reason = choice
+            .and_then(|choice| choice.get("finish_reason"))
+            .and_then(|field| field.as_str());
+        let usage = chunk.get("usage").filter(|usage| usage.is_object());
+        if finish_reason.is_some() || usage.is_some() {
+            self.close_block();
+            let mut message_delta = serde_json::json!({"delta": {}});
+            if let Some(finish_reason) = finish_reason {
+                message_delta["delta"]["stop_reason"] =
+                    serde_json::Value::String(map_finish_reason(finish_reason).to_string());
+            }
+            if let Some(usage) = usage {
+                message_delta["usage"] = serde_json::json!({
+                    "input_tokens": usage.get("prompt_tokens"),
+                    "output_tokens": usage.get("completion_tokens"),
+                });
+            }
+            self.push("message_delta", message_delta);
+        }
+    }
+
+    fn finish(mut self, done: bool) -> Vec<serde_json::Value> {
+        self.close_block();
+        if done {
+            self.push("message_stop", serde_json::json!({}));
+        }
+        self.events
+    }
+}
+
+/// The Messages API `stop_reason` for a chat-completions `finish_reason`.
+fn map_finish_reason(finish_reason: &str) -> &str {
+    match finish_reason {
+        "stop" => "end_turn",
+        "length" => "max_tokens",
+        "tool_calls" | "function_call" => "tool_use",
+        other => other,
+    }
+}
+
+/// Decode a streamed chat-completions body (`data: {...}` chunks ending with
+/// `data: [DONE]`) into Messages API-shaped events for `response_events_json`.
+pub fn parse_chat_completion_events(body: &str) -> Vec<serde_json::Value> {
+    let mut decoder = ChatCompletionDecoder::default();
+    let mut done = false;
+    for event in parse_sse_events(body) {
+        match event.get("data") {
+            Some(serde_json::Value::String(data)) if data == "[DONE]" => done = true,
+            Some(chunk) if chunk.is_object() => decoder.feed_chunk(chunk),
+            _ => {}
+        }
+    }
+    decoder.finish(done)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -193,4 +417,191 @@ mod tests {
         assert!(events[0].get("event").is_none());
         assert_eq!(events[0]["data"], "hello");
     }
+
+    #[test]
+    fn chat_completion_text_chunks() {
+        let body = "data: {\"id\":\"c1\",\"model\":\"llama3\",\"choices\":[{\"index\":0,\
+                    \"delta\":{\"role\":\"assistant\",\"content\":\"Hel\"}}]}\n\n\
+                    data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\
+                    \"delta\":{\"content\":\"lo\"}}]}\n\n\
+                    data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{},\
+                    \"finish_reason\":\"stop\"}],\
+                    \"usage\":{\"prompt_tokens\":5,\"completion_tokens\":2}}\n\n\
+                    data: [DONE]\n\n";
+        let events = parse_chat_completion_events(body);
+        let names: Vec<&str> = events
+            .iter()
+            .map(|event| event["event"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "message_start",
+                "content_block_start",
+                "content_block_delta",
+                "content_block_delta",
+                "content_block_stop",
+                "message_delta",
+                "message_stop",
+            ]
+        );
+        assert_eq!(events[0]["data"]["message"]["model"], "llama3");
+        assert_eq!(events[3]["data"]["delta"]["text"], "lo");
+        assert_eq!(events[5]["data"]["delta"]["stop_reason"], "end_turn");
+        assert_eq!(events[5]["data"]["usage"]["output_tokens"], 2);
+    }
+
+    #[test]
+    fn chat_completion_tool_call_chunks() {
+        let body =
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\
+                    \"function\":{\"name\":\"get_weather\",\"arguments\":\"\"}}]}}]}\n\n\
+                    data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\
+                    \"function\":{\"arguments\":\"{\\\"city\\\":1}\"}}]}}]}\n\n\
+                    data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"tool_calls\"}]}\n\n";
+        let events = parse_chat_completion_events(body);
+        assert_eq!(events[1]["data"]["content_block"]["type"], "tool_use");
+        assert_eq!(events[1]["data"]["content_block"]["name"], "get_weather");
+        assert_eq!(events[2]["data"]["delta"]["partial_json"], "{\"city\":1}");
+        assert_eq!(events[3]["event"], "content_block_stop");
+        assert_eq!(events[4]["data"]["delta"]["stop_reason"], "tool_use");
+        // No [DONE] sentinel, so no message_stop.
+        assert_eq!(events.len(), 5);
+    }
+
+    #[test]
+    fn crlf_and_lone_cr_line_endings() {
+        let body = "event: a\r\ndata: 1\r\n\r\nevent: b\rdata: 2\r\r";
+        let events = parse_sse_events(body);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "a");
+        assert_eq!(events[1]["event"], "b");
+        assert_eq!(events[1]["data"], 2);
+    }
+
+    #[test]
+    fn comments_and_ids_are_skipped() {
+        let body = ": keepalive\nid: 7\nretry: 100\nevent: ping\ndata: {}\n\n";
+        let events = parse_sse_events(body);
+        assert_eq!(
+            events,
+            vec![serde_json::json!({"event": "ping", "data": {}})]
+        );
+    }
+
+    #[test]
+    fn multi_line_data_roundtrips_through_serialize() {
+        let wire = serialize_sse_event("msg", "line1\n line2");
+        assert_eq!(wire, "event: msg\ndata: line1\ndata:  line2\n\n");
+        let events = parse_sse_events(&wire);
+        assert_eq!(events[0]["data"], "line1\n line2");
+    }
+
+    #[test]
+    fn unparsed_lines_are_recorded() {
+        let body = "event: a\ngarbage here\ndata: 1\n\n";
+        let events = parse_sse_events(body);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["unparsed"], true);
+        assert_eq!(events[0]["data"], "garbage here");
+        assert_eq!(events[1]["event"], "a");
+    }
+
+    #[test]
+    fn parser_waits_for_lf_after_chunk_ending_in_cr() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed("data: x\r").is_empty());
+        assert!(parser.feed("\n").is_empty());
+        assert_eq!(parser.feed("\r\n"), vec![("".to_string(), "x".to_string())]);
+    }
+
+    #[test]
+    fn parser_flush_reads_unterminated_last_line() {
+        let mut parser = SseParser::new();
+        assert!(parser.feed("event: last\ndata: final").is_empty());
+        assert_eq!(
+            parser.flush(),
+            Some(("last".to_string(), "final".to_string()))
+        );
+    }
+
+    mod properties {
+        use super::super::*;
+        use proptest::prelude::*;
+
+        fn line_ending() -> impl Strategy<Value = &'static str> {
+            prop_oneof![Just("\n"), Just("\r\n"), Just("\r")]
+        }
+
+        /// Events as `(event_type, data lines)`; data lines hold no line breaks.
+        fn events() -> impl Strategy<Value = Vec<(String, Vec<String>)>> {
+            prop::collection::vec(
+                ("[a-z_]{0,12}", prop::collection::vec("[^\r\n]{0,20}", 1..4)),
+                0..8,
+            )
+        }
+
+        fn write_stream(events: &[(String, Vec<String>)], ending: &str) -> String {
+            events
+                .iter()
+                .map(|(event_type, lines)| {
+                    serialize_sse_event(event_type, &lines.join("\n")).replace('\n', ending)
+                })
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn parser_recovers_serialized_events(
+                events in events(),
+                ending in line_ending(),
+            ) {
+                let mut parser = SseParser::new();
+                let mut parsed = parser.feed(&write_stream(&events, ending));
+                parsed.extend(parser.flush());
+                let expected: Vec<(String, String)> = events
+                    .iter()
+                    .map(|(event_type, lines)| (event_type.clone(), lines.join("\n")))
+                    .collect();
+                prop_assert_eq!(parsed, expected);
+            }
+
+            #[test]
+            fn chunk_boundaries_do_not_change_events(
+                events in events(),
+                ending in line_ending(),
+                cuts in prop::collection::vec(any::<prop::sample::Index>(), 0..6),
+            ) {
+                let stream = write_stream(&events, ending);
+                let mut boundaries: Vec<usize> = cuts
+                    .iter()
+                    .map(|cut| cut.index(stream.len() + 1))
+                    .filter(|&boundary| stream.is_char_boundary(boundary))
+                    .collect();
+                boundaries.sort_unstable();
+
+                let mut whole = SseParser::new();
+                let mut expected = whole.feed(&stream);
+                expected.extend(whole.flush());
+
+                let mut chunked = SseParser::new();
+                let mut parsed = Vec::new();
+                let mut start = 0;
+                for boundary in boundaries.into_iter().chain([stream.len()]) {
+                    parsed.extend(chunked.feed(&stream[start..boundary]));
+                    start = boundary;
+                }
+                parsed.extend(chunked.flush());
+                prop_assert_eq!(parsed, expected);
+            }
+
+            #[test]
+            fn arbitrary_input_yields_events_with_data(body in "(?s).{0,200}") {
+                let events = parse_sse_events(&body);
+                for event in &events {
+                    prop_assert!(event.get("data").is_some());
+                }
+            }
+        }
+    }
 }
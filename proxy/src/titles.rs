@@ -0,0 +1,200 @@
+//! One-line conversation titles for logged Messages requests, generated by
+//! asking a cheap model through the session's upstream.
+
+use common::models::{ProxyRequest, Session};
+use common::upstream::UPSTREAM_PRESET_OPENAI_COMPATIBLE;
+use common::url::parse_query_params;
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+use crate::shared::{build_replay_headers, build_target_url, effective_client};
+
+/// Untitled requests handled per run of the background job.
+const TITLE_BATCH_SIZE: i64 = 20;
+
+/// Characters of the first user message sent to the model.
+const TITLE_MAX_INPUT_CHARS: usize = 2000;
+
+/// Characters kept of a generated title.
+const TITLE_MAX_CHARS: usize = 100;
+
+const TITLE_MAX_OUTPUT_TOKENS: i64 = 30;
+
+const TITLE_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+const TITLE_PROMPT: &str = "Write a short title, at most eight words, for a conversation that \
+starts with the message below. Reply with the title only.";
+
+/// The text of the first user message: a string content, or its text blocks
+/// joined by newlines.
+fn extract_first_user_text(messages: &Value) -> Option<String> {
+    let first_user_message = messages
+        .as_array()?
+        .iter()
+        .find(|message| message.get("role").and_then(Value::as_str) == Some("user"))?;
+    let content = first_user_message.get("content")?;
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.chars().take(TITLE_MAX_INPUT_CHARS).collect())
+}
+
+fn build_title_body(title_model: &str, first_user_text: &str) -> Value {
+    serde_json::json!({
+        "model": title_model,
+        "max_tokens": TITLE_MAX_OUTPUT_TOKENS,
+        "messages": [{
+            "role": "user",
+            "content": format!("{}\n\n<message>\n{}\n</message>", TITLE_PROMPT, first_user_text),
+        }],
+    })
+}
+
+/// The first line of a Messages response's text, without surrounding quotes
+/// or markdown heading marks.
+fn extract_title(response: &Value) -> Option<String> {
+    let text = response
+        .get("content")?
+        .as_array()?
+        .iter()
+        .filter_map(|block| block.get("text").and_then(Value::as_str))
+        .collect::<String>();
+    let title = text
+        .lines()
+        .map(|line| line.trim().trim_start_matches('#').trim().trim_matches('"'))
+        .find(|line| !line.is_empty())?;
+    Some(title.chars().take(TITLE_MAX_CHARS).collect())
+}
+
+/// Ask `title_model`, through the session's upstream and with the request's
+/// credentials, for a title of the request's conversation.
+pub async fn request_conversation_title(
+    client: &reqwest::Client,
+    session: &Session,
+    title_model: &str,
+    req: &ProxyRequest,
+) -> anyhow::Result<String> {
+    let path = req.path.split('?').next().unwrap_or(&req.path);
+    anyhow::ensure!(
+        path.trim_end_matches('/').ends_with("messages"),
+        "not a Messages request"
+    );
+    anyhow::ensure!(
+        session.upstream_preset.as_deref() != Some(UPSTREAM_PRESET_OPENAI_COMPATIBLE),
+        "titles are not supported for OpenAI-compatible upstreams"
+    );
+    let messages: Value = serde_json::from_str(req.messages_json.as_deref().unwrap_or("null"))?;
+    let first_user_text = extract_first_user_text(&messages)
+        .ok_or_else(|| anyhow::anyhow!("request has no user text"))?;
+
+    let auth_query_params = parse_query_params(session.auth_query_params.as_deref());
+    let title_url = build_target_url(&session.target_url, path, None, &auth_query_params);
+    let mut title_headers = build_replay_headers(req.headers_json.as_deref(), session)?;
+    title_headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let response_bytes = effective_client(session, client)
+        .post(title_url)
+        .headers(title_headers)
+        .timeout(TITLE_REQUEST_TIMEOUT)
+        .body(serde_json::to_vec(&build_title_body(
+            title_model,
+            &first_user_text,
+        ))?)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    let response: Value = serde_json::from_slice(&response_bytes)?;
+    extract_title(&response).ok_or_else(|| anyhow::anyhow!("response has no text"))
+}
+
+/// Generate and store a title for one request, reusing the title of an
+/// earlier request in the same conversation when there is one.
+async fn title_request(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    title_model: &str,
+    req: &ProxyRequest,
+) -> anyhow::Result<()> {
+    let session_id = req.session_id.to_string();
+    let messages_json = req.messages_json.as_deref().unwrap_or("null");
+    if let Some(title) = db::find_conversation_title(pool, &session_id, messages_json).await? {
+        return db::set_request_title(pool, &req.id.to_string(), &title).await;
+    }
+    let session = db::get_session(pool, &session_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("session {} not found", session_id))?;
+    let title = match request_conversation_title(client, &session, title_model, req).await {
+        Ok(title) => title,
+        Err(e) => {
+            log::warn!("titles: failed to title request {}: {}", req.id, e);
+            String::new()
+        }
+    };
+    db::set_request_title(pool, &req.id.to_string(), &title).await
+}
+
+/// Title the oldest untitled Messages requests. Requests whose title can't
+/// be generated get an empty one, so they aren't retried on every run.
+pub async fn title_untitled_requests(
+    pool: &SqlitePool,
+    client: &reqwest::Client,
+    title_model: &str,
+) {
+    let requests = match db::list_untitled_requests(pool, TITLE_BATCH_SIZE).await {
+        Ok(requests) => requests,
+        Err(e) => {
+            log::warn!("titles: failed to list untitled requests: {}", e);
+            return;
+        }
+    };
+    for req in &requests {
+        if let Err(e) = title_request(pool, client, title_model, req).await {
+            log::warn!("titles: failed to store title of {}: {}", req.id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extract_first_user_text_joins_text_blocks() {
+        let messages = json!([
+            {"role": "user", "content": [
+                {"type": "text", "text": "Fix the"},
+                {"type": "image", "source": {}},
+                {"type": "text", "text": "login bug"}
+            ]},
+            {"role": "assistant", "content": "ok"}
+        ]);
+        assert_eq!(
+            extract_first_user_text(&messages).as_deref(),
+            Some("Fix the\nlogin bug")
+        );
+        assert_eq!(extract_first_user_text(&json!([])), None);
+    }
+
+    #[test]
+    fn extract_title_takes_first_line_without_quotes() {
+        let response =
+            json!({"content": [{"type": "text", "text": "\n\"Fixing the login bug\"\nmore"}]});
+        assert_eq!(
+            extract_title(&response).as_deref(),
+            Some("Fixing the login bug")
+        );
+        assert_eq!(extract_title(&json!({"content": []})), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Tool calls answered by a request, extracted when it is logged so the
+//! tool statistics page can aggregate them without re-parsing bodies.
+
+use common::models::AnsweredToolCall;
+use serde_json::Value;
+
+/// The tool calls answered by the body's last user message: each
+/// `tool_result` there paired with its `tool_use` in the assistant message
+/// before it. Each call is counted once, in the request carrying its result.
+pub fn extract_answered_tool_calls(data: &Value) -> Vec<AnsweredToolCall> {
+    let Some(messages) = data.get("messages").and_then(Value::as_array) else {
+        return vec![];
+    };
+    let Some((last_message, earlier_messages)) = messages.split_last() else {
+        return vec![];
+    };
+    if last_message.get("role").and_then(Value::as_str) != Some("user") {
+        return vec![];
+    }
+    let tool_uses: Vec<&Value> = earlier_messages
+        .iter()
+        .rev()
+        .find(|message| message.get("role").and_then(Value::as_str) == Some("assistant"))
+        .map(|message| find_content_blocks(message, "tool_use"))
+        .unwrap_or_default();
+    find_content_blocks(last_message, "tool_result")
+        .into_iter()
+        .filter_map(|tool_result| {
+            let tool_use_id = tool_result.get("tool_use_id").and_then(Value::as_str)?;
+            let tool_use = tool_uses
+                .iter()
+                .find(|tool_use| tool_use.get("id").and_then(Value::as_str) == Some(tool_use_id))?;
+            Some(AnsweredToolCall {
+                id: tool_use_id.to_string(),
+                name: tool_use.get("name").and_then(Value::as_str)?.to_string(),
+                input_bytes: tool_use
+                    .get("input")
+                    .map_or(0, |input| input.to_string().len()),
+                is_error: tool_result.get("is_error").and_then(Value::as_bool) == Some(true),
+            })
+        })
+        .collect()
+}
+
+fn find_content_blocks<'a>(message: &'a Value, block_type: &str) -> Vec<&'a Value> {
+    message
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|block| block.get("type").and_then(Value::as_str) == Some(block_type))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_answered_tool_calls_pairs_results_with_their_calls() {
+        let data = serde_json::json!({"messages": [
+            {"role": "user", "content": "read two files"},
+            {"role": "assistant", "content": [
+                {"type": "text", "text": "ok"},
+                {"type": "tool_use", "id": "t1", "name": "Read", "input": {"path": "a"}},
+                {"type": "tool_use", "id": "t2", "name": "Read", "input": {"path": "bb"}},
+            ]},
+            {"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "t1", "content": "A"},
+                {"type": "tool_result", "tool_use_id": "t2", "content": "missing", "is_error": true},
+            ]},
+        ]});
+        let answered_tool_calls = extract_answered_tool_calls(&data);
+        assert_eq!(
+            answered_tool_calls,
+            vec![
+                AnsweredToolCall {
+                    id: "t1".to_string(),
+                    name: "Read".to_string(),
+                    input_bytes: r#"{"path":"a"}"#.len(),
+                    is_error: false,
+                },
+                AnsweredToolCall {
+                    id: "t2".to_string(),
+                    name: "Read".to_string(),
+                    input_bytes: r#"{"path":"bb"}"#.len(),
+                    is_error: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_answered_tool_calls_ignores_history() {
+        let data = serde_json::json!({"messages": [
+            {"role": "assistant", "content": [
+                {"type": "tool_use", "id": "t1", "name": "Read", "input": {}},
+            ]},
+            {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "t1"}]},
+            {"role": "assistant", "content": "done"},
+            {"role": "user", "content": "thanks"},
+        ]});
+        assert!(extract_answered_tool_calls(&data).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use jsonschema::Validator;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+/// Upper bound on violations reported per request so notes stay readable.
+const MAX_REPORTED_VIOLATIONS: usize = 20;
+
+static MESSAGES_VALIDATOR: LazyLock<Validator> = LazyLock::new(|| {
+    let schema: Value = serde_json::from_str(include_str!("messages_schema.json"))
+        .expect("built-in messages schema is valid JSON");
+    jsonschema::validator_for(&schema).expect("built-in messages schema compiles")
+});
+
+fn is_messages_path(path: &str) -> bool {
+    path.trim_end_matches('/').ends_with("messages")
+}
+
+/// Compile a user-provided JSON Schema.
+pub fn build_validator(schema_str: &str) -> anyhow::Result<Validator> {
+    let schema: Value = serde_json::from_str(schema_str)?;
+    jsonschema::validator_for(&schema).map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+fn collect_validator_violations(validator: &Validator, instance: &Value) -> Vec<String> {
+    validator
+        .iter_errors(instance)
+        .take(MAX_REPORTED_VIOLATIONS)
+        .map(|error| {
+            let path = error.instance_path().as_str();
+            let path = if path.is_empty() { "(root)" } else { path };
+            format!("{}: {}", path, error)
+        })
+        .collect()
+}
+
+/// Check a request body against the session's custom JSON Schema, or against
+/// the built-in Anthropic Messages schema for `messages` endpoints when no
+/// custom schema is set. Returns human-readable violations; empty means valid.
+pub fn collect_schema_violations(
+    body: &[u8],
+    path: &str,
+    custom_schema: Option<&str>,
+) -> Vec<String> {
+    let custom_schema = custom_schema.filter(|schema| !schema.trim().is_empty());
+    if custom_schema.is_none() && !is_messages_path(path) {
+        return vec![];
+    }
+    let instance: Value = match serde_json::from_slice(body) {
+        Ok(instance) => instance,
+        Err(e) => return vec![format!("body is not valid JSON: {}", e)],
+    };
+    match custom_schema {
+        Some(schema_str) => match build_validator(schema_str) {
+            Ok(validator) => collect_validator_violations(&validator, &instance),
+            Err(e) => vec![format!("invalid validation schema: {}", e)],
+        },
+        None => collect_validator_violations(&MESSAGES_VALIDATOR, &instance),
+    }
+}
+
+/// Summarize violations for the request note.
+pub fn format_violations_note(violations: &[String]) -> String {
+    format!(
+        "schema: {} violation(s): {}",
+        violations.len(),
+        violations.join("; ")
+    )
+}
+
+/// Build the Anthropic-style 400 error body returned in reject mode.
+pub fn build_violations_error_body(violations: &[String]) -> String {
+    serde_json::json!({
+        "type": "error",
+        "error": {
+            "type": "invalid_request_error",
+            "message": format!("Request failed schema validation: {}", violations.join("; ")),
+        }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(body: Value) -> Vec<String> {
+        collect_schema_violations(body.to_string().as_bytes(), "v1/messages", None)
+    }
+
+    #[test]
+    fn valid_messages_body_has_no_violations() {
+        let body = serde_json::json!({
+            "model": "claude-sonnet-4-5",
+            "max_tokens": 1024,
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": [{"name": "get_weather", "input_schema": {"type": "object"}}]
+        });
+        assert!(check(body).is_empty());
+    }
+
+    #[test]
+    fn missing_max_tokens_is_reported() {
+        let body = serde_json::json!({
+            "messages": [{"role": "user", "content": "hi"}]
+        });
+        let violations = check(body);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("max_tokens"));
+    }
+
+    #[test]
+    fn custom_tool_without_input_schema_is_reported() {
+        let body = serde_json::json!({
+            "max_tokens": 10,
+            "messages": [],
+            "tools": [{"name": "broken"}]
+        });
+        let violations = check(body);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("/tools/0"));
+    }
+
+    #[test]
+    fn server_tool_without_input_schema_is_valid() {
+        let body = serde_json::json!({
+            "max_tokens": 10,
+            "messages": [],
+            "tools": [{"type": "web_search_20250305", "name": "web_search"}]
+        });
+        assert!(check(body).is_empty());
+    }
+
+    #[test]
+    fn invalid_tool_name_is_reported() {
+        let body = serde_json::json!({
+            "max_tokens": 10,
+            "messages": [],
+            "tools": [{"name": "bad name!", "input_schema": {"type": "object"}}]
+        });
+        let violations = check(body);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("/tools/0/name"));
+    }
+
+    #[test]
+    fn tool_use_block_without_input_is_reported() {
+        let body = serde_json::json!({
+            "max_tokens": 10,
+            "messages": [{
+                "role": "assistant",
+                "content": [{"type": "tool_use", "id": "t1", "name": "x"}]
+            }]
+        });
+        let violations = check(body);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("/messages/0/content"));
+    }
+
+    #[test]
+    fn non_messages_path_skips_builtin_schema() {
+        let body = serde_json::json!({"anything": true});
+        let violations = collect_schema_violations(body.to_string().as_bytes(), "v1/models", None);
+        assert!(violations.is_empty());
+    }
+
+    #[test]
+    fn invalid_json_body_is_reported() {
+        let violations = collect_schema_violations(b"{not json", "v1/messages", None);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("body is not valid JSON"));
+    }
+
+    #[test]
+    fn custom_schema_applies_to_any_path() {
+        let schema = r#"{"type": "object", "required": ["foo"]}"#;
+        let violations = collect_schema_violations(b"{}", "v1/models", Some(schema));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].contains("foo"));
+    }
+
+    #[test]
+    fn invalid_custom_schema_is_reported() {
+        let violations = collect_schema_violations(b"{}", "v1/messages", Some("{oops"));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("invalid validation schema"));
+    }
+
+    #[test]
+    fn violations_error_body_is_invalid_request_error() {
+        let body = build_violations_error_body(&["/x: bad".to_string()]);
+        let parsed: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed["error"]["type"], "invalid_request_error");
+        assert!(parsed["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("/x: bad"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Anthropic models on Vertex AI. Requests keep Vertex's URL layout upstream
+//! (`v1/projects/{project}/locations/{location}/publishers/anthropic/models/{model}:{method}`)
+//! and are logged like the Messages API endpoint they correspond to. Vertex
+//! takes the model from the URL and `anthropic_version` from the body; the
+//! OAuth bearer token is forwarded like any `Authorization` header.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::config::SharedConfig;
+use sqlx::SqlitePool;
+
+use crate::{forward_proxy_request, hooks::HookRegistry, ProxyRoute};
+
+/// Model name Vertex uses for the Token Counting endpoint.
+const COUNT_TOKENS_MODEL: &str = "count-tokens";
+
+/// The model and method of a Vertex publisher model path, e.g.
+/// `("claude-sonnet-4@20250514", "streamRawPredict")`.
+pub fn parse_vertex_model_path(path: &str) -> Option<(&str, &str)> {
+    let mut segments = path.trim_end_matches('/').split('/');
+    segments.by_ref().find(|&segment| segment == "models")?;
+    let model_and_method = segments.next()?;
+    if segments.next().is_some() {
+        return None;
+    }
+    model_and_method
+        .rsplit_once(':')
+        .filter(|(model, method)| !model.is_empty() && !method.is_empty())
+}
+
+/// Map a Vertex path to the route it is forwarded and logged as.
+fn build_vertex_route(path: &str) -> ProxyRoute {
+    let (api_path, url_model) = match parse_vertex_model_path(path) {
+        Some((COUNT_TOKENS_MODEL, _)) => ("v1/messages/count_tokens".to_string(), None),
+        Some((model, "rawPredict" | "streamRawPredict")) => {
+            ("v1/messages".to_string(), Some(model.to_string()))
+        }
+        Some((model, _)) => (path.to_string(), Some(model.to_string())),
+        None => (path.to_string(), None),
+    };
+    ProxyRoute {
+        upstream_path: path.to_string(),
+        api_path,
+        url_model,
+    }
+}
+
+pub async fn vertex_handler(
+    req: HttpRequest,
+    body: web::Bytes,
+    pool: web::Data<SqlitePool>,
+    client: web::Data<reqwest::Client>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let route = build_vertex_route(req.match_info().get("tail").unwrap_or(""));
+    forward_proxy_request(req, body, pool, client, hook_registry, config, route).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STREAM_PATH: &str = "v1/projects/my-project/locations/us-east5/publishers/anthropic/\
+                               models/claude-sonnet-4@20250514:streamRawPredict";
+
+    #[test]
+    fn parse_vertex_model_path_splits_model_and_method() {
+        assert_eq!(
+            parse_vertex_model_path(STREAM_PATH),
+            Some(("claude-sonnet-4@20250514", "streamRawPredict"))
+        );
+        assert_eq!(parse_vertex_model_path("v1/projects/p/locations/l"), None);
+        assert_eq!(
+            parse_vertex_model_path("v1/publishers/anthropic/models/claude"),
+            None
+        );
+    }
+
+    #[test]
+    fn build_vertex_route_maps_predict_to_messages() {
+        let route = build_vertex_route(STREAM_PATH);
+        assert_eq!(route.upstream_path, STREAM_PATH);
+        assert_eq!(route.api_path, "v1/messages");
+        assert_eq!(route.url_model.as_deref(), Some("claude-sonnet-4@20250514"));
+    }
+
+    #[test]
+    fn build_vertex_route_maps_count_tokens() {
+        let path =
+            "v1/projects/p/locations/us-east5/publishers/anthropic/models/count-tokens:rawPredict";
+        let route = build_vertex_route(path);
+        assert_eq!(route.api_path, "v1/messages/count_tokens");
+        assert_eq!(route.url_model, None);
+    }
+}
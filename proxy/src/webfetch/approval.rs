@@ -1,29 +1,179 @@
-use common::models::PendingToolInfo;
+use common::models::{PendingApprovalInfo, PendingToolInfo};
+use futures::Stream;
+use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use tokio::sync::oneshot;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, oneshot};
+use tokio::time::{Instant, Interval};
+
+use super::running::RunningIntercepts;
+
+/// Queue changes a dashboard stream may fall behind by before it is sent a
+/// fresh snapshot instead.
+const APPROVAL_EVENT_CAPACITY: usize = 64;
+
+/// How often an idle approval event stream sends a keep-alive.
+const APPROVAL_STREAM_KEEP_ALIVE: Duration = Duration::from_secs(15);
 
 /// User decision for a pending webfetch tool call.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ApprovalDecision {
     Fail,
     Mock,
     Accept,
+    /// Accept, returning fetched page text without the agent summary.
+    AcceptRaw,
+    /// Accept with replacement inputs: an object mapping tool_use ids to the
+    /// input to run instead. Calls without an entry keep their original input.
+    AcceptEdited(Value),
+    /// A separate decision for each tool call of the round, in call order.
+    PerTool(Vec<ApprovalDecision>),
+}
+
+impl ApprovalDecision {
+    /// Human-readable label for logging/display.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApprovalDecision::Fail => "Fail",
+            ApprovalDecision::Mock => "Mock",
+            ApprovalDecision::Accept => "Accept",
+            ApprovalDecision::AcceptRaw => "Accept (raw)",
+            ApprovalDecision::AcceptEdited(_) => "Accept (edited)",
+            ApprovalDecision::PerTool(_) => "Per-tool",
+        }
+    }
+}
+
+/// A decided approval and the dashboard users who decided it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalResolution {
+    pub decision: ApprovalDecision,
+    /// Each user once, in the order their decisions were made.
+    pub decided_by: Vec<String>,
 }
 
 /// A pending approval waiting for user action.
 pub struct PendingApproval {
     pub session_id: String,
     pub tools: Vec<PendingToolInfo>,
-    pub sender: oneshot::Sender<ApprovalDecision>,
+    /// Decisions made so far for individual tool calls, in call order.
+    pub tool_decisions: Vec<Option<ApprovalDecision>>,
+    /// Users who decided individual tool calls so far, in decision order.
+    pub decided_by: Vec<String>,
+    pub sender: oneshot::Sender<ApprovalResolution>,
+    pub queued_at: Instant,
 }
 
-/// Shared approval queue: maps approval_id → PendingApproval.
-pub type ApprovalQueue = Arc<Mutex<HashMap<String, PendingApproval>>>;
+impl PendingApproval {
+    pub fn new(
+        session_id: String,
+        tools: Vec<PendingToolInfo>,
+        sender: oneshot::Sender<ApprovalResolution>,
+    ) -> Self {
+        Self {
+            session_id,
+            tool_decisions: vec![None; tools.len()],
+            decided_by: Vec::new(),
+            tools,
+            sender,
+            queued_at: Instant::now(),
+        }
+    }
+}
+
+/// A change to the approval queue, pushed to dashboard pages.
+#[derive(Debug, Clone)]
+pub enum ApprovalEvent {
+    /// An approval round started waiting for the user.
+    Added {
+        session_id: String,
+        approval_id: String,
+        tools: Vec<PendingToolInfo>,
+    },
+    /// Some tool calls of an approval were decided; the rest still wait.
+    Updated {
+        session_id: String,
+        approval_id: String,
+        tools: Vec<PendingToolInfo>,
+    },
+    /// An approval was decided or timed out.
+    Removed {
+        session_id: String,
+        approval_id: String,
+    },
+}
+
+impl ApprovalEvent {
+    fn session_id(&self) -> &str {
+        match self {
+            ApprovalEvent::Added { session_id, .. }
+            | ApprovalEvent::Updated { session_id, .. }
+            | ApprovalEvent::Removed { session_id, .. } => session_id,
+        }
+    }
+}
+
+/// Shared approval queue: maps approval_id → PendingApproval, and announces
+/// every change to subscribed dashboard pages. It also lists the
+/// interceptions in progress.
+#[derive(Clone)]
+pub struct ApprovalQueue {
+    pending: Arc<Mutex<HashMap<String, PendingApproval>>>,
+    events: broadcast::Sender<ApprovalEvent>,
+    pub(super) running_intercepts: RunningIntercepts,
+}
 
 /// Create a new empty approval queue.
 pub fn new_approval_queue() -> ApprovalQueue {
-    Arc::new(Mutex::new(HashMap::new()))
+    ApprovalQueue {
+        pending: Arc::new(Mutex::new(HashMap::new())),
+        events: broadcast::channel(APPROVAL_EVENT_CAPACITY).0,
+        running_intercepts: RunningIntercepts::default(),
+    }
+}
+
+/// Announce a queue change. Nobody may be listening, which is fine.
+fn send_approval_event(queue: &ApprovalQueue, event: ApprovalEvent) {
+    let _ = queue.events.send(event);
+}
+
+/// Add an approval to the queue.
+pub fn insert_pending(queue: &ApprovalQueue, approval_id: &str, pending: PendingApproval) {
+    let mut queue_map = queue.pending.lock().unwrap();
+    send_approval_event(
+        queue,
+        ApprovalEvent::Added {
+            session_id: pending.session_id.clone(),
+            approval_id: approval_id.to_string(),
+            tools: pending.tools.clone(),
+        },
+    );
+    queue_map.insert(approval_id.to_string(), pending);
+}
+
+/// Take an approval off the queue, announcing its removal.
+fn take_pending(
+    queue_map: &mut HashMap<String, PendingApproval>,
+    queue: &ApprovalQueue,
+    approval_id: &str,
+) -> Option<PendingApproval> {
+    let pending = queue_map.remove(approval_id)?;
+    send_approval_event(
+        queue,
+        ApprovalEvent::Removed {
+            session_id: pending.session_id.clone(),
+            approval_id: approval_id.to_string(),
+        },
+    );
+    Some(pending)
+}
+
+/// Drop an approval without deciding it, e.g. after it timed out.
+pub fn remove_pending(queue: &ApprovalQueue, approval_id: &str) {
+    let mut queue_map = queue.pending.lock().unwrap();
+    take_pending(&mut queue_map, queue, approval_id);
 }
 
 /// List pending approvals for a given session.
@@ -31,7 +181,7 @@ pub fn list_pending(
     queue: &ApprovalQueue,
     session_id: &str,
 ) -> Vec<(String, Vec<PendingToolInfo>)> {
-    let queue_map = queue.lock().unwrap();
+    let queue_map = queue.pending.lock().unwrap();
     queue_map
         .iter()
         .filter(|(_, pending)| pending.session_id == session_id)
@@ -39,21 +189,180 @@ pub fn list_pending(
         .collect()
 }
 
-/// Resolve a pending approval by sending the decision through the oneshot channel.
-/// Returns `true` if the approval was found and resolved.
+/// Every pending approval across sessions, oldest first.
+pub fn list_all_pending(queue: &ApprovalQueue) -> Vec<PendingApprovalInfo> {
+    let queue_map = queue.pending.lock().unwrap();
+    let mut pending_approvals: Vec<_> = queue_map.iter().collect();
+    pending_approvals.sort_by_key(|(_, pending)| pending.queued_at);
+    pending_approvals
+        .into_iter()
+        .map(|(approval_id, pending)| PendingApprovalInfo {
+            approval_id: approval_id.clone(),
+            session_id: pending.session_id.clone(),
+            tools: pending.tools.clone(),
+            waiting_secs: pending.queued_at.elapsed().as_secs(),
+        })
+        .collect()
+}
+
+/// The session and tool calls of a pending approval, if it is still waiting.
+pub fn get_pending(
+    queue: &ApprovalQueue,
+    approval_id: &str,
+) -> Option<(String, Vec<PendingToolInfo>)> {
+    let queue_map = queue.pending.lock().unwrap();
+    queue_map
+        .get(approval_id)
+        .map(|pending| (pending.session_id.clone(), pending.tools.clone()))
+}
+
+/// Resolve a pending approval by sending the decision, made by `decided_by`,
+/// through the oneshot channel. Returns `true` if the approval was found and
+/// resolved.
 pub fn resolve_pending(
     queue: &ApprovalQueue,
     approval_id: &str,
     decision: ApprovalDecision,
+    decided_by: &str,
 ) -> bool {
     let pending = {
-        let mut queue_map = queue.lock().unwrap();
-        queue_map.remove(approval_id)
+        let mut queue_map = queue.pending.lock().unwrap();
+        take_pending(&mut queue_map, queue, approval_id)
     };
-    if let Some(pending) = pending {
-        let _ = pending.sender.send(decision);
+    if let Some(mut pending) = pending {
+        add_decided_by(&mut pending.decided_by, decided_by);
+        let _ = pending.sender.send(ApprovalResolution {
+            decision,
+            decided_by: pending.decided_by,
+        });
         true
     } else {
         false
     }
 }
+
+/// Combine per-tool decisions into the round's decision: a single decision
+/// when they all agree, otherwise `PerTool`.
+pub(super) fn combine_tool_decisions(tool_decisions: Vec<ApprovalDecision>) -> ApprovalDecision {
+    match tool_decisions.first() {
+        Some(first) if tool_decisions.iter().all(|decision| decision == first) => first.clone(),
+        _ => ApprovalDecision::PerTool(tool_decisions),
+    }
+}
+
+fn add_decided_by(decided_by: &mut Vec<String>, user_name: &str) {
+    if !decided_by.iter().any(|name| name == user_name) {
+        decided_by.push(user_name.to_string());
+    }
+}
+
+/// Record the decision for one tool call of a pending approval, made by
+/// `decided_by`. Once every call has a decision the approval is resolved with
+/// them. Returns `true` if the approval and tool call were found.
+pub fn decide_pending_tool(
+    queue: &ApprovalQueue,
+    approval_id: &str,
+    tool_use_id: &str,
+    decision: ApprovalDecision,
+    decided_by: &str,
+) -> bool {
+    let pending = {
+        let mut queue_map = queue.pending.lock().unwrap();
+        let Some(pending) = queue_map.get_mut(approval_id) else {
+            return false;
+        };
+        let Some(index) = pending
+            .tools
+            .iter()
+            .position(|tool| tool.tool_use_id == tool_use_id)
+        else {
+            return false;
+        };
+        pending.tools[index].decision = Some(decision.label().to_string());
+        pending.tools[index].decided_by = Some(decided_by.to_string());
+        pending.tool_decisions[index] = Some(decision);
+        add_decided_by(&mut pending.decided_by, decided_by);
+        if pending.tool_decisions.iter().any(Option::is_none) {
+            send_approval_event(
+                queue,
+                ApprovalEvent::Updated {
+                    session_id: pending.session_id.clone(),
+                    approval_id: approval_id.to_string(),
+                    tools: pending.tools.clone(),
+                },
+            );
+            return true;
+        }
+        take_pending(&mut queue_map, queue, approval_id)
+    };
+    if let Some(pending) = pending {
+        let tool_decisions = pending.tool_decisions.into_iter().flatten().collect();
+        let _ = pending.sender.send(ApprovalResolution {
+            decision: combine_tool_decisions(tool_decisions),
+            decided_by: pending.decided_by,
+        });
+    }
+    true
+}
+
+/// A message on a session's approval event stream.
+#[derive(Debug)]
+pub enum ApprovalStreamMessage {
+    /// Every approval pending for the session. Sent first, and again after
+    /// the stream fell too far behind to replay the changes it missed.
+    Snapshot(Vec<(String, Vec<PendingToolInfo>)>),
+    Event(ApprovalEvent),
+    /// Nothing changed for a while; keeps idle connections open.
+    KeepAlive,
+}
+
+struct ApprovalStreamState {
+    queue: ApprovalQueue,
+    session_id: String,
+    receiver: broadcast::Receiver<ApprovalEvent>,
+    keep_alive: Interval,
+    needs_snapshot: bool,
+}
+
+/// Stream the approval queue changes of one session, starting with a snapshot
+/// of its pending approvals. Subscribing before taking the snapshot means no
+/// change is lost; a change may show up in both, so consumers should treat
+/// additions as upserts.
+pub fn stream_approval_events(
+    queue: &ApprovalQueue,
+    session_id: &str,
+) -> impl Stream<Item = ApprovalStreamMessage> {
+    let state = ApprovalStreamState {
+        queue: queue.clone(),
+        session_id: session_id.to_string(),
+        receiver: queue.events.subscribe(),
+        keep_alive: tokio::time::interval_at(
+            Instant::now() + APPROVAL_STREAM_KEEP_ALIVE,
+            APPROVAL_STREAM_KEEP_ALIVE,
+        ),
+        needs_snapshot: true,
+    };
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if state.needs_snapshot {
+                state.needs_snapshot = false;
+                let pending = list_pending(&state.queue, &state.session_id);
+                return Some((ApprovalStreamMessage::Snapshot(pending), state));
+            }
+            tokio::select! {
+                received = state.receiver.recv() => match received {
+                    Ok(event) if event.session_id() == state.session_id => {
+                        state.keep_alive.reset();
+                        return Some((ApprovalStreamMessage::Event(event), state));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => state.needs_snapshot = true,
+                    Err(RecvError::Closed) => return None,
+                },
+                _ = state.keep_alive.tick() => {
+                    return Some((ApprovalStreamMessage::KeepAlive, state));
+                }
+            }
+        }
+    })
+}
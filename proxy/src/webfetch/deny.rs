@@ -0,0 +1,222 @@
+use common::models::WebfetchDenyRule;
+use std::net::IpAddr;
+use url::{Host, Url};
+
+use super::extract::ToolUse;
+
+/// A parsed deny rule pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DenyPattern {
+    /// An IP range in CIDR notation; a bare address is a single-host range.
+    Network { address: IpAddr, prefix_len: u8 },
+    /// A domain, matching itself and all of its subdomains.
+    Domain(String),
+}
+
+/// Parse a deny rule pattern: `10.0.0.0/8`, `169.254.169.254`, `::1/128` or
+/// a domain such as `metadata.google.internal`.
+pub fn parse_deny_pattern(pattern: &str) -> Result<DenyPattern, String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("pattern is required".to_string());
+    }
+    let (address_str, prefix_len_str) = match pattern.split_once('/') {
+        Some((address_str, prefix_len_str)) => (address_str, Some(prefix_len_str)),
+        None => (pattern, None),
+    };
+    if let Ok(address) = address_str.trim_matches(['[', ']']).parse::<IpAddr>() {
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len_str {
+            Some(prefix_len_str) => prefix_len_str
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_prefix_len)
+                .ok_or_else(|| format!("invalid prefix length in {:?}", pattern))?,
+            None => max_prefix_len,
+        };
+        return Ok(DenyPattern::Network {
+            address,
+            prefix_len,
+        });
+    }
+    if prefix_len_str.is_some() || pattern.contains([':', ' ']) {
+        return Err(format!("{:?} is neither an IP range nor a domain", pattern));
+    }
+    Ok(DenyPattern::Domain(
+        pattern.trim_end_matches('.').to_ascii_lowercase(),
+    ))
+}
+
+/// Check whether `address` lies in the network `network/prefix_len`.
+fn is_in_network(address: IpAddr, network: IpAddr, prefix_len: u8) -> bool {
+    match (address, network) {
+        (IpAddr::V4(address), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            u32::from(address) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            u128::from(address) & mask == u128::from(network) & mask
+        }
+        (IpAddr::V6(address), IpAddr::V4(_)) => address
+            .to_ipv4_mapped()
+            .is_some_and(|address| is_in_network(IpAddr::V4(address), network, prefix_len)),
+        (IpAddr::V4(_), IpAddr::V6(_)) => false,
+    }
+}
+
+/// Check whether a URL's host matches a deny pattern. Network patterns only
+/// match IP literal hosts; domain names are not resolved here.
+pub(super) fn matches_deny_pattern(url: &Url, pattern: &DenyPattern) -> bool {
+    match (url.host(), pattern) {
+        (
+            Some(Host::Ipv4(address)),
+            DenyPattern::Network {
+                address: network,
+                prefix_len,
+            },
+        ) => is_in_network(IpAddr::V4(address), *network, *prefix_len),
+        (
+            Some(Host::Ipv6(address)),
+            DenyPattern::Network {
+                address: network,
+                prefix_len,
+            },
+        ) => is_in_network(IpAddr::V6(address), *network, *prefix_len),
+        (Some(Host::Domain(host)), DenyPattern::Domain(domain)) => {
+            host == domain || host.ends_with(&format!(".{}", domain))
+        }
+        _ => false,
+    }
+}
+
+/// Find the first WebFetch call whose URL matches a deny rule. Returns a
+/// description of the match (URL and rule pattern) for logging and display.
+pub(super) fn find_denied_webfetch(
+    tool_uses: &[ToolUse],
+    rules: &[WebfetchDenyRule],
+    webfetch_names: &[String],
+) -> Option<String> {
+    let patterns: Vec<(&str, DenyPattern)> = rules
+        .iter()
+        .filter_map(|rule| {
+            parse_deny_pattern(&rule.pattern)
+                .ok()
+                .map(|pattern| (rule.pattern.as_str(), pattern))
+        })
+        .collect();
+    tool_uses
+        .iter()
+        .filter(|tool_use| webfetch_names.iter().any(|name| name == &tool_use.name))
+        .filter_map(|tool_use| tool_use.input.get("url").and_then(|field| field.as_str()))
+        .filter_map(|url_str| Url::parse(url_str).ok())
+        .find_map(|url| {
+            patterns
+                .iter()
+                .find(|(_, pattern)| matches_deny_pattern(&url, pattern))
+                .map(|(rule_pattern, _)| format!("{} matched deny rule {}", url, rule_pattern))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_deny_rule(pattern: &str) -> WebfetchDenyRule {
+        serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::nil(),
+            "session_id": uuid::Uuid::nil(),
+            "pattern": pattern,
+            "created_at": "",
+            "updated_at": "",
+        }))
+        .unwrap()
+    }
+
+    fn make_webfetch_tool_use(url: &str) -> ToolUse {
+        ToolUse {
+            id: "toolu_1".to_string(),
+            name: "WebFetch".to_string(),
+            input: serde_json::json!({"url": url}),
+        }
+    }
+
+    fn is_denied(url: &str, pattern: &str) -> bool {
+        matches_deny_pattern(
+            &Url::parse(url).unwrap(),
+            &parse_deny_pattern(pattern).unwrap(),
+        )
+    }
+
+    #[test]
+    fn parse_patterns() {
+        assert_eq!(
+            parse_deny_pattern("10.0.0.0/8"),
+            Ok(DenyPattern::Network {
+                address: "10.0.0.0".parse().unwrap(),
+                prefix_len: 8
+            })
+        );
+        assert_eq!(
+            parse_deny_pattern("169.254.169.254"),
+            Ok(DenyPattern::Network {
+                address: "169.254.169.254".parse().unwrap(),
+                prefix_len: 32
+            })
+        );
+        assert_eq!(
+            parse_deny_pattern("Metadata.Google.Internal."),
+            Ok(DenyPattern::Domain("metadata.google.internal".to_string()))
+        );
+        assert!(parse_deny_pattern("10.0.0.0/33").is_err());
+        assert!(parse_deny_pattern("example.com/path").is_err());
+        assert!(parse_deny_pattern("").is_err());
+    }
+
+    #[test]
+    fn match_ip_ranges() {
+        assert!(is_denied("http://10.1.2.3/", "10.0.0.0/8"));
+        assert!(!is_denied("http://11.1.2.3/", "10.0.0.0/8"));
+        assert!(is_denied(
+            "http://169.254.169.254/latest",
+            "169.254.169.254"
+        ));
+        // Numeric host forms are normalized by the URL parser.
+        assert!(is_denied("http://2130706433/", "127.0.0.0/8"));
+        assert!(is_denied("http://[::1]/", "::1/128"));
+        assert!(is_denied("http://[::ffff:10.0.0.1]/", "10.0.0.0/8"));
+        assert!(is_denied("http://1.2.3.4/", "0.0.0.0/0"));
+    }
+
+    #[test]
+    fn match_domains() {
+        assert!(is_denied("http://localhost:8080/", "localhost"));
+        assert!(is_denied(
+            "https://a.internal.example.com/",
+            "internal.example.com"
+        ));
+        assert!(!is_denied(
+            "https://notinternal.example.com/",
+            "internal.example.com"
+        ));
+        assert!(!is_denied("http://10.0.0.1/", "example.com"));
+    }
+
+    #[test]
+    fn find_denied_among_tool_uses() {
+        let rules = vec![
+            make_deny_rule("169.254.0.0/16"),
+            make_deny_rule("bad pattern/"),
+        ];
+        let webfetch_names = vec!["WebFetch".to_string()];
+        let tool_uses = vec![
+            make_webfetch_tool_use("https://docs.rs/"),
+            make_webfetch_tool_use("http://169.254.169.254/latest/meta-data"),
+        ];
+        assert_eq!(
+            find_denied_webfetch(&tool_uses, &rules, &webfetch_names).as_deref(),
+            Some("http://169.254.169.254/latest/meta-data matched deny rule 169.254.0.0/16")
+        );
+        assert!(find_denied_webfetch(&tool_uses[..1], &rules, &webfetch_names).is_none());
+    }
+}
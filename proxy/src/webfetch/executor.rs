@@ -0,0 +1,352 @@
+use common::config::{ToolExecutorConfig, ToolExecutorKind};
+use serde_json::Value;
+use std::process::Stdio;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+
+use super::extract::ToolUse;
+use super::fetch::{
+    build_accept_error, build_accept_result, build_truncation_notice, read_capped_body,
+    AcceptResult, FetchContext,
+};
+use super::mcp::run_mcp_executor;
+use super::mock::render_template;
+
+/// The executor configured for `tool_name`, if any.
+pub(super) fn find_tool_executor<'a>(
+    tool_executors: &'a [ToolExecutorConfig],
+    tool_name: &str,
+) -> Option<&'a ToolExecutorConfig> {
+    tool_executors
+        .iter()
+        .find(|executor| executor.tool_name == tool_name)
+}
+
+/// Tool names to intercept: the WebFetch names plus every tool with an executor.
+pub(super) fn collect_intercepted_names(
+    webfetch_names: &[String],
+    tool_executors: &[ToolExecutorConfig],
+) -> Vec<String> {
+    let mut intercepted_names = webfetch_names.to_vec();
+    for executor in tool_executors {
+        if !intercepted_names.contains(&executor.tool_name) {
+            intercepted_names.push(executor.tool_name.clone());
+        }
+    }
+    intercepted_names
+}
+
+/// Run an Accept-ed tool call with its configured executor, falling back to
+/// the built-in URL fetcher for WebFetch calls.
+pub(super) async fn execute_tool(tool_use: &ToolUse, ctx: &FetchContext<'_>) -> AcceptResult {
+    let Some(executor) = find_tool_executor(ctx.tool_executors, &tool_use.name) else {
+        return build_accept_result(tool_use, ctx).await;
+    };
+    match &executor.kind {
+        ToolExecutorKind::Fetch => build_accept_result(tool_use, ctx).await,
+        ToolExecutorKind::Command { command } => run_command_executor(command, tool_use, ctx).await,
+        ToolExecutorKind::Http { url } => run_http_executor(url, tool_use, ctx).await,
+        ToolExecutorKind::Mcp { tool } => {
+            let mcp_tool_name = tool.as_deref().unwrap_or(&tool_use.name);
+            run_mcp_executor(mcp_tool_name, tool_use, ctx).await
+        }
+    }
+}
+
+/// Quote a value as a single shell word. Strings are used as-is, anything
+/// else as its JSON text.
+fn quote_shell_arg(value: &Value) -> String {
+    let text = match value {
+        Value::String(text) => text.clone(),
+        _ => value.to_string(),
+    };
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Fill a command template's `{{field}}` placeholders with the shell-quoted
+/// top-level fields of the tool input. Unknown fields render as nothing.
+fn render_command(command: &str, input: &Value) -> String {
+    let quoted_fields: serde_json::Map<String, Value> = input
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| (key.clone(), Value::String(quote_shell_arg(value))))
+        .collect();
+    render_template(command, &Value::Object(quoted_fields))
+}
+
+/// Read at most `max_bytes` from a child's output pipe. Returns the bytes and
+/// whether more output was left unread.
+async fn read_capped_output(
+    pipe: Option<impl AsyncRead + Unpin>,
+    max_bytes: usize,
+) -> std::io::Result<(Vec<u8>, bool)> {
+    let Some(pipe) = pipe else {
+        return Ok((Vec::new(), false));
+    };
+    let mut bytes = Vec::new();
+    pipe.take(max_bytes as u64 + 1)
+        .read_to_end(&mut bytes)
+        .await?;
+    let truncated = bytes.len() > max_bytes;
+    bytes.truncate(max_bytes);
+    Ok((bytes, truncated))
+}
+
+/// Run a command executor with `sh -c`. The tool input is passed as JSON on
+/// stdin and in `TOOL_INPUT`; stdout becomes the tool_result and a non-zero
+/// exit status makes it an error carrying stderr.
+async fn run_command_executor(
+    command: &str,
+    tool_use: &ToolUse,
+    ctx: &FetchContext<'_>,
+) -> AcceptResult {
+    let command_line = render_command(command, &tool_use.input);
+    let input_json = tool_use.input.to_string();
+    let mut child = match Command::new("sh")
+        .arg("-c")
+        .arg(&command_line)
+        .env("TOOL_NAME", &tool_use.name)
+        .env("TOOL_USE_ID", &tool_use.id)
+        .env("TOOL_INPUT", &input_json)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            log::warn!("Tool executor: failed to start '{}': {}", command_line, e);
+            return build_accept_error(
+                tool_use,
+                format!("Failed to run the '{}' tool: {}", tool_use.name, e),
+            );
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Written in the background so a command that never reads stdin cannot block us.
+        tokio::spawn(async move {
+            let _ = stdin.write_all(input_json.as_bytes()).await;
+        });
+    }
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let run = async {
+        let (stdout, stderr) = tokio::try_join!(
+            read_capped_output(stdout, ctx.max_content_bytes),
+            read_capped_output(stderr, ctx.max_content_bytes),
+        )?;
+        // A command still writing past the cap would never exit on its own.
+        if stdout.1 {
+            let _ = child.start_kill();
+        }
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>((status, stdout, stderr))
+    };
+    let (status, (stdout, stdout_truncated), (stderr, _)) =
+        match tokio::time::timeout(ctx.fetch_timeout, run).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                return build_accept_error(
+                    tool_use,
+                    format!("Failed to run the '{}' tool: {}", tool_use.name, e),
+                );
+            }
+            Err(_) => {
+                log::info!("Tool executor: '{}' timed out", command_line);
+                return build_accept_error(
+                    tool_use,
+                    format!(
+                        "The '{}' tool timed out after {}s",
+                        tool_use.name,
+                        ctx.fetch_timeout.as_secs()
+                    ),
+                );
+            }
+        };
+
+    let mut content = String::from_utf8_lossy(&stdout).to_string();
+    if stdout_truncated {
+        content.push_str(&build_truncation_notice(ctx.max_content_bytes, None));
+    }
+    if !stdout_truncated && !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let output = if stderr.trim().is_empty() {
+            content.trim()
+        } else {
+            stderr.trim()
+        };
+        return build_accept_error(
+            tool_use,
+            format!(
+                "The '{}' tool failed ({}):\n{}",
+                tool_use.name, status, output
+            ),
+        );
+    }
+    AcceptResult {
+        tool_result: serde_json::json!({
+            "type": "tool_result",
+            "tool_use_id": tool_use.id,
+            "content": content,
+        }),
+        agent_request_id: None,
+        fetch_meta: None,
+    }
+}
+
+/// Build a tool_result from an HTTP executor's response, which must be a JSON
+/// object with `content` and optionally `is_error`. The tool_use id is always
+/// the call's own, whatever the service returned.
+fn build_http_tool_result(tool_use: &ToolUse, response_json: &Value) -> Result<Value, String> {
+    let content = response_json
+        .get("content")
+        .ok_or_else(|| "response has no 'content' field".to_string())?;
+    let mut tool_result = serde_json::json!({
+        "type": "tool_result",
+        "tool_use_id": tool_use.id,
+        "content": content,
+    });
+    if response_json.get("is_error").and_then(Value::as_bool) == Some(true) {
+        tool_result["is_error"] = Value::Bool(true);
+    }
+    Ok(tool_result)
+}
+
+/// POST the tool call to an external service and use its JSON response as
+/// the tool_result.
+async fn run_http_executor(url: &str, tool_use: &ToolUse, ctx: &FetchContext<'_>) -> AcceptResult {
+    let request_json = serde_json::json!({
+        "session_id": ctx.session_id,
+        "tool_use_id": tool_use.id,
+        "name": tool_use.name,
+        "input": tool_use.input,
+    });
+    let response = match ctx
+        .client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(request_json.to_string())
+        .timeout(ctx.fetch_timeout)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            log::warn!("Tool executor: request to {} failed: {}", url, e);
+            let reason = if e.is_timeout() {
+                format!("timed out after {}s", ctx.fetch_timeout.as_secs())
+            } else {
+                e.to_string()
+            };
+            return build_accept_error(
+                tool_use,
+                format!("The '{}' tool service failed: {}", tool_use.name, reason),
+            );
+        }
+    };
+    let status = response.status();
+    if !status.is_success() {
+        return build_accept_error(
+            tool_use,
+            format!(
+                "The '{}' tool service returned HTTP {}",
+                tool_use.name,
+                status.as_u16()
+            ),
+        );
+    }
+    let tool_result = match read_capped_body(response, ctx.max_content_bytes).await {
+        Ok(body) => serde_json::from_slice::<Value>(&body.bytes)
+            .map_err(|e| format!("invalid JSON response: {}", e))
+            .and_then(|response_json| build_http_tool_result(tool_use, &response_json)),
+        Err(e) => Err(format!("failed to read the response: {}", e)),
+    };
+    match tool_result {
+        Ok(tool_result) => AcceptResult {
+            tool_result,
+            agent_request_id: None,
+            fetch_meta: None,
+        },
+        Err(reason) => {
+            log::warn!("Tool executor: bad response from {}: {}", url, reason);
+            build_accept_error(
+                tool_use,
+                format!("The '{}' tool service failed: {}", tool_use.name, reason),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_executor(tool_name: &str, kind: ToolExecutorKind) -> ToolExecutorConfig {
+        ToolExecutorConfig {
+            tool_name: tool_name.to_string(),
+            kind,
+        }
+    }
+
+    fn make_tool_use(name: &str, input: Value) -> ToolUse {
+        ToolUse {
+            id: "toolu_1".to_string(),
+            name: name.to_string(),
+            input,
+        }
+    }
+
+    #[test]
+    fn render_command_quotes_input_fields() {
+        let input = serde_json::json!({"pattern": "it's; rm -rf /", "limit": 5});
+        assert_eq!(
+            render_command("grep -m {{limit}} -- {{pattern}} {{missing}}.", &input),
+            r"grep -m '5' -- 'it'\''s; rm -rf /' ."
+        );
+    }
+
+    #[test]
+    fn intercepted_names_include_executor_tools() {
+        let webfetch_names = vec!["WebFetch".to_string()];
+        let tool_executors = vec![
+            make_executor("WebFetch", ToolExecutorKind::Fetch),
+            make_executor(
+                "Grep",
+                ToolExecutorKind::Command {
+                    command: "grep".to_string(),
+                },
+            ),
+        ];
+        assert_eq!(
+            collect_intercepted_names(&webfetch_names, &tool_executors),
+            vec!["WebFetch".to_string(), "Grep".to_string()]
+        );
+        assert_eq!(
+            find_tool_executor(&tool_executors, "Grep").map(|executor| &executor.kind),
+            Some(&ToolExecutorKind::Command {
+                command: "grep".to_string()
+            })
+        );
+        assert!(find_tool_executor(&tool_executors, "Bash").is_none());
+    }
+
+    #[test]
+    fn http_tool_result_keeps_call_id() {
+        let tool_use = make_tool_use("LookupTicket", serde_json::json!({}));
+        let response_json =
+            serde_json::json!({"tool_use_id": "other", "content": "done", "is_error": true});
+        assert_eq!(
+            build_http_tool_result(&tool_use, &response_json).unwrap(),
+            serde_json::json!({
+                "type": "tool_result",
+                "tool_use_id": "toolu_1",
+                "content": "done",
+                "is_error": true,
+            })
+        );
+        assert!(build_http_tool_result(&tool_use, &serde_json::json!({})).is_err());
+    }
+}
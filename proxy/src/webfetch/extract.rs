@@ -1,9 +1,12 @@
+use common::models::WebfetchWhitelistRule;
+use common::sse::{find_stop_reason, reconstruct_content_blocks};
 use serde_json::Value;
 use std::collections::HashSet;
+use url::Url;
 
 /// A tool_use block extracted from SSE events.
 #[derive(Debug, Clone)]
-pub(super) struct ToolUse {
+pub struct ToolUse {
     pub id: String,
     pub name: String,
     pub input: Value,
@@ -11,27 +14,43 @@ pub(super) struct ToolUse {
 
 /// Intercepted tool_use blocks extracted from the SSE stream.
 #[derive(Debug)]
-pub(super) struct InterceptedTools {
+pub struct InterceptedTools {
     pub content_blocks: Vec<Value>,
     pub tool_uses: Vec<ToolUse>,
 }
 
-/// Check if a host matches a whitelisted domain (exact match or subdomain).
-/// E.g. whitelist entry "github.com" matches "github.com" and "api.github.com".
-pub(super) fn matches_whitelist_host(host: &str, whitelist: &[String]) -> bool {
-    whitelist
-        .iter()
-        .any(|domain| host == domain || host.ends_with(&format!(".{}", domain)))
+/// Check if a URL is covered by a whitelist rule: the host is the rule's domain
+/// (or one of its subdomains, when allowed), the path starts with the rule's path
+/// prefix, and the rule has not expired as of `now` ("YYYY-MM-DD HH:MM:SS", UTC).
+pub(super) fn matches_whitelist_rule(url: &Url, rule: &WebfetchWhitelistRule, now: &str) -> bool {
+    if rule
+        .expires_at
+        .as_deref()
+        .is_some_and(|expires_at| expires_at <= now)
+    {
+        return false;
+    }
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host_matches = host == rule.domain
+        || (rule.allow_subdomains && host.ends_with(&format!(".{}", rule.domain)));
+    host_matches
+        && rule
+            .path_prefix
+            .as_deref()
+            .is_none_or(|path_prefix| url.path().starts_with(path_prefix))
 }
 
 /// Check if ALL tool calls are whitelisted WebFetch calls.
-/// Returns true only if every tool is a WebFetch with a URL whose host matches the whitelist.
+/// Returns true only if every tool is a WebFetch with a URL matched by some rule.
 pub(super) fn is_all_whitelisted(
     tool_uses: &[ToolUse],
-    whitelist: &[String],
+    rules: &[WebfetchWhitelistRule],
     webfetch_names: &[String],
+    now: &str,
 ) -> bool {
-    if whitelist.is_empty() || tool_uses.is_empty() {
+    if rules.is_empty() || tool_uses.is_empty() {
         return false;
     }
     tool_uses.iter().all(|tool_use| {
@@ -42,124 +61,24 @@ pub(super) fn is_all_whitelisted(
             Some(url) => url,
             None => return false,
         };
-        let parsed = match url::Url::parse(url_str) {
+        let parsed = match Url::parse(url_str) {
             Ok(url) => url,
             Err(_) => return false,
         };
-        let host = match parsed.host_str() {
-            Some(host) => host,
-            None => return false,
-        };
-        matches_whitelist_host(host, whitelist)
+        rules
+            .iter()
+            .any(|rule| matches_whitelist_rule(&parsed, rule, now))
     })
 }
 
 /// Parse SSE events and detect webfetch tool usage — `tool_use` blocks with
 /// stop_reason "tool_use" (custom tools needing a follow-up request).
-pub(super) fn extract_webfetch_from_sse(
+pub fn extract_webfetch_from_sse(
     events: &[Value],
     webfetch_names: &[String],
 ) -> Option<InterceptedTools> {
-    let stop_reason = events.iter().find_map(|event| {
-        if event.get("event").and_then(|field| field.as_str()) != Some("message_delta") {
-            return None;
-        }
-        event
-            .get("data")
-            .and_then(|data| data.get("delta"))
-            .and_then(|delta| delta.get("stop_reason"))
-            .and_then(|field| field.as_str())
-            .map(|string| string.to_string())
-    });
-
-    // Reconstruct all content blocks from SSE events
-    let mut content_blocks: Vec<Value> = Vec::new();
-    let mut current_block: Option<Value> = None;
-    let mut text_accum = String::new();
-    let mut json_accum = String::new();
-    let mut thinking_accum = String::new();
-    let mut signature_accum = String::new();
-
-    for event in events {
-        let event_type = match event.get("event").and_then(|field| field.as_str()) {
-            Some(event_type) => event_type,
-            None => continue,
-        };
-        let data = match event.get("data") {
-            Some(data) => data,
-            None => continue,
-        };
-
-        match event_type {
-            "content_block_start" => {
-                current_block = data.get("content_block").cloned();
-                text_accum.clear();
-                json_accum.clear();
-                thinking_accum.clear();
-                signature_accum.clear();
-            }
-            "content_block_delta" => {
-                if let Some(delta) = data.get("delta") {
-                    let delta_type = delta.get("type").and_then(|field| field.as_str()).unwrap_or("");
-                    match delta_type {
-                        "text_delta" => {
-                            if let Some(text) = delta.get("text").and_then(|field| field.as_str()) {
-                                text_accum.push_str(text);
-                            }
-                        }
-                        "input_json_delta" => {
-                            if let Some(json_part) =
-                                delta.get("partial_json").and_then(|field| field.as_str())
-                            {
-                                json_accum.push_str(json_part);
-                            }
-                        }
-                        "thinking_delta" => {
-                            if let Some(text) = delta.get("thinking").and_then(|field| field.as_str()) {
-                                thinking_accum.push_str(text);
-                            }
-                        }
-                        "signature_delta" => {
-                            if let Some(signature) =
-                                delta.get("signature").and_then(|field| field.as_str()) {
-                                signature_accum.push_str(signature);
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            "content_block_stop" => {
-                if let Some(mut block) = current_block.take() {
-                    let block_type = block
-                        .get("type")
-                        .and_then(|field| field.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    match block_type.as_str() {
-                        "text" => {
-                            block["text"] = Value::String(text_accum.clone());
-                        }
-                        "thinking" => {
-                            block["thinking"] = Value::String(thinking_accum.clone());
-                            if !signature_accum.is_empty() {
-                                block["signature"] = Value::String(signature_accum.clone());
-                            }
-                        }
-                        // Both custom tool_use and native server_tool_use carry input JSON
-                        "tool_use" | "server_tool_use" => {
-                            let input: Value =
-                                serde_json::from_str(&json_accum).unwrap_or(serde_json::json!({}));
-                            block["input"] = input;
-                        }
-                        _ => {}
-                    }
-                    content_blocks.push(block);
-                }
-            }
-            _ => {}
-        }
-    }
+    let stop_reason = find_stop_reason(events);
+    let content_blocks = reconstruct_content_blocks(events);
 
     match stop_reason.as_deref() {
         Some("tool_use") => {
@@ -1,14 +1,25 @@
+use chrono::{TimeDelta, Utc};
+use common::config::ToolExecutorConfig;
+use common::models::{Session, WebfetchCacheEntry};
+use common::tokens::count_text_tokens;
+use db::WebfetchCacheEntryParams;
 use serde_json::Value;
+use std::{
+    ops::ControlFlow,
+    time::{Duration, Instant},
+};
 
 use super::extract::ToolUse;
+use super::mcp::McpServer;
+use super::guard::{check_fetch_target, select_fetch_client};
 use super::mock::render_template;
+use super::page_text::extract_page_text;
+use super::robots::check_robots_txt;
 use crate::shared::{
-    extract_request_fields, headers_to_json, log_request, store_response, RequestMeta,
+    apply_session_auth_headers, extract_request_fields, headers_to_json, log_request,
+    store_response, RequestMeta,
 };
-use crate::sse::{extract_text_from_events, parse_sse_events};
-
-/// Maximum size (in bytes) of fetched content to include in a tool_result.
-const MAX_ACCEPT_CONTENT_BYTES: usize = 100 * 1024;
+use crate::sse::{extract_text_from_events, parse_sse_events, SseParser};
 
 pub const WEBFETCH_AGENT_SYSTEM_PROMPT: &str =
     "You are Claude Code, Anthropic's official CLI for Claude.";
@@ -17,59 +28,106 @@ pub const WEBFETCH_AGENT_SYSTEM_PROMPT: &str =
 pub(super) struct AcceptResult {
     pub tool_result: Value,
     pub agent_request_id: Option<String>,
+    /// URL, original length and bytes read of the fetched page, if one was read.
+    pub fetch_meta: Option<Value>,
+}
+
+/// A fetched page body, cut off at the session's size cap.
+pub(super) struct FetchedBody {
+    pub bytes: Vec<u8>,
+    /// The page's full length: its Content-Length, or the bytes read when the
+    /// whole body fit under the cap.
+    original_length: Option<u64>,
+    truncated: bool,
+}
+
+/// A session's override of where agent summarization requests are sent.
+/// Unset fields fall back to the session's upstream and the configured model.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AgentBackend<'a> {
+    /// Messages endpoint URL, e.g. `http://localhost:4000/v1/messages`.
+    pub url: Option<&'a str>,
+    pub model: Option<&'a str>,
+    /// Sent as `x-api-key` in place of the client's credentials.
+    pub api_key: Option<&'a str>,
+}
+
+impl<'a> AgentBackend<'a> {
+    pub fn from_session(session: &'a Session) -> Self {
+        AgentBackend {
+            url: session.webfetch_agent_url.as_deref(),
+            model: session.webfetch_agent_model.as_deref(),
+            api_key: session.webfetch_agent_api_key.as_deref(),
+        }
+    }
 }
 
 /// Shared context for fetching and agent requests.
+#[derive(Clone)]
 pub(super) struct FetchContext<'a> {
     pub client: &'a reqwest::Client,
     pub webfetch_names: &'a [String],
     pub accept_prompt: &'a str,
     pub redirect_prompt: &'a str,
     pub agent_model: &'a str,
+    /// `max_tokens` of an agent request, and the estimated output tokens read
+    /// before its stream is cut off.
+    pub agent_max_output_tokens: i64,
+    /// Bytes of an agent response read before its stream is cut off.
+    pub agent_max_response_bytes: usize,
     pub target_url: &'a str,
     pub forward_headers: &'a reqwest::header::HeaderMap,
     pub pool: &'a sqlx::SqlitePool,
     pub session_id: &'a str,
     pub stored_path: &'a str,
+    pub allowed_schemes: &'a [String],
+    pub max_redirects: usize,
+    pub allow_private_targets: bool,
+    pub max_content_bytes: usize,
+    pub fetch_timeout: Duration,
+    /// Seconds a fetched page is reused for the same URL; 0 disables the cache.
+    pub cache_ttl_secs: u64,
+    /// Key of the `common::extraction` mode used to turn pages into text.
+    pub extraction_mode: &'a str,
+    /// Check each URL against its site's robots.txt before fetching it.
+    pub respect_robots_txt: bool,
+    /// Return the page text as the tool_result, skipping the agent request.
+    pub raw_mode: bool,
+    /// Executors for Accept-ed calls, looked up by tool name.
+    pub tool_executors: &'a [ToolExecutorConfig],
+    /// The session's MCP server, used by `mcp` executors.
+    pub mcp_server: Option<McpServer<'a>>,
+    /// The session's agent backend override.
+    pub agent_backend: AgentBackend<'a>,
 }
 
 /// Actually fetch the URL for a WebFetch tool call and return the content as a tool_result.
 /// For non-WebFetch tools this returns an error result since we can only
 /// perform URL fetching.
 ///
-/// When `agent_model` is non-empty, sends the rendered page content to an agentic API
-/// request instead of stuffing it directly into the tool_result.
+/// The page text is summarized by an agent request, unless `raw_mode` is set,
+/// in which case it becomes the tool_result as-is.
 pub(super) async fn build_accept_result(
     tool_use: &ToolUse,
     ctx: &FetchContext<'_>,
 ) -> AcceptResult {
     if !ctx.webfetch_names.iter().any(|name| name == &tool_use.name) {
-        return AcceptResult {
-            tool_result: serde_json::json!({
-                "type": "tool_result",
-                "tool_use_id": tool_use.id,
-                "is_error": true,
-                "content": format!(
-                    "Accept is only supported for WebFetch tool calls. '{}' cannot be executed by the proxy.",
-                    tool_use.name
-                ),
-            }),
-            agent_request_id: None,
-        };
+        return build_accept_error(
+            tool_use,
+            format!(
+                "Accept is only supported for WebFetch tool calls and tools with a configured executor. '{}' cannot be executed by the proxy.",
+                tool_use.name
+            ),
+        );
     }
 
     let url_str = match tool_use.input.get("url").and_then(|field| field.as_str()) {
         Some(url) => url,
         None => {
-            return AcceptResult {
-                tool_result: serde_json::json!({
-                    "type": "tool_result",
-                    "tool_use_id": tool_use.id,
-                    "is_error": true,
-                    "content": "WebFetch tool call is missing the 'url' input field.",
-                }),
-                agent_request_id: None,
-            };
+            return build_accept_error(
+                tool_use,
+                "WebFetch tool call is missing the 'url' input field.".to_string(),
+            );
         }
     };
 
@@ -82,271 +140,389 @@ pub(super) async fn build_accept_result(
     let original_url = match url::Url::parse(url_str) {
         Ok(url) => url,
         Err(e) => {
-            return AcceptResult {
-                tool_result: serde_json::json!({
-                    "type": "tool_result",
-                    "tool_use_id": tool_use.id,
-                    "is_error": true,
-                    "content": format!("Invalid URL '{}': {}", url_str, e),
-                }),
-                agent_request_id: None,
-            };
+            return build_accept_error(tool_use, format!("Invalid URL '{}': {}", url_str, e));
         }
     };
 
     let original_host = original_url.host_str().unwrap_or("").to_string();
+    // The timeout covers the whole fetch, redirects and body included.
+    let deadline = Instant::now() + ctx.fetch_timeout;
 
-    // Fetch with Accept header preferring markdown/html
-    let fetch_response = match ctx
-        .client
-        .get(url_str)
-        .header("Accept", "text/markdown, text/html, */*")
-        .send()
+    // Follow same-host redirects manually (client has redirect::Policy::none()),
+    // checking every hop against the SSRF guard. Without private targets, the
+    // fetch client also refuses to connect to private addresses, so a host
+    // can't pass the check and then resolve elsewhere.
+    let mut current_url = original_url;
+    let mut redirect_count = 0;
+    let fetch_response = loop {
+        if let Err(reason) = check_fetch_target(
+            &current_url,
+            ctx.allowed_schemes,
+            ctx.allow_private_targets,
+        )
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
+        {
+            log::warn!("WebFetch accept: refused '{}': {}", current_url, reason);
+            return build_accept_error(
+                tool_use,
+                format!("Refused to fetch '{}': {}", current_url, reason),
+            );
+        }
+
+        if ctx.respect_robots_txt {
+            if let Err(reason) = check_robots_txt(&current_url, deadline, ctx).await {
+                log::info!("WebFetch accept: robots.txt refused '{}'", current_url);
+                return build_accept_error(tool_use, reason);
+            }
+        }
+
+        // Reuse a fresh copy of the requested URL. This runs after the guard so
+        // a session that has since blocked a target cannot read it back.
+        if redirect_count == 0 {
+            if let Some(entry) = find_cached_fetch(url_str, ctx).await {
+                return build_cached_accept_result(
+                    &tool_use.id,
+                    &entry,
+                    user_prompt,
+                    &original_host,
+                    ctx,
+                )
+                .await;
+            }
+        }
+
+        // Fetch with Accept header preferring markdown/html
+        let response = match select_fetch_client(ctx.client, ctx.allow_private_targets)
+            .get(current_url.as_str())
+            .header("Accept", "text/markdown, text/html, */*")
+            .timeout(deadline.saturating_duration_since(Instant::now()))
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => {
+                return build_accept_error(tool_use, build_timeout_message(&current_url, ctx));
+            }
+            Err(e) => {
+                return build_accept_error(
+                    tool_use,
+                    format!("Failed to fetch URL '{}': {}", current_url, e),
+                );
+            }
+        };
+
+        let status = response.status();
+        if !status.is_redirection() {
+            break response;
+        }
+        let Some(location) = response
+            .headers()
+            .get("location")
+            .and_then(|header_value| header_value.to_str().ok())
+        else {
+            return build_accept_error(
+                tool_use,
+                format!(
+                    "HTTP {} redirect without Location header for '{}'",
+                    status.as_u16(),
+                    current_url
+                ),
+            );
+        };
+        // Resolve relative redirects against the current URL
+        let redirect_url = match current_url.join(location) {
+            Ok(url) => url,
+            Err(_) => {
+                return build_accept_error(
+                    tool_use,
+                    format!("Redirect to invalid URL: {}", location),
+                );
+            }
+        };
+
+        if redirect_url.host_str().unwrap_or("") != original_host {
+            // Cross-host redirect: inform the LLM so it can re-call with the new URL
+            let content = render_template(
+                ctx.redirect_prompt,
+                &serde_json::json!({
+                    "original_url": url_str,
+                    "redirect_url": redirect_url.as_str(),
+                    "status": status.as_u16().to_string(),
+                    "prompt": user_prompt,
+                }),
+            );
             return AcceptResult {
                 tool_result: serde_json::json!({
                     "type": "tool_result",
                     "tool_use_id": tool_use.id,
-                    "is_error": true,
-                    "content": format!("Failed to fetch URL '{}': {}", url_str, e),
+                    "content": content,
                 }),
                 agent_request_id: None,
+                fetch_meta: None,
             };
         }
+
+        redirect_count += 1;
+        if redirect_count > ctx.max_redirects {
+            return build_accept_error(
+                tool_use,
+                format!(
+                    "Too many redirects (more than {}) when fetching '{}'",
+                    ctx.max_redirects, url_str
+                ),
+            );
+        }
+        current_url = redirect_url;
     };
 
     let status = fetch_response.status();
 
-    // Handle redirects (client has redirect::Policy::none())
-    if status.is_redirection() {
-        if let Some(location) = fetch_response
-            .headers()
-            .get("location")
-            .and_then(|header_value| header_value.to_str().ok())
-        {
-            // Resolve relative redirects against the original URL
-            let redirect_url = match original_url.join(location) {
-                Ok(url) => url,
-                Err(_) => {
-                    return AcceptResult {
-                        tool_result: serde_json::json!({
-                            "type": "tool_result",
-                            "tool_use_id": tool_use.id,
-                            "is_error": true,
-                            "content": format!("Redirect to invalid URL: {}", location),
-                        }),
-                        agent_request_id: None,
-                    };
-                }
-            };
-            let redirect_host = redirect_url.host_str().unwrap_or("").to_string();
-
-            if redirect_host != original_host {
-                // Cross-host redirect: inform the LLM so it can re-call with the new URL
-                let content = render_template(
-                    ctx.redirect_prompt,
-                    &serde_json::json!({
-                        "original_url": url_str,
-                        "redirect_url": redirect_url.as_str(),
-                        "status": status.as_u16().to_string(),
-                        "prompt": user_prompt,
-                    }),
-                );
-                return AcceptResult {
-                    tool_result: serde_json::json!({
-                        "type": "tool_result",
-                        "tool_use_id": tool_use.id,
-                        "content": content,
-                    }),
-                    agent_request_id: None,
-                };
-            }
+    if !status.is_success() {
+        return build_accept_error(
+            tool_use,
+            format!(
+                "HTTP error {} when fetching '{}'",
+                status.as_u16(),
+                current_url
+            ),
+        );
+    }
 
-            // Same-host redirect: follow it manually
-            let follow_response = match ctx
-                .client
-                .get(redirect_url.as_str())
-                .header("Accept", "text/markdown, text/html, */*")
-                .send()
-                .await
-            {
-                Ok(response) => response,
-                Err(e) => {
-                    return AcceptResult {
-                        tool_result: serde_json::json!({
-                            "type": "tool_result",
-                            "tool_use_id": tool_use.id,
-                            "is_error": true,
-                            "content": format!("Failed to follow redirect to '{}': {}", redirect_url, e),
-                        }),
-                        agent_request_id: None,
-                    };
-                }
-            };
+    match read_capped_body(fetch_response, ctx.max_content_bytes).await {
+        Ok(body) => {
+            let text = convert_body_to_text(&body, ctx.extraction_mode, ctx.max_content_bytes);
+            store_cached_fetch(url_str, &text, &body, ctx).await;
+            let mut accept =
+                build_page_result(&tool_use.id, &text, user_prompt, &original_host, ctx).await;
+            accept.fetch_meta = Some(serde_json::json!({
+                "url": current_url.as_str(),
+                "original_length": body.original_length,
+                "bytes_read": body.bytes.len(),
+                "truncated": body.truncated,
+                "cached": false,
+            }));
+            accept
+        }
+        Err(e) if e.is_timeout() => {
+            build_accept_error(tool_use, build_timeout_message(&current_url, ctx))
+        }
+        Err(e) => build_accept_error(
+            tool_use,
+            format!(
+                "Failed to read response body from '{}': {}",
+                current_url, e
+            ),
+        ),
+    }
+}
 
-            if !follow_response.status().is_success() {
-                return AcceptResult {
-                    tool_result: serde_json::json!({
-                        "type": "tool_result",
-                        "tool_use_id": tool_use.id,
-                        "is_error": true,
-                        "content": format!("HTTP error {} when fetching '{}'", follow_response.status().as_u16(), redirect_url),
-                    }),
-                    agent_request_id: None,
-                };
-            }
+/// An error tool_result for an Accept-ed tool call that could not be fetched.
+pub(super) fn build_accept_error(tool_use: &ToolUse, content: String) -> AcceptResult {
+    AcceptResult {
+        tool_result: serde_json::json!({
+            "type": "tool_result",
+            "tool_use_id": tool_use.id,
+            "is_error": true,
+            "content": content,
+        }),
+        agent_request_id: None,
+        fetch_meta: None,
+    }
+}
 
-            return match follow_response.bytes().await {
-                Ok(bytes) => {
-                    parse_bytes_to_accept_result(
-                        &tool_use.id,
-                        &bytes,
-                        user_prompt,
-                        &original_host,
-                        ctx,
-                    )
-                    .await
-                }
-                Err(e) => AcceptResult {
-                    tool_result: serde_json::json!({
-                        "type": "tool_result",
-                        "tool_use_id": tool_use.id,
-                        "is_error": true,
-                        "content": format!("Failed to read response body from '{}': {}", redirect_url, e),
-                    }),
-                    agent_request_id: None,
-                },
-            };
-        }
+fn build_timeout_message(url: &url::Url, ctx: &FetchContext<'_>) -> String {
+    format!(
+        "Timed out after {}s when fetching '{}'",
+        ctx.fetch_timeout.as_secs(),
+        url
+    )
+}
 
-        // 3xx without Location header
-        return AcceptResult {
-            tool_result: serde_json::json!({
-                "type": "tool_result",
-                "tool_use_id": tool_use.id,
-                "is_error": true,
-                "content": format!("HTTP {} redirect without Location header for '{}'", status.as_u16(), url_str),
-            }),
-            agent_request_id: None,
-        };
+/// Append `chunk` to `bytes` without letting it grow past `max_bytes`.
+/// Returns `false` when part of the chunk had to be dropped.
+fn append_capped(bytes: &mut Vec<u8>, chunk: &[u8], max_bytes: usize) -> bool {
+    let remaining = max_bytes.saturating_sub(bytes.len());
+    if chunk.len() > remaining {
+        bytes.extend_from_slice(&chunk[..remaining]);
+        return false;
     }
+    bytes.extend_from_slice(chunk);
+    true
+}
 
-    if !status.is_success() {
-        return AcceptResult {
-            tool_result: serde_json::json!({
-                "type": "tool_result",
-                "tool_use_id": tool_use.id,
-                "is_error": true,
-                "content": format!("HTTP error {} when fetching '{}'", status.as_u16(), url_str),
-            }),
-            agent_request_id: None,
-        };
+/// Stream a response body, stopping once `max_bytes` have been read.
+pub(super) async fn read_capped_body(
+    mut response: reqwest::Response,
+    max_bytes: usize,
+) -> Result<FetchedBody, reqwest::Error> {
+    let content_length = response.content_length();
+    let mut bytes = Vec::new();
+    let mut truncated = false;
+    while let Some(chunk) = response.chunk().await? {
+        if !append_capped(&mut bytes, &chunk, max_bytes) {
+            truncated = true;
+            break;
+        }
     }
+    let original_length = content_length.or((!truncated).then_some(bytes.len() as u64));
+    Ok(FetchedBody {
+        bytes,
+        original_length,
+        truncated,
+    })
+}
+
+/// Cache entries fetched after this time ("YYYY-MM-DD HH:MM:SS", UTC) are
+/// still fresh under `ttl_secs`.
+pub fn build_cache_cutoff(ttl_secs: u64) -> String {
+    i64::try_from(ttl_secs)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|ttl| Utc::now().checked_sub_signed(ttl))
+        .map(|cutoff| cutoff.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
 
-    match fetch_response.bytes().await {
-        Ok(bytes) => {
-            parse_bytes_to_accept_result(&tool_use.id, &bytes, user_prompt, &original_host, ctx)
-                .await
+/// Look up a fresh cached copy of `url` for this session.
+async fn find_cached_fetch(url: &str, ctx: &FetchContext<'_>) -> Option<WebfetchCacheEntry> {
+    if ctx.cache_ttl_secs == 0 {
+        return None;
+    }
+    let fetched_after = build_cache_cutoff(ctx.cache_ttl_secs);
+    match db::find_fresh_webfetch_cache_entry(ctx.pool, ctx.session_id, url, &fetched_after).await
+    {
+        Ok(entry) => entry,
+        Err(e) => {
+            log::warn!("WebFetch accept: failed to read cache: {}", e);
+            None
         }
-        Err(e) => AcceptResult {
-            tool_result: serde_json::json!({
-                "type": "tool_result",
-                "tool_use_id": tool_use.id,
-                "is_error": true,
-                "content": format!("Failed to read response body from '{}': {}", url_str, e),
-            }),
-            agent_request_id: None,
-        },
     }
 }
 
-/// Helper: send fetched bytes to agent model for summarization.
-async fn parse_bytes_to_accept_result(
+/// Cache the text of a fetched page under the requested URL, dropping the
+/// session's stale entries.
+async fn store_cached_fetch(url: &str, text: &str, body: &FetchedBody, ctx: &FetchContext<'_>) {
+    if ctx.cache_ttl_secs == 0 {
+        return;
+    }
+    let fetched_before = build_cache_cutoff(ctx.cache_ttl_secs);
+    if let Err(e) =
+        db::delete_stale_webfetch_cache_entries(ctx.pool, ctx.session_id, &fetched_before).await
+    {
+        log::warn!("WebFetch accept: failed to prune cache: {}", e);
+    }
+    let params = WebfetchCacheEntryParams {
+        url,
+        content: text,
+        original_length: body.original_length.map(|length| length as i64),
+        bytes_read: body.bytes.len() as i64,
+        truncated: body.truncated,
+    };
+    if let Err(e) = db::upsert_webfetch_cache_entry(ctx.pool, ctx.session_id, &params).await {
+        log::warn!("WebFetch accept: failed to store cache entry: {}", e);
+    }
+}
+
+/// Build the accept result from a cached page. Outside raw mode the agent
+/// still runs with the new prompt; the tool_result is marked as cached.
+async fn build_cached_accept_result(
     tool_use_id: &str,
-    bytes: &[u8],
+    entry: &WebfetchCacheEntry,
     user_prompt: &str,
     url_host: &str,
     ctx: &FetchContext<'_>,
 ) -> AcceptResult {
-    let rendered = render_accept_content(bytes, ctx.accept_prompt, user_prompt);
-    send_agent_request(tool_use_id, &rendered, url_host, ctx).await
+    log::info!(
+        "WebFetch accept: using cached '{}' fetched at {}",
+        entry.url,
+        entry.fetched_at
+    );
+    let mut accept =
+        build_page_result(tool_use_id, &entry.content, user_prompt, url_host, ctx).await;
+    if let Some(Value::String(content)) = accept.tool_result.get_mut("content") {
+        content.push_str(&build_cached_marker(&entry.fetched_at));
+    }
+    accept.fetch_meta = Some(serde_json::json!({
+        "url": entry.url,
+        "original_length": entry.original_length,
+        "bytes_read": entry.bytes_read,
+        "truncated": entry.truncated,
+        "cached": true,
+        "fetched_at": entry.fetched_at,
+    }));
+    accept
 }
 
-/// Convert fetched HTML bytes into rendered text content using the accept prompt template.
-/// Returns the rendered string (HTML-to-text + truncation + Handlebars template).
-fn render_accept_content(bytes: &[u8], accept_prompt: &str, user_prompt: &str) -> String {
-    let text = match html2text::from_read(bytes, 120) {
-        Ok(text) => text,
-        Err(_) => String::from_utf8_lossy(bytes).to_string(),
-    };
-    let raw_content = if text.len() > MAX_ACCEPT_CONTENT_BYTES {
-        let mut truncated = text[..MAX_ACCEPT_CONTENT_BYTES].to_string();
-        truncated.push_str("\n\n[Content truncated at 100KB]");
-        truncated
-    } else {
-        text
-    };
-    render_template(
-        accept_prompt,
-        &serde_json::json!({"content": raw_content, "prompt": user_prompt}),
-    )
+/// Note appended to a tool_result served from the cache.
+fn build_cached_marker(fetched_at: &str) -> String {
+    format!("\n\n[Cached: fetched at {} UTC]", fetched_at)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn render_accept_content_basic_html() {
-        let html = b"<html><body><h1>Hello World</h1><p>Some content</p></body></html>";
-        let result = render_accept_content(html, "Content: {{content}}", "summarize this");
-        assert!(result.contains("Hello World"));
-        assert!(result.contains("Some content"));
-        assert!(result.starts_with("Content: "));
+/// Note appended to content cut off at `max_bytes`, with the page's full length if known.
+pub(super) fn build_truncation_notice(max_bytes: usize, original_length: Option<u64>) -> String {
+    match original_length {
+        Some(original_length) => format!(
+            "\n\n[Content truncated at {} bytes; original length {} bytes]",
+            max_bytes, original_length
+        ),
+        None => format!(
+            "\n\n[Content truncated at {} bytes; original length unknown]",
+            max_bytes
+        ),
     }
+}
 
-    #[test]
-    fn render_accept_content_plain_text() {
-        let text = b"Just plain text content";
-        let result = render_accept_content(text, "{{content}}", "");
-        assert!(result.contains("Just plain text content"));
+/// Convert a fetched HTML body into text with the session's extraction mode,
+/// truncated to `max_content_bytes` with a note when the body or the text was cut off.
+fn convert_body_to_text(
+    body: &FetchedBody,
+    extraction_mode: &str,
+    max_content_bytes: usize,
+) -> String {
+    let mut raw_content = extract_page_text(&body.bytes, extraction_mode);
+    let is_text_too_long = raw_content.len() > max_content_bytes;
+    if is_text_too_long {
+        raw_content.truncate(raw_content.floor_char_boundary(max_content_bytes));
     }
-
-    #[test]
-    fn render_accept_content_includes_prompt() {
-        let html = b"<p>Page</p>";
-        let result =
-            render_accept_content(html, "Content: {{content}} Prompt: {{prompt}}", "my prompt");
-        assert!(result.contains("my prompt"));
-    }
-
-    #[test]
-    fn render_accept_content_truncation() {
-        // Create content larger than 100KB
-        let large_html = vec![b'a'; 200 * 1024];
-        let result = render_accept_content(&large_html, "{{content}}", "");
-        assert!(result.contains("[Content truncated at 100KB]"));
-        // The output should be bounded in size (template wrapping + truncated content)
-        assert!(result.len() < 150 * 1024);
+    if body.truncated || is_text_too_long {
+        raw_content.push_str(&build_truncation_notice(
+            max_content_bytes,
+            body.original_length,
+        ));
     }
+    raw_content
+}
 
-    #[test]
-    fn render_accept_content_empty_template() {
-        let html = b"<p>test</p>";
-        let result = render_accept_content(html, "", "");
-        // Empty template renders to empty string
-        assert!(result.is_empty());
+/// Turn fetched page text into the accept result: the agent's summary, or
+/// the text itself in raw mode.
+async fn build_page_result(
+    tool_use_id: &str,
+    text: &str,
+    user_prompt: &str,
+    url_host: &str,
+    ctx: &FetchContext<'_>,
+) -> AcceptResult {
+    if ctx.raw_mode {
+        return AcceptResult {
+            tool_result: serde_json::json!({
+                "type": "tool_result",
+                "tool_use_id": tool_use_id,
+                "content": text,
+            }),
+            agent_request_id: None,
+            fetch_meta: None,
+        };
     }
+    let rendered = render_accept_content(text, ctx.accept_prompt, user_prompt);
+    send_agent_request(tool_use_id, &rendered, url_host, ctx).await
+}
 
-    #[test]
-    fn render_accept_content_no_template_vars() {
-        let html = b"<p>test</p>";
-        let result = render_accept_content(html, "static prompt", "");
-        assert_eq!(result, "static prompt");
-    }
+/// Render page text into the accept prompt template.
+fn render_accept_content(text: &str, accept_prompt: &str, user_prompt: &str) -> String {
+    render_template(
+        accept_prompt,
+        &serde_json::json!({"content": text, "prompt": user_prompt}),
+    )
 }
 
 /// Log an agent request to the database. Returns the request ID on success.
@@ -357,22 +533,26 @@ async fn log_agent_request(
 ) -> Result<String, ()> {
     let note = format!("webfetch agent ({})", url_host);
     let fields = extract_request_fields(agent_body, None).unwrap_or_default();
+    let agent_headers = build_agent_headers(ctx.forward_headers, &ctx.agent_backend);
     let headers_json = headers_to_json(
-        ctx.forward_headers
+        agent_headers
             .iter()
             .filter_map(|(key, value)| value.to_str().ok().map(|string| (key.to_string(), string.to_string()))),
     )
     .ok();
     match log_request(
         &RequestMeta {
+            id: None,
             pool: ctx.pool,
             session_id: ctx.session_id,
             method: "POST",
             path: ctx.stored_path,
             headers_json: headers_json.as_deref(),
             note: Some(&note),
+            client: None,
+            parent_request_id: None,
         },
-        &fields,
+        fields,
     )
     .await
     {
@@ -384,22 +564,154 @@ async fn log_agent_request(
     }
 }
 
-/// Send the agent request upstream and return `(status, headers, body)`.
+/// Headers for an agent request. A custom agent URL never receives the
+/// client's credentials; the backend's API key replaces them when set.
+fn build_agent_headers(
+    forward_headers: &reqwest::header::HeaderMap,
+    agent_backend: &AgentBackend<'_>,
+) -> reqwest::header::HeaderMap {
+    let mut agent_headers = forward_headers.clone();
+    agent_headers.remove(reqwest::header::CONTENT_LENGTH);
+    if agent_backend.url.is_some() || agent_backend.api_key.is_some() {
+        agent_headers.remove(reqwest::header::AUTHORIZATION);
+        agent_headers.remove("x-api-key");
+    }
+    apply_session_auth_headers(&mut agent_headers, None, agent_backend.api_key);
+    agent_headers
+}
+
+/// Model for an agent request: the session's override, then
+/// `ANTHROPIC_DEFAULT_HAIKU_MODEL`, then the configured `webfetch_agent_model`.
+fn resolve_agent_model(ctx: &FetchContext<'_>) -> String {
+    match ctx.agent_backend.model {
+        Some(model) => model.to_string(),
+        None => std::env::var("ANTHROPIC_DEFAULT_HAIKU_MODEL")
+            .unwrap_or_else(|_| ctx.agent_model.to_string()),
+    }
+}
+
+/// An agent response, read as it streamed and cut off at the context's bounds.
+struct AgentResponse {
+    status: u16,
+    headers: reqwest::header::HeaderMap,
+    body: Vec<u8>,
+    /// Why reading stopped before the stream ended, if it did.
+    abort_reason: Option<String>,
+}
+
+/// Bounds on reading an agent response stream.
+struct AgentStreamLimits {
+    max_bytes: usize,
+    max_output_tokens: i64,
+}
+
+/// Reading state of an agent response stream.
+struct AgentStreamReader {
+    body: Vec<u8>,
+    parser: SseParser,
+    output_tokens: i64,
+}
+
+impl AgentStreamReader {
+    fn new() -> Self {
+        AgentStreamReader {
+            body: Vec::new(),
+            parser: SseParser::new(),
+            output_tokens: 0,
+        }
+    }
+
+    /// Take one chunk of the stream. Returns `Break` with the abort reason
+    /// once a bound is passed, or with `None` once the message is complete.
+    fn push_chunk(
+        &mut self,
+        chunk: &[u8],
+        limits: &AgentStreamLimits,
+    ) -> ControlFlow<Option<String>> {
+        let fits = append_capped(&mut self.body, chunk, limits.max_bytes);
+        for (event_type, data_str) in self.parser.feed(&String::from_utf8_lossy(chunk)) {
+            if event_type == "message_stop" {
+                return ControlFlow::Break(None);
+            }
+            self.output_tokens += count_delta_tokens(&data_str);
+        }
+        if !fits {
+            return ControlFlow::Break(Some(format!("response passed {} bytes", limits.max_bytes)));
+        }
+        if self.output_tokens > limits.max_output_tokens {
+            return ControlFlow::Break(Some(format!(
+                "response passed an estimated {} output tokens",
+                limits.max_output_tokens
+            )));
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// Estimated tokens of the text in one `content_block_delta` event.
+fn count_delta_tokens(data_str: &str) -> i64 {
+    let Ok(data) = serde_json::from_str::<Value>(data_str) else {
+        return 0;
+    };
+    data.get("delta")
+        .filter(|delta| delta.get("type").and_then(Value::as_str) == Some("text_delta"))
+        .and_then(|delta| delta.get("text"))
+        .and_then(Value::as_str)
+        .map(count_text_tokens)
+        .unwrap_or(0)
+}
+
+/// Read an agent response as it streams, stopping at the message's end, at
+/// the context's size and output bounds, or when the fetch timeout runs out.
+/// What was read before an abort is kept.
+async fn read_agent_response(
+    mut response: reqwest::Response,
+    ctx: &FetchContext<'_>,
+) -> Result<AgentResponse, reqwest::Error> {
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let limits = AgentStreamLimits {
+        max_bytes: ctx.agent_max_response_bytes,
+        max_output_tokens: ctx.agent_max_output_tokens,
+    };
+    let deadline = tokio::time::Instant::now() + ctx.fetch_timeout;
+    let mut stream_reader = AgentStreamReader::new();
+    let abort_reason = loop {
+        let chunk = match tokio::time::timeout_at(deadline, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => chunk,
+            Ok(Ok(None)) => break None,
+            Ok(Err(e)) if stream_reader.body.is_empty() => return Err(e),
+            Ok(Err(e)) => break Some(format!("stream failed: {}", e)),
+            Err(_) => break Some(format!("timed out after {}s", ctx.fetch_timeout.as_secs())),
+        };
+        if let ControlFlow::Break(abort_reason) = stream_reader.push_chunk(&chunk, &limits) {
+            break abort_reason;
+        }
+    };
+    Ok(AgentResponse {
+        status,
+        headers,
+        body: stream_reader.body,
+        abort_reason,
+    })
+}
+
+/// Send the agent request to the agent backend and read its response.
 async fn send_upstream_agent_request(
     ctx: &FetchContext<'_>,
     agent_body: &Value,
-) -> Result<(u16, reqwest::header::HeaderMap, bytes::Bytes), ()> {
+) -> Result<AgentResponse, ()> {
     let agent_bytes = match serde_json::to_vec(agent_body) {
         Ok(bytes) => bytes,
         Err(_) => return Err(()),
     };
 
-    let mut agent_headers = ctx.forward_headers.clone();
-    agent_headers.remove(reqwest::header::CONTENT_LENGTH);
+    let agent_headers = build_agent_headers(ctx.forward_headers, &ctx.agent_backend);
+    let agent_url = ctx.agent_backend.url.unwrap_or(ctx.target_url);
 
     let agent_response = match ctx
         .client
-        .post(ctx.target_url)
+        .post(agent_url)
         .headers(agent_headers)
         .body(agent_bytes)
         .send()
@@ -412,17 +724,29 @@ async fn send_upstream_agent_request(
         }
     };
 
-    let resp_status = agent_response.status().as_u16();
-    let resp_headers = agent_response.headers().clone();
-    let resp_body = match agent_response.bytes().await {
-        Ok(body_bytes) => body_bytes,
-        Err(e) => {
-            log::warn!("webfetch agent: failed to read response: {}", e);
-            return Err(());
-        }
-    };
+    read_agent_response(agent_response, ctx).await.map_err(|e| {
+        log::warn!("webfetch agent: failed to read response: {}", e);
+    })
+}
 
-    Ok((resp_status, resp_headers, resp_body))
+/// Add why the agent response was cut off to the agent request's note.
+async fn store_agent_abort_note(
+    ctx: &FetchContext<'_>,
+    agent_request_id: &str,
+    url_host: &str,
+    abort_reason: &str,
+) {
+    log::warn!("webfetch agent: stopped reading response: {}", abort_reason);
+    let write = db::RequestWrite::Note {
+        request_id: agent_request_id.to_string(),
+        note: format!(
+            "webfetch agent ({}); stopped early: {}",
+            url_host, abort_reason
+        ),
+    };
+    if let Err(e) = db::write_request(ctx.pool, write).await {
+        log::warn!("webfetch agent: failed to store request note: {}", e);
+    }
 }
 
 /// Store the agent response in the database.
@@ -461,8 +785,7 @@ async fn send_agent_request(
     url_host: &str,
     ctx: &FetchContext<'_>,
 ) -> AcceptResult {
-    let agent_model = std::env::var("ANTHROPIC_DEFAULT_HAIKU_MODEL")
-        .unwrap_or_else(|_| ctx.agent_model.to_string());
+    let agent_model = resolve_agent_model(ctx);
     let agent_body = serde_json::json!({
         "model": agent_model,
         "messages": [{
@@ -470,7 +793,7 @@ async fn send_agent_request(
             "content": [{"type": "text", "text": rendered_content}]
         }],
         "system": [{"type": "text", "text": WEBFETCH_AGENT_SYSTEM_PROMPT}],
-        "max_tokens": 16384,
+        "max_tokens": ctx.agent_max_output_tokens,
         "stream": true,
     });
 
@@ -485,37 +808,41 @@ async fn send_agent_request(
                     "content": rendered_content,
                 }),
                 agent_request_id: None,
+                fetch_meta: None,
             };
         }
     };
 
     // Send the agent request upstream
-    let (resp_status, resp_headers, resp_body) =
-        match send_upstream_agent_request(ctx, &agent_body).await {
-            Ok(result) => result,
-            Err(()) => {
-                return AcceptResult {
-                    tool_result: serde_json::json!({
-                        "type": "tool_result",
-                        "tool_use_id": tool_use_id,
-                        "content": rendered_content,
-                    }),
-                    agent_request_id: Some(agent_request_id),
-                };
-            }
-        };
+    let agent_response = match send_upstream_agent_request(ctx, &agent_body).await {
+        Ok(agent_response) => agent_response,
+        Err(()) => {
+            return AcceptResult {
+                tool_result: serde_json::json!({
+                    "type": "tool_result",
+                    "tool_use_id": tool_use_id,
+                    "content": rendered_content,
+                }),
+                agent_request_id: Some(agent_request_id),
+                fetch_meta: None,
+            };
+        }
+    };
 
-    let resp_body_str = String::from_utf8_lossy(&resp_body).to_string();
+    let resp_body_str = String::from_utf8_lossy(&agent_response.body).to_string();
 
     // Store the response
     store_agent_response(
         ctx,
         &agent_request_id,
-        resp_status,
-        &resp_headers,
+        agent_response.status,
+        &agent_response.headers,
         &resp_body_str,
     )
     .await;
+    if let Some(abort_reason) = &agent_response.abort_reason {
+        store_agent_abort_note(ctx, &agent_request_id, url_host, abort_reason).await;
+    }
 
     // Extract text from the SSE events
     let sse_events = parse_sse_events(&resp_body_str);
@@ -530,6 +857,7 @@ async fn send_agent_request(
                 "content": rendered_content,
             }),
             agent_request_id: Some(agent_request_id),
+            fetch_meta: None,
         };
     }
 
@@ -540,5 +868,229 @@ async fn send_agent_request(
             "content": agent_text,
         }),
         agent_request_id: Some(agent_request_id),
+        fetch_meta: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_CONTENT_BYTES: usize = 100 * 1024;
+
+    fn render_body(
+        body: &FetchedBody,
+        accept_prompt: &str,
+        user_prompt: &str,
+        max_content_bytes: usize,
+    ) -> String {
+        let text = convert_body_to_text(body, "text", max_content_bytes);
+        render_accept_content(&text, accept_prompt, user_prompt)
+    }
+
+    fn make_body(bytes: &[u8]) -> FetchedBody {
+        FetchedBody {
+            bytes: bytes.to_vec(),
+            original_length: Some(bytes.len() as u64),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn render_accept_content_basic_html() {
+        let html = b"<html><body><h1>Hello World</h1><p>Some content</p></body></html>";
+        let result = render_body(
+            &make_body(html),
+            "Content: {{content}}",
+            "summarize this",
+            MAX_CONTENT_BYTES,
+        );
+        assert!(result.contains("Hello World"));
+        assert!(result.contains("Some content"));
+        assert!(result.starts_with("Content: "));
+    }
+
+    #[test]
+    fn render_accept_content_plain_text() {
+        let text = b"Just plain text content";
+        let result = render_body(&make_body(text), "{{content}}", "", MAX_CONTENT_BYTES);
+        assert!(result.contains("Just plain text content"));
+    }
+
+    #[test]
+    fn render_accept_content_includes_prompt() {
+        let html = b"<p>Page</p>";
+        let result = render_body(
+            &make_body(html),
+            "Content: {{content}} Prompt: {{prompt}}",
+            "my prompt",
+            MAX_CONTENT_BYTES,
+        );
+        assert!(result.contains("my prompt"));
+    }
+
+    #[test]
+    fn render_accept_content_truncation() {
+        // Create content larger than 100KB
+        let large_html = vec![b'a'; 200 * 1024];
+        let result =
+            render_body(&make_body(&large_html), "{{content}}", "", MAX_CONTENT_BYTES);
+        assert!(
+            result.contains("[Content truncated at 102400 bytes; original length 204800 bytes]")
+        );
+        // The output should be bounded in size (template wrapping + truncated content)
+        assert!(result.len() < 150 * 1024);
+    }
+
+    #[test]
+    fn render_accept_content_empty_template() {
+        let html = b"<p>test</p>";
+        let result = render_body(&make_body(html), "", "", MAX_CONTENT_BYTES);
+        // Empty template renders to empty string
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn render_accept_content_no_template_vars() {
+        let html = b"<p>test</p>";
+        let result =
+            render_body(&make_body(html), "static prompt", "", MAX_CONTENT_BYTES);
+        assert_eq!(result, "static prompt");
+    }
+
+    #[test]
+    fn render_accept_content_truncated_body() {
+        let body = FetchedBody {
+            bytes: b"<p>partial</p>".to_vec(),
+            original_length: None,
+            truncated: true,
+        };
+        let result = render_body(&body, "{{content}}", "", 14);
+        assert!(result.contains("partial"));
+        assert!(result.ends_with("[Content truncated at 14 bytes; original length unknown]"));
+    }
+
+    #[test]
+    fn render_accept_content_truncates_on_char_boundary() {
+        let text = "é".repeat(10);
+        let result = render_body(&make_body(text.as_bytes()), "{{content}}", "", 5);
+        assert!(result.starts_with("éé\n\n[Content truncated at 5 bytes"));
+    }
+
+    #[test]
+    fn append_capped_stops_at_limit() {
+        let mut bytes = Vec::new();
+        assert!(append_capped(&mut bytes, b"abc", 5));
+        assert!(append_capped(&mut bytes, b"de", 5));
+        assert!(!append_capped(&mut bytes, b"f", 5));
+        assert_eq!(bytes, b"abcde");
+
+        let mut bytes = Vec::new();
+        assert!(!append_capped(&mut bytes, b"abcdef", 4));
+        assert_eq!(bytes, b"abcd");
+    }
+
+    fn text_delta_event(text: &str) -> String {
+        let data = serde_json::json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "text_delta", "text": text},
+        });
+        format!("event: content_block_delta\ndata: {}\n\n", data)
+    }
+
+    #[test]
+    fn agent_stream_reader_stops_at_message_stop() {
+        let limits = AgentStreamLimits {
+            max_bytes: 1024,
+            max_output_tokens: 100,
+        };
+        let mut stream_reader = AgentStreamReader::new();
+        let first_event = text_delta_event("Hello");
+        let (head, tail) = first_event.split_at(20);
+        assert!(stream_reader.push_chunk(head.as_bytes(), &limits).is_continue());
+        assert!(stream_reader.push_chunk(tail.as_bytes(), &limits).is_continue());
+        let stop_event = "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+        assert_eq!(
+            stream_reader.push_chunk(stop_event.as_bytes(), &limits),
+            ControlFlow::Break(None)
+        );
+        let body_str = String::from_utf8(stream_reader.body).unwrap();
+        assert_eq!(extract_text_from_events(&parse_sse_events(&body_str)), "Hello");
+    }
+
+    #[test]
+    fn agent_stream_reader_aborts_past_bounds() {
+        let limits = AgentStreamLimits {
+            max_bytes: 1024,
+            max_output_tokens: 5,
+        };
+        let mut stream_reader = AgentStreamReader::new();
+        let first_event = text_delta_event("one two three");
+        assert!(stream_reader.push_chunk(first_event.as_bytes(), &limits).is_continue());
+        let second_event = text_delta_event("four five six");
+        assert_eq!(
+            stream_reader.push_chunk(second_event.as_bytes(), &limits),
+            ControlFlow::Break(Some(
+                "response passed an estimated 5 output tokens".to_string()
+            ))
+        );
+
+        let limits = AgentStreamLimits {
+            max_bytes: 64,
+            max_output_tokens: 100,
+        };
+        let mut stream_reader = AgentStreamReader::new();
+        let long_event = text_delta_event(&"a ".repeat(100));
+        assert_eq!(
+            stream_reader.push_chunk(long_event.as_bytes(), &limits),
+            ControlFlow::Break(Some("response passed 64 bytes".to_string()))
+        );
+        assert_eq!(stream_reader.body.len(), 64);
+    }
+
+    #[test]
+    fn cached_marker_and_cutoff() {
+        assert_eq!(
+            build_cached_marker("2026-01-02 03:04:05"),
+            "\n\n[Cached: fetched at 2026-01-02 03:04:05 UTC]"
+        );
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let cutoff = build_cache_cutoff(300);
+        assert_eq!(cutoff.len(), now.len());
+        assert!(cutoff < now);
+        // A TTL too large to subtract keeps every entry fresh.
+        assert_eq!(build_cache_cutoff(u64::MAX), "");
+    }
+
+    #[test]
+    fn agent_headers_swap_credentials_for_custom_backend() {
+        let mut forward_headers = reqwest::header::HeaderMap::new();
+        forward_headers.insert("x-api-key", "sk-production".parse().unwrap());
+        forward_headers.insert(reqwest::header::AUTHORIZATION, "Bearer prod".parse().unwrap());
+        forward_headers.insert(reqwest::header::CONTENT_LENGTH, "42".parse().unwrap());
+        forward_headers.insert("anthropic-version", "2023-06-01".parse().unwrap());
+
+        let headers = build_agent_headers(&forward_headers, &AgentBackend::default());
+        assert_eq!(headers["x-api-key"], "sk-production");
+        assert!(headers.contains_key(reqwest::header::AUTHORIZATION));
+        assert!(!headers.contains_key(reqwest::header::CONTENT_LENGTH));
+
+        let local_backend = AgentBackend {
+            url: Some("http://localhost:4000/v1/messages"),
+            ..AgentBackend::default()
+        };
+        let headers = build_agent_headers(&forward_headers, &local_backend);
+        assert!(!headers.contains_key("x-api-key"));
+        assert!(!headers.contains_key(reqwest::header::AUTHORIZATION));
+        assert_eq!(headers["anthropic-version"], "2023-06-01");
+
+        let keyed_backend = AgentBackend {
+            api_key: Some("sk-cheap"),
+            ..local_backend
+        };
+        let headers = build_agent_headers(&forward_headers, &keyed_backend);
+        assert_eq!(headers["x-api-key"], "sk-cheap");
+        assert!(!headers.contains_key(reqwest::header::AUTHORIZATION));
     }
 }
@@ -0,0 +1,250 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::LazyLock;
+use url::{Host, Url};
+
+/// Client for the Accept-ed fetches of sessions that don't allow private
+/// targets: it only connects to public addresses.
+static PUBLIC_FETCH_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(|| {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(PublicAddressResolver)
+        .build()
+        .expect("Failed to build public fetch client")
+});
+
+/// Resolves hosts to their public addresses only. Checking a host before
+/// fetching isn't enough, since the client resolves it again to connect: a
+/// DNS-rebinding host could pass the check and then point at 127.0.0.1.
+struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|socket_address| !is_private_address(socket_address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                return Err(format!("host '{}' resolves only to private addresses", host).into());
+            }
+            let addrs: Addrs = Box::new(addresses.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// The client Accept-ed fetches go through: `client` when private targets
+/// are allowed, otherwise one that never connects to a private address.
+pub(super) fn select_fetch_client(
+    client: &reqwest::Client,
+    allow_private_targets: bool,
+) -> &reqwest::Client {
+    if allow_private_targets {
+        client
+    } else {
+        &PUBLIC_FETCH_CLIENT
+    }
+}
+
+/// Check whether an IPv4 address is loopback, private, link-local, shared
+/// (carrier-grade NAT), unspecified, broadcast or multicast.
+fn is_private_ipv4(address: Ipv4Addr) -> bool {
+    let [first, second, ..] = address.octets();
+    address.is_loopback()
+        || address.is_private()
+        || address.is_link_local()
+        || address.is_unspecified()
+        || address.is_broadcast()
+        || address.is_multicast()
+        || first == 0
+        || (first == 100 && (64..128).contains(&second))
+}
+
+/// The IPv4 address carried in an IPv4-mapped (`::ffff:a.b.c.d`),
+/// IPv4-compatible (`::a.b.c.d`), NAT64 (`64:ff9b::/96`) or 6to4
+/// (`2002::/16`) IPv6 address.
+fn find_embedded_ipv4(address: Ipv6Addr) -> Option<Ipv4Addr> {
+    if let Some(mapped) = address.to_ipv4_mapped() {
+        return Some(mapped);
+    }
+    let octets = address.octets();
+    let trailing_ipv4 = Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]);
+    match address.segments() {
+        [0, 0, 0, 0, 0, 0, ..] | [0x64, 0xff9b, 0, 0, 0, 0, ..] => Some(trailing_ipv4),
+        [0x2002, ..] => Some(Ipv4Addr::new(octets[2], octets[3], octets[4], octets[5])),
+        _ => None,
+    }
+}
+
+/// Check whether an IPv6 address is loopback, unique local, link-local,
+/// unspecified or multicast, or carries a private IPv4 address.
+fn is_private_ipv6(address: Ipv6Addr) -> bool {
+    if let Some(embedded) = find_embedded_ipv4(address) {
+        if is_private_ipv4(embedded) {
+            return true;
+        }
+    }
+    address.is_loopback()
+        || address.is_unspecified()
+        || address.is_multicast()
+        || address.is_unique_local()
+        || address.is_unicast_link_local()
+}
+
+/// Check whether an address points into a private, loopback or link-local
+/// range that an Accept-ed WebFetch, or a forward proxy pass-through tunnel,
+/// must not reach.
+pub(crate) fn is_private_address(address: IpAddr) -> bool {
+    match address {
+        IpAddr::V4(address) => is_private_ipv4(address),
+        IpAddr::V6(address) => is_private_ipv6(address),
+    }
+}
+
+/// Resolve the addresses a URL's host points to. IP literal hosts are returned as-is.
+async fn resolve_host_addresses(url: &Url) -> Result<Vec<IpAddr>, String> {
+    let port = url.port_or_known_default().unwrap_or(80);
+    match url.host() {
+        Some(Host::Ipv4(address)) => Ok(vec![IpAddr::V4(address)]),
+        Some(Host::Ipv6(address)) => Ok(vec![IpAddr::V6(address)]),
+        Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+            .await
+            .map(|socket_addresses| {
+                socket_addresses
+                    .map(|socket_address| socket_address.ip())
+                    .collect()
+            })
+            .map_err(|e| format!("failed to resolve host '{}': {}", domain, e)),
+        None => Err("URL has no host".to_string()),
+    }
+}
+
+/// Check that a URL may be fetched: its scheme must be in `allowed_schemes`
+/// and, unless `allow_private_targets` is set, none of the addresses its host
+/// resolves to may be private, loopback or link-local.
+pub(super) async fn check_fetch_target(
+    url: &Url,
+    allowed_schemes: &[String],
+    allow_private_targets: bool,
+) -> Result<(), String> {
+    if !allowed_schemes
+        .iter()
+        .any(|scheme| scheme.eq_ignore_ascii_case(url.scheme()))
+    {
+        return Err(format!(
+            "scheme '{}' is not allowed (allowed: {})",
+            url.scheme(),
+            allowed_schemes.join(", ")
+        ));
+    }
+    if allow_private_targets {
+        return Ok(());
+    }
+    let addresses = resolve_host_addresses(url).await?;
+    if let Some(address) = addresses
+        .into_iter()
+        .find(|address| is_private_address(*address))
+    {
+        return Err(format!(
+            "host '{}' resolves to private address {}",
+            url.host_str().unwrap_or(""),
+            address
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_private(address: &str) -> bool {
+        is_private_address(address.parse().unwrap())
+    }
+
+    #[test]
+    fn private_ranges() {
+        for address in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.5.4",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::127.0.0.1",
+            "::a9fe:a9fe",
+            "64:ff9b::7f00:1",
+            "64:ff9b::169.254.169.254",
+            "2002:7f00:1::",
+            "2002:a9fe:a9fe::1",
+        ] {
+            assert!(is_private(address), "{} should be private", address);
+        }
+        for address in [
+            "8.8.8.8",
+            "172.32.0.1",
+            "100.128.0.1",
+            "2606:4700::1111",
+            "64:ff9b::808:808",
+            "2002:808:808::1",
+        ] {
+            assert!(!is_private(address), "{} should be public", address);
+        }
+    }
+
+    #[tokio::test]
+    async fn public_fetch_client_refuses_private_hosts() {
+        let resolved = PublicAddressResolver
+            .resolve("localhost".parse().unwrap())
+            .await;
+        assert!(resolved
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("only to private addresses"));
+
+        let error = select_fetch_client(&reqwest::Client::new(), false)
+            .get("http://localhost:1/")
+            .send()
+            .await
+            .unwrap_err();
+        assert!(error.is_connect());
+    }
+
+    #[tokio::test]
+    async fn check_scheme_and_private_targets() {
+        let allowed_schemes = vec!["http".to_string(), "https".to_string()];
+        let file_url = Url::parse("file:///etc/passwd").unwrap();
+        assert!(check_fetch_target(&file_url, &allowed_schemes, true)
+            .await
+            .unwrap_err()
+            .contains("scheme 'file'"));
+
+        let metadata_url = Url::parse("http://169.254.169.254/latest/meta-data").unwrap();
+        assert!(check_fetch_target(&metadata_url, &allowed_schemes, false)
+            .await
+            .unwrap_err()
+            .contains("private address 169.254.169.254"));
+        assert!(check_fetch_target(&metadata_url, &allowed_schemes, true)
+            .await
+            .is_ok());
+
+        let localhost_url = Url::parse("http://localhost:8081/").unwrap();
+        assert!(check_fetch_target(&localhost_url, &allowed_schemes, false)
+            .await
+            .is_err());
+
+        let public_url = Url::parse("https://1.1.1.1/").unwrap();
+        assert!(check_fetch_target(&public_url, &allowed_schemes, false)
+            .await
+            .is_ok());
+    }
+}
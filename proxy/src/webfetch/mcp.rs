@@ -0,0 +1,571 @@
+use common::mcp::{MCP_STATUS_ERROR, MCP_STATUS_OK};
+use common::models::Session;
+use serde_json::Value;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Lines, Take};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+use super::extract::ToolUse;
+use super::fetch::{build_accept_error, read_capped_body, AcceptResult, FetchContext};
+use crate::shared::effective_client;
+use crate::sse::parse_sse_events;
+
+/// MCP protocol revision sent in `initialize`.
+const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Bytes read from an MCP server per connection before giving up on it.
+const MAX_MCP_RESPONSE_BYTES: usize = 10 * 1024 * 1024;
+
+/// A session's MCP server.
+#[derive(Debug, Clone, Copy)]
+pub struct McpServer<'a> {
+    /// Key of the `common::mcp` transport.
+    pub transport: &'a str,
+    /// Command (stdio) or URL (http).
+    pub target: &'a str,
+}
+
+impl<'a> McpServer<'a> {
+    /// The session's MCP server, if one is configured.
+    pub fn from_session(session: &'a Session) -> Option<Self> {
+        Some(McpServer {
+            transport: session.mcp_server_transport.as_deref()?,
+            target: session.mcp_server_target.as_deref()?,
+        })
+    }
+}
+
+/// What an MCP server reported during a health check.
+struct McpServerHealth {
+    pub server_name: String,
+    pub tool_names: Vec<String>,
+}
+
+/// An open MCP connection; one is made per check or tool call.
+enum McpConnection<'a> {
+    Stdio {
+        /// Kept so the server process is killed when the connection is dropped.
+        _child: Box<Child>,
+        stdin: ChildStdin,
+        stdout: Lines<BufReader<Take<ChildStdout>>>,
+    },
+    Http {
+        client: &'a reqwest::Client,
+        url: &'a str,
+        /// `Mcp-Session-Id` assigned by the server on `initialize`.
+        mcp_session_id: Option<String>,
+        initialized: bool,
+    },
+}
+
+/// The error text of a JSON-RPC error response.
+fn build_jsonrpc_error(error: &Value) -> String {
+    let message = error
+        .get("message")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown error");
+    match error.get("code").and_then(Value::as_i64) {
+        Some(code) => format!("{} (code {})", message, code),
+        None => message.to_string(),
+    }
+}
+
+/// Find the response to request `id` among JSON-RPC messages.
+fn find_jsonrpc_response(messages: &[Value], id: u64) -> Option<Result<Value, String>> {
+    messages
+        .iter()
+        .find(|message| message.get("id").and_then(Value::as_u64) == Some(id))
+        .map(|message| match message.get("error") {
+            Some(error) => Err(build_jsonrpc_error(error)),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        })
+}
+
+/// Write one JSON-RPC message to a stdio server as a line. A server that
+/// already exited is left for the following read to report.
+async fn write_stdio_message(stdin: &mut ChildStdin, message: &Value) -> Result<(), String> {
+    let line = format!("{}\n", message);
+    match stdin.write_all(line.as_bytes()).await {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+            Err(format!("failed to write to the server: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
+
+impl<'a> McpConnection<'a> {
+    /// Start the server process or prepare HTTP requests to it.
+    fn connect(server: McpServer<'a>, client: &'a reqwest::Client) -> Result<Self, String> {
+        match server.transport {
+            "stdio" => {
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(server.target)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|e| format!("failed to start '{}': {}", server.target, e))?;
+                let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+                    return Err("failed to open the server's stdin/stdout".to_string());
+                };
+                Ok(McpConnection::Stdio {
+                    _child: Box::new(child),
+                    stdin,
+                    stdout: BufReader::new(stdout.take(MAX_MCP_RESPONSE_BYTES as u64)).lines(),
+                })
+            }
+            "http" => Ok(McpConnection::Http {
+                client,
+                url: server.target,
+                mcp_session_id: None,
+                initialized: false,
+            }),
+            transport => Err(format!("unknown MCP transport '{}'", transport)),
+        }
+    }
+
+    /// Send one JSON-RPC message. For HTTP, returns the messages in the reply.
+    async fn send(&mut self, message: &Value) -> Result<Vec<Value>, String> {
+        match self {
+            McpConnection::Stdio { stdin, .. } => {
+                write_stdio_message(stdin, message).await?;
+                Ok(Vec::new())
+            }
+            McpConnection::Http {
+                client,
+                url,
+                mcp_session_id,
+                initialized,
+            } => {
+                let mut request = client
+                    .post(*url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(
+                        reqwest::header::ACCEPT,
+                        "application/json, text/event-stream",
+                    )
+                    .body(message.to_string());
+                if let Some(mcp_session_id) = mcp_session_id.as_deref() {
+                    request = request.header("Mcp-Session-Id", mcp_session_id);
+                }
+                if *initialized {
+                    request = request.header("MCP-Protocol-Version", MCP_PROTOCOL_VERSION);
+                }
+                let response = request
+                    .send()
+                    .await
+                    .map_err(|e| format!("request to {} failed: {}", url, e))?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(format!("{} returned HTTP {}", url, status.as_u16()));
+                }
+                if let Some(session_header) = response.headers().get("mcp-session-id") {
+                    *mcp_session_id = session_header.to_str().ok().map(str::to_string);
+                }
+                let is_event_stream = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|header_value| header_value.to_str().ok())
+                    .is_some_and(|content_type| content_type.starts_with("text/event-stream"));
+                let body = read_capped_body(response, MAX_MCP_RESPONSE_BYTES)
+                    .await
+                    .map_err(|e| format!("failed to read the response from {}: {}", url, e))?;
+                let body_str = String::from_utf8_lossy(&body.bytes);
+                if is_event_stream {
+                    return Ok(parse_sse_events(&body_str)
+                        .into_iter()
+                        .filter_map(|event| event.get("data").cloned())
+                        .collect());
+                }
+                if body_str.trim().is_empty() {
+                    return Ok(Vec::new());
+                }
+                match serde_json::from_str::<Value>(&body_str) {
+                    Ok(Value::Array(messages)) => Ok(messages),
+                    Ok(message) => Ok(vec![message]),
+                    Err(e) => Err(format!("invalid JSON from {}: {}", url, e)),
+                }
+            }
+        }
+    }
+
+    /// Send a request and wait for its response. Requests from a stdio server
+    /// are answered so it does not stall: `ping` succeeds, anything else is
+    /// reported as unsupported.
+    async fn request(&mut self, id: u64, method: &str, params: Value) -> Result<Value, String> {
+        let message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        });
+        let reply_messages = self.send(&message).await?;
+        let McpConnection::Stdio { stdin, stdout, .. } = self else {
+            return find_jsonrpc_response(&reply_messages, id)
+                .unwrap_or_else(|| Err(format!("no response to '{}'", method)));
+        };
+        loop {
+            let line = stdout
+                .next_line()
+                .await
+                .map_err(|e| format!("failed to read from the server: {}", e))?
+                .ok_or_else(|| format!("the server exited before answering '{}'", method))?;
+            let Ok(server_message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if let Some(response) = find_jsonrpc_response(std::slice::from_ref(&server_message), id)
+            {
+                return response;
+            }
+            if let (Some(request_id), Some(request_method)) = (
+                server_message.get("id"),
+                server_message.get("method").and_then(Value::as_str),
+            ) {
+                let reply = if request_method == "ping" {
+                    serde_json::json!({"jsonrpc": "2.0", "id": request_id, "result": {}})
+                } else {
+                    serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request_id,
+                        "error": {"code": -32601, "message": "Method not found"},
+                    })
+                };
+                write_stdio_message(stdin, &reply).await?;
+            }
+        }
+    }
+
+    /// Run the `initialize` handshake. Returns the server's name.
+    async fn initialize(&mut self) -> Result<String, String> {
+        let initialize_result = self
+            .request(
+                1,
+                "initialize",
+                serde_json::json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "gateway-proxy",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+            .await?;
+        if let McpConnection::Http { initialized, .. } = self {
+            *initialized = true;
+        }
+        self.send(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized",
+        }))
+        .await?;
+        Ok(initialize_result
+            .pointer("/serverInfo/name")
+            .and_then(Value::as_str)
+            .unwrap_or("MCP server")
+            .to_string())
+    }
+}
+
+/// Connect to an MCP server and list its tools.
+async fn check_mcp_server(
+    server: McpServer<'_>,
+    client: &reqwest::Client,
+    timeout: Duration,
+) -> Result<McpServerHealth, String> {
+    let check = async {
+        let mut connection = McpConnection::connect(server, client)?;
+        let server_name = connection.initialize().await?;
+        let tools_result = connection
+            .request(2, "tools/list", serde_json::json!({}))
+            .await?;
+        let tool_names = tools_result
+            .get("tools")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|tool| tool.get("name").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+        Ok(McpServerHealth {
+            server_name,
+            tool_names,
+        })
+    };
+    tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", timeout.as_secs())))
+}
+
+/// Check the session's MCP server by listing its tools, and record its health
+/// status. Fails only when the status cannot be stored.
+pub async fn check_session_mcp_server(
+    pool: &sqlx::SqlitePool,
+    session: &Session,
+    default_client: &reqwest::Client,
+    timeout: Duration,
+) -> anyhow::Result<()> {
+    let Some(mcp_server) = McpServer::from_session(session) else {
+        return Ok(());
+    };
+    let client = effective_client(session, default_client);
+    let (status, detail) = match check_mcp_server(mcp_server, client, timeout).await {
+        Ok(health) => (
+            MCP_STATUS_OK,
+            format!(
+                "{}, {} tool(s): {}",
+                health.server_name,
+                health.tool_names.len(),
+                health.tool_names.join(", ")
+            ),
+        ),
+        Err(reason) => (MCP_STATUS_ERROR, reason),
+    };
+    let session_id = session.id.to_string();
+    db::set_session_mcp_server_status(pool, &session_id, status, &detail).await
+}
+
+/// Connect to an MCP server and call one tool. Returns the `tools/call` result.
+async fn call_mcp_tool(
+    server: McpServer<'_>,
+    client: &reqwest::Client,
+    timeout: Duration,
+    tool_name: &str,
+    arguments: &Value,
+) -> Result<Value, String> {
+    let call = async {
+        let mut connection = McpConnection::connect(server, client)?;
+        connection.initialize().await?;
+        connection
+            .request(
+                2,
+                "tools/call",
+                serde_json::json!({"name": tool_name, "arguments": arguments}),
+            )
+            .await
+    };
+    tokio::time::timeout(timeout, call)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {}s", timeout.as_secs())))
+}
+
+/// Convert an MCP content block into a tool_result content block. Text and
+/// images carry over; other blocks are described in text.
+fn convert_mcp_content(block: &Value) -> Value {
+    let text = match block.get("type").and_then(Value::as_str) {
+        Some("text") => block
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string(),
+        Some("image") => {
+            return serde_json::json!({
+                "type": "image",
+                "source": {
+                    "type": "base64",
+                    "media_type": block.get("mimeType").cloned().unwrap_or(Value::Null),
+                    "data": block.get("data").cloned().unwrap_or(Value::Null),
+                },
+            });
+        }
+        Some("resource") => {
+            let resource = block.get("resource").unwrap_or(&Value::Null);
+            let uri = resource.get("uri").and_then(Value::as_str).unwrap_or("");
+            match resource.get("text").and_then(Value::as_str) {
+                Some(text) => text.to_string(),
+                None => format!("[Binary resource {} omitted]", uri),
+            }
+        }
+        Some("resource_link") => format!(
+            "[Resource: {}]",
+            block.get("uri").and_then(Value::as_str).unwrap_or("")
+        ),
+        _ => block.to_string(),
+    };
+    serde_json::json!({"type": "text", "text": text})
+}
+
+/// Build the tool_result for a `tools/call` result.
+fn build_mcp_tool_result(tool_use: &ToolUse, call_result: &Value) -> Value {
+    let mut content: Vec<Value> = call_result
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(convert_mcp_content)
+        .collect();
+    if content.is_empty() {
+        if let Some(structured_content) = call_result.get("structuredContent") {
+            content
+                .push(serde_json::json!({"type": "text", "text": structured_content.to_string()}));
+        }
+    }
+    let mut tool_result = serde_json::json!({
+        "type": "tool_result",
+        "tool_use_id": tool_use.id,
+        "content": content,
+    });
+    if call_result.get("isError").and_then(Value::as_bool) == Some(true) {
+        tool_result["is_error"] = Value::Bool(true);
+    }
+    tool_result
+}
+
+/// Forward an Accept-ed tool call to the session's MCP server. The server's
+/// health status is updated with the outcome.
+pub(super) async fn run_mcp_executor(
+    mcp_tool_name: &str,
+    tool_use: &ToolUse,
+    ctx: &FetchContext<'_>,
+) -> AcceptResult {
+    let Some(mcp_server) = ctx.mcp_server else {
+        return build_accept_error(
+            tool_use,
+            format!(
+                "The '{}' tool runs on an MCP server, but none is configured for this session.",
+                tool_use.name
+            ),
+        );
+    };
+    let call_result = call_mcp_tool(
+        mcp_server,
+        ctx.client,
+        ctx.fetch_timeout,
+        mcp_tool_name,
+        &tool_use.input,
+    )
+    .await;
+    let (status, detail) = match &call_result {
+        Ok(_) => (MCP_STATUS_OK, format!("Called {}", mcp_tool_name)),
+        Err(reason) => (MCP_STATUS_ERROR, reason.clone()),
+    };
+    if let Err(e) =
+        db::set_session_mcp_server_status(ctx.pool, ctx.session_id, status, &detail).await
+    {
+        log::warn!("MCP executor: failed to store server status: {}", e);
+    }
+    match call_result {
+        Ok(call_result) => AcceptResult {
+            tool_result: build_mcp_tool_result(tool_use, &call_result),
+            agent_request_id: None,
+            fetch_meta: None,
+        },
+        Err(reason) => {
+            log::warn!("MCP executor: {} failed: {}", mcp_tool_name, reason);
+            build_accept_error(
+                tool_use,
+                format!(
+                    "The MCP server failed to run '{}': {}",
+                    mcp_tool_name, reason
+                ),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_tool_use() -> ToolUse {
+        ToolUse {
+            id: "toolu_1".to_string(),
+            name: "read_file".to_string(),
+            input: serde_json::json!({"path": "/tmp/a"}),
+        }
+    }
+
+    #[test]
+    fn tool_result_converts_content_blocks() {
+        let call_result = serde_json::json!({
+            "content": [
+                {"type": "text", "text": "hello"},
+                {"type": "image", "data": "aGk=", "mimeType": "image/png"},
+                {"type": "resource", "resource": {"uri": "file:///a", "text": "file body"}},
+                {"type": "resource_link", "uri": "file:///b", "name": "b"},
+            ],
+            "isError": true,
+        });
+        assert_eq!(
+            build_mcp_tool_result(&make_tool_use(), &call_result),
+            serde_json::json!({
+                "type": "tool_result",
+                "tool_use_id": "toolu_1",
+                "content": [
+                    {"type": "text", "text": "hello"},
+                    {"type": "image", "source": {
+                        "type": "base64", "media_type": "image/png", "data": "aGk=",
+                    }},
+                    {"type": "text", "text": "file body"},
+                    {"type": "text", "text": "[Resource: file:///b]"},
+                ],
+                "is_error": true,
+            })
+        );
+    }
+
+    #[test]
+    fn tool_result_falls_back_to_structured_content() {
+        let call_result = serde_json::json!({"content": [], "structuredContent": {"total": 3}});
+        let tool_result = build_mcp_tool_result(&make_tool_use(), &call_result);
+        assert_eq!(tool_result["content"][0]["text"], r#"{"total":3}"#);
+        assert!(tool_result.get("is_error").is_none());
+    }
+
+    #[test]
+    fn jsonrpc_response_matches_id() {
+        let messages = vec![
+            serde_json::json!({"jsonrpc": "2.0", "method": "notifications/message"}),
+            serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {"ok": true}}),
+            serde_json::json!({"jsonrpc": "2.0", "id": 2, "error": {"code": -32602, "message": "bad"}}),
+        ];
+        assert_eq!(
+            find_jsonrpc_response(&messages, 1),
+            Some(Ok(serde_json::json!({"ok": true})))
+        );
+        assert_eq!(
+            find_jsonrpc_response(&messages, 2),
+            Some(Err("bad (code -32602)".to_string()))
+        );
+        assert_eq!(find_jsonrpc_response(&messages, 3), None);
+    }
+
+    #[tokio::test]
+    async fn stdio_server_check_and_call() {
+        // A minimal MCP server: answers initialize, tools/list and tools/call
+        // by request id and ignores the initialized notification.
+        let script = r#"while read -r line; do
+            case "$line" in
+                *'"id":1'*) echo '{"jsonrpc":"2.0","id":1,"result":{"serverInfo":{"name":"echo-server"}}}' ;;
+                *'tools/list'*) echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"echo"}]}}' ;;
+                *'tools/call'*) echo '{"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"pong"}]}}' ;;
+            esac
+        done"#;
+        let server = McpServer {
+            transport: "stdio",
+            target: script,
+        };
+        let client = reqwest::Client::new();
+        let timeout = Duration::from_secs(10);
+        let health = check_mcp_server(server, &client, timeout).await.unwrap();
+        assert_eq!(health.server_name, "echo-server");
+        assert_eq!(health.tool_names, vec!["echo"]);
+        let call_result = call_mcp_tool(server, &client, timeout, "echo", &serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(call_result["content"][0]["text"], "pong");
+
+        let server = McpServer {
+            transport: "stdio",
+            target: "exit 0",
+        };
+        let error = check_mcp_server(server, &client, timeout)
+            .await
+            .err()
+            .unwrap();
+        assert!(error.contains("exited before answering 'initialize'"));
+    }
+}
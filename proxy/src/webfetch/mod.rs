@@ -1,24 +1,50 @@
 mod approval;
+mod deny;
+mod executor;
 mod extract;
 mod fetch;
+mod guard;
+mod mcp;
 mod mock;
+mod page_text;
+mod policy;
+mod robots;
+mod running;
 
 pub use approval::{
-    list_pending, new_approval_queue, resolve_pending, ApprovalDecision, ApprovalQueue,
-    PendingApproval,
+    decide_pending_tool, get_pending, insert_pending, list_all_pending, list_pending,
+    new_approval_queue, remove_pending, resolve_pending, stream_approval_events, ApprovalDecision,
+    ApprovalEvent, ApprovalQueue, ApprovalResolution, ApprovalStreamMessage, PendingApproval,
 };
 pub use common::models::PendingToolInfo;
-pub use fetch::WEBFETCH_AGENT_SYSTEM_PROMPT;
-
-use common::config::AppConfig;
+pub use deny::{parse_deny_pattern, DenyPattern};
+pub use extract::{extract_webfetch_from_sse, InterceptedTools, ToolUse};
+pub use fetch::{build_cache_cutoff, AgentBackend, WEBFETCH_AGENT_SYSTEM_PROMPT};
+pub(crate) use guard::is_private_address;
+pub use mcp::{check_session_mcp_server, McpServer};
+pub use policy::parse_policy_value;
+pub use running::{cancel_running_intercept, list_running_intercepts};
+
+use chrono::Utc;
+use common::config::{AppConfig, WEBFETCH_FOLLOWUP_FALLBACK_ERROR};
+use common::models::{
+    WebfetchDenyRule, WebfetchPolicy, WebfetchWhitelistRule, SESSION_EVENT_APPROVAL,
+};
 use serde_json::Value;
+use std::future::{pending, Future};
+use std::time::Duration;
+use tokio::time::{sleep_until, Instant};
 
+use self::deny::find_denied_webfetch;
 use self::extract::{
-    build_followup_body, build_input_summary, extract_webfetch_from_sse, is_all_whitelisted,
-    retain_matched_tool_blocks, InterceptedTools, ToolUse,
+    build_followup_body, build_input_summary, is_all_whitelisted, retain_matched_tool_blocks,
 };
-use self::fetch::{build_accept_result, FetchContext};
+use self::executor::{collect_intercepted_names, execute_tool};
+use self::fetch::{AcceptResult, FetchContext};
 use self::mock::{build_fail_result, build_mock_result};
+use self::policy::{evaluate_policies, PolicyOutcome};
+use self::running::{start_running_intercept, RunningInterceptGuard};
+use crate::notify::send_webhook_notifications;
 use crate::shared::{
     extract_request_fields, headers_to_json, log_request, store_response, RequestMeta,
 };
@@ -27,32 +53,43 @@ use crate::sse::parse_sse_events;
 /// Maximum number of intercept rounds to prevent infinite loops.
 const MAX_INTERCEPT_ROUNDS: usize = 10;
 
-/// Timeout in seconds for waiting for user approval.
-const APPROVAL_TIMEOUT_SECS: u64 = 120;
-
 /// Data collected for each round of interception.
 struct RoundData {
     decision: String,
+    /// Why the decision was made automatically (e.g. the matching deny rule).
+    decision_reason: Option<String>,
     tool_names: Vec<String>,
+    /// Per tool call: the label of the decision applied to it.
+    tool_decisions: Vec<String>,
     request_id: Option<String>,
     agent_request_ids: Vec<Option<String>>,
+    /// Per tool call: URL, original length and bytes read of the fetched page.
+    fetches: Vec<Option<Value>>,
     followup_body: Value,
     response_body: String,
     response_events: Vec<Value>,
 }
 
+/// The last follow-up response of an intercepted request, returned to the
+/// client in place of the original one.
+#[derive(Debug)]
+pub struct InterceptedResponse {
+    pub status: u16,
+    pub headers: reqwest::header::HeaderMap,
+    pub body: bytes::Bytes,
+    pub note: String,
+    pub followup_body_json: String,
+    pub rounds_json: String,
+}
+
 /// Result of webfetch interception.
 #[derive(Debug)]
 pub enum InterceptResult {
     /// Custom tool_use was intercepted: contains the follow-up response.
-    Intercepted {
-        status: u16,
-        headers: reqwest::header::HeaderMap,
-        body: bytes::Bytes,
-        note: String,
-        followup_body_json: String,
-        rounds_json: String,
-    },
+    Intercepted(InterceptedResponse),
+    /// A follow-up request failed, and `webfetch_followup_fallback` answers
+    /// the client with an error instead of the original response.
+    FollowupFailed { message: String },
 }
 
 /// Parameters for webfetch interception.
@@ -64,68 +101,272 @@ pub struct InterceptParams<'a> {
     pub client: &'a reqwest::Client,
     pub approval_queue: &'a ApprovalQueue,
     pub session_id: &'a str,
-    pub whitelist: &'a [String],
+    /// The intercepted request, which follow-up rounds are logged under.
+    pub request_id: &'a str,
+    pub whitelist: &'a [WebfetchWhitelistRule],
+    pub deny_rules: &'a [WebfetchDenyRule],
+    /// Auto-decision policies, in the order they are checked.
+    pub policies: &'a [WebfetchPolicy],
+    /// Let Accept-ed fetches reach private, loopback and link-local addresses.
+    pub allow_private_targets: bool,
+    /// Bytes of a fetched page read before the rest is cut off.
+    pub max_content_bytes: usize,
+    /// Time limit for each Accept-ed fetch, including reading the body.
+    pub fetch_timeout: Duration,
+    /// Key of the `common::extraction` mode used to turn fetched pages into text.
+    pub extraction_mode: &'a str,
+    /// Refuse Accept-ed fetches the target site's robots.txt disallows.
+    pub respect_robots_txt: bool,
+    /// Return fetched page text as the tool_result instead of an agent summary.
+    pub raw_mode: bool,
+    /// The session's MCP server, used by `mcp` tool executors.
+    pub mcp_server: Option<McpServer<'a>>,
+    /// Where agent summarization requests go.
+    pub agent_backend: AgentBackend<'a>,
     pub pool: &'a sqlx::SqlitePool,
     pub stored_path: &'a str,
     pub webfetch_names: &'a [String],
     pub config: &'a AppConfig,
 }
 
-/// Wait for user approval via the dashboard UI, or auto-accept if all tools are whitelisted.
-/// Returns the decision and a human-readable label for logging/display.
+/// The decision for one interception round.
+struct ApprovalOutcome {
+    decision: ApprovalDecision,
+    /// Human-readable label for logging/display.
+    label: &'static str,
+    /// Why the decision was made without waiting for the user, if it was.
+    reason: Option<String>,
+    /// The dashboard users who decided the round, if it waited for them.
+    decided_by: Vec<String>,
+}
+
+impl ApprovalOutcome {
+    fn new(decision: ApprovalDecision, label: &'static str) -> Self {
+        Self {
+            decision,
+            label,
+            reason: None,
+            decided_by: Vec::new(),
+        }
+    }
+}
+
+/// Label for a round decided by the session's policies.
+fn build_policy_label(decision: &ApprovalDecision) -> &'static str {
+    match decision {
+        ApprovalDecision::Accept
+        | ApprovalDecision::AcceptRaw
+        | ApprovalDecision::AcceptEdited(_) => "Auto-Accept (policy)",
+        ApprovalDecision::Mock => "Auto-Mock (policy)",
+        ApprovalDecision::Fail => "Auto-Fail (policy)",
+        ApprovalDecision::PerTool(_) => "Auto-Per-tool (policy)",
+    }
+}
+
+/// Count the user's Accept of each call, for "after N identical approvals" policies.
+async fn record_user_approvals(
+    params: &InterceptParams<'_>,
+    tool_uses: &[ToolUse],
+    decision: &ApprovalDecision,
+) {
+    for (index, tool_use) in tool_uses.iter().enumerate() {
+        if !matches!(
+            select_tool_decision(decision, index),
+            ApprovalDecision::Accept | ApprovalDecision::AcceptRaw
+        ) {
+            continue;
+        }
+        let input_json = tool_use.input.to_string();
+        if let Err(e) =
+            db::record_webfetch_approval(params.pool, params.session_id, &tool_use.name, &input_json)
+                .await
+        {
+            log::warn!("WebFetch interception: failed to record approval: {}", e);
+        }
+    }
+}
+
+/// Wait for user approval via the dashboard UI. Calls to denied hosts are
+/// auto-failed first, then the session's policies are applied in order, then
+/// calls that are all whitelisted are auto-accepted.
 async fn wait_for_approval(
+    params: &InterceptParams<'_>,
     tool_uses: &[extract::ToolUse],
     tools_info: Vec<PendingToolInfo>,
-    whitelist: &[String],
-    webfetch_names: &[String],
-    approval_queue: &ApprovalQueue,
-    session_id: &str,
     round_idx: usize,
-) -> (ApprovalDecision, &'static str) {
-    if is_all_whitelisted(tool_uses, whitelist, webfetch_names) {
+) -> ApprovalOutcome {
+    if let Some(denied) = find_denied_webfetch(tool_uses, params.deny_rules, params.webfetch_names)
+    {
         log::info!(
-            "WebFetch interception round {}: all tools whitelisted, auto-accepting",
+            "WebFetch interception round {}: {}, auto-failing",
             round_idx + 1,
+            denied
         );
-        return (ApprovalDecision::Accept, "Auto-Accept (whitelisted)");
+        return ApprovalOutcome {
+            decision: ApprovalDecision::Fail,
+            label: "Auto-Fail (denied)",
+            reason: Some(denied),
+            decided_by: Vec::new(),
+        };
     }
 
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    let approval_id = uuid::Uuid::new_v4().to_string();
+    let now_utc = Utc::now();
+    let policy_outcome = evaluate_policies(
+        params.policies,
+        tool_uses,
+        params.pool,
+        params.session_id,
+        now_utc,
+    )
+    .await;
+    let policy_asks = match policy_outcome {
+        PolicyOutcome::Decided { decision, reason } => {
+            log::info!(
+                "WebFetch interception round {}: decided by {}",
+                round_idx + 1,
+                reason
+            );
+            return ApprovalOutcome {
+                label: build_policy_label(&decision),
+                decision,
+                reason: Some(reason),
+                decided_by: Vec::new(),
+            };
+        }
+        PolicyOutcome::Ask { reason } => {
+            log::info!(
+                "WebFetch interception round {}: {} asks for a decision",
+                round_idx + 1,
+                reason
+            );
+            true
+        }
+        PolicyOutcome::Undecided => false,
+    };
+
+    let now = now_utc.format("%Y-%m-%d %H:%M:%S").to_string();
+    if !policy_asks && is_all_whitelisted(tool_uses, params.whitelist, params.webfetch_names, &now)
     {
-        let mut queue_map = approval_queue.lock().unwrap();
-        queue_map.insert(
-            approval_id.clone(),
-            PendingApproval {
-                session_id: session_id.to_string(),
-                tools: tools_info,
-                sender: tx,
-            },
+        log::info!(
+            "WebFetch interception round {}: all tools whitelisted, auto-accepting",
+            round_idx + 1,
         );
+        return ApprovalOutcome::new(ApprovalDecision::Accept, "Auto-Accept (whitelisted)");
     }
 
-    match tokio::time::timeout(std::time::Duration::from_secs(APPROVAL_TIMEOUT_SECS), rx).await {
-        Ok(Ok(decision)) => {
-            let label = match decision {
-                ApprovalDecision::Accept => "Accept",
-                ApprovalDecision::Fail => "Fail",
-                ApprovalDecision::Mock => "Mock",
-            };
-            (decision, label)
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let approval_id = uuid::Uuid::new_v4().to_string();
+    notify_approval_pending(params, &approval_id, &tools_info);
+    insert_pending(
+        params.approval_queue,
+        &approval_id,
+        PendingApproval::new(params.session_id.to_string(), tools_info, tx),
+    );
+
+    let approval_timeout = Duration::from_secs(params.config.approval_timeout_secs);
+    match tokio::time::timeout(approval_timeout, rx).await {
+        Ok(Ok(ApprovalResolution {
+            decision,
+            decided_by,
+        })) => {
+            // Edited inputs are checked against the deny rules like the originals.
+            if let ApprovalDecision::AcceptEdited(ref edited_inputs) = decision {
+                let edited_tool_uses = apply_input_edits(tool_uses, edited_inputs);
+                if let Some(denied) = find_denied_webfetch(
+                    &edited_tool_uses,
+                    params.deny_rules,
+                    params.webfetch_names,
+                ) {
+                    log::info!(
+                        "WebFetch interception round {}: edited input {}, auto-failing",
+                        round_idx + 1,
+                        denied
+                    );
+                    return ApprovalOutcome {
+                        decision: ApprovalDecision::Fail,
+                        label: "Auto-Fail (edited input denied)",
+                        reason: Some(denied),
+                        decided_by,
+                    };
+                }
+            }
+            record_user_approvals(params, tool_uses, &decision).await;
+            let label = decision.label();
+            ApprovalOutcome {
+                decided_by,
+                ..ApprovalOutcome::new(decision, label)
+            }
         }
         _ => {
-            let mut queue_map = approval_queue.lock().unwrap();
-            queue_map.remove(&approval_id);
+            remove_pending(params.approval_queue, &approval_id);
             log::info!("WebFetch interception: approval timed out, auto-failing");
-            (ApprovalDecision::Fail, "Timeout (auto-fail)")
+            ApprovalOutcome::new(ApprovalDecision::Fail, "Timeout (auto-fail)")
         }
     }
 }
 
+/// Record an approval decision and how long it took on the session timeline.
+async fn record_approval_event(
+    params: &InterceptParams<'_>,
+    tool_uses: &[extract::ToolUse],
+    approval_outcome: &ApprovalOutcome,
+    waited: Duration,
+) {
+    let tool_names: Vec<String> = match &approval_outcome.decision {
+        ApprovalDecision::PerTool(_) => tool_uses
+            .iter()
+            .enumerate()
+            .map(|(index, tool_use)| {
+                let tool_decision = select_tool_decision(&approval_outcome.decision, index);
+                format!("{} ({})", tool_use.name, tool_decision.label())
+            })
+            .collect(),
+        _ => tool_uses.iter().map(|tool_use| tool_use.name.clone()).collect(),
+    };
+    let mut detail = format!("{}: {}", approval_outcome.label, tool_names.join(", "));
+    if let Some(ref reason) = approval_outcome.reason {
+        detail.push_str(&format!(" ({})", reason));
+    }
+    if !approval_outcome.decided_by.is_empty() {
+        detail.push_str(&format!(" by {}", approval_outcome.decided_by.join(", ")));
+    }
+    if let Err(e) = db::create_session_event(
+        params.pool,
+        params.session_id,
+        SESSION_EVENT_APPROVAL,
+        &detail,
+        Some(waited.as_millis() as i64),
+    )
+    .await
+    {
+        log::warn!("WebFetch interception: failed to record approval event: {}", e);
+    }
+}
+
+/// Tell the configured notification webhooks that an approval is waiting.
+fn notify_approval_pending(
+    params: &InterceptParams<'_>,
+    approval_id: &str,
+    tools_info: &[PendingToolInfo],
+) {
+    let tools: Vec<Value> = tools_info
+        .iter()
+        .map(|tool| serde_json::json!({"name": tool.name, "input_summary": tool.input_summary}))
+        .collect();
+    let payload = serde_json::json!({
+        "event": "approval_pending",
+        "session_id": params.session_id,
+        "approval_id": approval_id,
+        "tools": tools,
+    });
+    send_webhook_notifications(params.client, &params.config.notification_webhooks, &payload);
+}
+
 /// Context for logging a follow-up round to the database.
 struct FollowupRoundContext<'a> {
     pool: &'a sqlx::SqlitePool,
     session_id: &'a str,
+    parent_request_id: &'a str,
     stored_path: &'a str,
     headers: &'a reqwest::header::HeaderMap,
     followup_body: &'a Value,
@@ -148,14 +389,17 @@ async fn log_followup_round(ctx: &FollowupRoundContext<'_>) -> Option<String> {
     .ok();
     match log_request(
         &RequestMeta {
+            id: None,
             pool: ctx.pool,
             session_id: ctx.session_id,
             method: "POST",
             path: ctx.stored_path,
             headers_json: headers_json.as_deref(),
             note: Some(&note),
+            client: None,
+            parent_request_id: Some(ctx.parent_request_id),
         },
-        &fields,
+        fields,
     )
     .await
     {
@@ -189,87 +433,218 @@ async fn log_followup_round(ctx: &FollowupRoundContext<'_>) -> Option<String> {
     }
 }
 
+/// Tool results for a single round, with per-call agent request IDs and fetch metadata.
+struct RoundToolResults {
+    tool_results: Vec<Value>,
+    agent_request_ids: Vec<Option<String>>,
+    fetches: Vec<Option<Value>>,
+}
+
+/// The decision that applies to the tool call at `index`: its own decision
+/// for a `PerTool` round, otherwise the round's decision.
+fn select_tool_decision(decision: &ApprovalDecision, index: usize) -> &ApprovalDecision {
+    match decision {
+        ApprovalDecision::PerTool(tool_decisions) => {
+            tool_decisions.get(index).unwrap_or(&ApprovalDecision::Fail)
+        }
+        _ => decision,
+    }
+}
+
 /// Build tool results for a single round based on the approval decision.
 async fn build_tool_results(
     decision: &ApprovalDecision,
     tool_uses: &[ToolUse],
     config: &AppConfig,
     ctx: &FetchContext<'_>,
-) -> (Vec<Value>, Vec<Option<String>>) {
-    match decision {
-        ApprovalDecision::Fail => {
-            let results: Vec<Value> = tool_uses.iter().map(build_fail_result).collect();
-            let ids = vec![None; results.len()];
-            (results, ids)
-        }
-        ApprovalDecision::Mock => {
-            let results: Vec<Value> = tool_uses
-                .iter()
-                .map(|tool_use| build_mock_result(tool_use, &config.webfetch_mock_prompt))
-                .collect();
-            let ids = vec![None; results.len()];
-            (results, ids)
+) -> RoundToolResults {
+    let mut results = Vec::with_capacity(tool_uses.len());
+    let mut ids = Vec::with_capacity(tool_uses.len());
+    let mut fetches = Vec::with_capacity(tool_uses.len());
+    for (index, tu) in tool_uses.iter().enumerate() {
+        let tool_decision = select_tool_decision(decision, index);
+        let accept = build_tool_result(tool_decision, tu, config, ctx).await;
+        results.push(accept.tool_result);
+        ids.push(accept.agent_request_id);
+        fetches.push(accept.fetch_meta);
+    }
+    RoundToolResults {
+        tool_results: results,
+        agent_request_ids: ids,
+        fetches,
+    }
+}
+
+/// Build the tool_result for one tool call.
+async fn build_tool_result(
+    decision: &ApprovalDecision,
+    tool_use: &ToolUse,
+    config: &AppConfig,
+    ctx: &FetchContext<'_>,
+) -> AcceptResult {
+    let tool_result = match decision {
+        ApprovalDecision::Accept => return execute_tool(tool_use, ctx).await,
+        ApprovalDecision::AcceptRaw => {
+            let raw_ctx = FetchContext {
+                raw_mode: true,
+                ..ctx.clone()
+            };
+            return execute_tool(tool_use, &raw_ctx).await;
         }
-        ApprovalDecision::Accept => {
-            let mut results = Vec::with_capacity(tool_uses.len());
-            let mut ids = Vec::with_capacity(tool_uses.len());
-            for tu in tool_uses {
-                let accept = build_accept_result(tu, ctx).await;
-                results.push(accept.tool_result);
-                ids.push(accept.agent_request_id);
-            }
-            (results, ids)
+        ApprovalDecision::AcceptEdited(edited_inputs) => {
+            return execute_tool(&apply_input_edit(tool_use, edited_inputs), ctx).await;
         }
+        ApprovalDecision::Mock => build_mock_result(tool_use, &config.webfetch_mock_prompt),
+        // Per-tool decisions are not nested; treat one like a Fail.
+        ApprovalDecision::Fail | ApprovalDecision::PerTool(_) => build_fail_result(tool_use),
+    };
+    AcceptResult {
+        tool_result,
+        agent_request_id: None,
+        fetch_meta: None,
     }
 }
 
-/// Send a follow-up request to the upstream API and return the response.
+/// Replace a tool call's input with the user's edit, keyed by tool_use id. The
+/// id and name are kept so the tool_result still answers the model's call.
+fn apply_input_edit(tool_use: &ToolUse, edited_inputs: &Value) -> ToolUse {
+    ToolUse {
+        input: edited_inputs
+            .get(&tool_use.id)
+            .filter(|input| input.is_object())
+            .cloned()
+            .unwrap_or_else(|| tool_use.input.clone()),
+        ..tool_use.clone()
+    }
+}
+
+fn apply_input_edits(tool_uses: &[ToolUse], edited_inputs: &Value) -> Vec<ToolUse> {
+    tool_uses
+        .iter()
+        .map(|tool_use| apply_input_edit(tool_use, edited_inputs))
+        .collect()
+}
+
+/// Send a follow-up request to the upstream API and return the response,
+/// or why it failed.
 async fn send_followup_request(
     client: &reqwest::Client,
     target_url: &str,
     headers: &reqwest::header::HeaderMap,
     followup_body: &Value,
-) -> Option<(u16, reqwest::header::HeaderMap, bytes::Bytes)> {
-    let followup_bytes = match serde_json::to_vec(followup_body) {
-        Ok(serialized) => serialized,
-        Err(e) => {
-            log::warn!(
-                "WebFetch interception: failed to serialize follow-up body: {}",
-                e
-            );
-            return None;
-        }
-    };
+    round_timeout: Option<Duration>,
+) -> Result<(u16, reqwest::header::HeaderMap, bytes::Bytes), String> {
+    let followup_bytes = serde_json::to_vec(followup_body)
+        .map_err(|e| format!("failed to serialize follow-up body: {}", e))?;
 
-    let followup_response = match client
+    let mut followup_request = client
         .post(target_url)
         .headers(headers.clone())
-        .body(followup_bytes)
+        .body(followup_bytes);
+    if let Some(round_timeout) = round_timeout {
+        followup_request = followup_request.timeout(round_timeout);
+    }
+    let followup_response = followup_request
         .send()
         .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            log::warn!("WebFetch interception: follow-up request failed: {}", e);
-            return None;
-        }
-    };
+        .map_err(|e| format!("follow-up request failed: {}", e))?;
 
     let status = followup_response.status().as_u16();
     let response_headers = followup_response.headers().clone();
 
-    let body = match followup_response.bytes().await {
-        Ok(body_bytes) => body_bytes,
-        Err(e) => {
-            log::warn!(
-                "WebFetch interception: failed to read follow-up response: {}",
-                e
-            );
-            return None;
+    let body = followup_response
+        .bytes()
+        .await
+        .map_err(|e| format!("failed to read follow-up response: {}", e))?;
+
+    Ok((status, response_headers, body))
+}
+
+/// Send a follow-up request, sending it again up to `retries` times while it
+/// fails or times out. Returns the last failure once every attempt has failed.
+async fn send_followup_request_with_retries(
+    client: &reqwest::Client,
+    target_url: &str,
+    headers: &reqwest::header::HeaderMap,
+    followup_body: &Value,
+    retries: usize,
+    round_timeout: Option<Duration>,
+) -> Result<(u16, reqwest::header::HeaderMap, bytes::Bytes), String> {
+    let mut attempt = 0;
+    loop {
+        match send_followup_request(client, target_url, headers, followup_body, round_timeout).await
+        {
+            Ok(followup_response) => return Ok(followup_response),
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                log::warn!(
+                    "WebFetch interception: {}; retrying ({} of {})",
+                    e,
+                    attempt,
+                    retries
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Why an interception stopped before its rounds were done.
+enum InterceptStop {
+    /// Cancelled from the Tool Intercept page.
+    Cancelled,
+    /// `webfetch_intercept_deadline_secs` passed.
+    DeadlinePassed,
+}
+
+impl InterceptStop {
+    fn describe(&self, round_idx: usize) -> String {
+        match self {
+            Self::Cancelled => {
+                format!("WebFetch interception cancelled in round {}", round_idx + 1)
+            }
+            Self::DeadlinePassed => format!(
+                "WebFetch interception passed its deadline in round {}",
+                round_idx + 1
+            ),
+        }
+    }
+}
+
+/// Run `future` unless the interception is cancelled or its deadline passes
+/// first, in which case `future` is dropped along with its requests.
+async fn run_until_stopped<F: Future>(
+    running_intercept: &mut RunningInterceptGuard,
+    deadline: Option<Instant>,
+    future: F,
+) -> Result<F::Output, InterceptStop> {
+    let deadline_passed = async {
+        match deadline {
+            Some(deadline) => sleep_until(deadline).await,
+            None => pending().await,
         }
     };
+    tokio::select! {
+        biased;
+        _ = running_intercept.cancelled() => Err(InterceptStop::Cancelled),
+        _ = deadline_passed => Err(InterceptStop::DeadlinePassed),
+        output = future => Ok(output),
+    }
+}
 
-    Some((status, response_headers, body))
+/// Record why an interception stopped early on the intercepted request.
+/// Returns whether `webfetch_followup_fallback` answers the client with an
+/// error rather than the last good response.
+async fn record_intercept_stop(params: &InterceptParams<'_>, message: &str) -> bool {
+    log::warn!("{}", message);
+    let write = db::RequestWrite::WebfetchFollowupError {
+        request_id: params.request_id.to_string(),
+        error: message.to_string(),
+    };
+    if let Err(e) = db::write_request(params.pool, write).await {
+        log::warn!("webfetch: failed to store follow-up error: {}", e);
+    }
+    params.config.webfetch_followup_fallback == WEBFETCH_FOLLOWUP_FALLBACK_ERROR
 }
 
 /// Build the note string summarizing the interception.
@@ -305,9 +680,12 @@ fn serialize_rounds(rounds: &[RoundData]) -> Option<(String, String)> {
         .map(|round| {
             serde_json::json!({
                 "decision": round.decision,
+                "decision_reason": round.decision_reason,
                 "tool_names": round.tool_names,
+                "tool_decisions": round.tool_decisions,
                 "request_id": round.request_id,
                 "agent_request_ids": round.agent_request_ids,
+                "fetches": round.fetches,
                 "followup_body": round.followup_body,
                 "response_body": round.response_body,
                 "response_events": round.response_events,
@@ -327,26 +705,32 @@ fn serialize_rounds(rounds: &[RoundData]) -> Option<(String, String)> {
 /// to the client.
 ///
 /// Returns `Some(InterceptResult)` if any webfetch tool calls were detected, `None` otherwise.
+/// Each follow-up request is limited to `webfetch_round_timeout_secs` and the rounds as a
+/// whole to `webfetch_intercept_deadline_secs`, and they can be cancelled from the Tool
+/// Intercept page. A follow-up request that fails after `webfetch_followup_retries` retries,
+/// a cancellation or a passed deadline is recorded on the request, then the client gets the
+/// last good response (`None` before the first round is done) or `FollowupFailed`, as
+/// `webfetch_followup_fallback` says.
 pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptResult> {
     let response_body = params.response_body;
     let original_body = params.original_body;
     let target_url = params.target_url;
     let forward_headers = params.forward_headers;
     let client = params.client;
-    let approval_queue = params.approval_queue;
     let session_id = params.session_id;
-    let whitelist = params.whitelist;
     let pool = params.pool;
     let stored_path = params.stored_path;
     let webfetch_names = params.webfetch_names;
     let config = params.config;
+    // Tools with an executor are intercepted alongside the WebFetch names.
+    let intercepted_names = collect_intercepted_names(webfetch_names, &config.tool_executors);
 
     let sse_events = parse_sse_events(response_body);
 
     let InterceptedTools {
         mut content_blocks,
         tool_uses,
-    } = extract_webfetch_from_sse(&sse_events, webfetch_names)?;
+    } = extract_webfetch_from_sse(&sse_events, &intercepted_names)?;
 
     // Remove tool_use content blocks that were filtered out, so the
     // follow-up body stays consistent with the tool_results we provide.
@@ -378,6 +762,14 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
     let mut final_status: u16 = 0;
     let mut final_headers = reqwest::header::HeaderMap::new();
     let mut final_body = bytes::Bytes::new();
+    let mut stop_message: Option<String> = None;
+
+    let mut running_intercept =
+        start_running_intercept(params.approval_queue, session_id, params.request_id);
+    let deadline = (config.webfetch_intercept_deadline_secs > 0)
+        .then(|| Instant::now() + Duration::from_secs(config.webfetch_intercept_deadline_secs));
+    let round_timeout = (config.webfetch_round_timeout_secs > 0)
+        .then(|| Duration::from_secs(config.webfetch_round_timeout_secs));
 
     let fetch_ctx = FetchContext {
         client,
@@ -385,14 +777,29 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
         accept_prompt: &config.webfetch_accept_prompt,
         redirect_prompt: &config.webfetch_redirect_prompt,
         agent_model: &config.webfetch_agent_model,
+        agent_max_output_tokens: config.webfetch_agent_max_output_tokens,
+        agent_max_response_bytes: config.webfetch_agent_max_response_bytes,
         target_url,
         forward_headers: &headers,
         pool,
         session_id,
         stored_path,
+        allowed_schemes: &config.webfetch_allowed_schemes,
+        max_redirects: config.webfetch_max_redirects,
+        allow_private_targets: params.allow_private_targets,
+        max_content_bytes: params.max_content_bytes,
+        fetch_timeout: params.fetch_timeout,
+        cache_ttl_secs: config.webfetch_cache_ttl_secs,
+        extraction_mode: params.extraction_mode,
+        respect_robots_txt: params.respect_robots_txt,
+        raw_mode: params.raw_mode,
+        tool_executors: &config.tool_executors,
+        mcp_server: params.mcp_server,
+        agent_backend: params.agent_backend,
     };
 
     for round_idx in 0..MAX_INTERCEPT_ROUNDS {
+        running_intercept.set_round(round_idx);
         let intercepted_tools: Vec<&str> =
             current_tool_uses.iter().map(|tool_use| tool_use.name.as_str()).collect();
         all_tool_names.extend(intercepted_tools.iter().map(|string| string.to_string()));
@@ -407,22 +814,32 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
         let tools_info: Vec<PendingToolInfo> = current_tool_uses
             .iter()
             .map(|tool_use| PendingToolInfo {
+                tool_use_id: tool_use.id.clone(),
                 name: tool_use.name.clone(),
                 input_summary: build_input_summary(tool_use),
+                input: tool_use.input.clone(),
+                decision: None,
+                decided_by: None,
             })
             .collect();
 
-        // Auto-accept if all tools are whitelisted WebFetch calls
-        let (decision, decision_label) = wait_for_approval(
+        // Auto-fail denied hosts, auto-accept if all tools are whitelisted WebFetch calls
+        let approval_started = Instant::now();
+        let approval_outcome =
+            wait_for_approval(params, &current_tool_uses, tools_info, round_idx).await;
+        record_approval_event(
+            params,
             &current_tool_uses,
-            tools_info,
-            whitelist,
-            webfetch_names,
-            approval_queue,
-            session_id,
-            round_idx,
+            &approval_outcome,
+            approval_started.elapsed(),
         )
         .await;
+        let ApprovalOutcome {
+            decision,
+            label: decision_label,
+            reason: decision_reason,
+            ..
+        } = approval_outcome;
 
         log::info!(
             "WebFetch interception round {}: user decided {:?}",
@@ -430,14 +847,55 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
             decision
         );
 
-        let (tool_results, agent_request_ids) =
-            build_tool_results(&decision, &current_tool_uses, config, &fetch_ctx).await;
+        let RoundToolResults {
+            tool_results,
+            agent_request_ids,
+            fetches,
+        } = match run_until_stopped(
+            &mut running_intercept,
+            deadline,
+            build_tool_results(&decision, &current_tool_uses, config, &fetch_ctx),
+        )
+        .await
+        {
+            Ok(round_tool_results) => round_tool_results,
+            Err(stop) => {
+                stop_message = Some(stop.describe(round_idx));
+                break;
+            }
+        };
 
         let followup_body =
             build_followup_body(&current_body, current_content_blocks, tool_results);
 
-        let (followup_status, followup_headers, followup_body_bytes) =
-            send_followup_request(client, target_url, &headers, &followup_body).await?;
+        let followup_result = run_until_stopped(
+            &mut running_intercept,
+            deadline,
+            send_followup_request_with_retries(
+                client,
+                target_url,
+                &headers,
+                &followup_body,
+                config.webfetch_followup_retries,
+                round_timeout,
+            ),
+        )
+        .await;
+        let (followup_status, followup_headers, followup_body_bytes) = match followup_result {
+            Ok(Ok(followup_response)) => followup_response,
+            Ok(Err(e)) => {
+                stop_message = Some(format!(
+                    "WebFetch follow-up request failed in round {}: {}",
+                    round_idx + 1,
+                    e
+                ));
+                break;
+            }
+            Err(stop) => {
+                stop_message = Some(stop.describe(round_idx));
+                break;
+            }
+        };
 
         final_status = followup_status;
         final_headers = followup_headers;
@@ -450,6 +908,7 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
         let round_request_id = log_followup_round(&FollowupRoundContext {
             pool,
             session_id,
+            parent_request_id: params.request_id,
             stored_path,
             headers: &headers,
             followup_body: &followup_body,
@@ -462,16 +921,21 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
 
         rounds.push(RoundData {
             decision: decision_label.to_string(),
+            decision_reason,
             tool_names: current_tool_uses.iter().map(|tool_use| tool_use.name.clone()).collect(),
+            tool_decisions: (0..current_tool_uses.len())
+                .map(|index| select_tool_decision(&decision, index).label().to_string())
+                .collect(),
             request_id: round_request_id,
             agent_request_ids,
+            fetches,
             followup_body: followup_body.clone(),
             response_body: response_body_str,
             response_events: response_events.clone(),
         });
 
         // Check if the follow-up response contains more webfetch tool calls
-        match extract_webfetch_from_sse(&response_events, webfetch_names) {
+        match extract_webfetch_from_sse(&response_events, &intercepted_names) {
             Some(InterceptedTools {
                 content_blocks: mut next_blocks,
                 tool_uses: next_uses,
@@ -493,6 +957,14 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
         }
     }
 
+    drop(running_intercept);
+
+    if let Some(message) = stop_message {
+        if record_intercept_stop(params, &message).await {
+            return Some(InterceptResult::FollowupFailed { message });
+        }
+    }
+
     if rounds.is_empty() {
         return None;
     }
@@ -507,23 +979,25 @@ pub async fn maybe_intercept(params: &InterceptParams<'_>) -> Option<InterceptRe
     let note = build_intercept_note(&all_tool_names, rounds.len());
     let (followup_body_json, rounds_json) = serialize_rounds(&rounds)?;
 
-    Some(InterceptResult::Intercepted {
+    Some(InterceptResult::Intercepted(InterceptedResponse {
         status: final_status,
         headers: final_headers,
         body: final_body,
         note,
         followup_body_json,
         rounds_json,
-    })
+    }))
 }
 
 #[cfg(test)]
 mod tests {
     use super::extract::*;
+    use super::fetch::build_accept_result;
     use super::mock::*;
     use super::*;
-    use common::config::AppConfig;
+    use common::config::{AppConfig, ToolExecutorConfig, ToolExecutorKind};
     use tokio::sync::oneshot;
+    use url::Url;
 
     fn default_config() -> AppConfig {
         AppConfig::default()
@@ -572,9 +1046,17 @@ mod tests {
     fn test_serialize_rounds_single() {
         let rounds = vec![RoundData {
             decision: "Accept".to_string(),
+            decision_reason: None,
             tool_names: vec!["WebFetch".to_string()],
+            tool_decisions: vec!["Accept".to_string()],
             request_id: Some("req_1".to_string()),
             agent_request_ids: vec![Some("agent_1".to_string())],
+            fetches: vec![Some(serde_json::json!({
+                "url": "https://example.com/",
+                "original_length": 2048,
+                "bytes_read": 1024,
+                "truncated": true,
+            }))],
             followup_body: serde_json::json!({"model": "test", "messages": []}),
             response_body: "response data".to_string(),
             response_events: vec![serde_json::json!({"event": "message_start"})],
@@ -588,6 +1070,8 @@ mod tests {
         assert_eq!(parsed_rounds.len(), 1);
         assert_eq!(parsed_rounds[0]["decision"], "Accept");
         assert_eq!(parsed_rounds[0]["tool_names"][0], "WebFetch");
+        assert_eq!(parsed_rounds[0]["tool_decisions"][0], "Accept");
+        assert_eq!(parsed_rounds[0]["fetches"][0]["original_length"], 2048);
     }
 
     #[test]
@@ -595,18 +1079,24 @@ mod tests {
         let rounds = vec![
             RoundData {
                 decision: "Accept".to_string(),
+                decision_reason: None,
                 tool_names: vec!["WebFetch".to_string()],
+                tool_decisions: vec!["Accept".to_string()],
                 request_id: Some("req_1".to_string()),
                 agent_request_ids: vec![None],
+                fetches: vec![None],
                 followup_body: serde_json::json!({"round": 1}),
                 response_body: "resp1".to_string(),
                 response_events: vec![],
             },
             RoundData {
                 decision: "Mock".to_string(),
+                decision_reason: None,
                 tool_names: vec!["WebSearch".to_string()],
+                tool_decisions: vec!["Mock".to_string()],
                 request_id: None,
                 agent_request_ids: vec![],
+                fetches: vec![],
                 followup_body: serde_json::json!({"round": 2}),
                 response_body: "resp2".to_string(),
                 response_events: vec![],
@@ -659,24 +1149,77 @@ mod tests {
         assert_eq!(build_input_summary(&fetch), "URL: https://example.com");
     }
 
+    #[test]
+    fn test_apply_input_edits() {
+        let tool_uses = vec![
+            ToolUse {
+                id: "toolu_1".to_string(),
+                name: "WebFetch".to_string(),
+                input: serde_json::json!({"url": "https://example.com", "prompt": "Summarize"}),
+            },
+            ToolUse {
+                id: "toolu_2".to_string(),
+                name: "WebFetch".to_string(),
+                input: serde_json::json!({"url": "https://docs.rs", "prompt": "List crates"}),
+            },
+        ];
+        let edited_inputs = serde_json::json!({
+            "toolu_1": {"url": "https://example.com/docs", "prompt": "Summarize the docs"},
+            "toolu_2": "not an object",
+            "toolu_unknown": {"url": "https://ignored.example"},
+        });
+        let edited = apply_input_edits(&tool_uses, &edited_inputs);
+        assert_eq!(edited.len(), 2);
+        assert_eq!(edited[0].id, "toolu_1");
+        assert_eq!(edited[0].name, "WebFetch");
+        assert_eq!(edited[0].input["url"], "https://example.com/docs");
+        assert_eq!(edited[0].input["prompt"], "Summarize the docs");
+        assert_eq!(edited[1].input, tool_uses[1].input);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_running_intercept_stops_the_round() {
+        let queue = new_approval_queue();
+        let mut running_intercept = start_running_intercept(&queue, "sess_a", "req_1");
+        running_intercept.set_round(1);
+
+        let running_intercepts = list_running_intercepts(&queue, "sess_a");
+        assert_eq!(running_intercepts.len(), 1);
+        assert_eq!(running_intercepts[0].request_id, "req_1");
+        assert_eq!(running_intercepts[0].round, 2);
+        assert!(list_running_intercepts(&queue, "sess_b").is_empty());
+
+        // Only the request's own session can cancel it
+        assert!(!cancel_running_intercept(&queue, "sess_b", "req_1"));
+        assert!(cancel_running_intercept(&queue, "sess_a", "req_1"));
+        let stopped = run_until_stopped(&mut running_intercept, None, pending::<()>()).await;
+        assert!(matches!(stopped, Err(InterceptStop::Cancelled)));
+
+        drop(running_intercept);
+        assert!(list_running_intercepts(&queue, "sess_a").is_empty());
+        assert!(!cancel_running_intercept(&queue, "sess_a", "req_1"));
+    }
+
     #[test]
     fn test_list_pending_and_resolve() {
         let queue = new_approval_queue();
         let (tx, rx) = oneshot::channel();
-        {
-            let mut queue_map = queue.lock().unwrap();
-            queue_map.insert(
-                "approval_1".to_string(),
-                PendingApproval {
-                    session_id: "sess_a".to_string(),
-                    tools: vec![PendingToolInfo {
-                        name: "WebSearch".to_string(),
-                        input_summary: "Query: test".to_string(),
-                    }],
-                    sender: tx,
-                },
-            );
-        }
+        insert_pending(
+            &queue,
+            "approval_1",
+            PendingApproval::new(
+                "sess_a".to_string(),
+                vec![PendingToolInfo {
+                    tool_use_id: "toolu_1".to_string(),
+                    name: "WebSearch".to_string(),
+                    input_summary: "Query: test".to_string(),
+                    input: serde_json::json!({"query": "test"}),
+                    decision: None,
+                    decided_by: None,
+                }],
+                tx,
+            ),
+        );
 
         // list_pending filters by session_id
         let pending = list_pending(&queue, "sess_a");
@@ -686,20 +1229,222 @@ mod tests {
         let empty = list_pending(&queue, "sess_b");
         assert!(empty.is_empty());
 
+        let (pending_session_id, tools) = get_pending(&queue, "approval_1").unwrap();
+        assert_eq!(pending_session_id, "sess_a");
+        assert_eq!(tools[0].input["query"], "test");
+        assert!(get_pending(&queue, "approval_2").is_none());
+
         // resolve_pending sends the decision
         assert!(resolve_pending(
             &queue,
             "approval_1",
-            ApprovalDecision::Mock
+            ApprovalDecision::Mock,
+            "alice"
         ));
-        assert_eq!(rx.blocking_recv().unwrap(), ApprovalDecision::Mock);
+        assert_eq!(
+            rx.blocking_recv().unwrap(),
+            ApprovalResolution {
+                decision: ApprovalDecision::Mock,
+                decided_by: vec!["alice".to_string()],
+            }
+        );
 
         // Already removed
         assert!(!resolve_pending(
             &queue,
             "approval_1",
-            ApprovalDecision::Fail
+            ApprovalDecision::Fail,
+            "alice"
+        ));
+    }
+
+    fn make_pending_tool(tool_use_id: &str) -> PendingToolInfo {
+        PendingToolInfo {
+            tool_use_id: tool_use_id.to_string(),
+            name: "WebFetch".to_string(),
+            input_summary: String::new(),
+            input: serde_json::json!({}),
+            decision: None,
+            decided_by: None,
+        }
+    }
+
+    #[test]
+    fn test_decide_pending_tool_mixed() {
+        let queue = new_approval_queue();
+        let (tx, rx) = oneshot::channel();
+        insert_pending(
+            &queue,
+            "approval_1",
+            PendingApproval::new(
+                "sess_a".to_string(),
+                vec![make_pending_tool("toolu_1"), make_pending_tool("toolu_2")],
+                tx,
+            ),
+        );
+
+        assert!(!decide_pending_tool(
+            &queue,
+            "approval_1",
+            "toolu_unknown",
+            ApprovalDecision::Mock,
+            "alice"
+        ));
+        assert!(decide_pending_tool(
+            &queue,
+            "approval_1",
+            "toolu_2",
+            ApprovalDecision::Mock,
+            "alice"
+        ));
+        // Still waiting for the first call; the decided one is shown as such.
+        let pending = list_pending(&queue, "sess_a");
+        assert_eq!(pending[0].1[0].decision, None);
+        assert_eq!(pending[0].1[1].decision.as_deref(), Some("Mock"));
+        assert_eq!(pending[0].1[1].decided_by.as_deref(), Some("alice"));
+
+        assert!(decide_pending_tool(
+            &queue,
+            "approval_1",
+            "toolu_1",
+            ApprovalDecision::Accept,
+            "bob"
         ));
+        assert!(list_pending(&queue, "sess_a").is_empty());
+        assert_eq!(
+            rx.blocking_recv().unwrap(),
+            ApprovalResolution {
+                decision: ApprovalDecision::PerTool(vec![
+                    ApprovalDecision::Accept,
+                    ApprovalDecision::Mock
+                ]),
+                decided_by: vec!["alice".to_string(), "bob".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_decide_pending_tool_uniform() {
+        let queue = new_approval_queue();
+        let (tx, rx) = oneshot::channel();
+        insert_pending(
+            &queue,
+            "approval_1",
+            PendingApproval::new(
+                "sess_a".to_string(),
+                vec![make_pending_tool("toolu_1"), make_pending_tool("toolu_2")],
+                tx,
+            ),
+        );
+        decide_pending_tool(&queue, "approval_1", "toolu_1", ApprovalDecision::Fail, "alice");
+        decide_pending_tool(&queue, "approval_1", "toolu_2", ApprovalDecision::Fail, "alice");
+        assert_eq!(
+            rx.blocking_recv().unwrap(),
+            ApprovalResolution {
+                decision: ApprovalDecision::Fail,
+                decided_by: vec!["alice".to_string()],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_approval_events() {
+        use futures::StreamExt;
+
+        let queue = new_approval_queue();
+        let (tx, _rx) = oneshot::channel();
+        insert_pending(
+            &queue,
+            "approval_1",
+            PendingApproval::new(
+                "sess_a".to_string(),
+                vec![make_pending_tool("toolu_1"), make_pending_tool("toolu_2")],
+                tx,
+            ),
+        );
+        let mut events = Box::pin(stream_approval_events(&queue, "sess_a"));
+        let Some(ApprovalStreamMessage::Snapshot(pending)) = events.next().await else {
+            panic!("expected a snapshot first");
+        };
+        assert_eq!(pending.len(), 1);
+
+        // Changes to other sessions are not streamed.
+        let (tx, _rx) = oneshot::channel();
+        insert_pending(
+            &queue,
+            "approval_2",
+            PendingApproval::new("sess_b".to_string(), vec![make_pending_tool("toolu_3")], tx),
+        );
+        decide_pending_tool(&queue, "approval_1", "toolu_1", ApprovalDecision::Mock, "alice");
+        resolve_pending(&queue, "approval_1", ApprovalDecision::Fail, "alice");
+
+        let Some(ApprovalStreamMessage::Event(ApprovalEvent::Updated { tools, .. })) =
+            events.next().await
+        else {
+            panic!("expected the per-tool decision");
+        };
+        assert_eq!(tools[0].decision.as_deref(), Some("Mock"));
+        let Some(ApprovalStreamMessage::Event(ApprovalEvent::Removed { approval_id, .. })) =
+            events.next().await
+        else {
+            panic!("expected the removal");
+        };
+        assert_eq!(approval_id, "approval_1");
+    }
+
+    #[tokio::test]
+    async fn test_build_tool_results_per_tool() {
+        let client = reqwest::Client::new();
+        let headers = reqwest::header::HeaderMap::new();
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let wf_names = default_wf_names();
+        let config = default_config();
+        let ctx = FetchContext {
+            client: &client,
+            webfetch_names: &wf_names,
+            accept_prompt: "",
+            redirect_prompt: "",
+            agent_model: "",
+            agent_max_output_tokens: 16384,
+            agent_max_response_bytes: 1024 * 1024,
+            target_url: "",
+            forward_headers: &headers,
+            pool: &pool,
+            session_id: "test-session",
+            stored_path: "/test",
+            allowed_schemes: &config.webfetch_allowed_schemes,
+            max_redirects: 5,
+            allow_private_targets: false,
+            max_content_bytes: 100 * 1024,
+            fetch_timeout: Duration::from_secs(30),
+            cache_ttl_secs: 0,
+            extraction_mode: "text",
+            respect_robots_txt: false,
+            tool_executors: &[],
+            mcp_server: None,
+            agent_backend: AgentBackend::default(),
+            raw_mode: false,
+        };
+        let tool_uses: Vec<ToolUse> = ["toolu_1", "toolu_2", "toolu_3"]
+            .into_iter()
+            .map(|id| ToolUse {
+                id: id.to_string(),
+                name: "WebFetch".to_string(),
+                input: serde_json::json!({"url": "https://example.com"}),
+            })
+            .collect();
+        // The third call has no decision of its own and is failed.
+        let decision =
+            ApprovalDecision::PerTool(vec![ApprovalDecision::Mock, ApprovalDecision::Fail]);
+        let round = build_tool_results(&decision, &tool_uses, &config, &ctx).await;
+        assert_eq!(round.tool_results.len(), 3);
+        assert_eq!(round.tool_results[0]["tool_use_id"], "toolu_1");
+        assert!(round.tool_results[0].get("is_error").is_none());
+        assert_eq!(round.tool_results[1]["is_error"], true);
+        assert_eq!(round.tool_results[2]["is_error"], true);
+        assert_eq!(round.agent_request_ids, vec![None, None, None]);
+        assert_eq!(select_tool_decision(&decision, 0).label(), "Mock");
+        assert_eq!(select_tool_decision(&ApprovalDecision::Accept, 2).label(), "Accept");
     }
 
     #[test]
@@ -712,6 +1457,21 @@ mod tests {
         assert!(extract_webfetch_from_sse(&events, &default_wf_names()).is_none());
     }
 
+    #[test]
+    fn test_extract_webfetch_from_captured_response() {
+        let fixture = crate::fixtures::load_fixture("webfetch_tool_use");
+        let events = parse_sse_events(&fixture.build_response_sse_body());
+
+        let InterceptedTools {
+            content_blocks,
+            tool_uses,
+        } = extract_webfetch_from_sse(&events, &default_wf_names()).unwrap();
+        assert_eq!(content_blocks.len(), 1);
+        assert_eq!(tool_uses.len(), 1);
+        assert_eq!(tool_uses[0].id, "toolu_1");
+        assert_eq!(tool_uses[0].input["url"], "https://example.invalid/a");
+    }
+
     #[test]
     fn test_extract_custom_tool_webfetch() {
         let events = vec![
@@ -923,17 +1683,32 @@ mod tests {
         let headers = reqwest::header::HeaderMap::new();
         let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
         let wf_names = default_wf_names();
+        let allowed_schemes = default_config().webfetch_allowed_schemes;
         let ctx = FetchContext {
             client: &client,
             webfetch_names: &wf_names,
             accept_prompt: "",
             redirect_prompt: "",
             agent_model: "",
+            agent_max_output_tokens: 16384,
+            agent_max_response_bytes: 1024 * 1024,
             target_url: "",
             forward_headers: &headers,
             pool: &pool,
             session_id: "test-session",
             stored_path: "/test",
+            allowed_schemes: &allowed_schemes,
+            max_redirects: 5,
+            allow_private_targets: false,
+            max_content_bytes: 100 * 1024,
+            fetch_timeout: Duration::from_secs(30),
+            cache_ttl_secs: 0,
+            extraction_mode: "text",
+            respect_robots_txt: false,
+            tool_executors: &[],
+            mcp_server: None,
+            agent_backend: AgentBackend::default(),
+            raw_mode: false,
         };
         let result = build_accept_result(&tool_use, &ctx).await;
         assert_eq!(result.tool_result["type"], "tool_result");
@@ -958,17 +1733,32 @@ mod tests {
         let headers = reqwest::header::HeaderMap::new();
         let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
         let wf_names = default_wf_names();
+        let allowed_schemes = default_config().webfetch_allowed_schemes;
         let ctx = FetchContext {
             client: &client,
             webfetch_names: &wf_names,
             accept_prompt: "",
             redirect_prompt: "",
             agent_model: "",
+            agent_max_output_tokens: 16384,
+            agent_max_response_bytes: 1024 * 1024,
             target_url: "",
             forward_headers: &headers,
             pool: &pool,
             session_id: "test-session",
             stored_path: "/test",
+            allowed_schemes: &allowed_schemes,
+            max_redirects: 5,
+            allow_private_targets: false,
+            max_content_bytes: 100 * 1024,
+            fetch_timeout: Duration::from_secs(30),
+            cache_ttl_secs: 0,
+            extraction_mode: "text",
+            respect_robots_txt: false,
+            tool_executors: &[],
+            mcp_server: None,
+            agent_backend: AgentBackend::default(),
+            raw_mode: false,
         };
         let result = build_accept_result(&tool_use, &ctx).await;
         assert_eq!(result.tool_result["type"], "tool_result");
@@ -978,4 +1768,228 @@ mod tests {
         assert!(content.contains("only supported for WebFetch"));
         assert!(result.agent_request_id.is_none());
     }
+
+    #[tokio::test]
+    async fn test_build_accept_result_refuses_private_target() {
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let tool_use = ToolUse {
+            id: "toolu_accept3".to_string(),
+            name: "WebFetch".to_string(),
+            input: serde_json::json!({"url": "http://169.254.169.254/latest/meta-data"}),
+        };
+        let headers = reqwest::header::HeaderMap::new();
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let wf_names = default_wf_names();
+        let allowed_schemes = default_config().webfetch_allowed_schemes;
+        let ctx = FetchContext {
+            client: &client,
+            webfetch_names: &wf_names,
+            accept_prompt: "",
+            redirect_prompt: "",
+            agent_model: "",
+            agent_max_output_tokens: 16384,
+            agent_max_response_bytes: 1024 * 1024,
+            target_url: "",
+            forward_headers: &headers,
+            pool: &pool,
+            session_id: "test-session",
+            stored_path: "/test",
+            allowed_schemes: &allowed_schemes,
+            max_redirects: 5,
+            allow_private_targets: false,
+            max_content_bytes: 100 * 1024,
+            fetch_timeout: Duration::from_secs(30),
+            cache_ttl_secs: 0,
+            extraction_mode: "text",
+            respect_robots_txt: false,
+            tool_executors: &[],
+            mcp_server: None,
+            agent_backend: AgentBackend::default(),
+            raw_mode: false,
+        };
+        let result = build_accept_result(&tool_use, &ctx).await;
+        assert_eq!(result.tool_result["is_error"], true);
+        let content = result.tool_result["content"].as_str().unwrap();
+        assert!(content.starts_with("Refused to fetch"));
+        assert!(content.contains("private address 169.254.169.254"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_tool_command_executor() {
+        let client = reqwest::Client::new();
+        let headers = reqwest::header::HeaderMap::new();
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let wf_names = default_wf_names();
+        let allowed_schemes = default_config().webfetch_allowed_schemes;
+        let tool_executors = vec![
+            ToolExecutorConfig {
+                tool_name: "Echo".to_string(),
+                kind: ToolExecutorKind::Command {
+                    command: "printf '%s|' {{text}}; cat; printf '|%s' \"$TOOL_NAME\"".to_string(),
+                },
+            },
+            ToolExecutorConfig {
+                tool_name: "Broken".to_string(),
+                kind: ToolExecutorKind::Command {
+                    command: "echo oops >&2; exit 3".to_string(),
+                },
+            },
+        ];
+        let ctx = FetchContext {
+            client: &client,
+            webfetch_names: &wf_names,
+            accept_prompt: "",
+            redirect_prompt: "",
+            agent_model: "",
+            agent_max_output_tokens: 16384,
+            agent_max_response_bytes: 1024 * 1024,
+            target_url: "",
+            forward_headers: &headers,
+            pool: &pool,
+            session_id: "test-session",
+            stored_path: "/test",
+            allowed_schemes: &allowed_schemes,
+            max_redirects: 5,
+            allow_private_targets: false,
+            max_content_bytes: 100 * 1024,
+            fetch_timeout: Duration::from_secs(30),
+            cache_ttl_secs: 0,
+            extraction_mode: "text",
+            respect_robots_txt: false,
+            tool_executors: &tool_executors,
+            mcp_server: None,
+            agent_backend: AgentBackend::default(),
+            raw_mode: false,
+        };
+        let tool_use = ToolUse {
+            id: "toolu_echo".to_string(),
+            name: "Echo".to_string(),
+            input: serde_json::json!({"text": "a b; $(id)"}),
+        };
+        let result = execute_tool(&tool_use, &ctx).await;
+        assert_eq!(result.tool_result["tool_use_id"], "toolu_echo");
+        assert!(result.tool_result.get("is_error").is_none());
+        assert_eq!(
+            result.tool_result["content"],
+            r#"a b; $(id)|{"text":"a b; $(id)"}|Echo"#
+        );
+
+        let tool_use = ToolUse {
+            id: "toolu_broken".to_string(),
+            name: "Broken".to_string(),
+            input: serde_json::json!({}),
+        };
+        let result = execute_tool(&tool_use, &ctx).await;
+        assert_eq!(result.tool_result["is_error"], true);
+        let content = result.tool_result["content"].as_str().unwrap();
+        assert!(content.contains("exit status: 3"));
+        assert!(content.ends_with("oops"));
+    }
+
+    // --- whitelist tests ---
+
+    fn make_whitelist_rule(
+        domain: &str,
+        allow_subdomains: bool,
+        path_prefix: Option<&str>,
+        expires_at: Option<&str>,
+    ) -> WebfetchWhitelistRule {
+        serde_json::from_value(serde_json::json!({
+            "id": uuid::Uuid::nil(),
+            "session_id": uuid::Uuid::nil(),
+            "domain": domain,
+            "allow_subdomains": allow_subdomains,
+            "path_prefix": path_prefix,
+            "expires_at": expires_at,
+            "created_at": "",
+            "updated_at": "",
+        }))
+        .unwrap()
+    }
+
+    fn make_webfetch_tool_use(url: &str) -> ToolUse {
+        ToolUse {
+            id: "toolu_1".to_string(),
+            name: "WebFetch".to_string(),
+            input: serde_json::json!({"url": url}),
+        }
+    }
+
+    const NOW: &str = "2026-01-01 12:00:00";
+
+    #[test]
+    fn test_whitelist_rule_subdomains() {
+        let url = Url::parse("https://api.github.com/repos").unwrap();
+        assert!(matches_whitelist_rule(
+            &url,
+            &make_whitelist_rule("github.com", true, None, None),
+            NOW
+        ));
+        assert!(!matches_whitelist_rule(
+            &url,
+            &make_whitelist_rule("github.com", false, None, None),
+            NOW
+        ));
+        let evil_url = Url::parse("https://evilgithub.com/").unwrap();
+        assert!(!matches_whitelist_rule(
+            &evil_url,
+            &make_whitelist_rule("github.com", true, None, None),
+            NOW
+        ));
+    }
+
+    #[test]
+    fn test_whitelist_rule_path_prefix_and_expiry() {
+        let url = Url::parse("https://docs.rs/serde/latest").unwrap();
+        assert!(matches_whitelist_rule(
+            &url,
+            &make_whitelist_rule("docs.rs", false, Some("/serde"), None),
+            NOW
+        ));
+        assert!(!matches_whitelist_rule(
+            &url,
+            &make_whitelist_rule("docs.rs", false, Some("/tokio"), None),
+            NOW
+        ));
+        assert!(matches_whitelist_rule(
+            &url,
+            &make_whitelist_rule("docs.rs", false, None, Some("2026-01-01 12:00:01")),
+            NOW
+        ));
+        assert!(!matches_whitelist_rule(
+            &url,
+            &make_whitelist_rule("docs.rs", false, None, Some(NOW)),
+            NOW
+        ));
+    }
+
+    #[test]
+    fn test_is_all_whitelisted_requires_every_tool() {
+        let rules = vec![make_whitelist_rule("docs.rs", true, None, None)];
+        let tool_uses = vec![
+            make_webfetch_tool_use("https://docs.rs/serde"),
+            make_webfetch_tool_use("https://example.com/"),
+        ];
+        assert!(is_all_whitelisted(
+            &tool_uses[..1],
+            &rules,
+            &default_wf_names(),
+            NOW
+        ));
+        assert!(!is_all_whitelisted(
+            &tool_uses,
+            &rules,
+            &default_wf_names(),
+            NOW
+        ));
+        assert!(!is_all_whitelisted(
+            &tool_uses[..1],
+            &[],
+            &default_wf_names(),
+            NOW
+        ));
+    }
 }
@@ -0,0 +1,383 @@
+use html2text::render::{TaggedLine, TextDecorator};
+use html2text::{config, Element, Handle, RcDom};
+use regex::Regex;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::LazyLock;
+
+/// Wrap width for plain and readability text.
+const TEXT_WIDTH: usize = 120;
+
+/// Width for markdown output, wide enough that paragraphs and links are not wrapped.
+const MARKDOWN_WIDTH: usize = 10_000;
+
+/// Elements that never hold the main content of a page.
+const BOILERPLATE_TAGS: &[&str] = &[
+    "nav", "aside", "script", "style", "noscript", "form", "iframe", "svg", "button", "template",
+    "dialog", "select",
+];
+
+/// Page chrome that is removed unless it sits inside the article itself,
+/// where it usually holds the title or byline.
+const CHROME_TAGS: &[&str] = &["header", "footer"];
+
+const BOILERPLATE_ROLES: &[&str] = &[
+    "navigation",
+    "banner",
+    "contentinfo",
+    "complementary",
+    "search",
+    "dialog",
+];
+
+/// Class or id hints for navigation, sidebars, comment threads and the like.
+static BOILERPLATE_HINT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)nav|menu|footer|sidebar|comment|cookie|consent|banner|share|social|breadcrumb|related|promo|advert|newsletter|subscribe|popup|modal|skip-link",
+    )
+    .unwrap()
+});
+
+/// Class or id hints for the main content; they keep an element that also
+/// matches a boilerplate hint (e.g. `article-footer-notes` is kept).
+static CONTENT_HINT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)article|content|main|post|entry|story").unwrap());
+
+/// Convert a fetched page into text using the session's extraction mode
+/// (see `common::extraction::EXTRACTION_MODES`). Falls back to the raw bytes
+/// as text if the page cannot be rendered.
+pub(super) fn extract_page_text(bytes: &[u8], extraction_mode: &str) -> String {
+    let text = match extraction_mode {
+        "readability" => extract_main_content(bytes),
+        "markdown" => convert_to_markdown(bytes),
+        _ => html2text::from_read(bytes, TEXT_WIDTH),
+    };
+    text.unwrap_or_else(|_| String::from_utf8_lossy(bytes).to_string())
+}
+
+/// Render only the main content of a page: boilerplate elements are dropped
+/// and the body is re-rooted at the article, or at the element holding the
+/// most paragraphs.
+fn extract_main_content(bytes: &[u8]) -> Result<String, html2text::Error> {
+    let text_config = config::plain();
+    let dom = text_config.parse_html(bytes)?;
+    strip_boilerplate(&dom.document, false);
+    if let (Some(body), Some(main_content)) = (
+        find_first_element(&dom.document, "body"),
+        find_main_content(&dom),
+    ) {
+        if !Rc::ptr_eq(&body, &main_content) {
+            *body.children.borrow_mut() = vec![main_content];
+        }
+    }
+    let render_tree = text_config.dom_to_render_tree(&dom)?;
+    text_config.render_to_string(render_tree, TEXT_WIDTH)
+}
+
+/// Render a page as markdown: inline links, emphasis, code spans, headings and lists.
+fn convert_to_markdown(bytes: &[u8]) -> Result<String, html2text::Error> {
+    config::with_decorator(MarkdownDecorator::default())
+        .no_link_wrapping()
+        .string_from_read(bytes, MARKDOWN_WIDTH)
+}
+
+/// The local name of an element node, or `None` for other nodes.
+fn get_element_name(node: &Handle) -> Option<&str> {
+    match &node.data {
+        Element { name, .. } => Some(&*name.local),
+        _ => None,
+    }
+}
+
+fn get_attribute(node: &Handle, attribute_name: &str) -> Option<String> {
+    match &node.data {
+        Element { attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|attribute| &*attribute.name.local == attribute_name)
+            .map(|attribute| attribute.value.to_string()),
+        _ => None,
+    }
+}
+
+/// The element's class and id joined, for matching against hint patterns.
+fn get_class_and_id(node: &Handle) -> String {
+    format!(
+        "{} {}",
+        get_attribute(node, "class").unwrap_or_default(),
+        get_attribute(node, "id").unwrap_or_default()
+    )
+}
+
+fn is_content_container(node: &Handle) -> bool {
+    matches!(get_element_name(node), Some("article" | "main"))
+        || get_attribute(node, "role").as_deref() == Some("main")
+}
+
+/// Whether an element is navigation, page chrome or another non-content block.
+fn is_boilerplate(node: &Handle, in_content: bool) -> bool {
+    let Some(element_name) = get_element_name(node) else {
+        return false;
+    };
+    if matches!(element_name, "html" | "body") {
+        return false;
+    }
+    if BOILERPLATE_TAGS.contains(&element_name)
+        || (!in_content && CHROME_TAGS.contains(&element_name))
+    {
+        return true;
+    }
+    if get_attribute(node, "role").is_some_and(|role| BOILERPLATE_ROLES.contains(&role.as_str()))
+        || get_attribute(node, "hidden").is_some()
+        || get_attribute(node, "aria-hidden").as_deref() == Some("true")
+    {
+        return true;
+    }
+    if is_content_container(node) {
+        return false;
+    }
+    let class_and_id = get_class_and_id(node);
+    BOILERPLATE_HINT_REGEX.is_match(&class_and_id) && !CONTENT_HINT_REGEX.is_match(&class_and_id)
+}
+
+/// Remove boilerplate elements from the subtree under `node`.
+fn strip_boilerplate(node: &Handle, in_content: bool) {
+    let children = {
+        let mut children = node.children.borrow_mut();
+        children.retain(|child| !is_boilerplate(child, in_content));
+        children.clone()
+    };
+    for child in &children {
+        strip_boilerplate(child, in_content || is_content_container(child));
+    }
+}
+
+fn find_first_element(node: &Handle, element_name: &str) -> Option<Handle> {
+    if get_element_name(node) == Some(element_name) {
+        return Some(node.clone());
+    }
+    node.children
+        .borrow()
+        .iter()
+        .find_map(|child| find_first_element(child, element_name))
+}
+
+fn collect_elements(node: &Handle, elements: &mut Vec<Handle>) {
+    if get_element_name(node).is_some() {
+        elements.push(node.clone());
+    }
+    for child in node.children.borrow().iter() {
+        collect_elements(child, elements);
+    }
+}
+
+fn count_paragraphs(node: &Handle) -> usize {
+    let own = usize::from(matches!(get_element_name(node), Some("p" | "pre")));
+    own + node
+        .children
+        .borrow()
+        .iter()
+        .map(count_paragraphs)
+        .sum::<usize>()
+}
+
+/// Pick the element holding the page's main content: the `article`/`main`
+/// element with the most paragraphs if there is one, otherwise the element
+/// whose children and grandchildren include the most paragraphs.
+fn find_main_content(dom: &RcDom) -> Option<Handle> {
+    let mut elements = Vec::new();
+    collect_elements(&dom.document, &mut elements);
+
+    let container = elements
+        .iter()
+        .filter(|element| is_content_container(element))
+        .map(|element| (count_paragraphs(element), element))
+        .filter(|(paragraph_count, _)| *paragraph_count > 0)
+        .max_by_key(|(paragraph_count, _)| *paragraph_count)
+        .map(|(_, element)| element.clone());
+    if container.is_some() {
+        return container;
+    }
+
+    // Score the parents and grandparents of paragraphs; ties go to the first one seen.
+    let mut candidates: Vec<(Handle, f64)> = Vec::new();
+    let mut candidate_indexes: HashMap<*const (), usize> = HashMap::new();
+    for paragraph in elements
+        .iter()
+        .filter(|element| matches!(get_element_name(element), Some("p" | "pre")))
+    {
+        let parent = paragraph.get_parent();
+        let grandparent = parent.as_ref().and_then(|parent| parent.get_parent());
+        for (ancestor, weight) in [(parent, 1.0), (grandparent, 0.5)] {
+            let Some(ancestor) = ancestor else { continue };
+            if get_element_name(&ancestor).is_none() {
+                continue;
+            }
+            let index = *candidate_indexes
+                .entry(Rc::as_ptr(&ancestor) as *const ())
+                .or_insert_with(|| {
+                    candidates.push((ancestor.clone(), 0.0));
+                    candidates.len() - 1
+                });
+            candidates[index].1 += weight;
+        }
+    }
+    candidates
+        .into_iter()
+        .rev()
+        .max_by(|(_, left_score), (_, right_score)| left_score.total_cmp(right_score))
+        .map(|(candidate, _)| candidate)
+}
+
+/// A decorator that renders inline markup as markdown.
+#[derive(Clone, Debug, Default)]
+struct MarkdownDecorator {
+    /// URLs of the links currently open, closed by `decorate_link_end`.
+    link_urls: Vec<String>,
+}
+
+impl TextDecorator for MarkdownDecorator {
+    type Annotation = ();
+
+    fn decorate_link_start(&mut self, url: &str) -> (String, Self::Annotation) {
+        self.link_urls.push(url.to_string());
+        ("[".to_string(), ())
+    }
+
+    fn decorate_link_end(&mut self) -> String {
+        match self.link_urls.pop() {
+            Some(url) => format!("]({})", url),
+            None => "]".to_string(),
+        }
+    }
+
+    fn decorate_em_start(&self) -> (String, Self::Annotation) {
+        ("*".to_string(), ())
+    }
+
+    fn decorate_em_end(&self) -> String {
+        "*".to_string()
+    }
+
+    fn decorate_strong_start(&self) -> (String, Self::Annotation) {
+        ("**".to_string(), ())
+    }
+
+    fn decorate_strong_end(&self) -> String {
+        "**".to_string()
+    }
+
+    fn decorate_strikeout_start(&self) -> (String, Self::Annotation) {
+        ("~~".to_string(), ())
+    }
+
+    fn decorate_strikeout_end(&self) -> String {
+        "~~".to_string()
+    }
+
+    fn decorate_code_start(&self) -> (String, Self::Annotation) {
+        ("`".to_string(), ())
+    }
+
+    fn decorate_code_end(&self) -> String {
+        "`".to_string()
+    }
+
+    fn decorate_preformat_first(&self) -> Self::Annotation {}
+
+    fn decorate_preformat_cont(&self) -> Self::Annotation {}
+
+    fn decorate_image(&mut self, src: &str, title: &str) -> (String, Self::Annotation) {
+        (format!("![{}]({})", title, src), ())
+    }
+
+    fn header_prefix(&self, level: usize) -> String {
+        "#".repeat(level) + " "
+    }
+
+    fn quote_prefix(&self) -> String {
+        "> ".to_string()
+    }
+
+    fn unordered_item_prefix(&self) -> String {
+        "- ".to_string()
+    }
+
+    fn ordered_item_prefix(&self, i: i64) -> String {
+        format!("{}. ", i)
+    }
+
+    fn make_subblock_decorator(&self) -> Self {
+        Self::default()
+    }
+
+    fn finalise(&mut self, _urls: Vec<String>) -> Vec<TaggedLine<Self::Annotation>> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE_PAGE: &str = r#"<html><body>
+        <nav><a href="/">Home</a> <a href="/pricing">Pricing menu</a></nav>
+        <header><h1>Site banner</h1></header>
+        <article>
+            <h1>Release notes</h1>
+            <p>The proxy now extracts the <a href="https://example.com/docs">main content</a>.</p>
+            <p>It drops <strong>navigation</strong> and footers.</p>
+        </article>
+        <div class="sidebar">Related posts</div>
+        <footer>Copyright notice</footer>
+    </body></html>"#;
+
+    #[test]
+    fn readability_keeps_article_only() {
+        let text = extract_page_text(ARTICLE_PAGE.as_bytes(), "readability");
+        assert!(text.contains("Release notes"));
+        assert!(text.contains("main content"));
+        for boilerplate in [
+            "Pricing menu",
+            "Site banner",
+            "Related posts",
+            "Copyright notice",
+        ] {
+            assert!(
+                !text.contains(boilerplate),
+                "{} should be dropped",
+                boilerplate
+            );
+        }
+    }
+
+    #[test]
+    fn readability_scores_paragraph_parents() {
+        let page = r#"<html><body>
+            <div><span>Top links</span></div>
+            <div id="body-text"><p>First paragraph.</p><p>Second paragraph.</p></div>
+            <div><p>Stray line.</p></div>
+        </body></html>"#;
+        let text = extract_page_text(page.as_bytes(), "readability");
+        assert!(text.contains("First paragraph."));
+        assert!(text.contains("Second paragraph."));
+        assert!(!text.contains("Top links"));
+        assert!(!text.contains("Stray line."));
+    }
+
+    #[test]
+    fn markdown_renders_links_headings_and_emphasis() {
+        let text = extract_page_text(ARTICLE_PAGE.as_bytes(), "markdown");
+        assert!(text.contains("# Release notes"));
+        assert!(text.contains("[main content](https://example.com/docs)"));
+        assert!(text.contains("**navigation**"));
+    }
+
+    #[test]
+    fn text_mode_keeps_whole_page() {
+        let text = extract_page_text(ARTICLE_PAGE.as_bytes(), "text");
+        assert!(text.contains("Pricing menu"));
+        assert!(text.contains("Release notes"));
+        assert!(text.contains("Copyright notice"));
+    }
+}
@@ -0,0 +1,302 @@
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use common::models::WebfetchPolicy;
+use common::policy::find_action_by_key;
+
+use super::approval::{combine_tool_decisions, ApprovalDecision};
+use super::extract::ToolUse;
+
+/// The outcome of a session's policies for one interception round.
+pub(super) enum PolicyOutcome {
+    /// Some call matched no policy: the whitelist and the user decide.
+    Undecided,
+    /// A policy asked for the user's decision, whatever the whitelist says.
+    Ask { reason: String },
+    /// Policies decided every call.
+    Decided {
+        decision: ApprovalDecision,
+        reason: String,
+    },
+}
+
+/// Parse working hours written as "HH:MM-HH:MM". The end may be earlier than
+/// the start for hours that span midnight.
+pub fn parse_working_hours(value: &str) -> Result<(NaiveTime, NaiveTime), String> {
+    let (start_str, end_str) = value
+        .split_once('-')
+        .ok_or_else(|| format!("{:?} is not of the form HH:MM-HH:MM", value))?;
+    let parse_time = |time_str: &str| {
+        NaiveTime::parse_from_str(time_str.trim(), "%H:%M")
+            .map_err(|_| format!("{:?} is not a time of the form HH:MM", time_str.trim()))
+    };
+    let (start, end) = (parse_time(start_str)?, parse_time(end_str)?);
+    if start == end {
+        return Err("working hours must not start and end at the same time".to_string());
+    }
+    Ok((start, end))
+}
+
+/// Check and normalize a policy condition's value: working hours for
+/// "outside_hours", a positive count for "repeat_approvals", and none for "always".
+pub fn parse_policy_value(condition: &str, value: &str) -> Result<Option<String>, String> {
+    let value = value.trim();
+    match condition {
+        "always" => Ok(None),
+        "outside_hours" => {
+            let (start, end) = parse_working_hours(value)?;
+            Ok(Some(format!(
+                "{}-{}",
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            )))
+        }
+        "repeat_approvals" => match value.parse::<u32>() {
+            Ok(count) if count > 0 => Ok(Some(count.to_string())),
+            _ => Err(format!("{:?} is not a positive number of approvals", value)),
+        },
+        _ => Err(format!("unknown condition {:?}", condition)),
+    }
+}
+
+/// Whether `now` falls outside working hours, Monday to Friday (UTC).
+fn is_outside_working_hours(now: DateTime<Utc>, start: NaiveTime, end: NaiveTime) -> bool {
+    if matches!(now.weekday(), Weekday::Sat | Weekday::Sun) {
+        return true;
+    }
+    let time = now.time();
+    let is_working = if start < end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    };
+    !is_working
+}
+
+/// Whether a policy's condition holds for a tool call.
+async fn matches_policy(
+    policy: &WebfetchPolicy,
+    tool_use: &ToolUse,
+    pool: &sqlx::SqlitePool,
+    session_id: &str,
+    now: DateTime<Utc>,
+) -> bool {
+    if policy
+        .tool_name
+        .as_deref()
+        .is_some_and(|tool_name| tool_name != tool_use.name)
+    {
+        return false;
+    }
+    let value = policy.condition_value.as_deref().unwrap_or("");
+    match policy.condition.as_str() {
+        "always" => true,
+        "outside_hours" => parse_working_hours(value)
+            .is_ok_and(|(start, end)| is_outside_working_hours(now, start, end)),
+        "repeat_approvals" => {
+            let Ok(min_count) = value.parse::<i64>() else {
+                return false;
+            };
+            let input_json = tool_use.input.to_string();
+            match db::get_webfetch_approval_count(pool, session_id, &tool_use.name, &input_json)
+                .await
+            {
+                Ok(count) => count >= min_count,
+                Err(e) => {
+                    log::warn!("WebFetch policies: failed to read approval count: {}", e);
+                    false
+                }
+            }
+        }
+        _ => false,
+    }
+}
+
+/// A policy's number in the list and its rule, e.g.
+/// "policy 2 (WebFetch, outside_hours 09:00-17:00: Fail)".
+fn describe_policy(number: usize, policy: &WebfetchPolicy) -> String {
+    let action_label = find_action_by_key(&policy.action).map_or("?", |action| action.label);
+    format!(
+        "policy {} ({}, {}{}: {})",
+        number,
+        policy.tool_name.as_deref().unwrap_or("any tool"),
+        policy.condition,
+        policy
+            .condition_value
+            .as_deref()
+            .map(|value| format!(" {}", value))
+            .unwrap_or_default(),
+        action_label
+    )
+}
+
+/// Check each call of a round against the session's policies in order; the
+/// first matching policy decides the call.
+pub(super) async fn evaluate_policies(
+    policies: &[WebfetchPolicy],
+    tool_uses: &[ToolUse],
+    pool: &sqlx::SqlitePool,
+    session_id: &str,
+    now: DateTime<Utc>,
+) -> PolicyOutcome {
+    if policies.is_empty() {
+        return PolicyOutcome::Undecided;
+    }
+    let mut tool_decisions = Vec::with_capacity(tool_uses.len());
+    let mut reasons = Vec::with_capacity(tool_uses.len());
+    let mut ask_reason = None;
+    let mut has_unmatched_call = false;
+    for tool_use in tool_uses {
+        let mut matched_policy = None;
+        for (index, policy) in policies.iter().enumerate() {
+            if matches_policy(policy, tool_use, pool, session_id, now).await {
+                matched_policy = Some((index + 1, policy));
+                break;
+            }
+        }
+        let Some((number, policy)) = matched_policy else {
+            has_unmatched_call = true;
+            continue;
+        };
+        let reason = format!("{}: {}", tool_use.name, describe_policy(number, policy));
+        let tool_decision = match policy.action.as_str() {
+            "accept" => ApprovalDecision::Accept,
+            "mock" => ApprovalDecision::Mock,
+            "fail" => ApprovalDecision::Fail,
+            _ => {
+                ask_reason.get_or_insert(reason);
+                continue;
+            }
+        };
+        tool_decisions.push(tool_decision);
+        reasons.push(reason);
+    }
+    // An "ask" wins over the rest of the round, so the whitelist cannot accept it.
+    if let Some(reason) = ask_reason {
+        return PolicyOutcome::Ask { reason };
+    }
+    if has_unmatched_call {
+        return PolicyOutcome::Undecided;
+    }
+    PolicyOutcome::Decided {
+        decision: combine_tool_decisions(tool_decisions),
+        reason: reasons.join("; "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn make_policy(
+        position: i64,
+        tool_name: Option<&str>,
+        condition: &str,
+        condition_value: Option<&str>,
+        action: &str,
+    ) -> WebfetchPolicy {
+        WebfetchPolicy {
+            id: uuid::Uuid::new_v4(),
+            session_id: uuid::Uuid::nil(),
+            position,
+            tool_name: tool_name.map(str::to_string),
+            condition: condition.to_string(),
+            condition_value: condition_value.map(str::to_string),
+            action: action.to_string(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn make_tool_use(id: &str, name: &str) -> ToolUse {
+        ToolUse {
+            id: id.to_string(),
+            name: name.to_string(),
+            input: serde_json::json!({"url": "https://example.com"}),
+        }
+    }
+
+    #[test]
+    fn working_hours_parse_and_match() {
+        assert_eq!(
+            parse_policy_value("outside_hours", " 9:00 - 17:30 "),
+            Ok(Some("09:00-17:30".to_string()))
+        );
+        assert!(parse_policy_value("outside_hours", "09:00").is_err());
+        assert!(parse_policy_value("outside_hours", "09:00-09:00").is_err());
+        assert!(parse_policy_value("repeat_approvals", "0").is_err());
+        assert_eq!(parse_policy_value("always", "ignored"), Ok(None));
+
+        let (start, end) = parse_working_hours("09:00-17:00").unwrap();
+        // 2026-10-16 is a Friday.
+        let friday_noon = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let friday_evening = Utc.with_ymd_and_hms(2026, 10, 16, 17, 0, 0).unwrap();
+        let saturday_noon = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        assert!(!is_outside_working_hours(friday_noon, start, end));
+        assert!(is_outside_working_hours(friday_evening, start, end));
+        assert!(is_outside_working_hours(saturday_noon, start, end));
+
+        let (start, end) = parse_working_hours("22:00-06:00").unwrap();
+        let friday_night = Utc.with_ymd_and_hms(2026, 10, 16, 23, 0, 0).unwrap();
+        assert!(!is_outside_working_hours(friday_night, start, end));
+        assert!(is_outside_working_hours(friday_noon, start, end));
+    }
+
+    #[tokio::test]
+    async fn first_matching_policy_decides_each_call() {
+        let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let policies = vec![
+            make_policy(1, Some("WebSearch"), "always", None, "ask"),
+            make_policy(2, Some("WebFetch"), "always", None, "accept"),
+            make_policy(3, None, "always", None, "mock"),
+        ];
+
+        let tool_uses = vec![
+            make_tool_use("toolu_1", "WebFetch"),
+            make_tool_use("toolu_2", "Bash"),
+        ];
+        let PolicyOutcome::Decided { decision, reason } =
+            evaluate_policies(&policies, &tool_uses, &pool, "session", now).await
+        else {
+            panic!("expected a decision");
+        };
+        assert_eq!(
+            decision,
+            ApprovalDecision::PerTool(vec![ApprovalDecision::Accept, ApprovalDecision::Mock])
+        );
+        assert_eq!(
+            reason,
+            "WebFetch: policy 2 (WebFetch, always: Accept); Bash: policy 3 (any tool, always: Mock)"
+        );
+
+        let tool_uses = vec![
+            make_tool_use("toolu_1", "WebFetch"),
+            make_tool_use("toolu_2", "WebSearch"),
+        ];
+        assert!(matches!(
+            evaluate_policies(&policies, &tool_uses, &pool, "session", now).await,
+            PolicyOutcome::Ask { .. }
+        ));
+
+        // A call no policy matches does not hide a later "ask".
+        let policies = vec![make_policy(1, Some("WebSearch"), "always", None, "ask")];
+        let tool_uses = vec![
+            make_tool_use("toolu_1", "Bash"),
+            make_tool_use("toolu_2", "WebSearch"),
+        ];
+        assert!(matches!(
+            evaluate_policies(&policies, &tool_uses, &pool, "session", now).await,
+            PolicyOutcome::Ask { .. }
+        ));
+
+        let policies = vec![make_policy(1, Some("WebFetch"), "always", None, "fail")];
+        let tool_uses = vec![
+            make_tool_use("toolu_1", "WebFetch"),
+            make_tool_use("toolu_2", "Bash"),
+        ];
+        assert!(matches!(
+            evaluate_policies(&policies, &tool_uses, &pool, "session", now).await,
+            PolicyOutcome::Undecided
+        ));
+    }
+}
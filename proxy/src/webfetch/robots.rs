@@ -0,0 +1,270 @@
+use regex::Regex;
+use std::time::Instant;
+use url::Url;
+
+use super::fetch::{read_capped_body, FetchContext};
+use super::guard::select_fetch_client;
+
+/// Product token matched against robots.txt `User-agent` lines, falling back to `*`.
+const ROBOTS_USER_AGENT: &str = "Claude-User";
+
+/// robots.txt bytes parsed; RFC 9309 asks crawlers to read at least 500 KiB.
+const MAX_ROBOTS_TXT_BYTES: usize = 500 * 1024;
+
+/// An `Allow` or `Disallow` line from the group that applies to us.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RobotsRule {
+    allow: bool,
+    pattern: String,
+}
+
+/// Whether a `User-agent` value names us. Values are matched case-insensitively
+/// on their product token, so "Claude-User/1.0" also matches.
+fn matches_user_agent(value: &str, user_agent: &str) -> bool {
+    let product_token = value.split(['/', ' ']).next().unwrap_or("");
+    product_token.eq_ignore_ascii_case(user_agent)
+}
+
+/// Collect the rules of every group addressed to `user_agent`, or of the `*`
+/// groups when none is.
+fn parse_robots_rules(robots_txt: &str, user_agent: &str) -> Vec<RobotsRule> {
+    let mut named_rules = Vec::new();
+    let mut wildcard_rules = Vec::new();
+    let mut has_named_group = false;
+    // User agents of the group being read, and whether its rules have started.
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" => {
+                if in_rules {
+                    group_agents.clear();
+                    in_rules = false;
+                }
+                group_agents.push(value.to_string());
+            }
+            key @ ("allow" | "disallow") => {
+                in_rules = true;
+                let is_named = group_agents
+                    .iter()
+                    .any(|agent| matches_user_agent(agent, user_agent));
+                has_named_group |= is_named;
+                // An empty Disallow allows everything, so it adds no rule.
+                if value.is_empty() {
+                    continue;
+                }
+                let rule = RobotsRule {
+                    allow: key == "allow",
+                    pattern: value.to_string(),
+                };
+                if is_named {
+                    named_rules.push(rule);
+                } else if group_agents.iter().any(|agent| agent == "*") {
+                    wildcard_rules.push(rule);
+                }
+            }
+            _ => {}
+        }
+    }
+    if has_named_group {
+        named_rules
+    } else {
+        wildcard_rules
+    }
+}
+
+/// Match a rule pattern against a path: `*` matches any run of characters and
+/// a trailing `$` anchors the end; otherwise the pattern is a prefix.
+fn matches_robots_pattern(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut regex_str = format!("^{}", regex::escape(pattern).replace(r"\*", ".*"));
+    if anchored {
+        regex_str.push('$');
+    }
+    Regex::new(&regex_str).is_ok_and(|regex| regex.is_match(path))
+}
+
+/// Find the `Disallow` rule that blocks `path`, if any. The longest matching
+/// pattern wins and `Allow` wins a tie.
+fn find_blocking_rule<'a>(rules: &'a [RobotsRule], path: &str) -> Option<&'a RobotsRule> {
+    rules
+        .iter()
+        .filter(|rule| matches_robots_pattern(&rule.pattern, path))
+        .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+        .filter(|rule| !rule.allow)
+}
+
+/// The origin a URL's robots.txt is served from, e.g. "https://example.com:8443".
+fn build_robots_origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+/// Fetch robots.txt from `origin`, following same-host redirects. A missing
+/// file (any 4xx) or one behind a cross-host redirect allows everything and is
+/// returned as an empty string; a server error or failed request is an error,
+/// since the site's rules are then unknown.
+async fn fetch_robots_txt(
+    origin: &str,
+    deadline: Instant,
+    ctx: &FetchContext<'_>,
+) -> Result<String, String> {
+    let mut robots_url = Url::parse(&format!("{}/robots.txt", origin))
+        .map_err(|e| format!("invalid robots.txt URL: {}", e))?;
+    let original_host = robots_url.host_str().unwrap_or("").to_string();
+    let mut redirect_count = 0;
+    loop {
+        let response = select_fetch_client(ctx.client, ctx.allow_private_targets)
+            .get(robots_url.as_str())
+            .timeout(deadline.saturating_duration_since(Instant::now()))
+            .send()
+            .await
+            .map_err(|e| format!("failed to fetch {}: {}", robots_url, e))?;
+        let status = response.status();
+        if status.is_success() {
+            let body = read_capped_body(response, MAX_ROBOTS_TXT_BYTES)
+                .await
+                .map_err(|e| format!("failed to read {}: {}", robots_url, e))?;
+            return Ok(String::from_utf8_lossy(&body.bytes).to_string());
+        }
+        if status.is_client_error() {
+            return Ok(String::new());
+        }
+        if !status.is_redirection() {
+            return Err(format!("{} returned HTTP {}", robots_url, status.as_u16()));
+        }
+        let redirect_url = response
+            .headers()
+            .get("location")
+            .and_then(|header_value| header_value.to_str().ok())
+            .and_then(|location| robots_url.join(location).ok());
+        redirect_count += 1;
+        match redirect_url {
+            Some(redirect_url)
+                if redirect_url.host_str().unwrap_or("") == original_host
+                    && redirect_count <= ctx.max_redirects =>
+            {
+                robots_url = redirect_url;
+            }
+            _ => return Ok(String::new()),
+        }
+    }
+}
+
+/// The session's robots.txt for `origin`, looked up once per session and
+/// stored for later fetches. Failed lookups are not stored, so they are retried.
+async fn load_robots_txt(
+    origin: &str,
+    deadline: Instant,
+    ctx: &FetchContext<'_>,
+) -> Result<String, String> {
+    match db::get_webfetch_robots_txt(ctx.pool, ctx.session_id, origin).await {
+        Ok(Some(robots_txt)) => return Ok(robots_txt),
+        Ok(None) => {}
+        Err(e) => log::warn!("WebFetch accept: failed to read robots.txt cache: {}", e),
+    }
+    let robots_txt = fetch_robots_txt(origin, deadline, ctx).await?;
+    if let Err(e) =
+        db::upsert_webfetch_robots_txt(ctx.pool, ctx.session_id, origin, &robots_txt).await
+    {
+        log::warn!("WebFetch accept: failed to store robots.txt: {}", e);
+    }
+    Ok(robots_txt)
+}
+
+/// Check a URL against its site's robots.txt. Returns an explanation for the
+/// tool_result when the site disallows it or its robots.txt cannot be read.
+pub(super) async fn check_robots_txt(
+    url: &Url,
+    deadline: Instant,
+    ctx: &FetchContext<'_>,
+) -> Result<(), String> {
+    let origin = build_robots_origin(url);
+    let robots_txt = load_robots_txt(&origin, deadline, ctx)
+        .await
+        .map_err(|reason| {
+            format!(
+                "Could not check robots.txt before fetching '{}': {}. \
+                 The proxy only fetches pages the site's robots.txt allows.",
+                url, reason
+            )
+        })?;
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    let rules = parse_robots_rules(&robots_txt, ROBOTS_USER_AGENT);
+    match find_blocking_rule(&rules, &path) {
+        Some(rule) => Err(format!(
+            "Refused to fetch '{}': {}/robots.txt disallows it for {} (rule \"Disallow: {}\"). \
+             Do not retry this URL; use another source instead.",
+            url, origin, ROBOTS_USER_AGENT, rule.pattern
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_allowed(robots_txt: &str, path: &str) -> bool {
+        find_blocking_rule(&parse_robots_rules(robots_txt, ROBOTS_USER_AGENT), path).is_none()
+    }
+
+    #[test]
+    fn wildcard_group_applies_without_named_group() {
+        let robots_txt = "User-agent: *\nDisallow: /private/\n\nUser-agent: OtherBot\nDisallow: /";
+        assert!(!is_allowed(robots_txt, "/private/notes"));
+        assert!(is_allowed(robots_txt, "/docs/intro"));
+        assert!(is_allowed("", "/anything"));
+        assert!(is_allowed("User-agent: *\nDisallow:", "/anything"));
+    }
+
+    #[test]
+    fn named_group_replaces_wildcard_group() {
+        let robots_txt = "\
+            User-agent: *\n\
+            Disallow: /\n\
+            \n\
+            # Assistants may read the docs\n\
+            User-agent: GPTBot\n\
+            User-agent: claude-user/1.0\n\
+            Disallow: /admin\n";
+        assert!(is_allowed(robots_txt, "/docs"));
+        assert!(!is_allowed(robots_txt, "/admin/users"));
+    }
+
+    #[test]
+    fn longest_match_wins_and_allow_wins_ties() {
+        let robots_txt =
+            "User-agent: *\nDisallow: /docs/\nAllow: /docs/public/\nAllow: /page\nDisallow: /page";
+        assert!(!is_allowed(robots_txt, "/docs/secret"));
+        assert!(is_allowed(robots_txt, "/docs/public/readme"));
+        assert!(is_allowed(robots_txt, "/page"));
+    }
+
+    #[test]
+    fn wildcards_and_end_anchor() {
+        let robots_txt = "User-agent: *\nDisallow: /*.pdf$\nDisallow: /search?*q=";
+        assert!(!is_allowed(robots_txt, "/files/report.pdf"));
+        assert!(is_allowed(robots_txt, "/files/report.pdf.html"));
+        assert!(!is_allowed(robots_txt, "/search?lang=en&q=rust"));
+        assert!(is_allowed(robots_txt, "/search"));
+    }
+
+    #[test]
+    fn origin_includes_non_default_port() {
+        let url = Url::parse("https://Example.com:8443/a/b?c=d").unwrap();
+        assert_eq!(build_robots_origin(&url), "https://example.com:8443");
+        let url = Url::parse("http://example.com:80/").unwrap();
+        assert_eq!(build_robots_origin(&url), "http://example.com");
+    }
+}
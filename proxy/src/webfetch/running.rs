@@ -0,0 +1,122 @@
+//! Interceptions whose rounds are in progress, listed on the Tool Intercept
+//! page so one stuck on a slow upstream can be cancelled.
+
+use common::models::RunningInterceptInfo;
+use std::collections::HashMap;
+use std::future::pending;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+use super::approval::ApprovalQueue;
+
+struct RunningIntercept {
+    session_id: String,
+    /// The round in progress, counted from 1.
+    round: usize,
+    started_at: Instant,
+    cancel_sender: watch::Sender<bool>,
+}
+
+/// Running interceptions, by the id of the intercepted request.
+#[derive(Clone, Default)]
+pub struct RunningIntercepts {
+    running: Arc<Mutex<HashMap<String, RunningIntercept>>>,
+}
+
+/// Held by a running interception, which is taken off the list when it is
+/// dropped.
+pub(super) struct RunningInterceptGuard {
+    running_intercepts: RunningIntercepts,
+    request_id: String,
+    cancel_receiver: watch::Receiver<bool>,
+}
+
+impl RunningInterceptGuard {
+    pub(super) fn set_round(&self, round_idx: usize) {
+        let mut running = self.running_intercepts.running.lock().unwrap();
+        if let Some(running_intercept) = running.get_mut(&self.request_id) {
+            running_intercept.round = round_idx + 1;
+        }
+    }
+
+    /// Resolves once the interception is cancelled from the dashboard.
+    pub(super) async fn cancelled(&mut self) {
+        if self
+            .cancel_receiver
+            .wait_for(|cancelled| *cancelled)
+            .await
+            .is_err()
+        {
+            pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for RunningInterceptGuard {
+    fn drop(&mut self) {
+        self.running_intercepts
+            .running
+            .lock()
+            .unwrap()
+            .remove(&self.request_id);
+    }
+}
+
+/// List an interception of `request_id` as running until the returned guard
+/// is dropped.
+pub(super) fn start_running_intercept(
+    queue: &ApprovalQueue,
+    session_id: &str,
+    request_id: &str,
+) -> RunningInterceptGuard {
+    let (cancel_sender, cancel_receiver) = watch::channel(false);
+    queue.running_intercepts.running.lock().unwrap().insert(
+        request_id.to_string(),
+        RunningIntercept {
+            session_id: session_id.to_string(),
+            round: 1,
+            started_at: Instant::now(),
+            cancel_sender,
+        },
+    );
+    RunningInterceptGuard {
+        running_intercepts: queue.running_intercepts.clone(),
+        request_id: request_id.to_string(),
+        cancel_receiver,
+    }
+}
+
+/// The session's running interceptions, oldest first.
+pub fn list_running_intercepts(
+    queue: &ApprovalQueue,
+    session_id: &str,
+) -> Vec<RunningInterceptInfo> {
+    let running = queue.running_intercepts.running.lock().unwrap();
+    let mut running_intercepts: Vec<_> = running
+        .iter()
+        .filter(|(_, running_intercept)| running_intercept.session_id == session_id)
+        .collect();
+    running_intercepts.sort_by_key(|(_, running_intercept)| running_intercept.started_at);
+    running_intercepts
+        .into_iter()
+        .map(|(request_id, running_intercept)| RunningInterceptInfo {
+            request_id: request_id.clone(),
+            round: running_intercept.round,
+            running_secs: running_intercept.started_at.elapsed().as_secs(),
+        })
+        .collect()
+}
+
+/// Cancel a running interception of the session. Returns false when it
+/// isn't running, e.g. because it already finished.
+pub fn cancel_running_intercept(queue: &ApprovalQueue, session_id: &str, request_id: &str) -> bool {
+    let running = queue.running_intercepts.running.lock().unwrap();
+    match running.get(request_id) {
+        Some(running_intercept) if running_intercept.session_id == session_id => {
+            running_intercept.cancel_sender.send_replace(true);
+            true
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,206 @@
+//! A local stand-in for the Anthropic API: it answers each request with the
+//! next scripted response and keeps the request bodies it was sent.
+
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A response the mock upstream sends for one request.
+pub struct ScriptedResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+    /// Break the connection after the headers instead of sending `body`.
+    pub broken: bool,
+    /// How long to wait before answering, to stand in for a hung upstream.
+    pub delay: Duration,
+}
+
+impl ScriptedResponse {
+    /// A streamed Messages API response made of `(event, data)` pairs.
+    pub fn sse(events: &[(&str, Value)]) -> Self {
+        let body = events
+            .iter()
+            .map(|(event_type, data)| format!("event: {}\ndata: {}\n\n", event_type, data))
+            .collect();
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            body,
+            broken: false,
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn json(status: u16, body: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+            broken: false,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// A response whose body can't be read: the connection breaks after
+    /// the headers.
+    pub fn broken() -> Self {
+        Self {
+            status: 200,
+            content_type: "text/event-stream",
+            body: String::new(),
+            broken: true,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// This response, sent after `delay`.
+    pub fn delayed(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+}
+
+/// A received request's path and JSON body.
+pub struct ReceivedRequest {
+    pub path: String,
+    pub body: Value,
+}
+
+struct MockState {
+    responses: VecDeque<ScriptedResponse>,
+    received_requests: Vec<ReceivedRequest>,
+}
+
+pub struct MockUpstream {
+    /// Base URL to use as a session's target URL.
+    pub url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockUpstream {
+    /// Serve `responses` in order on a free local port; requests after the
+    /// last one get a 500.
+    pub fn start(responses: Vec<ScriptedResponse>) -> Self {
+        let state = Arc::new(Mutex::new(MockState {
+            responses: responses.into(),
+            received_requests: vec![],
+        }));
+        let app_state = web::Data::from(state.clone());
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .default_service(web::to(answer_scripted_request))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let url = format!("http://{}", server.addrs()[0]);
+        actix_web::rt::spawn(server.run());
+        Self { url, state }
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn take_received_requests(&self) -> Vec<ReceivedRequest> {
+        std::mem::take(&mut self.state.lock().unwrap().received_requests)
+    }
+}
+
+async fn answer_scripted_request(
+    req: HttpRequest,
+    body: web::Bytes,
+    state: web::Data<Mutex<MockState>>,
+) -> HttpResponse {
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.received_requests.push(ReceivedRequest {
+            path: req.path().to_string(),
+            body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+        });
+        state.responses.pop_front()
+    };
+    if let Some(response) = &response {
+        actix_web::rt::time::sleep(response.delay).await;
+    }
+    match response {
+        Some(response) if response.broken => HttpResponse::Ok()
+            .content_type(response.content_type)
+            .streaming(futures::stream::once(async {
+                Err::<web::Bytes, _>(actix_web::error::ErrorInternalServerError("broken"))
+            })),
+        Some(response) => {
+            HttpResponse::build(actix_web::http::StatusCode::from_u16(response.status).unwrap())
+                .content_type(response.content_type)
+                .body(response.body)
+        }
+        None => {
+            HttpResponse::InternalServerError().body("mock upstream: no scripted response left")
+        }
+    }
+}
+
+/// A streamed response holding one text block.
+pub fn build_text_response(text: &str) -> ScriptedResponse {
+    ScriptedResponse::sse(&[
+        ("message_start", build_message_start_data()),
+        (
+            "content_block_start",
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        ),
+        (
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": text}}),
+        ),
+        (
+            "content_block_stop",
+            json!({"type": "content_block_stop", "index": 0}),
+        ),
+        ("message_delta", build_message_delta_data("end_turn")),
+        ("message_stop", json!({"type": "message_stop"})),
+    ])
+}
+
+/// A streamed response holding one tool call.
+pub fn build_tool_use_response(tool_use_id: &str, name: &str, input: &Value) -> ScriptedResponse {
+    ScriptedResponse::sse(&[
+        ("message_start", build_message_start_data()),
+        (
+            "content_block_start",
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "tool_use", "id": tool_use_id, "name": name, "input": {}}}),
+        ),
+        (
+            "content_block_delta",
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "input_json_delta", "partial_json": input.to_string()}}),
+        ),
+        (
+            "content_block_stop",
+            json!({"type": "content_block_stop", "index": 0}),
+        ),
+        ("message_delta", build_message_delta_data("tool_use")),
+        ("message_stop", json!({"type": "message_stop"})),
+    ])
+}
+
+fn build_message_start_data() -> Value {
+    json!({
+        "type": "message_start",
+        "message": {
+            "id": "msg_mock",
+            "type": "message",
+            "role": "assistant",
+            "content": [],
+            "model": "claude-sonnet-4-5",
+            "stop_reason": null,
+            "usage": {"input_tokens": 10, "output_tokens": 1}
+        }
+    })
+}
+
+fn build_message_delta_data(stop_reason: &str) -> Value {
+    json!({
+        "type": "message_delta",
+        "delta": {"stop_reason": stop_reason},
+        "usage": {"output_tokens": 5}
+    })
+}
@@ -0,0 +1,364 @@
+//! End-to-end runs of `proxy_handler` against a mock upstream.
+
+mod mock_upstream;
+
+use actix_web::{test, web, App};
+use common::config::{AppConfig, SharedConfig, WEBFETCH_FOLLOWUP_FALLBACK_ERROR};
+use common::models::{ProxyRequest, REQUEST_ERROR_STATUS, REQUEST_ERROR_WEBFETCH_FOLLOWUP};
+use db::test_util::{build_session_params, open_temp_pool};
+use db::{SessionParams, WebfetchPolicyParams};
+use mock_upstream::{build_text_response, build_tool_use_response, MockUpstream, ScriptedResponse};
+use proxy::hooks::HookRegistry;
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+async fn create_test_session(
+    pool: &SqlitePool,
+    target_url: &str,
+    profile_id: Option<&str>,
+) -> String {
+    let session_id = Uuid::new_v4().to_string();
+    let session_params = SessionParams {
+        target_url,
+        profile_id,
+        ..build_session_params(&session_id, "e2e")
+    };
+    db::create_session(pool, &session_params).await.unwrap();
+    session_id
+}
+
+fn build_messages_body(system: Value, tools: Value) -> Value {
+    json!({
+        "model": "claude-sonnet-4-5",
+        "max_tokens": 1024,
+        "stream": true,
+        "system": system,
+        "tools": tools,
+        "messages": [{"role": "user", "content": "Summarize https://example.com"}]
+    })
+}
+
+/// Send `body` to `/_proxy/{session_id}/v1/messages` through an app wired
+/// like the server's, returning the status and response body.
+async fn send_proxy_request(pool: &SqlitePool, session_id: &str, body: &Value) -> (u16, String) {
+    send_proxy_request_with_config(pool, session_id, body, AppConfig::default()).await
+}
+
+async fn send_proxy_request_with_config(
+    pool: &SqlitePool,
+    session_id: &str,
+    body: &Value,
+    app_config: AppConfig,
+) -> (u16, String) {
+    let client = reqwest::Client::new();
+    let hook_registry = HookRegistry::with_builtin_hooks(
+        &web::Data::new(proxy::webfetch::new_approval_queue()),
+        &web::Data::new(proxy::breakpoint::new_breakpoint_queue()),
+        &client,
+    );
+    let config: SharedConfig = Arc::new(RwLock::new(app_config));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(client))
+            .app_data(web::Data::new(config))
+            .app_data(web::Data::new(hook_registry))
+            .route(
+                "/_proxy/{session_id}/{tail:.*}",
+                web::to(proxy::proxy_handler),
+            ),
+    )
+    .await;
+    let req = test::TestRequest::post()
+        .uri(&format!("/_proxy/{}/v1/messages", session_id))
+        .insert_header(("content-type", "application/json"))
+        .set_payload(body.to_string())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let status = resp.status().as_u16();
+    let resp_body = test::read_body(resp).await;
+    (status, String::from_utf8(resp_body.to_vec()).unwrap())
+}
+
+/// The session's logged top-level request, once its response is stored;
+/// streamed responses are written after the client has read them.
+async fn wait_for_logged_request(pool: &SqlitePool, session_id: &str) -> ProxyRequest {
+    for _ in 0..50 {
+        let requests = db::list_requests_paginated(pool, session_id, None, None, false, 10, 0)
+            .await
+            .unwrap();
+        if let Some(request) = requests
+            .into_iter()
+            .find(|request| request.response_status.is_some())
+        {
+            return request;
+        }
+        actix_web::rt::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("no response was logged for session {}", session_id);
+}
+
+#[actix_web::test]
+async fn streamed_response_is_forwarded_and_logged() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let mock_upstream = MockUpstream::start(vec![build_text_response("Hello there")]);
+    let session_id = create_test_session(pool, &mock_upstream.url, None).await;
+
+    let body = build_messages_body(json!("Be brief."), json!([]));
+    let (status, resp_body) = send_proxy_request(pool, &session_id, &body).await;
+    assert_eq!(status, 200);
+    assert!(resp_body.contains("Hello there"));
+
+    let received_requests = mock_upstream.take_received_requests();
+    assert_eq!(received_requests.len(), 1);
+    assert_eq!(received_requests[0].path, "/v1/messages");
+    assert_eq!(received_requests[0].body, body);
+
+    let request = wait_for_logged_request(pool, &session_id).await;
+    assert_eq!(request.response_status, Some(200));
+    assert_eq!(request.model.as_deref(), Some("claude-sonnet-4-5"));
+    assert!(request
+        .response_events_json
+        .unwrap()
+        .contains("Hello there"));
+}
+
+#[actix_web::test]
+async fn filter_profile_strips_system_blocks_and_tools_before_forwarding() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let profile_id = db::create_filter_profile(pool, "e2e")
+        .await
+        .unwrap()
+        .to_string();
+    db::create_system_filter(pool, &profile_id, "Internal notes")
+        .await
+        .unwrap();
+    db::create_tool_filter(pool, &profile_id, "WebSearch")
+        .await
+        .unwrap();
+    let mock_upstream = MockUpstream::start(vec![build_text_response("ok")]);
+    let session_id = create_test_session(pool, &mock_upstream.url, Some(&profile_id)).await;
+
+    let body = build_messages_body(
+        json!([
+            {"type": "text", "text": "You are helpful."},
+            {"type": "text", "text": "Internal notes"}
+        ]),
+        json!([
+            {"name": "WebSearch", "input_schema": {"type": "object"}},
+            {"name": "Read", "input_schema": {"type": "object"}}
+        ]),
+    );
+    let (status, _) = send_proxy_request(pool, &session_id, &body).await;
+    assert_eq!(status, 200);
+
+    let received_requests = mock_upstream.take_received_requests();
+    let forwarded_body = &received_requests[0].body;
+    assert_eq!(
+        forwarded_body["system"],
+        json!([{"type": "text", "text": "You are helpful."}])
+    );
+    assert_eq!(
+        forwarded_body["tools"],
+        json!([{"name": "Read", "input_schema": {"type": "object"}}])
+    );
+
+    let request = wait_for_logged_request(pool, &session_id).await;
+    let applied_filters: Value =
+        serde_json::from_str(&request.applied_filters_json.unwrap()).unwrap();
+    assert_eq!(applied_filters.as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn upstream_error_status_is_forwarded_and_logged() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let overloaded_error =
+        json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}});
+    let mock_upstream = MockUpstream::start(vec![ScriptedResponse::json(529, &overloaded_error)]);
+    let session_id = create_test_session(pool, &mock_upstream.url, None).await;
+
+    let body = build_messages_body(json!("Be brief."), json!([]));
+    let (status, resp_body) = send_proxy_request(pool, &session_id, &body).await;
+    assert_eq!(status, 529);
+    assert_eq!(
+        serde_json::from_str::<Value>(&resp_body).unwrap(),
+        overloaded_error
+    );
+
+    let request = wait_for_logged_request(pool, &session_id).await;
+    assert_eq!(request.response_status, Some(529));
+    assert_eq!(request.error_kind.as_deref(), Some(REQUEST_ERROR_STATUS));
+}
+
+#[actix_web::test]
+async fn injected_error_is_returned_without_calling_upstream() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let mock_upstream = MockUpstream::start(vec![build_text_response("unused")]);
+    let session_id = create_test_session(pool, &mock_upstream.url, None).await;
+    db::set_session_error_inject(pool, &session_id, Some("permission_error"))
+        .await
+        .unwrap();
+
+    let body = build_messages_body(json!("Be brief."), json!([]));
+    let (status, resp_body) = send_proxy_request(pool, &session_id, &body).await;
+    assert_eq!(status, 403);
+    let error: Value = serde_json::from_str(&resp_body).unwrap();
+    assert_eq!(error["error"]["type"], "permission_error");
+    assert!(mock_upstream.take_received_requests().is_empty());
+}
+
+#[actix_web::test]
+async fn webfetch_mock_policy_answers_tool_call_in_a_followup_round() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let tool_input = json!({"url": "https://example.com", "prompt": "Summarize"});
+    let mock_upstream = MockUpstream::start(vec![
+        build_tool_use_response("toolu_01", "WebFetch", &tool_input),
+        build_text_response("The page is an example."),
+    ]);
+    let session_id = create_test_session(pool, &mock_upstream.url, None).await;
+    db::set_session_webfetch_intercept(pool, &session_id, true)
+        .await
+        .unwrap();
+    db::create_webfetch_policy(
+        pool,
+        &session_id,
+        &WebfetchPolicyParams {
+            tool_name: None,
+            condition: "always",
+            condition_value: None,
+            action: "mock",
+        },
+    )
+    .await
+    .unwrap();
+
+    let tools = json!([{"name": "WebFetch", "input_schema": {"type": "object"}}]);
+    let body = build_messages_body(json!("Be brief."), tools);
+    let (status, resp_body) = send_proxy_request(pool, &session_id, &body).await;
+    assert_eq!(status, 200);
+    assert!(resp_body.contains("The page is an example."));
+    assert!(!resp_body.contains("toolu_01"));
+
+    let received_requests = mock_upstream.take_received_requests();
+    assert_eq!(received_requests.len(), 2);
+    let followup_messages = received_requests[1].body["messages"].as_array().unwrap();
+    assert_eq!(followup_messages.len(), 3);
+    assert_eq!(followup_messages[1]["content"][0]["type"], "tool_use");
+    let tool_result = &followup_messages[2]["content"][0];
+    assert_eq!(tool_result["type"], "tool_result");
+    assert_eq!(tool_result["tool_use_id"], "toolu_01");
+
+    let request = wait_for_logged_request(pool, &session_id).await;
+    let round_ids = db::list_round_request_ids(pool, &request.id.to_string())
+        .await
+        .unwrap();
+    assert_eq!(round_ids.len(), 1);
+}
+
+#[actix_web::test]
+async fn failed_webfetch_followup_is_retried_then_answered_with_an_error() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let tool_input = json!({"url": "https://example.com", "prompt": "Summarize"});
+    let mock_upstream = MockUpstream::start(vec![
+        build_tool_use_response("toolu_01", "WebFetch", &tool_input),
+        ScriptedResponse::broken(),
+        ScriptedResponse::broken(),
+    ]);
+    let session_id = create_test_session(pool, &mock_upstream.url, None).await;
+    db::set_session_webfetch_intercept(pool, &session_id, true)
+        .await
+        .unwrap();
+    db::create_webfetch_policy(
+        pool,
+        &session_id,
+        &WebfetchPolicyParams {
+            tool_name: None,
+            condition: "always",
+            condition_value: None,
+            action: "mock",
+        },
+    )
+    .await
+    .unwrap();
+    let app_config = AppConfig {
+        webfetch_followup_retries: 1,
+        webfetch_followup_fallback: WEBFETCH_FOLLOWUP_FALLBACK_ERROR.to_string(),
+        ..AppConfig::default()
+    };
+
+    let tools = json!([{"name": "WebFetch", "input_schema": {"type": "object"}}]);
+    let body = build_messages_body(json!("Be brief."), tools);
+    let (status, resp_body) =
+        send_proxy_request_with_config(pool, &session_id, &body, app_config).await;
+    assert_eq!(status, 502);
+    let error: Value = serde_json::from_str(&resp_body).unwrap();
+    assert_eq!(error["error"]["type"], "api_error");
+    assert_eq!(mock_upstream.take_received_requests().len(), 3);
+
+    let request = wait_for_logged_request(pool, &session_id).await;
+    assert_eq!(request.response_status, Some(502));
+    assert_eq!(
+        request.error_kind.as_deref(),
+        Some(REQUEST_ERROR_WEBFETCH_FOLLOWUP)
+    );
+    assert!(request
+        .webfetch_followup_error
+        .unwrap()
+        .starts_with("WebFetch follow-up request failed in round 1"));
+}
+
+#[actix_web::test]
+async fn webfetch_interception_past_its_deadline_returns_the_last_good_response() {
+    let temp_db = open_temp_pool().await;
+    let pool = &temp_db.pool;
+    let tool_input = json!({"url": "https://example.com", "prompt": "Summarize"});
+    let mock_upstream = MockUpstream::start(vec![
+        build_tool_use_response("toolu_01", "WebFetch", &tool_input),
+        build_tool_use_response("toolu_02", "WebFetch", &tool_input),
+        build_text_response("Too late.").delayed(Duration::from_secs(30)),
+    ]);
+    let session_id = create_test_session(pool, &mock_upstream.url, None).await;
+    db::set_session_webfetch_intercept(pool, &session_id, true)
+        .await
+        .unwrap();
+    db::create_webfetch_policy(
+        pool,
+        &session_id,
+        &WebfetchPolicyParams {
+            tool_name: None,
+            condition: "always",
+            condition_value: None,
+            action: "mock",
+        },
+    )
+    .await
+    .unwrap();
+    let app_config = AppConfig {
+        webfetch_intercept_deadline_secs: 1,
+        ..AppConfig::default()
+    };
+
+    let tools = json!([{"name": "WebFetch", "input_schema": {"type": "object"}}]);
+    let body = build_messages_body(json!("Be brief."), tools);
+    let (status, resp_body) =
+        send_proxy_request_with_config(pool, &session_id, &body, app_config).await;
+    assert_eq!(status, 200);
+    assert!(resp_body.contains("toolu_02"));
+    assert_eq!(mock_upstream.take_received_requests().len(), 3);
+
+    let request = wait_for_logged_request(pool, &session_id).await;
+    assert_eq!(request.error_kind, None);
+    assert_eq!(
+        request.webfetch_followup_error.as_deref(),
+        Some("WebFetch interception passed its deadline in round 2")
+    );
+}
@@ -0,0 +1,165 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web, HttpResponse,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::Utc;
+use common::config::{AuthConfig, DashboardRole, SharedConfig};
+use common::share::{verify_share_token, ShareSecret, SHARE_COOKIE};
+
+/// Dashboard forms that stay open to viewers and in read-only mode: picking
+/// the name shown on notes doesn't change any proxy data.
+const READ_ONLY_POST_PATHS: &[&str] = &["/_dashboard/user"];
+
+/// Require HTTP Basic credentials on `/_dashboard` routes when `[auth]` is
+/// configured, and keep viewers to browsing: any other method than GET or
+/// HEAD needs the operator role. Proxy routes are never gated so clients
+/// keep working. Without credentials, a share link cookie still lets the
+/// browser read the one request it was issued for.
+pub async fn require_dashboard_auth(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let dashboard_role = match req.app_data::<web::Data<SharedConfig>>() {
+        Some(config) if req.path().starts_with("/_dashboard") => {
+            let authorization = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+            let auth_config = config.read().unwrap().auth.clone();
+            find_dashboard_role(&auth_config, authorization)
+        }
+        _ => Some(DashboardRole::Operator),
+    };
+
+    let response = match dashboard_role {
+        Some(DashboardRole::Viewer) if !is_read_only_request(&req) => {
+            HttpResponse::Forbidden().body("Operator role required")
+        }
+        Some(_) => return Ok(next.call(req).await?.map_into_left_body()),
+        None if is_shared_request_read(&req) => {
+            return Ok(next.call(req).await?.map_into_left_body())
+        }
+        None => HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"gateway-proxy\""))
+            .body("Authentication required"),
+    };
+    Ok(req.into_response(response).map_into_right_body())
+}
+
+/// Whether a dashboard request leaves the data as it is: reads, and the forms
+/// in [`READ_ONLY_POST_PATHS`].
+pub fn is_read_only_request(req: &ServiceRequest) -> bool {
+    matches!(*req.method(), Method::GET | Method::HEAD)
+        || (*req.method() == Method::POST && READ_ONLY_POST_PATHS.contains(&req.path()))
+}
+
+/// Whether the request reads a page of a request shared with this browser.
+fn is_shared_request_read(req: &ServiceRequest) -> bool {
+    let (Some(share_secret), Some(cookie)) = (
+        req.app_data::<web::Data<ShareSecret>>(),
+        req.cookie(SHARE_COOKIE),
+    ) else {
+        return false;
+    };
+    matches!(*req.method(), Method::GET | Method::HEAD)
+        && verify_share_token(&share_secret.0, cookie.value(), Utc::now().timestamp())
+            .is_some_and(|share_grant| share_grant.allows_path(req.path()))
+}
+
+/// The role of the account in an `Authorization` header value, or `None` if
+/// it matches no configured account. Everyone is an operator when no
+/// accounts are configured.
+fn find_dashboard_role(
+    auth_config: &AuthConfig,
+    authorization: Option<&str>,
+) -> Option<DashboardRole> {
+    if !auth_config.is_enabled() {
+        return Some(DashboardRole::Operator);
+    }
+    let encoded = authorization?.strip_prefix("Basic ")?;
+    let decoded = BASE64.decode(encoded.trim()).ok()?;
+    let decoded = String::from_utf8_lossy(&decoded);
+    let (username, password) = decoded.split_once(':')?;
+    auth_config.find_role(username, password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::config::ViewerCredentials;
+
+    fn build_auth_config() -> AuthConfig {
+        AuthConfig {
+            username: Some("admin".to_string()),
+            password: Some("secret".to_string()),
+            viewers: vec![ViewerCredentials {
+                username: "guest".to_string(),
+                password: "look:here".to_string(),
+            }],
+        }
+    }
+
+    fn build_basic_authorization(credentials: &str) -> String {
+        format!("Basic {}", BASE64.encode(credentials))
+    }
+
+    #[test]
+    fn find_dashboard_role_reads_basic_credentials() {
+        let auth_config = build_auth_config();
+        assert_eq!(
+            find_dashboard_role(
+                &auth_config,
+                Some(&build_basic_authorization("admin:secret"))
+            ),
+            Some(DashboardRole::Operator)
+        );
+        assert_eq!(
+            find_dashboard_role(
+                &auth_config,
+                Some(&build_basic_authorization("guest:look:here"))
+            ),
+            Some(DashboardRole::Viewer)
+        );
+        assert_eq!(
+            find_dashboard_role(
+                &auth_config,
+                Some(&build_basic_authorization("admin:wrong"))
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn find_dashboard_role_rejects_malformed_headers() {
+        let auth_config = build_auth_config();
+        assert_eq!(find_dashboard_role(&auth_config, None), None);
+        assert_eq!(
+            find_dashboard_role(&auth_config, Some("Basic not base64!")),
+            None
+        );
+        assert_eq!(
+            find_dashboard_role(
+                &auth_config,
+                Some(&build_basic_authorization("adminsecret"))
+            ),
+            None
+        );
+        let bearer_authorization = format!("Bearer {}", BASE64.encode("admin:secret"));
+        assert_eq!(
+            find_dashboard_role(&auth_config, Some(&bearer_authorization)),
+            None
+        );
+    }
+
+    #[test]
+    fn find_dashboard_role_allows_everyone_without_accounts() {
+        assert_eq!(
+            find_dashboard_role(&AuthConfig::default(), None),
+            Some(DashboardRole::Operator)
+        );
+    }
+}
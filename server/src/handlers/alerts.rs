@@ -0,0 +1,145 @@
+use actix_web::{web, HttpResponse};
+use common::alerts::{find_alert_condition_by_key, parse_alert_value};
+use db::AlertRuleParams;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Alert events listed on the alerts page.
+const RECENT_ALERT_EVENTS: i64 = 50;
+
+struct AlertRuleForm {
+    name: String,
+    condition: String,
+    condition_value: String,
+    window_minutes: i64,
+}
+
+impl AlertRuleForm {
+    fn to_params(&self) -> AlertRuleParams<'_> {
+        AlertRuleParams {
+            name: &self.name,
+            condition: &self.condition,
+            condition_value: &self.condition_value,
+            window_minutes: self.window_minutes,
+        }
+    }
+}
+
+/// Validate the submitted rule, normalizing the value for its condition.
+fn parse_alert_rule_form(form: &HashMap<String, String>) -> Result<AlertRuleForm, String> {
+    let read_field = |name: &str| form.get(name).map(|field| field.trim()).unwrap_or("");
+    let name = read_field("name");
+    if name.is_empty() {
+        return Err("a name is required".to_string());
+    }
+    let condition = read_field("condition");
+    if find_alert_condition_by_key(condition).is_none() {
+        return Err(format!("unknown condition {:?}", condition));
+    }
+    let condition_value = parse_alert_value(condition, read_field("condition_value"))?;
+    let window_minutes = match read_field("window_minutes").parse::<i64>() {
+        Ok(window_minutes) if window_minutes > 0 => window_minutes,
+        _ => return Err("the window must be a positive number of minutes".to_string()),
+    };
+    Ok(AlertRuleForm {
+        name: name.to_string(),
+        condition: condition.to_string(),
+        condition_value,
+        window_minutes,
+    })
+}
+
+fn redirect_to_alerts() -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header(("Location", "/_dashboard/alerts"))
+        .finish()
+}
+
+pub async fn show_alerts_page(pool: web::Data<SqlitePool>) -> HttpResponse {
+    let rules = match db::list_alert_rules(pool.get_ref()).await {
+        Ok(rules) => rules,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let events = match db::list_alert_events(pool.get_ref(), RECENT_ALERT_EVENTS).await {
+        Ok(events) => events,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::alerts::render_alerts_view(&rules, &events);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn show_new_alert_rule_form() -> HttpResponse {
+    let html = pages::alerts::render_new_alert_rule_form();
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn create_alert_rule_post(
+    pool: web::Data<SqlitePool>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let rule_form = match parse_alert_rule_form(&form) {
+        Ok(rule_form) => rule_form,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid alert rule: {}", e)),
+    };
+    if let Err(e) = db::create_alert_rule(pool.get_ref(), &rule_form.to_params()).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_alerts()
+}
+
+pub async fn show_edit_alert_rule_form(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let rule_id = path.into_inner();
+    let rule = match db::get_alert_rule(pool.get_ref(), &rule_id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return HttpResponse::NotFound().body("Alert rule not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::alerts::render_edit_alert_rule_form(&rule);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn update_alert_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let rule_id = path.into_inner();
+    let rule_form = match parse_alert_rule_form(&form) {
+        Ok(rule_form) => rule_form,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid alert rule: {}", e)),
+    };
+    if let Err(e) = db::update_alert_rule(pool.get_ref(), &rule_id, &rule_form.to_params()).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_alerts()
+}
+
+pub async fn toggle_alert_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let rule_id = path.into_inner();
+    let rule = match db::get_alert_rule(pool.get_ref(), &rule_id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return HttpResponse::NotFound().body("Alert rule not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    if let Err(e) = db::set_alert_rule_enabled(pool.get_ref(), &rule_id, !rule.enabled).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_alerts()
+}
+
+pub async fn delete_alert_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let rule_id = path.into_inner();
+    if let Err(e) = db::delete_alert_rule(pool.get_ref(), &rule_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_alerts()
+}
@@ -0,0 +1,59 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::config::SharedConfig;
+use proxy::webfetch::{ApprovalDecision, ApprovalQueue};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+use super::users::get_dashboard_author;
+
+pub async fn show_all_approvals_page(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let sessions = match db::list_sessions(pool.get_ref()).await {
+        Ok(sessions) => sessions,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let session_names: HashMap<String, String> = sessions
+        .into_iter()
+        .map(|session| (session.id.to_string(), session.name))
+        .collect();
+    let pending = proxy::webfetch::list_all_pending(approval_queue.get_ref());
+    let approval_timeout_secs = config.read().unwrap().approval_timeout_secs;
+    let html = pages::approvals::render_all_approvals_view(
+        &pending,
+        &session_names,
+        approval_timeout_secs,
+    );
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Accept, Accept raw, Mock or Fail a whole approval round from the overview page.
+pub async fn decide_approval_post(
+    req: HttpRequest,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let approval_id = path.into_inner();
+    let decision = match form.get("decision").map(|field| field.as_str()) {
+        Some("accept") => ApprovalDecision::Accept,
+        Some("accept_raw") => ApprovalDecision::AcceptRaw,
+        Some("mock") => ApprovalDecision::Mock,
+        Some("fail") => ApprovalDecision::Fail,
+        other => {
+            return HttpResponse::BadRequest()
+                .body(format!("Unknown decision: {}", other.unwrap_or("")));
+        }
+    };
+    proxy::webfetch::resolve_pending(
+        approval_queue.get_ref(),
+        &approval_id,
+        decision,
+        &get_dashboard_author(&req),
+    );
+    HttpResponse::SeeOther()
+        .insert_header(("Location", "/_dashboard/approvals"))
+        .finish()
+}
@@ -0,0 +1,29 @@
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+
+pub async fn show_batches_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let batches = match db::list_message_batches(pool.get_ref(), &session_id).await {
+        Ok(batches) => batches,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let mut batches_with_items = Vec::with_capacity(batches.len());
+    for batch in batches {
+        let items = match db::list_message_batch_items(pool.get_ref(), &batch.id.to_string()).await
+        {
+            Ok(items) => items,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+        batches_with_items.push((batch, items));
+    }
+    let html = pages::batches::render_batches_view(&session, &batches_with_items);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
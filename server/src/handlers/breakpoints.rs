@@ -0,0 +1,101 @@
+use actix_web::{web, HttpResponse};
+use proxy::breakpoint::{BreakpointDecision, BreakpointQueue};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+pub async fn show_breakpoints_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    breakpoint_queue: web::Data<BreakpointQueue>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let held_requests =
+        proxy::breakpoint::list_held_requests(breakpoint_queue.get_ref(), &session_id);
+    let html = pages::breakpoints::render_breakpoints_view(&session, &held_requests);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn set_breakpoint_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_session_breakpoint(pool.get_ref(), &session_id, true).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_breakpoints(&session_id)
+}
+
+/// Turn breakpoint mode off and forward the requests it holds unchanged.
+pub async fn clear_breakpoint_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    breakpoint_queue: web::Data<BreakpointQueue>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_session_breakpoint(pool.get_ref(), &session_id, false).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    proxy::breakpoint::release_session_requests(breakpoint_queue.get_ref(), &session_id);
+    redirect_to_breakpoints(&session_id)
+}
+
+/// Forward a held request, with the submitted body when it was edited.
+pub async fn forward_held_request_post(
+    path: web::Path<(String, String)>,
+    form: web::Form<HashMap<String, String>>,
+    breakpoint_queue: web::Data<BreakpointQueue>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    let edited_body = match read_edited_body(&form) {
+        Ok(edited_body) => edited_body,
+        Err(resp) => return resp,
+    };
+    proxy::breakpoint::resolve_held_request(
+        breakpoint_queue.get_ref(),
+        &request_id,
+        BreakpointDecision::Forward(edited_body),
+    );
+    redirect_to_breakpoints(&session_id)
+}
+
+pub async fn reject_held_request_post(
+    path: web::Path<(String, String)>,
+    breakpoint_queue: web::Data<BreakpointQueue>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    proxy::breakpoint::resolve_held_request(
+        breakpoint_queue.get_ref(),
+        &request_id,
+        BreakpointDecision::Reject,
+    );
+    redirect_to_breakpoints(&session_id)
+}
+
+/// The `body` field as JSON, or `None` when it is empty.
+fn read_edited_body(form: &HashMap<String, String>) -> Result<Option<Value>, HttpResponse> {
+    let Some(body) = form.get("body").map(|body| body.trim()) else {
+        return Ok(None);
+    };
+    if body.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(body)
+        .map(Some)
+        .map_err(|e| HttpResponse::BadRequest().body(format!("Invalid JSON body: {}", e)))
+}
+
+fn redirect_to_breakpoints(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/breakpoints", session_id),
+        ))
+        .finish()
+}
@@ -0,0 +1,34 @@
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+
+/// Requests shown in the context usage chart.
+const CONTEXT_USAGE_LIMIT: i64 = 100;
+
+pub async fn show_context_usage_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let mut requests = match db::list_requests_paginated(
+        pool.get_ref(),
+        &session_id,
+        None,
+        None,
+        false,
+        CONTEXT_USAGE_LIMIT,
+        0,
+    )
+    .await
+    {
+        Ok(requests) => requests,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    requests.reverse();
+    let html = pages::context_usage::render_context_usage_view(&session, &requests);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
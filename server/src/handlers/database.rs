@@ -0,0 +1,104 @@
+use actix_web::{web, HttpResponse};
+use common::config::SharedConfig;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+fn get_backup_dir(config: &SharedConfig) -> Option<PathBuf> {
+    config.read().unwrap().backup_dir.clone().map(PathBuf::from)
+}
+
+fn redirect_to_database() -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header(("Location", "/_dashboard/admin/db"))
+        .finish()
+}
+
+pub async fn show_database_page(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let database_stats = match db::get_database_stats(pool.get_ref()).await {
+        Ok(database_stats) => database_stats,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let integrity_results = if query.contains_key("integrity_check") {
+        match db::check_database_integrity(pool.get_ref()).await {
+            Ok(integrity_results) => Some(integrity_results),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        }
+    } else {
+        None
+    };
+    let backup_dir = get_backup_dir(&config);
+    let snapshots = match backup_dir.as_deref().map(db::list_database_snapshots) {
+        Some(Ok(snapshots)) => snapshots,
+        Some(Err(e)) => {
+            return HttpResponse::InternalServerError().body(format!("Backup error: {}", e))
+        }
+        None => Vec::new(),
+    };
+    let html = pages::database::render_database_view(
+        &database_stats,
+        integrity_results.as_deref(),
+        backup_dir.as_deref(),
+        &snapshots,
+    );
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn vacuum_database_post(pool: web::Data<SqlitePool>) -> HttpResponse {
+    if let Err(e) = db::vacuum_database(pool.get_ref()).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_database()
+}
+
+/// Compress the stored bodies over the compression threshold that are still
+/// kept as text.
+pub async fn compress_database_bodies_post(pool: web::Data<SqlitePool>) -> HttpResponse {
+    match db::compress_stored_bodies(pool.get_ref()).await {
+        Ok(compressed_count) => log::info!("database: compressed {} bodies", compressed_count),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    }
+    redirect_to_database()
+}
+
+pub async fn create_database_snapshot_post(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+) -> HttpResponse {
+    let Some(backup_dir) = get_backup_dir(&config) else {
+        return HttpResponse::BadRequest().body("No backup_dir configured");
+    };
+    if let Err(e) = db::snapshot_database(pool.get_ref(), &backup_dir).await {
+        return HttpResponse::InternalServerError().body(format!("Backup error: {}", e));
+    }
+    redirect_to_database()
+}
+
+/// Restore a snapshot over the live database, snapshotting the current
+/// contents first so the restore can be undone.
+pub async fn restore_database_snapshot_post(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let Some(backup_dir) = get_backup_dir(&config) else {
+        return HttpResponse::BadRequest().body("No backup_dir configured");
+    };
+    let file_name = form.get("file_name").map(String::as_str).unwrap_or("");
+    if !db::is_snapshot_file_name(file_name) {
+        return HttpResponse::BadRequest().body("Invalid snapshot name");
+    }
+    if let Err(e) = db::snapshot_database(pool.get_ref(), &backup_dir).await {
+        return HttpResponse::InternalServerError().body(format!("Backup error: {}", e));
+    }
+    if let Err(e) = db::restore_database_snapshot(pool.get_ref(), &backup_dir.join(file_name)).await
+    {
+        return HttpResponse::InternalServerError().body(format!("Restore error: {}", e));
+    }
+    log::info!("backup: restored {}", file_name);
+    redirect_to_database()
+}
@@ -0,0 +1,24 @@
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+
+/// Duplicate groups shown on the duplicates page.
+const TOP_DUPLICATES_LIMIT: i64 = 50;
+
+pub async fn show_duplicates_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let duplicate_groups =
+        match db::list_duplicate_groups(pool.get_ref(), &session_id, TOP_DUPLICATES_LIMIT).await {
+            Ok(duplicate_groups) => duplicate_groups,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+    let html = pages::duplicates::render_duplicates_view(&session, &duplicate_groups);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
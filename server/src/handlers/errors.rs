@@ -0,0 +1,23 @@
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+
+/// Days of failures counted on the errors page and the home page.
+pub(super) const RECENT_FAILURE_DAYS: i64 = 7;
+
+/// Failed requests listed under "Recent Failures".
+const RECENT_FAILURES: i64 = 50;
+
+pub async fn show_errors_page(pool: web::Data<SqlitePool>) -> HttpResponse {
+    let error_summaries = match db::list_error_summaries(pool.get_ref(), RECENT_FAILURE_DAYS).await
+    {
+        Ok(error_summaries) => error_summaries,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let recent_failures = match db::list_recent_failures(pool.get_ref(), RECENT_FAILURES).await {
+        Ok(recent_failures) => recent_failures,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html =
+        pages::errors::render_errors_view(RECENT_FAILURE_DAYS, &error_summaries, &recent_failures);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
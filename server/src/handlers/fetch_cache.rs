@@ -0,0 +1,79 @@
+use actix_web::{web, HttpResponse};
+use common::config::SharedConfig;
+use proxy::webfetch::build_cache_cutoff;
+use sqlx::SqlitePool;
+
+fn redirect_to_fetch_cache(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!(
+                "/_dashboard/sessions/{}/tool-intercept/webfetch/cache",
+                session_id
+            ),
+        ))
+        .finish()
+}
+
+pub async fn show_fetch_cache_page(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let entries = match db::list_webfetch_cache_entries(pool.get_ref(), &session_id).await {
+        Ok(entries) => entries,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let ttl_secs = config.read().unwrap().webfetch_cache_ttl_secs;
+    let fresh_after = build_cache_cutoff(ttl_secs);
+    let html =
+        pages::fetch_cache::render_fetch_cache_view(&session, &entries, ttl_secs, &fresh_after);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn show_fetch_cache_entry_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, entry_id) = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let entry = match db::get_webfetch_cache_entry(pool.get_ref(), &entry_id).await {
+        Ok(Some(entry)) => entry,
+        Ok(None) => return HttpResponse::NotFound().body("Cache entry not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::fetch_cache::render_fetch_cache_entry_view(&session, &entry);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn delete_fetch_cache_entry_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, entry_id) = path.into_inner();
+    if let Err(e) = db::delete_webfetch_cache_entry(pool.get_ref(), &entry_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_fetch_cache(&session_id)
+}
+
+pub async fn clear_fetch_cache_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::clear_webfetch_cache(pool.get_ref(), &session_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_fetch_cache(&session_id)
+}
@@ -1,8 +1,15 @@
-use actix_web::{web, HttpResponse};
-use proxy::webfetch::{ApprovalDecision, ApprovalQueue};
+use actix_web::{web, HttpRequest, HttpResponse};
+use common::config::SharedConfig;
+use common::extraction::find_by_key;
+use common::models::PendingToolInfo;
+use futures::StreamExt;
+use proxy::webfetch::{ApprovalDecision, ApprovalEvent, ApprovalQueue, ApprovalStreamMessage};
+use serde_json::{Map, Value};
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 
+use super::users::get_dashboard_author;
+
 pub async fn show_intercept_page(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>,
@@ -14,13 +21,32 @@ pub async fn show_intercept_page(
         Ok(None) => return HttpResponse::NotFound().body("Session not found"),
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
-    let pending_count = proxy::webfetch::list_pending(approval_queue.get_ref(), &session_id).len();
-    let html = pages::intercept::render_intercept_view(&session, pending_count);
+    let pending = proxy::webfetch::list_pending(approval_queue.get_ref(), &session_id);
+    let running_intercepts =
+        proxy::webfetch::list_running_intercepts(approval_queue.get_ref(), &session_id);
+    let html = pages::intercept::render_intercept_view(&session, &pending, &running_intercepts);
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
+/// Cancel a running interception's rounds; the client gets the last good
+/// response or an error, as the follow-up fallback says.
+pub async fn cancel_running_intercept_post(
+    path: web::Path<(String, String)>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    proxy::webfetch::cancel_running_intercept(approval_queue.get_ref(), &session_id, &request_id);
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept", session_id),
+        ))
+        .finish()
+}
+
 pub async fn show_webfetch_page(
     pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
     path: web::Path<String>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
@@ -29,7 +55,33 @@ pub async fn show_webfetch_page(
         Ok(None) => return HttpResponse::NotFound().body("Session not found"),
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
-    let html = pages::webfetch::render_webfetch_view(&session);
+    let whitelist_rule_count =
+        match db::list_webfetch_whitelist_rules(pool.get_ref(), &session_id).await {
+            Ok(rules) => rules.len(),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+    let deny_rule_count = match db::list_webfetch_deny_rules(pool.get_ref(), &session_id).await {
+        Ok(rules) => rules.len(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let policy_count = match db::list_webfetch_policies(pool.get_ref(), &session_id).await {
+        Ok(policies) => policies.len(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let cache_entry_count =
+        match db::list_webfetch_cache_entries(pool.get_ref(), &session_id).await {
+            Ok(entries) => entries.len(),
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+    let config = config.read().unwrap();
+    let html = pages::webfetch::render_webfetch_view(
+        &session,
+        &config,
+        whitelist_rule_count,
+        deny_rule_count,
+        policy_count,
+        cache_entry_count,
+    );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
@@ -65,19 +117,13 @@ pub async fn clear_webfetch_intercept_post(
         .finish()
 }
 
-pub async fn set_webfetch_whitelist_post(
+pub async fn allow_webfetch_private_targets_post(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>,
-    form: web::Form<HashMap<String, String>>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
-    let whitelist = form.get("whitelist").map(|field| field.as_str()).unwrap_or("");
-    let whitelist = if whitelist.trim().is_empty() {
-        None
-    } else {
-        Some(whitelist)
-    };
-    if let Err(e) = db::set_session_webfetch_whitelist(pool.get_ref(), &session_id, whitelist).await
+    if let Err(e) =
+        db::set_session_webfetch_allow_private_targets(pool.get_ref(), &session_id, true).await
     {
         return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
     }
@@ -89,12 +135,86 @@ pub async fn set_webfetch_whitelist_post(
         .finish()
 }
 
-pub async fn clear_webfetch_whitelist_post(
+pub async fn block_webfetch_private_targets_post(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
-    if let Err(e) = db::set_session_webfetch_whitelist(pool.get_ref(), &session_id, None).await {
+    if let Err(e) =
+        db::set_session_webfetch_allow_private_targets(pool.get_ref(), &session_id, false).await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
+
+pub async fn respect_webfetch_robots_txt_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    // Start from fresh robots.txt lookups each time the option is turned on.
+    if let Err(e) = db::clear_webfetch_robots_cache(pool.get_ref(), &session_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    if let Err(e) =
+        db::set_session_webfetch_respect_robots_txt(pool.get_ref(), &session_id, true).await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
+
+pub async fn ignore_webfetch_robots_txt_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) =
+        db::set_session_webfetch_respect_robots_txt(pool.get_ref(), &session_id, false).await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
+
+pub async fn enable_webfetch_raw_mode_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_session_webfetch_raw_mode(pool.get_ref(), &session_id, true).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
+
+pub async fn disable_webfetch_raw_mode_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_session_webfetch_raw_mode(pool.get_ref(), &session_id, false).await {
         return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
     }
     HttpResponse::SeeOther()
@@ -121,7 +241,70 @@ pub async fn show_approvals_page(
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
+/// An approval as sent on the event stream: its id and rendered table rows,
+/// ready to drop into the page.
+fn build_approval_json(session_id: &str, approval_id: &str, tools: &[PendingToolInfo]) -> Value {
+    serde_json::json!({
+        "approval_id": approval_id,
+        "html": pages::webfetch::render_pending_approval_html(session_id, approval_id, tools),
+    })
+}
+
+/// Format an approval stream message as a server-sent event.
+fn format_approval_stream_message(session_id: &str, message: &ApprovalStreamMessage) -> String {
+    let (event_name, data) = match message {
+        ApprovalStreamMessage::Snapshot(pending) => {
+            let approvals: Vec<Value> = pending
+                .iter()
+                .map(|(approval_id, tools)| build_approval_json(session_id, approval_id, tools))
+                .collect();
+            ("snapshot", serde_json::json!({ "approvals": approvals }))
+        }
+        ApprovalStreamMessage::Event(ApprovalEvent::Added {
+            approval_id, tools, ..
+        }) => ("added", build_approval_json(session_id, approval_id, tools)),
+        ApprovalStreamMessage::Event(ApprovalEvent::Updated {
+            approval_id, tools, ..
+        }) => (
+            "updated",
+            build_approval_json(session_id, approval_id, tools),
+        ),
+        ApprovalStreamMessage::Event(ApprovalEvent::Removed { approval_id, .. }) => {
+            ("removed", serde_json::json!({ "approval_id": approval_id }))
+        }
+        ApprovalStreamMessage::KeepAlive => return ": keep-alive\n\n".to_string(),
+    };
+    format!("event: {}\ndata: {}\n\n", event_name, data)
+}
+
+/// Server-sent events announcing the session's pending approvals as they are
+/// queued, partly decided and removed.
+pub async fn stream_approval_events(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let event_stream =
+        proxy::webfetch::stream_approval_events(approval_queue.get_ref(), &session_id).map(
+            move |message| {
+                let event = format_approval_stream_message(&session_id, &message);
+                Ok::<_, actix_web::Error>(web::Bytes::from(event))
+            },
+        );
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(event_stream)
+}
+
 pub async fn fail_approval_post(
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     approval_queue: web::Data<ApprovalQueue>,
 ) -> HttpResponse {
@@ -130,6 +313,7 @@ pub async fn fail_approval_post(
         approval_queue.get_ref(),
         &approval_id,
         ApprovalDecision::Fail,
+        &get_dashboard_author(&req),
     );
     HttpResponse::SeeOther()
         .insert_header((
@@ -140,6 +324,7 @@ pub async fn fail_approval_post(
 }
 
 pub async fn mock_approval_post(
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     approval_queue: web::Data<ApprovalQueue>,
 ) -> HttpResponse {
@@ -148,6 +333,7 @@ pub async fn mock_approval_post(
         approval_queue.get_ref(),
         &approval_id,
         ApprovalDecision::Mock,
+        &get_dashboard_author(&req),
     );
     HttpResponse::SeeOther()
         .insert_header((
@@ -158,6 +344,7 @@ pub async fn mock_approval_post(
 }
 
 pub async fn accept_approval_post(
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     approval_queue: web::Data<ApprovalQueue>,
 ) -> HttpResponse {
@@ -166,6 +353,63 @@ pub async fn accept_approval_post(
         approval_queue.get_ref(),
         &approval_id,
         ApprovalDecision::Accept,
+        &get_dashboard_author(&req),
+    );
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/approvals", session_id),
+        ))
+        .finish()
+}
+
+/// Accept a round and return fetched page text without the agent summary,
+/// whatever the session's raw mode.
+pub async fn accept_raw_approval_post(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let (session_id, approval_id) = path.into_inner();
+    proxy::webfetch::resolve_pending(
+        approval_queue.get_ref(),
+        &approval_id,
+        ApprovalDecision::AcceptRaw,
+        &get_dashboard_author(&req),
+    );
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/approvals", session_id),
+        ))
+        .finish()
+}
+
+/// Record an Accept, Mock or Fail for one tool call of a pending approval. The
+/// round continues once every call has a decision.
+pub async fn decide_tool_approval_post(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    form: web::Form<HashMap<String, String>>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let (session_id, approval_id, tool_use_id) = path.into_inner();
+    let decision = match form.get("decision").map(|field| field.as_str()) {
+        Some("accept") => ApprovalDecision::Accept,
+        Some("accept_raw") => ApprovalDecision::AcceptRaw,
+        Some("mock") => ApprovalDecision::Mock,
+        Some("fail") => ApprovalDecision::Fail,
+        other => {
+            return HttpResponse::BadRequest()
+                .body(format!("Unknown decision: {}", other.unwrap_or("")));
+        }
+    };
+    proxy::webfetch::decide_pending_tool(
+        approval_queue.get_ref(),
+        &approval_id,
+        &tool_use_id,
+        decision,
+        &get_dashboard_author(&req),
     );
     HttpResponse::SeeOther()
         .insert_header((
@@ -174,3 +418,193 @@ pub async fn accept_approval_post(
         ))
         .finish()
 }
+
+/// Collect the edited `input_<tool_use_id>` fields into an object keyed by
+/// tool_use id. Each field must hold a JSON object.
+fn parse_edited_inputs(
+    tools: &[PendingToolInfo],
+    form: &HashMap<String, String>,
+) -> Result<Value, String> {
+    let mut edited_inputs = Map::new();
+    for tool in tools {
+        let Some(field) = form.get(&format!("input_{}", tool.tool_use_id)) else {
+            continue;
+        };
+        let input: Value = serde_json::from_str(field)
+            .map_err(|e| format!("Invalid JSON for {} input: {}", tool.name, e))?;
+        if !input.is_object() {
+            return Err(format!("{} input must be a JSON object", tool.name));
+        }
+        edited_inputs.insert(tool.tool_use_id.clone(), input);
+    }
+    Ok(Value::Object(edited_inputs))
+}
+
+pub async fn show_edit_approval_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let (session_id, approval_id) = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let tools = match proxy::webfetch::get_pending(approval_queue.get_ref(), &approval_id) {
+        Some((pending_session_id, tools)) if pending_session_id == session_id => tools,
+        _ => return HttpResponse::NotFound().body("Approval not found"),
+    };
+    let html = pages::webfetch::render_edit_approval_view(&session, &approval_id, &tools);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn accept_edited_approval_post(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    form: web::Form<HashMap<String, String>>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
+    let (session_id, approval_id) = path.into_inner();
+    if let Some((_, tools)) = proxy::webfetch::get_pending(approval_queue.get_ref(), &approval_id)
+    {
+        let edited_inputs = match parse_edited_inputs(&tools, &form) {
+            Ok(edited_inputs) => edited_inputs,
+            Err(e) => return HttpResponse::BadRequest().body(e),
+        };
+        proxy::webfetch::resolve_pending(
+            approval_queue.get_ref(),
+            &approval_id,
+            ApprovalDecision::AcceptEdited(edited_inputs),
+            &get_dashboard_author(&req),
+        );
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/approvals", session_id),
+        ))
+        .finish()
+}
+
+/// Parse an optional positive integer form field; an empty field means "use the default".
+fn parse_fetch_limit(form: &HashMap<String, String>, key: &str) -> Result<Option<i64>, String> {
+    match form.get(key).map(|field| field.trim()) {
+        None | Some("") => Ok(None),
+        Some(value) => value
+            .parse::<i64>()
+            .ok()
+            .filter(|limit| *limit > 0)
+            .map(Some)
+            .ok_or_else(|| format!("{} must be a positive integer, got {:?}", key, value)),
+    }
+}
+
+pub async fn set_webfetch_fetch_limits_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let max_content_bytes = match parse_fetch_limit(&form, "max_content_bytes") {
+        Ok(max_content_bytes) => max_content_bytes,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let fetch_timeout_secs = match parse_fetch_limit(&form, "fetch_timeout_secs") {
+        Ok(fetch_timeout_secs) => fetch_timeout_secs,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    if let Err(e) = db::set_session_webfetch_fetch_limits(
+        pool.get_ref(),
+        &session_id,
+        max_content_bytes,
+        fetch_timeout_secs,
+    )
+    .await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
+
+/// Read an optional text form field; an empty field means "use the default".
+fn read_optional_field<'a>(form: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    form.get(key)
+        .map(|field| field.trim())
+        .filter(|field| !field.is_empty())
+}
+
+pub async fn set_webfetch_agent_backend_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let agent_url = read_optional_field(&form, "agent_url");
+    if let Some(agent_url) = agent_url {
+        match reqwest::Url::parse(agent_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => {
+                return HttpResponse::BadRequest()
+                    .body(format!("Unsupported agent URL scheme: {}", url.scheme()));
+            }
+            Err(e) => return HttpResponse::BadRequest().body(format!("Invalid agent URL: {}", e)),
+        }
+    }
+    if let Err(e) = db::set_session_webfetch_agent_backend(
+        pool.get_ref(),
+        &session_id,
+        agent_url,
+        read_optional_field(&form, "agent_model"),
+        read_optional_field(&form, "agent_api_key"),
+    )
+    .await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
+
+pub async fn set_webfetch_extraction_mode_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let mode = form.get("mode").map(|field| field.as_str()).unwrap_or("");
+    if find_by_key(mode).is_none() {
+        return HttpResponse::BadRequest().body(format!("Unknown extraction mode: {}", mode));
+    }
+    if mode != session.webfetch_extraction_mode {
+        if let Err(e) =
+            db::set_session_webfetch_extraction_mode(pool.get_ref(), &session_id, mode).await
+        {
+            return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+        }
+        // Cached text was extracted with the old mode.
+        if let Err(e) = db::clear_webfetch_cache(pool.get_ref(), &session_id).await {
+            return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+        }
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/webfetch", session_id),
+        ))
+        .finish()
+}
@@ -0,0 +1,59 @@
+use actix_web::{web, HttpResponse};
+use proxy::intercept_rules::validate_intercept_rule;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+fn redirect_to_intercept_rules(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/intercept-rules", session_id),
+        ))
+        .finish()
+}
+
+pub async fn show_intercept_rules_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let rules = match db::list_intercept_rules(pool.get_ref(), &session_id).await {
+        Ok(rules) => rules,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::intercept_rules::render_intercept_rules_view(&session, &rules);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn create_intercept_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let read_field = |name: &str| form.get(name).map(|field| field.trim()).unwrap_or("");
+    let (field, value) = (read_field("field"), read_field("value"));
+    if let Err(e) = validate_intercept_rule(field, value) {
+        return HttpResponse::BadRequest().body(e);
+    }
+    if let Err(e) = db::create_intercept_rule(pool.get_ref(), &session_id, field, value).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_intercept_rules(&session_id)
+}
+
+pub async fn delete_intercept_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, rule_id) = path.into_inner();
+    if let Err(e) = db::delete_intercept_rule(pool.get_ref(), &session_id, &rule_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_intercept_rules(&session_id)
+}
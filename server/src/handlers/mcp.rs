@@ -0,0 +1,112 @@
+use actix_web::{web, HttpResponse};
+use common::config::SharedConfig;
+use common::mcp::find_by_key;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn redirect_to_mcp_page(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/tool-intercept/mcp", session_id),
+        ))
+        .finish()
+}
+
+pub async fn show_mcp_server_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::mcp::render_mcp_server_view(&session);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn set_mcp_server_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let transport = form
+        .get("transport")
+        .map(|field| field.as_str())
+        .unwrap_or("");
+    if find_by_key(transport).is_none() {
+        return HttpResponse::BadRequest().body(format!("Unknown MCP transport: {}", transport));
+    }
+    let target = form.get("target").map(|field| field.trim()).unwrap_or("");
+    if target.is_empty() {
+        return HttpResponse::BadRequest().body("Command or URL is required");
+    }
+    if transport == "http" {
+        match reqwest::Url::parse(target) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => {
+                return HttpResponse::BadRequest().body(format!(
+                    "Unsupported MCP server URL scheme: {}",
+                    url.scheme()
+                ));
+            }
+            Err(e) => {
+                return HttpResponse::BadRequest().body(format!("Invalid MCP server URL: {}", e));
+            }
+        }
+    }
+    if let Err(e) =
+        db::set_session_mcp_server(pool.get_ref(), &session_id, Some(transport), Some(target)).await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_mcp_page(&session_id)
+}
+
+pub async fn check_mcp_server_post(
+    pool: web::Data<SqlitePool>,
+    client: web::Data<reqwest::Client>,
+    config: web::Data<SharedConfig>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    if session.mcp_server_transport.is_none() {
+        return HttpResponse::BadRequest().body("No MCP server is configured for this session");
+    }
+    // Checks get the same time limit as the session's tool calls.
+    let timeout_secs = session
+        .webfetch_fetch_timeout_secs
+        .map(|timeout_secs| timeout_secs as u64)
+        .unwrap_or_else(|| config.read().unwrap().webfetch_fetch_timeout_secs);
+    if let Err(e) = proxy::webfetch::check_session_mcp_server(
+        pool.get_ref(),
+        &session,
+        client.get_ref(),
+        Duration::from_secs(timeout_secs),
+    )
+    .await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_mcp_page(&session_id)
+}
+
+pub async fn clear_mcp_server_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_session_mcp_server(pool.get_ref(), &session_id, None, None).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_mcp_page(&session_id)
+}
@@ -1,15 +1,55 @@
+mod alerts;
+mod approvals;
+mod batches;
+mod breakpoints;
+mod context_usage;
+mod database;
+mod duplicates;
 mod error_inject;
+mod errors;
+mod fetch_cache;
 mod filters;
 mod intercept;
+mod intercept_rules;
+mod mcp;
+mod models;
+mod policies;
 mod proxy;
 mod requests;
 mod sessions;
+mod settings;
+mod share;
+mod timeline;
+mod tools;
+mod users;
+mod validation;
 mod webfetch;
+mod whitelist;
 
 pub use self::webfetch::*;
+pub use alerts::*;
+pub use approvals::*;
+pub use batches::*;
+pub use breakpoints::*;
+pub use context_usage::*;
+pub use database::*;
+pub use duplicates::*;
 pub use error_inject::*;
+pub use errors::*;
+pub use fetch_cache::*;
 pub use filters::*;
 pub use intercept::*;
+pub use intercept_rules::*;
+pub use mcp::*;
+pub use models::*;
+pub use policies::*;
 pub use proxy::*;
 pub use requests::*;
 pub use sessions::*;
+pub use settings::*;
+pub use share::*;
+pub use timeline::*;
+pub use tools::*;
+pub use users::*;
+pub use validation::*;
+pub use whitelist::*;
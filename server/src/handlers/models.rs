@@ -0,0 +1,56 @@
+use actix_web::{web, HttpResponse};
+use common::{config::SharedConfig, url::encode_uri_component};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use templates::Pagination;
+
+pub async fn show_models_page(pool: web::Data<SqlitePool>) -> HttpResponse {
+    let model_stats = match db::list_model_stats(pool.get_ref()).await {
+        Ok(model_stats) => model_stats,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::models::render_models_view(&model_stats);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Requests for the model in `?model=`, across sessions.
+pub async fn show_model_requests_page(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let model = query.get("model").map(|model| model.as_str()).unwrap_or("");
+    if model.is_empty() {
+        return HttpResponse::BadRequest().body("Missing model");
+    }
+    let page: i64 = query
+        .get("page")
+        .and_then(|page_str| page_str.parse().ok())
+        .unwrap_or(1)
+        .max(1);
+    let per_page = config.read().unwrap().dashboard_per_page;
+
+    let total = match db::count_model_requests(pool.get_ref(), model).await {
+        Ok(total) => total,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let offset = (page - 1) * per_page;
+    let model_requests =
+        match db::list_model_requests_paginated(pool.get_ref(), model, per_page, offset).await {
+            Ok(model_requests) => model_requests,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+
+    let extra_params = format!("&model={}", encode_uri_component(model));
+    let pagination = Pagination::new(
+        page,
+        total,
+        per_page,
+        "/_dashboard/models/requests",
+        &extra_params,
+    );
+
+    let html = pages::models::render_model_requests_view(model, &model_requests, &pagination);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
@@ -0,0 +1,148 @@
+use actix_web::{web, HttpResponse};
+use common::policy::{find_action_by_key, find_condition_by_key};
+use db::WebfetchPolicyParams;
+use proxy::webfetch::parse_policy_value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+struct PolicyForm {
+    tool_name: Option<String>,
+    condition: String,
+    condition_value: Option<String>,
+    action: String,
+}
+
+impl PolicyForm {
+    fn to_params(&self) -> WebfetchPolicyParams<'_> {
+        WebfetchPolicyParams {
+            tool_name: self.tool_name.as_deref(),
+            condition: &self.condition,
+            condition_value: self.condition_value.as_deref(),
+            action: &self.action,
+        }
+    }
+}
+
+/// Validate the submitted policy. An empty tool name matches every tool and
+/// the condition value is normalized for its condition.
+fn parse_policy_form(form: &HashMap<String, String>) -> Result<PolicyForm, String> {
+    let read_field = |name: &str| form.get(name).map(|field| field.trim()).unwrap_or("");
+    let condition = read_field("condition");
+    if find_condition_by_key(condition).is_none() {
+        return Err(format!("unknown condition {:?}", condition));
+    }
+    let action = read_field("action");
+    if find_action_by_key(action).is_none() {
+        return Err(format!("unknown action {:?}", action));
+    }
+    let condition_value = parse_policy_value(condition, read_field("condition_value"))?;
+    let tool_name = Some(read_field("tool_name"))
+        .filter(|tool_name| !tool_name.is_empty())
+        .map(str::to_string);
+    Ok(PolicyForm {
+        tool_name,
+        condition: condition.to_string(),
+        condition_value,
+        action: action.to_string(),
+    })
+}
+
+fn redirect_to_policies(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!(
+                "/_dashboard/sessions/{}/tool-intercept/webfetch/policies",
+                session_id
+            ),
+        ))
+        .finish()
+}
+
+pub async fn show_policies_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let policies = match db::list_webfetch_policies(pool.get_ref(), &session_id).await {
+        Ok(policies) => policies,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let approved_call_count =
+        match db::count_webfetch_approved_calls(pool.get_ref(), &session_id).await {
+            Ok(count) => count,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+    let html = pages::policies::render_policies_view(&session, &policies, approved_call_count);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn show_new_policy_form(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::policies::render_new_policy_form(&session);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn create_policy_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let policy_form = match parse_policy_form(&form) {
+        Ok(policy_form) => policy_form,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid policy: {}", e)),
+    };
+    if let Err(e) =
+        db::create_webfetch_policy(pool.get_ref(), &session_id, &policy_form.to_params()).await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_policies(&session_id)
+}
+
+pub async fn move_policy_up_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, policy_id) = path.into_inner();
+    if let Err(e) = db::move_webfetch_policy_up(pool.get_ref(), &session_id, &policy_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_policies(&session_id)
+}
+
+pub async fn delete_policy_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, policy_id) = path.into_inner();
+    if let Err(e) = db::delete_webfetch_policy(pool.get_ref(), &policy_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_policies(&session_id)
+}
+
+pub async fn clear_approved_calls_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::clear_webfetch_approved_calls(pool.get_ref(), &session_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_policies(&session_id)
+}
@@ -1,24 +1,101 @@
 use actix_web::{web, HttpRequest, HttpResponse};
-use common::config::AppConfig;
-use proxy::webfetch::ApprovalQueue;
+use common::config::SharedConfig;
+use proxy::concurrency::{acquire_session_slot, hold_slot_for_body, SessionLimiter, SessionSlot};
+use proxy::hooks::HookRegistry;
+use proxy::payload::read_request_body;
 use sqlx::SqlitePool;
 
+/// A proxied request let through: its body, read under the session's size
+/// limit, and the session's request slot, held for the length of the request.
+struct AdmittedRequest {
+    body: web::Bytes,
+    session_slot: Option<SessionSlot>,
+}
+
+/// Hand the session slot to the response body, which frees it once the
+/// response (streamed or not) has been sent.
+fn release_slot_with_response(
+    resp: Result<HttpResponse, actix_web::Error>,
+    session_slot: Option<SessionSlot>,
+) -> Result<HttpResponse, actix_web::Error> {
+    match session_slot {
+        Some(session_slot) => resp.map(|resp| hold_slot_for_body(resp, session_slot)),
+        None => resp,
+    }
+}
+
+/// Read the body and take one of the session's request slots. Unknown
+/// sessions get the global size limit and no slot; the proxy handler reports them.
+async fn admit_proxy_request(
+    req: &HttpRequest,
+    payload: web::Payload,
+    pool: &SqlitePool,
+    config: &SharedConfig,
+    limiter: &SessionLimiter,
+) -> Result<AdmittedRequest, HttpResponse> {
+    let session_id = req.match_info().get("session_id").unwrap_or("");
+    let session = db::get_session(pool, session_id).await.ok().flatten();
+    let max_payload_bytes = config.read().unwrap().max_payload_bytes;
+    let body = read_request_body(req, payload, pool, session.as_ref(), max_payload_bytes).await?;
+    let session_slot = match &session {
+        Some(session) => Some(acquire_session_slot(limiter, session).await?),
+        None => None,
+    };
+    Ok(AdmittedRequest { body, session_slot })
+}
+
 pub async fn proxy_catch_all(
     req: HttpRequest,
-    body: web::Bytes,
+    payload: web::Payload,
     pool: web::Data<SqlitePool>,
     client: web::Data<reqwest::Client>,
-    approval_queue: web::Data<ApprovalQueue>,
-    config: web::Data<AppConfig>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
+    limiter: web::Data<SessionLimiter>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    proxy::proxy_handler(req, body, pool, client, approval_queue, config).await
+    let AdmittedRequest { body, session_slot } =
+        match admit_proxy_request(&req, payload, &pool, &config, &limiter).await {
+            Ok(admitted_request) => admitted_request,
+            Err(resp) => return Ok(resp),
+        };
+    let resp = proxy::proxy_handler(req, body, pool, client, hook_registry, config).await;
+    release_slot_with_response(resp, session_slot)
 }
 
 pub async fn bedrock_invoke(
     req: HttpRequest,
-    body: web::Bytes,
+    payload: web::Payload,
+    pool: web::Data<SqlitePool>,
+    client: web::Data<reqwest::Client>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
+    limiter: web::Data<SessionLimiter>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let AdmittedRequest { body, session_slot } =
+        match admit_proxy_request(&req, payload, &pool, &config, &limiter).await {
+            Ok(admitted_request) => admitted_request,
+            Err(resp) => return Ok(resp),
+        };
+    let resp =
+        proxy::bedrock::bedrock_streaming_handler(req, body, pool, client, hook_registry, config)
+            .await;
+    release_slot_with_response(resp, session_slot)
+}
+
+pub async fn vertex_invoke(
+    req: HttpRequest,
+    payload: web::Payload,
     pool: web::Data<SqlitePool>,
     client: web::Data<reqwest::Client>,
+    hook_registry: web::Data<HookRegistry>,
+    config: web::Data<SharedConfig>,
+    limiter: web::Data<SessionLimiter>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    proxy::bedrock::bedrock_streaming_handler(req, body, pool, client).await
+    let AdmittedRequest { body, session_slot } =
+        match admit_proxy_request(&req, payload, &pool, &config, &limiter).await {
+            Ok(admitted_request) => admitted_request,
+            Err(resp) => return Ok(resp),
+        };
+    let resp = proxy::vertex::vertex_handler(req, body, pool, client, hook_registry, config).await;
+    release_slot_with_response(resp, session_slot)
 }
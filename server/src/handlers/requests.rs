@@ -1,21 +1,68 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{http::header::ContentDisposition, web, HttpRequest, HttpResponse};
+use common::dataset::{build_dataset_record, DatasetOptions};
+use common::fixture::build_request_fixture;
+use common::config::SharedConfig;
+use pages::detail::{
+    decode_block_source, find_message_block, find_request_download, get_block_file_extension,
+    ExportFilters, RequestAnnotations, RequestNeighbors,
+};
+use pages::requests::{RequestListFilters, RequestListRows};
 use sqlx::SqlitePool;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use templates::Pagination;
 
+use super::users::get_dashboard_author;
+
+/// Redirect to the request logged under `?id=`, e.g. an `x-proxy-request-id`.
+pub async fn find_request_page(
+    pool: web::Data<SqlitePool>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let request_id = query.get("id").map(|id| id.trim()).unwrap_or("");
+    if request_id.is_empty() {
+        return HttpResponse::BadRequest().body("Missing request id");
+    }
+    let request_summary = match db::get_request_summary(pool.get_ref(), request_id).await {
+        Ok(Some(request_summary)) => request_summary,
+        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!(
+                "/_dashboard/sessions/{}/requests/{}",
+                request_summary.session_id, request_summary.id
+            ),
+        ))
+        .finish()
+}
+
 pub async fn show_requests_page(
     pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
     path: web::Path<String>,
     query: web::Query<HashMap<String, String>>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
     let auto_refresh = query.get("refresh").map(|field| field.as_str()) == Some("on");
+    let request_list_filters = RequestListFilters {
+        stop_reason: query
+            .get("stop_reason")
+            .map(|field| field.as_str())
+            .filter(|field| !field.is_empty()),
+        client: query
+            .get("client")
+            .map(|field| field.as_str())
+            .filter(|field| !field.is_empty()),
+        intercepted: query.get("intercepted").map(|field| field.as_str()) == Some("on"),
+    };
     let page: i64 = query
         .get("page")
         .and_then(|page_str| page_str.parse().ok())
         .unwrap_or(1)
         .max(1);
-    let per_page: i64 = 50;
+    let per_page = config.read().unwrap().dashboard_per_page;
 
     let session = match db::get_session(pool.get_ref(), &session_id).await {
         Ok(Some(session)) => session,
@@ -23,32 +70,96 @@ pub async fn show_requests_page(
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
 
-    let total = match db::count_requests(pool.get_ref(), &session_id).await {
+    let total = match db::count_requests(
+        pool.get_ref(),
+        &session_id,
+        request_list_filters.stop_reason,
+        request_list_filters.client,
+        request_list_filters.intercepted,
+    )
+    .await
+    {
         Ok(total) => total,
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
 
     let offset = (page - 1) * per_page;
-    let requests =
-        match db::list_requests_paginated(pool.get_ref(), &session_id, per_page, offset).await {
-            Ok(requests) => requests,
-            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
-        };
+    let requests = match db::list_request_summaries_paginated(
+        pool.get_ref(),
+        &session_id,
+        request_list_filters.stop_reason,
+        request_list_filters.client,
+        request_list_filters.intercepted,
+        per_page,
+        offset,
+    )
+    .await
+    {
+        Ok(requests) => requests,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let round_requests = match db::list_round_request_summaries_paginated(
+        pool.get_ref(),
+        &session_id,
+        request_list_filters.stop_reason,
+        request_list_filters.client,
+        request_list_filters.intercepted,
+        per_page,
+        offset,
+    )
+    .await
+    {
+        Ok(round_requests) => round_requests,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let duplicate_ids: HashMap<String, String> = match db::list_duplicate_request_ids(
+        pool.get_ref(),
+        &session_id,
+        request_list_filters.stop_reason,
+        request_list_filters.client,
+        request_list_filters.intercepted,
+        per_page,
+        offset,
+    )
+    .await
+    {
+        Ok(duplicate_ids) => duplicate_ids.into_iter().collect(),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let clients = match db::list_request_clients(pool.get_ref(), &session_id).await {
+        Ok(clients) => clients,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
 
     let base_url = format!("/_dashboard/sessions/{}/requests", session_id);
-    let extra_params = if auto_refresh {
+    let mut extra_params = if auto_refresh {
         "&refresh=on".to_string()
     } else {
         String::new()
     };
+    extra_params.push_str(&request_list_filters.to_query_params());
     let pagination = Pagination::new(page, total, per_page, &base_url, &extra_params);
 
-    let html =
-        pages::requests::render_requests_view(&session, &requests, auto_refresh, &pagination);
+    let html = pages::requests::render_requests_view(
+        &session,
+        &RequestListRows {
+            requests: &requests,
+            round_requests: &round_requests,
+            duplicate_ids: &duplicate_ids,
+        },
+        &request_list_filters,
+        &clients,
+        auto_refresh,
+        &pagination,
+    );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
 pub async fn show_request_detail_page(
+    req: HttpRequest,
     pool: web::Data<SqlitePool>,
     path: web::Path<(String, String)>,
 ) -> HttpResponse {
@@ -74,15 +185,144 @@ pub async fn show_request_detail_page(
         .await
         .unwrap_or(None);
 
+    let notes = match db::list_request_notes(pool.get_ref(), &request_id).await {
+        Ok(notes) => notes,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let star_authors = match db::list_request_star_authors(pool.get_ref(), &request_id).await {
+        Ok(star_authors) => star_authors,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let author = get_dashboard_author(&req);
+
+    let parent_request_id = request
+        .parent_request_id
+        .clone()
+        .unwrap_or_else(|| request.id.to_string());
+    let round_ids = match db::list_round_request_ids(pool.get_ref(), &parent_request_id).await {
+        Ok(round_ids) => round_ids,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
     let html = pages::detail::render_request_detail_view(
         &request,
         &session,
-        prev_id.as_deref(),
-        next_id.as_deref(),
+        &RequestNeighbors {
+            prev_id: prev_id.as_deref(),
+            next_id: next_id.as_deref(),
+        },
+        &round_ids,
+        &RequestAnnotations {
+            notes: &notes,
+            star_authors: &star_authors,
+            author: &author,
+        },
     );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
+/// Add a note to the request as the current dashboard user.
+pub async fn add_request_note_post(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    let note = form.get("note").map(|note| note.trim()).unwrap_or_default();
+    if note.is_empty() {
+        return HttpResponse::BadRequest().body("Note is empty");
+    }
+    if let Err(e) = find_session_request(pool.get_ref(), &session_id, &request_id).await {
+        return e;
+    }
+    let author = get_dashboard_author(&req);
+    if let Err(e) = db::create_request_note(pool.get_ref(), &request_id, &author, note).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_request_detail(&session_id, &request_id)
+}
+
+/// Star the request for the current dashboard user, or remove their star.
+pub async fn toggle_request_star_post(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    if let Err(e) = find_session_request(pool.get_ref(), &session_id, &request_id).await {
+        return e;
+    }
+    let author = get_dashboard_author(&req);
+    if let Err(e) = db::toggle_request_star(pool.get_ref(), &request_id, &author).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_request_detail(&session_id, &request_id)
+}
+
+/// Ask the title model for a new title of the request's conversation.
+pub async fn regenerate_request_title_post(
+    pool: web::Data<SqlitePool>,
+    client: web::Data<reqwest::Client>,
+    config: web::Data<SharedConfig>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) if request.session_id == session.id => request,
+        Ok(_) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let title_model = config.read().unwrap().title_model.clone();
+    let title = match proxy::titles::request_conversation_title(
+        client.get_ref(),
+        &session,
+        &title_model,
+        &request,
+    )
+    .await
+    {
+        Ok(title) => title,
+        Err(e) => {
+            return HttpResponse::BadGateway().body(format!("Title generation failed: {}", e))
+        }
+    };
+    if let Err(e) = db::set_request_title(pool.get_ref(), &request_id, &title).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_request_detail(&session_id, &request_id)
+}
+
+/// Check the request exists and belongs to the session before annotating it.
+async fn find_session_request(
+    pool: &SqlitePool,
+    session_id: &str,
+    request_id: &str,
+) -> Result<(), HttpResponse> {
+    match db::get_request(pool, request_id).await {
+        Ok(Some(request)) if request.session_id.to_string() == session_id => Ok(()),
+        Ok(_) => Err(HttpResponse::NotFound().body("Request not found")),
+        Err(e) => Err(HttpResponse::InternalServerError().body(format!("DB error: {}", e))),
+    }
+}
+
+fn redirect_to_request_detail(session_id: &str, request_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!(
+                "/_dashboard/sessions/{}/requests/{}",
+                session_id, request_id
+            ),
+        ))
+        .finish()
+}
+
 pub async fn show_request_detail_subpage(
     pool: web::Data<SqlitePool>,
     path: web::Path<(String, String, String)>,
@@ -144,8 +384,161 @@ pub async fn show_request_detail_subpage(
         &query,
         &filters,
         keep_tool_pairs,
-        prev_id.as_deref(),
-        next_id.as_deref(),
+        &RequestNeighbors {
+            prev_id: prev_id.as_deref(),
+            next_id: next_id.as_deref(),
+        },
+    );
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Download the checked requests of a session, or all of them, as a JSONL
+/// dataset of prompt messages and completions, oldest first.
+pub async fn export_requests_jsonl(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let selected_ids: HashSet<&str> = query
+        .keys()
+        .filter_map(|key| key.strip_prefix("request:"))
+        .collect();
+    let dataset_options = DatasetOptions {
+        exclude_system: query.contains_key("exclude_system"),
+        strip_tool_use: query.contains_key("strip_tool_use"),
+    };
+
+    let requests = match db::list_requests(pool.get_ref(), &session_id).await {
+        Ok(requests) => requests,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let mut jsonl = String::new();
+    for request in requests.iter().rev() {
+        if !selected_ids.is_empty() && !selected_ids.contains(request.id.to_string().as_str()) {
+            continue;
+        }
+        let Some(record) = build_dataset_record(
+            request.messages_json.as_deref(),
+            request.system_json.as_deref(),
+            request.response_events_json.as_deref(),
+            request.response_body.as_deref(),
+            &dataset_options,
+        ) else {
+            continue;
+        };
+        jsonl.push_str(&record.to_string());
+        jsonl.push('\n');
+    }
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .insert_header(ContentDisposition::attachment(format!(
+            "session-{}.jsonl",
+            session_id
+        )))
+        .body(jsonl)
+}
+
+/// Download the whole request as one self-contained HTML file.
+pub async fn export_request_html(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let profile_id = session.profile_id.clone().unwrap_or_default();
+    let system_filters: Vec<String> = db::list_system_filters(pool.get_ref(), &profile_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|filter| filter.pattern)
+        .collect();
+    let tool_filters: Vec<String> = db::list_tool_filters(pool.get_ref(), &profile_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|filter| filter.name)
+        .collect();
+    let keep_tool_pairs = db::get_filter_profile_keep_tool_pairs(pool.get_ref(), &profile_id)
+        .await
+        .unwrap_or(0);
+
+    let html = pages::detail::render_request_export(
+        &request,
+        &session,
+        &ExportFilters {
+            system_filters: &system_filters,
+            tool_filters: &tool_filters,
+            keep_tool_pairs,
+        },
+    );
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header(ContentDisposition::attachment(format!(
+            "request-{}.html",
+            request.id
+        )))
+        .body(html)
+}
+
+pub async fn show_request_caching_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let session_id_str = request.session_id.to_string();
+    let previous_request = match db::get_older_request_for_model(
+        pool.get_ref(),
+        &session_id_str,
+        &request.created_at,
+        request.model.as_deref(),
+    )
+    .await
+    {
+        Ok(previous_request) => previous_request,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let prev_id = db::get_prev_request_id(pool.get_ref(), &session_id_str, &request.created_at)
+        .await
+        .unwrap_or(None);
+    let next_id = db::get_next_request_id(pool.get_ref(), &session_id_str, &request.created_at)
+        .await
+        .unwrap_or(None);
+
+    let html = pages::detail::render_request_caching_view(
+        &request,
+        &session,
+        previous_request.as_ref(),
+        &RequestNeighbors {
+            prev_id: prev_id.as_deref(),
+            next_id: next_id.as_deref(),
+        },
     );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
@@ -165,3 +558,94 @@ pub async fn clear_requests_post(
         ))
         .finish()
 }
+
+pub async fn download_request_block(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String, usize)>,
+) -> HttpResponse {
+    let (_session_id, request_id, block_index) = path.into_inner();
+
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let Some(block) = request
+        .messages_json
+        .as_deref()
+        .and_then(|messages_json| find_message_block(messages_json, block_index))
+    else {
+        return HttpResponse::NotFound().body("Block not found");
+    };
+    let decoded_source = match decode_block_source(&block) {
+        Ok(decoded_source) => decoded_source,
+        Err(reason) => return HttpResponse::NotFound().body(reason),
+    };
+
+    let file_name = format!(
+        "block-{}.{}",
+        block_index,
+        get_block_file_extension(&decoded_source.media_type)
+    );
+    HttpResponse::Ok()
+        .content_type(decoded_source.media_type)
+        .insert_header(ContentDisposition::attachment(file_name))
+        .body(decoded_source.bytes)
+}
+
+pub async fn download_request_file(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String, String)>,
+) -> HttpResponse {
+    let (_session_id, request_id, file_name) = path.into_inner();
+
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let Some(request_download) = find_request_download(&request, &file_name) else {
+        return HttpResponse::NotFound().body("File not recorded for this request");
+    };
+    HttpResponse::Ok()
+        .content_type(request_download.content_type)
+        .insert_header(ContentDisposition::attachment(format!(
+            "{}-{}",
+            request.id, request_download.file_name
+        )))
+        .body(request_download.content.to_string())
+}
+
+/// Download the request and its response as a test fixture for the proxy
+/// crate's tests.
+pub async fn download_request_fixture(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (_session_id, request_id) = path.into_inner();
+
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) => request,
+        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let Some(fixture) = build_request_fixture(
+        &request.path,
+        request.body_json.as_deref(),
+        request.response_status,
+        request.response_events_json.as_deref(),
+        request.response_body.as_deref(),
+    ) else {
+        return HttpResponse::NotFound().body("Request body not recorded as JSON");
+    };
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header(ContentDisposition::attachment(format!(
+            "{}-fixture.json",
+            request.id
+        )))
+        .body(serde_json::to_string_pretty(&fixture).unwrap_or_default())
+}
@@ -1,20 +1,57 @@
 use actix_web::{web, HttpResponse};
+use common::betas::{parse_beta_values, validate_beta_values, KNOWN_BETAS};
+use common::config::SharedConfig;
+use common::headers::{parse_header_patterns, validate_header_patterns};
+use common::models::Session;
+use common::upstream::UPSTREAM_PRESET_OPENAI_COMPATIBLE;
+use common::url::{parse_query_params, validate_query_params};
+use pages::presentation::REDACTED_TEXT;
+use proxy::concurrency::{get_session_load, SessionLimiter};
+use proxy::webfetch::ApprovalQueue;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
+use super::errors::RECENT_FAILURE_DAYS;
 use templates::Pagination;
 use uuid::Uuid;
 
-use crate::Args;
+/// Requests listed under "Recent Requests" on the home page.
+const HOME_RECENT_REQUESTS: i64 = 5;
 
-pub async fn show_home_page(pool: web::Data<SqlitePool>) -> HttpResponse {
+pub async fn show_home_page(
+    pool: web::Data<SqlitePool>,
+    approval_queue: web::Data<ApprovalQueue>,
+) -> HttpResponse {
     let session_count = db::count_sessions(pool.get_ref()).await.unwrap_or(0);
     let profile_count = db::count_filter_profiles(pool.get_ref()).await.unwrap_or(0);
-    let html = pages::home::render_home_view(session_count, profile_count);
+    let model_count = db::count_models(pool.get_ref()).await.unwrap_or(0);
+    let failure_count = db::count_recent_failures(pool.get_ref(), RECENT_FAILURE_DAYS)
+        .await
+        .unwrap_or(0);
+    let pending_count = proxy::webfetch::list_all_pending(approval_queue.get_ref()).len();
+    let request_stats = match db::get_request_stats(pool.get_ref()).await {
+        Ok(request_stats) => request_stats,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let recent_requests =
+        match db::list_recent_requests(pool.get_ref(), HOME_RECENT_REQUESTS).await {
+            Ok(recent_requests) => recent_requests,
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        };
+    let html = pages::home::render_home_view(
+        session_count,
+        profile_count,
+        model_count,
+        failure_count,
+        pending_count,
+        &request_stats,
+        &recent_requests,
+    );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
 pub async fn show_sessions_page(
     pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
     query: web::Query<HashMap<String, String>>,
 ) -> HttpResponse {
     let page: i64 = query
@@ -22,7 +59,7 @@ pub async fn show_sessions_page(
         .and_then(|page_str| page_str.parse().ok())
         .unwrap_or(1)
         .max(1);
-    let per_page: i64 = 50;
+    let per_page = config.read().unwrap().dashboard_per_page;
 
     let total = match db::count_sessions(pool.get_ref()).await {
         Ok(total) => total,
@@ -51,6 +88,211 @@ pub async fn show_new_session_form(pool: web::Data<SqlitePool>) -> HttpResponse
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
+/// Read a trimmed form field; an empty field means `None`.
+fn read_optional_field(form: &HashMap<String, String>, key: &str) -> Option<String> {
+    form.get(key)
+        .map(|field| field.trim())
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+}
+
+/// Put back the stored value of every field that still holds presentation
+/// mode's mask.
+fn restore_masked_fields(
+    form: &mut HashMap<String, String>,
+    stored_fields: &[(&str, Option<&str>)],
+) {
+    for &(key, stored_value) in stored_fields {
+        let is_masked = form
+            .get(key)
+            .is_some_and(|field| field.contains(REDACTED_TEXT));
+        if is_masked {
+            form.insert(key.to_string(), stored_value.unwrap_or_default().to_string());
+        }
+    }
+}
+
+/// Keep the stored credentials that the edit form shows masked, so saving it
+/// in presentation mode doesn't replace a real key with the mask.
+fn restore_masked_credentials(form: &mut HashMap<String, String>, session: &Session) {
+    let credential_fields = [
+        ("auth_header", session.auth_header.as_deref()),
+        ("x_api_key", session.x_api_key.as_deref()),
+        ("auth_query_params", session.auth_query_params.as_deref()),
+        ("aws_access_key_id", session.aws_access_key_id.as_deref()),
+        (
+            "aws_secret_access_key",
+            session.aws_secret_access_key.as_deref(),
+        ),
+        ("aws_session_token", session.aws_session_token.as_deref()),
+    ];
+    restore_masked_fields(form, &credential_fields);
+}
+
+/// Read the `upstream_preset` field, rejecting unknown presets.
+fn read_upstream_preset(form: &HashMap<String, String>) -> Result<Option<String>, HttpResponse> {
+    match read_optional_field(form, "upstream_preset") {
+        Some(key) if common::upstream::find_by_key(&key).is_none() => Err(
+            HttpResponse::BadRequest().body(format!("Unknown upstream preset: {}", key)),
+        ),
+        upstream_preset => Ok(upstream_preset),
+    }
+}
+
+/// Read the `header_preset` field, rejecting unknown presets.
+fn read_header_preset(form: &HashMap<String, String>) -> Result<Option<String>, HttpResponse> {
+    match read_optional_field(form, "header_preset") {
+        Some(key) if common::header_presets::find_by_key(&key).is_none() => {
+            Err(HttpResponse::BadRequest().body(format!("Unknown header preset: {}", key)))
+        }
+        header_preset => Ok(header_preset),
+    }
+}
+
+/// Read the concurrency limit and overflow mode; an empty limit means unlimited.
+fn read_concurrency_limit(
+    form: &HashMap<String, String>,
+) -> Result<(Option<i64>, Option<String>), HttpResponse> {
+    let max_concurrent_requests = match read_optional_field(form, "max_concurrent_requests") {
+        Some(field) => match field.parse::<i64>() {
+            Ok(limit) if limit > 0 => Some(limit),
+            _ => {
+                return Err(HttpResponse::BadRequest()
+                    .body("Max concurrent requests must be a positive number"))
+            }
+        },
+        None => None,
+    };
+    match read_optional_field(form, "concurrency_overflow") {
+        Some(key) if common::concurrency::find_by_key(&key).is_none() => Err(
+            HttpResponse::BadRequest().body(format!("Unknown overflow mode: {}", key)),
+        ),
+        concurrency_overflow => Ok((max_concurrent_requests, concurrency_overflow)),
+    }
+}
+
+/// Read the session's request body limit; an empty field uses the global limit.
+fn read_max_body_bytes(form: &HashMap<String, String>) -> Result<Option<i64>, HttpResponse> {
+    match read_optional_field(form, "max_body_bytes") {
+        Some(field) => match field.parse::<i64>() {
+            Ok(max_body_bytes) if max_body_bytes > 0 => Ok(Some(max_body_bytes)),
+            _ => Err(HttpResponse::BadRequest().body("Max body bytes must be a positive number")),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Read a header pattern textarea as one lowercased pattern per line,
+/// rejecting lines that aren't header names.
+fn read_header_patterns(
+    form: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<String>, HttpResponse> {
+    let Some(field) = read_optional_field(form, key) else {
+        return Ok(None);
+    };
+    if let Err(e) = validate_header_patterns(&field) {
+        return Err(HttpResponse::BadRequest().body(format!("Header rules: {}", e)));
+    }
+    Ok(Some(parse_header_patterns(Some(&field)).join("\n")))
+}
+
+/// The session's header rule fields.
+struct HeaderRuleFields {
+    forward_header_allow: Option<String>,
+    forward_header_deny: Option<String>,
+    response_header_allow: Option<String>,
+    response_header_deny: Option<String>,
+}
+
+fn read_header_rule_fields(
+    form: &HashMap<String, String>,
+) -> Result<HeaderRuleFields, HttpResponse> {
+    Ok(HeaderRuleFields {
+        forward_header_allow: read_header_patterns(form, "forward_header_allow")?,
+        forward_header_deny: read_header_patterns(form, "forward_header_deny")?,
+        response_header_allow: read_header_patterns(form, "response_header_allow")?,
+        response_header_deny: read_header_patterns(form, "response_header_deny")?,
+    })
+}
+
+/// The session's query auth parameters, one `name=value` per line.
+fn read_auth_query_params(form: &HashMap<String, String>) -> Result<Option<String>, HttpResponse> {
+    let Some(field) = read_optional_field(form, "auth_query_params") else {
+        return Ok(None);
+    };
+    if let Err(e) = validate_query_params(&field) {
+        return Err(HttpResponse::BadRequest().body(format!("Query auth parameters: {}", e)));
+    }
+    let query_params = parse_query_params(Some(&field))
+        .into_iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>();
+    Ok(Some(query_params.join("\n")))
+}
+
+/// Read a beta list: the checked known betas, named `{key}:{value}`, plus the
+/// comma-separated `{key}` field for any others.
+fn read_beta_values(
+    form: &HashMap<String, String>,
+    key: &str,
+) -> Result<Option<String>, HttpResponse> {
+    let mut betas: Vec<String> = KNOWN_BETAS
+        .iter()
+        .filter(|known_beta| form.contains_key(&format!("{}:{}", key, known_beta.value)))
+        .map(|known_beta| known_beta.value.to_string())
+        .collect();
+    if let Some(field) = read_optional_field(form, key) {
+        if let Err(e) = validate_beta_values(&field) {
+            return Err(HttpResponse::BadRequest().body(format!("Anthropic betas: {}", e)));
+        }
+        for beta in parse_beta_values(Some(&field)) {
+            if !betas.contains(&beta) {
+                betas.push(beta);
+            }
+        }
+    }
+    Ok((!betas.is_empty()).then(|| betas.join("\n")))
+}
+
+/// Read the `completion_webhook_url` field, which must be an http(s) URL.
+fn read_completion_webhook_url(
+    form: &HashMap<String, String>,
+) -> Result<Option<String>, HttpResponse> {
+    let Some(completion_webhook_url) = read_optional_field(form, "completion_webhook_url") else {
+        return Ok(None);
+    };
+    match reqwest::Url::parse(&completion_webhook_url) {
+        Ok(parsed_url) if matches!(parsed_url.scheme(), "http" | "https") => {
+            Ok(Some(completion_webhook_url))
+        }
+        _ => Err(HttpResponse::BadRequest()
+            .body("Completion webhook must be an http:// or https:// URL")),
+    }
+}
+
+/// Read the `hostname` field, which must be a valid hostname that no session
+/// other than `session_id` (`None` for a new session) routes from.
+async fn read_session_hostname(
+    pool: &SqlitePool,
+    form: &HashMap<String, String>,
+    session_id: Option<&str>,
+) -> Result<Option<String>, HttpResponse> {
+    let Some(hostname_field) = read_optional_field(form, "hostname") else {
+        return Ok(None);
+    };
+    let hostname = proxy::hostname::parse_session_hostname(&hostname_field)
+        .map_err(|e| HttpResponse::BadRequest().body(format!("Hostname {}", e)))?;
+    match db::find_session_id_by_hostname(pool, &hostname).await {
+        Ok(Some(other_session_id)) if Some(other_session_id.as_str()) != session_id => {
+            Err(HttpResponse::BadRequest()
+                .body(format!("Hostname {} is used by another session", hostname)))
+        }
+        Ok(_) => Ok(Some(hostname)),
+        Err(e) => Err(HttpResponse::InternalServerError().body(format!("DB error: {}", e))),
+    }
+}
+
 pub async fn create_session_post(
     pool: web::Data<SqlitePool>,
     form: web::Form<HashMap<String, String>>,
@@ -61,31 +303,61 @@ pub async fn create_session_post(
         }
         _ => return HttpResponse::BadRequest().body("Name and target_url are required"),
     };
-    let tls_verify_disabled = form.get("tls_verify_disabled").is_some_and(|field| field == "1");
-    let auth_header = form.get("auth_header").and_then(|field| {
-        let trimmed = field.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
-    let x_api_key = form.get("x_api_key").and_then(|field| {
-        let trimmed = field.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
-    let profile_id = form.get("profile_id").and_then(|field| {
-        let trimmed = field.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
+    let upstream_preset = match read_upstream_preset(&form) {
+        Ok(upstream_preset) => upstream_preset,
+        Err(resp) => return resp,
+    };
+    let header_preset = match read_header_preset(&form) {
+        Ok(header_preset) => header_preset,
+        Err(resp) => return resp,
+    };
+    let beta_inject = match read_beta_values(&form, "beta_inject") {
+        Ok(beta_inject) => beta_inject,
+        Err(resp) => return resp,
+    };
+    let beta_strip = match read_beta_values(&form, "beta_strip") {
+        Ok(beta_strip) => beta_strip,
+        Err(resp) => return resp,
+    };
+    let completion_webhook_url = match read_completion_webhook_url(&form) {
+        Ok(completion_webhook_url) => completion_webhook_url,
+        Err(resp) => return resp,
+    };
+    let hostname = match read_session_hostname(&pool, &form, None).await {
+        Ok(hostname) => hostname,
+        Err(resp) => return resp,
+    };
+    let (max_concurrent_requests, concurrency_overflow) = match read_concurrency_limit(&form) {
+        Ok(limit) => limit,
+        Err(resp) => return resp,
+    };
+    let max_body_bytes = match read_max_body_bytes(&form) {
+        Ok(max_body_bytes) => max_body_bytes,
+        Err(resp) => return resp,
+    };
+    let header_rule_fields = match read_header_rule_fields(&form) {
+        Ok(header_rule_fields) => header_rule_fields,
+        Err(resp) => return resp,
+    };
+    let mut auth_query_params = match read_auth_query_params(&form) {
+        Ok(auth_query_params) => auth_query_params,
+        Err(resp) => return resp,
+    };
+    let mut tls_verify_disabled = form.get("tls_verify_disabled").is_some_and(|field| field == "1");
+    let mut auth_header = read_optional_field(&form, "auth_header");
+    let mut x_api_key = read_optional_field(&form, "x_api_key");
+    let profile_id = read_optional_field(&form, "profile_id");
+    let aws_region = read_optional_field(&form, "aws_region");
+    let aws_access_key_id = read_optional_field(&form, "aws_access_key_id");
+    let aws_secret_access_key = read_optional_field(&form, "aws_secret_access_key");
+    let aws_session_token = read_optional_field(&form, "aws_session_token");
+    // Local servers usually have self-signed or no certificates and no auth.
+    if upstream_preset.as_deref() == Some(UPSTREAM_PRESET_OPENAI_COMPATIBLE) {
+        tls_verify_disabled = true;
+        auth_header = None;
+        x_api_key = None;
+        auth_query_params = None;
+    }
 
     let id = Uuid::new_v4();
     let id_str = id.to_string();
@@ -99,6 +371,24 @@ pub async fn create_session_post(
             auth_header: auth_header.as_deref(),
             x_api_key: x_api_key.as_deref(),
             profile_id: profile_id.as_deref(),
+            aws_region: aws_region.as_deref(),
+            aws_access_key_id: aws_access_key_id.as_deref(),
+            aws_secret_access_key: aws_secret_access_key.as_deref(),
+            aws_session_token: aws_session_token.as_deref(),
+            upstream_preset: upstream_preset.as_deref(),
+            max_concurrent_requests,
+            concurrency_overflow: concurrency_overflow.as_deref(),
+            max_body_bytes,
+            forward_header_allow: header_rule_fields.forward_header_allow.as_deref(),
+            forward_header_deny: header_rule_fields.forward_header_deny.as_deref(),
+            response_header_allow: header_rule_fields.response_header_allow.as_deref(),
+            response_header_deny: header_rule_fields.response_header_deny.as_deref(),
+            auth_query_params: auth_query_params.as_deref(),
+            header_preset: header_preset.as_deref(),
+            beta_inject: beta_inject.as_deref(),
+            beta_strip: beta_strip.as_deref(),
+            completion_webhook_url: completion_webhook_url.as_deref(),
+            hostname: hostname.as_deref(),
         },
     )
     .await
@@ -113,7 +403,9 @@ pub async fn create_session_post(
 pub async fn show_session_page(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>,
-    args: web::Data<Args>,
+    config: web::Data<SharedConfig>,
+    client: web::Data<reqwest::Client>,
+    limiter: web::Data<SessionLimiter>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
 
@@ -132,15 +424,40 @@ pub async fn show_session_page(
         None
     };
 
-    let html =
-        pages::session_show::render_session_view(&session, args.port, profile_name.as_deref());
+    let discovered_models =
+        if session.upstream_preset.as_deref() == Some(UPSTREAM_PRESET_OPENAI_COMPATIBLE) {
+            Some(
+                proxy::openai::list_models(&session, client.get_ref())
+                    .await
+                    .map_err(|e| e.to_string()),
+            )
+        } else {
+            None
+        };
+
+    let (proxy_origin, hostname_origin) = {
+        let config = config.read().unwrap();
+        let hostname_origin = session
+            .hostname
+            .as_deref()
+            .map(|hostname| config.proxy_origin_for_host(hostname));
+        (config.proxy_origin(), hostname_origin)
+    };
+    let html = pages::session_show::render_session_view(
+        &session,
+        &proxy_origin,
+        hostname_origin.as_deref(),
+        profile_name.as_deref(),
+        discovered_models.as_ref(),
+        &get_session_load(&limiter, &session.id.to_string()),
+    );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
 pub async fn show_edit_session_form(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>,
-    args: web::Data<Args>,
+    config: web::Data<SharedConfig>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
 
@@ -153,7 +470,11 @@ pub async fn show_edit_session_form(
     let profiles = db::list_filter_profiles(pool.get_ref())
         .await
         .unwrap_or_default();
-    let html = pages::sessions::render_edit_session_form(&session, args.port, &profiles);
+    let html = pages::sessions::render_edit_session_form(
+        &session,
+        &config.read().unwrap().proxy_origin(),
+        &profiles,
+    );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
@@ -163,37 +484,67 @@ pub async fn update_session_post(
     form: web::Form<HashMap<String, String>>,
 ) -> HttpResponse {
     let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let mut form = form.into_inner();
+    restore_masked_credentials(&mut form, &session);
     let (name, target_url) = match (form.get("name"), form.get("target_url")) {
         (Some(name), Some(target_url)) if !name.is_empty() && !target_url.is_empty() => {
             (name.clone(), target_url.clone())
         }
         _ => return HttpResponse::BadRequest().body("Name and target_url are required"),
     };
+    let upstream_preset = match read_upstream_preset(&form) {
+        Ok(upstream_preset) => upstream_preset,
+        Err(resp) => return resp,
+    };
+    let header_preset = match read_header_preset(&form) {
+        Ok(header_preset) => header_preset,
+        Err(resp) => return resp,
+    };
+    let beta_inject = match read_beta_values(&form, "beta_inject") {
+        Ok(beta_inject) => beta_inject,
+        Err(resp) => return resp,
+    };
+    let beta_strip = match read_beta_values(&form, "beta_strip") {
+        Ok(beta_strip) => beta_strip,
+        Err(resp) => return resp,
+    };
+    let completion_webhook_url = match read_completion_webhook_url(&form) {
+        Ok(completion_webhook_url) => completion_webhook_url,
+        Err(resp) => return resp,
+    };
+    let hostname = match read_session_hostname(&pool, &form, Some(&session_id)).await {
+        Ok(hostname) => hostname,
+        Err(resp) => return resp,
+    };
+    let (max_concurrent_requests, concurrency_overflow) = match read_concurrency_limit(&form) {
+        Ok(limit) => limit,
+        Err(resp) => return resp,
+    };
+    let max_body_bytes = match read_max_body_bytes(&form) {
+        Ok(max_body_bytes) => max_body_bytes,
+        Err(resp) => return resp,
+    };
+    let header_rule_fields = match read_header_rule_fields(&form) {
+        Ok(header_rule_fields) => header_rule_fields,
+        Err(resp) => return resp,
+    };
+    let auth_query_params = match read_auth_query_params(&form) {
+        Ok(auth_query_params) => auth_query_params,
+        Err(resp) => return resp,
+    };
     let tls_verify_disabled = form.get("tls_verify_disabled").is_some_and(|field| field == "1");
-    let auth_header = form.get("auth_header").and_then(|field| {
-        let trimmed = field.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
-    let x_api_key = form.get("x_api_key").and_then(|field| {
-        let trimmed = field.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
-    let profile_id = form.get("profile_id").and_then(|field| {
-        let trimmed = field.trim();
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
-        }
-    });
+    let auth_header = read_optional_field(&form, "auth_header");
+    let x_api_key = read_optional_field(&form, "x_api_key");
+    let profile_id = read_optional_field(&form, "profile_id");
+    let aws_region = read_optional_field(&form, "aws_region");
+    let aws_access_key_id = read_optional_field(&form, "aws_access_key_id");
+    let aws_secret_access_key = read_optional_field(&form, "aws_secret_access_key");
+    let aws_session_token = read_optional_field(&form, "aws_session_token");
 
     match db::update_session(
         pool.get_ref(),
@@ -205,6 +556,24 @@ pub async fn update_session_post(
             auth_header: auth_header.as_deref(),
             x_api_key: x_api_key.as_deref(),
             profile_id: profile_id.as_deref(),
+            aws_region: aws_region.as_deref(),
+            aws_access_key_id: aws_access_key_id.as_deref(),
+            aws_secret_access_key: aws_secret_access_key.as_deref(),
+            aws_session_token: aws_session_token.as_deref(),
+            upstream_preset: upstream_preset.as_deref(),
+            max_concurrent_requests,
+            concurrency_overflow: concurrency_overflow.as_deref(),
+            max_body_bytes,
+            forward_header_allow: header_rule_fields.forward_header_allow.as_deref(),
+            forward_header_deny: header_rule_fields.forward_header_deny.as_deref(),
+            response_header_allow: header_rule_fields.response_header_allow.as_deref(),
+            response_header_deny: header_rule_fields.response_header_deny.as_deref(),
+            auth_query_params: auth_query_params.as_deref(),
+            header_preset: header_preset.as_deref(),
+            beta_inject: beta_inject.as_deref(),
+            beta_strip: beta_strip.as_deref(),
+            completion_webhook_url: completion_webhook_url.as_deref(),
+            hostname: hostname.as_deref(),
         },
     )
     .await
@@ -216,6 +585,32 @@ pub async fn update_session_post(
     }
 }
 
+pub async fn set_default_session_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_default_session(pool.get_ref(), &session_id, true).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header(("Location", format!("/_dashboard/sessions/{}", session_id)))
+        .finish()
+}
+
+pub async fn clear_default_session_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    if let Err(e) = db::set_default_session(pool.get_ref(), &session_id, false).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header(("Location", format!("/_dashboard/sessions/{}", session_id)))
+        .finish()
+}
+
 pub async fn delete_session_post(
     pool: web::Data<SqlitePool>,
     path: web::Path<String>,
@@ -228,3 +623,31 @@ pub async fn delete_session_post(
         .insert_header(("Location", "/_dashboard/sessions"))
         .finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_masked_fields_keeps_stored_values() {
+        let mut form: HashMap<String, String> = [
+            ("x_api_key", REDACTED_TEXT),
+            ("auth_header", "Bearer new-token-value"),
+            ("aws_session_token", "x[redacted]"),
+            ("name", REDACTED_TEXT),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let stored_fields = [
+            ("x_api_key", Some("sk-ant-api03-abcdefghijkl")),
+            ("auth_header", Some("Bearer abcdefghijkl")),
+            ("aws_session_token", None),
+        ];
+        restore_masked_fields(&mut form, &stored_fields);
+        assert_eq!(form["x_api_key"], "sk-ant-api03-abcdefghijkl");
+        assert_eq!(form["auth_header"], "Bearer new-token-value");
+        assert_eq!(form["aws_session_token"], "");
+        assert_eq!(form["name"], REDACTED_TEXT);
+    }
+}
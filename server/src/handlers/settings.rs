@@ -0,0 +1,43 @@
+use actix_web::{web, HttpResponse};
+use common::config::{SharedConfig, RUNTIME_SETTINGS};
+use common::truncate;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+pub async fn show_settings_page(config: web::Data<SharedConfig>) -> HttpResponse {
+    let config = config.read().unwrap().clone();
+    let html = pages::settings::render_settings_view(&config);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn update_settings_post(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let mut updated_config = config.read().unwrap().clone();
+    let mut changed_values = Vec::new();
+    for setting in RUNTIME_SETTINGS {
+        let Some(value) = form.get(setting.key) else {
+            continue;
+        };
+        // Browsers submit textarea line breaks as CRLF.
+        let value = value.replace("\r\n", "\n");
+        if let Err(e) = updated_config.set_runtime_setting(setting.key, &value) {
+            return HttpResponse::BadRequest().body(format!("{}: {}", setting.label, e));
+        }
+        changed_values.push((setting.key, value));
+    }
+    for (key, value) in &changed_values {
+        if let Err(e) = db::set_setting(pool.get_ref(), key, value).await {
+            return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+        }
+    }
+    templates::set_collapse_threshold(updated_config.collapse_threshold);
+    truncate::set_truncation_profiles(updated_config.build_truncation_profiles());
+    db::set_body_compression_threshold(updated_config.body_compression_threshold);
+    *config.write().unwrap() = updated_config;
+    HttpResponse::SeeOther()
+        .insert_header(("Location", "/_dashboard/settings"))
+        .finish()
+}
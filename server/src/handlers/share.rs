@@ -0,0 +1,79 @@
+use actix_web::{cookie::time, cookie::Cookie, web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use common::share::{
+    sign_share_token, verify_share_token, ShareGrant, ShareSecret, SHARE_COOKIE,
+    SHARE_LINK_EXPIRIES,
+};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Sign a link to the request that works without dashboard credentials.
+pub async fn create_share_link_post(
+    req: HttpRequest,
+    pool: web::Data<SqlitePool>,
+    share_secret: web::Data<ShareSecret>,
+    path: web::Path<(String, String)>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let (session_id, request_id) = path.into_inner();
+    let expiry = form
+        .get("expires_hours")
+        .and_then(|value| value.parse::<i64>().ok())
+        .and_then(|hours| SHARE_LINK_EXPIRIES.iter().find(|(h, _)| *h == hours));
+    let Some(&(expires_hours, expires_label)) = expiry else {
+        return HttpResponse::BadRequest().body("Invalid expiry");
+    };
+
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let request = match db::get_request(pool.get_ref(), &request_id).await {
+        Ok(Some(request)) if request.session_id.to_string() == session_id => request,
+        Ok(_) => return HttpResponse::NotFound().body("Request not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let share_grant = ShareGrant {
+        session_id,
+        request_id,
+        expires_at: (expires_hours > 0).then(|| Utc::now().timestamp() + expires_hours * 60 * 60),
+    };
+    let token = sign_share_token(&share_secret.0, &share_grant);
+    let connection_info = req.connection_info();
+    let share_url = format!(
+        "{}://{}/_share/{}",
+        connection_info.scheme(),
+        connection_info.host(),
+        token
+    );
+    let html = pages::detail::render_share_link_view(&request, &session, &share_url, expires_label);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Open a share link: keep its token in a cookie scoped to the shared
+/// request's pages and go to the request.
+pub async fn open_share_link(
+    share_secret: web::Data<ShareSecret>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let token = path.into_inner();
+    let now = Utc::now().timestamp();
+    let Some(share_grant) = verify_share_token(&share_secret.0, &token, now) else {
+        return HttpResponse::NotFound().body("Share link not found or expired");
+    };
+    let request_path = share_grant.build_request_path();
+    let mut cookie = Cookie::build(SHARE_COOKIE, token)
+        .path(request_path.clone())
+        .http_only(true)
+        .finish();
+    match share_grant.expires_at {
+        Some(expires_at) => cookie.set_max_age(time::Duration::seconds(expires_at - now)),
+        None => cookie.make_permanent(),
+    }
+    HttpResponse::SeeOther()
+        .insert_header(("Location", request_path))
+        .cookie(cookie)
+        .finish()
+}
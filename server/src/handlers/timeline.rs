@@ -0,0 +1,25 @@
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+
+pub async fn show_timeline_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let requests = match db::list_requests(pool.get_ref(), &session_id).await {
+        Ok(requests) => requests,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let events = match db::list_session_events(pool.get_ref(), &session_id).await {
+        Ok(events) => events,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let entries = pages::timeline::build_timeline_entries(&requests, &events);
+    let html = pages::timeline::render_timeline_view(&session, &entries);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
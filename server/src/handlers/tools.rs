@@ -0,0 +1,73 @@
+use actix_web::{web, HttpResponse};
+use common::{config::SharedConfig, url::encode_uri_component};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use templates::Pagination;
+
+pub async fn show_tools_page(pool: web::Data<SqlitePool>, path: web::Path<String>) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let tool_call_stats = match db::list_tool_call_stats(pool.get_ref(), &session_id).await {
+        Ok(tool_call_stats) => tool_call_stats,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::tools::render_tools_view(&session, &tool_call_stats);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Requests sending back results of calls to the tool in `?tool=`.
+pub async fn show_tool_requests_page(
+    pool: web::Data<SqlitePool>,
+    config: web::Data<SharedConfig>,
+    path: web::Path<String>,
+    query: web::Query<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let tool_name = query.get("tool").map(|tool| tool.as_str()).unwrap_or("");
+    if tool_name.is_empty() {
+        return HttpResponse::BadRequest().body("Missing tool");
+    }
+    let page: i64 = query
+        .get("page")
+        .and_then(|page_str| page_str.parse().ok())
+        .unwrap_or(1)
+        .max(1);
+    let per_page = config.read().unwrap().dashboard_per_page;
+
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let total = match db::count_tool_call_requests(pool.get_ref(), &session_id, tool_name).await {
+        Ok(total) => total,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let offset = (page - 1) * per_page;
+    let tool_requests = match db::list_tool_call_requests_paginated(
+        pool.get_ref(),
+        &session_id,
+        tool_name,
+        per_page,
+        offset,
+    )
+    .await
+    {
+        Ok(tool_requests) => tool_requests,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+
+    let base_url = format!("/_dashboard/sessions/{}/tools/requests", session_id);
+    let extra_params = format!("&tool={}", encode_uri_component(tool_name));
+    let pagination = Pagination::new(page, total, per_page, &base_url, &extra_params);
+
+    let html =
+        pages::tools::render_tool_requests_view(&session, tool_name, &tool_requests, &pagination);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
@@ -0,0 +1,40 @@
+use actix_web::{cookie::Cookie, web, HttpRequest, HttpResponse};
+use common::users::{validate_user_name, ANONYMOUS_USER, USER_COOKIE};
+use std::collections::HashMap;
+
+/// The dashboard user set on this browser, if any.
+pub fn get_dashboard_user(req: &HttpRequest) -> Option<String> {
+    req.cookie(USER_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .filter(|name| validate_user_name(name).is_ok())
+}
+
+/// The name recorded as the author of a note, star or approval decision.
+pub fn get_dashboard_author(req: &HttpRequest) -> String {
+    get_dashboard_user(req).unwrap_or_else(|| ANONYMOUS_USER.to_string())
+}
+
+pub async fn show_user_page(req: HttpRequest) -> HttpResponse {
+    let user_name = get_dashboard_user(&req);
+    let html = pages::users::render_user_view(user_name.as_deref());
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+/// Set the user cookie, or clear it when the name is blank.
+pub async fn set_user_post(form: web::Form<HashMap<String, String>>) -> HttpResponse {
+    let name = form.get("name").map(|name| name.trim()).unwrap_or_default();
+    let mut cookie = Cookie::build(USER_COOKIE, name.to_string())
+        .path("/")
+        .http_only(true)
+        .permanent()
+        .finish();
+    if name.is_empty() {
+        cookie.make_removal();
+    } else if let Err(e) = validate_user_name(name) {
+        return HttpResponse::BadRequest().body(format!("Invalid user name: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header(("Location", "/_dashboard/user"))
+        .cookie(cookie)
+        .finish()
+}
@@ -0,0 +1,54 @@
+use actix_web::{web, HttpResponse};
+use common::validation::find_by_key;
+use proxy::validate::build_validator;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+pub async fn show_validation_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::validation::render_validation_view(&session);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn update_validation_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let mode = form
+        .get("mode")
+        .map(|field| field.as_str())
+        .filter(|mode| !mode.is_empty());
+    if let Some(mode) = mode {
+        if find_by_key(mode).is_none() {
+            return HttpResponse::BadRequest().body(format!("Unknown validation mode: {}", mode));
+        }
+    }
+    let schema = form
+        .get("schema")
+        .map(|field| field.trim())
+        .filter(|schema| !schema.is_empty());
+    if let Some(schema) = schema {
+        if let Err(e) = build_validator(schema) {
+            return HttpResponse::BadRequest().body(format!("Invalid JSON Schema: {}", e));
+        }
+    }
+    if let Err(e) = db::set_session_validation(pool.get_ref(), &session_id, mode, schema).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!("/_dashboard/sessions/{}/validation", session_id),
+        ))
+        .finish()
+}
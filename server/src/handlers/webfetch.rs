@@ -1,10 +1,43 @@
 use actix_web::{web, HttpResponse};
+use common::models::ProxyRequest;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 
+/// The intercepted request and one of its agent requests. Answers 404 unless
+/// both are in `session_id` and the agent request was made by the request's
+/// interception rounds, so the agent request ID can't be swapped for another
+/// request's.
+async fn load_agent_request(
+    pool: &SqlitePool,
+    session_id: &str,
+    request_id: &str,
+    agent_request_id: &str,
+) -> Result<(ProxyRequest, ProxyRequest), HttpResponse> {
+    let request = match db::get_request(pool, request_id).await {
+        Ok(Some(request)) if request.session_id.to_string() == session_id => request,
+        Ok(_) => return Err(HttpResponse::NotFound().body("Request not found")),
+        Err(e) => return Err(HttpResponse::InternalServerError().body(format!("DB error: {}", e))),
+    };
+    let agent_request = match db::get_request(pool, agent_request_id).await {
+        Ok(Some(agent_request)) if agent_request.session_id == request.session_id => agent_request,
+        Ok(_) => return Err(HttpResponse::NotFound().body("Agent request not found")),
+        Err(e) => return Err(HttpResponse::InternalServerError().body(format!("DB error: {}", e))),
+    };
+    let rounds =
+        pages::detail::parse_rounds(request.webfetch_rounds_json.as_deref()).unwrap_or_default();
+    let is_round_agent_request = pages::detail::collect_agent_calls(&rounds)
+        .iter()
+        .any(|agent_call| agent_call.agent_request_id == agent_request_id);
+    if agent_request.parent_request_id.as_deref() != Some(request_id) && !is_round_agent_request {
+        return Err(HttpResponse::NotFound().body("Agent request not found"));
+    }
+    Ok((request, agent_request))
+}
+
 pub async fn show_webfetch_intercept_page(
     pool: web::Data<SqlitePool>,
     path: web::Path<(String, String)>,
+    query: web::Query<HashMap<String, String>>,
 ) -> HttpResponse {
     let (session_id, request_id) = path.into_inner();
 
@@ -20,15 +53,27 @@ pub async fn show_webfetch_intercept_page(
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
 
-    let html = pages::detail::render_webfetch_intercept_hub(&request, &session);
+    let rounds =
+        pages::detail::parse_rounds(request.webfetch_rounds_json.as_deref()).unwrap_or_default();
+    let mut agent_requests = Vec::new();
+    for agent_call in pages::detail::collect_agent_calls(&rounds) {
+        match db::get_request(pool.get_ref(), agent_call.agent_request_id).await {
+            Ok(Some(agent_request)) => agent_requests.push(agent_request),
+            Ok(None) => {}
+            Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+        }
+    }
+
+    let html =
+        pages::detail::render_webfetch_intercept_hub(&request, &session, &query, &agent_requests);
     HttpResponse::Ok().content_type("text/html").body(html)
 }
 
-pub async fn show_webfetch_agent_page(
+pub async fn show_webfetch_transcript_page(
     pool: web::Data<SqlitePool>,
-    path: web::Path<(String, String, String)>,
+    path: web::Path<(String, String)>,
 ) -> HttpResponse {
-    let (session_id, request_id, agent_request_id) = path.into_inner();
+    let (session_id, request_id) = path.into_inner();
 
     let session = match db::get_session(pool.get_ref(), &session_id).await {
         Ok(Some(session)) => session,
@@ -42,12 +87,29 @@ pub async fn show_webfetch_agent_page(
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
 
-    let agent_request = match db::get_request(pool.get_ref(), &agent_request_id).await {
-        Ok(Some(request)) => request,
-        Ok(None) => return HttpResponse::NotFound().body("Agent request not found"),
+    let html = pages::detail::render_webfetch_transcript_view(&request, &session);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn show_webfetch_agent_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String, String)>,
+) -> HttpResponse {
+    let (session_id, request_id, agent_request_id) = path.into_inner();
+
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
 
+    let (request, agent_request) =
+        match load_agent_request(pool.get_ref(), &session_id, &request_id, &agent_request_id).await
+        {
+            Ok(requests) => requests,
+            Err(response) => return response,
+        };
+
     let html = pages::detail::render_webfetch_agent_overview(&request, &session, &agent_request);
     HttpResponse::Ok().content_type("text/html").body(html)
 }
@@ -65,17 +127,12 @@ pub async fn show_webfetch_agent_subpage(
         Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
     };
 
-    let request = match db::get_request(pool.get_ref(), &request_id).await {
-        Ok(Some(request)) => request,
-        Ok(None) => return HttpResponse::NotFound().body("Request not found"),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
-    };
-
-    let agent_request = match db::get_request(pool.get_ref(), &agent_request_id).await {
-        Ok(Some(request)) => request,
-        Ok(None) => return HttpResponse::NotFound().body("Agent request not found"),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
-    };
+    let (request, agent_request) =
+        match load_agent_request(pool.get_ref(), &session_id, &request_id, &agent_request_id).await
+        {
+            Ok(requests) => requests,
+            Err(response) => return response,
+        };
 
     let profile_id = session.profile_id.clone().unwrap_or_default();
 
@@ -114,3 +171,105 @@ pub async fn show_webfetch_agent_subpage(
     );
     HttpResponse::Ok().content_type("text/html").body(html)
 }
+
+#[cfg(test)]
+mod tests {
+    use actix_web::cookie::Cookie;
+    use actix_web::{middleware, test, web, App};
+    use common::config::{AppConfig, AuthConfig, SharedConfig};
+    use common::share::{sign_share_token, ShareGrant, ShareSecret, SHARE_COOKIE};
+    use db::test_util::{build_request_params, create_test_session, open_temp_pool};
+    use db::CreateRequestParams;
+    use sqlx::SqlitePool;
+    use std::sync::{Arc, RwLock};
+    use uuid::Uuid;
+
+    use super::{show_webfetch_agent_page, show_webfetch_agent_subpage};
+    use crate::auth::require_dashboard_auth;
+
+    async fn create_test_request(pool: &SqlitePool, session_id: &str) -> String {
+        let request_id = Uuid::new_v4().to_string();
+        let params = CreateRequestParams {
+            model: Some("claude-sonnet-4-5"),
+            ..build_request_params(session_id)
+        };
+        db::insert_request(pool, &request_id, &params)
+            .await
+            .unwrap();
+        request_id
+    }
+
+    #[actix_web::test]
+    async fn share_link_reads_only_the_shared_request_agent_calls() {
+        let temp_db = open_temp_pool().await;
+        let pool = &temp_db.pool;
+        let session_id = create_test_session(pool, "shared").await;
+        let request_id = create_test_request(pool, &session_id).await;
+        let agent_request_id = create_test_request(pool, &session_id).await;
+        let unrelated_request_id = create_test_request(pool, &session_id).await;
+        let rounds_json = serde_json::json!([{
+            "tool_names": ["WebFetch"],
+            "agent_request_ids": [agent_request_id],
+        }])
+        .to_string();
+        db::set_request_webfetch_data(pool, &request_id, None, None, None, Some(&rounds_json))
+            .await
+            .unwrap();
+
+        let secret = b"share-secret".to_vec();
+        let token = sign_share_token(
+            &secret,
+            &ShareGrant {
+                session_id: session_id.clone(),
+                request_id: request_id.clone(),
+                expires_at: None,
+            },
+        );
+        let config: SharedConfig = Arc::new(RwLock::new(AppConfig {
+            auth: AuthConfig {
+                username: Some("admin".to_string()),
+                password: Some("secret".to_string()),
+                viewers: vec![],
+            },
+            ..AppConfig::default()
+        }));
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware::from_fn(require_dashboard_auth))
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(config))
+                .app_data(web::Data::new(ShareSecret(secret)))
+                .route(
+                    "/_dashboard/sessions/{id}/requests/{req_id}/webfetch_intercept/agent/{agent_req_id}",
+                    web::get().to(show_webfetch_agent_page),
+                )
+                .route(
+                    "/_dashboard/sessions/{id}/requests/{req_id}/webfetch_intercept/agent/{agent_req_id}/{page}",
+                    web::get().to(show_webfetch_agent_subpage),
+                ),
+        )
+        .await;
+        let agent_base = format!(
+            "/_dashboard/sessions/{}/requests/{}/webfetch_intercept/agent",
+            session_id, request_id
+        );
+
+        for (uri, expected_status) in [
+            (format!("{}/{}", agent_base, agent_request_id), 200),
+            (format!("{}/{}/messages", agent_base, agent_request_id), 200),
+            (format!("{}/{}", agent_base, unrelated_request_id), 404),
+            (
+                format!("{}/{}/messages", agent_base, unrelated_request_id),
+                404,
+            ),
+            (format!("{}/{}/messages", agent_base, request_id), 404),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(&uri)
+                .cookie(Cookie::new(SHARE_COOKIE, token.clone()))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status().as_u16(), expected_status, "{}", uri);
+        }
+    }
+}
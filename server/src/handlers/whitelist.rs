@@ -0,0 +1,268 @@
+use actix_web::{web, HttpResponse};
+use chrono::NaiveDateTime;
+use db::WebfetchWhitelistRuleParams;
+use proxy::webfetch::parse_deny_pattern;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Formats accepted for the expiry field: `datetime-local` values with or
+/// without seconds, and the stored "YYYY-MM-DD HH:MM:SS" form.
+const EXPIRY_INPUT_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+];
+
+struct WhitelistRuleForm {
+    domain: String,
+    allow_subdomains: bool,
+    path_prefix: Option<String>,
+    expires_at: Option<String>,
+}
+
+impl WhitelistRuleForm {
+    fn to_params(&self) -> WebfetchWhitelistRuleParams<'_> {
+        WebfetchWhitelistRuleParams {
+            domain: &self.domain,
+            allow_subdomains: self.allow_subdomains,
+            path_prefix: self.path_prefix.as_deref(),
+            expires_at: self.expires_at.as_deref(),
+        }
+    }
+}
+
+/// Validate and normalize the submitted rule: the domain is lowercased with any
+/// trailing dot removed, the path prefix gets a leading slash, and the expiry
+/// is stored as "YYYY-MM-DD HH:MM:SS".
+fn parse_whitelist_rule_form(form: &HashMap<String, String>) -> Result<WhitelistRuleForm, String> {
+    let domain = form
+        .get("domain")
+        .map(|field| field.trim().trim_end_matches('.').to_ascii_lowercase())
+        .unwrap_or_default();
+    if domain.is_empty() {
+        return Err("domain is required".to_string());
+    }
+    if domain.contains(['/', ':', ' ']) {
+        return Err(format!("domain must be a bare host name, got {:?}", domain));
+    }
+    let path_prefix = form
+        .get("path_prefix")
+        .map(|field| field.trim())
+        .filter(|path_prefix| !path_prefix.is_empty())
+        .map(|path_prefix| {
+            if path_prefix.starts_with('/') {
+                path_prefix.to_string()
+            } else {
+                format!("/{}", path_prefix)
+            }
+        });
+    let expires_at = match form
+        .get("expires_at")
+        .map(|field| field.trim())
+        .filter(|expires_at| !expires_at.is_empty())
+    {
+        Some(expires_at) => {
+            let parsed = EXPIRY_INPUT_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(expires_at, format).ok())
+                .ok_or_else(|| format!("invalid expiry: {:?}", expires_at))?;
+            Some(parsed.format("%Y-%m-%d %H:%M:%S").to_string())
+        }
+        None => None,
+    };
+    Ok(WhitelistRuleForm {
+        domain,
+        allow_subdomains: form
+            .get("allow_subdomains")
+            .is_some_and(|field| field == "1"),
+        path_prefix,
+        expires_at,
+    })
+}
+
+fn redirect_to_whitelist(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!(
+                "/_dashboard/sessions/{}/tool-intercept/webfetch/whitelist",
+                session_id
+            ),
+        ))
+        .finish()
+}
+
+pub async fn show_whitelist_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let rules = match db::list_webfetch_whitelist_rules(pool.get_ref(), &session_id).await {
+        Ok(rules) => rules,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::whitelist::render_whitelist_view(&session, &rules);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn show_new_whitelist_rule_form(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::whitelist::render_new_whitelist_rule_form(&session);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn create_whitelist_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let rule_form = match parse_whitelist_rule_form(&form) {
+        Ok(rule_form) => rule_form,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    if let Err(e) =
+        db::create_webfetch_whitelist_rule(pool.get_ref(), &session_id, &rule_form.to_params())
+            .await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_whitelist(&session_id)
+}
+
+pub async fn show_edit_whitelist_rule_form(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, rule_id) = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let rule = match db::get_webfetch_whitelist_rule(pool.get_ref(), &rule_id).await {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return HttpResponse::NotFound().body("Whitelist rule not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::whitelist::render_edit_whitelist_rule_form(&session, &rule);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn update_whitelist_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let (session_id, rule_id) = path.into_inner();
+    let rule_form = match parse_whitelist_rule_form(&form) {
+        Ok(rule_form) => rule_form,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    if let Err(e) =
+        db::update_webfetch_whitelist_rule(pool.get_ref(), &rule_id, &rule_form.to_params()).await
+    {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_whitelist(&session_id)
+}
+
+pub async fn delete_whitelist_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, rule_id) = path.into_inner();
+    if let Err(e) = db::delete_webfetch_whitelist_rule(pool.get_ref(), &rule_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_whitelist(&session_id)
+}
+
+fn redirect_to_denylist(session_id: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((
+            "Location",
+            format!(
+                "/_dashboard/sessions/{}/tool-intercept/webfetch/denylist",
+                session_id
+            ),
+        ))
+        .finish()
+}
+
+pub async fn show_denylist_page(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let rules = match db::list_webfetch_deny_rules(pool.get_ref(), &session_id).await {
+        Ok(rules) => rules,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::whitelist::render_denylist_view(&session, &rules);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn show_new_deny_rule_form(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let session = match db::get_session(pool.get_ref(), &session_id).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return HttpResponse::NotFound().body("Session not found"),
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let rules = match db::list_webfetch_deny_rules(pool.get_ref(), &session_id).await {
+        Ok(rules) => rules,
+        Err(e) => return HttpResponse::InternalServerError().body(format!("DB error: {}", e)),
+    };
+    let html = pages::whitelist::render_new_deny_rule_form(&session, &rules);
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+pub async fn create_deny_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<String>,
+    form: web::Form<HashMap<String, String>>,
+) -> HttpResponse {
+    let session_id = path.into_inner();
+    let pattern = form.get("pattern").map(|field| field.trim()).unwrap_or("");
+    if let Err(e) = parse_deny_pattern(pattern) {
+        return HttpResponse::BadRequest().body(format!("Invalid deny rule: {}", e));
+    }
+    if let Err(e) = db::create_webfetch_deny_rule(pool.get_ref(), &session_id, pattern).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_denylist(&session_id)
+}
+
+pub async fn delete_deny_rule_post(
+    pool: web::Data<SqlitePool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (session_id, rule_id) = path.into_inner();
+    if let Err(e) = db::delete_webfetch_deny_rule(pool.get_ref(), &rule_id).await {
+        return HttpResponse::InternalServerError().body(format!("DB error: {}", e));
+    }
+    redirect_to_denylist(&session_id)
+}
@@ -1,24 +1,520 @@
+mod auth;
 mod handlers;
+mod presentation;
+mod read_only;
+mod tls;
 
-use actix_web::{middleware, web, App, HttpServer};
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Uri,
+    },
+    middleware::{self, Next},
+    web, App, HttpServer,
+};
 use clap::Parser;
-use common::config::AppConfig;
+use common::config::{AppConfig, SharedConfig, RUNTIME_SETTINGS};
+use common::share::{ShareSecret, SHARE_SECRET_SETTING};
+use common::truncate;
+use proxy::breakpoint::BreakpointQueue;
+use proxy::correlation::{assign_proxy_request_id, PROXY_REQUEST_ID_HEADER};
+use proxy::hooks::HookRegistry;
+use proxy::webfetch::ApprovalQueue;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+/// Command-line flags. Every flag except `--config` overrides the matching
+/// value from the config file when given.
 #[derive(Parser, Clone)]
 #[command(name = "gateway-proxy-rs")]
 pub struct Args {
-    #[arg(long, default_value = "8081")]
-    pub port: u16,
+    #[arg(long)]
+    pub port: Option<u16>,
 
-    #[arg(long, default_value = "proxy.db")]
-    pub db: String,
+    /// Interface the proxy listens on, e.g. 0.0.0.0.
+    #[arg(long)]
+    pub bind: Option<String>,
+
+    /// Serve the dashboard on this port instead of alongside the proxy.
+    #[arg(long)]
+    pub dashboard_port: Option<u16>,
+
+    /// Interface the dashboard listens on with `--dashboard-port`.
+    #[arg(long)]
+    pub dashboard_bind: Option<String>,
+
+    /// PEM certificate chain; serve HTTPS when given with `--tls-key`.
+    #[arg(long)]
+    pub tls_cert: Option<String>,
+
+    /// PEM private key for `--tls-cert`.
+    #[arg(long)]
+    pub tls_key: Option<String>,
+
+    /// Generate a self-signed certificate at `--tls-cert` and `--tls-key`
+    /// when the certificate doesn't exist yet.
+    #[arg(long)]
+    pub tls_self_signed: bool,
+
+    /// Also accept `HTTPS_PROXY` traffic on this port.
+    #[arg(long)]
+    pub forward_proxy_port: Option<u16>,
+
+    /// Interface the forward proxy listens on with `--forward-proxy-port`.
+    #[arg(long)]
+    pub forward_proxy_bind: Option<String>,
+
+    #[arg(long)]
+    pub db: Option<String>,
+
+    #[arg(long)]
+    pub db_max_connections: Option<u32>,
+
+    /// Directory for periodic database snapshots.
+    #[arg(long)]
+    pub backup_dir: Option<String>,
+
+    /// Minutes between database snapshots.
+    #[arg(long)]
+    pub backup_interval_mins: Option<u64>,
+
+    /// Largest proxied request body in bytes; sessions can set their own.
+    #[arg(long)]
+    pub max_body_size: Option<usize>,
 
     #[arg(long, default_value = "config.toml")]
     pub config: String,
+
+    /// Browse the database only: refuse proxied requests and dashboard
+    /// changes, and skip retention cleanup and batch polling.
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Import a JSONL capture log or a directory of request/response dumps
+    /// into a new session, then exit.
+    #[arg(long)]
+    pub import: Option<PathBuf>,
+
+    /// Name of the session created by `--import`; defaults to the file name.
+    #[arg(long)]
+    pub import_session: Option<String>,
+}
+
+fn apply_cli_overrides(config: &mut AppConfig, args: &Args) {
+    if let Some(port) = args.port {
+        config.port = port;
+    }
+    if let Some(ref bind_address) = args.bind {
+        config.bind_address = bind_address.clone();
+    }
+    if let Some(dashboard_port) = args.dashboard_port {
+        config.dashboard_port = Some(dashboard_port);
+    }
+    if let Some(ref dashboard_bind_address) = args.dashboard_bind {
+        config.dashboard_bind_address = dashboard_bind_address.clone();
+    }
+    if let Some(ref tls_cert_path) = args.tls_cert {
+        config.tls_cert_path = Some(tls_cert_path.clone());
+    }
+    if let Some(ref tls_key_path) = args.tls_key {
+        config.tls_key_path = Some(tls_key_path.clone());
+    }
+    if args.tls_self_signed {
+        config.tls_self_signed = true;
+    }
+    if let Some(forward_proxy_port) = args.forward_proxy_port {
+        config.forward_proxy_port = Some(forward_proxy_port);
+    }
+    if let Some(ref forward_proxy_bind_address) = args.forward_proxy_bind {
+        config.forward_proxy_bind_address = forward_proxy_bind_address.clone();
+    }
+    if let Some(ref db_path) = args.db {
+        config.db_path = db_path.clone();
+    }
+    if let Some(db_max_connections) = args.db_max_connections {
+        config.db_max_connections = db_max_connections;
+    }
+    if let Some(ref backup_dir) = args.backup_dir {
+        config.backup_dir = Some(backup_dir.clone());
+    }
+    if let Some(backup_interval_mins) = args.backup_interval_mins {
+        config.backup_interval_mins = backup_interval_mins;
+    }
+    if args.read_only {
+        config.read_only = true;
+    }
+}
+
+/// Flags for runtime settings, applied after the saved settings so the
+/// command line wins over the settings page.
+fn apply_cli_setting_overrides(config: &mut AppConfig, args: &Args) {
+    if let Some(max_body_size) = args.max_body_size {
+        config.max_payload_bytes = max_body_size;
+    }
+}
+
+/// Apply values saved from the settings page on top of the config file.
+async fn apply_persisted_settings(pool: &SqlitePool, config: &mut AppConfig) -> anyhow::Result<()> {
+    for setting in RUNTIME_SETTINGS {
+        let Some(value) = db::get_setting(pool, setting.key).await? else {
+            continue;
+        };
+        if let Err(e) = config.set_runtime_setting(setting.key, &value) {
+            log::warn!("ignoring saved setting {}: {}", setting.key, e);
+        }
+    }
+    Ok(())
+}
+
+/// The share link secret, generated and saved on first start. In read-only
+/// mode a new secret is kept in memory only, so links shared from that run
+/// stop working on restart.
+async fn load_share_secret(pool: &SqlitePool, read_only: bool) -> anyhow::Result<ShareSecret> {
+    if let Some(secret) = db::get_setting(pool, SHARE_SECRET_SETTING).await? {
+        return Ok(ShareSecret(hex::decode(secret)?));
+    }
+    let secret = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    if !read_only {
+        db::set_setting(pool, SHARE_SECRET_SETTING, &secret).await?;
+    }
+    Ok(ShareSecret(hex::decode(secret)?))
+}
+
+/// Import captured traffic into a new session for `--import`.
+async fn import_captures(pool: &SqlitePool, import_path: &Path, args: &Args) -> anyhow::Result<()> {
+    let captured_exchanges = proxy::import::read_captured_exchanges(import_path)?;
+    let session_name = args.import_session.clone().unwrap_or_else(|| {
+        import_path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Imported".to_string())
+    });
+    let session_id =
+        proxy::import::import_captured_exchanges(pool, &session_name, &captured_exchanges).await?;
+    log::info!(
+        "Imported {} requests into session {} ({})",
+        captured_exchanges.len(),
+        session_name,
+        session_id
+    );
+    Ok(())
+}
+
+const RETENTION_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically delete requests older than the configured retention period.
+fn spawn_retention_task(pool: SqlitePool, config: SharedConfig) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(RETENTION_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let retention_days = config.read().unwrap().retention_days;
+            if retention_days == 0 {
+                continue;
+            }
+            match db::delete_requests_older_than(&pool, retention_days).await {
+                Ok(0) => {}
+                Ok(count) => log::info!("retention: deleted {} old requests", count),
+                Err(e) => log::warn!("retention: failed to delete old requests: {}", e),
+            }
+        }
+    });
+}
+
+/// Periodically snapshot the database into `backup_dir`, deleting all but
+/// the newest `backup_keep` snapshots.
+fn spawn_backup_task(
+    pool: SqlitePool,
+    backup_dir: PathBuf,
+    backup_interval: Duration,
+    backup_keep: usize,
+) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(backup_interval);
+        loop {
+            interval.tick().await;
+            match db::snapshot_database(&pool, &backup_dir).await {
+                Ok(file_name) => log::info!("backup: wrote {}", file_name),
+                Err(e) => {
+                    log::warn!("backup: failed to snapshot the database: {}", e);
+                    continue;
+                }
+            }
+            match db::prune_database_snapshots(&backup_dir, backup_keep) {
+                Ok(0) => {}
+                Ok(count) => log::info!("backup: deleted {} old snapshots", count),
+                Err(e) => log::warn!("backup: failed to delete old snapshots: {}", e),
+            }
+        }
+    });
+}
+
+const BATCH_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically poll unfinished Message Batches and attach their results.
+fn spawn_batch_poll_task(pool: SqlitePool, client: reqwest::Client) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(BATCH_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            proxy::batches::poll_message_batches(&pool, &client).await;
+        }
+    });
+}
+
+const TITLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Periodically give untitled Messages requests a generated title, while
+/// `auto_titles` is on.
+fn spawn_title_task(pool: SqlitePool, client: reqwest::Client, config: SharedConfig) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(TITLE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let title_model = {
+                let config = config.read().unwrap();
+                if !config.auto_titles {
+                    continue;
+                }
+                config.title_model.clone()
+            };
+            proxy::titles::title_untitled_requests(&pool, &client, &title_model).await;
+        }
+    });
+}
+
+const ALERT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Bind the forward proxy listener on `forward_proxy_bind_address` and serve
+/// it in the background.
+fn spawn_forward_proxy(
+    pool: SqlitePool,
+    config: &AppConfig,
+    forward_proxy_port: u16,
+) -> anyhow::Result<()> {
+    let forward_proxy = proxy::forward::ForwardProxy::new(pool, config)?;
+    let listener = std::net::TcpListener::bind((
+        config.forward_proxy_bind_address.as_str(),
+        forward_proxy_port,
+    ))?;
+    log::info!(
+        "Forward proxy listening on http://{}:{} (set HTTPS_PROXY to it)",
+        config.forward_proxy_bind_address,
+        forward_proxy_port
+    );
+    actix_web::rt::spawn(async move {
+        if let Err(e) = proxy::forward::run_forward_proxy(forward_proxy, listener).await {
+            log::error!("Forward proxy stopped: {}", e);
+        }
+    });
+    Ok(())
+}
+
+/// Periodically check the alert rules against the newly logged requests.
+fn spawn_alert_task(pool: SqlitePool, client: reqwest::Client, config: SharedConfig) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(ALERT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let webhook_urls = config.read().unwrap().notification_webhooks.clone();
+            proxy::alerts::evaluate_alert_rules(&pool, &client, &webhook_urls).await;
+        }
+    });
 }
 
+/// The hooks run on every proxied request. Custom `ProxyHook`s are
+/// registered here, after the built-in ones.
+fn build_hook_registry(
+    approval_queue: &web::Data<ApprovalQueue>,
+    breakpoint_queue: &web::Data<BreakpointQueue>,
+    client: &reqwest::Client,
+) -> HookRegistry {
+    HookRegistry::with_builtin_hooks(approval_queue, breakpoint_queue, client)
+}
+
+/// Dashboard pages read request rows the proxy has only queued; wait for
+/// the request writer to catch up first.
+async fn flush_request_writes_before_dashboard(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if req.path().starts_with("/_dashboard") {
+        db::flush_request_writes().await;
+    }
+    next.call(req).await
+}
+
+/// Path prefixes of the routes that proxy requests upstream.
+const PROXY_PATH_PREFIXES: &[&str] = &["/_proxy/", "/_bedrock/", "/_vertex/"];
+
+/// Give each proxied request the id it is logged under and return it to the
+/// client in `x-proxy-request-id`.
+async fn assign_proxy_request_ids(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if !PROXY_PATH_PREFIXES
+        .iter()
+        .any(|prefix| req.path().starts_with(prefix))
+    {
+        return next.call(req).await;
+    }
+    let proxy_request_id = assign_proxy_request_id(req.request());
+    let mut res = next.call(req).await?;
+    if let Ok(request_id_value) = HeaderValue::from_str(&proxy_request_id) {
+        res.headers_mut().insert(
+            HeaderName::from_static(PROXY_REQUEST_ID_HEADER),
+            request_id_value,
+        );
+    }
+    Ok(res)
+}
+
+/// Send requests without a session prefix to the `/_proxy/` route of the
+/// session whose hostname they were sent to, or else of the default session,
+/// so clients can use the real API paths. Paths starting with `/_` are the
+/// proxy's own and are never rerouted.
+async fn route_unprefixed_requests(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    if !req.path().starts_with("/_") {
+        let session_id = match find_hostname_session_id(&req).await {
+            Some(session_id) => Some(session_id),
+            None => find_default_session_id(&req).await,
+        };
+        if let Some(session_id) = session_id {
+            let path_and_query = req
+                .uri()
+                .path_and_query()
+                .map_or("/", |path_and_query| path_and_query.as_str());
+            let route_path =
+                proxy::hostname::build_hostname_route_path(&session_id, path_and_query);
+            if let Ok(route_uri) = route_path.parse::<Uri>() {
+                req.match_info_mut().get_mut().update(&route_uri);
+                req.head_mut().uri = route_uri;
+            }
+        }
+    }
+    next.call(req).await
+}
+
+/// The session whose hostname the request was sent to.
+async fn find_hostname_session_id(req: &ServiceRequest) -> Option<String> {
+    let host = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| req.uri().host())?;
+    let hostname = proxy::hostname::find_request_hostname(host)?;
+    let pool = req.app_data::<web::Data<SqlitePool>>()?;
+    match db::find_session_id_by_hostname(pool.get_ref(), &hostname).await {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            log::warn!("Failed to look up the session for host {}: {}", hostname, e);
+            None
+        }
+    }
+}
+
+/// The session requests without a session prefix or hostname go to.
+async fn find_default_session_id(req: &ServiceRequest) -> Option<String> {
+    let pool = req.app_data::<web::Data<SqlitePool>>()?;
+    match db::find_default_session_id(pool.get_ref()).await {
+        Ok(session_id) => session_id,
+        Err(e) => {
+            log::warn!("Failed to look up the default session: {}", e);
+            None
+        }
+    }
+}
+
+/// Dashboard and proxy routes together, for a single listener.
 fn configure_routes(cfg: &mut web::ServiceConfig) {
+    configure_dashboard_routes(cfg);
+    configure_proxy_routes(cfg);
+}
+
+fn configure_dashboard_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/_dashboard", web::get().to(handlers::show_home_page))
+        .route("/_dashboard/find", web::get().to(handlers::find_request_page))
+        .route("/_dashboard/models", web::get().to(handlers::show_models_page))
+        .route("/_dashboard/errors", web::get().to(handlers::show_errors_page))
+        .route(
+            "/_dashboard/models/requests",
+            web::get().to(handlers::show_model_requests_page),
+        )
+        .route("/_dashboard/alerts", web::get().to(handlers::show_alerts_page))
+        .route(
+            "/_dashboard/alerts",
+            web::post().to(handlers::create_alert_rule_post),
+        )
+        .route(
+            "/_dashboard/alerts/new",
+            web::get().to(handlers::show_new_alert_rule_form),
+        )
+        .route(
+            "/_dashboard/alerts/{id}/edit",
+            web::get().to(handlers::show_edit_alert_rule_form),
+        )
+        .route(
+            "/_dashboard/alerts/{id}/edit",
+            web::post().to(handlers::update_alert_rule_post),
+        )
+        .route(
+            "/_dashboard/alerts/{id}/toggle",
+            web::post().to(handlers::toggle_alert_rule_post),
+        )
+        .route(
+            "/_dashboard/alerts/{id}/delete",
+            web::post().to(handlers::delete_alert_rule_post),
+        )
+        .route(
+            "/_dashboard/approvals",
+            web::get().to(handlers::show_all_approvals_page),
+        )
+        .route(
+            "/_dashboard/approvals/{approval_id}",
+            web::post().to(handlers::decide_approval_post),
+        )
+        .route(
+            "/_dashboard/settings",
+            web::get().to(handlers::show_settings_page),
+        )
+        .route(
+            "/_dashboard/settings",
+            web::post().to(handlers::update_settings_post),
+        )
+        .route("/_dashboard/user", web::get().to(handlers::show_user_page))
+        .route("/_dashboard/user", web::post().to(handlers::set_user_post))
+        .route(
+            "/_dashboard/admin/db",
+            web::get().to(handlers::show_database_page),
+        )
+        .route(
+            "/_dashboard/admin/db/vacuum",
+            web::post().to(handlers::vacuum_database_post),
+        )
+        .route(
+            "/_dashboard/admin/db/compress",
+            web::post().to(handlers::compress_database_bodies_post),
+        )
+        .route(
+            "/_dashboard/admin/db/backups",
+            web::post().to(handlers::create_database_snapshot_post),
+        )
+        .route(
+            "/_dashboard/admin/db/backups/restore",
+            web::post().to(handlers::restore_database_snapshot_post),
+        )
         .route(
             "/_dashboard/sessions",
             web::get().to(handlers::show_sessions_page),
@@ -159,10 +655,50 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             "/_dashboard/sessions/{id}/requests/{req_id}",
             web::get().to(handlers::show_request_detail_page),
         )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/notes",
+            web::post().to(handlers::add_request_note_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/star",
+            web::post().to(handlers::toggle_request_star_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/export",
+            web::get().to(handlers::export_request_html),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/share",
+            web::post().to(handlers::create_share_link_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/title",
+            web::post().to(handlers::regenerate_request_title_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/caching",
+            web::get().to(handlers::show_request_caching_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/download/{file}",
+            web::get().to(handlers::download_request_file),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/fixture",
+            web::get().to(handlers::download_request_fixture),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/blocks/{index}/download",
+            web::get().to(handlers::download_request_block),
+        )
         .route(
             "/_dashboard/sessions/{id}/requests/{req_id}/webfetch_intercept",
             web::get().to(handlers::show_webfetch_intercept_page),
         )
+        .route(
+            "/_dashboard/sessions/{id}/requests/{req_id}/webfetch_intercept/transcript",
+            web::get().to(handlers::show_webfetch_transcript_page),
+        )
         .route(
             "/_dashboard/sessions/{id}/requests/{req_id}/webfetch_intercept/agent/{agent_req_id}",
             web::get().to(handlers::show_webfetch_agent_page),
@@ -183,6 +719,82 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             "/_dashboard/sessions/{id}/delete",
             web::post().to(handlers::delete_session_post),
         )
+        .route(
+            "/_dashboard/sessions/{id}/default",
+            web::post().to(handlers::set_default_session_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/default/clear",
+            web::post().to(handlers::clear_default_session_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/batches",
+            web::get().to(handlers::show_batches_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/export/jsonl",
+            web::get().to(handlers::export_requests_jsonl),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/timeline",
+            web::get().to(handlers::show_timeline_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tools",
+            web::get().to(handlers::show_tools_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tools/requests",
+            web::get().to(handlers::show_tool_requests_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/duplicates",
+            web::get().to(handlers::show_duplicates_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/context",
+            web::get().to(handlers::show_context_usage_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/validation",
+            web::get().to(handlers::show_validation_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/validation",
+            web::post().to(handlers::update_validation_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/breakpoints",
+            web::get().to(handlers::show_breakpoints_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/breakpoints",
+            web::post().to(handlers::set_breakpoint_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/breakpoints/clear",
+            web::post().to(handlers::clear_breakpoint_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/breakpoints/{request_id}/forward",
+            web::post().to(handlers::forward_held_request_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/breakpoints/{request_id}/reject",
+            web::post().to(handlers::reject_held_request_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/intercept-rules",
+            web::get().to(handlers::show_intercept_rules_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/intercept-rules",
+            web::post().to(handlers::create_intercept_rule_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/intercept-rules/{rid}/delete",
+            web::post().to(handlers::delete_intercept_rule_post),
+        )
         .route(
             "/_dashboard/sessions/{id}/error-inject",
             web::get().to(handlers::show_error_inject_page),
@@ -200,6 +812,10 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             "/_dashboard/sessions/{id}/tool-intercept",
             web::get().to(handlers::show_intercept_page),
         )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/running/{request_id}/cancel",
+            web::post().to(handlers::cancel_running_intercept_post),
+        )
         // WebFetch Intercept
         .route(
             "/_dashboard/sessions/{id}/tool-intercept/webfetch",
@@ -213,19 +829,152 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             "/_dashboard/sessions/{id}/tool-intercept/webfetch/clear",
             web::post().to(handlers::clear_webfetch_intercept_post),
         )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/private-targets",
+            web::post().to(handlers::allow_webfetch_private_targets_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/private-targets/clear",
+            web::post().to(handlers::block_webfetch_private_targets_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/robots-txt",
+            web::post().to(handlers::respect_webfetch_robots_txt_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/robots-txt/clear",
+            web::post().to(handlers::ignore_webfetch_robots_txt_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/raw-mode",
+            web::post().to(handlers::enable_webfetch_raw_mode_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/raw-mode/clear",
+            web::post().to(handlers::disable_webfetch_raw_mode_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/extraction-mode",
+            web::post().to(handlers::set_webfetch_extraction_mode_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/fetch-limits",
+            web::post().to(handlers::set_webfetch_fetch_limits_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/agent-backend",
+            web::post().to(handlers::set_webfetch_agent_backend_post),
+        )
+        // WebFetch Fetch Cache
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/cache",
+            web::get().to(handlers::show_fetch_cache_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/cache/clear",
+            web::post().to(handlers::clear_fetch_cache_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/cache/{eid}",
+            web::get().to(handlers::show_fetch_cache_entry_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/cache/{eid}/delete",
+            web::post().to(handlers::delete_fetch_cache_entry_post),
+        )
+        // WebFetch Domain Whitelist
         .route(
             "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist",
-            web::post().to(handlers::set_webfetch_whitelist_post),
+            web::get().to(handlers::show_whitelist_page),
         )
         .route(
-            "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist/clear",
-            web::post().to(handlers::clear_webfetch_whitelist_post),
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist",
+            web::post().to(handlers::create_whitelist_rule_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist/new",
+            web::get().to(handlers::show_new_whitelist_rule_form),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist/{rid}/edit",
+            web::get().to(handlers::show_edit_whitelist_rule_form),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist/{rid}/edit",
+            web::post().to(handlers::update_whitelist_rule_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/whitelist/{rid}/delete",
+            web::post().to(handlers::delete_whitelist_rule_post),
+        )
+        // WebFetch Deny Rules
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/denylist",
+            web::get().to(handlers::show_denylist_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/denylist",
+            web::post().to(handlers::create_deny_rule_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/denylist/new",
+            web::get().to(handlers::show_new_deny_rule_form),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/denylist/{rid}/delete",
+            web::post().to(handlers::delete_deny_rule_post),
+        )
+        // WebFetch Auto-Decision Policies
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/policies",
+            web::get().to(handlers::show_policies_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/policies",
+            web::post().to(handlers::create_policy_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/policies/new",
+            web::get().to(handlers::show_new_policy_form),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/policies/approvals/clear",
+            web::post().to(handlers::clear_approved_calls_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/policies/{pid}/move-up",
+            web::post().to(handlers::move_policy_up_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/webfetch/policies/{pid}/delete",
+            web::post().to(handlers::delete_policy_post),
+        )
+        // MCP Server
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/mcp",
+            web::get().to(handlers::show_mcp_server_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/mcp",
+            web::post().to(handlers::set_mcp_server_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/mcp/check",
+            web::post().to(handlers::check_mcp_server_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/mcp/clear",
+            web::post().to(handlers::clear_mcp_server_post),
         )
         // Pending Approvals
         .route(
             "/_dashboard/sessions/{id}/tool-intercept/approvals",
             web::get().to(handlers::show_approvals_page),
         )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/approvals/events",
+            web::get().to(handlers::stream_approval_events),
+        )
         .route(
             "/_dashboard/sessions/{id}/tool-intercept/approvals/fail/{approval_id}",
             web::post().to(handlers::fail_approval_post),
@@ -239,13 +988,100 @@ fn configure_routes(cfg: &mut web::ServiceConfig) {
             web::post().to(handlers::accept_approval_post),
         )
         .route(
-            "/_proxy/{session_id}/{tail:.*}",
-            web::to(handlers::proxy_catch_all),
+            "/_dashboard/sessions/{id}/tool-intercept/approvals/accept-raw/{approval_id}",
+            web::post().to(handlers::accept_raw_approval_post),
         )
         .route(
-            "/_bedrock/{session_id}/model/{model_id}/invoke-with-response-stream",
-            web::post().to(handlers::bedrock_invoke),
-        );
+            "/_dashboard/sessions/{id}/tool-intercept/approvals/{approval_id}/tools/{tool_use_id}",
+            web::post().to(handlers::decide_tool_approval_post),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/approvals/edit/{approval_id}",
+            web::get().to(handlers::show_edit_approval_page),
+        )
+        .route(
+            "/_dashboard/sessions/{id}/tool-intercept/approvals/edit/{approval_id}",
+            web::post().to(handlers::accept_edited_approval_post),
+        )
+        .route("/_share/{token}", web::get().to(handlers::open_share_link));
+}
+
+fn configure_proxy_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route(
+        "/_proxy/{session_id}/{tail:.*}",
+        web::to(handlers::proxy_catch_all),
+    )
+    .route(
+        "/_bedrock/{session_id}/model/{model_id}/invoke-with-response-stream",
+        web::post().to(handlers::bedrock_invoke),
+    )
+    .route(
+        "/_vertex/{session_id}/{tail:.*}",
+        web::post().to(handlers::vertex_invoke),
+    );
+}
+
+/// The `Data` handles every listener's app shares.
+#[derive(Clone)]
+struct SharedAppData {
+    max_payload_bytes: usize,
+    pool: web::Data<SqlitePool>,
+    client: web::Data<reqwest::Client>,
+    config: web::Data<SharedConfig>,
+    approval_queue: web::Data<ApprovalQueue>,
+    breakpoint_queue: web::Data<BreakpointQueue>,
+    hook_registry: web::Data<HookRegistry>,
+    session_limiter: web::Data<proxy::concurrency::SessionLimiter>,
+    share_secret: web::Data<ShareSecret>,
+}
+
+/// An app serving `configure`'s routes with the shared middleware and data.
+fn build_app(
+    shared_app_data: &SharedAppData,
+    configure: fn(&mut web::ServiceConfig),
+) -> App<
+    impl ServiceFactory<
+        ServiceRequest,
+        Config = (),
+        Response = ServiceResponse<impl MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    App::new()
+        .wrap(middleware::from_fn(presentation::redact_dashboard_pages))
+        .wrap(middleware::from_fn(flush_request_writes_before_dashboard))
+        .wrap(middleware::from_fn(assign_proxy_request_ids))
+        .wrap(middleware::from_fn(read_only::reject_read_only_writes))
+        .wrap(middleware::from_fn(auth::require_dashboard_auth))
+        .wrap(middleware::from_fn(route_unprefixed_requests))
+        .wrap(middleware::NormalizePath::trim())
+        .app_data(web::PayloadConfig::new(shared_app_data.max_payload_bytes))
+        .app_data(shared_app_data.pool.clone())
+        .app_data(shared_app_data.client.clone())
+        .app_data(shared_app_data.config.clone())
+        .app_data(shared_app_data.approval_queue.clone())
+        .app_data(shared_app_data.breakpoint_queue.clone())
+        .app_data(shared_app_data.hook_registry.clone())
+        .app_data(shared_app_data.session_limiter.clone())
+        .app_data(shared_app_data.share_secret.clone())
+        .configure(configure)
+}
+
+/// Bind an HTTP server for the routes `configure` registers, over HTTPS when
+/// `tls_config` is given.
+fn start_listener(
+    shared_app_data: SharedAppData,
+    configure: fn(&mut web::ServiceConfig),
+    address: (String, u16),
+    tls_config: Option<rustls::ServerConfig>,
+) -> std::io::Result<actix_web::dev::Server> {
+    let http_server = HttpServer::new(move || build_app(&shared_app_data, configure));
+    let http_server = match tls_config {
+        Some(tls_config) => http_server.bind_rustls_0_23(address, tls_config)?,
+        None => http_server.bind(address)?,
+    };
+    Ok(http_server.run())
 }
 
 #[actix_web::main]
@@ -254,39 +1090,190 @@ async fn main() -> anyhow::Result<()> {
         env_logger::Env::default().default_filter_or("server=info,proxy=info"),
     );
     let args = Args::parse();
-    let port = args.port;
+    let mut config = AppConfig::load(&args.config)?;
+    apply_cli_overrides(&mut config, &args);
+    let tls_config = tls::build_tls_config(&config)?;
+    let scheme = if tls_config.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let port = config.port;
+    let bind_address = config.bind_address.clone();
+    let dashboard_listener = config
+        .dashboard_port
+        .map(|dashboard_port| (config.dashboard_bind_address.clone(), dashboard_port));
 
-    let pool = db::init_pool(&args.db).await?;
-    let config = AppConfig::load(&args.config)?;
+    let pool = db::init_pool(&config.db_path, config.db_max_connections, config.read_only).await?;
+    if let Some(ref import_path) = args.import {
+        anyhow::ensure!(
+            !config.read_only,
+            "--import can't be used in read-only mode"
+        );
+        return import_captures(&pool, import_path, &args).await;
+    }
+    apply_persisted_settings(&pool, &mut config).await?;
+    apply_cli_setting_overrides(&mut config, &args);
+    let share_secret = load_share_secret(&pool, config.read_only).await?;
+    let max_payload_bytes = config.max_payload_bytes;
+    let read_only = config.read_only;
+    let backup_dir = config.backup_dir.clone().map(PathBuf::from);
+    let backup_interval = Duration::from_secs(config.backup_interval_mins.max(1) * 60);
+    let backup_keep = config.backup_keep;
+    templates::set_collapse_threshold(config.collapse_threshold);
+    truncate::set_truncation_profiles(config.build_truncation_profiles());
+    db::set_body_compression_threshold(config.body_compression_threshold);
 
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
         .build()?;
 
-    log::info!("Gateway Proxy listening on http://localhost:{}", port);
-    log::info!("Dashboard at http://localhost:{}/_dashboard/", port);
+    log::info!(
+        "Gateway Proxy listening on {}://{}:{}",
+        scheme,
+        bind_address,
+        port
+    );
+    match dashboard_listener {
+        Some((ref dashboard_bind_address, dashboard_port)) => log::info!(
+            "Dashboard at {}://{}:{}/_dashboard/",
+            scheme,
+            dashboard_bind_address,
+            dashboard_port
+        ),
+        None => log::info!("Dashboard at {}://localhost:{}/_dashboard/", scheme, port),
+    }
+    if read_only {
+        log::info!("Read-only mode: proxying and dashboard changes are disabled");
+    }
+
+    let shared_config: SharedConfig = Arc::new(RwLock::new(config));
+    db::start_request_writer(pool.clone());
+    if !read_only {
+        spawn_retention_task(pool.clone(), shared_config.clone());
+        spawn_batch_poll_task(pool.clone(), client.clone());
+        spawn_title_task(pool.clone(), client.clone(), shared_config.clone());
+        spawn_alert_task(pool.clone(), client.clone(), shared_config.clone());
+        if let Some(backup_dir) = backup_dir {
+            spawn_backup_task(pool.clone(), backup_dir, backup_interval, backup_keep);
+        }
+        let forward_proxy_port = shared_config.read().unwrap().forward_proxy_port;
+        if let Some(forward_proxy_port) = forward_proxy_port {
+            spawn_forward_proxy(
+                pool.clone(),
+                &shared_config.read().unwrap(),
+                forward_proxy_port,
+            )?;
+        }
+    }
 
-    let args_data = web::Data::new(args);
-    let pool_data = web::Data::new(pool);
-    let client_data = web::Data::new(client);
-    let config_data = web::Data::new(config);
     let approval_queue_data = web::Data::new(proxy::webfetch::new_approval_queue());
+    let breakpoint_queue_data = web::Data::new(proxy::breakpoint::new_breakpoint_queue());
+    let hook_registry_data = web::Data::new(build_hook_registry(
+        &approval_queue_data,
+        &breakpoint_queue_data,
+        &client,
+    ));
+    let shared_app_data = SharedAppData {
+        max_payload_bytes,
+        pool: web::Data::new(pool),
+        client: web::Data::new(client),
+        config: web::Data::new(shared_config),
+        approval_queue: approval_queue_data,
+        breakpoint_queue: breakpoint_queue_data,
+        hook_registry: hook_registry_data,
+        session_limiter: web::Data::new(proxy::concurrency::new_session_limiter()),
+        share_secret: web::Data::new(share_secret),
+    };
 
-    HttpServer::new(move || {
-        let payload_cfg = web::PayloadConfig::new(100 * 1024 * 1024); // 100 MB
-        App::new()
-            .wrap(middleware::NormalizePath::trim())
-            .app_data(payload_cfg)
-            .app_data(pool_data.clone())
-            .app_data(client_data.clone())
-            .app_data(args_data.clone())
-            .app_data(config_data.clone())
-            .app_data(approval_queue_data.clone())
-            .configure(configure_routes)
-    })
-    .bind(("0.0.0.0", port))?
-    .run()
-    .await?;
+    match dashboard_listener {
+        None => {
+            start_listener(
+                shared_app_data,
+                configure_routes,
+                (bind_address, port),
+                tls_config,
+            )?
+            .await?;
+        }
+        Some(dashboard_address) => {
+            let proxy_server = start_listener(
+                shared_app_data.clone(),
+                configure_proxy_routes,
+                (bind_address, port),
+                tls_config.clone(),
+            )?;
+            let dashboard_server = start_listener(
+                shared_app_data,
+                configure_dashboard_routes,
+                dashboard_address,
+                tls_config,
+            )?;
+            futures::try_join!(proxy_server, dashboard_server)?;
+        }
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_file_config() -> AppConfig {
+        AppConfig {
+            port: 9000,
+            bind_address: "10.0.0.1".to_string(),
+            db_path: "file.db".to_string(),
+            backup_dir: Some("file-backups".to_string()),
+            read_only: false,
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn apply_cli_overrides_keeps_file_values_without_flags() {
+        let mut config = build_file_config();
+        apply_cli_overrides(&mut config, &Args::parse_from(["gateway-proxy-rs"]));
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.bind_address, "10.0.0.1");
+        assert_eq!(config.db_path, "file.db");
+        assert_eq!(config.backup_dir.as_deref(), Some("file-backups"));
+        assert!(!config.read_only);
+    }
+
+    #[test]
+    fn apply_cli_overrides_prefers_flags_over_file_values() {
+        let mut config = build_file_config();
+        let args = Args::parse_from([
+            "gateway-proxy-rs",
+            "--port",
+            "9100",
+            "--db",
+            "cli.db",
+            "--read-only",
+        ]);
+        apply_cli_overrides(&mut config, &args);
+        assert_eq!(config.port, 9100);
+        assert_eq!(config.db_path, "cli.db");
+        assert!(config.read_only);
+        assert_eq!(config.bind_address, "10.0.0.1");
+        assert_eq!(config.backup_dir.as_deref(), Some("file-backups"));
+    }
+
+    #[actix_web::test]
+    async fn load_share_secret_saves_nothing_in_read_only_mode() {
+        let temp_db = db::test_util::open_temp_pool().await;
+        let pool = &temp_db.pool;
+
+        load_share_secret(pool, true).await.unwrap();
+        let saved_secret = db::get_setting(pool, SHARE_SECRET_SETTING).await.unwrap();
+        assert_eq!(saved_secret, None);
+
+        let share_secret = load_share_secret(pool, false).await.unwrap();
+        let saved_secret = db::get_setting(pool, SHARE_SECRET_SETTING).await.unwrap();
+        assert_eq!(saved_secret, Some(hex::encode(&share_secret.0)));
+        let read_only_secret = load_share_secret(pool, true).await.unwrap();
+        assert_eq!(read_only_secret.0, share_secret.0);
+    }
+}
@@ -0,0 +1,59 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    error::ErrorInternalServerError,
+    http::header,
+    middleware::Next,
+    web,
+};
+use common::config::SharedConfig;
+
+/// Left unmasked so the extra patterns can be reviewed and presentation mode
+/// turned off.
+const SETTINGS_PATH: &str = "/_dashboard/settings";
+
+/// In presentation mode, mask keys, emails and the configured patterns in
+/// dashboard HTML pages and show a banner saying so. Only the rendered page
+/// changes; stored requests and proxied traffic are untouched.
+pub async fn redact_dashboard_pages(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let extra_regexes = match req.app_data::<web::Data<SharedConfig>>() {
+        Some(config) if req.path().starts_with("/_dashboard") => {
+            let config = config.read().unwrap();
+            config
+                .presentation_mode
+                .then(|| config.presentation_redact_regexes.clone())
+        }
+        _ => None,
+    };
+    let is_settings_page = req.path() == SETTINGS_PATH;
+    let res = next.call(req).await?;
+    let Some(extra_regexes) = extra_regexes else {
+        return Ok(res.map_into_boxed_body());
+    };
+    let is_html = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("text/html"));
+    if !is_html {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, res_body) = res.into_parts();
+    let html_bytes = body::to_bytes(res_body)
+        .await
+        .map_err(|e| ErrorInternalServerError(e.into()))?;
+    let html = String::from_utf8_lossy(&html_bytes);
+    let html = if is_settings_page {
+        html.into_owned()
+    } else {
+        pages::presentation::redact_page_html(&html, &extra_regexes)
+    };
+    let html = pages::presentation::insert_presentation_banner(&html);
+    let res = res.set_body(html).map_into_boxed_body();
+    Ok(ServiceResponse::new(req, res))
+}
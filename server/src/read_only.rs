@@ -0,0 +1,40 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    web, HttpResponse,
+};
+use common::config::SharedConfig;
+
+use crate::auth::is_read_only_request;
+use crate::PROXY_PATH_PREFIXES;
+
+/// In read-only mode, refuse everything that would write to the database:
+/// proxied requests, and dashboard forms other than picking a user name.
+pub async fn reject_read_only_writes(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let read_only = req
+        .app_data::<web::Data<SharedConfig>>()
+        .is_some_and(|config| config.read().unwrap().read_only);
+    let is_proxy_request = PROXY_PATH_PREFIXES
+        .iter()
+        .any(|prefix| req.path().starts_with(prefix));
+    let is_dashboard_change = req.path().starts_with("/_dashboard") && !is_read_only_request(&req);
+
+    let response = if read_only && is_proxy_request {
+        HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": "api_error",
+                "message": "The proxy is in read-only mode and does not forward requests",
+            }
+        }))
+    } else if read_only && is_dashboard_change {
+        HttpResponse::Forbidden().body("Read-only mode: changes are disabled")
+    } else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+    Ok(req.into_response(response).map_into_right_body())
+}
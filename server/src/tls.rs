@@ -0,0 +1,79 @@
+//! HTTPS for the listeners, so clients that only accept https endpoints can
+//! point at the proxy without a TLS-terminating reverse proxy in front.
+
+use common::config::AppConfig;
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Names the generated self-signed certificate is valid for.
+const SELF_SIGNED_NAMES: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+/// The listeners' TLS config, or `None` to serve plain HTTP when no
+/// certificate is configured. Generates a self-signed certificate first when
+/// `tls_self_signed` is set and the certificate is missing.
+pub fn build_tls_config(config: &AppConfig) -> anyhow::Result<Option<rustls::ServerConfig>> {
+    let (cert_path, key_path) = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => (Path::new(cert_path), Path::new(key_path)),
+        (None, None) => {
+            anyhow::ensure!(
+                !config.tls_self_signed,
+                "--tls-self-signed needs --tls-cert and --tls-key to say where to write the certificate"
+            );
+            return Ok(None);
+        }
+        _ => anyhow::bail!("--tls-cert and --tls-key must be given together"),
+    };
+    if config.tls_self_signed && ensure_self_signed_certificate(cert_path, key_path)? {
+        log::info!(
+            "Generated a self-signed certificate at {}",
+            cert_path.display()
+        );
+    }
+    Ok(Some(load_tls_config(cert_path, key_path)?))
+}
+
+/// Write a self-signed certificate for `SELF_SIGNED_NAMES` and its key to
+/// `cert_path` and `key_path`, unless the certificate already exists.
+/// Returns whether new files were written.
+fn ensure_self_signed_certificate(cert_path: &Path, key_path: &Path) -> anyhow::Result<bool> {
+    if cert_path.exists() {
+        return Ok(false);
+    }
+    let subject_alt_names: Vec<String> = SELF_SIGNED_NAMES
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let certified_key = rcgen::generate_simple_self_signed(subject_alt_names)?;
+    write_private_file(key_path, &certified_key.signing_key.serialize_pem())?;
+    fs::write(cert_path, certified_key.cert.pem())?;
+    Ok(true)
+}
+
+/// Write a file only its owner can read.
+fn write_private_file(path: &Path, contents: &str) -> anyhow::Result<()> {
+    let mut open_options = fs::OpenOptions::new();
+    open_options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut open_options, 0o600);
+    open_options.open(path)?.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+/// The server TLS config for a PEM certificate chain and private key.
+fn load_tls_config(cert_path: &Path, key_path: &Path) -> anyhow::Result<rustls::ServerConfig> {
+    let cert_chain = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("reading {}: {}", cert_path.display(), e))?;
+    anyhow::ensure!(
+        !cert_chain.is_empty(),
+        "{} has no certificates",
+        cert_path.display()
+    );
+    let private_key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| anyhow::anyhow!("reading {}: {}", key_path.display(), e))?;
+    Ok(rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, private_key)?)
+}
@@ -1,9 +1,16 @@
 use leptos::{either::Either, prelude::*};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-const COLLAPSE_THRESHOLD: usize = 200;
+static COLLAPSE_THRESHOLD: AtomicUsize = AtomicUsize::new(200);
+
+/// Set the content length above which `collapsible_block` folds its content.
+pub fn set_collapse_threshold(threshold: usize) {
+    COLLAPSE_THRESHOLD.store(threshold, Ordering::Relaxed);
+}
 
 pub fn collapsible_block(content: &str, css_class: &str) -> AnyView {
-    if content.len() <= COLLAPSE_THRESHOLD {
+    let collapse_threshold = COLLAPSE_THRESHOLD.load(Ordering::Relaxed);
+    if content.len() <= collapse_threshold {
         let tag_content = content.to_string();
         let class = css_class.to_string();
         return if content.contains('\n') {
@@ -12,7 +19,7 @@ pub fn collapsible_block(content: &str, css_class: &str) -> AnyView {
             view! { <div class={class}>{tag_content}</div> }.into_any()
         };
     }
-    let preview: String = content.chars().take(COLLAPSE_THRESHOLD).collect();
+    let preview: String = content.chars().take(collapse_threshold).collect();
     let preview_display = format!("{}...", preview);
     let preview_class = format!("preview-text {}", css_class);
     let full_class = format!("collapsible-full {}", css_class);
@@ -31,6 +38,136 @@ pub fn collapsible_block(content: &str, css_class: &str) -> AnyView {
     .into_any()
 }
 
+/// Applies the theme before first paint. A `?theme=dark|light` query param
+/// wins and is persisted in the `theme` cookie; otherwise the cookie, then
+/// the OS preference, decides. The toggle button flips and persists it.
+const THEME_SCRIPT: &str = r#"(function () {
+  var param = new URLSearchParams(location.search).get('theme');
+  var cookie = document.cookie.match(/(?:^|; )theme=(dark|light)/);
+  var prefersDark = window.matchMedia && window.matchMedia('(prefers-color-scheme: dark)').matches;
+  var theme = param === 'dark' || param === 'light' ? param
+    : cookie ? cookie[1]
+    : prefersDark ? 'dark' : 'light';
+  if (param === 'dark' || param === 'light') {
+    document.cookie = 'theme=' + theme + '; path=/; max-age=31536000; SameSite=Lax';
+  }
+  document.documentElement.setAttribute('data-theme', theme);
+})();
+document.addEventListener('click', function (event) {
+  if (!event.target.closest('[data-theme-toggle]')) return;
+  var theme = document.documentElement.getAttribute('data-theme') === 'dark' ? 'light' : 'dark';
+  document.cookie = 'theme=' + theme + '; path=/; max-age=31536000; SameSite=Lax';
+  document.documentElement.setAttribute('data-theme', theme);
+});"#;
+
+/// Expands collapsible blocks inside (and around) the element targeted by the
+/// URL fragment, so deep links to long message blocks show their full content.
+const ANCHOR_SCRIPT: &str = r#"function openAnchorTarget() {
+  if (!location.hash) return;
+  var target = document.getElementById(decodeURIComponent(location.hash.slice(1)));
+  if (!target) return;
+  target.querySelectorAll('details.collapsible').forEach(function (details) { details.open = true; });
+  for (var node = target.parentElement; node; node = node.parentElement) {
+    if (node.tagName === 'DETAILS') node.open = true;
+  }
+  target.scrollIntoView();
+}
+window.addEventListener('hashchange', openAnchorTarget);
+document.addEventListener('DOMContentLoaded', openAnchorTarget);"#;
+
+/// Click handler for `copy_button` and `copy_target_button`. Delegated from the
+/// document so buttons work wherever they are rendered.
+const COPY_SCRIPT: &str = r#"document.addEventListener('click', function (event) {
+  var button = event.target.closest('[data-copy], [data-copy-target]');
+  if (!button) return;
+  var text = button.hasAttribute('data-copy')
+    ? button.getAttribute('data-copy')
+    : (document.getElementById(button.getAttribute('data-copy-target')) || {}).textContent || '';
+  navigator.clipboard.writeText(text).then(function () {
+    var label = button.textContent;
+    button.textContent = 'Copied';
+    setTimeout(function () { button.textContent = label; }, 1200);
+  });
+});"#;
+
+/// Keeps a `[data-approval-events]` section in sync with the server-sent
+/// approval events at its URL, and submits its decision forms in the
+/// background so deciding an approval does not reload the page.
+const APPROVALS_SCRIPT: &str = r#"document.addEventListener('DOMContentLoaded', function () {
+  var section = document.querySelector('[data-approval-events]');
+  if (!section || !window.EventSource) return;
+  var table = section.querySelector('[data-approval-table]');
+  var count = section.querySelector('[data-approval-count]');
+  var status = section.querySelector('[data-approval-status]');
+  function findApproval(approvalId) {
+    return Array.prototype.find.call(table.tBodies, function (tbody) {
+      return tbody.getAttribute('data-approval-id') === approvalId;
+    });
+  }
+  function showApproval(approval) {
+    var template = document.createElement('template');
+    template.innerHTML = '<table>' + approval.html + '</table>';
+    var tbody = template.content.querySelector('tbody');
+    var existing = findApproval(approval.approval_id);
+    if (existing) existing.replaceWith(tbody); else table.appendChild(tbody);
+  }
+  function updateCount() {
+    var total = table.tBodies.length;
+    count.textContent = total ? total + ' pending' : 'No pending approvals.';
+    table.classList.toggle('hidden', total === 0);
+  }
+  var events = new EventSource(section.getAttribute('data-approval-events'));
+  events.addEventListener('snapshot', function (event) {
+    Array.prototype.slice.call(table.tBodies).forEach(function (tbody) { tbody.remove(); });
+    JSON.parse(event.data).approvals.forEach(showApproval);
+    updateCount();
+  });
+  ['added', 'updated'].forEach(function (name) {
+    events.addEventListener(name, function (event) {
+      showApproval(JSON.parse(event.data));
+      updateCount();
+    });
+  });
+  events.addEventListener('removed', function (event) {
+    var existing = findApproval(JSON.parse(event.data).approval_id);
+    if (existing) existing.remove();
+    updateCount();
+  });
+  events.onopen = function () { status.textContent = '(live)'; };
+  events.onerror = function () { status.textContent = '(reconnecting...)'; };
+  section.addEventListener('submit', function (event) {
+    var form = event.target;
+    event.preventDefault();
+    var buttons = form.querySelectorAll('button');
+    buttons.forEach(function (button) { button.disabled = true; });
+    fetch(form.action, { method: 'POST', body: new URLSearchParams(new FormData(form)) })
+      .then(function (response) { if (!response.ok) throw new Error(response.statusText); })
+      .catch(function () {
+        buttons.forEach(function (button) { button.disabled = false; });
+        status.textContent = '(decision failed, try again)';
+      });
+  });
+});"#;
+
+/// Button that copies `text` to the clipboard.
+pub fn copy_button(text: &str, label: &str) -> AnyView {
+    let text = text.to_string();
+    let label = label.to_string();
+    view! { <button type="button" class="copy-button" data-copy={text}>{label}</button> }
+        .into_any()
+}
+
+/// Button that copies the text content of the element with id `target_id`,
+/// for content too large to duplicate into an attribute.
+pub fn copy_target_button(target_id: &str, label: &str) -> AnyView {
+    let target_id = target_id.to_string();
+    let label = label.to_string();
+    view! {
+        <button type="button" class="copy-button" data-copy-target={target_id}>{label}</button>
+    }
+    .into_any()
+}
+
 pub fn page_layout(title: &str, body_html: String) -> String {
     let title = title
         .replace('&', "&amp;")
@@ -44,10 +181,26 @@ pub fn page_layout(title: &str, body_html: String) -> String {
 <meta charset="utf-8">
 <title>{title}</title>
 <style>
-body {{ font-family: monospace; padding: 16px; }}
+:root {{
+  --bg: #ffffff; --fg: #1f2328; --muted: #6e7781; --link: #0969da;
+  --border: #cccccc; --row-border: #eeeeee; --code-bg: #f8f8f8; --image-border: #dddddd;
+  --hl-key: #881391; --hl-string: #1a7f37; --hl-number: #0550ae;
+  --hl-literal: #cf222e; --hl-keyword: #cf222e; --hl-comment: #6e7781;
+  --target-bg: #fff8c5;
+}}
+[data-theme="dark"] {{
+  --bg: #1e1e1e; --fg: #d4d4d4; --muted: #858585; --link: #4fc1ff;
+  --border: #3c3c3c; --row-border: #2d2d2d; --code-bg: #252526; --image-border: #3c3c3c;
+  --hl-key: #9cdcfe; --hl-string: #ce9178; --hl-number: #b5cea8;
+  --hl-literal: #569cd6; --hl-keyword: #c586c0; --hl-comment: #6a9955;
+  --target-bg: #3a3d41;
+}}
+body {{ font-family: monospace; padding: 16px; background: var(--bg); color: var(--fg); }}
+a {{ color: var(--link); }}
+input, select, textarea, button {{ background: var(--bg); color: var(--fg); border: 1px solid var(--border); }}
 table {{ width: 100%; border-collapse: collapse; }}
-th {{ text-align: left; padding: 6px 8px; border-bottom: 1px solid #ccc; }}
-td {{ padding: 6px 8px; border-bottom: 1px solid #eee; vertical-align: top; }}
+th {{ text-align: left; padding: 6px 8px; border-bottom: 1px solid var(--border); }}
+td {{ padding: 6px 8px; border-bottom: 1px solid var(--row-border); vertical-align: top; }}
 tr:last-child td {{ border-bottom: none; }}
 pre {{ white-space: pre-wrap; }}
 form {{ display: inline; }}
@@ -61,14 +214,53 @@ details.collapsible[open] > summary .show-more {{ display: none; }}
 details.collapsible[open] > summary .show-less {{ display: inline; }}
 .hidden {{ display: none; }}
 .filtered-row {{ opacity: 0.45; }}
-.filtered-badge {{ color: #888; font-weight: bold; font-size: 0.85em; }}
+.filtered-badge {{ color: var(--muted); font-weight: bold; font-size: 0.85em; }}
+.stderr {{ color: var(--hl-literal); }}
+.stop-alert {{ color: var(--hl-literal); font-weight: bold; }}
+.context-bar {{ display: flex; height: 14px; width: 100%; max-width: 480px; border: 1px solid var(--border); }}
+.context-chart {{ display: flex; align-items: flex-end; gap: 2px; height: 160px; border-bottom: 1px solid var(--border); }}
+.context-column {{ display: flex; flex-direction: column-reverse; flex: 1; max-width: 16px; }}
+.context-system {{ background: var(--hl-key); }}
+.context-tools {{ background: var(--hl-number); }}
+.context-messages {{ background: var(--hl-string); }}
+.context-tool-results {{ background: var(--hl-literal); }}
+.context-swatch {{ display: inline-block; width: 10px; height: 10px; margin-right: 4px; }}
+img.image-preview {{ max-width: 320px; max-height: 240px; border: 1px solid var(--image-border); }}
+pre.code {{ white-space: pre; overflow-x: auto; background: var(--code-bg); padding: 8px; }}
+.hl-key {{ color: var(--hl-key); }}
+.hl-string {{ color: var(--hl-string); }}
+.hl-number {{ color: var(--hl-number); }}
+.hl-literal {{ color: var(--hl-literal); }}
+.hl-keyword {{ color: var(--hl-keyword); font-weight: bold; }}
+.hl-comment {{ color: var(--hl-comment); font-style: italic; }}
+.markdown {{ font-family: sans-serif; line-height: 1.4; }}
+.markdown pre {{ background: var(--code-bg); padding: 8px; overflow-x: auto; }}
+.markdown code {{ font-family: monospace; }}
+.markdown table {{ width: auto; }}
+.markdown th, .markdown td {{ border: 1px solid var(--border); }}
+.copy-button {{ font-family: monospace; font-size: 0.8em; cursor: pointer; }}
+tr:target {{ background: var(--target-bg); }}
+.anchor-link {{ color: var(--muted); text-decoration: none; }}
+.theme-toggle {{ position: absolute; top: 16px; right: 16px; font-family: monospace; cursor: pointer; }}
+.presentation-banner {{ padding: 6px 8px; margin-bottom: 8px; border: 1px solid var(--border); }}
 </style>
+<script>
+{theme_script}
+{copy_script}
+{anchor_script}
+{approvals_script}
+</script>
 </head>
 <body>
+<button type="button" class="theme-toggle" data-theme-toggle>Toggle theme</button>
 {body_html}
 </body>
 </html>"#,
         title = title,
+        theme_script = THEME_SCRIPT,
+        copy_script = COPY_SCRIPT,
+        anchor_script = ANCHOR_SCRIPT,
+        approvals_script = APPROVALS_SCRIPT,
         body_html = body_html
     )
 }
@@ -365,6 +557,19 @@ mod tests {
         assert!(!result.contains("<script>alert"));
     }
 
+    #[test]
+    fn copy_button_escapes_text() {
+        let result = copy_button("a\"b<c>", "Copy").to_html();
+        assert!(result.contains("data-copy=\"a&quot;b&lt;c&gt;\""));
+        assert!(result.contains(">Copy</button>"));
+    }
+
+    #[test]
+    fn copy_target_button_references_element() {
+        let result = copy_target_button("full-json", "Copy Full JSON").to_html();
+        assert!(result.contains(r#"data-copy-target="full-json""#));
+    }
+
     #[test]
     fn page_layout_wraps_body() {
         let result = page_layout("Test Title", "<p>body</p>".to_string());
@@ -373,6 +578,13 @@ mod tests {
         assert!(result.starts_with("<!DOCTYPE html>"));
     }
 
+    #[test]
+    fn page_layout_includes_theme_toggle() {
+        let result = page_layout("Test", "".to_string());
+        assert!(result.contains("data-theme-toggle"));
+        assert!(result.contains(r#"[data-theme="dark"]"#));
+    }
+
     #[test]
     fn page_layout_escapes_title() {
         let result = page_layout("<script>", "".to_string());